@@ -0,0 +1,378 @@
+use super::Processable;
+use crate::fame::{blame_files, BlameOutput};
+use crate::identity;
+use crate::output;
+use crate::table::Table;
+use crate::utils::grit_utils;
+use crate::utils::grit_utils::{OutputFormat, RelativeTo};
+use anyhow::Result;
+use chrono::NaiveDate;
+use std::collections::HashMap;
+
+const DAYS_PER_MONTH: f64 = 30.44;
+const TOTAL_FILE: &str = "Total";
+
+pub struct AgeArgs {
+    path: String,
+    include: Option<String>,
+    exclude: Option<String>,
+    older_than: i64,
+    as_of: Option<NaiveDate>,
+    file: Option<String>,
+    format: OutputFormat,
+    relative_to: RelativeTo,
+    strict: bool,
+}
+
+impl AgeArgs {
+    pub fn builder(path: String) -> AgeArgsBuilder {
+        AgeArgsBuilder {
+            args: AgeArgs {
+                path,
+                include: None,
+                exclude: None,
+                older_than: 12,
+                as_of: None,
+                file: None,
+                format: OutputFormat::Csv,
+                relative_to: RelativeTo::Repo,
+                strict: false,
+            },
+        }
+    }
+}
+
+/// Builds a `AgeArgs` by name rather than position, validating it on `build`.
+pub struct AgeArgsBuilder {
+    args: AgeArgs,
+}
+
+impl AgeArgsBuilder {
+    pub fn include(mut self, include: Option<String>) -> AgeArgsBuilder {
+        self.args.include = include;
+        self
+    }
+
+    pub fn exclude(mut self, exclude: Option<String>) -> AgeArgsBuilder {
+        self.args.exclude = exclude;
+        self
+    }
+
+    pub fn older_than(mut self, older_than: i64) -> AgeArgsBuilder {
+        self.args.older_than = older_than;
+        self
+    }
+
+    /// the day the ages are counted to, `--as-of`
+    pub fn on_date(mut self, as_of: Option<NaiveDate>) -> AgeArgsBuilder {
+        self.args.as_of = as_of;
+        self
+    }
+
+    pub fn file(mut self, file: Option<String>) -> AgeArgsBuilder {
+        self.args.file = file;
+        self
+    }
+
+    pub fn format(mut self, format: OutputFormat) -> AgeArgsBuilder {
+        self.args.format = format;
+        self
+    }
+
+    pub fn relative_to(mut self, relative_to: RelativeTo) -> AgeArgsBuilder {
+        self.args.relative_to = relative_to;
+        self
+    }
+
+    pub fn strict(mut self, strict: bool) -> AgeArgsBuilder {
+        self.args.strict = strict;
+        self
+    }
+
+    pub fn build(self) -> Result<AgeArgs> {
+        grit_utils::check_output_file(self.args.file.as_deref(), self.args.format)?;
+
+        Ok(self.args)
+    }
+}
+
+/// The ages of the blamed lines of a file, or of the whole repository.
+#[derive(Clone, Debug, PartialEq)]
+struct AgeOutput {
+    file: String,
+    /// age in days and the number of lines that old, youngest first
+    ages: Vec<(i64, i32)>,
+    lines: i32,
+    oldest: Option<NaiveDate>,
+}
+
+impl AgeOutput {
+    fn new(file: String) -> AgeOutput {
+        AgeOutput {
+            file,
+            ages: Vec::new(),
+            lines: 0,
+            oldest: None,
+        }
+    }
+
+    fn add(&mut self, date: NaiveDate, lines: i32, as_of: NaiveDate) {
+        self.add_age((as_of - date).num_days().max(0), lines);
+        self.oldest = Some(self.oldest.map_or(date, |o| o.min(date)));
+    }
+
+    fn add_age(&mut self, age: i64, lines: i32) {
+        match self.ages.binary_search_by_key(&age, |a| a.0) {
+            Ok(i) => self.ages[i].1 += lines,
+            Err(i) => self.ages.insert(i, (age, lines)),
+        }
+
+        self.lines += lines;
+    }
+
+    fn merge(&mut self, other: &AgeOutput) {
+        for (age, lines) in other.ages.iter() {
+            self.add_age(*age, *lines);
+        }
+
+        self.oldest = match (self.oldest, other.oldest) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+    }
+
+    /// the age in days of the line at `p` percent, by nearest rank
+    fn percentile(&self, p: f64) -> i64 {
+        let rank = ((p / 100.0) * self.lines as f64).ceil().max(1.0) as i32;
+        let mut seen = 0;
+
+        for (age, lines) in self.ages.iter() {
+            seen += lines;
+            if seen >= rank {
+                return *age;
+            }
+        }
+
+        0
+    }
+
+    /// share of the lines more than `days` old
+    fn perc_older_than(&self, days: f64) -> f64 {
+        if self.lines == 0 {
+            return 0.0;
+        }
+
+        let older: i32 = self
+            .ages
+            .iter()
+            .filter(|(age, _)| *age as f64 > days)
+            .map(|(_, lines)| lines)
+            .sum();
+
+        older as f64 / self.lines as f64
+    }
+}
+
+pub struct Age {
+    args: AgeArgs,
+}
+
+impl Age {
+    pub fn new(args: AgeArgs) -> Age {
+        Age { args }
+    }
+
+    /// the line ages of every file, stalest first, and of the whole repository
+    fn age_results(&self, as_of: NaiveDate) -> Result<(Vec<AgeOutput>, AgeOutput)> {
+        let file_names = grit_utils::generate_file_list(
+            &self.args.path,
+            self.args.include.clone(),
+            self.args.exclude.clone(),
+        )?;
+
+        let (collector, skipped) = blame_files(&self.args.path, &file_names, None, None)?;
+
+        grit_utils::report_skipped(&skipped, self.args.strict)?;
+
+        let mut files: HashMap<String, AgeOutput> = HashMap::new();
+
+        for b in collector.iter().flatten() {
+            if identity::is_excluded(&b.author, &b.email) {
+                continue;
+            }
+
+            if let BlameOutput {
+                date: Some(date),
+                lines,
+                file_name,
+                ..
+            } = b
+            {
+                files
+                    .entry(file_name.clone())
+                    .or_insert_with(|| AgeOutput::new(file_name.clone()))
+                    .add(*date, *lines, as_of);
+            }
+        }
+
+        let mut results: Vec<AgeOutput> = files.into_values().collect();
+        let mut total = AgeOutput::new(String::from(TOTAL_FILE));
+
+        results.iter().for_each(|r| total.merge(r));
+
+        let file_names: Vec<String> = results.iter().map(|r| r.file.clone()).collect();
+        let display_names =
+            grit_utils::relative_file_paths(&self.args.path, &file_names, self.args.relative_to)?;
+
+        results
+            .iter_mut()
+            .zip(display_names)
+            .for_each(|(r, f)| r.file = f);
+
+        results.sort_by(|a, b| {
+            b.percentile(50.0)
+                .cmp(&a.percentile(50.0))
+                .then(a.file.cmp(&b.file))
+        });
+
+        Ok((results, total))
+    }
+
+    fn age_table(&self, results: &[AgeOutput], total: Option<&AgeOutput>) -> Table {
+        let older_days = self.args.older_than as f64 * DAYS_PER_MONTH;
+        let mut table = Table::new();
+
+        if self.args.format == OutputFormat::Table {
+            table.set_titles(table_row![
+                "File",
+                "Lines",
+                "Median Days",
+                "P75 Days",
+                "P90 Days",
+                format!("Older Than {} Months (%)", self.args.older_than),
+                "Oldest Line"
+            ]);
+        } else {
+            table.set_titles(table_row![
+                "file",
+                "lines",
+                "median days",
+                "p75 days",
+                "p90 days",
+                format!("older than {} months (%)", self.args.older_than),
+                "oldest line"
+            ]);
+        }
+
+        for r in results.iter().chain(total) {
+            table.add_row(table_row![
+                r.file,
+                r.lines,
+                r.percentile(50.0),
+                r.percentile(75.0),
+                r.percentile(90.0),
+                output::percent(r.perc_older_than(older_days) * 100.0),
+                r.oldest.map(grit_utils::format_date).unwrap_or_default()
+            ]);
+        }
+
+        table.add_label(0, "file");
+        table.add_metric(
+            2,
+            "grit_file_median_line_age_days",
+            "Median age in days of the blamed lines of the file.",
+        );
+        table.add_metric(
+            4,
+            "grit_file_p90_line_age_days",
+            "Age in days of the 90th percentile blamed line of the file.",
+        );
+        table.add_metric(
+            5,
+            "grit_file_stale_lines_percent",
+            "Percent of the file's lines older than the --older-than months.",
+        );
+
+        table
+    }
+}
+
+impl Processable<()> for Age {
+    fn process(&self) -> Result<()> {
+        let as_of = self.args.as_of.unwrap_or_else(grit_utils::today);
+        let (results, total) = self.age_results(as_of)?;
+
+        let show_total =
+            self.args.format != OutputFormat::Json && self.args.format != OutputFormat::Prom;
+
+        let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+        self.age_table(&results, Some(&total).filter(|_| show_total))
+            .write(&mut w, self.args.format)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use log::LevelFilter;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    #[test]
+    fn test_age_output() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let as_of = NaiveDate::from_ymd_opt(2021, 1, 1).unwrap();
+        let mut o = AgeOutput::new(String::from("src/main.rs"));
+        o.add(NaiveDate::from_ymd_opt(2020, 12, 22).unwrap(), 6, as_of);
+        o.add(NaiveDate::from_ymd_opt(2019, 1, 1).unwrap(), 3, as_of);
+        o.add(NaiveDate::from_ymd_opt(2020, 12, 22).unwrap(), 1, as_of);
+
+        assert_eq!(o.lines, 10);
+        assert_eq!(o.ages, vec![(10, 7), (731, 3)]);
+        assert_eq!(o.percentile(50.0), 10);
+        assert_eq!(o.percentile(75.0), 731);
+        assert!((o.perc_older_than(365.0) - 0.3).abs() < f64::EPSILON);
+        assert_eq!(o.oldest, Some(NaiveDate::from_ymd_opt(2019, 1, 1).unwrap()));
+    }
+
+    #[test]
+    fn test_process_age() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+        let file = "target/test_process_age.csv";
+
+        let args = AgeArgs::builder(String::from(path))
+            .on_date(Some(NaiveDate::from_ymd_opt(2021, 1, 1).unwrap()))
+            .file(Some(String::from(file)))
+            .build()
+            .unwrap();
+
+        Age::new(args).process().unwrap();
+
+        let rows: Vec<String> = std::fs::read_to_string(file)
+            .unwrap()
+            .lines()
+            .skip(1)
+            .map(String::from)
+            .collect();
+
+        // main.rs is 3 lines from 2020-01-01 and 2 from 2020-01-02, README.md one line from
+        // each of 2020-01-01 and 2020-01-05, lib.rs 4 lines from 2020-01-02
+        assert_eq!(
+            rows,
+            vec![
+                "src/main.rs,5,366,366,366,60.0,2020-01-01",
+                "src/lib.rs,4,365,365,365,0.0,2020-01-02",
+                "README.md,2,362,366,366,50.0,2020-01-01",
+                "Total,11,365,366,366,36.4,2020-01-01",
+            ]
+        );
+    }
+}
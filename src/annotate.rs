@@ -0,0 +1,292 @@
+use super::Processable;
+use crate::identity;
+use crate::ignore_revs;
+use crate::output;
+use crate::table::Table;
+use crate::utils::grit_utils;
+use crate::utils::grit_utils::{OutputFormat, RelativeTo};
+use anyhow::Result;
+use chrono::NaiveDate;
+use std::io::Write;
+
+pub struct AnnotateArgs {
+    path: String,
+    full_path_filename: String,
+    output_file: Option<String>,
+    format: OutputFormat,
+    relative_to: RelativeTo,
+}
+
+impl AnnotateArgs {
+    pub fn new(
+        path: String,
+        full_path_filename: String,
+        output_file: Option<String>,
+        format: OutputFormat,
+        relative_to: RelativeTo,
+    ) -> AnnotateArgs {
+        AnnotateArgs {
+            path,
+            full_path_filename,
+            output_file,
+            format,
+            relative_to,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AnnotateLine {
+    pub line: usize,
+    pub author: String,
+    pub email: String,
+    pub commit_id: String,
+    pub date: NaiveDate,
+    pub age_days: i64,
+}
+
+impl AnnotateLine {
+    fn to_json(&self) -> String {
+        format!(
+            "{{\"line\":{},\"author\":{},\"email\":{},\"commit\":{},\"date\":{},\"age_days\":{}}}",
+            self.line,
+            grit_utils::json_string(&self.author),
+            grit_utils::json_string(&self.email),
+            grit_utils::json_string(&self.commit_id),
+            grit_utils::json_string(&grit_utils::format_date(self.date)),
+            self.age_days
+        )
+    }
+}
+
+/// blames `file_name` at HEAD, looking past the ignored revisions, and expands the runs of
+/// lines into one entry per line
+pub fn annotate_file(repo_path: &str, file_name: &str) -> Result<Vec<AnnotateLine>> {
+    let repo = grit_utils::open_repo(repo_path)?;
+    let mailmap = repo.mailmap()?;
+    let today = grit_utils::today();
+
+    let blame = ignore_revs::blame_file(&repo, &grit_utils::git_path(file_name), None, None)?;
+
+    let mut result: Vec<AnnotateLine> = Vec::new();
+
+    for b in blame.iter() {
+        let sig = mailmap.resolve_signature(&b.signature)?;
+        let name = String::from_utf8_lossy(sig.name_bytes()).to_string();
+        let email = String::from_utf8_lossy(sig.email_bytes()).to_string();
+        let author = identity::display_author(&name, &email);
+        let email = identity::display_email(&name, &email);
+        let commit_id = b.commit_id.to_string();
+        let date = grit_utils::convert_git_time(&sig.when());
+        let age_days = (today - date).num_days();
+
+        for _ in 0..b.lines {
+            result.push(AnnotateLine {
+                line: result.len() + 1,
+                author: author.clone(),
+                email: email.clone(),
+                commit_id: commit_id.clone(),
+                date,
+                age_days,
+            });
+        }
+    }
+
+    Ok(result)
+}
+
+pub struct Annotate {
+    args: AnnotateArgs,
+}
+
+impl Annotate {
+    pub fn new(args: AnnotateArgs) -> Annotate {
+        Annotate { args }
+    }
+
+    fn write_json(&self, w: &mut dyn Write, data: &[AnnotateLine]) -> Result<()> {
+        let lines: Vec<String> = data.iter().map(|l| l.to_json()).collect();
+
+        output::write_json(
+            w,
+            &format!(
+                "{{\"file\":{},\"lines\":[{}]}}",
+                grit_utils::json_string(&self.args.full_path_filename),
+                lines.join(",")
+            ),
+        )?;
+
+        Ok(())
+    }
+
+    fn write_rows(&self, w: &mut dyn Write, data: &[AnnotateLine]) -> Result<()> {
+        let mut table = Table::new();
+
+        table.set_titles(table_row![
+            "line", "author", "email", "commit", "date", "age days"
+        ]);
+
+        for l in data.iter() {
+            table.add_row(table_row![
+                l.line,
+                l.author,
+                l.email,
+                l.commit_id,
+                grit_utils::format_date(l.date),
+                l.age_days
+            ]);
+        }
+
+        table.write(w, self.args.format)?;
+
+        Ok(())
+    }
+}
+
+impl Processable<()> for Annotate {
+    fn process(&self) -> Result<()> {
+        let file_name = grit_utils::repo_file_path(
+            &self.args.path,
+            &self.args.full_path_filename,
+            self.args.relative_to,
+        )?;
+
+        let data = annotate_file(&self.args.path, &file_name)?;
+
+        let mut w = grit_utils::output_writer(self.args.output_file.as_deref())?;
+
+        match self.args.format {
+            OutputFormat::Json => self.write_json(&mut w, &data)?,
+            _ => self.write_rows(&mut w, &data)?,
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use log::LevelFilter;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    #[test]
+    fn test_annotate_line_to_json() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let line = AnnotateLine {
+            line: 3,
+            author: String::from("Todd \"T\" Bush"),
+            email: String::from("todd@example.com"),
+            commit_id: String::from("abc123"),
+            date: NaiveDate::from_ymd_opt(2020, 3, 13).unwrap(),
+            age_days: 10,
+        };
+
+        assert_eq!(
+            line.to_json(),
+            "{\"line\":3,\"author\":\"Todd \\\"T\\\" Bush\",\"email\":\"todd@example.com\",\"commit\":\"abc123\",\"date\":\"2020-03-13\",\"age_days\":10}"
+        );
+    }
+
+    #[test]
+    fn test_annotate() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let args = AnnotateArgs::new(
+            path.to_string(),
+            "README.md".to_string(),
+            None,
+            OutputFormat::Json,
+            RelativeTo::Repo,
+        );
+
+        let annotate = Annotate::new(args);
+
+        let lines = annotate_file(path, "README.md").unwrap();
+
+        let authors: Vec<(usize, &str, String)> = lines
+            .iter()
+            .map(|l| (l.line, l.author.as_str(), grit_utils::format_date(l.date)))
+            .collect();
+
+        assert_eq!(
+            authors,
+            vec![
+                (1, "Alice", String::from("2020-01-01")),
+                (2, "Bob", String::from("2020-01-05")),
+            ]
+        );
+        assert!(lines.iter().all(|l| l.age_days >= 0));
+
+        let result = match annotate.process() {
+            Ok(()) => true,
+            Err(e) => {
+                error!("test_annotate ended in error {:?}", e);
+                false
+            }
+        };
+
+        assert!(result, "See error above");
+    }
+
+    #[test]
+    fn test_annotate_ignore_revs() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+        let repo = git2::Repository::open(path).unwrap();
+
+        // Carol adds a trailing space to every line of the README
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let blob = repo.blob(b"# fixture \nrun with cargo run \n").unwrap();
+        let mut tb = repo.treebuilder(Some(&head.tree().unwrap())).unwrap();
+        tb.insert("README.md", blob, 0o100644).unwrap();
+        let tree = repo.find_tree(tb.write().unwrap()).unwrap();
+        let carol = git2::Signature::new(
+            "Carol",
+            "carol@example.com",
+            &git2::Time::new(1_578_484_800, 0),
+        )
+        .unwrap();
+        let reformat = repo
+            .commit(Some("HEAD"), &carol, &carol, "reformat", &tree, &[&head])
+            .unwrap();
+
+        let authors = || -> Vec<(usize, String)> {
+            annotate_file(path, "README.md")
+                .unwrap()
+                .into_iter()
+                .map(|l| (l.line, l.author))
+                .collect()
+        };
+
+        assert_eq!(
+            authors(),
+            vec![(1, String::from("Carol")), (2, String::from("Carol"))]
+        );
+
+        std::fs::write(
+            td.path().join(".git-blame-ignore-revs"),
+            format!("{}\n", reformat),
+        )
+        .unwrap();
+        repo.config()
+            .unwrap()
+            .set_str("blame.ignoreRevsFile", ".git-blame-ignore-revs")
+            .unwrap();
+
+        // the reformat is looked past to the lines Alice and Bob wrote
+        assert_eq!(
+            authors(),
+            vec![(1, String::from("Alice")), (2, String::from("Bob"))]
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use crate::identity;
+use crate::utils::grit_utils;
+use anyhow::Result;
+use chrono::NaiveDate;
+use git2::{Oid, Repository};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// An author's non merge commits over a walk of the history and the days they fall on.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AuthorStats {
+    pub commits: HashSet<String>,
+    pub days: BTreeSet<NaiveDate>,
+}
+
+impl AuthorStats {
+    pub fn first_commit(&self) -> Option<NaiveDate> {
+        self.days.iter().next().copied()
+    }
+
+    pub fn last_commit(&self) -> Option<NaiveDate> {
+        self.days.iter().next_back().copied()
+    }
+
+    /// days with at least one commit
+    pub fn active_days(&self) -> usize {
+        self.days.len()
+    }
+
+    /// `lines` spread over the active days, 0 without any
+    pub fn per_active_day(&self, lines: i32) -> f64 {
+        match self.active_days() {
+            0 => 0.0,
+            d => lines as f64 / d as f64,
+        }
+    }
+
+    pub fn merge(&mut self, other: AuthorStats) {
+        self.commits.extend(other.commits);
+        self.days.extend(other.days);
+    }
+}
+
+/// The commits an author walk takes in.
+#[derive(Clone, Debug, Default)]
+pub struct AuthorWalk {
+    /// the commit the walk starts from, HEAD when None
+    pub tip: Option<Oid>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    /// author names left out, as fame's `--restrict-author`
+    pub restrict_authors: Option<Vec<String>>,
+}
+
+impl AuthorWalk {
+    /// the stats of every author with a non merge commit in the walk, keyed by how the
+    /// author is displayed, so they join with the other per author results.  Excluded
+    /// authors are left out.
+    pub fn stats(&self, repo: &Repository) -> Result<HashMap<String, AuthorStats>> {
+        let mut revwalk = repo.revwalk()?;
+        revwalk.set_sorting(git2::Sort::TIME)?;
+
+        match self.tip {
+            Some(oid) => revwalk.push(oid)?,
+            None => revwalk.push_head()?,
+        }
+
+        let mut stats: HashMap<String, AuthorStats> = HashMap::new();
+
+        for oid in revwalk {
+            let commit = repo.find_commit(oid?)?;
+
+            if commit.parent_count() > 1 {
+                continue;
+            }
+
+            let day = grit_utils::convert_git_time(&commit.time());
+
+            if self.start_date.map(|d| day < d).unwrap_or(false)
+                || self.end_date.map(|d| day > d).unwrap_or(false)
+            {
+                continue;
+            }
+
+            let author = commit.author();
+            let name = String::from_utf8_lossy(author.name_bytes()).to_string();
+            let email = String::from_utf8_lossy(author.email_bytes()).to_string();
+
+            if self
+                .restrict_authors
+                .as_ref()
+                .map(|ra| ra.contains(&name))
+                .unwrap_or(false)
+                || identity::is_excluded(&name, &email)
+            {
+                continue;
+            }
+
+            let s = stats
+                .entry(identity::display_author(&name, &email))
+                .or_default();
+            s.commits.insert(commit.id().to_string());
+            s.days.insert(day);
+        }
+
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use log::LevelFilter;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    #[test]
+    fn test_author_stats() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let repo = Repository::open(td.path()).unwrap();
+
+        let stats = AuthorWalk::default().stats(&repo).unwrap();
+
+        let alice = &stats["Alice"];
+        assert_eq!(alice.commits.len(), 2);
+        assert_eq!(
+            alice.first_commit(),
+            Some(NaiveDate::from_ymd_opt(2020, 1, 1).unwrap())
+        );
+        assert_eq!(
+            alice.last_commit(),
+            Some(NaiveDate::from_ymd_opt(2020, 1, 2).unwrap())
+        );
+        assert_eq!(alice.active_days(), 2);
+        assert_eq!(alice.per_active_day(6), 3.0);
+
+        let bob = &stats["Bob"];
+        assert_eq!(bob.commits.len(), 2);
+        assert_eq!(bob.active_days(), 2);
+
+        let walk = AuthorWalk {
+            start_date: Some(NaiveDate::from_ymd_opt(2020, 1, 2).unwrap()),
+            end_date: Some(NaiveDate::from_ymd_opt(2020, 1, 2).unwrap()),
+            restrict_authors: Some(vec![String::from("Bob")]),
+            ..AuthorWalk::default()
+        };
+        let stats = walk.stats(&repo).unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats["Alice"].active_days(), 1);
+        assert_eq!(AuthorStats::default().per_active_day(10), 0.0);
+    }
+}
@@ -0,0 +1,266 @@
+use super::Processable;
+use crate::identity;
+use crate::table::Table;
+use crate::utils::grit_utils;
+use crate::utils::grit_utils::OutputFormat;
+use anyhow::Result;
+use chrono::NaiveDate;
+use git2::Oid;
+
+pub struct BranchesArgs {
+    path: String,
+    base: String,
+    older_than: Option<i64>,
+    file: Option<String>,
+    format: OutputFormat,
+}
+
+impl BranchesArgs {
+    pub fn new(
+        path: String,
+        base: String,
+        older_than: Option<i64>,
+        file: Option<String>,
+        format: OutputFormat,
+    ) -> BranchesArgs {
+        BranchesArgs {
+            path,
+            base,
+            older_than,
+            file,
+            format,
+        }
+    }
+}
+
+/// A branch, its last commit and how far it has drifted from the base.
+#[derive(Clone, Debug, PartialEq)]
+struct BranchOutput {
+    name: String,
+    last_commit: NaiveDate,
+    author: String,
+    /// commits on the branch the base doesn't have
+    ahead: usize,
+    /// commits on the base the branch doesn't have
+    behind: usize,
+}
+
+impl BranchOutput {
+    fn days_inactive(&self, as_of: NaiveDate) -> i64 {
+        (as_of - self.last_commit).num_days().max(0)
+    }
+}
+
+pub struct Branches {
+    args: BranchesArgs,
+}
+
+impl Branches {
+    pub fn new(args: BranchesArgs) -> Branches {
+        Branches { args }
+    }
+
+    /// every local and remote branch idle for more than `older_than` days as of `as_of`,
+    /// the longest idle first
+    fn process_branches(&self, as_of: NaiveDate) -> Result<Vec<BranchOutput>> {
+        let repo = grit_utils::open_repo(&self.args.path)?;
+
+        let base = Oid::from_bytes(&grit_utils::resolve_rev(&self.args.path, &self.args.base)?)?;
+
+        let mut output: Vec<BranchOutput> = Vec::new();
+
+        for branch in repo.branches(None)? {
+            let (branch, _) = branch?;
+            let reference = branch.get();
+
+            // a remote's HEAD only points at one of its branches
+            if reference.symbolic_target_bytes().is_some() {
+                continue;
+            }
+
+            let commit = reference.peel_to_commit()?;
+
+            let author = commit.author();
+            let name = String::from_utf8_lossy(author.name_bytes()).to_string();
+            let email = String::from_utf8_lossy(author.email_bytes()).to_string();
+
+            if identity::is_excluded(&name, &email) {
+                continue;
+            }
+
+            let (ahead, behind) = repo.graph_ahead_behind(commit.id(), base)?;
+
+            let o = BranchOutput {
+                name: String::from_utf8_lossy(branch.name_bytes()?).to_string(),
+                last_commit: grit_utils::convert_git_time(&commit.time()),
+                author: identity::display_author(&name, &email),
+                ahead,
+                behind,
+            };
+
+            if self
+                .args
+                .older_than
+                .map(|d| o.days_inactive(as_of) <= d)
+                .unwrap_or(false)
+            {
+                continue;
+            }
+
+            output.push(o);
+        }
+
+        output.sort_by(|a, b| a.last_commit.cmp(&b.last_commit).then(a.name.cmp(&b.name)));
+
+        Ok(output)
+    }
+
+    fn display_output(&self, output: &[BranchOutput], as_of: NaiveDate) -> Result<()> {
+        let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+
+        let mut table = Table::new();
+
+        table.set_titles(table_row![
+            "branch",
+            "last commit",
+            "author",
+            "days inactive",
+            "ahead",
+            "behind"
+        ]);
+
+        output.iter().for_each(|r| {
+            table.add_row(table_row![
+                r.name,
+                grit_utils::format_date(r.last_commit),
+                r.author,
+                r.days_inactive(as_of),
+                r.ahead,
+                r.behind
+            ]);
+        });
+
+        table.add_label(0, "branch");
+        table.add_label(2, "author");
+        table.add_metric(
+            3,
+            "grit_branch_days_inactive",
+            "Days since the branch's last commit.",
+        );
+        table.add_metric(
+            4,
+            "grit_branch_ahead",
+            "Commits on the branch the base doesn't have.",
+        );
+        table.add_metric(
+            5,
+            "grit_branch_behind",
+            "Commits on the base the branch doesn't have.",
+        );
+
+        table.write(&mut w, self.args.format)?;
+
+        Ok(())
+    }
+}
+
+impl Processable<()> for Branches {
+    fn process(&self) -> Result<()> {
+        let as_of = grit_utils::today();
+        let output = self.process_branches(as_of)?;
+
+        self.display_output(&output, as_of)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use git2::{Repository, Signature, Time};
+    use log::LevelFilter;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    /// the fixture with a `stale` branch at Bob's 2020-01-02 commit and a `fresh` branch one
+    /// commit ahead of HEAD, made on 2020-01-08
+    fn branch_repo() -> TempDir {
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let repo = Repository::open(td.path()).unwrap();
+
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let stale = head.parent(0).unwrap().parent(0).unwrap();
+        repo.branch("stale", &stale, false).unwrap();
+
+        // 2020-01-08 12:00 UTC
+        let carol =
+            Signature::new("Carol", "carol@example.com", &Time::new(1_578_484_800, 0)).unwrap();
+        let fresh = repo
+            .commit(
+                None,
+                &carol,
+                &carol,
+                "fresh",
+                &head.tree().unwrap(),
+                &[&head],
+            )
+            .unwrap();
+        repo.branch("fresh", &repo.find_commit(fresh).unwrap(), false)
+            .unwrap();
+
+        td
+    }
+
+    fn branches(path: &str, older_than: Option<i64>) -> Vec<(String, String, i64, usize, usize)> {
+        let as_of = NaiveDate::from_ymd_opt(2020, 1, 10).unwrap();
+
+        Branches::new(BranchesArgs::new(
+            path.to_string(),
+            String::from("HEAD"),
+            older_than,
+            None,
+            OutputFormat::Csv,
+        ))
+        .process_branches(as_of)
+        .unwrap()
+        .into_iter()
+        .map(|b| {
+            let days = b.days_inactive(as_of);
+            (b.name, b.author, days, b.ahead, b.behind)
+        })
+        .collect()
+    }
+
+    #[test]
+    fn test_process_branches() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td = branch_repo();
+        let path = td.path().to_str().unwrap();
+        let head = Repository::open(path)
+            .unwrap()
+            .head()
+            .unwrap()
+            .shorthand()
+            .unwrap()
+            .to_string();
+
+        assert_eq!(
+            branches(path, None),
+            vec![
+                (String::from("stale"), String::from("Bob"), 8, 0, 2),
+                (head.clone(), String::from("Bob"), 5, 0, 0),
+                (String::from("fresh"), String::from("Carol"), 2, 1, 0),
+            ]
+        );
+
+        assert_eq!(
+            branches(path, Some(5))
+                .into_iter()
+                .map(|b| b.0)
+                .collect::<Vec<String>>(),
+            vec![String::from("stale")]
+        );
+    }
+}
@@ -1,73 +1,213 @@
 use super::Processable;
+use crate::chart;
+use crate::chart::ChartOptions;
+use crate::identity;
+use crate::identity::GroupBy;
+use crate::metric;
+use crate::metric::{Commits, Metrics};
+use crate::table::Table;
 use crate::utils::grit_utils;
-use anyhow::Result;
-use charts::{
-    Chart, LineSeriesView, MarkerType, PointDatum, PointLabelPosition, ScaleBand, ScaleLinear,
-};
-use chrono::naive::{MAX_DATE, MIN_DATE};
-use chrono::offset::{Local, TimeZone};
-use chrono::{Date, Datelike, Duration, NaiveDateTime, Weekday};
-use csv::Writer;
-use git2::Repository;
-use std::collections::hash_map::Entry::{Occupied, Vacant};
-use std::collections::HashMap;
+use crate::utils::grit_utils::OutputFormat;
+use anyhow::{anyhow, Result};
+use charts::{LineSeriesView, MarkerType, PointDatum, PointLabelPosition, ScaleBand, ScaleLinear};
+use chrono::{Duration, NaiveDate};
+use std::collections::BTreeMap;
 use std::fs::File;
-use std::io;
 use std::io::Write;
 use std::ops::Add;
 use std::path::Path;
+use std::str::FromStr;
+
+/// the kinds of commit `--split-merges` counts apart
+const MERGE_KIND: &str = "merge";
+const REGULAR_KIND: &str = "regular";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChartType {
+    Line,
+    Calendar,
+}
+
+impl FromStr for ChartType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "line" => Ok(ChartType::Line),
+            "calendar" => Ok(ChartType::Calendar),
+            _ => Err(anyhow!("chart must be either 'line' or 'calendar'")),
+        }
+    }
+}
 
 pub struct ByDateArgs {
     path: String,
-    start_date: Option<Date<Local>>,
-    end_date: Option<Date<Local>>,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
     file: Option<String>,
     image: bool,
     ignore_weekends: bool,
     ignore_gap_fill: bool,
     html: bool,
     restrict_authors: Option<String>,
+    chart: ChartType,
+    chart_options: ChartOptions,
+    format: OutputFormat,
+    path_filter: Option<String>,
+    cumulative: bool,
+    rolling: Option<usize>,
+    split_merges: bool,
 }
 
 impl ByDateArgs {
-    pub fn new(
-        path: String,
-        start_date: Option<Date<Local>>,
-        end_date: Option<Date<Local>>,
-        file: Option<String>,
-        image: bool,
-        ignore_weekends: bool,
-        ignore_gap_fill: bool,
-        html: bool,
-        restrict_authors: Option<String>,
-    ) -> ByDateArgs {
-        ByDateArgs {
-            path: path,
-            start_date: start_date,
-            end_date: end_date,
-            file: file,
-            image: image,
-            ignore_weekends: ignore_weekends,
-            ignore_gap_fill: ignore_gap_fill,
-            html: html,
-            restrict_authors: restrict_authors,
+    pub fn builder(path: String) -> ByDateArgsBuilder {
+        ByDateArgsBuilder {
+            args: ByDateArgs {
+                path,
+                start_date: None,
+                end_date: None,
+                file: None,
+                image: false,
+                ignore_weekends: false,
+                ignore_gap_fill: false,
+                html: false,
+                restrict_authors: None,
+                chart: ChartType::Line,
+                chart_options: ChartOptions::default(),
+                format: OutputFormat::Csv,
+                path_filter: None,
+                cumulative: false,
+                rolling: None,
+                split_merges: false,
+            },
+        }
+    }
+}
+
+/// Builds a `ByDateArgs` by name rather than position, validating it on `build`.
+pub struct ByDateArgsBuilder {
+    args: ByDateArgs,
+}
+
+impl ByDateArgsBuilder {
+    pub fn start_date(mut self, start_date: Option<NaiveDate>) -> ByDateArgsBuilder {
+        self.args.start_date = start_date;
+        self
+    }
+
+    pub fn end_date(mut self, end_date: Option<NaiveDate>) -> ByDateArgsBuilder {
+        self.args.end_date = end_date;
+        self
+    }
+
+    pub fn file(mut self, file: Option<String>) -> ByDateArgsBuilder {
+        self.args.file = file;
+        self
+    }
+
+    pub fn image(mut self, image: bool) -> ByDateArgsBuilder {
+        self.args.image = image;
+        self
+    }
+
+    pub fn ignore_weekends(mut self, ignore_weekends: bool) -> ByDateArgsBuilder {
+        self.args.ignore_weekends = ignore_weekends;
+        self
+    }
+
+    pub fn ignore_gap_fill(mut self, ignore_gap_fill: bool) -> ByDateArgsBuilder {
+        self.args.ignore_gap_fill = ignore_gap_fill;
+        self
+    }
+
+    pub fn html(mut self, html: bool) -> ByDateArgsBuilder {
+        self.args.html = html;
+        self
+    }
+
+    pub fn restrict_authors(mut self, restrict_authors: Option<String>) -> ByDateArgsBuilder {
+        self.args.restrict_authors = restrict_authors;
+        self
+    }
+
+    pub fn chart(mut self, chart: ChartType) -> ByDateArgsBuilder {
+        self.args.chart = chart;
+        self
+    }
+
+    pub fn chart_options(mut self, chart_options: ChartOptions) -> ByDateArgsBuilder {
+        self.args.chart_options = chart_options;
+        self
+    }
+
+    pub fn format(mut self, format: OutputFormat) -> ByDateArgsBuilder {
+        self.args.format = format;
+        self
+    }
+
+    pub fn path_filter(mut self, path_filter: Option<String>) -> ByDateArgsBuilder {
+        self.args.path_filter = path_filter;
+        self
+    }
+
+    pub fn cumulative(mut self, cumulative: bool) -> ByDateArgsBuilder {
+        self.args.cumulative = cumulative;
+        self
+    }
+
+    pub fn rolling(mut self, rolling: Option<usize>) -> ByDateArgsBuilder {
+        self.args.rolling = rolling;
+        self
+    }
+
+    pub fn split_merges(mut self, split_merges: bool) -> ByDateArgsBuilder {
+        self.args.split_merges = split_merges;
+        self
+    }
+
+    pub fn build(self) -> Result<ByDateArgs> {
+        grit_utils::validate_args(
+            self.args.start_date,
+            self.args.end_date,
+            self.args.file.as_deref(),
+            self.args.format,
+        )?;
+
+        if self.args.image {
+            grit_utils::check_image_file(self.args.file.as_deref())?;
         }
+
+        Ok(self.args)
     }
 }
 
-#[derive(Ord, PartialOrd, Eq, PartialEq, Clone)]
+#[derive(PartialEq, Clone)]
 struct ByDateOutput {
-    date: Date<Local>,
+    date: NaiveDate,
+    /// the team of the series when grouping by team, otherwise empty
+    team: String,
+    /// 'merge' or 'regular' commits with `--split-merges`, otherwise empty
+    kind: String,
     count: i32,
+    /// the moving average of the count, with `--rolling`
+    average: Option<f32>,
 }
 
 impl ByDateOutput {
-    fn new(date: Date<Local>, count: i32) -> ByDateOutput {
+    fn new(date: NaiveDate, count: i32) -> ByDateOutput {
         ByDateOutput {
-            date: date,
-            count: count,
+            date,
+            team: String::new(),
+            kind: String::new(),
+            count,
+            average: None,
         }
     }
+
+    /// the charted value, the moving average when there is one
+    fn value(&self) -> f32 {
+        self.average.unwrap_or(self.count as f32)
+    }
 }
 
 impl PointDatum<String, f32> for ByDateOutput {
@@ -76,11 +216,16 @@ impl PointDatum<String, f32> for ByDateOutput {
     }
 
     fn get_y(&self) -> f32 {
-        self.count as f32
+        self.value()
     }
 
     fn get_key(&self) -> String {
-        String::from("")
+        [self.team.as_str(), self.kind.as_str()]
+            .iter()
+            .filter(|k| !k.is_empty())
+            .cloned()
+            .collect::<Vec<&str>>()
+            .join(" ")
     }
 }
 
@@ -90,107 +235,155 @@ pub struct ByDate {
 
 impl ByDate {
     pub fn new(args: ByDateArgs) -> ByDate {
-        ByDate { args: args }
+        ByDate { args }
     }
 
     fn process_date(&self) -> Result<Vec<ByDateOutput>> {
         let end_date = match self.args.end_date {
             Some(d) => d,
-            None => Local
-                .from_local_date(&MAX_DATE)
-                .single()
-                .expect("Cannot unwrap MAX DATE"),
+            None => NaiveDate::MAX,
         };
 
         let start_date = match self.args.start_date {
             Some(d) => d,
-            None => Local
-                .from_local_date(&MIN_DATE)
-                .single()
-                .expect("Cannot unwrap MIN DATE"),
+            None => NaiveDate::MIN,
         };
 
         let restrict_authors =
             grit_utils::convert_string_list_to_vec(self.args.restrict_authors.clone());
 
-        let end_date_sec = end_date.naive_local().and_hms(23, 59, 59).timestamp();
-        let start_date_sec = start_date.naive_local().and_hms(0, 0, 0).timestamp();
+        let path_filter = grit_utils::parse_patterns(self.args.path_filter.clone())?;
 
-        let mut output_map: HashMap<Date<Local>, ByDateOutput> = HashMap::new();
+        let end_date_sec = grit_utils::end_of_day_seconds(end_date);
+        let start_date_sec = grit_utils::start_of_day_seconds(start_date);
 
-        let repo = Repository::open(&self.args.path).expect(format_tostr!(
-            "Could not open repo for path {}",
-            &self.args.path
-        ));
+        let repo = grit_utils::open_repo(&self.args.path)?;
 
-        let mut revwalk = repo.revwalk()?;
+        let pb = grit_utils::spinner("{spinner} {pos} commits read");
 
-        revwalk
-            .set_sorting(git2::Sort::NONE | git2::Sort::TIME)
-            .expect("Could not sort revwalk");
+        let by_team = identity::group_by() == GroupBy::Team;
 
-        revwalk.push_head()?;
+        let mut metrics: Metrics<(String, String, NaiveDate)> =
+            Metrics::new().with(Commits::default());
 
-        let revwalk = revwalk.filter_map(|id| {
-            let id = filter_try!(id);
-            let commit = filter_try!(repo.find_commit(id));
+        metric::walk_commits(&repo, &mut metrics, |commit| {
+            pb.inc(1);
             let commit_time = commit.time().seconds();
 
-            if self.args.ignore_weekends && self.is_weekend(commit_time) {
-                return None;
-            }
-
-            if commit_time < start_date_sec {
-                return None;
+            if self.args.ignore_weekends && self.is_weekend(&commit.time()) {
+                return Ok(None);
             }
 
-            if commit_time > end_date_sec {
-                return None;
+            if commit_time < start_date_sec || commit_time > end_date_sec {
+                return Ok(None);
             }
 
             if let Some(v) = &restrict_authors {
-                let name: String = commit.clone().author().name().unwrap().to_string();
+                let name = String::from_utf8_lossy(commit.author().name_bytes()).to_string();
                 if v.iter().any(|a| a == &name) {
-                    return None;
+                    return Ok(None);
                 }
             }
 
-            Some(Ok(commit))
-        });
+            let name = String::from_utf8_lossy(commit.author().name_bytes()).to_string();
+            let email = String::from_utf8_lossy(commit.author().email_bytes()).to_string();
 
-        debug!("filtering completed");
+            if identity::is_excluded(&name, &email) {
+                return Ok(None);
+            }
+
+            if let Some(pf) = &path_filter {
+                if !grit_utils::commit_touches_paths(&repo, commit, pf)? {
+                    return Ok(None);
+                }
+            }
 
-        for commit in revwalk {
-            let commit = commit?;
-            let commit_time = &commit.time();
-            let dt = grit_utils::convert_git_time(commit_time);
+            let team = if by_team {
+                identity::team_of(&name, &email)
+            } else {
+                String::new()
+            };
 
-            let v = match output_map.entry(dt) {
-                Vacant(entry) => entry.insert(ByDateOutput::new(dt, 0)),
-                Occupied(entry) => entry.into_mut(),
+            let kind = if !self.args.split_merges {
+                ""
+            } else if commit.parent_count() > 1 {
+                MERGE_KIND
+            } else {
+                REGULAR_KIND
             };
 
-            v.count += 1;
+            Ok(Some((
+                team,
+                kind.to_string(),
+                grit_utils::convert_git_time(&commit.time()),
+            )))
+        })?;
+
+        pb.finish_and_clear();
+
+        // one series per team and kind of commit, or a single one
+        let mut series: BTreeMap<(String, String), Vec<ByDateOutput>> = BTreeMap::new();
+
+        for key in metrics.keys() {
+            series
+                .entry((key.0.clone(), key.1.clone()))
+                .or_default()
+                .push(ByDateOutput::new(
+                    key.2,
+                    metrics.value("commits", key) as i32,
+                ));
+        }
+
+        let mut output: Vec<ByDateOutput> = Vec::new();
+
+        for ((team, kind), s) in series.into_iter() {
+            let mut s = self.series_output(s);
+            s.iter_mut().for_each(|o| {
+                o.team = team.clone();
+                o.kind = kind.clone();
+            });
+            output.extend(s);
         }
 
-        let mut output: Vec<ByDateOutput> = output_map.values().cloned().collect();
+        output.sort_by(|a, b| {
+            a.date
+                .cmp(&b.date)
+                .then(a.team.cmp(&b.team))
+                .then(a.kind.cmp(&b.kind))
+        });
+
+        Ok(output)
+    }
 
-        output.sort();
+    /// the dates of one series in order, gap filled, totalled and averaged as asked
+    fn series_output(&self, mut output: Vec<ByDateOutput>) -> Vec<ByDateOutput> {
+        output.sort_by_key(|o| o.date);
 
         if !&self.args.ignore_gap_fill {
             output = self.fill_date_gaps(output);
         }
 
-        Ok(output)
+        if self.args.cumulative {
+            output = running_totals(output);
+        }
+
+        if let Some(days) = self.args.rolling {
+            output = moving_averages(output, days);
+        }
+
+        output
     }
 
-    fn is_weekend(&self, ts: i64) -> bool {
-        let d = Local.from_utc_datetime(&NaiveDateTime::from_timestamp(ts, 0));
-        d.weekday() == Weekday::Sun || d.weekday() == Weekday::Sat
+    fn is_weekend(&self, time: &git2::Time) -> bool {
+        grit_utils::is_weekend(time)
     }
 
     fn fill_date_gaps(&self, input: Vec<ByDateOutput>) -> Vec<ByDateOutput> {
-        let mut last_date: Date<Local> = input[0].date;
+        if input.is_empty() {
+            return input;
+        }
+
+        let mut last_date: NaiveDate = input[0].date;
         let mut output = input;
         let mut i = 0;
 
@@ -211,31 +404,147 @@ impl ByDate {
     }
 
     fn display_text_output(&self, output: Vec<ByDateOutput>) -> Result<()> {
-        let w = match &self.args.file {
-            Some(f) => {
-                let file = File::create(f)?;
-                Box::new(file) as Box<dyn Write>
-            }
-            None => Box::new(io::stdout()) as Box<dyn Write>,
-        };
+        let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+
+        // the last running total already is the total
+        let total = !self.args.cumulative
+            && self.args.format != OutputFormat::Json
+            && self.args.format != OutputFormat::Prom;
+
+        self.date_table(output, total)
+            .write(&mut w, self.args.format)?;
+
+        Ok(())
+    }
+
+    /// the commits per date as a table, for callers showing it themselves
+    pub(crate) fn results_table(&self) -> Result<Table> {
+        Ok(self.date_table(self.process_date()?, false))
+    }
 
-        let mut wtr = Writer::from_writer(w);
+    /// the commits per date, followed by a total row when `total` is set
+    fn date_table(&self, output: Vec<ByDateOutput>, total: bool) -> Table {
+        let mut table = Table::new();
 
-        wtr.write_record(&["date", "count"])?;
+        let rolling = self.args.rolling.is_some();
+        let by_team = identity::group_by() == GroupBy::Team;
+
+        let split_merges = self.args.split_merges;
+
+        let mut titles = table_row!["date"];
+        if by_team {
+            titles.push(String::from("team"));
+        }
+        if split_merges {
+            titles.push(String::from("kind"));
+        }
+        titles.push(String::from("count"));
+        if rolling {
+            titles.push(String::from("average"));
+        }
+        table.set_titles(titles);
+
+        // the count follows the date, and the team and kind when there are
+        let count = 1 + by_team as usize + split_merges as usize;
 
         let mut total_count = 0;
 
         output.iter().for_each(|r| {
-            wtr.serialize((grit_utils::format_date(r.date), r.count))
-                .expect("Cannot seralize table row");
+            let mut row = table_row![grit_utils::format_date(r.date)];
+
+            if by_team {
+                row.push(r.team.clone());
+            }
+
+            if split_merges {
+                row.push(r.kind.clone());
+            }
+
+            row.push(r.count.to_string());
+
+            if rolling {
+                row.push(format!("{:.2}", r.value()));
+            }
+
+            table.add_row(row);
 
             total_count += r.count;
         });
 
-        wtr.serialize(("Total", total_count))
-            .expect("Cannot Seralize Total Count Row");
+        if total {
+            let mut row = table_row!["Total"];
+            if by_team {
+                row.push(String::new());
+            }
+            if split_merges {
+                row.push(String::new());
+            }
+            row.push(total_count.to_string());
+            if rolling {
+                row.push(String::new());
+            }
+            table.add_row(row);
+        }
+
+        table.add_label(0, "date");
+        if by_team {
+            table.add_label(1, "team");
+        }
+        if split_merges {
+            table.add_label(count - 1, "kind");
+        }
+        if self.args.cumulative {
+            table.add_metric(
+                count,
+                "grit_commits_cumulative",
+                "Commits made up to and on the date.",
+            );
+        } else {
+            table.add_metric(count, "grit_commits_total", "Commits made on the date.");
+        }
+
+        if rolling {
+            table.add_metric(
+                count + 1,
+                "grit_commits_rolling_average",
+                "Moving average of the commits per day, ending on the date.",
+            );
+        }
+
+        table
+    }
+
+    fn create_calendar_image(&self, output: Vec<ByDateOutput>) -> Result<()> {
+        let file = self
+            .args
+            .file
+            .clone()
+            .unwrap_or_else(|| String::from("commits.svg"));
+
+        if identity::group_by() == GroupBy::Team {
+            return Err(anyhow!(
+                "the calendar chart has no series per team, use --chart=line"
+            ));
+        }
+
+        if self.args.split_merges {
+            return Err(anyhow!(
+                "the calendar chart has no series per kind of commit, use --chart=line"
+            ));
+        }
+
+        let data: Vec<(NaiveDate, i32)> = output.iter().map(|o| (o.date, o.count)).collect();
 
-        wtr.flush().expect("Cannot flush writer");
+        let mut w = File::create(&file)?;
+        writeln!(
+            w,
+            "{}",
+            chart::calendar_svg(&self.args.chart_options.title("By Date"), &data)
+        )?;
+
+        if self.args.html {
+            grit_utils::create_html(&file)?;
+        }
 
         Ok(())
     }
@@ -246,20 +555,21 @@ impl ByDate {
             .file
             .clone()
             .unwrap_or_else(|| String::from("commits.svg"));
-        let (width, height) = if output.len() > 60 {
+        // the series of every team share the dates
+        let mut dates: Vec<String> = output
+            .iter()
+            .map(|d| grit_utils::format_date(d.date))
+            .collect();
+        dates.dedup();
+        let (width, height) = self.args.chart_options.size(if dates.len() > 60 {
             (1920, 960)
-        } else if output.len() > 35 {
+        } else if dates.len() > 35 {
             (1280, 960)
         } else {
             (1027, 768)
-        };
+        });
         let (top, right, bottom, left) = (90, 40, 50, 60);
-        let dates = output
-            .iter()
-            .map(|d| grit_utils::format_date(d.date))
-            .collect();
-        let max_count_obj = output.iter().max_by(|x, y| x.count.cmp(&y.count));
-        let max_count = max_count_obj.expect("Cannot access max count object").count as f32 + 5.0;
+        let max_count = output.iter().map(|o| o.value()).fold(0_f32, f32::max) + 5.0;
         let x = ScaleBand::new()
             .set_domain(dates)
             .set_range(vec![0, width - left - right]);
@@ -272,33 +582,72 @@ impl ByDate {
             .set_marker_type(MarkerType::Circle)
             .set_label_position(PointLabelPosition::NW)
             .set_label_visibility(false) // remove this line to enable point labels, once configurable
+            .set_colors(self.args.chart_options.theme.colors())
             .load_data(&output)
             .expect("Failed to create Line View");
-        let _chart = Chart::new()
-            .set_width(width)
-            .set_height(height)
+        chart::configure_chart(&self.args.chart_options, (width, height), "By Date")
             .set_margins(top, right, bottom, left)
-            .add_title(String::from("By Date"))
             .add_view(&line_view)
             .add_axis_bottom(&x)
             .add_axis_left(&y)
-            .add_left_axis_label("Commits")
+            .add_left_axis_label(if self.args.cumulative {
+                "Total Commits"
+            } else {
+                "Commits"
+            })
             .set_bottom_axis_tick_label_rotation(-45)
             .save(Path::new(&file))
             .expect("Failed to create Chart");
         if self.args.html {
-            grit_utils::create_html(&file).expect("Failed to make HTML file.");
+            grit_utils::create_html(&file)?;
         }
         Ok(())
     }
 }
 
+/// replaces each date's count with the commits made up to and on it
+fn running_totals(output: Vec<ByDateOutput>) -> Vec<ByDateOutput> {
+    let mut total = 0;
+
+    output
+        .into_iter()
+        .map(|o| {
+            total += o.count;
+            ByDateOutput::new(o.date, total)
+        })
+        .collect()
+}
+
+/// sets each date's average to the mean count of the `days` rows ending on it, fewer
+/// at the start of the series
+fn moving_averages(output: Vec<ByDateOutput>, days: usize) -> Vec<ByDateOutput> {
+    let days = days.max(1);
+    let counts: Vec<i32> = output.iter().map(|o| o.count).collect();
+
+    output
+        .into_iter()
+        .enumerate()
+        .map(|(i, mut o)| {
+            let window = &counts[(i + 1).saturating_sub(days)..=i];
+            o.average = Some(window.iter().sum::<i32>() as f32 / window.len() as f32);
+            o
+        })
+        .collect()
+}
+
 impl Processable<()> for ByDate {
     fn process(&self) -> Result<()> {
+        if grit_utils::range_is_empty(&self.args.path, self.args.start_date, self.args.end_date)? {
+            return Ok(());
+        }
+
         let output = self.process_date()?;
 
         if self.args.image {
-            self.create_output_image(output)?;
+            match self.args.chart {
+                ChartType::Line => self.create_output_image(output)?,
+                ChartType::Calendar => self.create_calendar_image(output)?,
+            }
         } else {
             self.display_text_output(output)?;
         }
@@ -309,36 +658,51 @@ impl Processable<()> for ByDate {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::NaiveDate;
+    use chrono::{Local, NaiveDateTime, TimeZone};
     use log::LevelFilter;
     use std::time::Instant;
     use tempfile::TempDir;
 
     const LOG_LEVEL: LevelFilter = LevelFilter::Info;
 
+    fn counts(output: Vec<ByDateOutput>) -> Vec<(String, i32)> {
+        output
+            .iter()
+            .map(|o| (grit_utils::format_date(o.date), o.count))
+            .collect()
+    }
+
+    fn expected(counts: &[(&str, i32)]) -> Vec<(String, i32)> {
+        counts.iter().map(|(d, c)| (d.to_string(), *c)).collect()
+    }
+
     #[test]
     fn test_by_date_no_end() {
         crate::grit_test::set_test_logging(LOG_LEVEL);
 
-        let td: TempDir = crate::grit_test::init_repo();
+        let td: TempDir = crate::grit_test::fixture_repo();
         let path = td.path().to_str().unwrap();
 
-        let args = ByDateArgs::new(
-            String::from(path),
-            None,
-            None,
-            None,
-            false,
-            false,
-            false,
-            false,
-            None,
-        );
+        let args = ByDateArgs::builder(String::from(path))
+            .file(Some(String::from("target/test_by_date.csv")))
+            .build()
+            .unwrap();
 
         let bd = ByDate::new(args);
 
         let start = Instant::now();
 
+        assert_eq!(
+            counts(bd.process_date().unwrap()),
+            expected(&[
+                ("2020-01-01", 1),
+                ("2020-01-02", 2),
+                ("2020-01-03", 0),
+                ("2020-01-04", 0),
+                ("2020-01-05", 1)
+            ])
+        );
+
         let result = match bd.process() {
             Ok(()) => true,
             Err(e) => {
@@ -350,131 +714,285 @@ mod tests {
         println!("completed test_by_date_no_ends in {:?}", start.elapsed());
 
         assert!(result, "test_by_date_no_ends resut {}", result);
+
+        let csv = std::fs::read_to_string("target/test_by_date.csv").unwrap();
+
+        assert_eq!(
+            csv,
+            "date,count\n2020-01-01,1\n2020-01-02,2\n2020-01-03,0\n2020-01-04,0\n2020-01-05,1\nTotal,4\n"
+        );
+    }
+
+    #[test]
+    fn test_by_date_cumulative() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let args = ByDateArgs::builder(String::from(path))
+            .file(Some(String::from("target/test_by_date_cumulative.csv")))
+            .cumulative(true)
+            .build()
+            .unwrap();
+
+        let bd = ByDate::new(args);
+
+        assert_eq!(
+            counts(bd.process_date().unwrap()),
+            expected(&[
+                ("2020-01-01", 1),
+                ("2020-01-02", 3),
+                ("2020-01-03", 3),
+                ("2020-01-04", 3),
+                ("2020-01-05", 4)
+            ])
+        );
+
+        bd.process().unwrap();
+
+        let csv = std::fs::read_to_string("target/test_by_date_cumulative.csv").unwrap();
+
+        assert_eq!(
+            csv,
+            "date,count\n2020-01-01,1\n2020-01-02,3\n2020-01-03,3\n2020-01-04,3\n2020-01-05,4\n"
+        );
+    }
+
+    #[test]
+    fn test_by_date_rolling() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let args = ByDateArgs::builder(String::from(path))
+            .file(Some(String::from("target/test_by_date_rolling.csv")))
+            .rolling(Some(2))
+            .build()
+            .unwrap();
+
+        ByDate::new(args).process().unwrap();
+
+        let csv = std::fs::read_to_string("target/test_by_date_rolling.csv").unwrap();
+
+        assert_eq!(
+            csv,
+            "date,count,average\n2020-01-01,1,1.00\n2020-01-02,2,1.50\n2020-01-03,0,1.00\n2020-01-04,0,0.00\n2020-01-05,1,0.50\nTotal,4,\n"
+        );
+    }
+
+    #[test]
+    fn test_by_date_split_merges() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        // Carol merges the commit before HEAD back in on 2020-01-06
+        let repo = git2::Repository::open(path).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let carol = git2::Signature::new(
+            "Carol",
+            "carol@example.com",
+            &git2::Time::new(1_578_312_000, 0),
+        )
+        .unwrap();
+        repo.commit(
+            Some("HEAD"),
+            &carol,
+            &carol,
+            "merge",
+            &head.tree().unwrap(),
+            &[&head, &head.parent(0).unwrap()],
+        )
+        .unwrap();
+
+        let args = ByDateArgs::builder(String::from(path))
+            .file(Some(String::from("target/test_by_date_split_merges.csv")))
+            .split_merges(true)
+            .build()
+            .unwrap();
+
+        ByDate::new(args).process().unwrap();
+
+        let csv = std::fs::read_to_string("target/test_by_date_split_merges.csv").unwrap();
+
+        assert_eq!(
+            csv,
+            "date,kind,count\n2020-01-01,regular,1\n2020-01-02,regular,2\n2020-01-03,regular,0\n2020-01-04,regular,0\n2020-01-05,regular,1\n2020-01-06,merge,1\nTotal,,5\n"
+        );
     }
 
     #[test]
     fn test_by_date_no_weekends() {
         crate::grit_test::set_test_logging(LOG_LEVEL);
 
-        let td: TempDir = crate::grit_test::init_repo();
+        let td: TempDir = crate::grit_test::fixture_repo();
         let path = td.path().to_str().unwrap();
 
         let start = Instant::now();
 
-        let args = ByDateArgs::new(
-            String::from(path),
-            None,
-            None,
-            None,
-            false,
-            true,
-            true,
-            false,
-            None,
-        );
+        let args = ByDateArgs::builder(String::from(path))
+            .ignore_weekends(true)
+            .ignore_gap_fill(true)
+            .build()
+            .unwrap();
 
         let bd = ByDate::new(args);
 
-        let result = match bd.process() {
-            Ok(()) => true,
-            Err(_e) => false,
-        };
+        assert_eq!(
+            counts(bd.process_date().unwrap()),
+            expected(&[("2020-01-01", 1), ("2020-01-02", 2)])
+        );
 
         println!(
             "completed test_by_date_no_weekends in {:?}",
             start.elapsed()
         );
-
-        assert!(result, "test_by_date_no_weekends resut {}", result);
     }
 
     #[test]
     fn test_by_date_end_date_only() {
         crate::grit_test::set_test_logging(LOG_LEVEL);
 
-        let td: TempDir = crate::grit_test::init_repo();
+        let td: TempDir = crate::grit_test::fixture_repo();
         let path = td.path().to_str().unwrap();
 
-        let ed = parse_date("2020-03-26");
-        let args = ByDateArgs::new(
-            String::from(path),
-            None,
-            Some(ed),
-            None,
-            false,
-            false,
-            false,
-            false,
-            None,
-        );
+        let ed = parse_date("2020-01-02");
+        let args = ByDateArgs::builder(String::from(path))
+            .end_date(Some(ed))
+            .build()
+            .unwrap();
 
         let bd = ByDate::new(args);
 
         let start = Instant::now();
 
-        let result = match bd.process() {
-            Ok(()) => true,
-            Err(_e) => false,
-        };
+        assert_eq!(
+            counts(bd.process_date().unwrap()),
+            expected(&[("2020-01-01", 1), ("2020-01-02", 2)])
+        );
 
         println!(
             "completed test_by_date_end_date_only in {:?}",
             start.elapsed()
         );
-
-        assert!(result, "test_by_date_end_date_only resut {}", result);
     }
 
     #[test]
     fn test_restrict_author() {
         crate::grit_test::set_test_logging(LOG_LEVEL);
-        let td: TempDir = crate::grit_test::init_repo();
+        let td: TempDir = crate::grit_test::fixture_repo();
         let path = td.path().to_str().unwrap();
 
         let start = Instant::now();
 
-        let args = ByDateArgs::new(
-            String::from(path),
-            None,
-            None,
-            None,
-            false,
-            false,
-            false,
-            false,
-            Some(String::from("todd-bush-ln")),
-        );
+        let args = ByDateArgs::builder(String::from(path))
+            .restrict_authors(Some(String::from("Bob")))
+            .build()
+            .unwrap();
 
         let bd = ByDate::new(args);
 
-        let result = match bd.process() {
-            Ok(()) => true,
-            Err(_e) => false,
-        };
+        assert_eq!(
+            counts(bd.process_date().unwrap()),
+            expected(&[("2020-01-01", 1), ("2020-01-02", 1)])
+        );
 
         println!("completed test_restrict_author in {:?}", start.elapsed());
+    }
 
-        assert!(result, "test_restrict_author resut {}", result);
+    #[test]
+    fn test_by_date_path_filter() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let by_date_args = |path_filter: Option<String>| {
+            ByDateArgs::builder(String::from(path))
+                .ignore_gap_fill(true)
+                .path_filter(path_filter)
+                .build()
+                .unwrap()
+        };
+
+        let count = |output: Vec<ByDateOutput>| output.iter().map(|o| o.count).sum::<i32>();
+
+        let all = count(ByDate::new(by_date_args(None)).process_date().unwrap());
+        let readme = count(
+            ByDate::new(by_date_args(Some(String::from("README.md"))))
+                .process_date()
+                .unwrap(),
+        );
+        let nothing = count(
+            ByDate::new(by_date_args(Some(String::from("no/such/dir/*"))))
+                .process_date()
+                .unwrap(),
+        );
+
+        assert_eq!(all, 4);
+        assert_eq!(readme, 2);
+        assert_eq!(nothing, 0);
     }
 
     #[test]
-    fn test_by_date_image() {
+    fn test_by_date_no_commits_in_range() {
         crate::grit_test::set_test_logging(LOG_LEVEL);
 
-        let td: TempDir = crate::grit_test::init_repo();
+        let td: TempDir = crate::grit_test::fixture_repo();
         let path = td.path().to_str().unwrap();
+        let file = td.path().join("bydate.csv");
+
+        // the fixture's commits are made from 2020-01-01 to 2020-01-05
+        let args = ByDateArgs::builder(String::from(path))
+            .start_date(Some(NaiveDate::from_ymd_opt(2019, 6, 1).unwrap()))
+            .end_date(Some(NaiveDate::from_ymd_opt(2019, 12, 31).unwrap()))
+            .file(Some(file.to_str().unwrap().to_string()))
+            .build()
+            .unwrap();
+
+        ByDate::new(args).process().unwrap();
+        assert!(!file.exists());
+    }
+
+    #[test]
+    fn test_by_date_file_needs_svg_only_for_image() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let build = |file: &str, format: OutputFormat, image: bool| {
+            ByDateArgs::builder(String::from("."))
+                .file(Some(String::from(file)))
+                .format(format)
+                .image(image)
+                .build()
+        };
+
+        assert!(build("out.csv", OutputFormat::Csv, false).is_ok());
+        assert!(build("out.json", OutputFormat::Json, false).is_ok());
+        assert!(build("out.svg", OutputFormat::Csv, true).is_ok());
 
-        let args = ByDateArgs::new(
-            String::from(path),
-            None,
-            None,
-            Some(String::from("target/test_image.svg")),
-            true,
-            true,
-            true,
-            false,
-            None,
+        let e = build("out.csv", OutputFormat::Csv, true).err().unwrap();
+        assert_eq!(
+            e.to_string(),
+            "the image file format must be svg, not out.csv"
         );
+    }
+
+    #[test]
+    fn test_by_date_image() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let args = ByDateArgs::builder(String::from(path))
+            .file(Some(String::from("target/test_image.svg")))
+            .image(true)
+            .ignore_weekends(true)
+            .ignore_gap_fill(true)
+            .build()
+            .unwrap();
 
         let start = Instant::now();
 
@@ -493,21 +1011,56 @@ mod tests {
         assert!(result, "test_by_date_image resut {}", result);
     }
 
+    #[test]
+    fn test_by_date_calendar_image() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let args = ByDateArgs::builder(String::from(path))
+            .file(Some(String::from("target/test_calendar.svg")))
+            .image(true)
+            .ignore_gap_fill(true)
+            .chart(ChartType::Calendar)
+            .build()
+            .unwrap();
+
+        let bd = ByDate::new(args);
+
+        let result = match bd.process() {
+            Ok(()) => true,
+            Err(e) => {
+                error!("test_by_date_calendar_image ended in error {:?}", e);
+                false
+            }
+        };
+
+        assert!(result, "See error above");
+    }
+
+    #[test]
+    fn test_fill_date_gaps_empty() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let args = ByDateArgs::builder(String::from("path")).build().unwrap();
+
+        let bd = ByDate::new(args);
+
+        assert!(bd.fill_date_gaps(Vec::new()).is_empty());
+    }
+
     #[test]
     fn test_is_weekend() {
         crate::grit_test::set_test_logging(LOG_LEVEL);
 
-        let args = ByDateArgs::new(
-            String::from("path"),
-            None,
-            None,
-            Some(String::from("target/test_image.svg")),
-            true,
-            true,
-            true,
-            false,
-            None,
-        );
+        let args = ByDateArgs::builder(String::from("path"))
+            .file(Some(String::from("target/test_image.svg")))
+            .image(true)
+            .ignore_weekends(true)
+            .ignore_gap_fill(true)
+            .build()
+            .unwrap();
 
         let bd = ByDate::new(args);
 
@@ -519,7 +1072,10 @@ mod tests {
 
         let duration = start.elapsed();
 
-        assert!(!bd.is_weekend(weekday.timestamp()), "test_is_weekday");
+        assert!(
+            !bd.is_weekend(&git2::Time::new(weekday.timestamp(), 0)),
+            "test_is_weekday"
+        );
 
         println!("test_is_weekend done in {:?}", duration);
 
@@ -527,24 +1083,23 @@ mod tests {
             NaiveDateTime::parse_from_str("2020-04-19 0:0", "%Y-%m-%d %H:%M").unwrap();
         let weekend = Local.from_local_datetime(&utc_weekend).unwrap();
 
-        assert!(bd.is_weekend(weekend.timestamp()), "test_is_weekday");
+        assert!(
+            bd.is_weekend(&git2::Time::new(weekend.timestamp(), 0)),
+            "test_is_weekday"
+        );
     }
 
     #[test]
     fn test_fill_date_gaps() {
         crate::grit_test::set_test_logging(LOG_LEVEL);
 
-        let args = ByDateArgs::new(
-            String::from("path"),
-            None,
-            None,
-            Some(String::from("target/test_image.svg")),
-            true,
-            true,
-            true,
-            false,
-            None,
-        );
+        let args = ByDateArgs::builder(String::from("path"))
+            .file(Some(String::from("target/test_image.svg")))
+            .image(true)
+            .ignore_weekends(true)
+            .ignore_gap_fill(true)
+            .build()
+            .unwrap();
 
         let bd = ByDate::new(args);
 
@@ -564,10 +1119,8 @@ mod tests {
         assert_eq!(test_out[2].count, 0);
     }
 
-    fn parse_date(date_str: &str) -> Date<Local> {
+    fn parse_date(date_str: &str) -> NaiveDate {
         crate::grit_test::set_test_logging(LOG_LEVEL);
-        let utc_dt = NaiveDate::parse_from_str(date_str, "%Y-%m-%d").unwrap();
-
-        Local.from_local_date(&utc_dt).single().unwrap()
+        NaiveDate::parse_from_str(date_str, "%Y-%m-%d").unwrap()
     }
 }
@@ -1,19 +1,22 @@
 use super::Processable;
+use crate::chart;
+use crate::chart::ChartOptions;
+use crate::identity;
+use crate::ignore_revs;
+use crate::output;
+use crate::progress::Progress;
+use crate::table::Table;
 use crate::utils::grit_utils;
-use anyhow::Result;
-use charts::{
-    AxisPosition, BarDatum, BarLabelPosition, Chart, ScaleBand, ScaleLinear, VerticalBarView,
-};
-use chrono::offset::Local;
-use chrono::Date;
-use csv::Writer;
-use git2::Repository;
+use crate::utils::grit_utils::{OutputFormat, RelativeTo, SkippedFile};
+use anyhow::{anyhow, Result};
+use charts::{AxisPosition, BarDatum, BarLabelPosition, ScaleBand, ScaleLinear, VerticalBarView};
+use chrono::NaiveDate;
+use git2::{Delta, DiffOptions, Oid, Repository, Tree};
+use std::cmp::{Ordering, Reverse};
 use std::collections::hash_map::Entry::{Occupied, Vacant};
 use std::collections::HashMap;
-use std::fs::File;
-use std::io;
-use std::io::Write;
 use std::path::Path;
+use std::str::FromStr;
 
 pub struct ByFileArgs {
     path: String,
@@ -22,24 +25,142 @@ pub struct ByFileArgs {
     image: bool,
     html: bool,
     restrict_authors: Option<String>,
+    relative_to: RelativeTo,
+    at: Option<String>,
+    dir: Option<String>,
+    rank: bool,
+    chart_options: ChartOptions,
+    format: OutputFormat,
+    strict: bool,
+    history: bool,
+    lines: Option<LineRange>,
 }
 
 impl ByFileArgs {
-    pub fn new(
-        path: String,
-        full_path_filename: String,
-        output_file: Option<String>,
-        image: bool,
-        html: bool,
-        restrict_authors: Option<String>,
-    ) -> ByFileArgs {
-        ByFileArgs {
-            path: path,
-            full_path_filename: full_path_filename,
-            output_file: output_file,
-            image: image,
-            html: html,
-            restrict_authors: restrict_authors,
+    pub fn builder(path: String, full_path_filename: String) -> ByFileArgsBuilder {
+        ByFileArgsBuilder {
+            args: ByFileArgs {
+                path,
+                full_path_filename,
+                output_file: None,
+                image: false,
+                html: false,
+                restrict_authors: None,
+                relative_to: RelativeTo::Repo,
+                at: None,
+                dir: None,
+                rank: false,
+                chart_options: ChartOptions::default(),
+                format: OutputFormat::Csv,
+                strict: false,
+                history: false,
+                lines: None,
+            },
+        }
+    }
+}
+
+/// Builds a `ByFileArgs` by name rather than position, validating it on `build`.
+pub struct ByFileArgsBuilder {
+    args: ByFileArgs,
+}
+
+impl ByFileArgsBuilder {
+    pub fn output_file(mut self, output_file: Option<String>) -> ByFileArgsBuilder {
+        self.args.output_file = output_file;
+        self
+    }
+
+    pub fn image(mut self, image: bool) -> ByFileArgsBuilder {
+        self.args.image = image;
+        self
+    }
+
+    pub fn html(mut self, html: bool) -> ByFileArgsBuilder {
+        self.args.html = html;
+        self
+    }
+
+    pub fn restrict_authors(mut self, restrict_authors: Option<String>) -> ByFileArgsBuilder {
+        self.args.restrict_authors = restrict_authors;
+        self
+    }
+
+    pub fn relative_to(mut self, relative_to: RelativeTo) -> ByFileArgsBuilder {
+        self.args.relative_to = relative_to;
+        self
+    }
+
+    pub fn at(mut self, at: Option<String>) -> ByFileArgsBuilder {
+        self.args.at = at;
+        self
+    }
+
+    pub fn dir(mut self, dir: Option<String>) -> ByFileArgsBuilder {
+        self.args.dir = dir;
+        self
+    }
+
+    pub fn rank(mut self, rank: bool) -> ByFileArgsBuilder {
+        self.args.rank = rank;
+        self
+    }
+
+    pub fn chart_options(mut self, chart_options: ChartOptions) -> ByFileArgsBuilder {
+        self.args.chart_options = chart_options;
+        self
+    }
+
+    pub fn format(mut self, format: OutputFormat) -> ByFileArgsBuilder {
+        self.args.format = format;
+        self
+    }
+
+    pub fn strict(mut self, strict: bool) -> ByFileArgsBuilder {
+        self.args.strict = strict;
+        self
+    }
+
+    pub fn history(mut self, history: bool) -> ByFileArgsBuilder {
+        self.args.history = history;
+        self
+    }
+
+    pub fn lines(mut self, lines: Option<LineRange>) -> ByFileArgsBuilder {
+        self.args.lines = lines;
+        self
+    }
+
+    pub fn build(self) -> Result<ByFileArgs> {
+        grit_utils::check_output_file(self.args.output_file.as_deref(), self.args.format)?;
+
+        Ok(self.args)
+    }
+}
+
+/// The 1-based, inclusive range of lines to blame, such as `100-250`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LineRange {
+    first: usize,
+    last: usize,
+}
+
+impl FromStr for LineRange {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let range = s.split_once('-').and_then(|(first, last)| {
+            Some(LineRange {
+                first: first.trim().parse().ok()?,
+                last: last.trim().parse().ok()?,
+            })
+        });
+
+        match range {
+            Some(r) if r.first >= 1 && r.first <= r.last => Ok(r),
+            _ => Err(anyhow!(
+                "lines must be a range of line numbers such as '100-250', the first at least 1 and not after the last"
+            )),
         }
     }
 }
@@ -47,17 +168,13 @@ impl ByFileArgs {
 #[derive(Eq, Hash, PartialEq, Clone)]
 struct ByFileOutput {
     name: String,
-    day: Date<Local>,
+    day: NaiveDate,
     loc: i32,
 }
 
 impl ByFileOutput {
-    fn new(name: String, day: Date<Local>) -> ByFileOutput {
-        ByFileOutput {
-            name: name,
-            day: day,
-            loc: 0,
-        }
+    fn new(name: String, day: NaiveDate) -> ByFileOutput {
+        ByFileOutput { name, day, loc: 0 }
     }
 }
 
@@ -74,37 +191,412 @@ impl BarDatum for ByFileOutput {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
+struct ByFileRank {
+    file: String,
+    top_author: String,
+    top_author_loc: i32,
+    loc: i32,
+    authors: usize,
+    concentration: f64,
+}
+
+impl ByFileRank {
+    fn from_outputs(file: String, outputs: &[ByFileOutput]) -> ByFileRank {
+        let mut author_loc: HashMap<String, i32> = HashMap::new();
+
+        for o in outputs.iter() {
+            *author_loc.entry(o.name.clone()).or_insert(0) += o.loc;
+        }
+
+        let loc: i32 = author_loc.values().sum();
+
+        let (top_author, top_author_loc) = author_loc
+            .iter()
+            .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+            .map(|(a, l)| (a.clone(), *l))
+            .unwrap_or_default();
+
+        let concentration = if loc > 0 {
+            top_author_loc as f64 / loc as f64
+        } else {
+            0.0
+        };
+
+        ByFileRank {
+            file,
+            top_author,
+            top_author_loc,
+            loc,
+            authors: author_loc.len(),
+            concentration,
+        }
+    }
+}
+
+/// A commit changing the file, for `--history`.
+#[derive(Clone, Debug, PartialEq)]
+struct ByFileCommit {
+    commit_id: Oid,
+    author: String,
+    day: NaiveDate,
+    insertions: usize,
+    deletions: usize,
+    subject: String,
+    /// the file's path as of the commit, as renames are followed
+    path: String,
+}
+
 pub struct ByFile {
     args: ByFileArgs,
 }
 
 impl ByFile {
     pub fn new(args: ByFileArgs) -> ByFile {
-        ByFile { args: args }
+        ByFile { args }
+    }
+
+    fn process_blame(&self, repo: &Repository, file_name: &str) -> Result<Vec<ByFileOutput>> {
+        let path = grit_utils::git_path(file_name);
+
+        let mut auth_to_loc: HashMap<String, ByFileOutput> = HashMap::new();
+
+        let restrict_authors: Option<Vec<String>> =
+            grit_utils::convert_string_list_to_vec(self.args.restrict_authors.clone());
+
+        let at = match &self.args.at {
+            Some(rev) => Some(Oid::from_bytes(&grit_utils::resolve_rev(
+                &self.args.path,
+                rev,
+            )?)?),
+            None => None,
+        };
+
+        let blame = match self.args.lines {
+            Some(r) => ignore_revs::blame_file_lines(repo, &path, at, (r.first, r.last))?,
+            None => ignore_revs::blame_file(repo, &path, None, at)?,
+        };
+
+        for b in blame.iter() {
+            let sig = &b.signature;
+            let signame = String::from_utf8_lossy(sig.name_bytes()).to_string();
+            let commit = repo.find_commit(b.commit_id)?;
+            let commit_date = grit_utils::convert_git_time(&commit.time());
+
+            if let Some(ref v) = restrict_authors {
+                if v.iter().any(|a| a == &signame) {
+                    break;
+                }
+            }
+
+            if identity::is_excluded(&signame, &String::from_utf8_lossy(sig.email_bytes())) {
+                continue;
+            }
+
+            let commit_date_str = grit_utils::format_date(commit_date);
+
+            let author =
+                identity::display_author(&signame, &String::from_utf8_lossy(sig.email_bytes()));
+
+            let key = &[&author, "-", &commit_date_str].join("");
+
+            let v = match auth_to_loc.entry(key.to_string()) {
+                Vacant(entry) => entry.insert(ByFileOutput::new(author, commit_date)),
+                Occupied(entry) => entry.into_mut(),
+            };
+
+            v.loc += b.lines as i32;
+        }
+
+        Ok(auth_to_loc.values().cloned().collect())
     }
 
-    fn display_csv(&self, data: Vec<ByFileOutput>) -> Result<()> {
-        let w = match &self.args.output_file {
-            Some(f) => {
-                let file = File::create(f)?;
-                Box::new(file) as Box<dyn Write>
+    /// every non merge commit changing `file_name`, newest first, following the file back
+    /// through renames like `git log --follow`
+    fn process_history(&self, repo: &Repository, file_name: &str) -> Result<Vec<ByFileCommit>> {
+        let restrict_authors: Option<Vec<String>> =
+            grit_utils::convert_string_list_to_vec(self.args.restrict_authors.clone());
+
+        let mut revwalk = repo.revwalk()?;
+        revwalk.set_sorting(git2::Sort::TIME | git2::Sort::TOPOLOGICAL)?;
+
+        match &self.args.at {
+            Some(rev) => revwalk.push(Oid::from_bytes(&grit_utils::resolve_rev(
+                &self.args.path,
+                rev,
+            )?)?)?,
+            None => revwalk.push_head()?,
+        }
+
+        let mut path = file_name.to_string();
+        let mut history: Vec<ByFileCommit> = Vec::new();
+
+        for oid in revwalk {
+            let commit = repo.find_commit(oid?)?;
+
+            if commit.parent_count() > 1 {
+                continue;
+            }
+
+            let tree = commit.tree()?;
+            let parent_tree = match commit.parent(0) {
+                Ok(p) => Some(p.tree()?),
+                Err(_) => None,
+            };
+
+            let new_id = tree
+                .get_path(&grit_utils::git_path(&path))
+                .ok()
+                .map(|e| e.id());
+            if new_id.is_none() {
+                continue;
+            }
+
+            let old_id = parent_tree
+                .as_ref()
+                .and_then(|t| t.get_path(&grit_utils::git_path(&path)).ok())
+                .map(|e| e.id());
+            if old_id == new_id {
+                continue;
+            }
+
+            let old_path = match (&old_id, &parent_tree) {
+                (None, Some(pt)) => ByFile::renamed_from(repo, pt, &tree, &path)?,
+                _ => None,
+            }
+            .unwrap_or_else(|| path.clone());
+
+            let author = commit.author();
+            let name = String::from_utf8_lossy(author.name_bytes()).to_string();
+            let email = String::from_utf8_lossy(author.email_bytes()).to_string();
+
+            let skip = restrict_authors
+                .as_ref()
+                .map(|v| v.iter().any(|a| a == &name))
+                .unwrap_or(false)
+                || identity::is_excluded(&name, &email);
+
+            if !skip {
+                let (insertions, deletions) =
+                    ByFile::line_stats(repo, parent_tree.as_ref(), &tree, &old_path, &path)?;
+
+                history.push(ByFileCommit {
+                    commit_id: commit.id(),
+                    author: identity::display_author(&name, &email),
+                    day: grit_utils::convert_git_time(&commit.time()),
+                    insertions,
+                    deletions,
+                    subject: commit.summary().unwrap_or_default().to_string(),
+                    path: path.clone(),
+                });
             }
-            None => Box::new(io::stdout()) as Box<dyn Write>,
+
+            path = old_path;
+        }
+
+        Ok(history)
+    }
+
+    /// the path `path` was renamed from going from `old` to `new`, if it was
+    fn renamed_from(
+        repo: &Repository,
+        old: &Tree,
+        new: &Tree,
+        path: &str,
+    ) -> Result<Option<String>> {
+        let mut diff = repo.diff_tree_to_tree(Some(old), Some(new), None)?;
+        diff.find_similar(None)?;
+
+        let renamed = diff
+            .deltas()
+            .filter(|d| d.status() == Delta::Renamed)
+            .find(|d| d.new_file().path() == Some(grit_utils::git_path(path).as_path()))
+            .and_then(|d| {
+                d.old_file()
+                    .path_bytes()
+                    .map(grit_utils::file_name_from_bytes)
+            });
+
+        Ok(renamed)
+    }
+
+    /// lines added and removed going from `old_path` in `old` to `path` in `new`
+    fn line_stats(
+        repo: &Repository,
+        old: Option<&Tree>,
+        new: &Tree,
+        old_path: &str,
+        path: &str,
+    ) -> Result<(usize, usize)> {
+        let mut opts = DiffOptions::new();
+        opts.pathspec(grit_utils::git_path(path))
+            .pathspec(grit_utils::git_path(old_path));
+
+        let mut diff = repo.diff_tree_to_tree(old, Some(new), Some(&mut opts))?;
+        if old_path != path {
+            diff.find_similar(None)?;
+        }
+
+        let stats = diff.stats()?;
+
+        Ok((stats.insertions(), stats.deletions()))
+    }
+
+    fn display_history(&self, history: &[ByFileCommit]) -> Result<()> {
+        let mut w = grit_utils::output_writer(self.args.output_file.as_deref())?;
+
+        let mut table = Table::new();
+
+        table.set_titles(table_row![
+            "commit",
+            "author",
+            "date",
+            "insertions",
+            "deletions",
+            "subject",
+            "path"
+        ]);
+
+        history.iter().for_each(|c| {
+            table.add_row(table_row![
+                c.commit_id.to_string(),
+                c.author,
+                grit_utils::format_date(c.day),
+                c.insertions,
+                c.deletions,
+                c.subject,
+                c.path
+            ]);
+        });
+
+        table.add_label(0, "commit");
+        table.add_label(1, "author");
+        table.add_metric(
+            3,
+            "grit_file_commit_insertions",
+            "Lines the commit added to the file.",
+        );
+        table.add_metric(
+            4,
+            "grit_file_commit_deletions",
+            "Lines the commit removed from the file.",
+        );
+
+        table.write(&mut w, self.args.format)?;
+
+        Ok(())
+    }
+
+    /// runs the blame for every file under `dir` and summarizes each file's top contributor
+    fn process_dir(&self, repo: &Repository, dir: &str) -> Result<Vec<ByFileRank>> {
+        let dir = grit_utils::repo_file_path(&self.args.path, dir, self.args.relative_to)?;
+        let dir = dir.trim_end_matches('/');
+
+        let pattern = if dir.is_empty() || dir == "." {
+            String::from("*")
+        } else {
+            format!("{}/*", dir)
+        };
+
+        let file_names = match &self.args.at {
+            Some(rev) => grit_utils::generate_file_list_at(
+                &self.args.path,
+                &grit_utils::resolve_rev(&self.args.path, rev)?,
+                Some(pattern),
+                None,
+            )?,
+            None => grit_utils::generate_file_list(&self.args.path, Some(pattern), None)?,
         };
 
-        let mut writer = Writer::from_writer(w);
+        let mut ranks: Vec<ByFileRank> = Vec::new();
+        let mut skipped: Vec<SkippedFile> = Vec::new();
+
+        let pb = Progress::new("blamed", file_names.len());
+
+        for file_name in file_names.iter() {
+            pb.inc();
+
+            let outputs = match self.process_blame(repo, file_name) {
+                Ok(o) => o,
+                Err(e) => {
+                    error!("Error in processing file {}: {}", file_name, e);
+                    skipped.push(SkippedFile::new(file_name, e.to_string()));
+                    continue;
+                }
+            };
+
+            ranks.push(ByFileRank::from_outputs(file_name.clone(), &outputs));
+        }
+
+        pb.finish_and_clear();
+
+        grit_utils::report_skipped(&skipped, self.args.strict)?;
+
+        if self.args.rank {
+            ranks.sort_by(|a, b| {
+                b.concentration
+                    .partial_cmp(&a.concentration)
+                    .unwrap_or(Ordering::Equal)
+                    .then(b.loc.cmp(&a.loc))
+            });
+        } else {
+            ranks.sort_by(|a, b| a.file.cmp(&b.file));
+        }
+
+        let file_names: Vec<String> = ranks.iter().map(|r| r.file.clone()).collect();
+        let display_names =
+            grit_utils::relative_file_paths(&self.args.path, &file_names, self.args.relative_to)?;
+
+        ranks
+            .iter_mut()
+            .zip(display_names)
+            .for_each(|(r, f)| r.file = f);
+
+        Ok(ranks)
+    }
+
+    fn display_rank(&self, data: Vec<ByFileRank>) -> Result<()> {
+        let mut w = grit_utils::output_writer(self.args.output_file.as_deref())?;
+
+        let mut table = Table::new();
+
+        table.set_titles(table_row![
+            "file",
+            "top author",
+            "top author loc",
+            "loc",
+            "authors",
+            "concentration (%)"
+        ]);
+
+        data.iter().for_each(|d| {
+            table.add_row(table_row![
+                d.file,
+                d.top_author,
+                d.top_author_loc,
+                d.loc,
+                d.authors,
+                output::percent(d.concentration * 100.0)
+            ]);
+        });
+
+        table.write(&mut w, self.args.format)?;
 
-        writer
-            .write_record(&["author", "date", "loc"])
-            .expect("Could not write csv header");
+        Ok(())
+    }
+
+    fn display_text_output(&self, data: Vec<ByFileOutput>) -> Result<()> {
+        let mut w = grit_utils::output_writer(self.args.output_file.as_deref())?;
+
+        let mut table = Table::new();
+
+        table.set_titles(table_row!["author", "date", "loc"]);
 
         data.iter().for_each(|d| {
-            writer
-                .serialize((d.name.clone(), grit_utils::format_date(d.day), d.loc))
-                .expect("Could not write csv row");
+            table.add_row(table_row![d.name, grit_utils::format_date(d.day), d.loc]);
         });
 
-        writer.flush().expect("Could not flush csv writer");
+        table.write(&mut w, self.args.format)?;
 
         Ok(())
     }
@@ -115,13 +607,15 @@ impl ByFile {
             None => panic!("File name is manditory for images"),
         };
 
-        let (width, height) = if data.len() > 60 {
+        grit_utils::check_image_file(Some(f))?;
+
+        let (width, height) = self.args.chart_options.size(if data.len() > 60 {
             (1920, 960)
         } else if data.len() > 35 {
             (1280, 960)
         } else {
             (1028, 768)
-        };
+        });
 
         let (top, right, bottom, left) = (90, 40, 50, 60);
 
@@ -153,24 +647,26 @@ impl ByFile {
             .set_y_scale(&y_sb)
             .set_keys(authors)
             .set_label_position(BarLabelPosition::Center)
+            .set_colors(self.args.chart_options.theme.colors())
             .load_data(&data)
             .expect("Could not create view");
 
-        Chart::new()
-            .set_width(width)
-            .set_height(height)
-            .set_margins(top, right, bottom, left)
-            .add_title(self.args.full_path_filename.clone())
-            .add_view(&view)
-            .add_axis_bottom(&x_sb)
-            .add_axis_left(&y_sb)
-            .add_legend_at(AxisPosition::Top)
-            .set_bottom_axis_tick_label_rotation(-45)
-            .save(Path::new(&f))
-            .expect("Failed to create chart");
+        chart::configure_chart(
+            &self.args.chart_options,
+            (width, height),
+            &self.args.full_path_filename,
+        )
+        .set_margins(top, right, bottom, left)
+        .add_view(&view)
+        .add_axis_bottom(&x_sb)
+        .add_axis_left(&y_sb)
+        .add_legend_at(AxisPosition::Top)
+        .set_bottom_axis_tick_label_rotation(-45)
+        .save(Path::new(&f))
+        .expect("Failed to create chart");
 
         if self.args.html {
-            grit_utils::create_html(&f).expect("failed to creat HTML page");
+            grit_utils::create_html(f)?;
         }
 
         Ok(())
@@ -179,49 +675,39 @@ impl ByFile {
 
 impl Processable<()> for ByFile {
     fn process(&self) -> Result<()> {
-        let repo = Repository::open(&self.args.path)?;
+        let repo = grit_utils::open_repo(&self.args.path)?;
 
-        let path = Path::new(&self.args.full_path_filename);
-
-        let mut auth_to_loc: HashMap<String, ByFileOutput> = HashMap::new();
-
-        let restrict_authors: Option<Vec<String>> =
-            grit_utils::convert_string_list_to_vec(self.args.restrict_authors.clone());
-
-        let blame = repo.blame_file(path, None)?;
-
-        for hunk in blame.iter() {
-            let sig = hunk.final_signature();
-            let signame = String::from_utf8_lossy(sig.name_bytes()).to_string();
-            let commit = repo.find_commit(hunk.final_commit_id())?;
-            let commit_date = grit_utils::convert_git_time(&commit.time());
+        if let Some(dir) = &self.args.dir {
+            let ranks = self.process_dir(&repo, dir)?;
+            return self.display_rank(ranks);
+        }
 
-            if let Some(ref v) = restrict_authors {
-                if v.iter().any(|a| a == &signame) {
-                    break;
-                }
-            }
+        let file_name = grit_utils::repo_file_path(
+            &self.args.path,
+            &self.args.full_path_filename,
+            self.args.relative_to,
+        )?;
 
-            let commit_date_str = grit_utils::format_date(commit_date);
+        if self.args.history {
+            let history = self.process_history(&repo, &file_name)?;
+            return self.display_history(&history);
+        }
 
-            let key = &[&signame, "-", &commit_date_str].join("");
+        let pb = grit_utils::spinner("{spinner} {msg}");
+        pb.set_message(&format!("blaming {}", file_name));
 
-            let v = match auth_to_loc.entry(key.to_string()) {
-                Vacant(entry) => entry.insert(ByFileOutput::new(signame, commit_date)),
-                Occupied(entry) => entry.into_mut(),
-            };
+        let results = self.process_blame(&repo, &file_name);
 
-            v.loc += hunk.lines_in_hunk() as i32;
-        }
+        pb.finish_and_clear();
 
-        let mut results: Vec<ByFileOutput> = auth_to_loc.values().cloned().collect();
+        let mut results = results?;
 
-        results.sort_by(|a, b| b.day.cmp(&a.day));
+        results.sort_by_key(|r| Reverse(r.day));
 
         if self.args.image {
             self.display_image(results)?;
         } else {
-            self.display_csv(results)?;
+            self.display_text_output(results)?;
         }
 
         Ok(())
@@ -237,22 +723,58 @@ mod tests {
 
     const LOG_LEVEL: LevelFilter = LevelFilter::Info;
 
+    #[test]
+    fn test_by_file_args_builder() {
+        let args = ByFileArgs::builder(String::from("."), String::from("src/main.rs"))
+            .at(Some(String::from("HEAD~1")))
+            .rank(true)
+            .build()
+            .unwrap();
+
+        assert_eq!(args.at.as_deref(), Some("HEAD~1"));
+        assert!(args.rank);
+        assert_eq!(args.relative_to, RelativeTo::Repo);
+        assert_eq!(args.format, OutputFormat::Csv);
+
+        let mismatched = ByFileArgs::builder(String::from("."), String::from("src/main.rs"))
+            .output_file(Some(String::from("byfile.csv")))
+            .format(OutputFormat::Json)
+            .build();
+        assert!(mismatched.is_err());
+    }
+
     #[test]
     fn test_by_file() {
         crate::grit_test::set_test_logging(LOG_LEVEL);
 
-        let td: TempDir = crate::grit_test::init_repo();
+        let td: TempDir = crate::grit_test::fixture_repo();
 
-        let args = ByFileArgs::new(
+        let args = ByFileArgs::builder(
             td.path().to_str().unwrap().to_string(),
-            "src/by_date.rs".to_string(),
-            None,
-            false,
-            false,
-            None,
-        );
+            "src/main.rs".to_string(),
+        )
+        .build()
+        .unwrap();
 
         let bf = ByFile::new(args);
+        let repo = Repository::open(td.path()).unwrap();
+
+        let mut lines: Vec<(String, String, i32)> = bf
+            .process_blame(&repo, "src/main.rs")
+            .unwrap()
+            .into_iter()
+            .map(|o| (o.name, grit_utils::format_date(o.day), o.loc))
+            .collect();
+        lines.sort();
+
+        // Alice wrote main and then added run the next day
+        assert_eq!(
+            lines,
+            vec![
+                (String::from("Alice"), String::from("2020-01-01"), 3),
+                (String::from("Alice"), String::from("2020-01-02"), 2),
+            ]
+        );
 
         let s = match bf.process() {
             Ok(()) => true,
@@ -266,19 +788,114 @@ mod tests {
     }
 
     #[test]
-    fn test_by_file_with_image() {
+    fn test_by_file_at() {
         crate::grit_test::set_test_logging(LOG_LEVEL);
 
-        let td: TempDir = crate::grit_test::init_repo();
+        let td: TempDir = crate::grit_test::fixture_repo();
 
-        let args = ByFileArgs::new(
+        let args = ByFileArgs::builder(
             td.path().to_str().unwrap().to_string(),
             "README.md".to_string(),
-            Some(String::from("target/to_file.svg")),
-            true,
-            true,
-            None,
+        )
+        .at(Some(String::from("HEAD~1")))
+        .build()
+        .unwrap();
+
+        let bf = ByFile::new(args);
+        let repo = Repository::open(td.path()).unwrap();
+
+        // before Bob's edit both lines of the README were Alice's
+        let lines: Vec<(String, i32)> = bf
+            .process_blame(&repo, "README.md")
+            .unwrap()
+            .into_iter()
+            .map(|o| (o.name, o.loc))
+            .collect();
+        assert_eq!(lines, vec![(String::from("Alice"), 2)]);
+
+        let s = match bf.process() {
+            Ok(()) => true,
+            Err(e) => {
+                error!("test_by_file_at ended in error {:?}", e);
+                false
+            }
+        };
+
+        assert!(s, "See error above");
+    }
+
+    #[test]
+    fn test_by_file_rank_from_outputs() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let day = grit_utils::convert_git_time(&git2::Time::new(1584100000, 0));
+
+        let mut a1 = ByFileOutput::new(String::from("a"), day);
+        a1.loc = 30;
+        let mut a2 = ByFileOutput::new(String::from("a"), day);
+        a2.loc = 45;
+        let mut b = ByFileOutput::new(String::from("b"), day);
+        b.loc = 25;
+
+        let rank = ByFileRank::from_outputs(String::from("src/a.rs"), &[a1, b, a2]);
+
+        assert_eq!(rank.top_author, "a");
+        assert_eq!(rank.top_author_loc, 75);
+        assert_eq!(rank.loc, 100);
+        assert_eq!(rank.authors, 2);
+        assert!((rank.concentration - 0.75).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_by_file_dir_rank() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+
+        let args = ByFileArgs::builder(td.path().to_str().unwrap().to_string(), String::new())
+            .dir(Some(String::from("src/")))
+            .rank(true)
+            .build()
+            .unwrap();
+
+        let bf = ByFile::new(args);
+        let repo = Repository::open(td.path()).unwrap();
+
+        let ranks = bf.process_dir(&repo, "src/").unwrap();
+
+        let mut files: Vec<(String, String, i32)> = ranks
+            .iter()
+            .map(|r| (r.file.clone(), r.top_author.clone(), r.loc))
+            .collect();
+        files.sort();
+
+        assert_eq!(
+            files,
+            vec![
+                (String::from("src/lib.rs"), String::from("Bob"), 4),
+                (String::from("src/main.rs"), String::from("Alice"), 5),
+            ]
         );
+        assert!(ranks
+            .windows(2)
+            .all(|w| w[0].concentration >= w[1].concentration));
+    }
+
+    #[test]
+    fn test_by_file_with_image() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+
+        let args = ByFileArgs::builder(
+            td.path().to_str().unwrap().to_string(),
+            "README.md".to_string(),
+        )
+        .output_file(Some(String::from("target/to_file.svg")))
+        .image(true)
+        .html(true)
+        .build()
+        .unwrap();
 
         let bf = ByFile::new(args);
 
@@ -292,4 +909,95 @@ mod tests {
 
         assert!(s, "See error above");
     }
+
+    #[test]
+    fn test_by_file_lines() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let repo = Repository::open(td.path()).unwrap();
+
+        let by_file = |lines: &str| {
+            let args = ByFileArgs::builder(
+                td.path().to_str().unwrap().to_string(),
+                String::from("README.md"),
+            )
+            .lines(Some(lines.parse().unwrap()))
+            .build()
+            .unwrap();
+
+            ByFile::new(args)
+                .process_blame(&repo, "README.md")
+                .map(|o| {
+                    o.into_iter()
+                        .map(|o| (o.name, o.loc))
+                        .collect::<Vec<(String, i32)>>()
+                })
+        };
+
+        // the README's first line is Alice's and its second Bob's
+        assert_eq!(by_file("2-2").unwrap(), vec![(String::from("Bob"), 1)]);
+        assert_eq!(by_file("1 - 1").unwrap(), vec![(String::from("Alice"), 1)]);
+        assert_eq!(by_file("2-9").unwrap(), vec![(String::from("Bob"), 1)]);
+        assert!(by_file("5-9").is_err());
+
+        assert!("0-3".parse::<LineRange>().is_err());
+        assert!("9-3".parse::<LineRange>().is_err());
+        assert!("12".parse::<LineRange>().is_err());
+    }
+
+    #[test]
+    fn test_by_file_history() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let repo = Repository::open(td.path()).unwrap();
+
+        // Carol moves the README into docs/ on 2020-01-06
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let mut index = repo.index().unwrap();
+        std::fs::create_dir_all(td.path().join("docs")).unwrap();
+        std::fs::rename(
+            td.path().join("README.md"),
+            td.path().join("docs/README.md"),
+        )
+        .unwrap();
+        index.remove_path(Path::new("README.md")).unwrap();
+        index.add_path(Path::new("docs/README.md")).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let carol = git2::Signature::new(
+            "Carol",
+            "carol@example.com",
+            &git2::Time::new(1_578_312_000, 0),
+        )
+        .unwrap();
+        repo.commit(Some("HEAD"), &carol, &carol, "move readme", &tree, &[&head])
+            .unwrap();
+
+        let args = ByFileArgs::builder(
+            td.path().to_str().unwrap().to_string(),
+            String::from("docs/README.md"),
+        )
+        .history(true)
+        .build()
+        .unwrap();
+
+        let history: Vec<(String, usize, usize, String, String)> = ByFile::new(args)
+            .process_history(&repo, "docs/README.md")
+            .unwrap()
+            .into_iter()
+            .map(|c| (c.author, c.insertions, c.deletions, c.subject, c.path))
+            .collect();
+
+        let expected: Vec<(String, usize, usize, String, String)> = vec![
+            ("Carol", 0, 0, "move readme", "docs/README.md"),
+            ("Bob", 1, 1, "fixture commit", "README.md"),
+            ("Alice", 2, 0, "fixture commit", "README.md"),
+        ]
+        .into_iter()
+        .map(|(a, i, d, s, p)| (a.to_string(), i, d, s.to_string(), p.to_string()))
+        .collect();
+
+        assert_eq!(history, expected);
+    }
 }
@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Mutex, OnceLock};
+
+static ENABLED: OnceLock<bool> = OnceLock::new();
+
+/// keeps the file lists, commit ranges, commit walks and blames for reuse by the later
+/// analyses of the same run.  Off by default, as a single analysis never asks twice.
+/// Only the first call has an effect.
+pub fn set_enabled(enabled: bool) {
+    let _ = ENABLED.set(enabled);
+}
+
+pub fn is_enabled() -> bool {
+    *ENABLED.get().unwrap_or(&false)
+}
+
+/// A process wide map of results, only read or written while the cache is enabled.
+pub struct Memo<K, V> {
+    map: OnceLock<Mutex<HashMap<K, V>>>,
+}
+
+impl<K: Eq + Hash, V: Clone> Memo<K, V> {
+    pub const fn new() -> Memo<K, V> {
+        Memo {
+            map: OnceLock::new(),
+        }
+    }
+
+    fn map(&self) -> &Mutex<HashMap<K, V>> {
+        self.map.get_or_init(|| Mutex::new(HashMap::new()))
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        if !is_enabled() {
+            return None;
+        }
+
+        self.map()
+            .lock()
+            .expect("cannot lock the cache")
+            .get(key)
+            .cloned()
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        if is_enabled() {
+            self.map()
+                .lock()
+                .expect("cannot lock the cache")
+                .insert(key, value);
+        }
+    }
+
+    /// the cached value for `key`, or the result of `f`, kept when it succeeds
+    pub fn get_or_try<E>(&self, key: K, f: impl FnOnce() -> Result<V, E>) -> Result<V, E> {
+        if let Some(v) = self.get(&key) {
+            return Ok(v);
+        }
+
+        let v = f()?;
+        self.insert(key, v.clone());
+        Ok(v)
+    }
+}
@@ -0,0 +1,47 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
+
+static HANDLER: Once = Once::new();
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+static WARNED: AtomicBool = AtomicBool::new(false);
+
+/// exit code of a run interrupted twice, as for a shell's SIGINT
+const INTERRUPTED_EXIT: i32 = 130;
+
+/// Makes a first Ctrl-C ask the running blames to wind down, so what was done so far is
+/// still reported, and a second one exit at once.  Only the first call installs the handler.
+pub fn install_handler() {
+    HANDLER.call_once(|| {
+        let installed = ctrlc::set_handler(|| {
+            if CANCELLED.swap(true, Ordering::SeqCst) {
+                std::process::exit(INTERRUPTED_EXIT);
+            }
+
+            eprintln!("Interrupted, finishing the files in progress.  Ctrl-C again to quit now.");
+        });
+
+        if let Err(e) = installed {
+            warn!("cannot handle Ctrl-C, it will end the run at once: {}", e);
+        }
+    });
+}
+
+/// Leaves Ctrl-C to end the process at once, as `grit serve` wants: a cancelled blame would
+/// answer every later request with partial results.  Must run before `install_handler`.
+pub fn keep_default_handler() {
+    HANDLER.call_once(|| {});
+}
+
+/// whether Ctrl-C asked the run to stop
+pub fn is_cancelled() -> bool {
+    CANCELLED.load(Ordering::SeqCst)
+}
+
+/// warns on stderr, once per run, that the results of a cancelled run are partial
+pub fn warn_partial() {
+    if !WARNED.swap(true, Ordering::SeqCst) {
+        eprintln!(
+            "Warning: the run was interrupted, the results only cover the files finished before it"
+        );
+    }
+}
@@ -0,0 +1,401 @@
+use crate::utils::grit_utils;
+use anyhow::{anyhow, Result};
+use charts::{BarDatum, BarLabelPosition, Chart, Color, HorizontalBarView, ScaleBand, ScaleLinear};
+use chrono::{Datelike, Duration, NaiveDate};
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+
+const CELL_SIZE: i64 = 11;
+const CELL_STRIDE: i64 = 13;
+const LEFT_MARGIN: i64 = 40;
+const TOP_MARGIN: i64 = 50;
+const LEVEL_COLORS: [&str; 5] = ["#ebedf0", "#9be9a8", "#40c463", "#30a14e", "#216e39"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChartTheme {
+    Default,
+    Tableau,
+    Dark,
+}
+
+impl FromStr for ChartTheme {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "default" => Ok(ChartTheme::Default),
+            "tableau" => Ok(ChartTheme::Tableau),
+            "dark" => Ok(ChartTheme::Dark),
+            _ => Err(anyhow!(
+                "chart-theme must be either 'default', 'tableau' or 'dark'"
+            )),
+        }
+    }
+}
+
+impl ChartTheme {
+    pub fn colors(&self) -> Vec<Color> {
+        match self {
+            ChartTheme::Default => Color::color_scheme_10(),
+            ChartTheme::Tableau => Color::color_scheme_tableau_10(),
+            ChartTheme::Dark => Color::color_scheme_dark(),
+        }
+    }
+}
+
+/// user overrides for the generated SVG charts
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChartOptions {
+    pub theme: ChartTheme,
+    pub title: Option<String>,
+    pub width: Option<isize>,
+    pub height: Option<isize>,
+}
+
+impl Default for ChartOptions {
+    fn default() -> Self {
+        ChartOptions::new(ChartTheme::Default, None, None, None)
+    }
+}
+
+impl ChartOptions {
+    pub fn new(
+        theme: ChartTheme,
+        title: Option<String>,
+        width: Option<isize>,
+        height: Option<isize>,
+    ) -> ChartOptions {
+        ChartOptions {
+            theme,
+            title,
+            width,
+            height,
+        }
+    }
+
+    /// the overridden width and height, falling back to the chart's own sizing
+    pub fn size(&self, default: (isize, isize)) -> (isize, isize) {
+        (
+            self.width.unwrap_or(default.0),
+            self.height.unwrap_or(default.1),
+        )
+    }
+
+    pub fn title(&self, default: &str) -> String {
+        self.title.clone().unwrap_or_else(|| default.to_string())
+    }
+}
+
+/// applies the size and title shared by every chart
+pub fn configure_chart<'a>(
+    options: &ChartOptions,
+    size: (isize, isize),
+    default_title: &str,
+) -> Chart<'a> {
+    Chart::new()
+        .set_width(size.0)
+        .set_height(size.1)
+        .add_title(options.title(default_title))
+}
+
+/// One bar of `bar_chart_svg`, e.g. a file and its commits.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bar {
+    pub category: String,
+    pub value: f32,
+}
+
+impl BarDatum for Bar {
+    fn get_category(&self) -> String {
+        self.category.clone()
+    }
+
+    fn get_value(&self) -> f32 {
+        self.value
+    }
+
+    fn get_key(&self) -> String {
+        String::new()
+    }
+}
+
+/// renders `bars` as horizontal bars, the first on top, measured in `unit`, and saves the
+/// svg to `file`.  The left margin grows to fit the longest category.
+pub fn bar_chart_svg(
+    options: &ChartOptions,
+    default_title: &str,
+    unit: &str,
+    bars: &[Bar],
+    file: &str,
+) -> Result<()> {
+    let longest = bars.iter().map(|b| b.category.len()).max().unwrap_or(0) as isize;
+    let (top, right, bottom, left) = (90, 60, 60, (longest * 7 + 20).clamp(80, 480));
+    let (width, height) = options.size((1027, (bars.len() as isize * 30).max(300) + top + bottom));
+
+    let max_value = bars.iter().map(|b| b.value).fold(0.0, f32::max) * 1.05 + 1.0;
+
+    let x = ScaleLinear::new()
+        .set_domain(vec![0.0, max_value])
+        .set_range(vec![0, width - left - right]);
+    let y = ScaleBand::new()
+        .set_domain(bars.iter().map(|b| b.category.clone()).collect())
+        .set_range(vec![0, height - top - bottom]);
+
+    let view = HorizontalBarView::new()
+        .set_x_scale(&x)
+        .set_y_scale(&y)
+        .set_label_position(BarLabelPosition::EndOutside)
+        .set_colors(options.theme.colors())
+        .load_data(&bars.to_vec())
+        .map_err(|e| anyhow!("Failed to create Bar View: {}", e))?;
+
+    configure_chart(options, (width, height), default_title)
+        .set_margins(top, right, bottom, left)
+        .add_view(&view)
+        .add_axis_bottom(&x)
+        .add_axis_left(&y)
+        .add_bottom_axis_label(unit)
+        .save(Path::new(file))
+        .map_err(|e| anyhow!("Failed to create Chart: {}", e))?;
+
+    Ok(())
+}
+
+/// buckets a count into one of the 5 calendar shades, 0 being no commits
+fn calendar_level(count: i32, max_count: i32) -> usize {
+    if count <= 0 || max_count <= 0 {
+        return 0;
+    }
+
+    let level = (count as f64 * 4.0 / max_count as f64).ceil() as usize;
+
+    level.clamp(1, 4)
+}
+
+/// renders a GitHub style calendar, one column per week and one row per weekday
+pub fn calendar_svg(title: &str, data: &[(NaiveDate, i32)]) -> String {
+    let counts: HashMap<NaiveDate, i32> = data.iter().cloned().collect();
+    let max_count = data.iter().map(|d| d.1).max().unwrap_or(0);
+
+    let first = data.iter().map(|d| d.0).min();
+    let last = data.iter().map(|d| d.0).max();
+
+    let mut cells: Vec<String> = Vec::new();
+    let mut labels: Vec<String> = Vec::new();
+    let mut weeks: i64 = 0;
+
+    if let (Some(first), Some(last)) = (first, last) {
+        let start = first - Duration::days(first.weekday().num_days_from_sunday() as i64);
+        let mut day = start;
+        let mut last_month = None;
+
+        while day <= last {
+            let week = (day - start).num_days() / 7;
+            let weekday = day.weekday().num_days_from_sunday() as i64;
+            let x = LEFT_MARGIN + week * CELL_STRIDE;
+
+            if weekday == 0 && last_month != Some(day.month()) {
+                labels.push(format!(
+                    "<text x=\"{}\" y=\"{}\">{}</text>",
+                    x,
+                    TOP_MARGIN - 6,
+                    MONTHS[day.month0() as usize]
+                ));
+                last_month = Some(day.month());
+            }
+
+            if day >= first {
+                let count = counts.get(&day).cloned().unwrap_or(0);
+
+                cells.push(format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{size}\" height=\"{size}\" rx=\"2\" fill=\"{}\"><title>{}: {} commits</title></rect>",
+                    x,
+                    TOP_MARGIN + weekday * CELL_STRIDE,
+                    LEVEL_COLORS[calendar_level(count, max_count)],
+                    grit_utils::format_date(day),
+                    count,
+                    size = CELL_SIZE
+                ));
+            }
+
+            weeks = week + 1;
+            day += Duration::days(1);
+        }
+    }
+
+    for (weekday, name) in [(1, "Mon"), (3, "Wed"), (5, "Fri")].iter() {
+        labels.push(format!(
+            "<text x=\"0\" y=\"{}\">{}</text>",
+            TOP_MARGIN + weekday * CELL_STRIDE + CELL_SIZE - 1,
+            name
+        ));
+    }
+
+    let width = LEFT_MARGIN + weeks * CELL_STRIDE + 20;
+    let height = TOP_MARGIN + 7 * CELL_STRIDE + 20;
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" font-family=\"sans-serif\" font-size=\"10\"><text x=\"0\" y=\"16\" font-size=\"16\">{title}</text>{labels}{cells}</svg>",
+        width = width,
+        height = height,
+        title = grit_utils::html_escape(title),
+        labels = labels.join(""),
+        cells = cells.join("")
+    )
+}
+
+/// renders a grid with one row per entry of `rows` and one column per entry of `columns`,
+/// each cell of `values` shaded like the calendar by its share of the largest value
+pub fn heatmap_svg(
+    title: &str,
+    rows: &[String],
+    columns: &[String],
+    values: &[Vec<i32>],
+) -> String {
+    let max_count = values.iter().flatten().copied().max().unwrap_or(0);
+
+    // room for the longest row name, at about 6 pixels a character
+    let left = rows
+        .iter()
+        .map(|r| r.chars().count() as i64 * 6 + 10)
+        .max()
+        .unwrap_or(0)
+        .max(LEFT_MARGIN);
+    let top = TOP_MARGIN + 30;
+
+    let mut cells: Vec<String> = Vec::new();
+    let mut labels: Vec<String> = Vec::new();
+
+    for (c, column) in columns.iter().enumerate() {
+        let x = left + c as i64 * CELL_STRIDE + CELL_SIZE - 1;
+        labels.push(format!(
+            "<text x=\"{x}\" y=\"{y}\" transform=\"rotate(-60 {x} {y})\">{}</text>",
+            grit_utils::html_escape(column),
+            x = x,
+            y = top - 4
+        ));
+    }
+
+    for (r, (row, counts)) in rows.iter().zip(values).enumerate() {
+        let y = top + r as i64 * CELL_STRIDE;
+        labels.push(format!(
+            "<text x=\"0\" y=\"{}\">{}</text>",
+            y + CELL_SIZE - 1,
+            grit_utils::html_escape(row)
+        ));
+
+        for (c, (column, count)) in columns.iter().zip(counts).enumerate() {
+            cells.push(format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{size}\" height=\"{size}\" rx=\"2\" fill=\"{}\"><title>{} {}: {} commits</title></rect>",
+                left + c as i64 * CELL_STRIDE,
+                y,
+                LEVEL_COLORS[calendar_level(*count, max_count)],
+                grit_utils::html_escape(row),
+                grit_utils::html_escape(column),
+                count,
+                size = CELL_SIZE
+            ));
+        }
+    }
+
+    let width = left + columns.len() as i64 * CELL_STRIDE + 40;
+    let height = top + rows.len() as i64 * CELL_STRIDE + 20;
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" font-family=\"sans-serif\" font-size=\"10\"><text x=\"0\" y=\"16\" font-size=\"16\">{title}</text>{labels}{cells}</svg>",
+        width = width,
+        height = height,
+        title = grit_utils::html_escape(title),
+        labels = labels.join(""),
+        cells = cells.join("")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use log::LevelFilter;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    #[test]
+    fn test_chart_options() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let options = ChartOptions::default();
+
+        assert_eq!(options.size((1027, 768)), (1027, 768));
+        assert_eq!(options.title("By Date"), "By Date");
+
+        let options = ChartOptions::new(
+            "dark".parse().unwrap(),
+            Some(String::from("Team Commits")),
+            Some(800),
+            None,
+        );
+
+        assert_eq!(options.theme, ChartTheme::Dark);
+        assert_eq!(options.size((1027, 768)), (800, 768));
+        assert_eq!(options.title("By Date"), "Team Commits");
+        assert!("chaulk".parse::<ChartTheme>().is_err());
+    }
+
+    #[test]
+    fn test_calendar_level() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        assert_eq!(calendar_level(0, 10), 0);
+        assert_eq!(calendar_level(1, 10), 1);
+        assert_eq!(calendar_level(5, 10), 2);
+        assert_eq!(calendar_level(10, 10), 4);
+        assert_eq!(calendar_level(3, 0), 0);
+    }
+
+    #[test]
+    fn test_calendar_svg() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        // 2020-03-11 is a Wednesday, so the calendar spans two weeks
+        let data = vec![
+            (NaiveDate::from_ymd_opt(2020, 3, 11).unwrap(), 2),
+            (NaiveDate::from_ymd_opt(2020, 3, 16).unwrap(), 8),
+        ];
+
+        let svg = calendar_svg("By Date", &data);
+
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<rect").count(), 6);
+        assert!(svg.contains("2020-03-16: 8 commits"));
+        assert!(svg.contains(LEVEL_COLORS[4]));
+        assert!(svg.contains(">Mar</text>"));
+    }
+
+    #[test]
+    fn test_calendar_svg_empty() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let svg = calendar_svg("By Date", &[]);
+
+        assert_eq!(svg.matches("<rect").count(), 0);
+    }
+
+    #[test]
+    fn test_heatmap_svg() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let rows = vec![String::from("src/main.rs"), String::from("src/lib.rs")];
+        let columns = vec![String::from("2020-W01"), String::from("2020-W02")];
+        let svg = heatmap_svg("Effort", &rows, &columns, &[vec![4, 0], vec![1, 2]]);
+
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<rect").count(), 4);
+        assert!(svg.contains("src/main.rs 2020-W01: 4 commits"));
+        assert!(svg.contains(LEVEL_COLORS[4]));
+        assert!(svg.contains(">2020-W02</text>"));
+    }
+}
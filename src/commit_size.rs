@@ -0,0 +1,423 @@
+use super::Processable;
+use crate::diff_stats;
+use crate::diff_stats::CommitStats;
+use crate::identity;
+use crate::output;
+use crate::table::Table;
+use crate::utils::grit_utils;
+use crate::utils::grit_utils::OutputFormat;
+use anyhow::Result;
+use chrono::NaiveDate;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+const TOTAL_AUTHOR: &str = "Total";
+
+pub struct CommitSizeArgs {
+    path: String,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+    include: Option<String>,
+    exclude: Option<String>,
+    restrict_authors: Option<String>,
+    giant_lines: usize,
+    list_giant: bool,
+    file: Option<String>,
+    format: OutputFormat,
+}
+
+impl CommitSizeArgs {
+    pub fn builder(path: String) -> CommitSizeArgsBuilder {
+        CommitSizeArgsBuilder {
+            args: CommitSizeArgs {
+                path,
+                start_date: None,
+                end_date: None,
+                include: None,
+                exclude: None,
+                restrict_authors: None,
+                giant_lines: 1000,
+                list_giant: false,
+                file: None,
+                format: OutputFormat::Csv,
+            },
+        }
+    }
+}
+
+/// Builds a `CommitSizeArgs` by name rather than position, validating it on `build`.
+pub struct CommitSizeArgsBuilder {
+    args: CommitSizeArgs,
+}
+
+impl CommitSizeArgsBuilder {
+    pub fn start_date(mut self, start_date: Option<NaiveDate>) -> CommitSizeArgsBuilder {
+        self.args.start_date = start_date;
+        self
+    }
+
+    pub fn end_date(mut self, end_date: Option<NaiveDate>) -> CommitSizeArgsBuilder {
+        self.args.end_date = end_date;
+        self
+    }
+
+    pub fn include(mut self, include: Option<String>) -> CommitSizeArgsBuilder {
+        self.args.include = include;
+        self
+    }
+
+    pub fn exclude(mut self, exclude: Option<String>) -> CommitSizeArgsBuilder {
+        self.args.exclude = exclude;
+        self
+    }
+
+    pub fn restrict_authors(mut self, restrict_authors: Option<String>) -> CommitSizeArgsBuilder {
+        self.args.restrict_authors = restrict_authors;
+        self
+    }
+
+    pub fn giant_lines(mut self, giant_lines: usize) -> CommitSizeArgsBuilder {
+        self.args.giant_lines = giant_lines;
+        self
+    }
+
+    pub fn list_giant(mut self, list_giant: bool) -> CommitSizeArgsBuilder {
+        self.args.list_giant = list_giant;
+        self
+    }
+
+    pub fn file(mut self, file: Option<String>) -> CommitSizeArgsBuilder {
+        self.args.file = file;
+        self
+    }
+
+    pub fn format(mut self, format: OutputFormat) -> CommitSizeArgsBuilder {
+        self.args.format = format;
+        self
+    }
+
+    pub fn build(self) -> Result<CommitSizeArgs> {
+        grit_utils::validate_args(
+            self.args.start_date,
+            self.args.end_date,
+            self.args.file.as_deref(),
+            self.args.format,
+        )?;
+
+        Ok(self.args)
+    }
+}
+
+/// The lines changed by each of an author's commits, or of everyone's.
+#[derive(Clone, Debug, PartialEq)]
+struct CommitSizeOutput {
+    author: String,
+    /// lines changed per commit, smallest first
+    sizes: Vec<usize>,
+}
+
+impl CommitSizeOutput {
+    fn new(author: String) -> CommitSizeOutput {
+        CommitSizeOutput {
+            author,
+            sizes: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, lines: usize) {
+        let i = self.sizes.partition_point(|s| *s <= lines);
+        self.sizes.insert(i, lines);
+    }
+
+    /// the size of the commit at `p` percent, by nearest rank
+    fn percentile(&self, p: f64) -> usize {
+        if self.sizes.is_empty() {
+            return 0;
+        }
+
+        let rank = ((p / 100.0) * self.sizes.len() as f64).ceil().max(1.0) as usize;
+        self.sizes[rank.min(self.sizes.len()) - 1]
+    }
+
+    fn mean(&self) -> f64 {
+        if self.sizes.is_empty() {
+            0.0
+        } else {
+            self.sizes.iter().sum::<usize>() as f64 / self.sizes.len() as f64
+        }
+    }
+
+    fn giant(&self, giant_lines: usize) -> usize {
+        self.sizes.iter().filter(|s| **s >= giant_lines).count()
+    }
+}
+
+pub struct CommitSize {
+    args: CommitSizeArgs,
+}
+
+impl CommitSize {
+    pub fn new(args: CommitSizeArgs) -> CommitSize {
+        CommitSize { args }
+    }
+
+    /// the stats of every commit in the date range, less the restricted authors and bots
+    fn commits(&self) -> Result<Vec<CommitStats>> {
+        let restrict_authors =
+            grit_utils::convert_string_list_to_vec(self.args.restrict_authors.clone());
+
+        let commits = diff_stats::commit_stats(
+            &self.args.path,
+            self.args.start_date,
+            self.args.end_date,
+            self.args.include.clone(),
+            self.args.exclude.clone(),
+            false,
+        )?;
+
+        Ok(commits
+            .into_iter()
+            .filter(|c| {
+                !restrict_authors
+                    .as_ref()
+                    .map(|v| v.iter().any(|a| a == &c.name))
+                    .unwrap_or(false)
+            })
+            .filter(|c| !identity::is_excluded(&c.name, &c.email))
+            .collect())
+    }
+
+    /// the commit sizes of every author, most commits first, and of everyone together
+    fn process_sizes(&self, commits: &[CommitStats]) -> (Vec<CommitSizeOutput>, CommitSizeOutput) {
+        let mut output_map: HashMap<String, CommitSizeOutput> = HashMap::new();
+        let mut total = CommitSizeOutput::new(String::from(TOTAL_AUTHOR));
+
+        for c in commits.iter() {
+            let key = identity::display_author(&c.name, &c.email);
+            output_map
+                .entry(key.clone())
+                .or_insert_with(|| CommitSizeOutput::new(key))
+                .add(c.stats.lines());
+            total.add(c.stats.lines());
+        }
+
+        let mut output: Vec<CommitSizeOutput> = output_map.into_values().collect();
+
+        output.sort_by(|a, b| {
+            b.sizes
+                .len()
+                .cmp(&a.sizes.len())
+                .then(a.author.cmp(&b.author))
+        });
+
+        (output, total)
+    }
+
+    fn sizes_table(&self, output: &[CommitSizeOutput], total: Option<&CommitSizeOutput>) -> Table {
+        let mut table = Table::new();
+
+        table.set_titles(table_row![
+            "author",
+            "commits",
+            "mean lines",
+            "median lines",
+            "p90 lines",
+            "max lines",
+            format!("{}+ lines", self.args.giant_lines),
+            format!("{}+ lines (%)", self.args.giant_lines)
+        ]);
+
+        for r in output.iter().chain(total) {
+            let giant = r.giant(self.args.giant_lines);
+
+            table.add_row(table_row![
+                r.author,
+                r.sizes.len(),
+                format!("{:.1}", r.mean()),
+                r.percentile(50.0),
+                r.percentile(90.0),
+                r.percentile(100.0),
+                giant,
+                output::percent(giant as f64 / r.sizes.len().max(1) as f64 * 100.0)
+            ]);
+        }
+
+        table.add_label(0, "author");
+        table.add_metric(
+            3,
+            "grit_author_median_commit_lines",
+            "Median lines changed by the author's commits.",
+        );
+        table.add_metric(
+            4,
+            "grit_author_p90_commit_lines",
+            "Lines changed by the author's 90th percentile commit.",
+        );
+        table.add_metric(
+            6,
+            "grit_author_giant_commits",
+            "Number of the author's commits changing at least --giant-lines lines.",
+        );
+
+        table
+    }
+
+    /// the commits changing at least `giant_lines` lines, biggest first
+    fn giant_table(&self, commits: &[CommitStats]) -> Table {
+        let mut giants: Vec<&CommitStats> = commits
+            .iter()
+            .filter(|c| c.stats.lines() >= self.args.giant_lines)
+            .collect();
+
+        giants.sort_by_key(|c| Reverse(c.stats.lines()));
+
+        let mut table = Table::new();
+
+        table.set_titles(table_row![
+            "commit",
+            "date",
+            "author",
+            "files",
+            "lines added",
+            "lines deleted"
+        ]);
+
+        for c in giants.iter() {
+            table.add_row(table_row![
+                c.oid.to_string(),
+                grit_utils::format_date(grit_utils::convert_git_time(&c.time)),
+                identity::display_author(&c.name, &c.email),
+                c.stats.files,
+                c.stats.insertions,
+                c.stats.deletions
+            ]);
+        }
+
+        table.add_label(0, "commit");
+        table.add_label(2, "author");
+        table.add_metric(
+            4,
+            "grit_commit_lines_added",
+            "Lines added by the giant commit.",
+        );
+        table.add_metric(
+            5,
+            "grit_commit_lines_deleted",
+            "Lines deleted by the giant commit.",
+        );
+
+        table
+    }
+}
+
+impl Processable<()> for CommitSize {
+    fn process(&self) -> Result<()> {
+        let commits = self.commits()?;
+        let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+
+        if self.args.list_giant {
+            self.giant_table(&commits).write(&mut w, self.args.format)?;
+            return Ok(());
+        }
+
+        let (output, total) = self.process_sizes(&commits);
+
+        let show_total =
+            self.args.format != OutputFormat::Json && self.args.format != OutputFormat::Prom;
+
+        self.sizes_table(&output, Some(&total).filter(|_| show_total))
+            .write(&mut w, self.args.format)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::LevelFilter;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    fn size_args(path: &str, giant_lines: usize, list_giant: bool, file: &str) -> CommitSizeArgs {
+        CommitSizeArgs::builder(path.to_string())
+            .giant_lines(giant_lines)
+            .list_giant(list_giant)
+            .file(Some(String::from(file)))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_commit_size_output() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let mut o = CommitSizeOutput::new(String::from("Alice"));
+        vec![40, 2, 1000, 7, 2].into_iter().for_each(|s| o.add(s));
+
+        assert_eq!(o.sizes, vec![2, 2, 7, 40, 1000]);
+        assert_eq!(o.percentile(50.0), 7);
+        assert_eq!(o.percentile(90.0), 1000);
+        assert_eq!(o.giant(40), 2);
+        assert!((o.mean() - 210.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_process_commit_size() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+        let file = "target/test_process_commit_size.csv";
+
+        CommitSize::new(size_args(path, 4, false, file))
+            .process()
+            .unwrap();
+
+        let rows: Vec<String> = std::fs::read_to_string(file)
+            .unwrap()
+            .lines()
+            .skip(1)
+            .map(String::from)
+            .collect();
+
+        // Alice's commits change 5 and 2 lines, Bob's 4 and 2
+        assert_eq!(
+            rows,
+            vec![
+                "Alice,2,3.5,2,5,5,1,50.0",
+                "Bob,2,3.0,2,4,4,1,50.0",
+                "Total,4,3.2,2,5,5,2,50.0",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_process_commit_size_list_giant() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+        let file = "target/test_process_commit_size_list_giant.csv";
+
+        CommitSize::new(size_args(path, 4, true, file))
+            .process()
+            .unwrap();
+
+        let rows: Vec<Vec<String>> = std::fs::read_to_string(file)
+            .unwrap()
+            .lines()
+            .skip(1)
+            .map(|l| l.split(',').skip(1).map(String::from).collect())
+            .collect();
+
+        assert_eq!(
+            rows,
+            vec![
+                vec!["2020-01-01", "Alice", "2", "5", "0"],
+                vec!["2020-01-02", "Bob", "1", "4", "0"],
+            ]
+        );
+    }
+}
@@ -0,0 +1,383 @@
+use super::Processable;
+use crate::diff_stats;
+use crate::identity;
+use crate::table::Table;
+use crate::utils::grit_utils;
+use crate::utils::grit_utils::OutputFormat;
+use anyhow::{anyhow, Result};
+use git2::{Commit, Oid, Patch, Repository};
+use std::collections::{HashMap, HashSet};
+
+const TOTAL_AUTHOR: &str = "Total";
+
+pub struct CompareArgs {
+    path: String,
+    base: String,
+    head: String,
+    include: Option<String>,
+    exclude: Option<String>,
+    file: Option<String>,
+    format: OutputFormat,
+}
+
+impl CompareArgs {
+    pub fn new(
+        path: String,
+        base: String,
+        head: String,
+        include: Option<String>,
+        exclude: Option<String>,
+        file: Option<String>,
+        format: OutputFormat,
+    ) -> CompareArgs {
+        CompareArgs {
+            path,
+            base,
+            head,
+            include,
+            exclude,
+            file,
+            format,
+        }
+    }
+}
+
+/// The commits, files and lines an author's head branch commits bring, or the branch's as
+/// a whole.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct CompareOutput {
+    author: String,
+    commits: usize,
+    files: usize,
+    insertions: usize,
+    deletions: usize,
+}
+
+impl CompareOutput {
+    fn net(&self) -> i64 {
+        self.insertions as i64 - self.deletions as i64
+    }
+}
+
+pub struct Compare {
+    args: CompareArgs,
+}
+
+impl Compare {
+    pub fn new(args: CompareArgs) -> Compare {
+        Compare { args }
+    }
+
+    fn resolve(&self, rev: &str) -> Result<Oid> {
+        Ok(Oid::from_bytes(&grit_utils::resolve_rev(
+            &self.args.path,
+            rev,
+        )?)?)
+    }
+
+    /// every author with a non merge commit on head but not base, most commits first, and
+    /// the branch total.  The total's files and lines are the diff from the merge base to
+    /// head, so changes the branch made and then undid are not counted.
+    fn process_compare(&self) -> Result<(Vec<CompareOutput>, CompareOutput)> {
+        let repo = grit_utils::open_repo(&self.args.path)?;
+
+        let base = self.resolve(&self.args.base)?;
+        let head = self.resolve(&self.args.head)?;
+        let merge_base = repo.merge_base(base, head).map_err(|_| {
+            anyhow!(
+                "{} and {} have no common ancestor",
+                self.args.base,
+                self.args.head
+            )
+        })?;
+
+        let mut revwalk = repo.revwalk()?;
+        revwalk.set_sorting(git2::Sort::TIME)?;
+        revwalk.push(head)?;
+        revwalk.hide(merge_base)?;
+
+        let mut files_map: HashMap<String, HashSet<String>> = HashMap::new();
+        let mut output_map: HashMap<String, CompareOutput> = HashMap::new();
+        let mut total_commits = 0;
+
+        for oid in revwalk {
+            let commit = repo.find_commit(oid?)?;
+
+            if commit.parent_count() > 1 {
+                continue;
+            }
+
+            let author = commit.author();
+            let name = String::from_utf8_lossy(author.name_bytes()).to_string();
+            let email = String::from_utf8_lossy(author.email_bytes()).to_string();
+
+            if identity::is_excluded(&name, &email) {
+                continue;
+            }
+
+            let changes = self.commit_changes(&repo, &commit)?;
+
+            // commits changing none of the included files are left out
+            if changes.is_empty() {
+                continue;
+            }
+
+            let key = identity::display_author(&name, &email);
+            let o = output_map
+                .entry(key.clone())
+                .or_insert_with(|| CompareOutput {
+                    author: key.clone(),
+                    ..Default::default()
+                });
+            let files = files_map.entry(key).or_default();
+
+            o.commits += 1;
+            total_commits += 1;
+
+            for (path, insertions, deletions) in changes.into_iter() {
+                o.insertions += insertions;
+                o.deletions += deletions;
+                files.insert(path);
+            }
+        }
+
+        let mut output: Vec<CompareOutput> = output_map
+            .into_iter()
+            .map(|(author, mut o)| {
+                o.files = files_map.get(&author).map(|f| f.len()).unwrap_or(0);
+                o
+            })
+            .collect();
+
+        output.sort_by(|a, b| b.commits.cmp(&a.commits).then(a.author.cmp(&b.author)));
+
+        let stats = diff_stats::tree_diff_stats(
+            &repo,
+            Some(&repo.find_commit(merge_base)?.tree()?),
+            &repo.find_commit(head)?.tree()?,
+            self.args.include.clone(),
+            self.args.exclude.clone(),
+        )?;
+
+        let total = CompareOutput {
+            author: String::from(TOTAL_AUTHOR),
+            commits: total_commits,
+            files: stats.files,
+            insertions: stats.insertions,
+            deletions: stats.deletions,
+        };
+
+        Ok((output, total))
+    }
+
+    /// the path, lines added and lines removed of every included file the commit changes
+    /// from its first parent
+    fn commit_changes(
+        &self,
+        repo: &Repository,
+        commit: &Commit,
+    ) -> Result<Vec<(String, usize, usize)>> {
+        let parent_tree = match commit.parent_count() {
+            0 => None,
+            _ => Some(commit.parent(0)?.tree()?),
+        };
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+
+        let mut changes: Vec<(String, usize, usize)> = Vec::new();
+
+        for idx in 0..diff.deltas().len() {
+            let patch = match Patch::from_diff(&diff, idx)? {
+                Some(p) => p,
+                None => continue,
+            };
+
+            let delta = patch.delta();
+            let path = delta
+                .new_file()
+                .path()
+                .or_else(|| delta.old_file().path())
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            let (_, insertions, deletions) = patch.line_stats()?;
+            changes.push((path, insertions, deletions));
+        }
+
+        if self.args.include.is_none() && self.args.exclude.is_none() {
+            return Ok(changes);
+        }
+
+        let keep: HashSet<String> = grit_utils::filter_file_names(
+            changes.iter().map(|(p, _, _)| p.clone()).collect(),
+            self.args.include.clone(),
+            self.args.exclude.clone(),
+        )?
+        .into_iter()
+        .collect();
+
+        Ok(changes
+            .into_iter()
+            .filter(|(p, _, _)| keep.contains(p))
+            .collect())
+    }
+
+    fn display_output(&self, output: &[CompareOutput], total: &CompareOutput) -> Result<()> {
+        let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+
+        let mut table = Table::new();
+
+        table.set_titles(table_row![
+            "author",
+            "commits",
+            "files",
+            "insertions",
+            "deletions",
+            "net"
+        ]);
+
+        output.iter().chain(std::iter::once(total)).for_each(|o| {
+            table.add_row(table_row![
+                o.author,
+                o.commits,
+                o.files,
+                o.insertions,
+                o.deletions,
+                o.net()
+            ]);
+        });
+
+        table.add_label(0, "author");
+        table.add_metric(
+            1,
+            "grit_compare_commits",
+            "Commits on the head branch but not the base.",
+        );
+        table.add_metric(2, "grit_compare_files", "Files the head branch changed.");
+        table.add_metric(3, "grit_compare_insertions", "Lines the head branch added.");
+        table.add_metric(
+            4,
+            "grit_compare_deletions",
+            "Lines the head branch removed.",
+        );
+
+        table.write(&mut w, self.args.format)?;
+
+        Ok(())
+    }
+}
+
+impl Processable<()> for Compare {
+    fn process(&self) -> Result<()> {
+        let (output, total) = self.process_compare()?;
+
+        self.display_output(&output, &total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::{Signature, Time};
+    use log::LevelFilter;
+    use std::fs;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    /// the fixture with a `feature` branch off Bob's 2020-01-02 commit, where Dave adds a
+    /// file and then rewrites one of its lines
+    fn branch_repo() -> TempDir {
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let repo = Repository::open(td.path()).unwrap();
+
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let mut parent = head.parent(0).unwrap().parent(0).unwrap();
+
+        // 2020-01-03 12:00 UTC and 2020-01-04 12:00 UTC
+        for (time, content) in [(1_578_052_800, "a\nb\n"), (1_578_139_200, "a\nc\n")] {
+            fs::write(td.path().join("feature.txt"), content).unwrap();
+
+            let mut index = repo.index().unwrap();
+            index.read_tree(&parent.tree().unwrap()).unwrap();
+            index.add_path(Path::new("feature.txt")).unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+
+            let dave = Signature::new("Dave", "dave@example.com", &Time::new(time, 0)).unwrap();
+            let oid = repo
+                .commit(None, &dave, &dave, "feature", &tree, &[&parent])
+                .unwrap();
+            parent = repo.find_commit(oid).unwrap();
+        }
+
+        repo.branch("feature", &parent, false).unwrap();
+
+        td
+    }
+
+    #[test]
+    fn test_process_compare() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td = branch_repo();
+        let path = td.path().to_str().unwrap();
+
+        let (output, total) = Compare::new(CompareArgs::new(
+            path.to_string(),
+            String::from("HEAD"),
+            String::from("feature"),
+            None,
+            None,
+            None,
+            OutputFormat::Csv,
+        ))
+        .process_compare()
+        .unwrap();
+
+        assert_eq!(
+            output,
+            vec![CompareOutput {
+                author: String::from("Dave"),
+                commits: 2,
+                files: 1,
+                insertions: 3,
+                deletions: 1,
+            }]
+        );
+
+        // the rewritten line only counts once against the merge base
+        assert_eq!(total.commits, 2);
+        assert_eq!((total.files, total.insertions, total.deletions), (1, 2, 0));
+        assert_eq!(total.net(), 2);
+    }
+
+    #[test]
+    fn test_process_compare_exclude() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td = branch_repo();
+        let path = td.path().to_str().unwrap();
+
+        // from the feature branch, Alice and Bob's later commits are unique to HEAD
+        let (output, total) = Compare::new(CompareArgs::new(
+            path.to_string(),
+            String::from("feature"),
+            String::from("HEAD"),
+            None,
+            Some(String::from("README.md")),
+            None,
+            OutputFormat::Csv,
+        ))
+        .process_compare()
+        .unwrap();
+
+        assert_eq!(
+            output
+                .iter()
+                .map(|o| (o.author.as_str(), o.commits, o.files))
+                .collect::<Vec<_>>(),
+            vec![("Alice", 1, 1)]
+        );
+        assert_eq!((total.commits, total.files), (1, 1));
+    }
+}
@@ -0,0 +1,100 @@
+use super::Processable;
+use anyhow::Result;
+use clap::App;
+use clap_complete::Shell;
+use std::io::Write;
+
+pub const SHELLS: [&str; 4] = ["bash", "zsh", "fish", "powershell"];
+
+pub struct CompletionsArgs {
+    shell: Shell,
+    app: App<'static>,
+}
+
+impl CompletionsArgs {
+    pub fn new(shell: Shell, app: App<'static>) -> CompletionsArgs {
+        CompletionsArgs { shell, app }
+    }
+}
+
+pub struct Completions {
+    args: CompletionsArgs,
+}
+
+impl Completions {
+    pub fn new(args: CompletionsArgs) -> Completions {
+        Completions { args }
+    }
+
+    /// the completion script clap_complete generates for the shell from every subcommand
+    fn script(&self) -> Vec<u8> {
+        let mut app = self.args.app.clone();
+        let mut script: Vec<u8> = Vec::new();
+
+        clap_complete::generate(self.args.shell, &mut app, "grit", &mut script);
+
+        script
+    }
+}
+
+impl Processable<()> for Completions {
+    fn process(&self) -> Result<()> {
+        std::io::stdout().write_all(&self.script())?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Arg;
+    use log::LevelFilter;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    fn app() -> App<'static> {
+        App::new("grit").subcommand(
+            App::new("fame")
+                .about("will create a table of metrics per author.  This may take a while")
+                .args(&[
+                    Arg::new("sort")
+                        .help("sort field, either 'commit', 'loc' or 'files'")
+                        .takes_value(true)
+                        .possible_values(["commit", "loc", "files"])
+                        .long("sort"),
+                    Arg::new("file")
+                        .help("output file")
+                        .takes_value(true)
+                        .long("file"),
+                    Arg::new("debug").help("enables debug").long("debug"),
+                ]),
+        )
+    }
+
+    #[test]
+    fn test_scripts() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let script = |shell: &str| {
+            let completions = Completions::new(CompletionsArgs::new(shell.parse().unwrap(), app()));
+
+            String::from_utf8(completions.script()).unwrap()
+        };
+
+        for shell in SHELLS.iter() {
+            let s = script(shell);
+
+            assert!(s.contains("fame"), "{} script has no fame", shell);
+            assert!(s.contains("debug"), "{} script has no --debug", shell);
+        }
+
+        assert!(script("bash").contains("complete -F _grit"));
+        assert!(script("zsh").contains("#compdef grit"));
+        assert!(script("fish").contains("-l sort -d 'sort field, either \\'commit\\', \\'loc\\' or \\'files\\'' -r -f -a \"{commit\t,loc\t,files\t}\""));
+        assert!(
+            script("powershell").contains("Register-ArgumentCompleter -Native -CommandName 'grit'")
+        );
+        assert!("tcsh".parse::<Shell>().is_err());
+    }
+}
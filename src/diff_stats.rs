@@ -0,0 +1,273 @@
+use crate::cache::Memo;
+use crate::utils::grit_utils;
+use anyhow::Result;
+use chrono::NaiveDate;
+use futures::future::join_all;
+use git2::{Oid, Patch, Repository, Time, Tree};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+
+type CommitStatsKey = (
+    String,
+    Option<NaiveDate>,
+    Option<NaiveDate>,
+    Option<String>,
+    Option<String>,
+    bool,
+);
+
+static COMMIT_STATS: Memo<CommitStatsKey, Vec<CommitStats>> = Memo::new();
+
+/// The files and lines a diff changes.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct DiffStats {
+    pub files: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+impl DiffStats {
+    /// lines added plus lines removed
+    pub fn lines(&self) -> usize {
+        self.insertions + self.deletions
+    }
+}
+
+/// A commit and the stats of its diff from its first parent.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommitStats {
+    pub oid: Oid,
+    pub name: String,
+    pub email: String,
+    pub time: Time,
+    pub merge: bool,
+    pub stats: DiffStats,
+}
+
+/// the stats going from `old` to `new`, counting only the files passing the include and
+/// exclude globs
+pub fn tree_diff_stats(
+    repo: &Repository,
+    old: Option<&Tree>,
+    new: &Tree,
+    include: Option<String>,
+    exclude: Option<String>,
+) -> Result<DiffStats> {
+    let diff = repo.diff_tree_to_tree(old, Some(new), None)?;
+
+    if include.is_none() && exclude.is_none() {
+        let stats = diff.stats()?;
+        return Ok(DiffStats {
+            files: stats.files_changed(),
+            insertions: stats.insertions(),
+            deletions: stats.deletions(),
+        });
+    }
+
+    let paths: Vec<String> = diff
+        .deltas()
+        .filter_map(|d| d.new_file().path().or_else(|| d.old_file().path()))
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+
+    let keep: HashSet<String> = grit_utils::filter_file_names(paths, include, exclude)?
+        .into_iter()
+        .collect();
+
+    let mut stats = DiffStats::default();
+
+    for idx in 0..diff.deltas().len() {
+        let patch = match Patch::from_diff(&diff, idx)? {
+            Some(p) => p,
+            None => continue,
+        };
+
+        let delta = patch.delta();
+        let path = delta
+            .new_file()
+            .path()
+            .or_else(|| delta.old_file().path())
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        if keep.contains(&path) {
+            let (_, additions, deletions) = patch.line_stats()?;
+            stats.files += 1;
+            stats.insertions += additions;
+            stats.deletions += deletions;
+        }
+    }
+
+    Ok(stats)
+}
+
+/// the stats of every commit reachable from HEAD in the date range, newest first, leaving
+/// out merges unless `merges` is set.  Commits changing none of the files passing the
+/// include and exclude globs are left out.
+pub fn commit_stats(
+    path: &str,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+    include: Option<String>,
+    exclude: Option<String>,
+    merges: bool,
+) -> Result<Vec<CommitStats>> {
+    COMMIT_STATS.get_or_try(
+        (
+            path.to_string(),
+            start_date,
+            end_date,
+            include.clone(),
+            exclude.clone(),
+            merges,
+        ),
+        || walk_commit_stats(path, start_date, end_date, include, exclude, merges),
+    )
+}
+
+/// walks the history for the commits in range, then diffs them concurrently
+fn walk_commit_stats(
+    path: &str,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+    include: Option<String>,
+    exclude: Option<String>,
+    merges: bool,
+) -> Result<Vec<CommitStats>> {
+    let repo = grit_utils::open_repo(path)?;
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(git2::Sort::TIME)?;
+    revwalk.push_head()?;
+
+    let mut oids: Vec<Oid> = Vec::new();
+
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+
+        if commit.parent_count() > 1 && !merges {
+            continue;
+        }
+
+        let day = grit_utils::convert_git_time(&commit.time());
+
+        if start_date.map(|d| day < d).unwrap_or(false)
+            || end_date.map(|d| day > d).unwrap_or(false)
+        {
+            continue;
+        }
+
+        oids.push(commit.id());
+    }
+
+    let rt = grit_utils::runtime("grit-diff-thread-runner")?;
+
+    let globs = Arc::new((path.to_string(), include, exclude));
+
+    let tasks: Vec<JoinHandle<Result<Option<CommitStats>>>> = oids
+        .into_iter()
+        .map(|oid| {
+            let globs = globs.clone();
+
+            rt.spawn(async move {
+                let (path, include, exclude) = &*globs;
+                grit_utils::with_thread_repo(path, |repo| {
+                    diff_commit(repo, oid, include.clone(), exclude.clone())
+                })
+            })
+        })
+        .collect();
+
+    let mut output: Vec<CommitStats> = Vec::new();
+
+    for jh in rt.block_on(join_all(tasks)) {
+        if let Some(c) = jh?? {
+            output.push(c);
+        }
+    }
+
+    Ok(output)
+}
+
+/// the stats of the commit's diff from its first parent, None when it changes none of the
+/// files passing the globs
+fn diff_commit(
+    repo: &Repository,
+    oid: Oid,
+    include: Option<String>,
+    exclude: Option<String>,
+) -> Result<Option<CommitStats>> {
+    let commit = repo.find_commit(oid)?;
+
+    let parent_tree = match commit.parent(0) {
+        Ok(p) => Some(p.tree()?),
+        Err(_) => None,
+    };
+
+    let stats = tree_diff_stats(
+        repo,
+        parent_tree.as_ref(),
+        &commit.tree()?,
+        include,
+        exclude,
+    )?;
+
+    if stats.files == 0 {
+        return Ok(None);
+    }
+
+    let author = commit.author();
+
+    Ok(Some(CommitStats {
+        oid,
+        name: String::from_utf8_lossy(author.name_bytes()).to_string(),
+        email: String::from_utf8_lossy(author.email_bytes()).to_string(),
+        time: commit.time(),
+        merge: commit.parent_count() > 1,
+        stats,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::LevelFilter;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    #[test]
+    fn test_commit_stats() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let lines = |include: Option<&str>| -> Vec<(String, usize, usize, usize)> {
+            commit_stats(path, None, None, include.map(String::from), None, false)
+                .unwrap()
+                .into_iter()
+                .map(|c| (c.name, c.stats.files, c.stats.insertions, c.stats.deletions))
+                .collect()
+        };
+
+        assert_eq!(
+            lines(None),
+            vec![
+                (String::from("Bob"), 1, 1, 1),
+                (String::from("Alice"), 1, 2, 0),
+                (String::from("Bob"), 1, 4, 0),
+                (String::from("Alice"), 2, 5, 0),
+            ]
+        );
+
+        assert_eq!(
+            lines(Some("*.md")),
+            vec![
+                (String::from("Bob"), 1, 1, 1),
+                (String::from("Alice"), 1, 2, 0),
+            ]
+        );
+    }
+}
@@ -1,137 +1,542 @@
 use super::Processable;
+use crate::cancel;
+use crate::chart;
+use crate::chart::{Bar, ChartOptions};
+use crate::identity;
+use crate::ignore_revs;
+use crate::metric::{ActiveDays, Churn, Commits, Metrics, Observation};
+use crate::ownership::SnapshotPeriod;
+use crate::progress::Progress;
+use crate::sample::Sample;
+use crate::table::Table;
+use crate::timing;
 use crate::utils::grit_utils;
-use anyhow::Result;
-use chrono::offset::Local;
-use chrono::Date;
+use crate::utils::grit_utils::{CommitRange, OutputFormat, RelativeTo, SkippedFile};
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
 use csv::Writer;
 use futures::future::join_all;
-use git2::{BlameOptions, Oid, Repository};
-use indicatif::ProgressBar;
-use prettytable::{cell, format, row, Table};
-use std::collections::HashSet;
-use std::io;
-use std::path::Path;
-use std::sync::{Arc, RwLock};
-use tokio::runtime;
+use git2::{Delta, DiffFindOptions, Oid, Patch, Repository};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::Semaphore;
 use tokio::task::JoinHandle;
 
 pub struct EffortArgs {
     path: String,
-    start_date: Option<Date<Local>>,
-    end_date: Option<Date<Local>>,
-    table: bool,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+    format: OutputFormat,
     include: Option<String>,
     exclude: Option<String>,
     restrict_authors: Option<String>,
+    relative_to: RelativeTo,
+    top: Option<usize>,
+    min_commits: Option<i32>,
+    strict: bool,
+    file: Option<String>,
+    by_author: bool,
+    by_dir: Option<usize>,
+    stream: bool,
+    min_loc: Option<usize>,
+    min_age_days: Option<usize>,
+    image: bool,
+    html: bool,
+    chart_options: ChartOptions,
+    include_deleted: bool,
+    columns: Option<Vec<String>>,
+    trend: Option<SnapshotPeriod>,
+    sample: Option<f64>,
 }
 
 impl EffortArgs {
-    pub fn new(
-        path: String,
-        start_date: Option<Date<Local>>,
-        end_date: Option<Date<Local>>,
-        table: bool,
-        include: Option<String>,
-        exclude: Option<String>,
-        restrict_authors: Option<String>,
-    ) -> EffortArgs {
-        EffortArgs {
-            path: path,
-            start_date: start_date,
-            end_date: end_date,
-            table: table,
-            include: include,
-            exclude: exclude,
-            restrict_authors: restrict_authors,
+    pub fn builder(path: String) -> EffortArgsBuilder {
+        EffortArgsBuilder {
+            args: EffortArgs {
+                path,
+                start_date: None,
+                end_date: None,
+                format: OutputFormat::Csv,
+                include: None,
+                exclude: None,
+                restrict_authors: None,
+                relative_to: RelativeTo::Repo,
+                top: None,
+                min_commits: None,
+                strict: false,
+                file: None,
+                by_author: false,
+                by_dir: None,
+                stream: false,
+                min_loc: None,
+                min_age_days: None,
+                image: false,
+                html: false,
+                chart_options: ChartOptions::default(),
+                include_deleted: false,
+                columns: None,
+                trend: None,
+                sample: None,
+            },
         }
     }
 }
 
+/// Builds an `EffortArgs` by name rather than position, validating it on `build`.
+pub struct EffortArgsBuilder {
+    args: EffortArgs,
+}
+
+impl EffortArgsBuilder {
+    pub fn start_date(mut self, start_date: Option<NaiveDate>) -> EffortArgsBuilder {
+        self.args.start_date = start_date;
+        self
+    }
+
+    pub fn end_date(mut self, end_date: Option<NaiveDate>) -> EffortArgsBuilder {
+        self.args.end_date = end_date;
+        self
+    }
+
+    pub fn format(mut self, format: OutputFormat) -> EffortArgsBuilder {
+        self.args.format = format;
+        self
+    }
+
+    pub fn include(mut self, include: Option<String>) -> EffortArgsBuilder {
+        self.args.include = include;
+        self
+    }
+
+    pub fn exclude(mut self, exclude: Option<String>) -> EffortArgsBuilder {
+        self.args.exclude = exclude;
+        self
+    }
+
+    pub fn restrict_authors(mut self, restrict_authors: Option<String>) -> EffortArgsBuilder {
+        self.args.restrict_authors = restrict_authors;
+        self
+    }
+
+    pub fn relative_to(mut self, relative_to: RelativeTo) -> EffortArgsBuilder {
+        self.args.relative_to = relative_to;
+        self
+    }
+
+    pub fn top(mut self, top: Option<usize>) -> EffortArgsBuilder {
+        self.args.top = top;
+        self
+    }
+
+    pub fn min_commits(mut self, min_commits: Option<i32>) -> EffortArgsBuilder {
+        self.args.min_commits = min_commits;
+        self
+    }
+
+    pub fn strict(mut self, strict: bool) -> EffortArgsBuilder {
+        self.args.strict = strict;
+        self
+    }
+
+    pub fn file(mut self, file: Option<String>) -> EffortArgsBuilder {
+        self.args.file = file;
+        self
+    }
+
+    pub fn by_author(mut self, by_author: bool) -> EffortArgsBuilder {
+        self.args.by_author = by_author;
+        self
+    }
+
+    pub fn by_dir(mut self, by_dir: Option<usize>) -> EffortArgsBuilder {
+        self.args.by_dir = by_dir;
+        self
+    }
+
+    pub fn stream(mut self, stream: bool) -> EffortArgsBuilder {
+        self.args.stream = stream;
+        self
+    }
+
+    pub fn min_loc(mut self, min_loc: Option<usize>) -> EffortArgsBuilder {
+        self.args.min_loc = min_loc;
+        self
+    }
+
+    pub fn min_age_days(mut self, min_age_days: Option<usize>) -> EffortArgsBuilder {
+        self.args.min_age_days = min_age_days;
+        self
+    }
+
+    pub fn image(mut self, image: bool) -> EffortArgsBuilder {
+        self.args.image = image;
+        self
+    }
+
+    pub fn html(mut self, html: bool) -> EffortArgsBuilder {
+        self.args.html = html;
+        self
+    }
+
+    pub fn chart_options(mut self, chart_options: ChartOptions) -> EffortArgsBuilder {
+        self.args.chart_options = chart_options;
+        self
+    }
+
+    pub fn include_deleted(mut self, include_deleted: bool) -> EffortArgsBuilder {
+        self.args.include_deleted = include_deleted;
+        self
+    }
+
+    pub fn columns(mut self, columns: Option<Vec<String>>) -> EffortArgsBuilder {
+        self.args.columns = columns;
+        self
+    }
+
+    pub fn trend(mut self, trend: Option<SnapshotPeriod>) -> EffortArgsBuilder {
+        self.args.trend = trend;
+        self
+    }
+
+    pub fn sample(mut self, sample: Option<f64>) -> EffortArgsBuilder {
+        self.args.sample = sample;
+        self
+    }
+
+    pub fn build(self) -> Result<EffortArgs> {
+        grit_utils::validate_args(
+            self.args.start_date,
+            self.args.end_date,
+            self.args.file.as_deref(),
+            self.args.format,
+        )?;
+
+        Ok(self.args)
+    }
+}
+
+const OTHERS_FILE: &str = "Others";
+
+/// files charted by `--image` when `--top` doesn't say
+const IMAGE_TOP_FILES: usize = 20;
+
+/// One author's commits and active days on a file.
+#[derive(Clone, Default)]
+struct AuthorEffort {
+    commit_ids: HashSet<String>,
+    dates: HashSet<NaiveDate>,
+}
+
 #[derive(Clone)]
 struct EffortOutput {
     file: String,
     commits: i32,
     active_days: i32,
+    commit_ids: HashSet<String>,
+    dates: HashSet<NaiveDate>,
+    /// day of each commit in `commit_ids`
+    commit_days: HashMap<String, NaiveDate>,
+    authors: HashMap<String, AuthorEffort>,
+    /// lines in the file at the latest commit, by every author
+    loc: usize,
+    /// day of the most recent commit behind the file's lines
+    last_modified: Option<NaiveDate>,
+    /// day of the oldest commit behind the file's lines
+    first_modified: Option<NaiveDate>,
+    /// lines added plus removed by the commits in range, with `include_deleted`
+    churn: usize,
+    /// deleted by a commit in range, with `include_deleted`.  Directories are deleted
+    /// when all their files are.
+    deleted: bool,
 }
 
 impl EffortOutput {
     pub fn new(file: String) -> EffortOutput {
         EffortOutput {
-            file: file,
+            file,
             commits: 0,
             active_days: 0,
+            commit_ids: HashSet::new(),
+            dates: HashSet::new(),
+            commit_days: HashMap::new(),
+            authors: HashMap::new(),
+            loc: 0,
+            last_modified: None,
+            first_modified: None,
+            churn: 0,
+            deleted: false,
         }
     }
+
+    /// author, commits and active days, the busiest author first
+    fn author_rows(&self) -> Vec<(String, usize, usize)> {
+        let mut rows: Vec<(String, usize, usize)> = self
+            .authors
+            .iter()
+            .map(|(a, e)| (a.clone(), e.commit_ids.len(), e.dates.len()))
+            .collect();
+
+        rows.sort_by(|a, b| b.1.cmp(&a.1).then(b.2.cmp(&a.2)).then(a.0.cmp(&b.0)));
+        rows
+    }
+
+    /// adds the commits, days, authors and lines of `other` to this one
+    fn merge(&mut self, other: EffortOutput) {
+        self.loc += other.loc;
+        self.churn += other.churn;
+        self.deleted &= other.deleted;
+        self.last_modified = self.last_modified.max(other.last_modified);
+        self.first_modified = match (self.first_modified, other.first_modified) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.commit_ids.extend(other.commit_ids);
+        self.dates.extend(other.dates);
+        self.commit_days.extend(other.commit_days);
+        for (author, e) in other.authors.into_iter() {
+            let ae = self.authors.entry(author).or_default();
+            ae.commit_ids.extend(e.commit_ids);
+            ae.dates.extend(e.dates);
+        }
+    }
+
+    fn calculate(&mut self) {
+        self.commits = self.commit_ids.len() as i32;
+        self.active_days = self.dates.len() as i32;
+    }
 }
 
 #[derive(Clone)]
 struct EffortProcessor {
     path: String,
-    earliest_commit: Option<Vec<u8>>,
-    latest_commit: Option<Vec<u8>>,
+    range: CommitRange,
     restrict_authors: Option<Vec<String>>,
 }
 
 impl EffortProcessor {
     pub fn new(
         path: String,
-        earliest_commit: Option<Vec<u8>>,
-        latest_commit: Option<Vec<u8>>,
+        range: CommitRange,
         restrict_authors: Option<Vec<String>>,
     ) -> EffortProcessor {
         EffortProcessor {
-            path: path,
-            earliest_commit: earliest_commit,
-            latest_commit: latest_commit,
-            restrict_authors: restrict_authors,
+            path,
+            range,
+            restrict_authors,
         }
     }
 
     async fn process_file(&self, file_name: &str) -> Result<EffortOutput> {
-        let repo = Repository::open(&self.path)?;
-        let mut bo = BlameOptions::new();
-
-        bo.track_copies_any_commit_copies(false);
-
-        if let Some(ev) = &self.earliest_commit {
-            let oid: Oid = Oid::from_bytes(&ev)?;
-            bo.oldest_commit(oid);
-        };
+        grit_utils::with_thread_repo(&self.path, |repo| self.blame(repo, file_name))
+    }
 
-        if let Some(ov) = &self.latest_commit {
-            let oid: Oid = Oid::from_bytes(&ov)?;
-            bo.newest_commit(oid);
-        };
+    fn blame(&self, repo: &Repository, file_name: &str) -> Result<EffortOutput> {
+        let start = Instant::now();
+        let earliest = self
+            .range
+            .earliest
+            .as_deref()
+            .map(Oid::from_bytes)
+            .transpose()?;
+        let latest = self
+            .range
+            .latest
+            .as_deref()
+            .map(Oid::from_bytes)
+            .transpose()?;
 
-        let mut effort_commits: HashSet<String> = HashSet::new();
-        let mut effort_dates: HashSet<Date<Local>> = HashSet::new();
+        let mut metrics: Metrics<String> = Metrics::new()
+            .with(Commits::default())
+            .with(ActiveDays::default());
+        let mut loc = 0;
+        let mut last_modified: Option<NaiveDate> = None;
+        let mut first_modified: Option<NaiveDate> = None;
+        let mut commit_days: HashMap<String, NaiveDate> = HashMap::new();
 
-        let file_path = Path::new(file_name);
+        let file_path = grit_utils::git_path(file_name);
 
-        let blame = repo.blame_file(file_path, Some(&mut bo))?;
+        let blame = ignore_revs::blame_file(repo, &file_path, earliest, latest)?;
 
-        for hunk in blame.iter() {
-            let commit_id = hunk.final_commit_id();
+        for b in blame.iter() {
+            let commit_id = b.commit_id;
             let commit = repo.find_commit(commit_id)?;
             let commit_date = grit_utils::convert_git_time(&commit.time());
 
+            loc += b.lines;
+            last_modified = last_modified.max(Some(commit_date));
+            first_modified = Some(first_modified.map_or(commit_date, |d| d.min(commit_date)));
+
+            // between the boundary commits, but dated outside the range
+            if !self.range.contains(&commit_id) {
+                continue;
+            }
+
             if let Some(v) = &self.restrict_authors {
-                let name: String = commit.clone().author().name().unwrap().to_string();
+                let name = String::from_utf8_lossy(commit.author().name_bytes()).to_string();
                 if v.iter().any(|a| a == &name) {
-                    break;
+                    continue;
                 }
             }
 
-            effort_commits.insert(commit_id.to_string());
-            effort_dates.insert(commit_date);
+            let author = commit.author();
+            let name = String::from_utf8_lossy(author.name_bytes()).to_string();
+            let email = String::from_utf8_lossy(author.email_bytes()).to_string();
+
+            if identity::is_excluded(&name, &email) {
+                continue;
+            }
+
+            let commit_id = commit_id.to_string();
+            let mut o = Observation::new(identity::display_author(&name, &email), &commit_id);
+            o.day = Some(commit_date);
+
+            metrics.observe(&o);
+            commit_days.insert(commit_id, commit_date);
         }
 
+        let commits = metrics.get::<Commits<String>>().unwrap();
+        let days = metrics.get::<ActiveDays<String>>().unwrap();
+
         let mut result = EffortOutput::new(String::from(file_name));
-        result.commits = effort_commits.len() as i32;
-        result.active_days = effort_dates.len() as i32;
+        result.commit_ids = commits.all().clone();
+        result.dates = days.all().clone();
+        result.commit_days = commit_days;
+        result.authors = metrics
+            .keys()
+            .map(|a| {
+                let ae = AuthorEffort {
+                    commit_ids: commits.ids(a).cloned().unwrap_or_default(),
+                    dates: days.days(a).cloned().unwrap_or_default(),
+                };
+                (a.clone(), ae)
+            })
+            .collect();
+        result.loc = loc;
+        result.last_modified = last_modified;
+        result.first_modified = first_modified;
+        result.calculate();
+
+        timing::record_file(file_name, start.elapsed());
 
         Ok(result)
     }
+
+    /// the commits, active days and churn of every file changed by the commits in range,
+    /// from their diffs against their first parent, along with the files they delete.
+    /// Merges are left out, and renamed files are followed to their new name.
+    fn diff_effort(&self) -> Result<(HashMap<String, EffortOutput>, HashSet<String>)> {
+        let repo = grit_utils::open_repo(&self.path)?;
+
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push_head()?;
+
+        let mut metrics: Metrics<(String, String)> = Metrics::new()
+            .with(Commits::default())
+            .with(ActiveDays::default())
+            .with(Churn::default());
+        let mut deleted: HashSet<String> = HashSet::new();
+        let mut commit_days: HashMap<String, NaiveDate> = HashMap::new();
+
+        for id in revwalk {
+            let commit = repo.find_commit(id?)?;
+
+            if !self.range.contains(&commit.id()) || commit.parent_count() > 1 {
+                continue;
+            }
+
+            let author = commit.author();
+            let name = String::from_utf8_lossy(author.name_bytes()).to_string();
+            let email = String::from_utf8_lossy(author.email_bytes()).to_string();
+
+            if let Some(v) = &self.restrict_authors {
+                if v.iter().any(|a| a == &name) {
+                    continue;
+                }
+            }
+
+            if identity::is_excluded(&name, &email) {
+                continue;
+            }
+
+            let parent_tree = match commit.parent_count() {
+                0 => None,
+                _ => Some(commit.parent(0)?.tree()?),
+            };
+            let mut diff =
+                repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+            diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+
+            let commit_id = commit.id().to_string();
+            let author = identity::display_author(&name, &email);
+            let day = grit_utils::convert_git_time(&commit.time());
+            commit_days.insert(commit_id.clone(), day);
+
+            for idx in 0..diff.deltas().len() {
+                let patch = match Patch::from_diff(&diff, idx)? {
+                    Some(p) => p,
+                    None => continue,
+                };
+
+                let delta = patch.delta();
+                let path = match delta.new_file().path_bytes() {
+                    Some(p) if delta.status() != Delta::Deleted => p,
+                    _ => match delta.old_file().path_bytes() {
+                        Some(p) => p,
+                        None => continue,
+                    },
+                };
+                let path = grit_utils::file_name_from_bytes(path);
+
+                if delta.status() == Delta::Deleted {
+                    deleted.insert(path.clone());
+                }
+
+                let (_, additions, deletions) = patch.line_stats()?;
+
+                let mut o = Observation::new((path, author.clone()), &commit_id);
+                o.day = Some(day);
+                o.churn = additions + deletions;
+
+                metrics.observe(&o);
+            }
+        }
+
+        let commits = metrics.get::<Commits<(String, String)>>().unwrap();
+        let days = metrics.get::<ActiveDays<(String, String)>>().unwrap();
+
+        let mut files: HashMap<String, EffortOutput> = HashMap::new();
+
+        for key in metrics.keys() {
+            let ae = AuthorEffort {
+                commit_ids: commits.ids(key).cloned().unwrap_or_default(),
+                dates: days.days(key).cloned().unwrap_or_default(),
+            };
+
+            let f = files
+                .entry(key.0.clone())
+                .or_insert_with(|| EffortOutput::new(key.0.clone()));
+            f.commit_ids.extend(ae.commit_ids.iter().cloned());
+            f.dates.extend(ae.dates.iter().cloned());
+            f.churn += metrics.value("churn", key);
+            f.authors.insert(key.1.clone(), ae);
+        }
+
+        files.values_mut().for_each(|f| {
+            f.last_modified = f.dates.iter().max().copied();
+            f.first_modified = f.dates.iter().min().copied();
+            f.commit_days = f
+                .commit_ids
+                .iter()
+                .filter_map(|c| Some((c.clone(), *commit_days.get(c)?)))
+                .collect();
+            f.calculate();
+        });
+
+        Ok((files, deleted))
+    }
 }
 
 pub struct Effort {
@@ -140,115 +545,696 @@ pub struct Effort {
 
 impl Effort {
     pub fn new(args: EffortArgs) -> Effort {
-        Effort { args: args }
+        Effort { args }
     }
 
-    fn display_csv(&self, data: Vec<EffortOutput>) -> Result<()> {
-        let mut wtr = Writer::from_writer(io::stdout());
+    /// keeps the first `top` files meeting the commit threshold and folds the
+    /// rest into a single "Others" row.
+    fn collapse_output(&self, output: Vec<EffortOutput>) -> Vec<EffortOutput> {
+        let top = self.args.top.unwrap_or(usize::MAX);
+        let min_commits = self.args.min_commits.unwrap_or(0);
 
-        wtr.write_record(&["file", "commits", "active days"])
-            .expect("cannot serialize header row");
+        let mut result: Vec<EffortOutput> = Vec::new();
+        let mut others = EffortOutput::new(String::from(OTHERS_FILE));
+        let mut collapsed = 0;
 
-        data.iter().for_each(|r| {
-            wtr.serialize((r.file.clone(), r.commits, r.active_days))
-                .expect("Cannot serialize table row");
-        });
+        for o in output.into_iter() {
+            if result.len() < top && o.commits >= min_commits {
+                result.push(o);
+            } else {
+                others.merge(o);
+                collapsed += 1;
+            }
+        }
 
-        wtr.flush().expect("Cannot flush the writer");
+        if collapsed > 0 {
+            others.calculate();
+            result.push(others);
+        }
 
-        Ok(())
+        result
     }
 
-    fn display_table(&self, data: Vec<EffortOutput>) -> Result<()> {
-        let mut table = Table::new();
+    /// drops the files under `--min-loc` lines or whose oldest line is younger than
+    /// `--min-age-days`, returning how many were dropped
+    fn drop_small_files(&self, output: &mut Vec<EffortOutput>) -> usize {
+        let min_loc = self.args.min_loc.unwrap_or(0);
+        let min_age_days = self.args.min_age_days.unwrap_or(0);
+        let today = grit_utils::today();
+        let before = output.len();
 
-        table.set_titles(row!["File", "Commits", "Active Days"]);
+        output.retain(|o| {
+            let age_days = o
+                .first_modified
+                .map(|d| (today - d).num_days())
+                .unwrap_or(0);
 
-        data.iter().for_each(|r| {
-            table.add_row(row![r.file, r.commits, r.active_days]);
+            o.loc >= min_loc && age_days >= min_age_days as i64
         });
 
-        table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
-        table.printstd();
+        before - output.len()
+    }
 
-        Ok(())
+    /// rolls the files up into their first `depth` directories.  A commit touching several
+    /// files of a directory counts once.
+    fn roll_up_directories(output: Vec<EffortOutput>, depth: usize) -> Vec<EffortOutput> {
+        let mut dirs: HashMap<String, EffortOutput> = HashMap::new();
+
+        for o in output.into_iter() {
+            let dir = grit_utils::directory(&o.file, depth);
+            dirs.entry(dir.clone())
+                .or_insert_with(|| EffortOutput {
+                    deleted: true,
+                    ..EffortOutput::new(dir)
+                })
+                .merge(o);
+        }
+
+        dirs.into_values()
+            .map(|mut d| {
+                d.calculate();
+                d
+            })
+            .collect()
     }
-}
 
-impl Processable<()> for Effort {
-    fn process(&self) -> Result<()> {
-        let (earliest_commit, latest_commit) = grit_utils::find_commit_range(
+    /// the files in the tree of the latest commit in range, as files added after it have
+    /// no history to blame, and none when no commit is in range.  Logs the files at HEAD
+    /// left out.
+    fn file_names(&self, range: &CommitRange) -> Result<Vec<String>> {
+        if range.is_empty() {
+            info!("skipped every file, no commit is in range");
+            return Ok(Vec::new());
+        }
+
+        let head_names = grit_utils::generate_file_list(
             &self.args.path,
-            self.args.start_date,
-            self.args.end_date,
+            self.args.include.clone(),
+            self.args.exclude.clone(),
         )?;
 
-        let file_names: Vec<String> = grit_utils::generate_file_list(
+        let latest = match range.latest.as_deref() {
+            Some(c) => c,
+            None => return Ok(head_names),
+        };
+
+        let file_names = grit_utils::generate_file_list_at(
             &self.args.path,
+            latest,
             self.args.include.clone(),
             self.args.exclude.clone(),
         )?;
 
+        let present: HashSet<&String> = file_names.iter().collect();
+        let missing: Vec<&String> = head_names.iter().filter(|f| !present.contains(f)).collect();
+
+        if !missing.is_empty() {
+            info!(
+                "skipped {} file(s) not in the tree of the latest commit in range",
+                missing.len()
+            );
+            missing
+                .iter()
+                .for_each(|f| debug!("skipped {}, not at the latest commit", f));
+        }
+
+        Ok(file_names)
+    }
+
+    /// the processor blaming over the commit range, and the files for it to blame, or the
+    /// `--sample` of them
+    fn processor(&self) -> Result<(EffortProcessor, Vec<String>, Option<Sample>)> {
+        let range = grit_utils::find_commit_range(
+            &self.args.path,
+            self.args.start_date,
+            self.args.end_date,
+        )?;
+
+        let file_names: Vec<String> = self.file_names(&range)?;
+        let (sample, file_names) = match self.args.sample {
+            Some(p) => {
+                let (sample, picked) = Sample::files(file_names, p);
+                (Some(sample), picked)
+            }
+            None => (None, file_names),
+        };
+
         let restrict_authors =
             grit_utils::convert_string_list_to_vec(self.args.restrict_authors.clone());
 
-        let ep = EffortProcessor::new(
-            self.args.path.clone(),
-            earliest_commit,
-            latest_commit,
-            restrict_authors,
-        );
+        let ep = EffortProcessor::new(self.args.path.clone(), range, restrict_authors);
 
-        let pgb = ProgressBar::new(file_names.len() as u64);
-        let arc_pgb = Arc::new(RwLock::new(pgb));
+        Ok((ep, file_names, sample))
+    }
 
-        let mut rt = runtime::Builder::new()
-            .threaded_scheduler()
-            .thread_name("grit-effort-thread-runner")
-            .build()
-            .expect("Fail to create threadpool");
+    /// the commits and active days of each of `file_names`, blamed concurrently, along
+    /// with the files that could not be blamed, including any whose task panicked
+    fn effort_files(
+        ep: &EffortProcessor,
+        file_names: &[String],
+    ) -> Result<(Vec<EffortOutput>, Vec<SkippedFile>)> {
+        let arc_pgb = Arc::new(Progress::new("processed", file_names.len()));
+        let in_flight = Arc::new(Semaphore::new(grit_utils::MAX_IN_FLIGHT_FILES));
+        cancel::install_handler();
 
-        let mut tasks: Vec<JoinHandle<Result<EffortOutput, ()>>> = vec![];
+        let rt = grit_utils::runtime("grit-effort-thread-runner")?;
+
+        let mut tasks: Vec<JoinHandle<Result<EffortOutput, SkippedFile>>> = vec![];
+
+        for file_name in file_names.iter() {
+            if cancel::is_cancelled() {
+                break;
+            }
 
-        for file_name in file_names {
+            // waits for a file to finish once the most allowed are running
+            let permit = rt.block_on(in_flight.clone().acquire_owned())?;
+            let file_name = file_name.clone();
             let ep = ep.clone();
             let arc_pgb_c = arc_pgb.clone();
             tasks.push(rt.spawn(async move {
+                let _permit = permit;
                 ep.process_file(&file_name.clone())
                     .await
-                    .map(|e| {
-                        arc_pgb_c
-                            .write()
-                            .expect("cannot open ProgressBar to write")
-                            .inc(1);
-                        e
-                    })
+                    .inspect(|_e| arc_pgb_c.inc())
                     .map_err(|err| {
-                        error!("Error processing effort: {}", err);
+                        error!("Error processing effort for {}: {}", file_name, err);
+                        SkippedFile::new(&file_name, err.to_string())
                     })
             }));
         }
 
         let jh_results = rt.block_on(join_all(tasks));
 
-        arc_pgb
-            .write()
-            .expect("Cannot open ProgressBar to write")
-            .finish();
+        if jh_results.len() < file_names.len() {
+            arc_pgb.finish_and_clear();
+            cancel::warn_partial();
+        } else {
+            arc_pgb.finish();
+        }
+
+        let mut results: Vec<EffortOutput> = Vec::new();
+        let mut skipped: Vec<SkippedFile> = Vec::new();
+
+        for (file_name, jh) in file_names.iter().zip(jh_results) {
+            match jh {
+                Ok(Ok(r)) => results.push(r),
+                Ok(Err(s)) => skipped.push(s),
+                Err(e) => skipped.push(SkippedFile::new(file_name, e.to_string())),
+            }
+        }
+
+        Ok((results, skipped))
+    }
+
+    /// the commits and active days of every file, or directory with `by_dir`, sorted and
+    /// collapsed, along with the number of files too small or young to count and, with
+    /// `--sample`, the repo's totals estimated from the sampled files
+    fn effort_results(&self) -> Result<(Vec<EffortOutput>, usize, Option<String>)> {
+        let (ep, file_names, sample) = self.processor()?;
+        let (mut results, skipped) =
+            timing::phase("blame", || Effort::effort_files(&ep, &file_names))?;
+
+        grit_utils::report_skipped(&skipped, self.args.strict)?;
+
+        let estimate = sample.map(|s| Effort::estimate_totals(&s, &results));
+
+        if self.args.include_deleted {
+            timing::phase("churn", || {
+                self.add_deleted_files(&ep, &file_names, &mut results)
+            })?;
+        }
+
+        timing::phase("aggregation", || {
+            let dropped = self.drop_small_files(&mut results);
 
-        let mut results: Vec<EffortOutput> = jh_results
+            self.display_files(&mut results)?;
+
+            if let Some(depth) = self.args.by_dir {
+                results = Effort::roll_up_directories(results, depth);
+            }
+
+            results.sort_by(|a, b| b.commits.cmp(&a.commits).then(a.file.cmp(&b.file)));
+
+            Ok((self.collapse_output(results), dropped, estimate))
+        })
+    }
+
+    /// the repo's LOC and commits per file, summed over its files, extrapolated from
+    /// the sampled files
+    fn estimate_totals(sample: &Sample, results: &[EffortOutput]) -> String {
+        let loc: Vec<f64> = results.iter().map(|r| r.loc as f64).collect();
+        let commits: Vec<f64> = results.iter().map(|r| r.commits as f64).collect();
+
+        format!(
+            "Totals of the {}, estimated repo LOC {} and file commits {} (95% confidence)",
+            sample,
+            sample.estimate(&loc),
+            sample.estimate(&commits)
+        )
+    }
+
+    /// sets the churn of every file in range and adds a row for each file deleted in it,
+    /// left out of `file_names` as there is nothing left to blame
+    fn add_deleted_files(
+        &self,
+        ep: &EffortProcessor,
+        file_names: &[String],
+        results: &mut Vec<EffortOutput>,
+    ) -> Result<()> {
+        let (mut files, deleted) = ep.diff_effort()?;
+
+        for r in results.iter_mut() {
+            r.churn = files.get(&r.file).map(|f| f.churn).unwrap_or(0);
+        }
+
+        let tracked: HashSet<&String> = file_names.iter().collect();
+        let deleted: Vec<String> = deleted
             .into_iter()
-            .map(|jh| jh.unwrap().unwrap().clone())
+            .filter(|f| !tracked.contains(f))
             .collect();
 
-        results.sort_by(|a, b| b.commits.cmp(&a.commits));
+        for f in grit_utils::filter_file_names(
+            deleted,
+            self.args.include.clone(),
+            self.args.exclude.clone(),
+        )? {
+            if let Some(mut o) = files.remove(&f) {
+                o.deleted = true;
+                results.push(o);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// names the files relative to the `relative_to` directory
+    fn display_files(&self, results: &mut [EffortOutput]) -> Result<()> {
+        let file_names: Vec<String> = results.iter().map(|r| r.file.clone()).collect();
+        let display_names =
+            grit_utils::relative_file_paths(&self.args.path, &file_names, self.args.relative_to)?;
+
+        results
+            .iter_mut()
+            .zip(display_names)
+            .for_each(|(r, f)| r.file = f);
+
+        Ok(())
+    }
+
+    /// blames the files `STREAM_CHUNK_FILES` at a time, writing the rows of each as csv once
+    /// its chunk is done, then a summary of the whole run.  Only the distinct commits, days
+    /// and authors are kept between chunks.
+    fn stream_output(&self, w: &mut dyn Write) -> Result<()> {
+        let (ep, file_names, _) = self.processor()?;
+
+        let mut files = 0;
+        let mut commit_ids: HashSet<String> = HashSet::new();
+        let mut dates: HashSet<NaiveDate> = HashSet::new();
+        let mut authors: HashSet<String> = HashSet::new();
+        let mut skipped: Vec<SkippedFile> = Vec::new();
+        let mut dropped = 0;
+
+        {
+            let mut wrt = Writer::from_writer(&mut *w);
+
+            if self.args.by_author {
+                wrt.write_record([
+                    "file",
+                    identity::author_column().1,
+                    "commits",
+                    "active days",
+                ])?;
+            } else {
+                wrt.write_record(["file", "commits", "active days", "loc", "last modified"])?;
+            }
+
+            for chunk in file_names.chunks(grit_utils::STREAM_CHUNK_FILES) {
+                if cancel::is_cancelled() {
+                    break;
+                }
+
+                let (mut results, chunk_skipped) = Effort::effort_files(&ep, chunk)?;
+                skipped.extend(chunk_skipped);
+                dropped += self.drop_small_files(&mut results);
+
+                self.display_files(&mut results)?;
+
+                for r in results.into_iter() {
+                    if self.args.by_author {
+                        for (author, commits, active_days) in r.author_rows() {
+                            wrt.serialize((&r.file, author, commits, active_days))?;
+                        }
+                    } else {
+                        wrt.serialize((
+                            &r.file,
+                            r.commits,
+                            r.active_days,
+                            r.loc,
+                            Effort::format_last_modified(&r),
+                        ))?;
+                    }
+
+                    files += 1;
+                    authors.extend(r.authors.into_keys());
+                    commit_ids.extend(r.commit_ids);
+                    dates.extend(r.dates);
+                }
+
+                wrt.flush()?;
+            }
+        }
+
+        grit_utils::report_skipped(&skipped, self.args.strict)?;
+        Effort::log_dropped(dropped);
+
+        writeln!(w)?;
+
+        let mut wrt = Writer::from_writer(w);
+        wrt.write_record(["files", "commits", "active days", "authors"])?;
+        wrt.serialize((files, commit_ids.len(), dates.len(), authors.len()))?;
+        wrt.flush()?;
+
+        Ok(())
+    }
+
+    /// the effort results as a table, for callers showing it themselves
+    pub(crate) fn results_table(&self) -> Result<Table> {
+        Ok(self.effort_table(self.effort_results()?.0))
+    }
 
-        if self.args.table {
-            self.display_table(results)
-                .expect("Failed to create Effort table");
+    /// the title, prom label and help text wording of the first column
+    fn entity(&self) -> (&'static str, &'static str, &'static str) {
+        if self.args.by_dir.is_some() {
+            ("Directory", "directory", "a file in the directory")
         } else {
-            self.display_csv(results)
-                .expect("Failed to create Effort CSV");
+            ("File", "file", "the file")
+        }
+    }
+
+    fn effort_table(&self, data: Vec<EffortOutput>) -> Table {
+        if self.args.by_author {
+            return self.author_table(data);
+        }
+
+        let (title, entity, scope) = self.entity();
+        let mut table = Table::new();
+
+        let mut titles = if self.args.format == OutputFormat::Table {
+            table_row![title, "Commits", "Active Days", "LOC", "Last Modified"]
+        } else {
+            table_row![entity, "commits", "active days", "loc", "last modified"]
+        };
+
+        if self.args.include_deleted {
+            titles.extend(self.deleted_titles(&["Churn", "Deleted"], &["churn", "deleted"]));
+        }
+
+        table.set_titles(titles);
+
+        data.iter().for_each(|r| {
+            let mut row = table_row![
+                r.file,
+                r.commits,
+                r.active_days,
+                r.loc,
+                Effort::format_last_modified(r)
+            ];
+
+            if self.args.include_deleted {
+                row.extend(table_row![r.churn, r.deleted]);
+            }
+
+            table.add_row(row);
+        });
+
+        table.add_label(0, entity);
+        table.add_metric(
+            1,
+            &format!("grit_{}_commits", entity),
+            &format!("Commits touching {}.", scope),
+        );
+        table.add_metric(
+            2,
+            &format!("grit_{}_active_days", entity),
+            &format!("Days with at least one commit touching {}.", scope),
+        );
+        table.add_metric(
+            3,
+            &format!("grit_{}_loc", entity),
+            &format!("Lines in the {} at the latest commit.", entity),
+        );
+
+        if self.args.include_deleted {
+            table.add_label(6, "deleted");
+            table.add_metric(
+                5,
+                &format!("grit_{}_churn", entity),
+                &format!(
+                    "Lines added plus removed in {} by the commits in range.",
+                    scope
+                ),
+            );
+        }
+
+        table
+    }
+
+    /// the label of each `period` from the first commit of `data` to the last, and the
+    /// commits of each row in every one of them
+    fn trend_matrix(data: &[EffortOutput], period: SnapshotPeriod) -> (Vec<String>, Vec<Vec<i32>>) {
+        let days = data.iter().flat_map(|r| r.commit_days.values());
+        let period_ends = match (days.clone().min(), days.max()) {
+            (Some(first), Some(last)) => period.period_ends(*first, period.period_end(*last)),
+            _ => Vec::new(),
+        };
+        let index: HashMap<NaiveDate, usize> = period_ends
+            .iter()
+            .enumerate()
+            .map(|(i, d)| (*d, i))
+            .collect();
+
+        let values = data
+            .iter()
+            .map(|r| {
+                let mut counts = vec![0; period_ends.len()];
+                for d in r.commit_days.values() {
+                    counts[index[&period.period_end(*d)]] += 1;
+                }
+                counts
+            })
+            .collect();
+
+        (
+            period_ends.iter().map(|d| period.label(*d)).collect(),
+            values,
+        )
+    }
+
+    /// one row per file and one column per period, counting the commits touching the file
+    /// in it
+    fn trend_table(&self, data: Vec<EffortOutput>, period: SnapshotPeriod) -> Table {
+        let (title, entity, _) = self.entity();
+        let (periods, values) = Effort::trend_matrix(&data, period);
+        let mut table = Table::new();
+
+        let mut titles = if self.args.format == OutputFormat::Table {
+            table_row![title]
+        } else {
+            table_row![entity]
+        };
+        titles.extend(periods);
+        table.set_titles(titles);
+
+        for (r, counts) in data.iter().zip(values) {
+            let mut row = table_row![r.file];
+            row.extend(counts.iter().map(|c| c.to_string()));
+            table.add_row(row);
+        }
+
+        table.add_label(0, entity);
+
+        table
+    }
+
+    /// the titles of the columns `--include-deleted` adds, for the table or the data formats
+    fn deleted_titles(&self, table: &[&str], data: &[&str]) -> Vec<String> {
+        let titles = if self.args.format == OutputFormat::Table {
+            table
+        } else {
+            data
+        };
+
+        titles.iter().map(|t| t.to_string()).collect()
+    }
+
+    /// a bar of commits for each of the busiest files, or directories with `by_dir`, or a
+    /// heatmap of their commits per period with `trend`
+    fn create_output_image(&self, results: &[EffortOutput]) -> Result<()> {
+        let file = self
+            .args
+            .file
+            .clone()
+            .unwrap_or_else(|| String::from("effort.svg"));
+
+        if !grit_utils::check_file_type(&file, "svg") {
+            return Err(anyhow!("the image file format must be svg"));
+        }
+
+        let charted: Vec<EffortOutput> = results
+            .iter()
+            .filter(|r| r.file != OTHERS_FILE)
+            .take(self.args.top.unwrap_or(IMAGE_TOP_FILES))
+            .cloned()
+            .collect();
+
+        if let Some(period) = self.args.trend {
+            let (periods, values) = Effort::trend_matrix(&charted, period);
+            let files: Vec<String> = charted.into_iter().map(|r| r.file).collect();
+
+            let mut w = File::create(&file)?;
+            writeln!(
+                w,
+                "{}",
+                chart::heatmap_svg(
+                    &self.args.chart_options.title("Effort"),
+                    &files,
+                    &periods,
+                    &values
+                )
+            )?;
+        } else {
+            let bars: Vec<Bar> = charted
+                .iter()
+                .map(|r| Bar {
+                    category: r.file.clone(),
+                    value: r.commits as f32,
+                })
+                .collect();
+
+            chart::bar_chart_svg(&self.args.chart_options, "Effort", "Commits", &bars, &file)?;
+        }
+
+        if self.args.html {
+            grit_utils::create_html(&file)?;
+        }
+
+        Ok(())
+    }
+
+    fn log_dropped(dropped: usize) {
+        if dropped > 0 {
+            info!(
+                "left out {} file(s) under --min-loc or --min-age-days",
+                dropped
+            );
+        }
+    }
+
+    fn format_last_modified(r: &EffortOutput) -> String {
+        r.last_modified
+            .map(grit_utils::format_date)
+            .unwrap_or_default()
+    }
+
+    /// one row per author of each file
+    fn author_table(&self, data: Vec<EffortOutput>) -> Table {
+        let (title, entity, scope) = self.entity();
+        let mut table = Table::new();
+
+        let mut titles = if self.args.format == OutputFormat::Table {
+            table_row![title, identity::author_column().0, "Commits", "Active Days"]
+        } else {
+            table_row![
+                entity,
+                identity::author_column().1,
+                "commits",
+                "active days"
+            ]
+        };
+
+        if self.args.include_deleted {
+            titles.extend(self.deleted_titles(&["Deleted"], &["deleted"]));
+            table.add_label(4, "deleted");
+        }
+
+        table.set_titles(titles);
+
+        for r in data.iter() {
+            for (author, commits, active_days) in r.author_rows() {
+                let mut row = table_row![r.file, author, commits, active_days];
+
+                if self.args.include_deleted {
+                    row.push(r.deleted.to_string());
+                }
+
+                table.add_row(row);
+            }
+        }
+
+        table.add_label(0, entity);
+        table.add_label(1, identity::author_column().1);
+        table.add_metric(
+            2,
+            &format!("grit_{}_author_commits", entity),
+            &format!("Commits by the author touching {}.", scope),
+        );
+        table.add_metric(
+            3,
+            &format!("grit_{}_author_active_days", entity),
+            &format!("Days the author made a commit touching {}.", scope),
+        );
+
+        table
+    }
+}
+
+impl Processable<()> for Effort {
+    fn process(&self) -> Result<()> {
+        if grit_utils::range_is_empty(&self.args.path, self.args.start_date, self.args.end_date)? {
+            return Ok(());
+        }
+
+        if self.args.stream {
+            let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+            return self.stream_output(&mut w);
+        }
+
+        let (results, dropped, estimate) = self.effort_results()?;
+
+        // the sampled totals would be taken for the repo's, so the estimate goes to stderr
+        // where it can't break the data formats
+        if let Some(e) = &estimate {
+            eprintln!("{}", e);
+        }
+
+        if self.args.image {
+            Effort::log_dropped(dropped);
+            return timing::phase("output", || self.create_output_image(&results));
+        }
+
+        let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+        timing::phase("output", || -> Result<()> {
+            let mut table = match self.args.trend {
+                Some(period) => self.trend_table(results, period),
+                None => self.effort_table(results),
+            };
+
+            if let Some(columns) = &self.args.columns {
+                table.select_columns(columns)?;
+            }
+
+            Ok(table.write(&mut w, self.args.format)?)
+        })?;
+
+        // a footer would break the machine readable formats
+        if self.args.format == OutputFormat::Table && dropped > 0 {
+            writeln!(
+                w,
+                "{} file(s) under --min-loc or --min-age-days left out",
+                dropped
+            )?;
+        } else {
+            Effort::log_dropped(dropped);
         }
 
         Ok(())
@@ -258,64 +1244,425 @@ impl Processable<()> for Effort {
 #[cfg(test)]
 mod tests {
     use super::*;
+
     use log::LevelFilter;
     use tempfile::TempDir;
 
     const LOG_LEVEL: LevelFilter = LevelFilter::Info;
 
+    /// the csv rows written by `args`, in file order
+    fn effort_rows(args: EffortArgs) -> Vec<String> {
+        let file = args.file.clone().unwrap();
+
+        Effort::new(args).process().unwrap();
+
+        let mut rows: Vec<String> = std::fs::read_to_string(file)
+            .unwrap()
+            .lines()
+            .skip(1)
+            .map(String::from)
+            .collect();
+        rows.sort();
+        rows
+    }
+
     #[test]
     fn test_effort() {
         crate::grit_test::set_test_logging(LOG_LEVEL);
 
-        let td: TempDir = crate::grit_test::init_repo();
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let args = EffortArgs::builder(String::from(path))
+            .file(Some(String::from("target/test_effort.csv")))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            effort_rows(args),
+            vec![
+                "README.md,2,2,2,2020-01-05",
+                "src/lib.rs,1,1,4,2020-01-02",
+                "src/main.rs,2,2,5,2020-01-02"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_effort_no_commits_in_range() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
         let path = td.path().to_str().unwrap();
+        let file = td.path().join("effort.csv");
 
-        let args = EffortArgs::new(String::from(path), None, None, false, None, None, None);
+        // the fixture's last commit is made on 2020-01-05
+        let args = EffortArgs::builder(String::from(path))
+            .start_date(Some(NaiveDate::from_ymd_opt(2020, 1, 6).unwrap()))
+            .file(Some(file.to_str().unwrap().to_string()))
+            .build()
+            .unwrap();
+
+        Effort::new(args).process().unwrap();
+        assert!(!file.exists());
+    }
 
+    #[test]
+    fn test_effort_file_names_no_commits_in_range() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let args = EffortArgs::builder(String::from(path))
+            .start_date(Some(NaiveDate::from_ymd_opt(2027, 1, 1).unwrap()))
+            .build()
+            .unwrap();
         let effort = Effort::new(args);
 
-        let _result = effort.process();
+        let (_, file_names, _) = effort.processor().unwrap();
+        assert!(file_names.is_empty());
+
+        let all = effort.file_names(&CommitRange::default()).unwrap();
+        assert_eq!(all, vec!["README.md", "src/lib.rs", "src/main.rs"]);
     }
 
     #[test]
-    fn test_effort_include() {
+    fn test_effort_include_deleted() {
         crate::grit_test::set_test_logging(LOG_LEVEL);
 
-        let td: TempDir = crate::grit_test::init_repo();
+        let td: TempDir = crate::grit_test::fixture_repo();
         let path = td.path().to_str().unwrap();
-        let ea = EffortArgs::new(
-            path.to_string(),
-            None,
-            None,
-            true,
-            Some("*.rs,*.md".to_string()),
-            None,
-            None,
+
+        let repo = Repository::open(path).unwrap();
+        let mut index = repo.index().unwrap();
+        index
+            .remove_path(std::path::Path::new("src/lib.rs"))
+            .unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::new(
+            "Bob",
+            "bob@example.com",
+            &git2::Time::new(1_578_312_000, 0), // 2020-01-06 12:00 UTC
+        )
+        .unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "remove lib", &tree, &[&head])
+            .unwrap();
+
+        let args = EffortArgs::builder(String::from(path))
+            .file(Some(String::from("target/test_effort_include_deleted.csv")))
+            .include_deleted(true)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            effort_rows(args),
+            vec![
+                "README.md,2,2,2,2020-01-05,4,false",
+                "src/lib.rs,2,2,0,2020-01-06,8,true",
+                "src/main.rs,2,2,5,2020-01-02,5,false"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_effort_columns() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let args = EffortArgs::builder(String::from(path))
+            .file(Some(String::from("target/test_effort_columns.csv")))
+            .columns(Some(vec![String::from("loc"), String::from("file")]))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            effort_rows(args),
+            vec!["2,README.md", "4,src/lib.rs", "5,src/main.rs"]
+        );
+    }
+
+    #[test]
+    fn test_effort_trend() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let args = EffortArgs::builder(String::from(path))
+            .file(Some(String::from("target/test_effort_trend.csv")))
+            .trend(Some(SnapshotPeriod::Daily))
+            .build()
+            .unwrap();
+
+        // one column per day from 2020-01-01 to 2020-01-05
+        assert_eq!(
+            effort_rows(args),
+            vec![
+                "README.md,1,0,0,0,1",
+                "src/lib.rs,0,1,0,0,0",
+                "src/main.rs,1,1,0,0,0"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_effort_by_author() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let args = EffortArgs::builder(String::from(path))
+            .file(Some(String::from("target/test_effort_by_author.csv")))
+            .by_author(true)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            effort_rows(args),
+            vec![
+                "README.md,Alice,1,1",
+                "README.md,Bob,1,1",
+                "src/lib.rs,Bob,1,1",
+                "src/main.rs,Alice,2,2"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_effort_by_dir() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let args = EffortArgs::builder(String::from(path))
+            .file(Some(String::from("target/test_effort_by_dir.csv")))
+            .by_dir(Some(1))
+            .build()
+            .unwrap();
+
+        // main.rs and lib.rs share no commit, so src has three
+        assert_eq!(
+            effort_rows(args),
+            vec![".,2,2,2,2020-01-05", "src,3,2,9,2020-01-02"]
+        );
+    }
+
+    #[test]
+    fn test_effort_image() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+        let file = String::from("target/test_effort_image.svg");
+
+        let args = EffortArgs::builder(String::from(path))
+            .file(Some(file.clone()))
+            .top(Some(2))
+            .image(true)
+            .build()
+            .unwrap();
+
+        Effort::new(args).process().unwrap();
+
+        // lib.rs is folded into Others, which isn't charted
+        let svg = std::fs::read_to_string(file).unwrap();
+        assert!(svg.contains("src/main.rs"));
+        assert!(svg.contains("README.md"));
+        assert!(!svg.contains("src/lib.rs"));
+        assert!(!svg.contains(OTHERS_FILE));
+    }
+
+    #[test]
+    fn test_effort_min_loc_and_age() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let args = |file: &str, min_loc: Option<usize>, min_age_days: Option<usize>| {
+            EffortArgs::builder(String::from(path))
+                .file(Some(String::from(file)))
+                .min_loc(min_loc)
+                .min_age_days(min_age_days)
+                .build()
+                .unwrap()
+        };
+
+        // README.md has two lines
+        assert_eq!(
+            effort_rows(args("target/test_effort_min_loc.csv", Some(4), None)),
+            vec![
+                "src/lib.rs,1,1,4,2020-01-02",
+                "src/main.rs,2,2,5,2020-01-02"
+            ]
         );
 
-        let e = Effort::new(ea);
+        let effort = Effort::new(args("target/test_effort_min_age.csv", None, Some(36_500)));
+        let (results, dropped, _) = effort.effort_results().unwrap();
+        assert!(results.is_empty());
+        assert_eq!(dropped, 3);
+    }
+
+    fn test_output(file: &str, commits: &[&str], days: &[u32]) -> EffortOutput {
+        let mut output = EffortOutput::new(String::from(file));
+        output.commit_ids = commits.iter().map(|c| c.to_string()).collect();
+        output.dates = days
+            .iter()
+            .map(|d| NaiveDate::from_ymd_opt(2020, 3, *d).unwrap())
+            .collect();
+        output.calculate();
+        output
+    }
+
+    #[test]
+    fn test_collapse_output() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let args = EffortArgs::builder(String::from("."))
+            .top(Some(1))
+            .build()
+            .unwrap();
+
+        let effort = Effort::new(args);
+
+        let output = vec![
+            test_output("a.rs", &["1", "2", "3"], &[1, 2]),
+            test_output("b.rs", &["3", "4"], &[2, 3]),
+            test_output("c.rs", &["4"], &[3]),
+        ];
+
+        let result = effort.collapse_output(output);
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].file, "a.rs");
+        assert_eq!(result[1].file, OTHERS_FILE);
+        assert_eq!(result[1].commits, 2);
+        assert_eq!(result[1].active_days, 2);
+    }
+
+    #[test]
+    fn test_collapse_output_min_commits() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let args = EffortArgs::builder(String::from("."))
+            .min_commits(Some(2))
+            .build()
+            .unwrap();
+
+        let effort = Effort::new(args);
+
+        let output = vec![
+            test_output("a.rs", &["1", "2"], &[1]),
+            test_output("b.rs", &["3"], &[2]),
+        ];
+
+        let result = effort.collapse_output(output);
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].file, "a.rs");
+        assert_eq!(result[1].file, OTHERS_FILE);
+        assert_eq!(result[1].commits, 1);
+    }
+
+    #[test]
+    fn test_effort_include() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+        let ea = EffortArgs::builder(path.to_string())
+            .include(Some("src/*.rs".to_string()))
+            .file(Some(String::from("target/test_effort_include.csv")))
+            .build()
+            .unwrap();
 
-        let _result = e.process();
+        assert_eq!(
+            effort_rows(ea),
+            vec![
+                "src/lib.rs,1,1,4,2020-01-02",
+                "src/main.rs,2,2,5,2020-01-02"
+            ]
+        );
     }
 
     #[test]
     fn test_effort_restrict_author() {
         crate::grit_test::set_test_logging(LOG_LEVEL);
 
-        let td: TempDir = crate::grit_test::init_repo();
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+        let ea = EffortArgs::builder(path.to_string())
+            .restrict_authors(Some(String::from("Bob")))
+            .file(Some(String::from("target/test_effort_restrict_author.csv")))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            effort_rows(ea),
+            vec![
+                "README.md,1,1,2,2020-01-05",
+                "src/lib.rs,0,0,4,2020-01-02",
+                "src/main.rs,2,2,5,2020-01-02"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_effort_skips_files_missing_at_end_date() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
         let path = td.path().to_str().unwrap();
-        let ea = EffortArgs::new(
-            path.to_string(),
-            None,
-            None,
-            true,
-            None,
-            None,
-            Some(String::from("todd-bush-ln")),
+
+        let args = EffortArgs::builder(String::from(path))
+            .end_date(Some(NaiveDate::from_ymd_opt(2020, 1, 1).unwrap()))
+            .strict(true)
+            .file(Some(String::from("target/test_effort_skips_files.csv")))
+            .build()
+            .unwrap();
+
+        // lib.rs is first committed on 2020-01-02, so strict mode has nothing to fail on
+        assert_eq!(
+            effort_rows(args),
+            vec!["README.md,1,1,2,2020-01-01", "src/main.rs,1,1,3,2020-01-01"]
         );
+    }
 
-        let e = Effort::new(ea);
+    #[test]
+    fn test_effort_stream() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
 
-        let _result = e.process();
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+        let file = "target/test_effort_stream.csv";
+
+        let args = EffortArgs::builder(String::from(path))
+            .file(Some(String::from(file)))
+            .stream(true)
+            .build()
+            .unwrap();
+
+        Effort::new(args).process().unwrap();
+
+        let output = std::fs::read_to_string(file).unwrap();
+
+        assert_eq!(
+            output.lines().collect::<Vec<&str>>(),
+            vec![
+                "file,commits,active days,loc,last modified",
+                "README.md,2,2,2,2020-01-05",
+                "src/lib.rs,1,1,4,2020-01-02",
+                "src/main.rs,2,2,5,2020-01-02",
+                "",
+                "files,commits,active days,authors",
+                "3,4,3,2",
+            ]
+        );
     }
 }
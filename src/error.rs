@@ -0,0 +1,48 @@
+use std::io;
+use thiserror::Error;
+
+/// Errors expected from user input, reported as a message instead of a panic.
+#[derive(Debug, Error)]
+pub enum GritError {
+    #[error("invalid glob pattern '{pattern}': {message}")]
+    InvalidPattern { pattern: String, message: String },
+    #[error("invalid file name '{0}'")]
+    InvalidFileName(String),
+    #[error("no git repository found at '{0}'")]
+    RepoNotFound(String),
+    /// a path grit has to pass on as text
+    #[error("path '{0}' is not valid UTF-8")]
+    NonUtf8Path(String),
+    #[error("cannot write '{path}': {source}")]
+    Io { path: String, source: io::Error },
+    /// a `--fail-if-*` threshold was crossed
+    #[error("policy violated: {0}")]
+    PolicyViolation(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn test_grit_error_display() {
+        let e = GritError::InvalidPattern {
+            pattern: String::from("src/[*"),
+            message: String::from("invalid range pattern"),
+        };
+
+        assert_eq!(
+            e.to_string(),
+            "invalid glob pattern 'src/[*': invalid range pattern"
+        );
+
+        let e = GritError::Io {
+            path: String::from("out.html"),
+            source: io::Error::new(io::ErrorKind::NotFound, "missing"),
+        };
+
+        assert_eq!(e.to_string(), "cannot write 'out.html': missing");
+        assert!(e.source().is_some());
+    }
+}
@@ -1,92 +1,414 @@
 use super::Processable;
+use crate::author_stats::{AuthorStats, AuthorWalk};
+use crate::cache::Memo;
+use crate::cancel;
+use crate::diff_stats;
+use crate::identity;
+use crate::ignore_revs;
+use crate::language::LanguageMap;
+use crate::metric::{Commits, Files, Lines, Metrics, Observation};
+use crate::output;
+use crate::policy::Policy;
+use crate::progress::Progress;
+use crate::sample::{Estimate, Sample};
+use crate::table::Table;
+use crate::timing;
 use crate::utils::grit_utils;
-use anyhow::Result;
-use chrono::{Date, Local};
+use crate::utils::grit_utils::{CommitRange, OutputFormat, SkippedFile};
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
 use csv::Writer;
 use futures::future::join_all;
-use git2::{BlameOptions, Oid, Repository};
-use indicatif::ProgressBar;
-use prettytable::{cell, format, row, Table};
-use std::boxed::Box;
+use git2::{Oid, Repository};
+use std::cmp::Reverse;
 use std::collections::hash_map::Entry::{Occupied, Vacant};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::File;
-use std::io;
-use std::io::Write;
-use std::path::Path;
-use std::sync::{Arc, RwLock};
+use std::io::{BufRead, BufReader, Write};
+use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Instant;
-use tokio::runtime;
+use tokio::sync::Semaphore;
 use tokio::task::JoinHandle;
 
+const OTHERS_AUTHOR: &str = "Others";
+const BLAME_DATA_HEADER: &str = "# grit fame record v1";
+
+/// How fame credits lines to authors.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FameMode {
+    /// the lines each author last touched
+    Blame,
+    /// the lines each author's commits added and removed
+    Diff,
+}
+
+impl FromStr for FameMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "blame" => Ok(FameMode::Blame),
+            "diff" => Ok(FameMode::Diff),
+            _ => Err(anyhow!("mode must be either 'blame' or 'diff'")),
+        }
+    }
+}
+
+/// What fame counts as an author's commits.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CommitMetric {
+    /// the commits behind the author's blamed lines
+    Blame,
+    /// every commit the author made in the date range, overwritten or not
+    Revwalk,
+}
+
+impl FromStr for CommitMetric {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "blame" => Ok(CommitMetric::Blame),
+            "revwalk" => Ok(CommitMetric::Revwalk),
+            _ => Err(anyhow!("commit-metric must be either 'blame' or 'revwalk'")),
+        }
+    }
+}
+
 pub struct FameArgs {
     path: String,
     sort: Option<String>,
-    start_date: Option<Date<Local>>,
-    end_date: Option<Date<Local>>,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
     include: Option<String>,
     exclude: Option<String>,
     restrict_authors: Option<String>,
-    csv: bool,
+    format: OutputFormat,
     file: Option<String>,
+    top: Option<usize>,
+    min_lines: Option<i32>,
+    min_commits: Option<i32>,
+    at: Option<String>,
+    record: Option<String>,
+    replay: Option<String>,
+    strict: bool,
+    by_language: bool,
+    language_map: Option<String>,
+    subdir: Option<String>,
+    policy: Policy,
+    stream: bool,
+    mode: FameMode,
+    summary: bool,
+    commit_metric: CommitMetric,
+    columns: Option<Vec<String>>,
+    sample: Option<f64>,
+    former_names: bool,
+    extended: bool,
 }
 
 impl FameArgs {
-    pub fn new(
-        path: String,
-        sort: Option<String>,
-        start_date: Option<Date<Local>>,
-        end_date: Option<Date<Local>>,
-        include: Option<String>,
-        exclude: Option<String>,
-        restrict_authors: Option<String>,
-        csv: bool,
-        file: Option<String>,
-    ) -> FameArgs {
-        FameArgs {
-            path: path,
-            sort: sort,
-            start_date: start_date,
-            end_date: end_date,
-            include: include,
-            exclude: exclude,
-            restrict_authors: restrict_authors,
-            csv: csv,
-            file: file,
+    pub fn builder(path: String) -> FameArgsBuilder {
+        FameArgsBuilder {
+            args: FameArgs {
+                path,
+                sort: None,
+                start_date: None,
+                end_date: None,
+                include: None,
+                exclude: None,
+                restrict_authors: None,
+                format: OutputFormat::Csv,
+                file: None,
+                top: None,
+                min_lines: None,
+                min_commits: None,
+                at: None,
+                record: None,
+                replay: None,
+                strict: false,
+                by_language: false,
+                language_map: None,
+                subdir: None,
+                policy: Policy::default(),
+                stream: false,
+                mode: FameMode::Blame,
+                summary: false,
+                commit_metric: CommitMetric::Blame,
+                columns: None,
+                sample: None,
+                former_names: false,
+                extended: false,
+            },
         }
     }
 }
 
+/// Builds a `FameArgs` by name rather than position, validating it on `build`.
+pub struct FameArgsBuilder {
+    args: FameArgs,
+}
+
+impl FameArgsBuilder {
+    pub fn sort(mut self, sort: Option<String>) -> FameArgsBuilder {
+        self.args.sort = sort;
+        self
+    }
+
+    pub fn start_date(mut self, start_date: Option<NaiveDate>) -> FameArgsBuilder {
+        self.args.start_date = start_date;
+        self
+    }
+
+    pub fn end_date(mut self, end_date: Option<NaiveDate>) -> FameArgsBuilder {
+        self.args.end_date = end_date;
+        self
+    }
+
+    pub fn include(mut self, include: Option<String>) -> FameArgsBuilder {
+        self.args.include = include;
+        self
+    }
+
+    pub fn exclude(mut self, exclude: Option<String>) -> FameArgsBuilder {
+        self.args.exclude = exclude;
+        self
+    }
+
+    pub fn restrict_authors(mut self, restrict_authors: Option<String>) -> FameArgsBuilder {
+        self.args.restrict_authors = restrict_authors;
+        self
+    }
+
+    pub fn format(mut self, format: OutputFormat) -> FameArgsBuilder {
+        self.args.format = format;
+        self
+    }
+
+    pub fn file(mut self, file: Option<String>) -> FameArgsBuilder {
+        self.args.file = file;
+        self
+    }
+
+    pub fn top(mut self, top: Option<usize>) -> FameArgsBuilder {
+        self.args.top = top;
+        self
+    }
+
+    pub fn min_lines(mut self, min_lines: Option<i32>) -> FameArgsBuilder {
+        self.args.min_lines = min_lines;
+        self
+    }
+
+    pub fn min_commits(mut self, min_commits: Option<i32>) -> FameArgsBuilder {
+        self.args.min_commits = min_commits;
+        self
+    }
+
+    pub fn at(mut self, at: Option<String>) -> FameArgsBuilder {
+        self.args.at = at;
+        self
+    }
+
+    pub fn record(mut self, record: Option<String>) -> FameArgsBuilder {
+        self.args.record = record;
+        self
+    }
+
+    pub fn replay(mut self, replay: Option<String>) -> FameArgsBuilder {
+        self.args.replay = replay;
+        self
+    }
+
+    pub fn strict(mut self, strict: bool) -> FameArgsBuilder {
+        self.args.strict = strict;
+        self
+    }
+
+    pub fn by_language(mut self, by_language: bool) -> FameArgsBuilder {
+        self.args.by_language = by_language;
+        self
+    }
+
+    pub fn language_map(mut self, language_map: Option<String>) -> FameArgsBuilder {
+        self.args.language_map = language_map;
+        self
+    }
+
+    pub fn subdir(mut self, subdir: Option<String>) -> FameArgsBuilder {
+        self.args.subdir = subdir;
+        self
+    }
+
+    pub fn policy(mut self, policy: Policy) -> FameArgsBuilder {
+        self.args.policy = policy;
+        self
+    }
+
+    pub fn stream(mut self, stream: bool) -> FameArgsBuilder {
+        self.args.stream = stream;
+        self
+    }
+
+    pub fn mode(mut self, mode: FameMode) -> FameArgsBuilder {
+        self.args.mode = mode;
+        self
+    }
+
+    pub fn summary(mut self, summary: bool) -> FameArgsBuilder {
+        self.args.summary = summary;
+        self
+    }
+
+    pub fn commit_metric(mut self, commit_metric: CommitMetric) -> FameArgsBuilder {
+        self.args.commit_metric = commit_metric;
+        self
+    }
+
+    pub fn columns(mut self, columns: Option<Vec<String>>) -> FameArgsBuilder {
+        self.args.columns = columns;
+        self
+    }
+
+    pub fn sample(mut self, sample: Option<f64>) -> FameArgsBuilder {
+        self.args.sample = sample;
+        self
+    }
+
+    pub fn former_names(mut self, former_names: bool) -> FameArgsBuilder {
+        self.args.former_names = former_names;
+        self
+    }
+
+    pub fn extended(mut self, extended: bool) -> FameArgsBuilder {
+        self.args.extended = extended;
+        self
+    }
+
+    pub fn build(self) -> Result<FameArgs> {
+        grit_utils::validate_args(
+            self.args.start_date,
+            self.args.end_date,
+            self.args.file.as_deref(),
+            self.args.format,
+        )?;
+
+        Ok(self.args)
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
-struct BlameOutput {
-    author: String,
+pub(crate) struct BlameOutput {
+    pub(crate) author: String,
+    pub(crate) email: String,
     commit_id: String,
-    lines: i32,
-    file_name: String,
+    pub(crate) lines: i32,
+    pub(crate) file_name: String,
+    /// day the lines were authored, not kept in record files
+    pub(crate) date: Option<NaiveDate>,
+    /// the path the lines were written under, when the file has since been renamed.  Not
+    /// kept in record files.
+    pub(crate) former_name: Option<String>,
+    /// the lines are from before the range, blamed on the commit before it, and not counted
+    pub(crate) boundary: bool,
 }
 
 impl BlameOutput {
-    fn new(author: String, commit_id: String, file_name: String) -> BlameOutput {
+    fn new(author: String, email: String, commit_id: String, file_name: String) -> BlameOutput {
         BlameOutput {
-            author: author,
-            commit_id: commit_id,
+            author,
+            email,
+            commit_id,
             lines: 0,
-            file_name: file_name,
+            file_name,
+            date: None,
+            former_name: None,
+            boundary: false,
+        }
+    }
+}
+
+/// How concentrated the LOC is among the authors, appended to the fame rows with --summary.
+#[derive(Clone, Debug, PartialEq)]
+struct FameSummary {
+    /// 0 when every author owns the same LOC, approaching 1 when one owns it all
+    gini: f64,
+    /// percent of the LOC owned by the 3 biggest authors
+    top_3_share: f64,
+    mean: f64,
+    median: f64,
+}
+
+impl FameSummary {
+    fn new(lines: &[i32]) -> FameSummary {
+        let mut sorted: Vec<f64> = lines.iter().map(|l| *l as f64).collect();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let n = sorted.len() as f64;
+        let total: f64 = sorted.iter().sum();
+
+        if sorted.is_empty() || total <= 0.0 {
+            return FameSummary {
+                gini: 0.0,
+                top_3_share: 0.0,
+                mean: 0.0,
+                median: 0.0,
+            };
+        }
+
+        let weighted: f64 = sorted
+            .iter()
+            .enumerate()
+            .map(|(i, l)| (i + 1) as f64 * l)
+            .sum();
+
+        let mid = sorted.len() / 2;
+        let median = if mid * 2 == sorted.len() {
+            (sorted[mid - 1] + sorted[mid]) / 2.0
+        } else {
+            sorted[mid]
+        };
+
+        FameSummary {
+            gini: 2.0 * weighted / (n * total) - (n + 1.0) / n,
+            top_3_share: sorted.iter().rev().take(3).sum::<f64>() / total * 100.0,
+            mean: total / n,
+            median,
         }
     }
+
+    /// a row per measure, its label first and its value in the LOC column
+    fn rows(&self, columns: usize) -> Vec<Vec<String>> {
+        [
+            ("Gini coefficient (LOC)", format!("{:.3}", self.gini)),
+            ("Top 3 share (% LOC)", output::percent(self.top_3_share)),
+            ("Mean LOC per author", format!("{:.1}", self.mean)),
+            ("Median LOC per author", format!("{:.1}", self.median)),
+        ]
+        .iter()
+        .map(|(label, value)| {
+            let mut row = vec![String::new(); columns];
+            row[0] = label.to_string();
+            row[3] = value.clone();
+            row
+        })
+        .collect()
+    }
 }
 
 #[derive(Clone)]
-struct FameOutputLine {
-    author: String,
-    lines: i32,
-    file_count: usize,
+pub(crate) struct FameOutputLine {
+    pub(crate) author: String,
+    pub(crate) lines: i32,
+    pub(crate) file_count: usize,
     filenames: HashSet<String>,
     commits: HashSet<String>,
-    commits_count: i32,
-    perc_lines: f64,
+    pub(crate) commits_count: i32,
+    pub(crate) perc_lines: f64,
     perc_files: f64,
     perc_commits: f64,
+    /// the author's commit days, only walked for --extended
+    activity: AuthorStats,
 }
 
 impl FameOutputLine {
@@ -101,14 +423,64 @@ impl FameOutputLine {
             perc_files: 0.0,
             perc_lines: 0.0,
             perc_commits: 0.0,
+            activity: AuthorStats::default(),
+        }
+    }
+
+    /// a line from running totals, without the file and commit sets behind them
+    fn with_counts(
+        author: String,
+        lines: i32,
+        file_count: usize,
+        commits_count: i32,
+        max_files: usize,
+        max_commits: usize,
+        max_lines: i32,
+    ) -> FameOutputLine {
+        FameOutputLine {
+            author,
+            lines,
+            file_count,
+            commits_count,
+            perc_files: file_count as f64 / max_files.max(1) as f64,
+            perc_commits: commits_count as f64 / max_commits.max(1) as f64,
+            perc_lines: lines as f64 / max_lines.max(1) as f64,
+            ..FameOutputLine::new()
         }
     }
+
+    fn calculate(&mut self, max_files: usize, max_commits: usize, max_lines: i32) {
+        self.commits_count = self.commits.len() as i32;
+        self.file_count = self.filenames.len();
+        self.perc_files = (self.file_count) as f64 / (max_files) as f64;
+        self.perc_commits = (self.commits_count) as f64 / (max_commits) as f64;
+        self.perc_lines = (self.lines) as f64 / (max_lines) as f64;
+    }
+}
+
+/// The lines an author's commits added and removed, for `FameMode::Diff`.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct DiffOutputLine {
+    author: String,
+    commits: i32,
+    added: usize,
+    removed: usize,
+}
+
+impl DiffOutputLine {
+    fn net(&self) -> i64 {
+        self.added as i64 - self.removed as i64
+    }
 }
 
 pub struct Fame {
     args: FameArgs,
 }
 
+type BlameKey = (String, String, Option<Vec<u8>>, Option<Vec<u8>>);
+
+static BLAMES: Memo<BlameKey, Vec<BlameOutput>> = Memo::new();
+
 #[derive(Clone)]
 struct BlameProcessor {
     path: String,
@@ -123,470 +495,2025 @@ impl BlameProcessor {
         latest_commit: Option<Vec<u8>>,
     ) -> BlameProcessor {
         BlameProcessor {
-            path: path,
-            earliest_commit: earliest_commit,
-            latest_commit: latest_commit,
+            path,
+            earliest_commit,
+            latest_commit,
         }
     }
 
     async fn process(&self, file_name: String) -> Result<Vec<BlameOutput>> {
-        let repo = Repository::open(&self.path)?;
-        let file_path = Path::new(&file_name);
-        let start = Instant::now();
+        let key = (
+            self.path.clone(),
+            file_name.clone(),
+            self.earliest_commit.clone(),
+            self.latest_commit.clone(),
+        );
 
-        let mut bo = BlameOptions::new();
+        BLAMES.get_or_try(key, || {
+            grit_utils::with_thread_repo(&self.path, |repo| self.blame(repo, file_name))
+        })
+    }
 
-        if let Some(ev) = &self.earliest_commit {
-            let oid: Oid = Oid::from_bytes(&ev)?;
-            bo.oldest_commit(oid);
-        };
+    fn blame(&self, repo: &Repository, file_name: String) -> Result<Vec<BlameOutput>> {
+        let file_path = grit_utils::git_path(&file_name);
+        let start = Instant::now();
 
-        if let Some(ov) = &self.latest_commit {
-            let oid: Oid = Oid::from_bytes(&ov)?;
-            bo.newest_commit(oid);
+        let earliest = self
+            .earliest_commit
+            .as_deref()
+            .map(Oid::from_bytes)
+            .transpose()?;
+        let latest = self
+            .latest_commit
+            .as_deref()
+            .map(Oid::from_bytes)
+            .transpose()?;
+
+        // blamed from the commit before the range, the lines libgit2 takes as the boundary
+        // are all from before it, rather than mixed in with the earliest commit's own
+        let oldest = match earliest {
+            Some(oid) => repo.find_commit(oid)?.parent_id(0).ok(),
+            None => None,
         };
 
-        let blame = repo.blame_file(file_path, Some(&mut bo))?;
+        let blame = ignore_revs::blame_file(repo, &file_path, oldest, latest)?;
 
         let mut blame_map: HashMap<String, BlameOutput> = HashMap::new();
 
-        for hunk in blame.iter() {
-            let sig = hunk.final_signature();
+        for b in blame.iter() {
+            let sig = &b.signature;
             let signame = String::from_utf8_lossy(sig.name_bytes()).to_string();
-            let f_commit = hunk.final_commit_id().to_string();
+            let sigemail = String::from_utf8_lossy(sig.email_bytes()).to_string();
+            let f_commit = b.commit_id.to_string();
             let blame_key = &[&signame, "-", &f_commit].join("");
 
             let v = match blame_map.entry(blame_key.to_string()) {
-                Vacant(entry) => {
-                    entry.insert(BlameOutput::new(signame, f_commit, file_name.clone()))
-                }
+                Vacant(entry) => entry.insert(BlameOutput::new(
+                    signame,
+                    sigemail,
+                    f_commit,
+                    file_name.clone(),
+                )),
                 Occupied(entry) => entry.into_mut(),
             };
 
-            v.lines += hunk.lines_in_hunk() as i32;
+            v.lines += b.lines as i32;
+            v.boundary = b.boundary;
+            v.date = Some(grit_utils::convert_git_time(&sig.when()));
+            if b.path != file_path {
+                v.former_name = Some(b.path.to_string_lossy().to_string());
+            }
         }
 
         let result: Vec<BlameOutput> = blame_map.values().cloned().collect();
 
-        info!("Processed {} in {:?}", &file_name, start.elapsed());
+        timing::record_file(&file_name, start.elapsed());
 
         Ok(result)
     }
 }
 
-impl Fame {
-    pub fn new(args: FameArgs) -> Self {
-        Fame { args: args }
-    }
-
-    fn pretty_print_table(
-        &self,
-        output: Vec<FameOutputLine>,
-        tot_loc: i32,
-        tot_files: usize,
-        tot_commits: usize,
-    ) -> Result<()> {
-        println!("Stats on Repo");
-        println!("Total files: {}", tot_files);
-        println!("Total commits: {}", tot_commits);
-        println!("Total LOC: {}", tot_loc);
-
-        let mut table = Table::new();
-
-        table.set_titles(row![
-            "Author",
-            "Files",
-            "Commits",
-            "LOC",
-            "Distribution (%)"
-        ]);
-
-        for o in output.iter() {
-            let pf = format!("{:.1}", o.perc_files * 100.0);
-            let pc = format!("{:.1}", o.perc_commits * 100.0);
-            let pl = format!("{:.1}", o.perc_lines * 100.0);
-            let s = format!(
-                "{pf:<width$} / {pc:<width$} / {pl:<width$}",
-                pf = pf,
-                pc = pc,
-                pl = pl,
-                width = 5
-            );
-
-            table.add_row(row![o.author, o.file_count, o.commits_count, o.lines, s]);
-        }
-
-        table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
-        table.printstd();
-
-        Ok(())
-    }
-
-    fn csv_output(&self, output: Vec<FameOutputLine>, file_name: Option<String>) -> Result<()> {
-        let w = match file_name {
-            Some(f) => {
-                let file = File::create(f)?;
-                Box::new(file) as Box<dyn Write>
-            }
-            None => Box::new(io::stdout()) as Box<dyn Write>,
-        };
-
-        let mut wrt = Writer::from_writer(w);
-
-        wrt.write_record(&[
-            "Author",
-            "Files",
-            "Commits",
-            "LOC",
-            "Distribution (%) - Files",
-            "Distribution (%) - Commits",
-            "Distribution (%) - LoC",
-        ])
-        .expect("Cannot write header row");
-
-        output.iter().for_each(|r| {
-            let pf = format!("{:.1}", r.perc_files * 100.0);
-            let pc = format!("{:.1}", r.perc_commits * 100.0);
-            let pl = format!("{:.1}", r.perc_lines * 100.0);
-
-            wrt.serialize([
-                r.author.clone(),
-                r.file_count.to_string(),
-                r.commits_count.to_string(),
-                r.lines.to_string(),
-                pf,
-                pc,
-                pl,
-            ])
-            .expect("Could not write CSV row");
-        });
-
-        wrt.flush().expect("Cannot flush CVS buffer");
-
-        Ok(())
-    }
+/// the blame of each file, the files that could not be blamed and the `--sample` of the
+/// repo's files they are
+type BlamedRepo = (Vec<Vec<BlameOutput>>, Vec<SkippedFile>, Option<Sample>);
+
+/// totals for every author blamed over `file_names` between the two commits
+pub(crate) struct FameSnapshot {
+    pub(crate) output: Vec<FameOutputLine>,
+    pub(crate) max_files: usize,
+    pub(crate) max_commits: usize,
+    pub(crate) max_lines: i32,
+    pub(crate) skipped: Vec<SkippedFile>,
 }
 
-impl Processable<()> for Fame {
-    fn process(&self) -> Result<()> {
-        let (earliest_commit, latest_commit) = grit_utils::find_commit_range(
-            &self.args.path,
-            self.args.start_date,
-            self.args.end_date,
-        )?;
-
-        info!("Early, Late: {:?}, {:?}", earliest_commit, latest_commit);
-
-        let restrict_authors: Option<Vec<String>> =
-            grit_utils::convert_string_list_to_vec(self.args.restrict_authors.clone());
-
-        let file_names: Vec<String> = grit_utils::generate_file_list(
-            &self.args.path,
-            self.args.include.clone(),
-            self.args.exclude.clone(),
-        )?;
-
-        let bp = BlameProcessor::new(
-            self.args.path.clone(),
-            earliest_commit.clone(),
-            latest_commit.clone(),
-        );
+/// blames every file in `file_names` concurrently, one entry per author and commit in each file,
+/// along with the files that could not be blamed
+pub(crate) fn blame_files(
+    path: &str,
+    file_names: &[String],
+    earliest_commit: Option<Vec<u8>>,
+    latest_commit: Option<Vec<u8>>,
+) -> Result<(Vec<Vec<BlameOutput>>, Vec<SkippedFile>)> {
+    let bp = BlameProcessor::new(path.to_string(), earliest_commit, latest_commit);
 
-        let pgb = ProgressBar::new(file_names.len() as u64);
-        let arc_pgb = Arc::new(RwLock::new(pgb));
+    let arc_pgb = Arc::new(Progress::new("blamed", file_names.len()));
+    let in_flight = Arc::new(Semaphore::new(grit_utils::MAX_IN_FLIGHT_FILES));
+    cancel::install_handler();
 
-        let mut rt = runtime::Builder::new()
-            .threaded_scheduler()
-            .thread_name("grit-fame-thread-runner")
-            .build()
-            .expect("Failed to create threadpool.");
+    timing::phase("blame", || {
+        let rt = grit_utils::runtime("grit-fame-thread-runner")?;
 
-        let mut tasks: Vec<JoinHandle<Result<Vec<BlameOutput>, ()>>> = vec![];
+        let mut tasks: Vec<JoinHandle<Result<Vec<BlameOutput>, SkippedFile>>> = vec![];
 
         for file_name in file_names.iter() {
+            if cancel::is_cancelled() {
+                break;
+            }
+
+            // waits for a blame to finish once the most allowed are running
+            let permit = rt.block_on(in_flight.clone().acquire_owned())?;
             let file_name = file_name.clone();
             let bp = bp.clone();
             let arc_pgb_c = arc_pgb.clone();
 
             info!("processing file {}", file_name);
             tasks.push(rt.spawn(async move {
+                let _permit = permit;
                 bp.process(String::from(&file_name))
                     .await
-                    .map(|pr| {
-                        &arc_pgb_c
-                            .write()
-                            .expect("cannot open progress bar for write")
-                            .inc(1);
-                        pr
+                    .inspect(|_pr| arc_pgb_c.inc())
+                    .map_err(|err| {
+                        error!("Error in processing file {}: {}", file_name, err);
+                        SkippedFile::new(&file_name, err.to_string())
                     })
-                    .map_err(|err| error!("Error in processing file: {}", err))
             }));
         }
 
         let jh_results = rt.block_on(join_all(tasks));
 
-        arc_pgb
-            .write()
-            .expect("cannot open progress bar for write")
-            .finish();
-
-        let collector: Vec<Vec<BlameOutput>> = jh_results
-            .into_iter()
-            .map(|jh| jh.unwrap().unwrap().clone())
-            .collect();
+        if jh_results.len() < file_names.len() {
+            arc_pgb.finish_and_clear();
+            cancel::warn_partial();
+        } else {
+            arc_pgb.finish();
+        }
 
-        let max_files = collector.len();
+        let mut collector: Vec<Vec<BlameOutput>> = Vec::new();
+        let mut skipped: Vec<SkippedFile> = Vec::new();
 
-        let blame_outputs: Vec<BlameOutput> = collector.into_iter().flatten().collect();
+        for (file_name, jh) in file_names.iter().zip(jh_results) {
+            match jh {
+                Ok(Ok(r)) => collector.push(r),
+                Ok(Err(s)) => skipped.push(s),
+                Err(e) => skipped.push(SkippedFile::new(file_name, e.to_string())),
+            }
+        }
 
-        let mut max_lines = 0;
-        let mut output_map: HashMap<String, FameOutputLine> = HashMap::new();
-        let mut total_commits: HashSet<String> = HashSet::new();
+        Ok((collector, skipped))
+    })
+}
 
-        for v in blame_outputs.iter() {
-            if let Some(ra) = &restrict_authors {
-                if ra.contains(&v.author) {
-                    break;
-                }
-            }
+/// blames every file in `file_names` and aggregates the results per author
+pub(crate) fn fame_snapshot(
+    path: &str,
+    file_names: &[String],
+    earliest_commit: Option<Vec<u8>>,
+    latest_commit: Option<Vec<u8>>,
+    restrict_authors: Option<Vec<String>>,
+) -> Result<FameSnapshot> {
+    let (collector, skipped) = blame_files(path, file_names, earliest_commit, latest_commit)?;
 
-            let om = match output_map.entry(v.author.clone()) {
-                Vacant(entry) => entry.insert(FameOutputLine::new()),
-                Occupied(entry) => entry.into_mut(),
-            };
+    let mut snapshot = aggregate_blame(collector, restrict_authors);
+    snapshot.skipped = skipped;
 
-            om.commits.insert(v.commit_id.clone());
-            total_commits.insert(v.commit_id.clone());
-            om.filenames.insert(v.file_name.clone());
-            om.lines += v.lines;
-            max_lines += v.lines;
-        }
+    Ok(snapshot)
+}
 
-        let max_commits = total_commits.len();
+/// rolls the per file blame results up per author
+fn aggregate_blame(
+    collector: Vec<Vec<BlameOutput>>,
+    restrict_authors: Option<Vec<String>>,
+) -> FameSnapshot {
+    let max_files = collector.len();
 
-        info!(
-            "Max files/commits/lines: {} {} {}",
-            max_files, max_commits, max_lines
-        );
+    let blame_outputs: Vec<BlameOutput> = collector.into_iter().flatten().collect();
 
-        let mut output: Vec<FameOutputLine> = output_map
-            .iter_mut()
-            .map(|(key, val)| {
-                val.commits_count = val.commits.len() as i32;
-                val.file_count = val.filenames.len();
-                val.author = String::from(key);
-                val.perc_files = (val.file_count) as f64 / (max_files) as f64;
-                val.perc_commits = (val.commits_count) as f64 / (max_commits) as f64;
-                val.perc_lines = (val.lines) as f64 / (max_lines) as f64;
-                val.clone()
-            })
-            .collect();
+    let mut metrics: Metrics<String> = Metrics::new()
+        .with(Lines::default())
+        .with(Commits::default())
+        .with(Files::default());
 
-        match self.args.sort {
-            Some(ref x) if x == "loc" => output.sort_by(|a, b| b.lines.cmp(&a.lines)),
-            Some(ref x) if x == "files" => output.sort_by(|a, b| b.file_count.cmp(&a.file_count)),
-            _ => output.sort_by(|a, b| b.commits_count.cmp(&a.commits_count)),
+    for v in blame_outputs.iter().filter(|v| !v.boundary) {
+        if let Some(ra) = &restrict_authors {
+            if ra.contains(&v.author) {
+                continue;
+            }
         }
 
-        if self.args.csv {
-            self.csv_output(output, self.args.file.clone())?;
-        } else {
-            self.pretty_print_table(output, max_lines, max_files, max_commits)?;
+        if identity::is_excluded(&v.author, &v.email) {
+            continue;
         }
 
-        Ok(())
+        let mut o = Observation::new(identity::display_author(&v.author, &v.email), &v.commit_id);
+        o.day = v.date;
+        o.file = Some(&v.file_name);
+        o.lines = v.lines as usize;
+
+        metrics.observe(&o);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use chrono::{Duration, NaiveDate, TimeZone};
-    use log::LevelFilter;
-    use std::ops::Add;
-    use tempfile::TempDir;
+    let max_commits = metrics.total("commits");
+    let max_lines = metrics.total("lines") as i32;
+
+    info!(
+        "Max files/commits/lines: {} {} {}",
+        max_files, max_commits, max_lines
+    );
+
+    let commits = metrics.get::<Commits<String>>().unwrap();
+    let files = metrics.get::<Files<String>>().unwrap();
+
+    let output: Vec<FameOutputLine> = metrics
+        .keys()
+        .map(|key| {
+            let mut line = FameOutputLine::new();
+            line.author = String::from(key);
+            line.lines = metrics.value("lines", key) as i32;
+            line.commits = commits.ids(key).cloned().unwrap_or_default();
+            line.filenames = files.names(key).cloned().unwrap_or_default();
+            line.calculate(max_files, max_commits, max_lines);
+            line
+        })
+        .collect();
+
+    FameSnapshot {
+        output,
+        max_files,
+        max_commits,
+        max_lines,
+        skipped: Vec::new(),
+    }
+}
 
-    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+/// An author's running totals while streaming.
+#[derive(Default)]
+struct StreamTotal {
+    lines: i32,
+    files: usize,
+    commits: HashSet<String>,
+}
 
-    #[test]
-    fn test_process_fame() {
-        crate::grit_test::set_test_logging(LOG_LEVEL);
+#[derive(Clone, Debug, PartialEq)]
+struct LanguageLine {
+    author: String,
+    lines: HashMap<String, i32>,
+    total: i32,
+}
 
-        let td: TempDir = crate::grit_test::init_repo();
-        let path = td.path().to_str().unwrap();
+/// totals the blamed lines per author and language, both ordered by LOC
+fn language_matrix(
+    collector: &[Vec<BlameOutput>],
+    restrict_authors: &Option<Vec<String>>,
+    languages: &LanguageMap,
+) -> (Vec<String>, Vec<LanguageLine>) {
+    let mut author_map: HashMap<String, LanguageLine> = HashMap::new();
+    let mut language_totals: HashMap<String, i32> = HashMap::new();
+
+    for v in collector.iter().flatten() {
+        if let Some(ra) = restrict_authors {
+            if ra.contains(&v.author) {
+                continue;
+            }
+        }
 
-        let args = FameArgs::new(
-            String::from(path),
-            Some("loc".to_string()),
-            None,
-            None,
-            None,
-            None,
-            None,
-            false,
-            None,
-        );
+        if v.lines == 0 || v.boundary || identity::is_excluded(&v.author, &v.email) {
+            continue;
+        }
 
-        let f = Fame::new(args);
+        let author = identity::display_author(&v.author, &v.email);
+        let language = languages.language(&v.file_name);
 
-        let result = match f.process() {
-            Ok(()) => true,
-            Err(_t) => false,
-        };
+        let line = author_map
+            .entry(author.clone())
+            .or_insert_with(|| LanguageLine {
+                author,
+                lines: HashMap::new(),
+                total: 0,
+            });
 
-        assert!(result, "test_process_file result was {}", result);
+        *line.lines.entry(language.clone()).or_insert(0) += v.lines;
+        line.total += v.lines;
+        *language_totals.entry(language).or_insert(0) += v.lines;
     }
 
-    #[test]
-    fn test_process_fame_start_date() {
-        crate::grit_test::set_test_logging(LOG_LEVEL);
+    let mut language_names: Vec<(String, i32)> = language_totals.into_iter().collect();
+    language_names.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
 
-        let td: TempDir = crate::grit_test::init_repo();
-        let path = td.path().to_str().unwrap();
+    let mut output: Vec<LanguageLine> = author_map.into_values().collect();
+    output.sort_by(|a, b| b.total.cmp(&a.total).then(a.author.cmp(&b.author)));
 
-        let utc_dt = NaiveDate::parse_from_str("2020-03-26", "%Y-%m-%d").unwrap();
+    (language_names.into_iter().map(|(l, _)| l).collect(), output)
+}
 
-        let ed = Local.from_local_date(&utc_dt).single().unwrap();
+/// saves the raw blame results so a later run can `--replay` them without blaming again.
+/// Lines from before the range are left out, and files that blamed to nothing else get a
+/// row with no author so they still count as files.
+pub(crate) fn write_blame_data(file_name: &str, collector: &[Vec<BlameOutput>]) -> Result<()> {
+    let mut file = File::create(file_name)?;
+    writeln!(file, "{}", BLAME_DATA_HEADER)?;
 
-        let args = FameArgs::new(
-            path.to_string(),
-            Some("loc".to_string()),
-            Some(ed),
-            None,
-            None,
-            None,
-            None,
+    let mut wrt = Writer::from_writer(file);
+
+    wrt.write_record(["file index", "file", "author", "email", "commit", "lines"])?;
+
+    for (i, outputs) in collector.iter().enumerate() {
+        if outputs.iter().all(|o| o.boundary) {
+            wrt.serialize((i, "", "", "", "", 0))?;
+        }
+
+        for o in outputs.iter().filter(|o| !o.boundary) {
+            wrt.serialize((
+                i,
+                o.file_name.clone(),
+                o.author.clone(),
+                o.email.clone(),
+                o.commit_id.clone(),
+                o.lines,
+            ))?;
+        }
+    }
+
+    wrt.flush()?;
+
+    Ok(())
+}
+
+/// reads the blame results saved by `write_blame_data`, grouped per file
+pub(crate) fn read_blame_data(file_name: &str) -> Result<Vec<Vec<BlameOutput>>> {
+    let mut reader = BufReader::new(File::open(file_name)?);
+
+    let mut header = String::new();
+    reader.read_line(&mut header)?;
+
+    if header.trim_end() != BLAME_DATA_HEADER {
+        return Err(anyhow!("{} is not a grit record file", file_name));
+    }
+
+    let mut rdr = csv::Reader::from_reader(reader);
+
+    let mut collector: Vec<Vec<BlameOutput>> = Vec::new();
+    let mut last_file: Option<String> = None;
+
+    for record in rdr.records() {
+        let record = record?;
+        let file_index = record.get(0).unwrap_or_default().to_string();
+
+        if last_file.as_ref() != Some(&file_index) {
+            collector.push(Vec::new());
+            last_file = Some(file_index);
+        }
+
+        let author = record.get(2).unwrap_or_default().to_string();
+
+        if author.is_empty() {
+            continue;
+        }
+
+        let mut bo = BlameOutput::new(
+            author,
+            record.get(3).unwrap_or_default().to_string(),
+            record.get(4).unwrap_or_default().to_string(),
+            record.get(1).unwrap_or_default().to_string(),
+        );
+        bo.lines = record.get(5).unwrap_or_default().parse()?;
+
+        if let Some(outputs) = collector.last_mut() {
+            outputs.push(bo);
+        }
+    }
+
+    Ok(collector)
+}
+
+impl Fame {
+    pub fn new(args: FameArgs) -> Self {
+        Fame { args }
+    }
+
+    /// the totals heading the table, and the estimate when only a sample was blamed
+    fn print_totals(
+        w: &mut dyn Write,
+        tot_loc: i32,
+        tot_files: usize,
+        tot_commits: usize,
+        estimate: Option<(Sample, Estimate)>,
+    ) -> Result<()> {
+        writeln!(w, "Stats on Repo")?;
+        writeln!(
+            w,
+            "Total files: {}",
+            output::table_number(&tot_files.to_string())
+        )?;
+        writeln!(
+            w,
+            "Total commits: {}",
+            output::table_number(&tot_commits.to_string())
+        )?;
+        writeln!(
+            w,
+            "Total LOC: {}",
+            output::table_number(&tot_loc.to_string())
+        )?;
+
+        if let Some((sample, loc)) = estimate {
+            writeln!(w, "Totals of the {}", sample)?;
+            writeln!(w, "Estimated repo LOC: {} (95% confidence)", loc)?;
+        }
+
+        Ok(())
+    }
+
+    fn pretty_print_table(
+        &self,
+        w: &mut dyn Write,
+        output: Vec<FameOutputLine>,
+        summary: Option<&FameSummary>,
+    ) -> Result<()> {
+        let mut table = Table::new();
+
+        let mut titles = table_row![
+            identity::author_column().0,
+            "Files",
+            "Commits",
+            "LOC",
+            "Distribution (%)"
+        ];
+        titles.extend(self.extended_titles());
+        let columns = titles.len();
+        table.set_titles(titles);
+
+        for o in output.iter() {
+            let pf = output::percent(o.perc_files * 100.0);
+            let pc = output::percent(o.perc_commits * 100.0);
+            let pl = output::percent(o.perc_lines * 100.0);
+            let s = format!(
+                "{pf:<width$} / {pc:<width$} / {pl:<width$}",
+                pf = pf,
+                pc = pc,
+                pl = pl,
+                width = 5
+            );
+
+            let mut row = table_row![o.author, o.file_count, o.commits_count, o.lines, s];
+            row.extend(self.extended_cells(o));
+            table.add_row(row);
+        }
+
+        for row in summary.map(|s| s.rows(columns)).unwrap_or_default() {
+            table.add_row(row);
+        }
+
+        self.select_columns(&mut table)?;
+        table.print(w)?;
+
+        Ok(())
+    }
+
+    /// keeps the first `top` authors meeting the line and commit thresholds and
+    /// folds everyone else into a single "Others" row.
+    fn collapse_output(
+        &self,
+        output: Vec<FameOutputLine>,
+        max_files: usize,
+        max_commits: usize,
+        max_lines: i32,
+    ) -> Vec<FameOutputLine> {
+        let top = self.args.top.unwrap_or(usize::MAX);
+        let min_lines = self.args.min_lines.unwrap_or(0);
+        let min_commits = self.args.min_commits.unwrap_or(0);
+
+        let mut result: Vec<FameOutputLine> = Vec::new();
+        let mut others = FameOutputLine::new();
+        let mut collapsed = 0;
+
+        for o in output.into_iter() {
+            if result.len() < top && o.lines >= min_lines && o.commits_count >= min_commits {
+                result.push(o);
+            } else {
+                others.lines += o.lines;
+                others.commits.extend(o.commits);
+                others.filenames.extend(o.filenames);
+                others.activity.merge(o.activity);
+                collapsed += 1;
+            }
+        }
+
+        if collapsed > 0 {
+            others.author = String::from(OTHERS_AUTHOR);
+            others.calculate(max_files, max_commits, max_lines);
+            result.push(others);
+        }
+
+        result
+    }
+
+    /// the files to blame and the commit range to blame them over, picked by the date
+    /// range, revision and include/exclude args
+    fn blame_targets(&self) -> Result<(Vec<String>, CommitRange)> {
+        let mut range = grit_utils::find_commit_range(
+            &self.args.path,
+            self.args.start_date,
+            self.args.end_date,
+        )?;
+
+        // without bounds the whole history would be blamed
+        if range.is_empty() {
+            return Ok((Vec::new(), range));
+        }
+
+        let at_commit = match &self.args.at {
+            Some(rev) => Some(grit_utils::resolve_rev(&self.args.path, rev)?),
+            None => None,
+        };
+
+        range.latest = at_commit.clone().or(range.latest);
+
+        info!("Early, Late: {:?}, {:?}", range.earliest, range.latest);
+
+        let subdir = match &self.args.subdir {
+            Some(d) => Some(grit_utils::subdir_path(
+                &self.args.path,
+                at_commit.as_deref(),
+                d,
+            )?),
+            None => None,
+        };
+
+        let mut file_names: Vec<String> = match &at_commit {
+            Some(c) => grit_utils::generate_file_list_at(
+                &self.args.path,
+                c,
+                self.args.include.clone(),
+                self.args.exclude.clone(),
+            )?,
+            None => grit_utils::generate_file_list(
+                &self.args.path,
+                self.args.include.clone(),
+                self.args.exclude.clone(),
+            )?,
+        };
+
+        if let Some(d) = subdir {
+            let prefix = format!("{}/", d);
+            file_names.retain(|f| f.starts_with(&prefix));
+        }
+
+        Ok((file_names, range))
+    }
+
+    /// blames the files picked by the date range, revision and include/exclude args, or
+    /// the `--sample` of them
+    fn blame_repo(&self) -> Result<BlamedRepo> {
+        let (file_names, range) = self.blame_targets()?;
+
+        let (sample, file_names) = match self.args.sample {
+            Some(p) => {
+                let (sample, picked) = Sample::files(file_names, p);
+                (Some(sample), picked)
+            }
+            None => (None, file_names),
+        };
+
+        let (collector, skipped) =
+            blame_files(&self.args.path, &file_names, range.earliest, range.latest)?;
+
+        Ok((collector, skipped, sample))
+    }
+
+    /// blames the files `STREAM_CHUNK_FILES` at a time, writing each author's lines per file
+    /// as csv once its chunk is done, then the per author summary.  Only running totals are
+    /// kept between chunks.  Returns the lines of every author.
+    fn stream_output(
+        &self,
+        w: &mut dyn Write,
+        restrict_authors: &Option<Vec<String>>,
+    ) -> Result<Vec<(String, i32)>> {
+        let (file_names, range) = self.blame_targets()?;
+
+        let mut totals: HashMap<String, StreamTotal> = HashMap::new();
+        let mut total_commits: HashSet<String> = HashSet::new();
+        let mut skipped: Vec<SkippedFile> = Vec::new();
+        let mut max_files = 0;
+        let mut max_lines = 0;
+
+        {
+            let mut wrt = Writer::from_writer(&mut *w);
+            wrt.write_record(["File", identity::author_column().0, "LOC", "Commits"])?;
+
+            for chunk in file_names.chunks(grit_utils::STREAM_CHUNK_FILES) {
+                if cancel::is_cancelled() {
+                    break;
+                }
+
+                let (collector, chunk_skipped) = blame_files(
+                    &self.args.path,
+                    chunk,
+                    range.earliest.clone(),
+                    range.latest.clone(),
+                )?;
+
+                skipped.extend(chunk_skipped);
+                max_files += collector.len();
+
+                for outputs in collector.iter() {
+                    let mut authors: BTreeMap<String, StreamTotal> = BTreeMap::new();
+
+                    for o in outputs.iter().filter(|o| !o.boundary) {
+                        if restrict_authors
+                            .as_ref()
+                            .map(|ra| ra.contains(&o.author))
+                            .unwrap_or(false)
+                            || identity::is_excluded(&o.author, &o.email)
+                        {
+                            continue;
+                        }
+
+                        let t = authors
+                            .entry(identity::display_author(&o.author, &o.email))
+                            .or_default();
+                        t.lines += o.lines;
+                        t.commits.insert(o.commit_id.clone());
+                        total_commits.insert(o.commit_id.clone());
+                        max_lines += o.lines;
+                    }
+
+                    for (author, t) in authors.into_iter() {
+                        wrt.serialize((&outputs[0].file_name, &author, t.lines, t.commits.len()))?;
+
+                        let total = totals.entry(author).or_default();
+                        total.lines += t.lines;
+                        total.files += 1;
+                        total.commits.extend(t.commits);
+                    }
+                }
+
+                wrt.flush()?;
+            }
+        }
+
+        grit_utils::report_skipped(&skipped, self.args.strict)?;
+
+        let max_commits = total_commits.len();
+
+        let mut output: Vec<FameOutputLine> = totals
+            .iter()
+            .map(|(author, t)| {
+                FameOutputLine::with_counts(
+                    author.clone(),
+                    t.lines,
+                    t.files,
+                    t.commits.len() as i32,
+                    max_files,
+                    max_commits,
+                    max_lines,
+                )
+            })
+            .collect();
+
+        self.sort_output(&mut output);
+
+        writeln!(w)?;
+        self.data_output(w, output, OutputFormat::Csv, None)?;
+
+        Ok(totals.into_iter().map(|(a, t)| (a, t.lines)).collect())
+    }
+
+    /// author by language matrix of LOC, one column per language
+    fn language_output(
+        &self,
+        w: &mut dyn Write,
+        languages: &[String],
+        output: &[LanguageLine],
+    ) -> Result<()> {
+        let mut table = Table::new();
+
+        let mut titles = table_row![identity::author_column().0];
+        titles.extend(languages.iter().cloned());
+        titles.push(String::from("Total"));
+        table.set_titles(titles);
+
+        for o in output.iter() {
+            let mut row = table_row![o.author];
+            row.extend(
+                languages
+                    .iter()
+                    .map(|l| o.lines.get(l).cloned().unwrap_or(0).to_string()),
+            );
+            row.push(o.total.to_string());
+            table.add_row(row);
+        }
+
+        table.write(w, self.args.format)?;
+
+        Ok(())
+    }
+
+    /// one column per metric, used for the csv and json output
+    fn data_output(
+        &self,
+        w: &mut dyn Write,
+        output: Vec<FameOutputLine>,
+        format: OutputFormat,
+        summary: Option<&FameSummary>,
+    ) -> Result<()> {
+        // the measures are not authors, so are kept out of the json and prom output
+        let summary =
+            summary.filter(|_| format != OutputFormat::Json && format != OutputFormat::Prom);
+
+        let mut table = self.data_table(output, summary);
+        self.select_columns(&mut table)?;
+        table.write(w, format)?;
+
+        Ok(())
+    }
+
+    /// the sampled totals would be taken for the repo's, so the estimate goes to stderr
+    /// where it can't break the data formats
+    fn note_estimate(estimate: Option<(Sample, Estimate)>) {
+        if let Some((sample, loc)) = estimate {
+            eprintln!(
+                "Totals of the {}, estimated repo LOC {} (95% confidence)",
+                sample, loc
+            );
+        }
+    }
+
+    /// every file whose blamed lines were written under other paths, each of those paths
+    /// and the lines still from it.  A renamed file stays one file under its current path,
+    /// however many names its authors knew it by.
+    fn former_names_table(collector: &[Vec<BlameOutput>]) -> Table {
+        let mut table = Table::new();
+        table.set_titles(table_row!["File", "Former name", "LOC"]);
+
+        for outputs in collector.iter() {
+            let mut former: BTreeMap<&String, i32> = BTreeMap::new();
+
+            for b in outputs.iter() {
+                if let Some(name) = &b.former_name {
+                    *former.entry(name).or_default() += b.lines;
+                }
+            }
+
+            for (name, lines) in former {
+                table.add_row(table_row![outputs[0].file_name, name, lines]);
+            }
+        }
+
+        table
+    }
+
+    /// lists the former names on stderr, where they can't break the data formats
+    fn note_former_names(former_names: Option<Table>) -> Result<()> {
+        if let Some(table) = former_names {
+            table.write(&mut std::io::stderr(), OutputFormat::Table)?;
+        }
+
+        Ok(())
+    }
+
+    /// the columns --extended adds after the others
+    fn extended_titles(&self) -> Vec<String> {
+        if !self.args.extended {
+            return Vec::new();
+        }
+
+        table_row![
+            "First commit",
+            "Last commit",
+            "Active days",
+            "LOC per active day"
+        ]
+    }
+
+    /// when the author's commits started and ended, the days they committed on and their
+    /// LOC per such day, for --extended
+    fn extended_cells(&self, o: &FameOutputLine) -> Vec<String> {
+        if !self.args.extended {
+            return Vec::new();
+        }
+
+        let date = |d: Option<NaiveDate>| d.map(grit_utils::format_date).unwrap_or_default();
+
+        table_row![
+            date(o.activity.first_commit()),
+            date(o.activity.last_commit()),
+            o.activity.active_days(),
+            format!("{:.1}", o.activity.per_active_day(o.lines))
+        ]
+    }
+
+    /// keeps only the `--columns` asked for, in their order
+    fn select_columns(&self, table: &mut Table) -> Result<()> {
+        match &self.args.columns {
+            Some(columns) => table.select_columns(columns),
+            None => Ok(()),
+        }
+    }
+
+    fn data_table(&self, output: Vec<FameOutputLine>, summary: Option<&FameSummary>) -> Table {
+        let mut table = Table::new();
+
+        let mut titles = table_row![
+            identity::author_column().0,
+            "Files",
+            "Commits",
+            "LOC",
+            "Distribution (%) - Files",
+            "Distribution (%) - Commits",
+            "Distribution (%) - LoC"
+        ];
+        titles.extend(self.extended_titles());
+        let columns = titles.len();
+        table.set_titles(titles);
+
+        output.iter().for_each(|r| {
+            let mut row = table_row![
+                r.author,
+                r.file_count,
+                r.commits_count,
+                r.lines,
+                output::percent(r.perc_files * 100.0),
+                output::percent(r.perc_commits * 100.0),
+                output::percent(r.perc_lines * 100.0)
+            ];
+            row.extend(self.extended_cells(r));
+            table.add_row(row);
+        });
+
+        for row in summary.map(|s| s.rows(columns)).unwrap_or_default() {
+            table.add_row(row);
+        }
+
+        if self.args.extended {
+            table.add_metric(
+                9,
+                "grit_author_active_days",
+                "Days the author made a commit on.",
+            );
+            table.add_metric(
+                10,
+                "grit_author_loc_per_active_day",
+                "Lines blamed to the author per day they made a commit on.",
+            );
+        }
+
+        table.add_label(0, identity::author_column().1);
+        table.add_metric(3, "grit_author_loc", "Lines of code blamed to the author.");
+        table.add_metric(
+            1,
+            "grit_author_files",
+            "Files the author has blamed lines in.",
+        );
+        table.add_metric(
+            2,
+            "grit_author_commits",
+            match self.args.commit_metric {
+                CommitMetric::Blame => "Commits behind the author's blamed lines.",
+                CommitMetric::Revwalk => "Commits the author made in the date range.",
+            },
+        );
+
+        table
+    }
+
+    /// the blame data of the run, blamed now or replayed, recorded when asked, and the
+    /// sample of files it covers
+    fn collect_blame(&self) -> Result<(Vec<Vec<BlameOutput>>, Option<Sample>)> {
+        let (collector, skipped, sample) = match &self.args.replay {
+            Some(f) => (read_blame_data(f)?, Vec::new(), None),
+            None => self.blame_repo()?,
+        };
+
+        grit_utils::report_skipped(&skipped, self.args.strict)?;
+
+        if let Some(f) = &self.args.record {
+            write_blame_data(f, &collector)?;
+        }
+
+        Ok((collector, sample))
+    }
+
+    /// the repo's LOC extrapolated from the blamed lines of each sampled file
+    fn estimate_loc(sample: &Sample, collector: &[Vec<BlameOutput>]) -> Estimate {
+        let lines: Vec<f64> = collector
+            .iter()
+            .map(|f| {
+                f.iter()
+                    .filter(|b| !b.boundary)
+                    .map(|b| b.lines as f64)
+                    .sum()
+            })
+            .collect();
+
+        sample.estimate(&lines)
+    }
+
+    fn sort_output(&self, output: &mut [FameOutputLine]) {
+        match self.args.sort {
+            Some(ref x) if x == "loc" => output.sort_by_key(|o| Reverse(o.lines)),
+            Some(ref x) if x == "files" => output.sort_by_key(|o| Reverse(o.file_count)),
+            _ => output.sort_by_key(|o| Reverse(o.commits_count)),
+        }
+    }
+
+    /// the sorted and collapsed lines per author, with the total files, commits and LOC
+    fn fame_lines(
+        &self,
+        collector: Vec<Vec<BlameOutput>>,
+        restrict_authors: Option<Vec<String>>,
+    ) -> Result<(Vec<FameOutputLine>, usize, usize, i32)> {
+        let snapshot = aggregate_blame(collector, restrict_authors.clone());
+
+        let max_files = snapshot.max_files;
+        let max_lines = snapshot.max_lines;
+        let mut max_commits = snapshot.max_commits;
+        let mut output = snapshot.output;
+
+        let stats = if self.args.commit_metric == CommitMetric::Revwalk || self.args.extended {
+            self.author_stats(&restrict_authors)?
+        } else {
+            HashMap::new()
+        };
+
+        if self.args.commit_metric == CommitMetric::Revwalk {
+            max_commits = Fame::revwalk_commits(&mut output, &stats);
+            output
+                .iter_mut()
+                .for_each(|o| o.calculate(max_files, max_commits, max_lines));
+        }
+
+        if self.args.extended {
+            for o in output.iter_mut() {
+                o.activity = stats.get(&o.author).cloned().unwrap_or_default();
+            }
+        }
+
+        self.sort_output(&mut output);
+
+        let output = self.collapse_output(output, max_files, max_commits, max_lines);
+
+        Ok((output, max_files, max_commits, max_lines))
+    }
+
+    /// the commits and commit days of every author between the dates, up to `--at`
+    fn author_stats(
+        &self,
+        restrict_authors: &Option<Vec<String>>,
+    ) -> Result<HashMap<String, AuthorStats>> {
+        let repo = grit_utils::open_repo(&self.args.path)?;
+
+        let tip = match &self.args.at {
+            Some(rev) => Some(Oid::from_bytes(&grit_utils::resolve_rev(
+                &self.args.path,
+                rev,
+            )?)?),
+            None => None,
+        };
+
+        AuthorWalk {
+            tip,
+            start_date: self.args.start_date,
+            end_date: self.args.end_date,
+            restrict_authors: restrict_authors.clone(),
+        }
+        .stats(&repo)
+    }
+
+    /// replaces each author's blamed commits with every commit they made, adding the
+    /// authors whose lines were all overwritten, and returns the total commits
+    fn revwalk_commits(
+        output: &mut Vec<FameOutputLine>,
+        stats: &HashMap<String, AuthorStats>,
+    ) -> usize {
+        let mut commits: HashMap<String, HashSet<String>> = stats
+            .iter()
+            .map(|(a, s)| (a.clone(), s.commits.clone()))
+            .collect();
+        let total = commits.values().map(|c| c.len()).sum();
+
+        for o in output.iter_mut() {
+            o.commits = commits.remove(&o.author).unwrap_or_default();
+        }
+
+        for (author, c) in commits.into_iter() {
+            let mut line = FameOutputLine::new();
+            line.author = author;
+            line.commits = c;
+            output.push(line);
+        }
+
+        total
+    }
+
+    /// the lines added and removed by each author's commits in the date range, sorted and
+    /// collapsed.  Merges are left out, as their changes belong to the merged commits.
+    fn diff_lines(&self) -> Result<Vec<DiffOutputLine>> {
+        let restrict_authors =
+            grit_utils::convert_string_list_to_vec(self.args.restrict_authors.clone());
+
+        let commits = diff_stats::commit_stats(
+            &self.args.path,
+            self.args.start_date,
+            self.args.end_date,
+            self.args.include.clone(),
+            self.args.exclude.clone(),
             false,
-            None,
+        )?;
+
+        let mut output_map: HashMap<String, DiffOutputLine> = HashMap::new();
+
+        for c in commits.iter() {
+            if restrict_authors
+                .as_ref()
+                .map(|ra| ra.contains(&c.name))
+                .unwrap_or(false)
+                || identity::is_excluded(&c.name, &c.email)
+            {
+                continue;
+            }
+
+            let author = identity::display_author(&c.name, &c.email);
+            let o = output_map
+                .entry(author.clone())
+                .or_insert_with(|| DiffOutputLine {
+                    author,
+                    ..DiffOutputLine::default()
+                });
+
+            o.commits += 1;
+            o.added += c.stats.insertions;
+            o.removed += c.stats.deletions;
+        }
+
+        let mut output: Vec<DiffOutputLine> = output_map.into_values().collect();
+
+        match self.args.sort {
+            Some(ref x) if x == "loc" => {
+                output.sort_by(|a, b| b.added.cmp(&a.added).then(a.author.cmp(&b.author)))
+            }
+            _ => output.sort_by(|a, b| b.commits.cmp(&a.commits).then(a.author.cmp(&b.author))),
+        }
+
+        let top = self.args.top.unwrap_or(usize::MAX);
+        let min_lines = self.args.min_lines.unwrap_or(0).max(0) as usize;
+        let min_commits = self.args.min_commits.unwrap_or(0);
+
+        let mut result: Vec<DiffOutputLine> = Vec::new();
+        let mut others = DiffOutputLine {
+            author: String::from(OTHERS_AUTHOR),
+            ..DiffOutputLine::default()
+        };
+
+        for o in output.into_iter() {
+            if result.len() < top && o.added >= min_lines && o.commits >= min_commits {
+                result.push(o);
+            } else {
+                others.commits += o.commits;
+                others.added += o.added;
+                others.removed += o.removed;
+            }
+        }
+
+        if others.commits > 0 {
+            result.push(others);
+        }
+
+        Ok(result)
+    }
+
+    fn diff_table(&self, output: &[DiffOutputLine]) -> Table {
+        let total_added: usize = output.iter().map(|o| o.added).sum();
+
+        let mut table = Table::new();
+
+        table.set_titles(table_row![
+            identity::author_column().0,
+            "Commits",
+            "Added",
+            "Removed",
+            "Net",
+            "Distribution (%) - Added"
+        ]);
+
+        output.iter().for_each(|r| {
+            table.add_row(table_row![
+                r.author,
+                r.commits,
+                r.added,
+                r.removed,
+                r.net(),
+                output::percent(r.added as f64 / total_added.max(1) as f64 * 100.0)
+            ]);
+        });
+
+        table.add_label(0, identity::author_column().1);
+        table.add_metric(
+            2,
+            "grit_author_lines_added",
+            "Lines added by the author's commits.",
+        );
+        table.add_metric(
+            3,
+            "grit_author_lines_removed",
+            "Lines removed by the author's commits.",
+        );
+
+        table
+    }
+
+    /// the fame metrics as a table, for callers showing it themselves
+    pub(crate) fn results_table(&self) -> Result<Table> {
+        let restrict_authors =
+            grit_utils::convert_string_list_to_vec(self.args.restrict_authors.clone());
+
+        let (collector, _) = self.collect_blame()?;
+        let (output, _, _, _) = self.fame_lines(collector, restrict_authors)?;
+
+        Ok(self.data_table(output, None))
+    }
+}
+
+impl Processable<()> for Fame {
+    fn process(&self) -> Result<()> {
+        if grit_utils::range_is_empty(&self.args.path, self.args.start_date, self.args.end_date)? {
+            return Ok(());
+        }
+
+        let restrict_authors: Option<Vec<String>> =
+            grit_utils::convert_string_list_to_vec(self.args.restrict_authors.clone());
+
+        if self.args.mode == FameMode::Diff {
+            let output = self.diff_lines()?;
+
+            let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+            let mut table = self.diff_table(&output);
+            self.select_columns(&mut table)?;
+            table.write(&mut w, self.args.format)?;
+
+            return Ok(());
+        }
+
+        if self.args.stream {
+            let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+            let owned = self.stream_output(&mut w, &restrict_authors)?;
+
+            return self.args.policy.check(&owned);
+        }
+
+        let (collector, sample) = self.collect_blame()?;
+        let estimate = sample.map(|s| (s, Fame::estimate_loc(&s, &collector)));
+        let former_names = self
+            .args
+            .former_names
+            .then(|| Fame::former_names_table(&collector));
+
+        // the lines of every author, before --top and the minimums fold any into Others
+        let owned: Vec<(String, i32)> = if self.args.policy.is_set() || self.args.summary {
+            aggregate_blame(collector.clone(), restrict_authors.clone())
+                .output
+                .into_iter()
+                .map(|o| (o.author, o.lines))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        if self.args.by_language {
+            let language_map = LanguageMap::with_overrides(self.args.language_map.as_deref())?;
+            let (languages, output) = language_matrix(&collector, &restrict_authors, &language_map);
+
+            let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+            self.language_output(&mut w, &languages, &output)?;
+            Fame::note_estimate(estimate);
+            Fame::note_former_names(former_names)?;
+
+            return self.args.policy.check(&owned);
+        }
+
+        let (output, max_files, max_commits, max_lines) = timing::phase("aggregation", || {
+            self.fame_lines(collector, restrict_authors)
+        })?;
+
+        let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+
+        let summary = if self.args.summary {
+            let lines: Vec<i32> = owned.iter().map(|(_, l)| *l).collect();
+            Some(FameSummary::new(&lines))
+        } else {
+            None
+        };
+
+        timing::phase("output", || match self.args.format {
+            OutputFormat::Table => {
+                Fame::print_totals(&mut w, max_lines, max_files, max_commits, estimate)?;
+                self.pretty_print_table(&mut w, output, summary.as_ref())
+            }
+            f => {
+                self.data_output(&mut w, output, f, summary.as_ref())?;
+                Fame::note_estimate(estimate);
+                Ok(())
+            }
+        })?;
+        Fame::note_former_names(former_names)?;
+
+        self.args.policy.check(&owned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::GritError;
+    use chrono::NaiveDate;
+    use log::LevelFilter;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    /// author, LOC, files and commits per author, in author order
+    fn fame_totals(args: FameArgs) -> Vec<(String, i32, usize, i32)> {
+        let fame = Fame::new(args);
+        let (collector, _, _) = fame.blame_repo().unwrap();
+        let restrict_authors =
+            grit_utils::convert_string_list_to_vec(fame.args.restrict_authors.clone());
+
+        let mut output = aggregate_blame(collector, restrict_authors).output;
+        output.sort_by(|a, b| a.author.cmp(&b.author));
+
+        output
+            .iter()
+            .map(|o| (o.author.clone(), o.lines, o.file_count, o.commits_count))
+            .collect()
+    }
+
+    fn totals(expected: &[(&str, i32, usize, i32)]) -> Vec<(String, i32, usize, i32)> {
+        expected
+            .iter()
+            .map(|(a, l, f, c)| (a.to_string(), *l, *f, *c))
+            .collect()
+    }
+
+    #[test]
+    fn test_process_fame() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let args = FameArgs::builder(String::from(path))
+            .sort(Some("loc".to_string()))
+            .file(Some(String::from("target/test_fame.csv")))
+            .build()
+            .unwrap();
+
+        let f = Fame::new(args);
+
+        let result = match f.process() {
+            Ok(()) => true,
+            Err(_t) => false,
+        };
+
+        assert!(result, "test_process_file result was {}", result);
+
+        let csv = std::fs::read_to_string("target/test_fame.csv").unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1], "Alice,2,2,6,66.7,50.0,54.5");
+        assert_eq!(lines[2], "Bob,2,2,5,66.7,50.0,45.5");
+    }
+
+    #[test]
+    fn test_fame_sample() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let args = FameArgs::builder(String::from(path))
+            .sample(Some(100.0))
+            .build()
+            .unwrap();
+        let (collector, _, sample) = Fame::new(args).blame_repo().unwrap();
+
+        // every file is sampled, so the estimate is the repo's exact LOC
+        assert_eq!(
+            Fame::estimate_loc(&sample.unwrap(), &collector),
+            Estimate {
+                total: 11.0,
+                margin: 0.0
+            }
+        );
+    }
+
+    #[test]
+    fn test_process_fame_columns() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+        let file = "target/test_fame_columns.csv";
+
+        let args = FameArgs::builder(String::from(path))
+            .sort(Some("loc".to_string()))
+            .file(Some(String::from(file)))
+            .columns(Some(vec![String::from("loc"), String::from("author")]))
+            .build()
+            .unwrap();
+
+        Fame::new(args).process().unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(file).unwrap(),
+            "LOC,Author\n6,Alice\n5,Bob\n"
         );
 
+        let args = FameArgs::builder(String::from(path))
+            .columns(Some(vec![String::from("churn")]))
+            .build()
+            .unwrap();
+
+        assert!(Fame::new(args).process().is_err());
+    }
+
+    #[test]
+    fn test_process_fame_no_commits_in_range() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+        let file = td.path().join("fame.csv");
+
+        // the fixture's first commit is made on 2020-01-01
+        let args = FameArgs::builder(String::from(path))
+            .end_date(Some(NaiveDate::from_ymd_opt(2019, 12, 31).unwrap()))
+            .file(Some(file.to_str().unwrap().to_string()))
+            .build()
+            .unwrap();
         let fame = Fame::new(args);
 
-        let start = Instant::now();
+        let (file_names, range) = fame.blame_targets().unwrap();
+        assert!(file_names.is_empty());
+        assert!(range.is_empty());
 
-        let result = match fame.process() {
-            Ok(()) => true,
-            Err(_t) => false,
-        };
+        fame.process().unwrap();
+        assert!(!file.exists());
+    }
+
+    fn test_output_line(author: &str, lines: i32, commits: &[&str]) -> FameOutputLine {
+        let mut line = FameOutputLine::new();
+        line.author = String::from(author);
+        line.lines = lines;
+        line.commits = commits.iter().map(|c| c.to_string()).collect();
+        line.filenames.insert(format!("{}.rs", author));
+        line.calculate(3, 4, 100);
+        line
+    }
+
+    #[test]
+    fn test_collapse_output() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let args = FameArgs::builder(String::from("."))
+            .format(OutputFormat::Table)
+            .top(Some(1))
+            .min_commits(Some(2))
+            .build()
+            .unwrap();
+
+        let fame = Fame::new(args);
+
+        let output = vec![
+            test_output_line("a", 60, &["1", "2"]),
+            test_output_line("b", 30, &["3", "4"]),
+            test_output_line("c", 10, &["4"]),
+        ];
+
+        let result = fame.collapse_output(output, 3, 4, 100);
 
-        let duration = start.elapsed();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].author, "a");
+        assert_eq!(result[1].author, OTHERS_AUTHOR);
+        assert_eq!(result[1].lines, 40);
+        assert_eq!(result[1].commits_count, 2);
+        assert_eq!(result[1].file_count, 2);
+    }
+
+    #[test]
+    fn test_collapse_output_no_filters() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let args = FameArgs::builder(String::from("."))
+            .format(OutputFormat::Table)
+            .build()
+            .unwrap();
+
+        let fame = Fame::new(args);
+
+        let output = vec![
+            test_output_line("a", 60, &["1", "2"]),
+            test_output_line("b", 40, &["3", "4"]),
+        ];
+
+        let result = fame.collapse_output(output, 2, 4, 100);
+
+        assert_eq!(result.len(), 2);
+        assert!(result.iter().all(|o| o.author != OTHERS_AUTHOR));
+    }
+
+    #[test]
+    fn test_process_fame_start_date() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
 
-        assert!(result, "test_process_fame_start_date result was {}", result);
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let ed = NaiveDate::parse_from_str("2020-01-02", "%Y-%m-%d").unwrap();
+
+        let args = FameArgs::builder(path.to_string())
+            .sort(Some("loc".to_string()))
+            .start_date(Some(ed))
+            .format(OutputFormat::Table)
+            .build()
+            .unwrap();
+
+        let start = Instant::now();
+
+        // Alice's 4 lines of 2020-01-01 are older than the start date and not counted,
+        // rather than taken by Bob's first commit, the oldest in range
+        assert_eq!(
+            fame_totals(args),
+            totals(&[("Alice", 2, 1, 1), ("Bob", 5, 2, 2)])
+        );
 
-        println!("completed test_process_fame_start_date in {:?}", duration);
+        println!(
+            "completed test_process_fame_start_date in {:?}",
+            start.elapsed()
+        );
     }
 
     #[test]
     fn test_process_fame_end_date() {
         crate::grit_test::set_test_logging(LOG_LEVEL);
 
-        let td: TempDir = crate::grit_test::init_repo();
+        let td: TempDir = crate::grit_test::fixture_repo();
         let path = td.path().to_str().unwrap();
 
-        let ed = Local::now().add(Duration::days(-30)).date();
-
-        let args = FameArgs::new(
-            path.to_string(),
-            Some("loc".to_string()),
-            None,
-            Some(ed),
-            None,
-            None,
-            None,
-            true,
-            None,
+        let ed = NaiveDate::from_ymd_opt(2020, 1, 2).unwrap();
+
+        let args = FameArgs::builder(path.to_string())
+            .sort(Some("loc".to_string()))
+            .end_date(Some(ed))
+            .build()
+            .unwrap();
+
+        let start = Instant::now();
+
+        assert_eq!(
+            fame_totals(args),
+            totals(&[("Alice", 7, 2, 2), ("Bob", 4, 1, 1)])
         );
 
-        let fame = Fame::new(args);
+        println!(
+            "completed test_process_fame_end_date in {:?}",
+            start.elapsed()
+        );
+    }
+
+    #[test]
+    fn test_process_fame_include() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let args = FameArgs::builder(path.to_string())
+            .sort(Some("loc".to_string()))
+            .include(Some("src/*.rs".to_string()))
+            .build()
+            .unwrap();
 
         let start = Instant::now();
 
+        assert_eq!(
+            fame_totals(args),
+            totals(&[("Alice", 5, 1, 2), ("Bob", 4, 1, 1)])
+        );
+
+        println!(
+            "completed test_process_fame_include in {:?}",
+            start.elapsed()
+        );
+    }
+
+    #[test]
+    fn test_process_fame_at() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let args = FameArgs::builder(path.to_string())
+            .sort(Some("loc".to_string()))
+            .at(Some(String::from("HEAD~1")))
+            .build()
+            .unwrap();
+
+        let fame = Fame::new(args);
+
         let result = match fame.process() {
             Ok(()) => true,
-            Err(_t) => false,
+            Err(e) => {
+                error!("test_process_fame_at ended in error {:?}", e);
+                false
+            }
         };
 
-        let duration = start.elapsed();
+        assert!(result, "test_process_fame_at result was {}", result);
 
-        assert!(result, "test_process_fame_end_date result was {}", result);
+        let args = FameArgs::builder(path.to_string())
+            .at(Some(String::from("HEAD~1")))
+            .build()
+            .unwrap();
 
-        println!("completed test_process_fame_end_date in {:?}", duration);
+        // both README lines are still Alice's before Bob's last commit
+        assert_eq!(
+            fame_totals(args),
+            totals(&[("Alice", 7, 2, 2), ("Bob", 4, 1, 1)])
+        );
     }
 
     #[test]
-    fn test_process_fame_include() {
+    fn test_process_fame_subdir() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let fame_args = |subdir: &str| {
+            FameArgs::builder(path.to_string())
+                .subdir(Some(subdir.to_string()))
+                .build()
+                .unwrap()
+        };
+
+        assert_eq!(
+            fame_totals(fame_args("src")),
+            totals(&[("Alice", 5, 1, 2), ("Bob", 4, 1, 1)])
+        );
+        assert!(Fame::new(fame_args("docs")).process().is_err());
+    }
+
+    #[test]
+    fn test_former_names() {
         crate::grit_test::set_test_logging(LOG_LEVEL);
 
-        let td: TempDir = crate::grit_test::init_repo();
+        let td: TempDir = crate::grit_test::fixture_repo();
         let path = td.path().to_str().unwrap();
 
-        let args = FameArgs::new(
-            path.to_string(),
-            Some("loc".to_string()),
-            None,
-            None,
-            Some("*.rs,*.md".to_string()),
-            None,
-            None,
-            true,
-            None,
+        // Carol moves Bob's src/lib.rs to src/core.rs without changing it
+        let repo = Repository::open(path).unwrap();
+        std::fs::rename(td.path().join("src/lib.rs"), td.path().join("src/core.rs")).unwrap();
+        let mut index = repo.index().unwrap();
+        index.remove_path(Path::new("src/lib.rs")).unwrap();
+        index.add_path(Path::new("src/core.rs")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let carol = git2::Signature::new(
+            "Carol",
+            "carol@example.com",
+            &git2::Time::new(1_578_312_000, 0),
+        )
+        .unwrap();
+        repo.commit(Some("HEAD"), &carol, &carol, "move", &tree, &[&head])
+            .unwrap();
+
+        let file_names = vec!["src/core.rs".to_string(), "src/main.rs".to_string()];
+        let (collector, skipped) = blame_files(path, &file_names, None, None).unwrap();
+        assert!(skipped.is_empty());
+
+        let mut csv: Vec<u8> = Vec::new();
+        Fame::former_names_table(&collector)
+            .write(&mut csv, OutputFormat::Csv)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "File,Former name,LOC\nsrc/core.rs,src/lib.rs,4\n"
         );
 
-        let fame = Fame::new(args);
+        // the renamed file is still one of Bob's files, and none of Carol's
+        let args = FameArgs::builder(path.to_string())
+            .former_names(true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            fame_totals(args),
+            totals(&[("Alice", 6, 2, 2), ("Bob", 5, 2, 2)])
+        );
+    }
 
-        let start = Instant::now();
+    #[test]
+    fn test_extended() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
 
-        let result = match fame.process() {
-            Ok(()) => true,
-            Err(_t) => false,
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let args = FameArgs::builder(path.to_string())
+            .sort(Some("author".to_string()))
+            .extended(true)
+            .build()
+            .unwrap();
+
+        let mut csv: Vec<u8> = Vec::new();
+        Fame::new(args)
+            .results_table()
+            .unwrap()
+            .write(&mut csv, OutputFormat::Csv)
+            .unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+
+        assert!(
+            csv.starts_with("Author,Files,Commits,LOC,Distribution (%) - Files,Distribution (%) - Commits,Distribution (%) - LoC,First commit,Last commit,Active days,LOC per active day\n"),
+            "{}",
+            csv
+        );
+        assert!(csv.contains(",2020-01-01,2020-01-02,2,3.0\n"), "{}", csv);
+        assert!(csv.contains(",2020-01-02,2020-01-05,2,2.5\n"), "{}", csv);
+    }
+
+    #[test]
+    fn test_blame_files_skips_missing() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let file_names = vec![String::from("README.md"), String::from("not_there.rs")];
+
+        let (collector, skipped) = blame_files(path, &file_names, None, None).unwrap();
+
+        assert_eq!(collector.len(), 1);
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].file_name, "not_there.rs");
+    }
+
+    #[test]
+    fn test_language_matrix() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let blame = |author: &str, file: &str, lines: i32| {
+            let mut b = BlameOutput::new(
+                author.to_string(),
+                String::new(),
+                String::from("c1"),
+                file.to_string(),
+            );
+            b.lines = lines;
+            b
         };
 
-        let duration = start.elapsed();
+        let collector = vec![
+            vec![
+                blame("todd", "src/main.rs", 40),
+                blame("sam", "src/main.rs", 5),
+            ],
+            vec![blame("sam", "web/app.ts", 30)],
+            vec![blame("todd", "Makefile", 2)],
+        ];
+
+        let language_map = LanguageMap::default();
+        let (languages, output) = language_matrix(&collector, &None, &language_map);
 
-        assert!(result, "test_process_fame_include result was {}", result);
+        assert_eq!(languages, vec!["Rust", "TypeScript", "Other"]);
+        assert_eq!(output[0].author, "todd");
+        assert_eq!(output[0].total, 42);
+        assert_eq!(output[1].lines["TypeScript"], 30);
+        assert_eq!(output[1].lines.get("Other"), None);
 
-        println!("completed test_process_fame_include in {:?}", duration);
+        let (_, output) =
+            language_matrix(&collector, &Some(vec![String::from("todd")]), &language_map);
+
+        assert_eq!(output.len(), 1);
     }
 
     #[test]
-    fn test_process_fame_restrict_author() {
+    fn test_process_fame_by_language() {
         crate::grit_test::set_test_logging(LOG_LEVEL);
 
-        let td: TempDir = crate::grit_test::init_repo();
+        let td: TempDir = crate::grit_test::fixture_repo();
         let path = td.path().to_str().unwrap();
 
-        let args = FameArgs::new(
-            path.to_string(),
-            Some("loc".to_string()),
-            None,
-            None,
-            None,
-            None,
-            Some(String::from("todd-bush")),
-            false,
-            None,
-        );
+        let args = FameArgs::builder(path.to_string())
+            .file(Some(String::from("target/test_fame_by_language.csv")))
+            .by_language(true)
+            .language_map(Some(String::from("md=Docs")))
+            .build()
+            .unwrap();
 
         let fame = Fame::new(args);
 
-        let start = Instant::now();
-
         let result = match fame.process() {
             Ok(()) => true,
-            Err(_t) => false,
+            Err(e) => {
+                error!("test_process_fame_by_language ended in error {:?}", e);
+                false
+            }
         };
 
-        let duration = start.elapsed();
+        assert!(result, "See error above");
 
-        assert!(
-            result,
-            "test_process_fame_restrict_author result was {}",
-            result
+        let csv = std::fs::read_to_string("target/test_fame_by_language.csv").unwrap();
+
+        assert_eq!(csv, "Author,Rust,Docs,Total\nAlice,5,1,6\nBob,4,1,5\n");
+    }
+
+    #[test]
+    fn test_blame_data_round_trip() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let mut a = BlameOutput::new(
+            String::from("Todd, Bush"),
+            String::from("todd@example.com"),
+            String::from("abc123"),
+            String::from("src/main.rs"),
         );
+        a.lines = 12;
+
+        let collector = vec![vec![a.clone()], vec![]];
+
+        let file_name = "target/test_blame_data.gritdata";
+        write_blame_data(file_name, &collector).unwrap();
+
+        let replayed = read_blame_data(file_name).unwrap();
+
+        assert_eq!(replayed.len(), 2);
+        assert_eq!(replayed[0].len(), 1);
+        assert_eq!(replayed[0][0].author, a.author);
+        assert_eq!(replayed[0][0].email, a.email);
+        assert_eq!(replayed[0][0].file_name, a.file_name);
+        assert_eq!(replayed[0][0].lines, 12);
+        assert!(replayed[1].is_empty());
+
+        assert!(read_blame_data("Cargo.toml").is_err());
+    }
+
+    #[test]
+    fn test_process_fame_record_replay() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+        let record = td.path().join("run.gritdata");
+        let record = record.to_str().unwrap();
+
+        let args = FameArgs::builder(path.to_string())
+            .include(Some(String::from("*.rs")))
+            .record(Some(record.to_string()))
+            .build()
+            .unwrap();
+
+        Fame::new(args).process().unwrap();
+
+        let recorded = read_blame_data(record).unwrap();
+        assert_eq!(recorded.len(), 2);
+
+        let args = FameArgs::builder(String::from("/does/not/exist"))
+            .sort(Some("loc".to_string()))
+            .format(OutputFormat::Table)
+            .top(Some(1))
+            .replay(Some(record.to_string()))
+            .build()
+            .unwrap();
+
+        let result = match Fame::new(args).process() {
+            Ok(()) => true,
+            Err(e) => {
+                error!("test_process_fame_record_replay ended in error {:?}", e);
+                false
+            }
+        };
+
+        assert!(result, "See error above");
+    }
+
+    #[test]
+    fn test_process_fame_restrict_author() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let args = FameArgs::builder(path.to_string())
+            .sort(Some("loc".to_string()))
+            .restrict_authors(Some(String::from("Bob")))
+            .format(OutputFormat::Table)
+            .build()
+            .unwrap();
+
+        let start = Instant::now();
+
+        assert_eq!(fame_totals(args), totals(&[("Alice", 6, 2, 2)]));
 
         println!(
             "completed test_process_fame_restrict_author in {:?}",
-            duration
+            start.elapsed()
+        );
+    }
+
+    #[test]
+    fn test_process_fame_policy() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let fame = |policy: Policy| {
+            Fame::new(
+                FameArgs::builder(path.to_string())
+                    .file(Some(String::from("target/test_process_fame_policy.csv")))
+                    .top(Some(1))
+                    .policy(policy)
+                    .build()
+                    .unwrap(),
+            )
+            .process()
+        };
+
+        // Alice owns 6 of the 11 lines, so alone holds the majority even with --top folding
+        // Bob into Others
+        assert!(fame(Policy::new(Some(1), Some(60.0))).is_ok());
+
+        let err = fame(Policy::new(Some(2), None)).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<GritError>(),
+            Some(GritError::PolicyViolation(_))
+        ));
+    }
+
+    #[test]
+    fn test_process_fame_stream() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+        let file = "target/test_process_fame_stream.csv";
+
+        let args = FameArgs::builder(path.to_string())
+            .sort(Some(String::from("loc")))
+            .file(Some(String::from(file)))
+            .stream(true)
+            .build()
+            .unwrap();
+
+        Fame::new(args).process().unwrap();
+
+        let output = std::fs::read_to_string(file).unwrap();
+
+        assert_eq!(
+            output.lines().collect::<Vec<&str>>(),
+            vec![
+                "File,Author,LOC,Commits",
+                "README.md,Alice,1,1",
+                "README.md,Bob,1,1",
+                "src/lib.rs,Bob,4,1",
+                "src/main.rs,Alice,5,2",
+                "",
+                "Author,Files,Commits,LOC,Distribution (%) - Files,Distribution (%) - Commits,Distribution (%) - LoC",
+                "Alice,2,2,6,66.7,50.0,54.5",
+                "Bob,2,2,5,66.7,50.0,45.5",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_process_fame_diff_mode() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+        let file = "target/test_process_fame_diff_mode.csv";
+
+        let args = FameArgs::builder(path.to_string())
+            .file(Some(String::from(file)))
+            .mode(FameMode::Diff)
+            .build()
+            .unwrap();
+
+        Fame::new(args).process().unwrap();
+
+        let rows: Vec<String> = std::fs::read_to_string(file)
+            .unwrap()
+            .lines()
+            .skip(1)
+            .map(String::from)
+            .collect();
+
+        // Bob's README edit replaces one of Alice's lines, one added and one removed
+        assert_eq!(rows, vec!["Alice,2,7,0,7,58.3", "Bob,2,5,1,4,41.7"]);
+    }
+
+    #[test]
+    fn test_fame_args_builder() {
+        let args = FameArgs::builder(String::from("."))
+            .sort(Some(String::from("loc")))
+            .top(Some(3))
+            .build()
+            .unwrap();
+
+        assert_eq!(args.sort.as_deref(), Some("loc"));
+        assert_eq!(args.top, Some(3));
+        assert_eq!(args.format, OutputFormat::Csv);
+
+        let reversed = FameArgs::builder(String::from("."))
+            .start_date(Some(NaiveDate::from_ymd_opt(2020, 2, 1).unwrap()))
+            .end_date(Some(NaiveDate::from_ymd_opt(2020, 1, 1).unwrap()))
+            .build();
+        assert!(reversed.is_err());
+
+        let mismatched = FameArgs::builder(String::from("."))
+            .file(Some(String::from("fame.csv")))
+            .format(OutputFormat::Json)
+            .build();
+        assert!(mismatched.is_err());
+    }
+
+    #[test]
+    fn test_process_fame_commit_metric_revwalk() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+        let file = "target/test_process_fame_commit_metric_revwalk.csv";
+
+        let args = FameArgs::builder(path.to_string())
+            .include(Some("README.md".to_string()))
+            .file(Some(String::from(file)))
+            .commit_metric(CommitMetric::Revwalk)
+            .build()
+            .unwrap();
+
+        Fame::new(args).process().unwrap();
+
+        let mut rows: Vec<String> = std::fs::read_to_string(file)
+            .unwrap()
+            .lines()
+            .skip(1)
+            .map(String::from)
+            .collect();
+        rows.sort();
+
+        // blame only sees one README commit each, the revwalk every commit
+        assert_eq!(
+            rows,
+            vec!["Alice,1,2,1,100.0,50.0,50.0", "Bob,1,2,1,100.0,50.0,50.0"]
+        );
+    }
+
+    #[test]
+    fn test_fame_summary() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let summary = FameSummary::new(&[10, 0, 30, 20, 40]);
+
+        assert!((summary.gini - 0.4).abs() < 1e-9);
+        assert!((summary.top_3_share - 90.0).abs() < 1e-9);
+        assert!((summary.mean - 20.0).abs() < 1e-9);
+        assert!((summary.median - 20.0).abs() < 1e-9);
+
+        assert!((FameSummary::new(&[7, 7, 7]).gini).abs() < 1e-9);
+        assert!((FameSummary::new(&[4, 1, 2, 3]).median - 2.5).abs() < 1e-9);
+        assert_eq!(FameSummary::new(&[]).mean, 0.0);
+    }
+
+    #[test]
+    fn test_process_fame_summary() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+        let file = "target/test_process_fame_summary.csv";
+
+        let args = FameArgs::builder(path.to_string())
+            .sort(Some(String::from("loc")))
+            .file(Some(String::from(file)))
+            .top(Some(1))
+            .summary(true)
+            .build()
+            .unwrap();
+
+        Fame::new(args).process().unwrap();
+
+        let rows: Vec<String> = std::fs::read_to_string(file)
+            .unwrap()
+            .lines()
+            .skip(1)
+            .map(String::from)
+            .collect();
+
+        // the measures cover Bob's 5 lines too, though --top folds him into Others
+        assert_eq!(
+            rows,
+            vec![
+                "Alice,2,2,6,66.7,50.0,54.5",
+                "Others,2,2,5,66.7,50.0,45.5",
+                "Gini coefficient (LOC),,,0.045,,,",
+                "Top 3 share (% LOC),,,100.0,,,",
+                "Mean LOC per author,,,5.5,,,",
+                "Median LOC per author,,,5.5,,,",
+            ]
         );
     }
 }
@@ -0,0 +1,363 @@
+use super::Processable;
+use crate::fame::{fame_snapshot, FameSnapshot};
+use crate::output;
+use crate::table::Table;
+use crate::utils::grit_utils;
+use crate::utils::grit_utils::OutputFormat;
+use anyhow::Result;
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+use std::io::Write;
+
+pub struct FameDiffArgs {
+    path: String,
+    from: String,
+    to: String,
+    include: Option<String>,
+    exclude: Option<String>,
+    restrict_authors: Option<String>,
+    format: OutputFormat,
+    file: Option<String>,
+}
+
+impl FameDiffArgs {
+    pub fn builder(path: String, from: String, to: String) -> FameDiffArgsBuilder {
+        FameDiffArgsBuilder {
+            args: FameDiffArgs {
+                path,
+                from,
+                to,
+                include: None,
+                exclude: None,
+                restrict_authors: None,
+                format: OutputFormat::Csv,
+                file: None,
+            },
+        }
+    }
+}
+
+/// Builds a `FameDiffArgs` by name rather than position, validating it on `build`.
+pub struct FameDiffArgsBuilder {
+    args: FameDiffArgs,
+}
+
+impl FameDiffArgsBuilder {
+    pub fn include(mut self, include: Option<String>) -> FameDiffArgsBuilder {
+        self.args.include = include;
+        self
+    }
+
+    pub fn exclude(mut self, exclude: Option<String>) -> FameDiffArgsBuilder {
+        self.args.exclude = exclude;
+        self
+    }
+
+    pub fn restrict_authors(mut self, restrict_authors: Option<String>) -> FameDiffArgsBuilder {
+        self.args.restrict_authors = restrict_authors;
+        self
+    }
+
+    pub fn format(mut self, format: OutputFormat) -> FameDiffArgsBuilder {
+        self.args.format = format;
+        self
+    }
+
+    pub fn file(mut self, file: Option<String>) -> FameDiffArgsBuilder {
+        self.args.file = file;
+        self
+    }
+
+    pub fn build(self) -> Result<FameDiffArgs> {
+        grit_utils::check_output_file(self.args.file.as_deref(), self.args.format)?;
+
+        Ok(self.args)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct FameDiffLine {
+    author: String,
+    lines_from: i32,
+    lines_to: i32,
+    files_from: usize,
+    files_to: usize,
+    commits_from: i32,
+    commits_to: i32,
+    perc_lines_from: f64,
+    perc_lines_to: f64,
+}
+
+impl FameDiffLine {
+    fn lines_delta(&self) -> i32 {
+        self.lines_to - self.lines_from
+    }
+
+    fn files_delta(&self) -> i64 {
+        self.files_to as i64 - self.files_from as i64
+    }
+
+    fn commits_delta(&self) -> i32 {
+        self.commits_to - self.commits_from
+    }
+
+    /// change in the author's share of the lines, in percentage points
+    fn ownership_delta(&self) -> f64 {
+        (self.perc_lines_to - self.perc_lines_from) * 100.0
+    }
+}
+
+pub struct FameDiff {
+    args: FameDiffArgs,
+}
+
+impl FameDiff {
+    pub fn new(args: FameDiffArgs) -> FameDiff {
+        FameDiff { args }
+    }
+
+    fn snapshot(&self, rev: &str) -> Result<FameSnapshot> {
+        let commit = grit_utils::resolve_rev(&self.args.path, rev)?;
+
+        let file_names = grit_utils::generate_file_list_at(
+            &self.args.path,
+            &commit,
+            self.args.include.clone(),
+            self.args.exclude.clone(),
+        )?;
+
+        fame_snapshot(
+            &self.args.path,
+            &file_names,
+            None,
+            Some(commit),
+            grit_utils::convert_string_list_to_vec(self.args.restrict_authors.clone()),
+        )
+    }
+
+    /// pairs up the authors of both snapshots, biggest ownership gain first
+    fn diff(from: &FameSnapshot, to: &FameSnapshot) -> Vec<FameDiffLine> {
+        let from_map: HashMap<&str, _> =
+            from.output.iter().map(|o| (o.author.as_str(), o)).collect();
+        let to_map: HashMap<&str, _> = to.output.iter().map(|o| (o.author.as_str(), o)).collect();
+
+        let authors: BTreeSet<&str> = from_map.keys().chain(to_map.keys()).cloned().collect();
+
+        let mut result: Vec<FameDiffLine> = authors
+            .into_iter()
+            .map(|a| {
+                let mut line = FameDiffLine {
+                    author: a.to_string(),
+                    ..Default::default()
+                };
+
+                if let Some(f) = from_map.get(a) {
+                    line.lines_from = f.lines;
+                    line.files_from = f.file_count;
+                    line.commits_from = f.commits_count;
+                    line.perc_lines_from = f.perc_lines;
+                }
+
+                if let Some(t) = to_map.get(a) {
+                    line.lines_to = t.lines;
+                    line.files_to = t.file_count;
+                    line.commits_to = t.commits_count;
+                    line.perc_lines_to = t.perc_lines;
+                }
+
+                line
+            })
+            .collect();
+
+        result.sort_by(|a, b| {
+            b.ownership_delta()
+                .partial_cmp(&a.ownership_delta())
+                .unwrap_or(Ordering::Equal)
+                .then(b.lines_delta().cmp(&a.lines_delta()))
+        });
+
+        result
+    }
+
+    fn pretty_print_table(&self, w: &mut dyn Write, output: &[FameDiffLine]) -> Result<()> {
+        writeln!(
+            w,
+            "Ownership change from {} to {}",
+            self.args.from, self.args.to
+        )?;
+
+        let mut table = Table::new();
+
+        table.set_titles(table_row![
+            "Author",
+            "LOC",
+            "LOC Change",
+            "Files Change",
+            "Commits Change",
+            "Ownership Change (pp)"
+        ]);
+
+        for o in output.iter() {
+            table.add_row(table_row![
+                o.author,
+                format!("{} -> {}", o.lines_from, o.lines_to),
+                format!("{:+}", o.lines_delta()),
+                format!("{:+}", o.files_delta()),
+                format!("{:+}", o.commits_delta()),
+                format!("{:+.*}", output::precision(), o.ownership_delta())
+            ]);
+        }
+
+        table.print(w)?;
+
+        Ok(())
+    }
+
+    /// one column per value, used for the csv and json output
+    fn data_output(
+        &self,
+        w: &mut dyn Write,
+        output: &[FameDiffLine],
+        format: OutputFormat,
+    ) -> Result<()> {
+        let mut table = Table::new();
+
+        table.set_titles(table_row![
+            "Author",
+            "LOC - From",
+            "LOC - To",
+            "LOC - Change",
+            "Files - From",
+            "Files - To",
+            "Files - Change",
+            "Commits - From",
+            "Commits - To",
+            "Commits - Change",
+            "Ownership Change (pp)"
+        ]);
+
+        output.iter().for_each(|r| {
+            table.add_row(table_row![
+                r.author,
+                r.lines_from,
+                r.lines_to,
+                r.lines_delta(),
+                r.files_from,
+                r.files_to,
+                r.files_delta(),
+                r.commits_from,
+                r.commits_to,
+                r.commits_delta(),
+                output::percent(r.ownership_delta())
+            ]);
+        });
+
+        table.write(w, format)?;
+
+        Ok(())
+    }
+}
+
+impl Processable<()> for FameDiff {
+    fn process(&self) -> Result<()> {
+        let from = self.snapshot(&self.args.from)?;
+        let to = self.snapshot(&self.args.to)?;
+
+        grit_utils::report_skipped(&from.skipped, false)?;
+        grit_utils::report_skipped(&to.skipped, false)?;
+
+        let output = FameDiff::diff(&from, &to);
+
+        let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+
+        match self.args.format {
+            OutputFormat::Table => self.pretty_print_table(&mut w, &output)?,
+            f => self.data_output(&mut w, &output, f)?,
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::LevelFilter;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    #[test]
+    fn test_fame_diff_line() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let line = FameDiffLine {
+            author: String::from("todd"),
+            lines_from: 100,
+            lines_to: 60,
+            files_from: 4,
+            files_to: 5,
+            commits_from: 10,
+            commits_to: 8,
+            perc_lines_from: 0.5,
+            perc_lines_to: 0.25,
+        };
+
+        assert_eq!(line.lines_delta(), -40);
+        assert_eq!(line.files_delta(), 1);
+        assert_eq!(line.commits_delta(), -2);
+        assert!((line.ownership_delta() + 25.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_process_fame_diff() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let args = FameDiffArgs::builder(
+            path.to_string(),
+            String::from("HEAD~3"),
+            String::from("HEAD"),
+        )
+        .include(Some(String::from("*.rs")))
+        .format(OutputFormat::Table)
+        .build()
+        .unwrap();
+
+        let fd = FameDiff::new(args);
+
+        let from = fd.snapshot("HEAD~3").unwrap();
+        let to = fd.snapshot("HEAD").unwrap();
+        let output = FameDiff::diff(&from, &to);
+
+        let mut deltas: Vec<(&str, i32, i32, usize, usize)> = output
+            .iter()
+            .map(|o| {
+                (
+                    o.author.as_str(),
+                    o.lines_from,
+                    o.lines_to,
+                    o.files_from,
+                    o.files_to,
+                )
+            })
+            .collect();
+        deltas.sort();
+
+        // Alice grew main.rs and Bob's lib.rs is new since the first commit
+        assert_eq!(deltas, vec![("Alice", 3, 5, 1, 1), ("Bob", 0, 4, 0, 1)]);
+        assert_eq!(to.max_lines, 9);
+
+        let result = match fd.process() {
+            Ok(()) => true,
+            Err(e) => {
+                error!("test_process_fame_diff ended in error {:?}", e);
+                false
+            }
+        };
+
+        assert!(result, "See error above");
+    }
+}
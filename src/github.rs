@@ -0,0 +1,272 @@
+#![cfg_attr(not(feature = "github"), allow(dead_code))]
+
+use anyhow::Result;
+use serde_derive::Deserialize;
+use std::collections::HashMap;
+
+#[cfg(feature = "github")]
+const TOKEN_VAR: &str = "GITHUB_TOKEN";
+const NOREPLY_DOMAIN: &str = "@users.noreply.github.com";
+
+/// GitHub account an author email belongs to
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GithubUser {
+    pub login: String,
+    pub avatar_url: String,
+}
+
+impl GithubUser {
+    pub fn new(login: &str, avatar_url: &str) -> GithubUser {
+        GithubUser {
+            login: login.to_string(),
+            avatar_url: avatar_url.to_string(),
+        }
+    }
+
+    pub fn profile_url(&self) -> String {
+        format!("https://github.com/{}", self.login)
+    }
+}
+
+/// owner and repository name from a github.com remote, over https or ssh
+fn parse_remote(url: &str) -> Option<(String, String)> {
+    let rest = [
+        "https://github.com/",
+        "ssh://git@github.com/",
+        "git@github.com:",
+    ]
+    .iter()
+    .find_map(|p| url.strip_prefix(p))?;
+
+    let rest = rest.trim_end_matches('/');
+    let rest = rest.strip_suffix(".git").unwrap_or(rest);
+
+    match rest.split_once('/') {
+        Some((owner, repo)) if !owner.is_empty() && !repo.is_empty() && !repo.contains('/') => {
+            Some((owner.to_string(), repo.to_string()))
+        }
+        _ => None,
+    }
+}
+
+/// the account behind a GitHub noreply email, which needs no API call
+fn noreply_user(email: &str) -> Option<GithubUser> {
+    let local = email.strip_suffix(NOREPLY_DOMAIN)?;
+
+    match local.split_once('+') {
+        Some((id, login)) if id.chars().all(|c| c.is_ascii_digit()) => Some(GithubUser::new(
+            login,
+            &format!("https://avatars.githubusercontent.com/u/{}", id),
+        )),
+        _ => Some(GithubUser::new(
+            local,
+            &format!("https://github.com/{}.png", local),
+        )),
+    }
+}
+
+/// the parts of a commit response from the GitHub API read here.  Its top level "author" is
+/// the GitHub account, null when the commit's email isn't linked to one.
+#[derive(Deserialize)]
+struct ApiCommit {
+    author: Option<ApiUser>,
+}
+
+#[derive(Deserialize)]
+struct ApiUser {
+    login: String,
+    avatar_url: Option<String>,
+}
+
+/// the login and avatar of the account that authored the commit in a GitHub API response
+fn commit_author(body: &str) -> Result<Option<GithubUser>> {
+    let commit: ApiCommit = serde_json::from_str(body)?;
+
+    Ok(commit.author.map(|a| {
+        let avatar_url = match a.avatar_url {
+            Some(url) => url,
+            None => format!("https://github.com/{}.png", a.login),
+        };
+
+        GithubUser::new(&a.login, &avatar_url)
+    }))
+}
+
+#[cfg(feature = "github")]
+fn api_commit_author(
+    owner: &str,
+    repo: &str,
+    commit_id: &str,
+    token: &str,
+) -> Result<Option<GithubUser>> {
+    use anyhow::anyhow;
+
+    let url = format!(
+        "https://api.github.com/repos/{}/{}/commits/{}",
+        owner, repo, commit_id
+    );
+
+    let response = ureq::get(&url)
+        .set("Accept", "application/vnd.github+json")
+        .set("Authorization", &format!("Bearer {}", token))
+        .call()
+        .map_err(|e| match e {
+            ureq::Error::Status(status, r) => anyhow!(
+                "GitHub API request for {} failed: {} {}",
+                commit_id,
+                status,
+                r.status_text()
+            ),
+            e => anyhow!("GitHub API request for {} failed: {}", commit_id, e),
+        })?;
+
+    commit_author(&response.into_string()?)
+}
+
+/// maps each author email to its GitHub account.  Noreply emails are read directly, the rest
+/// are looked up with one of their commits through the GitHub API when `GITHUB_TOKEN` is set.
+/// `commits` pairs every email with a commit it authored.
+#[cfg(feature = "github")]
+pub fn resolve_users(
+    repo_path: &str,
+    commits: &[(String, String)],
+) -> Result<HashMap<String, GithubUser>> {
+    use crate::utils::grit_utils;
+
+    let mut users: HashMap<String, GithubUser> = HashMap::new();
+    let mut lookups: HashMap<String, String> = HashMap::new();
+
+    for (email, commit_id) in commits.iter() {
+        if users.contains_key(email) || lookups.contains_key(email) {
+            continue;
+        }
+
+        match noreply_user(email) {
+            Some(u) => {
+                users.insert(email.clone(), u);
+            }
+            None => {
+                lookups.insert(email.clone(), commit_id.clone());
+            }
+        }
+    }
+
+    let token = match std::env::var(TOKEN_VAR) {
+        Ok(t) if !t.is_empty() => t,
+        _ => {
+            if !lookups.is_empty() {
+                warn!(
+                    "{} is not set, only noreply emails are mapped to GitHub users",
+                    TOKEN_VAR
+                );
+            }
+            return Ok(users);
+        }
+    };
+
+    let repo = grit_utils::open_repo(repo_path)?;
+    let remote = repo.find_remote("origin")?;
+    let (owner, name) = match remote.url().and_then(parse_remote) {
+        Some(r) => r,
+        None => {
+            warn!("origin is not a github.com remote, skipping the GitHub API");
+            return Ok(users);
+        }
+    };
+
+    for (email, commit_id) in lookups.iter() {
+        match api_commit_author(&owner, &name, commit_id, &token) {
+            Ok(Some(u)) => {
+                users.insert(email.clone(), u);
+            }
+            Ok(None) => info!("no GitHub user for {}", email),
+            Err(e) => warn!("{}", e),
+        }
+    }
+
+    Ok(users)
+}
+
+#[cfg(not(feature = "github"))]
+pub fn resolve_users(
+    _repo_path: &str,
+    _commits: &[(String, String)],
+) -> Result<HashMap<String, GithubUser>> {
+    Err(anyhow::anyhow!(
+        "grit was built without GitHub support, rebuild it with --features github"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::LevelFilter;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    #[test]
+    fn test_parse_remote() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let expected = Some((String::from("todd-bush"), String::from("grit")));
+
+        assert_eq!(
+            parse_remote("https://github.com/todd-bush/grit.git"),
+            expected
+        );
+        assert_eq!(parse_remote("https://github.com/todd-bush/grit"), expected);
+        assert_eq!(parse_remote("git@github.com:todd-bush/grit.git"), expected);
+        assert_eq!(
+            parse_remote("ssh://git@github.com/todd-bush/grit"),
+            expected
+        );
+        assert_eq!(parse_remote("https://gitlab.com/todd-bush/grit.git"), None);
+        assert_eq!(parse_remote("https://github.com/todd-bush"), None);
+    }
+
+    #[test]
+    fn test_noreply_user() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        assert_eq!(
+            noreply_user("1234+todd-bush@users.noreply.github.com"),
+            Some(GithubUser::new(
+                "todd-bush",
+                "https://avatars.githubusercontent.com/u/1234"
+            ))
+        );
+        assert_eq!(
+            noreply_user("todd-bush@users.noreply.github.com").map(|u| u.login),
+            Some(String::from("todd-bush"))
+        );
+        assert_eq!(noreply_user("todd@example.com"), None);
+    }
+
+    #[test]
+    fn test_commit_author() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let body = r#"{"sha":"abc","commit":{"author":{"name":"Todd","email":"todd@example.com"},"committer":{"name":"GitHub"}},"author": {"login": "todd-bush", "id": 1, "avatar_url": "https://avatars.githubusercontent.com/u/1?v=4"},"committer":{"login":"web-flow"}}"#;
+
+        assert_eq!(
+            commit_author(body).unwrap(),
+            Some(GithubUser::new(
+                "todd-bush",
+                "https://avatars.githubusercontent.com/u/1?v=4"
+            ))
+        );
+
+        let unlinked = r#"{"commit":{"author":{"name":"Todd"}},"author":null,"committer":{"login":"web-flow"}}"#;
+
+        assert_eq!(commit_author(unlinked).unwrap(), None);
+
+        // an author listed after its commit, with braces and escapes in its values
+        let reordered = r#"{"author":{"avatar_url":"https://example.com/a}b.png","login":"todd\"bush"},"commit":{"author":{"name":"Todd"}}}"#;
+
+        assert_eq!(
+            commit_author(reordered).unwrap(),
+            Some(GithubUser::new("todd\"bush", "https://example.com/a}b.png"))
+        );
+        assert!(commit_author("<html>rate limited</html>").is_err());
+    }
+}
@@ -1,18 +1,85 @@
 extern crate tempfile;
 
-use git2::build::RepoBuilder;
+use git2::{Repository, Signature, Time};
 use log::LevelFilter;
 use simple_logger::SimpleLogger;
+use std::fs;
+use std::path::Path;
 use tempfile::{Builder, TempDir};
 
-pub fn init_repo() -> TempDir {
-    let td = Builder::new().prefix("grit-test").tempdir().unwrap();
+/// author, email, unix time and the (path, content) of every file a fixture commit writes
+pub type FixtureCommit = (
+    &'static str,
+    &'static str,
+    i64,
+    &'static [(&'static str, &'static str)],
+);
 
-    info!("test repo file path {}", td.path().to_str().unwrap());
+/// The history `fixture_repo` builds.
+/// Every commit is at noon or later UTC so the dates are the same in any local timezone
+/// within 11 hours of UTC.
+///
+/// At HEAD `src/main.rs` has 5 lines by Alice, `src/lib.rs` 4 lines by Bob and
+/// `README.md` one line each.  2020-01-05 is a Sunday.
+pub const FIXTURE_COMMITS: &[FixtureCommit] = &[
+    (
+        "Alice",
+        "alice@example.com",
+        1_577_880_000, // 2020-01-01 12:00 UTC
+        &[
+            ("src/main.rs", "fn main() {\n    run();\n}\n"),
+            ("README.md", "# fixture\nwork in progress\n"),
+        ],
+    ),
+    (
+        "Bob",
+        "bob@example.com",
+        1_577_966_400, // 2020-01-02 12:00 UTC
+        &[(
+            "src/lib.rs",
+            "pub fn run() {\n    let a = 1;\n    let b = 2;\n}\n",
+        )],
+    ),
+    (
+        "Alice",
+        "alice@example.com",
+        1_577_970_000, // 2020-01-02 13:00 UTC
+        &[("src/main.rs", "fn main() {\n    run();\n}\n\nfn run() {}\n")],
+    ),
+    (
+        "Bob",
+        "bob@example.com",
+        1_578_225_600, // 2020-01-05 12:00 UTC
+        &[("README.md", "# fixture\nrun with cargo run\n")],
+    ),
+];
 
-    RepoBuilder::new()
-        .clone(&"https://github.com/todd-bush/grit.git", td.path())
-        .unwrap();
+/// a repository built locally from `FIXTURE_COMMITS`, so tests can assert on exact numbers
+/// without the network
+pub fn fixture_repo() -> TempDir {
+    let td = Builder::new().prefix("grit-fixture").tempdir().unwrap();
+    let repo = Repository::init(td.path()).unwrap();
+
+    for (author, email, time, files) in FIXTURE_COMMITS.iter() {
+        let mut index = repo.index().unwrap();
+
+        for (name, content) in files.iter() {
+            let full_path = td.path().join(name);
+            fs::create_dir_all(full_path.parent().unwrap()).unwrap();
+            fs::write(&full_path, content).unwrap();
+            index.add_path(Path::new(name)).unwrap();
+        }
+
+        index.write().unwrap();
+
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = Signature::new(author, email, &Time::new(*time, 0)).unwrap();
+        let parent = repo.head().ok().map(|h| h.peel_to_commit().unwrap());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+
+        repo.commit(Some("HEAD"), &sig, &sig, "fixture commit", &tree, &parents)
+            .unwrap();
+    }
 
     td
 }
@@ -0,0 +1,422 @@
+use super::Processable;
+use crate::chart;
+use crate::chart::ChartOptions;
+use crate::diff_stats;
+use crate::ownership::SnapshotPeriod;
+use crate::table::Table;
+use crate::utils::grit_utils;
+use crate::utils::grit_utils::OutputFormat;
+use anyhow::{anyhow, Result};
+use charts::{LineSeriesView, MarkerType, PointDatum, PointLabelPosition, ScaleBand, ScaleLinear};
+use chrono::NaiveDate;
+use git2::{Oid, Repository, Tree};
+use std::path::Path;
+
+pub struct GrowthArgs {
+    path: String,
+    interval: SnapshotPeriod,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+    include: Option<String>,
+    exclude: Option<String>,
+    file: Option<String>,
+    image: bool,
+    html: bool,
+    chart_options: ChartOptions,
+    format: OutputFormat,
+}
+
+impl GrowthArgs {
+    pub fn builder(path: String) -> GrowthArgsBuilder {
+        GrowthArgsBuilder {
+            args: GrowthArgs {
+                path,
+                interval: SnapshotPeriod::Weekly,
+                start_date: None,
+                end_date: None,
+                include: None,
+                exclude: None,
+                file: None,
+                image: false,
+                html: false,
+                chart_options: ChartOptions::default(),
+                format: OutputFormat::Csv,
+            },
+        }
+    }
+}
+
+/// Builds a `GrowthArgs` by name rather than position, validating it on `build`.
+pub struct GrowthArgsBuilder {
+    args: GrowthArgs,
+}
+
+impl GrowthArgsBuilder {
+    pub fn interval(mut self, interval: SnapshotPeriod) -> GrowthArgsBuilder {
+        self.args.interval = interval;
+        self
+    }
+
+    pub fn start_date(mut self, start_date: Option<NaiveDate>) -> GrowthArgsBuilder {
+        self.args.start_date = start_date;
+        self
+    }
+
+    pub fn end_date(mut self, end_date: Option<NaiveDate>) -> GrowthArgsBuilder {
+        self.args.end_date = end_date;
+        self
+    }
+
+    pub fn include(mut self, include: Option<String>) -> GrowthArgsBuilder {
+        self.args.include = include;
+        self
+    }
+
+    pub fn exclude(mut self, exclude: Option<String>) -> GrowthArgsBuilder {
+        self.args.exclude = exclude;
+        self
+    }
+
+    pub fn file(mut self, file: Option<String>) -> GrowthArgsBuilder {
+        self.args.file = file;
+        self
+    }
+
+    pub fn image(mut self, image: bool) -> GrowthArgsBuilder {
+        self.args.image = image;
+        self
+    }
+
+    pub fn html(mut self, html: bool) -> GrowthArgsBuilder {
+        self.args.html = html;
+        self
+    }
+
+    pub fn chart_options(mut self, chart_options: ChartOptions) -> GrowthArgsBuilder {
+        self.args.chart_options = chart_options;
+        self
+    }
+
+    pub fn format(mut self, format: OutputFormat) -> GrowthArgsBuilder {
+        self.args.format = format;
+        self
+    }
+
+    pub fn build(self) -> Result<GrowthArgs> {
+        grit_utils::validate_args(
+            self.args.start_date,
+            self.args.end_date,
+            self.args.file.as_deref(),
+            self.args.format,
+        )?;
+
+        Ok(self.args)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct GrowthOutput {
+    sample: String,
+    date: NaiveDate,
+    commit_id: String,
+    loc: i64,
+}
+
+impl PointDatum<String, f32> for GrowthOutput {
+    fn get_x(&self) -> String {
+        self.sample.clone()
+    }
+
+    fn get_y(&self) -> f32 {
+        self.loc as f32
+    }
+
+    fn get_key(&self) -> String {
+        String::from("")
+    }
+}
+
+pub struct Growth {
+    args: GrowthArgs,
+}
+
+impl Growth {
+    pub fn new(args: GrowthArgs) -> Growth {
+        Growth { args }
+    }
+
+    /// the first parent history of HEAD, oldest commit first
+    pub(crate) fn history(repo: &Repository) -> Result<Vec<(i64, Oid)>> {
+        let mut revwalk = repo.revwalk()?;
+        revwalk.set_sorting(git2::Sort::TIME)?;
+        revwalk.simplify_first_parent()?;
+        revwalk.push_head()?;
+
+        let mut history: Vec<(i64, Oid)> = Vec::new();
+
+        for oid in revwalk {
+            let oid = oid?;
+            let commit = repo.find_commit(oid)?;
+            history.push((commit.time().seconds(), oid));
+        }
+
+        history.sort_by_key(|h| h.0);
+
+        Ok(history)
+    }
+
+    /// lines added minus lines removed going from `old` to `new`, counting only the
+    /// files passing the include and exclude args
+    fn loc_change(&self, repo: &Repository, old: Option<&Tree>, new: &Tree) -> Result<i64> {
+        let stats = diff_stats::tree_diff_stats(
+            repo,
+            old,
+            new,
+            self.args.include.clone(),
+            self.args.exclude.clone(),
+        )?;
+
+        Ok(stats.insertions as i64 - stats.deletions as i64)
+    }
+
+    /// the LOC at the last commit of each interval, built up from the diff between samples
+    /// instead of walking every tree
+    fn process_growth(&self) -> Result<Vec<GrowthOutput>> {
+        let repo = grit_utils::open_repo(&self.args.path)?;
+        let history = Growth::history(&repo)?;
+
+        let first = match history.first() {
+            Some(h) => h,
+            None => return Err(anyhow!("repository has no commits")),
+        };
+
+        let start_date = match self.args.start_date {
+            Some(d) => d,
+            None => grit_utils::convert_git_time(&git2::Time::new(first.0, 0)),
+        };
+        let end_date = self.args.end_date.unwrap_or_else(grit_utils::today);
+
+        let mut output: Vec<GrowthOutput> = Vec::new();
+        let mut previous: Option<(Oid, Tree)> = None;
+        let mut loc: i64 = 0;
+        let mut next = 0;
+
+        for period_end in self.args.interval.period_ends(start_date, end_date) {
+            let end_sec = grit_utils::end_of_day_seconds(period_end);
+
+            while next < history.len() && history[next].0 <= end_sec {
+                next += 1;
+            }
+
+            if next == 0 {
+                continue;
+            }
+
+            let oid = history[next - 1].1;
+
+            match &previous {
+                Some((prev_oid, _)) if *prev_oid == oid => {}
+                _ => {
+                    let tree = repo.find_commit(oid)?.tree()?;
+                    loc += self.loc_change(&repo, previous.as_ref().map(|p| &p.1), &tree)?;
+                    previous = Some((oid, tree));
+                }
+            }
+
+            output.push(GrowthOutput {
+                sample: self.args.interval.label(period_end),
+                date: period_end,
+                commit_id: oid.to_string(),
+                loc,
+            });
+        }
+
+        Ok(output)
+    }
+
+    fn display_text_output(&self, output: &[GrowthOutput]) -> Result<()> {
+        let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+
+        let mut table = Table::new();
+
+        table.set_titles(table_row!["sample", "date", "commit", "loc"]);
+
+        output.iter().for_each(|r| {
+            table.add_row(table_row![
+                r.sample,
+                grit_utils::format_date(r.date),
+                r.commit_id,
+                r.loc
+            ]);
+        });
+
+        table.write(&mut w, self.args.format)?;
+
+        Ok(())
+    }
+
+    fn create_output_image(&self, output: &[GrowthOutput]) -> Result<()> {
+        let file = self
+            .args
+            .file
+            .clone()
+            .unwrap_or_else(|| String::from("growth.svg"));
+
+        if !grit_utils::check_file_type(&file, "svg") {
+            return Err(anyhow!("the image file format must be svg"));
+        }
+
+        let (width, height) = self.args.chart_options.size(if output.len() > 60 {
+            (1920, 960)
+        } else if output.len() > 35 {
+            (1280, 960)
+        } else {
+            (1027, 768)
+        });
+        let (top, right, bottom, left) = (90, 40, 80, 80);
+        let samples = output.iter().map(|o| o.sample.clone()).collect();
+        let max_loc = output.iter().map(|o| o.loc).max().unwrap_or(0) as f32 * 1.05 + 1.0;
+        let x = ScaleBand::new()
+            .set_domain(samples)
+            .set_range(vec![0, width - left - right]);
+        let y = ScaleLinear::new()
+            .set_domain(vec![0_f32, max_loc])
+            .set_range(vec![height - top - bottom, 0]);
+        let line_view = LineSeriesView::new()
+            .set_x_scale(&x)
+            .set_y_scale(&y)
+            .set_marker_type(MarkerType::Circle)
+            .set_label_position(PointLabelPosition::NW)
+            .set_label_visibility(false)
+            .set_colors(self.args.chart_options.theme.colors())
+            .load_data(&output.to_vec())
+            .map_err(|e| anyhow!("Failed to create Line View: {}", e))?;
+        chart::configure_chart(&self.args.chart_options, (width, height), "Growth")
+            .set_margins(top, right, bottom, left)
+            .add_view(&line_view)
+            .add_axis_bottom(&x)
+            .add_axis_left(&y)
+            .add_left_axis_label("LOC")
+            .set_bottom_axis_tick_label_rotation(-45)
+            .save(Path::new(&file))
+            .map_err(|e| anyhow!("Failed to create Chart: {}", e))?;
+
+        if self.args.html {
+            grit_utils::create_html(&file)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Processable<()> for Growth {
+    fn process(&self) -> Result<()> {
+        let output = self.process_growth()?;
+
+        if self.args.image {
+            self.create_output_image(&output)
+        } else {
+            self.display_text_output(&output)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::LevelFilter;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    fn growth_args(path: &str, include: Option<String>) -> GrowthArgs {
+        GrowthArgs::builder(path.to_string())
+            .interval(SnapshotPeriod::Monthly)
+            .include(include)
+            .build()
+            .unwrap()
+    }
+
+    /// total lines of the files in HEAD's tree, the slow way
+    fn head_loc(path: &str, include: &str) -> i64 {
+        let repo = Repository::open(path).unwrap();
+        let tree = repo.head().unwrap().peel_to_tree().unwrap();
+        let pattern = glob::Pattern::new(include).unwrap();
+        let mut loc: i64 = 0;
+
+        tree.walk(git2::TreeWalkMode::PreOrder, |dir, entry| {
+            let name = format!("{}{}", dir, entry.name().unwrap_or_default());
+            if let Ok(blob) = entry.to_object(&repo).and_then(|o| o.peel_to_blob()) {
+                if !blob.is_binary() && pattern.matches(&name) {
+                    let content = blob.content();
+                    let mut lines = content.iter().filter(|b| **b == b'\n').count() as i64;
+                    if !content.is_empty() && content[content.len() - 1] != b'\n' {
+                        lines += 1;
+                    }
+                    loc += lines;
+                }
+            }
+            git2::TreeWalkResult::Ok
+        })
+        .unwrap();
+
+        loc
+    }
+
+    #[test]
+    fn test_process_growth() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let mut args = growth_args(path, Some(String::from("src/*.rs")));
+        args.interval = SnapshotPeriod::Daily;
+        args.end_date = NaiveDate::from_ymd_opt(2020, 1, 5);
+
+        let growth = Growth::new(args);
+
+        let output = growth.process_growth().unwrap();
+
+        let samples: Vec<(String, String, i64)> = output
+            .iter()
+            .map(|o| (o.sample.clone(), grit_utils::format_date(o.date), o.loc))
+            .collect();
+
+        let expected: Vec<(String, String, i64)> = vec![
+            ("2020-01-01", 3),
+            ("2020-01-02", 9),
+            ("2020-01-03", 9),
+            ("2020-01-04", 9),
+            ("2020-01-05", 9),
+        ]
+        .into_iter()
+        .map(|(d, loc)| (d.to_string(), d.to_string(), loc))
+        .collect();
+
+        // Alice's main.rs on the first day, then Bob's lib.rs and her run() the next
+        assert_eq!(samples, expected);
+        assert_eq!(output.last().unwrap().loc, head_loc(path, "src/*.rs"));
+    }
+
+    #[test]
+    fn test_process_growth_csv() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let mut args = growth_args(path, None);
+        args.file = Some(String::from("target/test_growth.csv"));
+
+        let result = match Growth::new(args).process() {
+            Ok(()) => true,
+            Err(e) => {
+                error!("test_process_growth_csv ended in error {:?}", e);
+                false
+            }
+        };
+
+        assert!(result, "See error above");
+    }
+}
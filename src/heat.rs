@@ -0,0 +1,285 @@
+use super::Processable;
+use crate::annotate::{annotate_file, AnnotateLine};
+use crate::github;
+use crate::github::GithubUser;
+use crate::identity;
+use crate::utils::grit_utils;
+use crate::utils::grit_utils::RelativeTo;
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::Write;
+use std::str::FromStr;
+
+const AUTHOR_COLORS: [&str; 10] = [
+    "#fde2e4", "#e2ece9", "#dfe7fd", "#fff1e6", "#e8dff5", "#fce1e4", "#ddedea", "#f0efeb",
+    "#daeaf6", "#fcf4dd",
+];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ColorBy {
+    Age,
+    Author,
+}
+
+impl FromStr for ColorBy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "age" => Ok(ColorBy::Age),
+            "author" => Ok(ColorBy::Author),
+            _ => Err(anyhow!("color-by must be either 'age' or 'author'")),
+        }
+    }
+}
+
+pub struct HeatArgs {
+    path: String,
+    full_path_filename: String,
+    output_file: Option<String>,
+    color_by: ColorBy,
+    relative_to: RelativeTo,
+    github: bool,
+}
+
+impl HeatArgs {
+    pub fn new(
+        path: String,
+        full_path_filename: String,
+        output_file: Option<String>,
+        color_by: ColorBy,
+        relative_to: RelativeTo,
+        github: bool,
+    ) -> HeatArgs {
+        HeatArgs {
+            path,
+            full_path_filename,
+            output_file,
+            color_by,
+            relative_to,
+            github,
+        }
+    }
+}
+
+pub struct Heat {
+    args: HeatArgs,
+}
+
+impl Heat {
+    pub fn new(args: HeatArgs) -> Heat {
+        Heat { args }
+    }
+
+    fn read_source(&self, file_name: &str) -> Result<Vec<String>> {
+        let repo = grit_utils::open_repo(&self.args.path)?;
+        let tree = repo.head()?.peel_to_tree()?;
+        let entry = tree.get_path(&grit_utils::git_path(file_name))?;
+        let object = entry.to_object(&repo)?;
+        let blob = object
+            .as_blob()
+            .ok_or_else(|| anyhow!("{} is not a file", file_name))?;
+
+        let source = String::from_utf8_lossy(blob.content());
+
+        Ok(source.lines().map(|l| l.to_string()).collect())
+    }
+
+    /// newest lines are red, fading to blue for the oldest line in the file
+    fn age_color(age_days: i64, max_age: i64) -> String {
+        let ratio = if max_age > 0 {
+            age_days as f64 / max_age as f64
+        } else {
+            0.0
+        };
+
+        format!("hsl({:.0}, 80%, 85%)", ratio * 220.0)
+    }
+
+    fn author_colors(data: &[AnnotateLine]) -> HashMap<String, String> {
+        let mut colors: HashMap<String, String> = HashMap::new();
+
+        for l in data.iter() {
+            if !colors.contains_key(&l.author) {
+                let color = AUTHOR_COLORS[colors.len() % AUTHOR_COLORS.len()];
+                colors.insert(l.author.clone(), color.to_string());
+            }
+        }
+
+        colors
+    }
+
+    /// the author's GitHub avatar and login when known, otherwise the author
+    fn author_cell(l: &AnnotateLine, users: &HashMap<String, GithubUser>) -> String {
+        match users.get(&l.email) {
+            Some(u) => format!(
+                "<a href=\"{}\"><img src=\"{}\" width=\"16\" height=\"16\" alt=\"\"> {}</a>",
+                grit_utils::html_escape(&u.profile_url()),
+                grit_utils::html_escape(&u.avatar_url),
+                grit_utils::html_escape(&u.login)
+            ),
+            None => grit_utils::html_escape(&l.author),
+        }
+    }
+
+    fn render_html(
+        &self,
+        source: &[String],
+        data: &[AnnotateLine],
+        users: &HashMap<String, GithubUser>,
+    ) -> String {
+        let max_age = data.iter().map(|l| l.age_days).max().unwrap_or(0);
+        let author_colors = Heat::author_colors(data);
+
+        let mut rows: Vec<String> = Vec::new();
+
+        for l in data.iter() {
+            let color = match self.args.color_by {
+                ColorBy::Age => Heat::age_color(l.age_days, max_age),
+                ColorBy::Author => author_colors[&l.author].clone(),
+            };
+
+            let code = source.get(l.line - 1).map(|c| c.as_str()).unwrap_or("");
+
+            rows.push(format!(
+                "<tr style=\"background-color:{}\" title=\"{} {}\"><td class=\"ln\">{}</td><td>{}</td><td>{}</td><td><pre>{}</pre></td></tr>",
+                color,
+                grit_utils::html_escape(&l.commit_id),
+                grit_utils::html_escape(&l.email),
+                l.line,
+                Heat::author_cell(l, users),
+                grit_utils::format_date(l.date),
+                grit_utils::html_escape(code)
+            ));
+        }
+
+        format!(
+            "<html><head><title>{title}</title><style>table {{border-collapse:collapse;font-family:monospace}} td {{padding:0 6px;white-space:nowrap}} pre {{margin:0}} .ln {{text-align:right;color:#666}}</style></head><body><h3>{title}</h3><table>{rows}</table></body></html>",
+            title = grit_utils::html_escape(&self.args.full_path_filename),
+            rows = rows.join("")
+        )
+    }
+}
+
+impl Processable<()> for Heat {
+    fn process(&self) -> Result<()> {
+        let file_name = grit_utils::repo_file_path(
+            &self.args.path,
+            &self.args.full_path_filename,
+            self.args.relative_to,
+        )?;
+
+        let data = annotate_file(&self.args.path, &file_name)?;
+        let source = self.read_source(&file_name)?;
+
+        // GitHub logins and avatars would give the authors away
+        if self.args.github && identity::is_anonymized() {
+            warn!("--github is ignored with --anonymize");
+        }
+
+        let users = if self.args.github && !identity::is_anonymized() {
+            let commits: Vec<(String, String)> = data
+                .iter()
+                .map(|l| (l.email.clone(), l.commit_id.clone()))
+                .collect();
+
+            github::resolve_users(&self.args.path, &commits)?
+        } else {
+            HashMap::new()
+        };
+
+        let html = self.render_html(&source, &data, &users);
+
+        let mut w = match &self.args.output_file {
+            Some(f) => {
+                let file = File::create(f)?;
+                Box::new(file) as Box<dyn Write>
+            }
+            None => Box::new(io::stdout()) as Box<dyn Write>,
+        };
+
+        writeln!(w, "{}", html)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::LevelFilter;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    #[test]
+    fn test_age_color() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        assert_eq!(Heat::age_color(0, 100), "hsl(0, 80%, 85%)");
+        assert_eq!(Heat::age_color(100, 100), "hsl(220, 80%, 85%)");
+        assert_eq!(Heat::age_color(5, 0), "hsl(0, 80%, 85%)");
+    }
+
+    #[test]
+    fn test_author_cell() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let data = annotate_file(path, "README.md").unwrap();
+        let line = &data[0];
+
+        let mut users: HashMap<String, GithubUser> = HashMap::new();
+
+        assert_eq!(
+            Heat::author_cell(line, &users),
+            grit_utils::html_escape(&line.author)
+        );
+
+        users.insert(
+            line.email.clone(),
+            GithubUser::new("todd-bush", "https://avatars.githubusercontent.com/u/1"),
+        );
+
+        let cell = Heat::author_cell(line, &users);
+
+        assert!(cell.starts_with("<a href=\"https://github.com/todd-bush\">"));
+        assert!(cell.contains("src=\"https://avatars.githubusercontent.com/u/1\""));
+    }
+
+    #[test]
+    fn test_heat() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let args = HeatArgs::new(
+            path.to_string(),
+            "README.md".to_string(),
+            Some(String::from("target/test_heat.html")),
+            ColorBy::Author,
+            RelativeTo::Repo,
+            false,
+        );
+
+        let heat = Heat::new(args);
+
+        let result = match heat.process() {
+            Ok(()) => true,
+            Err(e) => {
+                error!("test_heat ended in error {:?}", e);
+                false
+            }
+        };
+
+        assert!(result, "See error above");
+
+        let html = std::fs::read_to_string("target/test_heat.html").unwrap();
+        assert!(html.contains("Alice") && html.contains("Bob"));
+    }
+}
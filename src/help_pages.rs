@@ -0,0 +1,603 @@
+use super::Processable;
+use anyhow::Result;
+use clap::App;
+use clap_mangen::Man;
+use roff::{bold, roman, Roff};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+const MANUAL: &str = "Grit Manual";
+
+/// Example invocations, and what they do, for each subcommand.
+const EXAMPLES: &[(&str, &[(&str, &str)])] = &[
+    (
+        "fame",
+        &[
+            (
+                "grit fame --sort loc",
+                "authors ordered by the lines they own",
+            ),
+            (
+                "grit fame --start-date 2024-01-01 --include 'src/*'",
+                "ownership of src from the commits made since 2024",
+            ),
+            (
+                "grit fame --by-language",
+                "each author's lines per language",
+            ),
+            (
+                "grit fame --anonymize --anonymize-salt \"$SALT\" --format csv",
+                "ownership with every author replaced by a pseudonym, for sharing",
+            ),
+        ],
+    ),
+    (
+        "bydate",
+        &[
+            ("grit bydate --ignore-weekends", "commits per weekday"),
+            (
+                "grit bydate --file commits.svg --image --chart calendar",
+                "a calendar heatmap of commits per day",
+            ),
+            (
+                "grit bydate --cumulative --file burnup.svg --image",
+                "a burn-up chart of the commits made so far",
+            ),
+            (
+                "grit bydate --rolling 7 --file commits.svg --image",
+                "a chart of the weekly moving average of commits",
+            ),
+        ],
+    ),
+    (
+        "byfile",
+        &[(
+            "grit byfile --in-file src/main.rs",
+            "commits per author and day to src/main.rs",
+        )],
+    ),
+    (
+        "effort",
+        &[
+            (
+                "grit effort --table",
+                "commits and active days per file as a table",
+            ),
+            (
+                "grit effort --by-dir=2",
+                "the same rolled up to two directory levels",
+            ),
+            (
+                "grit effort --table --min-loc 20 --min-age-days 30",
+                "only the files of 20 lines or more that are at least a month old",
+            ),
+        ],
+    ),
+    (
+        "annotate",
+        &[(
+            "grit annotate src/main.rs",
+            "per line blame of src/main.rs as JSON",
+        )],
+    ),
+    (
+        "heat",
+        &[(
+            "grit heat src/main.rs --file main.html --color-by author",
+            "src/main.rs as HTML with each line colored by its author",
+        )],
+    ),
+    (
+        "fame-diff",
+        &[(
+            "grit fame-diff --from v1.0 --to v2.0",
+            "ownership change between two tags",
+        )],
+    ),
+    (
+        "ownership",
+        &[
+            (
+                "grit ownership --snapshots yearly --depth 1",
+                "the top owner of each top level directory every year",
+            ),
+            (
+                "grit ownership --treemap --image --file ownership.svg",
+                "a treemap of the directory tree colored by each file's owner",
+            ),
+        ],
+    ),
+    (
+        "growth",
+        &[(
+            "grit growth --interval monthly --file growth.svg --image",
+            "a chart of the LOC at the end of each month",
+        )],
+    ),
+    (
+        "survival",
+        &[(
+            "grit survival --interval quarterly",
+            "the share of each quarter's lines still alive in later quarters",
+        )],
+    ),
+    (
+        "tenure",
+        &[(
+            "grit tenure --inactive-days 180",
+            "authors idle for half a year marked inactive",
+        )],
+    ),
+    (
+        "worklife",
+        &[(
+            "grit worklife --work-hours 08:30-17:00 --timezone author",
+            "weekend and after hours commits in each author's own clock",
+        )],
+    ),
+    (
+        "commitsize",
+        &[
+            (
+                "grit commitsize",
+                "lines changed per commit for each author",
+            ),
+            (
+                "grit commitsize --list-giant --giant-lines 5000",
+                "the commits changing 5000 lines or more",
+            ),
+        ],
+    ),
+    (
+        "log-stats",
+        &[(
+            "grit log-stats --start-date 2024-01-01 --file commits.csv",
+            "this year's commits with their lines added and removed, for a spreadsheet",
+        )],
+    ),
+    (
+        "merges",
+        &[(
+            "grit merges --start-date 2024-01-01",
+            "hours from first branch commit to merge for this year's merges",
+        )],
+    ),
+    (
+        "compare",
+        &[(
+            "grit compare --base main --head feature/x",
+            "the commits, authors and lines feature/x adds over main, for a pre-merge summary",
+        )],
+    ),
+    (
+        "branches",
+        &[(
+            "grit branches --base main --older-than 90",
+            "branches idle for three months, with how far they trail main, for cleanup",
+        )],
+    ),
+    (
+        "releases",
+        &[(
+            "grit releases --tag-pattern 'v*'",
+            "the commits, authors and days between each v tag, for the release cadence",
+        )],
+    ),
+    (
+        "newcontributors",
+        &[(
+            "grit newcontributors --start-date 2024-01-01",
+            "first-time and returning contributors for each month of this year",
+        )],
+    ),
+    (
+        "lint-history",
+        &[
+            (
+                "grit lint-history --fail-under 90",
+                "fail CI when under 90% of the commits are conventional",
+            ),
+            (
+                "grit lint-history --pattern '^[A-Z]+-[0-9]+ '",
+                "the share of commits starting with a ticket id",
+            ),
+        ],
+    ),
+    (
+        "identities",
+        &[(
+            "grit identities --write-aliases aliases.toml --interactive",
+            "confirm the suggested author merges and save them for --alias-file",
+        )],
+    ),
+    (
+        "age",
+        &[(
+            "grit age --older-than 24",
+            "line age percentiles and the share of lines over two years old",
+        )],
+    ),
+    (
+        "timeline",
+        &[(
+            "grit timeline --top 8 --file timeline.svg --image",
+            "a stacked area chart of the weekly commits of the eight biggest contributors",
+        )],
+    ),
+    (
+        "snapshot",
+        &[(
+            "grit snapshot",
+            "record the current ownership and activity of every author in the git directory's store",
+        )],
+    ),
+    (
+        "trends",
+        &[(
+            "grit trends --since 2024-01-01",
+            "how ownership and activity changed from the first snapshot of 2024 to the latest",
+        )],
+    ),
+    (
+        "report",
+        &[(
+            "grit report --out-dir report --format json",
+            "every analysis written as json to the report directory, sharing the blame and commit walk",
+        )],
+    ),
+    (
+        "tui",
+        &[("grit tui", "browse fame, bydate and effort interactively")],
+    ),
+    (
+        "serve",
+        &[(
+            "grit serve --port 9000",
+            "answer /fame, /bydate and /effort requests with JSON on port 9000",
+        )],
+    ),
+    (
+        "completions",
+        &[(
+            "grit completions bash > /etc/bash_completion.d/grit",
+            "install bash completions",
+        )],
+    ),
+    (
+        "help-pages",
+        &[(
+            "grit help-pages --out-dir man",
+            "write the man pages to ./man",
+        )],
+    ),
+];
+
+/// An option or positional argument of a subcommand.
+#[derive(Clone, Debug, PartialEq)]
+struct OptionPage {
+    short: Option<char>,
+    long: Option<String>,
+    /// the value name when the option takes a value
+    value: Option<String>,
+    help: String,
+    values: Vec<String>,
+    default: Option<String>,
+}
+
+impl OptionPage {
+    fn usage(&self) -> String {
+        let flag = match (&self.long, self.short) {
+            (Some(l), _) => format!("--{}", l),
+            (None, Some(s)) => format!("-{}", s),
+            (None, None) => String::new(),
+        };
+
+        match (&self.value, flag.is_empty()) {
+            (Some(v), true) => format!("<{}>", v),
+            (Some(v), false) => format!("{}=<{}>", flag, v),
+            (None, _) => flag,
+        }
+    }
+
+    /// the short and long forms, e.g. `-d, --debug`
+    fn flags(&self) -> String {
+        let mut flags: Vec<String> = Vec::new();
+        if let Some(s) = self.short {
+            flags.push(format!("-{}", s));
+        }
+        flags.push(self.usage());
+        flags.retain(|f| !f.is_empty());
+        flags.dedup();
+        flags.join(", ")
+    }
+
+    fn notes(&self) -> String {
+        let mut notes: Vec<String> = Vec::new();
+        if !self.values.is_empty() {
+            notes.push(format!("Possible values: {}.", self.values.join(", ")));
+        }
+        if let Some(d) = &self.default {
+            notes.push(format!("Default: {}.", d));
+        }
+        notes.join("  ")
+    }
+}
+
+/// The man page and long form help of one subcommand.
+#[derive(Clone, Debug)]
+struct CommandPage {
+    name: String,
+    about: String,
+    options: Vec<OptionPage>,
+    command: App<'static>,
+}
+
+impl CommandPage {
+    /// the pages of every subcommand of `app`
+    fn from_app(app: &App<'static>) -> Vec<CommandPage> {
+        app.get_subcommands()
+            .map(|sc| {
+                let mut options: Vec<OptionPage> = sc
+                    .get_arguments()
+                    .filter(|a| a.get_id() != "version" || sc.get_version().is_some())
+                    .map(|a| OptionPage {
+                        short: a.get_short(),
+                        long: a.get_long().map(String::from),
+                        value: if a.is_takes_value_set() || a.is_positional() {
+                            Some(a.get_id().to_string())
+                        } else {
+                            None
+                        },
+                        help: a.get_help().unwrap_or_default().to_string(),
+                        values: a
+                            .get_possible_values()
+                            .map(|pv| pv.iter().map(|v| v.get_name().to_string()).collect())
+                            .unwrap_or_default(),
+                        default: Some(
+                            a.get_default_values()
+                                .iter()
+                                .map(|v| v.to_string_lossy().to_string())
+                                .collect::<Vec<String>>()
+                                .join(","),
+                        )
+                        .filter(|d| !d.is_empty()),
+                    })
+                    .collect();
+
+                // clap adds --help ahead of the subcommand's own options
+                options.sort_by_key(|o| o.long.as_deref() == Some("help"));
+
+                CommandPage {
+                    name: sc.get_name().to_string(),
+                    about: sc.get_about().unwrap_or_default().to_string(),
+                    options,
+                    command: sc.clone(),
+                }
+            })
+            .collect()
+    }
+
+    fn synopsis(&self) -> String {
+        let mut synopsis = format!("grit {}", self.name);
+        for o in self.options.iter() {
+            if o.long.is_none() && o.short.is_none() {
+                let _ = write!(synopsis, " {}", o.usage());
+            } else {
+                let _ = write!(synopsis, " [{}]", o.usage());
+            }
+        }
+        synopsis
+    }
+
+    fn examples(&self) -> &'static [(&'static str, &'static str)] {
+        EXAMPLES
+            .iter()
+            .find(|(name, _)| *name == self.name)
+            .map(|e| e.1)
+            .unwrap_or(&[])
+    }
+
+    /// the grit-<name>(1) page clap_mangen renders, followed by the examples
+    fn man(&self, version: &str) -> Result<String> {
+        let name = format!("grit-{}", self.name);
+        let mut page: Vec<u8> = Vec::new();
+
+        Man::new(self.command.clone().name(&name))
+            .title(name.to_uppercase())
+            .source(format!("grit {}", version))
+            .manual(MANUAL)
+            .render(&mut page)?;
+
+        let examples = self.examples();
+        if !examples.is_empty() {
+            let mut roff = Roff::new();
+            roff.control("SH", ["EXAMPLES"]);
+            for (command, description) in examples.iter() {
+                roff.control("TP", []);
+                roff.text([bold(*command)]);
+                roff.text([roman(*description)]);
+            }
+            roff.to_writer(&mut page)?;
+        }
+
+        Ok(String::from_utf8(page)?)
+    }
+
+    fn markdown(&self) -> String {
+        let mut s = String::new();
+
+        let _ = writeln!(s, "# grit {}\n\n{}\n", self.name, self.about);
+        let _ = writeln!(s, "## Usage\n\n    {}\n", self.synopsis());
+
+        if !self.options.is_empty() {
+            s.push_str("## Options\n\n");
+            for o in self.options.iter() {
+                let _ = write!(s, "- ```{}``` {}", o.flags(), o.help);
+                let notes = o.notes();
+                if !notes.is_empty() {
+                    let _ = write!(s, "  {}", notes);
+                }
+                s.push('\n');
+            }
+            s.push('\n');
+        }
+
+        let examples = self.examples();
+        if !examples.is_empty() {
+            s.push_str("## Examples\n\n");
+            for (command, description) in examples.iter() {
+                let _ = writeln!(s, "{}:\n\n    {}\n", description, command);
+            }
+        }
+
+        s
+    }
+}
+
+pub struct HelpPagesArgs {
+    out_dir: String,
+    app: App<'static>,
+}
+
+impl HelpPagesArgs {
+    pub fn new(out_dir: String, app: App<'static>) -> HelpPagesArgs {
+        HelpPagesArgs { out_dir, app }
+    }
+}
+
+pub struct HelpPages {
+    args: HelpPagesArgs,
+}
+
+impl HelpPages {
+    pub fn new(args: HelpPagesArgs) -> HelpPages {
+        HelpPages { args }
+    }
+
+    /// the grit(1) page listing every subcommand
+    fn index_man(&self, version: &str) -> Result<String> {
+        let mut page: Vec<u8> = Vec::new();
+
+        Man::new(self.args.app.clone().name("grit"))
+            .title("GRIT")
+            .source(format!("grit {}", version))
+            .manual(MANUAL)
+            .render(&mut page)?;
+
+        Ok(String::from_utf8(page)?)
+    }
+
+    /// writes the pages and returns the paths written
+    fn write_pages(&self) -> Result<Vec<String>> {
+        let version = env!("CARGO_PKG_VERSION");
+        let dir = Path::new(&self.args.out_dir);
+        fs::create_dir_all(dir)?;
+
+        let mut files: Vec<(String, String)> =
+            vec![(String::from("grit.1"), self.index_man(version)?)];
+
+        for p in CommandPage::from_app(&self.args.app).iter() {
+            files.push((format!("grit-{}.1", p.name), p.man(version)?));
+            files.push((format!("grit-{}.md", p.name), p.markdown()));
+        }
+
+        let mut written: Vec<String> = Vec::new();
+
+        for (name, content) in files.iter() {
+            let path = dir.join(name);
+            fs::write(&path, content)?;
+            written.push(path.to_string_lossy().to_string());
+        }
+
+        Ok(written)
+    }
+}
+
+impl Processable<()> for HelpPages {
+    fn process(&self) -> Result<()> {
+        let written = self.write_pages()?;
+
+        info!(
+            "wrote {} help pages to {}",
+            written.len(),
+            self.args.out_dir
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Arg;
+    use log::LevelFilter;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    fn app() -> App<'static> {
+        App::new("grit").subcommand(
+            App::new("fame")
+                .about("will create a table of metrics per author.  This may take a while")
+                .args(&[
+                    Arg::new("sort")
+                        .help("sort field, either 'commit', 'loc' or 'files'")
+                        .takes_value(true)
+                        .possible_values(["commit", "loc", "files"])
+                        .default_value("commit")
+                        .long("sort"),
+                    Arg::new("debug")
+                        .help("enables debug")
+                        .short('d')
+                        .long("debug"),
+                ]),
+        )
+    }
+
+    #[test]
+    fn test_command_page() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let pages = CommandPage::from_app(&app());
+        let fame = &pages[0];
+
+        assert_eq!(
+            fame.synopsis(),
+            "grit fame [--sort=<sort>] [--debug] [--help]"
+        );
+        assert_eq!(fame.options[1].flags(), "-d, --debug");
+        assert_eq!(
+            fame.options[0].notes(),
+            "Possible values: commit, loc, files.  Default: commit."
+        );
+
+        let man = fame.man("1.0.0").unwrap();
+        assert!(man.contains(".TH GRIT-FAME 1  \"grit 1.0.0\" \"Grit Manual\"\n"));
+        assert!(man.contains("grit\\-fame \\- will create a table of metrics per author"));
+        assert!(man.contains("\\fB\\-\\-sort\\fR [default: commit]\n"));
+        assert!(man.contains(".SH EXAMPLES\n.TP\n\\fBgrit fame \\-\\-sort loc\\fR\n"));
+
+        let markdown = fame.markdown();
+        assert!(markdown.contains("    grit fame --sort loc\n"));
+    }
+
+    #[test]
+    fn test_write_pages() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td = TempDir::new().unwrap();
+        let out_dir = td.path().join("man").to_str().unwrap().to_string();
+
+        let written = HelpPages::new(HelpPagesArgs::new(out_dir, app()))
+            .write_pages()
+            .unwrap();
+
+        assert_eq!(written.len(), 3);
+        assert!(written.iter().all(|w| Path::new(w).exists()));
+        assert!(fs::read_to_string(&written[0])
+            .unwrap()
+            .contains("grit\\-fame(1)"));
+    }
+}
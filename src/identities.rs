@@ -0,0 +1,640 @@
+use super::Processable;
+use crate::identity;
+use crate::table::Table;
+use crate::utils::grit_utils;
+use crate::utils::grit_utils::OutputFormat;
+use anyhow::Result;
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, Write};
+use std::iter;
+
+/// names shorter than this are only clustered when they are equal, as a few edits turn
+/// any short name into another
+const MIN_FUZZY_NAME: usize = 4;
+
+/// email local parts shorter than this are too common to cluster on
+const MIN_LOCAL_PART: usize = 3;
+
+pub struct IdentitiesArgs {
+    path: String,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+    max_distance: usize,
+    write_aliases: Option<String>,
+    write_mailmap: Option<String>,
+    interactive: bool,
+    file: Option<String>,
+    format: OutputFormat,
+}
+
+impl IdentitiesArgs {
+    pub fn builder(path: String) -> IdentitiesArgsBuilder {
+        IdentitiesArgsBuilder {
+            args: IdentitiesArgs {
+                path,
+                start_date: None,
+                end_date: None,
+                max_distance: 2,
+                write_aliases: None,
+                write_mailmap: None,
+                interactive: false,
+                file: None,
+                format: OutputFormat::Csv,
+            },
+        }
+    }
+}
+
+/// Builds a `IdentitiesArgs` by name rather than position, validating it on `build`.
+pub struct IdentitiesArgsBuilder {
+    args: IdentitiesArgs,
+}
+
+impl IdentitiesArgsBuilder {
+    pub fn start_date(mut self, start_date: Option<NaiveDate>) -> IdentitiesArgsBuilder {
+        self.args.start_date = start_date;
+        self
+    }
+
+    pub fn end_date(mut self, end_date: Option<NaiveDate>) -> IdentitiesArgsBuilder {
+        self.args.end_date = end_date;
+        self
+    }
+
+    pub fn max_distance(mut self, max_distance: usize) -> IdentitiesArgsBuilder {
+        self.args.max_distance = max_distance;
+        self
+    }
+
+    pub fn write_aliases(mut self, write_aliases: Option<String>) -> IdentitiesArgsBuilder {
+        self.args.write_aliases = write_aliases;
+        self
+    }
+
+    pub fn write_mailmap(mut self, write_mailmap: Option<String>) -> IdentitiesArgsBuilder {
+        self.args.write_mailmap = write_mailmap;
+        self
+    }
+
+    pub fn interactive(mut self, interactive: bool) -> IdentitiesArgsBuilder {
+        self.args.interactive = interactive;
+        self
+    }
+
+    pub fn file(mut self, file: Option<String>) -> IdentitiesArgsBuilder {
+        self.args.file = file;
+        self
+    }
+
+    pub fn format(mut self, format: OutputFormat) -> IdentitiesArgsBuilder {
+        self.args.format = format;
+        self
+    }
+
+    pub fn build(self) -> Result<IdentitiesArgs> {
+        grit_utils::validate_args(
+            self.args.start_date,
+            self.args.end_date,
+            self.args.file.as_deref(),
+            self.args.format,
+        )?;
+
+        Ok(self.args)
+    }
+}
+
+/// A name and email pair authors committed under.
+#[derive(Clone, Debug, PartialEq)]
+struct Variant {
+    name: String,
+    email: String,
+    commits: usize,
+}
+
+impl Variant {
+    fn display(&self) -> String {
+        format!("{} <{}>", self.name, self.email)
+    }
+}
+
+/// Variants likely to be the same person, the one with the most commits first.
+#[derive(Clone, Debug, PartialEq)]
+struct Cluster {
+    variants: Vec<Variant>,
+}
+
+impl Cluster {
+    fn canonical(&self) -> &Variant {
+        &self.variants[0]
+    }
+
+    fn aliases(&self) -> &[Variant] {
+        &self.variants[1..]
+    }
+
+    fn commits(&self) -> usize {
+        self.variants.iter().map(|v| v.commits).sum()
+    }
+}
+
+/// the lowercased letters and digits of a name, so punctuation and spacing don't matter
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// the lowercased part of an email before the @, without a +tag.  GitHub noreply
+/// addresses, `12345+login@users.noreply.github.com`, give the login.
+fn local_part(email: &str) -> String {
+    let local = email.split('@').next().unwrap_or_default().to_lowercase();
+
+    if email.to_lowercase().ends_with("@users.noreply.github.com") {
+        if let Some((_, login)) = local.split_once('+') {
+            return login.to_string();
+        }
+    }
+
+    local.split('+').next().unwrap_or_default().to_string()
+}
+
+/// the fewest single character insertions, deletions and substitutions turning `a` into `b`
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = (above + 1)
+                .min(row[j] + 1)
+                .min(diagonal + usize::from(ca != *cb));
+            diagonal = above;
+        }
+    }
+
+    row[b.len()]
+}
+
+/// true when the two variants share an email, a name, an email local part, or names
+/// within `max_distance` edits of each other
+fn similar(a: &Variant, b: &Variant, max_distance: usize) -> bool {
+    if !a.email.is_empty() && a.email.to_lowercase() == b.email.to_lowercase() {
+        return true;
+    }
+
+    let (na, nb) = (normalize_name(&a.name), normalize_name(&b.name));
+
+    if !na.is_empty() && na == nb {
+        return true;
+    }
+
+    let (la, lb) = (local_part(&a.email), local_part(&b.email));
+
+    if la.chars().count() >= MIN_LOCAL_PART && la == lb {
+        return true;
+    }
+
+    na.chars().count().min(nb.chars().count()) >= MIN_FUZZY_NAME
+        && levenshtein(&na, &nb) <= max_distance
+}
+
+/// groups the variants linked, directly or through others, by `similar`.  The clusters
+/// with the most commits come first.
+fn cluster(variants: Vec<Variant>, max_distance: usize) -> Vec<Cluster> {
+    let mut parent: Vec<usize> = (0..variants.len()).collect();
+
+    fn root(parent: &mut [usize], i: usize) -> usize {
+        let mut r = i;
+        while parent[r] != r {
+            r = parent[r];
+        }
+        parent[i] = r;
+        r
+    }
+
+    for i in 0..variants.len() {
+        for j in (i + 1)..variants.len() {
+            if similar(&variants[i], &variants[j], max_distance) {
+                let (ri, rj) = (root(&mut parent, i), root(&mut parent, j));
+                parent[ri.max(rj)] = ri.min(rj);
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<Variant>> = HashMap::new();
+
+    for (i, v) in variants.into_iter().enumerate() {
+        groups.entry(root(&mut parent, i)).or_default().push(v);
+    }
+
+    let mut clusters: Vec<Cluster> = groups
+        .into_values()
+        .map(|mut variants| {
+            variants.sort_by(|a, b| b.commits.cmp(&a.commits).then(a.name.cmp(&b.name)));
+            Cluster { variants }
+        })
+        .collect();
+
+    clusters.sort_by(|a, b| {
+        b.commits()
+            .cmp(&a.commits())
+            .then(a.canonical().name.cmp(&b.canonical().name))
+    });
+
+    clusters
+}
+
+/// asks on `output` whether to merge each cluster of several variants, keeping those
+/// answered with anything but n
+fn confirm(
+    clusters: Vec<Cluster>,
+    input: &mut dyn BufRead,
+    output: &mut dyn Write,
+) -> Result<Vec<Cluster>> {
+    let mut confirmed = Vec::new();
+
+    for c in clusters.into_iter().filter(|c| !c.aliases().is_empty()) {
+        writeln!(output, "merge into {}:", c.canonical().display())?;
+        for a in c.aliases() {
+            writeln!(output, "    {}", a.display())?;
+        }
+        write!(output, "[Y/n] ")?;
+        output.flush()?;
+
+        let mut answer = String::new();
+        input.read_line(&mut answer)?;
+
+        if !answer.trim().to_lowercase().starts_with('n') {
+            confirmed.push(c);
+        }
+    }
+
+    Ok(confirmed)
+}
+
+/// `value` as a quoted toml string, escaped as needed
+fn toml_string(value: &str) -> String {
+    toml::Value::String(value.to_string()).to_string()
+}
+
+/// the clusters as `--alias-file` entries, each alias listed by the names and emails its
+/// canonical identity does not already have
+fn alias_file(clusters: &[Cluster]) -> String {
+    let mut content = String::from("# suggested by grit identities\n");
+
+    for c in clusters.iter().filter(|c| !c.aliases().is_empty()) {
+        let canonical = c.canonical();
+        let mut aliases: Vec<String> = Vec::new();
+
+        for a in c.aliases() {
+            for key in [&a.name, &a.email] {
+                let taken = iter::once(&canonical.name)
+                    .chain(iter::once(&canonical.email))
+                    .chain(aliases.iter())
+                    .any(|k| k.to_lowercase() == key.to_lowercase());
+
+                if !taken && !key.is_empty() {
+                    aliases.push(key.clone());
+                }
+            }
+        }
+
+        let quoted: Vec<String> = aliases.iter().map(|a| toml_string(a)).collect();
+        content.push_str(&format!(
+            "{} = [{}]\n",
+            toml_string(&canonical.display()),
+            quoted.join(", ")
+        ));
+    }
+
+    content
+}
+
+/// the clusters as .mailmap lines mapping each alias to its canonical identity
+fn mailmap(clusters: &[Cluster]) -> String {
+    let mut content = String::new();
+
+    for c in clusters.iter() {
+        for a in c.aliases() {
+            content.push_str(&format!("{} {}\n", c.canonical().display(), a.display()));
+        }
+    }
+
+    content
+}
+
+pub struct Identities {
+    args: IdentitiesArgs,
+}
+
+impl Identities {
+    pub fn new(args: IdentitiesArgs) -> Identities {
+        Identities { args }
+    }
+
+    /// the name and email pairs of every commit author in the date range
+    fn process_variants(&self) -> Result<Vec<Variant>> {
+        let repo = grit_utils::open_repo(&self.args.path)?;
+
+        let mut revwalk = repo.revwalk()?;
+        revwalk.set_sorting(git2::Sort::TIME)?;
+        revwalk.push_head()?;
+
+        let mut variants: HashMap<(String, String), usize> = HashMap::new();
+
+        for oid in revwalk {
+            let commit = repo.find_commit(oid?)?;
+
+            let day = grit_utils::convert_git_time(&commit.time());
+
+            if self.args.start_date.map(|d| day < d).unwrap_or(false)
+                || self.args.end_date.map(|d| day > d).unwrap_or(false)
+            {
+                continue;
+            }
+
+            let author = commit.author();
+            let name = String::from_utf8_lossy(author.name_bytes()).to_string();
+            let email = String::from_utf8_lossy(author.email_bytes()).to_string();
+
+            if identity::is_excluded(&name, &email) {
+                continue;
+            }
+
+            *variants.entry((name, email)).or_insert(0) += 1;
+        }
+
+        Ok(variants
+            .into_iter()
+            .map(|((name, email), commits)| Variant {
+                name,
+                email,
+                commits,
+            })
+            .collect())
+    }
+
+    fn display_output(&self, clusters: &[Cluster]) -> Result<()> {
+        let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+
+        let mut table = Table::new();
+
+        table.set_titles(table_row![
+            "cluster",
+            "canonical",
+            "name",
+            "email",
+            "commits"
+        ]);
+
+        for (i, c) in clusters.iter().enumerate() {
+            for v in c.variants.iter() {
+                table.add_row(table_row![
+                    i + 1,
+                    c.canonical().display(),
+                    v.name,
+                    v.email,
+                    v.commits
+                ]);
+            }
+        }
+
+        table.add_label(1, "canonical");
+        table.add_label(3, "email");
+        table.add_metric(
+            4,
+            "grit_identity_commits",
+            "Commits made under the identity.",
+        );
+
+        table.write(&mut w, self.args.format)?;
+
+        Ok(())
+    }
+
+    /// writes the alias file and mailmap asked for, from the clusters of several variants
+    fn write_suggestions(&self, clusters: &[Cluster]) -> Result<()> {
+        if let Some(path) = &self.args.write_aliases {
+            write!(File::create(path)?, "{}", alias_file(clusters))?;
+            info!("wrote the suggested aliases to {}", path);
+        }
+
+        if let Some(path) = &self.args.write_mailmap {
+            write!(File::create(path)?, "{}", mailmap(clusters))?;
+            info!("wrote the suggested mailmap to {}", path);
+        }
+
+        Ok(())
+    }
+}
+
+impl Processable<()> for Identities {
+    fn process(&self) -> Result<()> {
+        let clusters = cluster(self.process_variants()?, self.args.max_distance);
+
+        self.display_output(&clusters)?;
+
+        if self.args.write_aliases.is_none() && self.args.write_mailmap.is_none() {
+            return Ok(());
+        }
+
+        let clusters = if self.args.interactive {
+            confirm(clusters, &mut io::stdin().lock(), &mut io::stderr())?
+        } else {
+            clusters
+        };
+
+        self.write_suggestions(&clusters)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::{Repository, Signature, Time};
+    use log::LevelFilter;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    fn variant(name: &str, email: &str, commits: usize) -> Variant {
+        Variant {
+            name: name.to_string(),
+            email: email.to_string(),
+            commits,
+        }
+    }
+
+    /// the fixture followed by commits from Alice's GitHub noreply address, Bob at work
+    /// and Carol under two spellings
+    fn identities_repo() -> TempDir {
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let repo = Repository::open(td.path()).unwrap();
+
+        let authors = [
+            ("alice", "1234+alice@users.noreply.github.com"),
+            ("Robert", "bob@work.example.com"),
+            ("Carol Jones", "carol@example.com"),
+            ("Carol Jones", "carol@example.com"),
+            ("carol jnoes", "cj@home.example.com"),
+        ];
+
+        for (i, (name, email)) in authors.iter().enumerate() {
+            let head = repo.head().unwrap().peel_to_commit().unwrap();
+            let sig = Signature::new(
+                name,
+                email,
+                &Time::new(1_578_312_000 + i as i64 * 86_400, 0),
+            )
+            .unwrap();
+
+            repo.commit(
+                Some("HEAD"),
+                &sig,
+                &sig,
+                "fixture commit",
+                &head.tree().unwrap(),
+                &[&head],
+            )
+            .unwrap();
+        }
+
+        td
+    }
+
+    #[test]
+    fn test_levenshtein() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "bob"), 3);
+        assert_eq!(levenshtein("caroljones", "caroljnoes"), 2);
+        assert_eq!(local_part("1234+alice@users.noreply.github.com"), "alice");
+        assert_eq!(local_part("Bob+grit@example.com"), "bob");
+    }
+
+    #[test]
+    fn test_cluster() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let clusters = cluster(
+            vec![
+                variant("Al", "al@example.com", 1),
+                variant("Bo", "bo@example.com", 1),
+                variant("todd-bush", "todd@example.com", 1),
+                variant("Todd Bush", "tbush@example.com", 3),
+            ],
+            2,
+        );
+
+        assert_eq!(clusters.len(), 3);
+        assert_eq!(clusters[0].canonical().name, "Todd Bush");
+        assert_eq!(clusters[0].aliases()[0].name, "todd-bush");
+        assert!(clusters[1..].iter().all(|c| c.aliases().is_empty()));
+    }
+
+    #[test]
+    fn test_process_identities() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td = identities_repo();
+        let path = td.path().to_str().unwrap();
+        let aliases = "target/test_identities_aliases.toml";
+        let mailmap_file = "target/test_identities.mailmap";
+
+        let identities = Identities::new(
+            IdentitiesArgs::builder(path.to_string())
+                .write_aliases(Some(aliases.to_string()))
+                .write_mailmap(Some(mailmap_file.to_string()))
+                .file(Some(String::from("target/test_identities.csv")))
+                .build()
+                .unwrap(),
+        );
+
+        identities.process().unwrap();
+
+        let csv = std::fs::read_to_string("target/test_identities.csv").unwrap();
+        assert_eq!(
+            csv,
+            "cluster,canonical,name,email,commits\n\
+             1,Alice <alice@example.com>,Alice,alice@example.com,2\n\
+             1,Alice <alice@example.com>,alice,1234+alice@users.noreply.github.com,1\n\
+             2,Bob <bob@example.com>,Bob,bob@example.com,2\n\
+             2,Bob <bob@example.com>,Robert,bob@work.example.com,1\n\
+             3,Carol Jones <carol@example.com>,Carol Jones,carol@example.com,2\n\
+             3,Carol Jones <carol@example.com>,carol jnoes,cj@home.example.com,1\n"
+        );
+
+        let written = std::fs::read_to_string(aliases).unwrap();
+        assert_eq!(
+            written,
+            "# suggested by grit identities\n\
+             \"Alice <alice@example.com>\" = [\"1234+alice@users.noreply.github.com\"]\n\
+             \"Bob <bob@example.com>\" = [\"Robert\", \"bob@work.example.com\"]\n\
+             \"Carol Jones <carol@example.com>\" = [\"carol jnoes\", \"cj@home.example.com\"]\n"
+        );
+
+        let parsed = identity::Aliases::parse(&written).unwrap();
+        assert_eq!(
+            parsed.resolve("Robert", "bob@work.example.com"),
+            (String::from("Bob"), String::from("bob@example.com"))
+        );
+
+        assert_eq!(
+            std::fs::read_to_string(mailmap_file).unwrap(),
+            "Alice <alice@example.com> alice <1234+alice@users.noreply.github.com>\n\
+             Bob <bob@example.com> Robert <bob@work.example.com>\n\
+             Carol Jones <carol@example.com> carol jnoes <cj@home.example.com>\n"
+        );
+    }
+
+    #[test]
+    fn test_alias_file_quotes() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let clusters = vec![Cluster {
+            variants: vec![
+                variant("Bush, Todd", "todd@example.com", 2),
+                variant("Todd \"T\" Bush", "todd=bush@example.com", 1),
+            ],
+        }];
+
+        let parsed = identity::Aliases::parse(&alias_file(&clusters)).unwrap();
+        let todd = (String::from("Bush, Todd"), String::from("todd@example.com"));
+
+        assert_eq!(parsed.resolve("Todd \"T\" Bush", "t@home.local"), todd);
+        assert_eq!(parsed.resolve("T", "todd=bush@example.com"), todd);
+    }
+
+    #[test]
+    fn test_confirm() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let clusters = cluster(
+            vec![
+                variant("Alice", "alice@example.com", 2),
+                variant("alice", "alice@home.example.com", 1),
+                variant("Bob", "bob@example.com", 2),
+                variant("Bob", "bob@home.example.com", 1),
+                variant("Carol", "carol@example.com", 1),
+            ],
+            2,
+        );
+
+        let mut prompts: Vec<u8> = Vec::new();
+        let confirmed = confirm(clusters, &mut "y\nn\n".as_bytes(), &mut prompts).unwrap();
+
+        assert_eq!(confirmed.len(), 1);
+        assert_eq!(confirmed[0].canonical().name, "Alice");
+        assert!(String::from_utf8(prompts).unwrap().starts_with(
+            "merge into Alice <alice@example.com>:\n    alice <alice@home.example.com>\n[Y/n] "
+        ));
+    }
+}
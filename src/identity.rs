@@ -0,0 +1,661 @@
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use serde_derive::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// names and emails of the usual automation accounts
+pub const DEFAULT_BOT_PATTERN: &str = r"(?i)\[bot\]|dependabot|renovate";
+
+/// the team of authors missing from the teams file
+pub const NO_TEAM: &str = "Unassigned";
+
+static AUTHOR_DISPLAY: OnceLock<AuthorDisplay> = OnceLock::new();
+static BOT_FILTER: OnceLock<Regex> = OnceLock::new();
+static ALIASES: OnceLock<Aliases> = OnceLock::new();
+static AUTHOR_FILTER: OnceLock<AuthorFilter> = OnceLock::new();
+static ANONYMIZE_SALT: OnceLock<String> = OnceLock::new();
+static TEAMS: OnceLock<Teams> = OnceLock::new();
+static GROUP_BY: OnceLock<GroupBy> = OnceLock::new();
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AuthorDisplay {
+    Name,
+    Email,
+    Both,
+    Initials,
+}
+
+impl FromStr for AuthorDisplay {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "name" => Ok(AuthorDisplay::Name),
+            "email" => Ok(AuthorDisplay::Email),
+            "both" => Ok(AuthorDisplay::Both),
+            "initials" => Ok(AuthorDisplay::Initials),
+            _ => Err(anyhow!(
+                "author-display must be either 'name', 'email', 'both' or 'initials'"
+            )),
+        }
+    }
+}
+
+/// sets how authors are shown for the rest of the run.  Only the first call has an effect.
+pub fn set_author_display(display: AuthorDisplay) {
+    let _ = AUTHOR_DISPLAY.set(display);
+}
+
+/// What authors are grouped into, themselves by default.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GroupBy {
+    Author,
+    Team,
+}
+
+impl FromStr for GroupBy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "author" => Ok(GroupBy::Author),
+            "team" => Ok(GroupBy::Team),
+            _ => Err(anyhow!("group-by must be either 'author' or 'team'")),
+        }
+    }
+}
+
+/// groups authors as `group_by` says for the rest of the run.  Only the first call has an
+/// effect.
+pub fn set_group_by(group_by: GroupBy) {
+    let _ = GROUP_BY.set(group_by);
+}
+
+pub fn group_by() -> GroupBy {
+    *GROUP_BY.get().unwrap_or(&GroupBy::Author)
+}
+
+/// the titles of the column authors are shown in, for the table and the data formats
+pub fn author_column() -> (&'static str, &'static str) {
+    match group_by() {
+        GroupBy::Author => ("Author", "author"),
+        GroupBy::Team => ("Team", "team"),
+    }
+}
+
+/// formats an author with the display set in `set_author_display`, the name by default.
+/// Aliased authors are shown as their canonical identity, and as their team when grouping
+/// by team.
+pub fn display_author(name: &str, email: &str) -> String {
+    if group_by() == GroupBy::Team {
+        return team_of(name, email);
+    }
+
+    let (name, email) = match ALIASES.get() {
+        Some(a) => a.resolve(name, email),
+        None => (name.to_string(), email.to_string()),
+    };
+
+    if let Some(salt) = ANONYMIZE_SALT.get() {
+        return pseudonym(&name, &email, salt);
+    }
+
+    format_author(
+        &name,
+        &email,
+        *AUTHOR_DISPLAY.get().unwrap_or(&AuthorDisplay::Name),
+    )
+}
+
+/// whether `query` names the author, by their name, email or aliased identity, ignoring case
+pub fn is_author(query: &str, name: &str, email: &str) -> bool {
+    let (canonical_name, canonical_email) = match ALIASES.get() {
+        Some(a) => a.resolve(name, email),
+        None => (name.to_string(), email.to_string()),
+    };
+
+    [name, email, &canonical_name, &canonical_email]
+        .iter()
+        .any(|a| a.eq_ignore_ascii_case(query))
+}
+
+/// the email to show for an author, an address made from their pseudonym when anonymizing
+pub fn display_email(name: &str, email: &str) -> String {
+    match ANONYMIZE_SALT.get() {
+        Some(_) => format!(
+            "{}@anonymized.invalid",
+            display_author(name, email).to_lowercase()
+        ),
+        None => email.to_string(),
+    }
+}
+
+/// replaces every author with a pseudonym salted with `salt` for the rest of the run.
+/// Only the first call has an effect.
+pub fn set_anonymize(salt: String) {
+    let _ = ANONYMIZE_SALT.set(salt);
+}
+
+/// true once `set_anonymize` was called
+pub fn is_anonymized() -> bool {
+    ANONYMIZE_SALT.get().is_some()
+}
+
+/// `Author-` and 8 hex digits of a salted FNV-1a hash of the email, or the name when there
+/// is no email.  The same for the author on every run with the same salt.
+pub fn pseudonym(name: &str, email: &str, salt: &str) -> String {
+    let key = if email.is_empty() { name } else { email };
+
+    let hash = salt
+        .bytes()
+        .chain(std::iter::once(0))
+        .chain(key.to_lowercase().bytes())
+        .fold(0xcbf2_9ce4_8422_2325_u64, |h, b| {
+            (h ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+        });
+
+    format!("Author-{:08x}", (hash >> 32) ^ (hash & 0xffff_ffff))
+}
+
+/// The value of a names file entry, one name or a list of them.
+#[derive(Deserialize)]
+#[serde(untagged, expecting = "a name or a list of names")]
+enum Names {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Names {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            Names::One(n) => vec![n],
+            Names::Many(n) => n,
+        }
+    }
+}
+
+/// An entry of a names file, or the `[section]` table holding the entries.
+#[derive(Deserialize)]
+#[serde(untagged, expecting = "a name, a list of names or a table of them")]
+enum NamesEntry {
+    Names(Names),
+    Section(BTreeMap<String, Names>),
+}
+
+/// the entries of a toml file of `"key" = ["name", ...]` or `"key" = "name"` entries, at the
+/// top level or in a `[section]` table
+fn parse_names(content: &str, section: &str) -> Result<BTreeMap<String, Vec<String>>> {
+    let file: BTreeMap<String, NamesEntry> = toml::from_str(content)?;
+
+    let mut entries: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+    for (key, entry) in file.into_iter() {
+        match entry {
+            NamesEntry::Names(n) => entries.entry(key).or_default().extend(n.into_vec()),
+            NamesEntry::Section(t) if key == section => {
+                for (k, n) in t.into_iter() {
+                    entries.entry(k).or_default().extend(n.into_vec());
+                }
+            }
+            NamesEntry::Section(_) => {
+                return Err(anyhow!("unknown table [{}], expected [{}]", key, section))
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Canonical identities keyed by the lowercased names and emails that belong to them.
+/// Read from a toml file of `"Name <email>" = ["other name", "other@email"]` entries.
+#[derive(Debug, Default)]
+pub struct Aliases {
+    identities: HashMap<String, (String, String)>,
+}
+
+impl Aliases {
+    pub fn parse(content: &str) -> Result<Aliases> {
+        let mut identities = HashMap::new();
+
+        for (canonical, aliases) in parse_names(content, "aliases")?.into_iter() {
+            let identity = match canonical.split_once('<') {
+                Some((n, e)) => (
+                    n.trim().to_string(),
+                    e.trim_end_matches('>').trim().to_string(),
+                ),
+                None => (canonical.trim().to_string(), String::new()),
+            };
+
+            for key in vec![identity.0.clone(), identity.1.clone()]
+                .into_iter()
+                .chain(aliases)
+                .filter(|k| !k.is_empty())
+            {
+                identities.insert(key.to_lowercase(), identity.clone());
+            }
+        }
+
+        Ok(Aliases { identities })
+    }
+
+    pub fn load(path: &str) -> Result<Aliases> {
+        let content =
+            fs::read_to_string(path).map_err(|e| anyhow!("cannot read {}: {}", path, e))?;
+
+        Aliases::parse(&content).map_err(|e| anyhow!("{}: {}", path, e))
+    }
+
+    /// the canonical name and email for an author, matched by email first.  An identity
+    /// without an email keeps the author's own.
+    pub fn resolve(&self, name: &str, email: &str) -> (String, String) {
+        let found = self
+            .identities
+            .get(&email.to_lowercase())
+            .or_else(|| self.identities.get(&name.to_lowercase()));
+
+        match found {
+            Some((n, e)) => (
+                if n.is_empty() { name } else { n }.to_string(),
+                if e.is_empty() { email } else { e }.to_string(),
+            ),
+            None => (name.to_string(), email.to_string()),
+        }
+    }
+}
+
+/// shows aliased authors as their canonical identity for the rest of the run.
+/// Only the first call has an effect.
+pub fn set_aliases(aliases: Aliases) {
+    let _ = ALIASES.set(aliases);
+}
+
+/// Team names keyed by the lowercased names and emails of their members.  Read from a
+/// toml file of `"Team" = ["name", "email", ...]` entries.
+#[derive(Debug, Default)]
+pub struct Teams {
+    members: HashMap<String, String>,
+}
+
+impl Teams {
+    pub fn parse(content: &str) -> Result<Teams> {
+        let mut members = HashMap::new();
+
+        for (team, authors) in parse_names(content, "teams")?.into_iter() {
+            for author in authors.into_iter() {
+                if let Some(other) = members.insert(author.to_lowercase(), team.clone()) {
+                    if other != team {
+                        return Err(anyhow!("{} is in both {} and {}", author, other, team));
+                    }
+                }
+            }
+        }
+
+        Ok(Teams { members })
+    }
+
+    pub fn load(path: &str) -> Result<Teams> {
+        let content =
+            fs::read_to_string(path).map_err(|e| anyhow!("cannot read {}: {}", path, e))?;
+
+        Teams::parse(&content).map_err(|e| anyhow!("{}: {}", path, e))
+    }
+
+    /// the team of an author, matched by email first and then name
+    pub fn team(&self, name: &str, email: &str) -> Option<&str> {
+        self.members
+            .get(&email.to_lowercase())
+            .or_else(|| self.members.get(&name.to_lowercase()))
+            .map(|t| t.as_str())
+    }
+}
+
+/// maps authors to the teams in `teams` for the rest of the run.  Only the first call has
+/// an effect.
+pub fn set_teams(teams: Teams) {
+    let _ = TEAMS.set(teams);
+}
+
+/// the team of an author, by the identity they committed under or their aliased one.
+/// `NO_TEAM` for authors in no team.
+pub fn team_of(name: &str, email: &str) -> String {
+    let teams = match TEAMS.get() {
+        Some(t) => t,
+        None => return String::from(NO_TEAM),
+    };
+
+    let canonical = ALIASES.get().map(|a| a.resolve(name, email));
+
+    teams
+        .team(name, email)
+        .or_else(|| canonical.as_ref().and_then(|(n, e)| teams.team(n, e)))
+        .unwrap_or(NO_TEAM)
+        .to_string()
+}
+
+/// the default bot pattern joined with the comma delimited `extra` patterns
+pub fn bot_regex(extra: Option<&str>) -> Result<Regex> {
+    let mut patterns = vec![String::from(DEFAULT_BOT_PATTERN)];
+
+    if let Some(e) = extra {
+        patterns.extend(
+            e.split(',')
+                .filter(|p| !p.trim().is_empty())
+                .map(|p| format!("(?:{})", p.trim())),
+        );
+    }
+
+    Regex::new(&patterns.join("|")).map_err(|e| anyhow!("invalid bot pattern: {}", e))
+}
+
+/// drops commits and lines from authors matching `filter` for the rest of the run.
+/// Only the first call has an effect.
+pub fn set_bot_filter(filter: Regex) {
+    let _ = BOT_FILTER.set(filter);
+}
+
+/// true when bots are being excluded and the author's name or email matches the bot filter
+pub fn is_bot(name: &str, email: &str) -> bool {
+    match BOT_FILTER.get() {
+        Some(r) => r.is_match(name) || r.is_match(email),
+        None => false,
+    }
+}
+
+/// Regexes an author's name or email must not, or must, match for their commits and
+/// lines to be counted.
+#[derive(Debug, Default)]
+pub struct AuthorFilter {
+    exclude: Option<Regex>,
+    only: Option<Regex>,
+}
+
+impl AuthorFilter {
+    pub fn new(exclude: Option<Regex>, only: Option<Regex>) -> AuthorFilter {
+        AuthorFilter { exclude, only }
+    }
+
+    /// true when the author is left out.  Aliased authors are matched by their canonical
+    /// identity as well as the one they committed under.
+    pub fn excludes(&self, name: &str, email: &str) -> bool {
+        let canonical = ALIASES.get().map(|a| a.resolve(name, email));
+
+        let matches = |r: &Regex| {
+            r.is_match(name)
+                || r.is_match(email)
+                || canonical
+                    .as_ref()
+                    .map(|(n, e)| r.is_match(n) || r.is_match(e))
+                    .unwrap_or(false)
+        };
+
+        self.exclude.as_ref().map(matches).unwrap_or(false)
+            || !self.only.as_ref().map(matches).unwrap_or(true)
+    }
+}
+
+pub fn author_regex(pattern: &str) -> Result<Regex> {
+    Regex::new(pattern).map_err(|e| anyhow!("invalid author regex: {}", e))
+}
+
+/// drops commits and lines from the authors `filter` excludes for the rest of the run.
+/// Only the first call has an effect.
+pub fn set_author_filter(filter: AuthorFilter) {
+    let _ = AUTHOR_FILTER.set(filter);
+}
+
+/// true when the author is a bot being excluded or is left out by the author regexes
+pub fn is_excluded(name: &str, email: &str) -> bool {
+    is_bot(name, email)
+        || AUTHOR_FILTER
+            .get()
+            .map(|f| f.excludes(name, email))
+            .unwrap_or(false)
+}
+
+fn initials(value: &str) -> String {
+    value
+        .split(|c: char| c.is_whitespace() || c == '-' || c == '_' || c == '.')
+        .filter_map(|p| p.chars().next())
+        .flat_map(|c| c.to_uppercase())
+        .collect()
+}
+
+pub fn format_author(name: &str, email: &str, display: AuthorDisplay) -> String {
+    match display {
+        AuthorDisplay::Name if !name.is_empty() => name.to_string(),
+        AuthorDisplay::Email if !email.is_empty() => email.to_string(),
+        AuthorDisplay::Both if !email.is_empty() => format!("{} <{}>", name, email),
+        AuthorDisplay::Initials if !name.is_empty() => initials(name),
+        AuthorDisplay::Initials => initials(email.split('@').next().unwrap_or("")),
+        _ if name.is_empty() => email.to_string(),
+        _ => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::LevelFilter;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    #[test]
+    fn test_format_author() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let (name, email) = ("Todd Bush", "todd@example.com");
+
+        assert_eq!(format_author(name, email, AuthorDisplay::Name), "Todd Bush");
+        assert_eq!(
+            format_author(name, email, AuthorDisplay::Email),
+            "todd@example.com"
+        );
+        assert_eq!(
+            format_author(name, email, AuthorDisplay::Both),
+            "Todd Bush <todd@example.com>"
+        );
+        assert_eq!(format_author(name, email, AuthorDisplay::Initials), "TB");
+        assert_eq!(
+            format_author("todd-bush", email, AuthorDisplay::Initials),
+            "TB"
+        );
+    }
+
+    #[test]
+    fn test_pseudonym() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let alice = pseudonym("Alice", "alice@example.com", "");
+
+        assert!(alice.starts_with("Author-"));
+        assert_eq!(alice.len(), "Author-".len() + 8);
+        assert_eq!(alice, pseudonym("A. Smith", "Alice@Example.com", ""));
+        assert_ne!(alice, pseudonym("Alice", "alice@example.com", "s3cret"));
+        assert_ne!(alice, pseudonym("Bob", "bob@example.com", ""));
+        assert_eq!(pseudonym("Bob", "", ""), pseudonym("bob", "", ""));
+    }
+
+    #[test]
+    fn test_format_author_missing_parts() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        assert_eq!(
+            format_author("", "todd.bush@example.com", AuthorDisplay::Name),
+            "todd.bush@example.com"
+        );
+        assert_eq!(
+            format_author("", "todd.bush@example.com", AuthorDisplay::Initials),
+            "TB"
+        );
+        assert_eq!(format_author("Todd", "", AuthorDisplay::Email), "Todd");
+        assert_eq!(format_author("Todd", "", AuthorDisplay::Both), "Todd");
+    }
+
+    #[test]
+    fn test_bot_regex() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let r = bot_regex(None).unwrap();
+
+        assert!(r.is_match("dependabot[bot]"));
+        assert!(r.is_match("Renovate Bot"));
+        assert!(r.is_match("github-actions[bot]"));
+        assert!(!r.is_match("Todd Bush"));
+
+        let r = bot_regex(Some("^ci-.*,release bot")).unwrap();
+
+        assert!(r.is_match("ci-runner"));
+        assert!(r.is_match("release bot"));
+        assert!(!r.is_match("my-ci-runner"));
+
+        assert!(bot_regex(Some("[unclosed")).is_err());
+    }
+
+    #[test]
+    fn test_aliases() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let aliases = Aliases::parse(
+            r#"
+# people who commit under more than one identity
+[aliases]
+"Todd Bush <todd@example.com>" = ["tbush", "TODD@old.example.com"]
+"Alice" = "alice@laptop.local"
+"#,
+        )
+        .unwrap();
+
+        let todd = (String::from("Todd Bush"), String::from("todd@example.com"));
+
+        assert_eq!(aliases.resolve("tbush", "tbush@ci.local"), todd);
+        assert_eq!(aliases.resolve("Todd", "todd@old.example.com"), todd);
+        assert_eq!(aliases.resolve("todd bush", "todd@home.local"), todd);
+        assert_eq!(
+            aliases.resolve("alice", "alice@laptop.local"),
+            (String::from("Alice"), String::from("alice@laptop.local"))
+        );
+        assert_eq!(
+            aliases.resolve("Bob", "bob@example.com"),
+            (String::from("Bob"), String::from("bob@example.com"))
+        );
+
+        let err = Aliases::parse("\"Todd\" = [tbush]").unwrap_err();
+        assert!(err.to_string().contains("line 1"), "{}", err);
+        assert!(Aliases::parse("[people]\n\"Todd\" = \"tbush\"").is_err());
+    }
+
+    #[test]
+    fn test_aliases_toml() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let aliases = Aliases::parse(
+            r#"
+"Bush, Todd <todd@example.com>" = [
+    "tbush",  # the CI account
+    "Todd = T. Bush",
+]
+
+[aliases]
+'Smith, Alice' = "alice@laptop.local"
+"#,
+        )
+        .unwrap();
+
+        let todd = (String::from("Bush, Todd"), String::from("todd@example.com"));
+
+        assert_eq!(aliases.resolve("tbush", "tbush@ci.local"), todd);
+        assert_eq!(aliases.resolve("Todd = T. Bush", "t@home.local"), todd);
+        assert_eq!(aliases.resolve("bush, todd", "todd@home.local"), todd);
+        assert_eq!(
+            aliases.resolve("alice", "alice@laptop.local"),
+            (
+                String::from("Smith, Alice"),
+                String::from("alice@laptop.local")
+            )
+        );
+    }
+
+    #[test]
+    fn test_teams() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let teams = Teams::parse(
+            "[teams]\n# comment\n\"Platform\" = [\"alice@example.com\", \"Bob\"]\n\"Web\" = \"carol\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(teams.team("Alice", "ALICE@example.com"), Some("Platform"));
+        assert_eq!(teams.team("bob", "bob@example.com"), Some("Platform"));
+        assert_eq!(teams.team("Carol", "carol@example.com"), Some("Web"));
+        assert_eq!(teams.team("Dave", "dave@example.com"), None);
+
+        assert!(Teams::parse("Platform = alice").is_err());
+        assert_eq!(
+            Teams::parse("\"A\" = [\"alice\"]\n\"B\" = [\"alice\"]")
+                .unwrap_err()
+                .to_string(),
+            "alice is in both A and B"
+        );
+
+        let teams = Teams::parse(
+            r#"
+[teams]
+"Platform, Infra" = [
+    "alice@example.com",
+    "Bob = the builder",
+]
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            teams.team("Alice", "alice@example.com"),
+            Some("Platform, Infra")
+        );
+        assert_eq!(
+            teams.team("Bob = the builder", "bob@example.com"),
+            Some("Platform, Infra")
+        );
+        assert_eq!("team".parse::<GroupBy>().unwrap(), GroupBy::Team);
+        assert!("dept".parse::<GroupBy>().is_err());
+    }
+
+    #[test]
+    fn test_author_display_from_str() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        assert_eq!(
+            "initials".parse::<AuthorDisplay>().unwrap(),
+            AuthorDisplay::Initials
+        );
+        assert!("full".parse::<AuthorDisplay>().is_err());
+    }
+
+    #[test]
+    fn test_author_filter() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let vendor = author_regex(r".*@vendor\.com").unwrap();
+
+        let f = AuthorFilter::new(Some(vendor.clone()), None);
+        assert!(f.excludes("Ann", "ann@vendor.com"));
+        assert!(!f.excludes("Todd", "todd@example.com"));
+
+        let f = AuthorFilter::new(None, Some(vendor));
+        assert!(!f.excludes("Ann", "ann@vendor.com"));
+        assert!(f.excludes("Todd", "todd@example.com"));
+
+        let f = AuthorFilter::new(
+            Some(author_regex("^Ann$").unwrap()),
+            Some(author_regex("@vendor").unwrap()),
+        );
+        assert!(f.excludes("Ann", "ann@vendor.com"));
+        assert!(!f.excludes("Bea", "bea@vendor.com"));
+
+        assert!(!AuthorFilter::default().excludes("Todd", "todd@example.com"));
+        assert!(author_regex("[unclosed").is_err());
+    }
+}
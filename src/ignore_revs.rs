@@ -0,0 +1,325 @@
+use crate::cache::Memo;
+use anyhow::{anyhow, Context, Result};
+use git2::{Blame, BlameOptions, Oid, Repository, Signature};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// the git config key naming the repo's ignore revs file
+const CONFIG_KEY: &str = "blame.ignoreRevsFile";
+
+static IGNORE_REVS_FILE: OnceLock<String> = OnceLock::new();
+static IGNORED_REVS: Memo<PathBuf, HashSet<Oid>> = Memo::new();
+
+/// sets the file of commits blame looks past for the rest of the run, in place of the repo's
+/// `blame.ignoreRevsFile`.  Only the first call has an effect.
+pub fn set_ignore_revs_file(file: Option<&str>) {
+    if let Some(f) = file {
+        let _ = IGNORE_REVS_FILE.set(f.to_string());
+    }
+}
+
+/// the commits listed in `contents`, one per line in the `.git-blame-ignore-revs` format
+/// where '#' starts a comment.  Abbreviated hashes are resolved against `repo`.
+fn parse_ignore_revs(repo: &Repository, contents: &str) -> Result<HashSet<Oid>> {
+    contents
+        .lines()
+        .map(|l| l.split('#').next().unwrap_or_default().trim())
+        .filter(|l| !l.is_empty())
+        .map(|l| {
+            repo.revparse_single(l)
+                .and_then(|o| o.peel_to_commit())
+                .map(|c| c.id())
+                .map_err(|_| anyhow!("cannot resolve ignored revision '{}'", l))
+        })
+        .collect()
+}
+
+/// the file set with `set_ignore_revs_file`, else the one the repo's git config names
+fn ignore_revs_file(repo: &Repository) -> Option<PathBuf> {
+    if let Some(f) = IGNORE_REVS_FILE.get() {
+        return Some(PathBuf::from(f));
+    }
+
+    let file = repo.config().ok()?.get_path(CONFIG_KEY).ok()?;
+
+    // git reads a relative path from the top of the work tree
+    match (file.is_relative(), repo.workdir()) {
+        (true, Some(w)) => Some(w.join(file)),
+        _ => Some(file),
+    }
+}
+
+/// the commits blame looks past in `repo`, empty when there is no ignore revs file
+pub fn ignored_revs(repo: &Repository) -> Result<HashSet<Oid>> {
+    let file = match ignore_revs_file(repo) {
+        Some(f) => f,
+        None => return Ok(HashSet::new()),
+    };
+
+    IGNORED_REVS.get_or_try(file.clone(), || {
+        let contents = fs::read_to_string(&file)
+            .with_context(|| format!("cannot read ignore revs file {}", file.display()))?;
+
+        parse_ignore_revs(repo, &contents)
+    })
+}
+
+/// A run of a file's lines blamed on one commit.
+pub struct BlamedLines {
+    pub commit_id: Oid,
+    pub signature: Signature<'static>,
+    pub lines: usize,
+    /// the file's path in the commit, which differs from the blamed path when the file
+    /// has since been renamed
+    pub path: PathBuf,
+    /// the lines are blamed on the `oldest` commit given, which takes every older line too
+    pub boundary: bool,
+}
+
+fn blame_at<'r>(
+    repo: &'r Repository,
+    path: &Path,
+    oldest: Option<Oid>,
+    newest: Option<Oid>,
+    lines: Option<(usize, usize)>,
+) -> Result<Blame<'r>> {
+    let mut bo = BlameOptions::new();
+
+    if let Some((first, last)) = lines {
+        bo.min_line(first).max_line(last);
+    }
+
+    if let Some(oid) = oldest {
+        bo.oldest_commit(oid);
+    }
+
+    if let Some(oid) = newest {
+        bo.newest_commit(oid);
+    }
+
+    Ok(repo.blame_file(path, Some(&mut bo))?)
+}
+
+/// blames `path` between `oldest` and `newest` like `git blame`, except that lines last
+/// changed by an ignored commit are blamed on whoever the commit's first parent blames for
+/// the same line, so a mass reformat doesn't take the credit.  Lines past the end of the
+/// parent's file go to its last line, and lines the parent doesn't have at all stay with
+/// the ignored commit.
+pub fn blame_file(
+    repo: &Repository,
+    path: &Path,
+    oldest: Option<Oid>,
+    newest: Option<Oid>,
+) -> Result<Vec<BlamedLines>> {
+    blame_file_ignoring(repo, path, oldest, newest, None, &ignored_revs(repo)?)
+}
+
+/// blames only the 1-based, inclusive first to last `lines` of `path` as of `newest`, like
+/// `git blame -L first,last`
+pub fn blame_file_lines(
+    repo: &Repository,
+    path: &Path,
+    newest: Option<Oid>,
+    lines: (usize, usize),
+) -> Result<Vec<BlamedLines>> {
+    let commit = match newest {
+        Some(oid) => repo.find_commit(oid)?,
+        None => repo.head()?.peel_to_commit()?,
+    };
+    let blob = commit
+        .tree()?
+        .get_path(path)?
+        .to_object(repo)?
+        .peel_to_blob()?;
+    let content = blob.content();
+    let line_count = content.iter().filter(|b| **b == b'\n').count()
+        + usize::from(!content.is_empty() && !content.ends_with(b"\n"));
+
+    // libgit2 makes up a hunk for lines past the end of the file, so the range is checked
+    // and trimmed here, as `git blame -L` does
+    if lines.0 > line_count {
+        return Err(anyhow!("{} has only {} lines", path.display(), line_count));
+    }
+
+    blame_file_ignoring(
+        repo,
+        path,
+        None,
+        newest,
+        Some((lines.0, lines.1.min(line_count))),
+        &ignored_revs(repo)?,
+    )
+}
+
+fn blame_file_ignoring(
+    repo: &Repository,
+    path: &Path,
+    oldest: Option<Oid>,
+    newest: Option<Oid>,
+    lines: Option<(usize, usize)>,
+    ignored: &HashSet<Oid>,
+) -> Result<Vec<BlamedLines>> {
+    let blame = blame_at(repo, path, oldest, newest, lines)?;
+
+    let mut output: Vec<BlamedLines> = Vec::new();
+
+    // the blame of each ignored commit's parent, and the number of lines it covers
+    let mut parents: HashMap<Oid, Option<(Blame, usize)>> = HashMap::new();
+
+    for hunk in blame.iter() {
+        let commit_id = hunk.final_commit_id();
+
+        // without an oldest commit libgit2 flags the lines of the root commit instead
+        if !ignored.contains(&commit_id) {
+            output.push(BlamedLines {
+                commit_id,
+                signature: hunk.final_signature().to_owned(),
+                lines: hunk.lines_in_hunk(),
+                path: hunk.path().unwrap_or(path).to_path_buf(),
+                boundary: oldest.is_some() && hunk.is_boundary(),
+            });
+            continue;
+        }
+
+        for line in hunk.final_start_line()..hunk.final_start_line() + hunk.lines_in_hunk() {
+            let (mut commit_id, mut signature, mut line) =
+                (commit_id, hunk.final_signature().to_owned(), line);
+            let mut orig_path = hunk.path().unwrap_or(path).to_path_buf();
+            let mut boundary = hunk.is_boundary();
+
+            while ignored.contains(&commit_id) && Some(commit_id) != oldest {
+                let parent = parents.entry(commit_id).or_insert_with(|| {
+                    let parent = repo.find_commit(commit_id).ok()?.parent_id(0).ok()?;
+                    let blame = blame_at(repo, path, oldest, Some(parent), None).ok()?;
+                    let lines = blame.iter().map(|h| h.lines_in_hunk()).sum();
+                    Some((blame, lines))
+                });
+
+                let h = match parent {
+                    Some((b, lines)) if *lines > 0 => {
+                        line = line.min(*lines);
+                        b.get_line(line)
+                    }
+                    _ => None,
+                };
+
+                match h {
+                    Some(h) => {
+                        commit_id = h.final_commit_id();
+                        signature = h.final_signature().to_owned();
+                        orig_path = h.path().unwrap_or(path).to_path_buf();
+                        boundary = h.is_boundary();
+                    }
+                    None => break,
+                }
+            }
+
+            match output.last_mut() {
+                Some(o) if o.commit_id == commit_id => o.lines += 1,
+                _ => output.push(BlamedLines {
+                    commit_id,
+                    signature,
+                    lines: 1,
+                    path: orig_path,
+                    boundary: oldest.is_some() && boundary,
+                }),
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Time;
+    use log::LevelFilter;
+    use std::collections::BTreeMap;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    #[test]
+    fn test_blame_file_boundary() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let repo = Repository::open(td.path()).unwrap();
+        let path = Path::new("README.md");
+
+        // Bob's README edit, on top of Alice's main.rs change of the day before
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let oldest = head.parent_id(0).unwrap();
+
+        let blamed = blame_file(&repo, path, Some(oldest), None).unwrap();
+        let lines = |boundary: bool| -> Vec<(Oid, usize)> {
+            blamed
+                .iter()
+                .filter(|b| b.boundary == boundary)
+                .map(|b| (b.commit_id, b.lines))
+                .collect()
+        };
+
+        // Alice's title line of the first commit is taken by the oldest commit
+        assert_eq!(lines(true), vec![(oldest, 1)]);
+        assert_eq!(lines(false), vec![(head.id(), 1)]);
+
+        // without an oldest commit, the first commit's lines are its own
+        let blamed = blame_file(&repo, path, None, None).unwrap();
+        assert!(blamed.iter().all(|b| !b.boundary));
+    }
+
+    #[test]
+    fn test_blame_file_ignoring_reformat() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let repo = Repository::open(td.path()).unwrap();
+        let path = Path::new("README.md");
+
+        let blamed = |ignored: &HashSet<Oid>| {
+            let mut lines: BTreeMap<String, usize> = BTreeMap::new();
+            for b in blame_file_ignoring(&repo, path, None, None, None, ignored).unwrap() {
+                *lines
+                    .entry(b.signature.name().unwrap().to_string())
+                    .or_default() += b.lines;
+            }
+            lines
+        };
+
+        let before = blamed(&HashSet::new());
+
+        // Carol rewrites every line of the file without changing how many there are
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let contents = fs::read_to_string(td.path().join(path)).unwrap();
+        let reformatted: String = contents.lines().map(|l| format!("{} \n", l)).collect();
+        let blob = repo.blob(reformatted.as_bytes()).unwrap();
+        let mut tb = repo.treebuilder(Some(&head.tree().unwrap())).unwrap();
+        tb.insert("README.md", blob, 0o100644).unwrap();
+        let tree = repo.find_tree(tb.write().unwrap()).unwrap();
+        let carol =
+            Signature::new("Carol", "carol@example.com", &Time::new(1_578_484_800, 0)).unwrap();
+        let reformat = repo
+            .commit(Some("HEAD"), &carol, &carol, "reformat", &tree, &[&head])
+            .unwrap();
+
+        assert_eq!(
+            blamed(&HashSet::new()),
+            BTreeMap::from([(String::from("Carol"), contents.lines().count())])
+        );
+
+        let ignored: HashSet<Oid> = parse_ignore_revs(
+            &repo,
+            &format!(
+                "# reformat\n{}  # trailing comment\n\n",
+                &reformat.to_string()[..10]
+            ),
+        )
+        .unwrap();
+        assert_eq!(ignored, HashSet::from([reformat]));
+        assert_eq!(blamed(&ignored), before);
+    }
+}
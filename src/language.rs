@@ -0,0 +1,141 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::path::Path;
+
+const OTHER_LANGUAGE: &str = "Other";
+
+const DEFAULT_LANGUAGES: [(&str, &str); 40] = [
+    ("rs", "Rust"),
+    ("py", "Python"),
+    ("js", "JavaScript"),
+    ("jsx", "JavaScript"),
+    ("mjs", "JavaScript"),
+    ("cjs", "JavaScript"),
+    ("ts", "TypeScript"),
+    ("tsx", "TypeScript"),
+    ("vue", "Vue"),
+    ("java", "Java"),
+    ("kt", "Kotlin"),
+    ("scala", "Scala"),
+    ("go", "Go"),
+    ("c", "C"),
+    ("h", "C"),
+    ("cc", "C++"),
+    ("cpp", "C++"),
+    ("cxx", "C++"),
+    ("hpp", "C++"),
+    ("cs", "C#"),
+    ("swift", "Swift"),
+    ("rb", "Ruby"),
+    ("php", "PHP"),
+    ("sh", "Shell"),
+    ("bash", "Shell"),
+    ("sql", "SQL"),
+    ("html", "HTML"),
+    ("htm", "HTML"),
+    ("css", "CSS"),
+    ("scss", "SCSS"),
+    ("md", "Markdown"),
+    ("json", "JSON"),
+    ("toml", "TOML"),
+    ("yaml", "YAML"),
+    ("yml", "YAML"),
+    ("xml", "XML"),
+    ("gradle", "Gradle"),
+    ("tf", "Terraform"),
+    ("proto", "Protobuf"),
+    ("dart", "Dart"),
+];
+
+/// maps file extensions to the language names used to group lines
+#[derive(Clone, Debug, PartialEq)]
+pub struct LanguageMap {
+    languages: HashMap<String, String>,
+}
+
+impl Default for LanguageMap {
+    fn default() -> Self {
+        LanguageMap {
+            languages: DEFAULT_LANGUAGES
+                .iter()
+                .map(|(e, l)| (e.to_string(), l.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl LanguageMap {
+    /// the default map with `overrides` applied, given as comma delimited ext=Language pairs
+    pub fn with_overrides(overrides: Option<&str>) -> Result<LanguageMap> {
+        let mut map = LanguageMap::default();
+
+        let overrides = match overrides {
+            Some(o) => o,
+            None => return Ok(map),
+        };
+
+        for pair in overrides.split(',').filter(|p| !p.trim().is_empty()) {
+            match pair.split_once('=') {
+                Some((ext, language)) if !ext.trim().is_empty() && !language.trim().is_empty() => {
+                    map.languages.insert(
+                        ext.trim().trim_start_matches('.').to_lowercase(),
+                        language.trim().to_string(),
+                    );
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "language-map entries must look like ext=Language, found '{}'",
+                        pair
+                    ))
+                }
+            }
+        }
+
+        Ok(map)
+    }
+
+    /// the language of `file_name` by its extension, "Other" when it is not in the map
+    pub fn language(&self, file_name: &str) -> String {
+        Path::new(file_name)
+            .extension()
+            .and_then(|e| self.languages.get(&e.to_string_lossy().to_lowercase()))
+            .cloned()
+            .unwrap_or_else(|| String::from(OTHER_LANGUAGE))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::LevelFilter;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    #[test]
+    fn test_language() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let map = LanguageMap::default();
+
+        assert_eq!(map.language("src/main.rs"), "Rust");
+        assert_eq!(map.language("web/App.TSX"), "TypeScript");
+        assert_eq!(map.language("Makefile"), "Other");
+        assert_eq!(map.language("notes.xyz"), "Other");
+    }
+
+    #[test]
+    fn test_with_overrides() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let map =
+            LanguageMap::with_overrides(Some("ts=Frontend, .vue=Frontend,xyz=Notes")).unwrap();
+
+        assert_eq!(map.language("web/app.ts"), "Frontend");
+        assert_eq!(map.language("web/App.vue"), "Frontend");
+        assert_eq!(map.language("notes.xyz"), "Notes");
+        assert_eq!(map.language("src/main.rs"), "Rust");
+
+        assert!(LanguageMap::with_overrides(Some("ts")).is_err());
+        assert!(LanguageMap::with_overrides(Some("=Rust")).is_err());
+    }
+}
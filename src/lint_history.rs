@@ -0,0 +1,382 @@
+use super::Processable;
+use crate::error::GritError;
+use crate::identity;
+use crate::output;
+use crate::table::Table;
+use crate::utils::grit_utils;
+use crate::utils::grit_utils::OutputFormat;
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+use regex::Regex;
+use std::collections::HashMap;
+
+/// the conventional commits subject, `type(scope)!: description`
+pub const CONVENTIONAL_PATTERN: &str =
+    r"^(build|chore|ci|docs|feat|fix|perf|refactor|revert|style|test)(\([^()]+\))?!?: \S";
+
+/// compiles the subject rule, the conventional commits one unless a pattern is given
+pub fn subject_regex(pattern: Option<&str>) -> Result<Regex> {
+    Regex::new(pattern.unwrap_or(CONVENTIONAL_PATTERN))
+        .map_err(|e| anyhow!("invalid commit message pattern: {}", e))
+}
+
+pub struct LintHistoryArgs {
+    path: String,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+    pattern: Option<String>,
+    include_merges: bool,
+    /// percent of the commits
+    fail_under: Option<f64>,
+    file: Option<String>,
+    format: OutputFormat,
+}
+
+impl LintHistoryArgs {
+    pub fn builder(path: String) -> LintHistoryArgsBuilder {
+        LintHistoryArgsBuilder {
+            args: LintHistoryArgs {
+                path,
+                start_date: None,
+                end_date: None,
+                pattern: None,
+                include_merges: false,
+                fail_under: None,
+                file: None,
+                format: OutputFormat::Csv,
+            },
+        }
+    }
+}
+
+/// Builds a `LintHistoryArgs` by name rather than position, validating it on `build`.
+pub struct LintHistoryArgsBuilder {
+    args: LintHistoryArgs,
+}
+
+impl LintHistoryArgsBuilder {
+    pub fn start_date(mut self, start_date: Option<NaiveDate>) -> LintHistoryArgsBuilder {
+        self.args.start_date = start_date;
+        self
+    }
+
+    pub fn end_date(mut self, end_date: Option<NaiveDate>) -> LintHistoryArgsBuilder {
+        self.args.end_date = end_date;
+        self
+    }
+
+    pub fn pattern(mut self, pattern: Option<String>) -> LintHistoryArgsBuilder {
+        self.args.pattern = pattern;
+        self
+    }
+
+    pub fn include_merges(mut self, include_merges: bool) -> LintHistoryArgsBuilder {
+        self.args.include_merges = include_merges;
+        self
+    }
+
+    pub fn fail_under(mut self, fail_under: Option<f64>) -> LintHistoryArgsBuilder {
+        self.args.fail_under = fail_under;
+        self
+    }
+
+    pub fn file(mut self, file: Option<String>) -> LintHistoryArgsBuilder {
+        self.args.file = file;
+        self
+    }
+
+    pub fn format(mut self, format: OutputFormat) -> LintHistoryArgsBuilder {
+        self.args.format = format;
+        self
+    }
+
+    pub fn build(self) -> Result<LintHistoryArgs> {
+        grit_utils::validate_args(
+            self.args.start_date,
+            self.args.end_date,
+            self.args.file.as_deref(),
+            self.args.format,
+        )?;
+
+        Ok(self.args)
+    }
+}
+
+/// The commits of an author and how many of their subjects follow the rule.
+#[derive(Clone, Debug, PartialEq)]
+struct LintOutput {
+    author: String,
+    commits: usize,
+    compliant: usize,
+}
+
+impl LintOutput {
+    fn compliance(&self) -> f64 {
+        compliance(self.compliant, self.commits)
+    }
+}
+
+fn compliance(compliant: usize, commits: usize) -> f64 {
+    compliant as f64 / commits.max(1) as f64 * 100.0
+}
+
+pub struct LintHistory {
+    args: LintHistoryArgs,
+}
+
+impl LintHistory {
+    pub fn new(args: LintHistoryArgs) -> LintHistory {
+        LintHistory { args }
+    }
+
+    /// checks the subject of every commit in the date range, most commits first
+    fn process_lint(&self) -> Result<Vec<LintOutput>> {
+        let rule = subject_regex(self.args.pattern.as_deref())?;
+
+        let repo = grit_utils::open_repo(&self.args.path)?;
+
+        let mut revwalk = repo.revwalk()?;
+        revwalk.set_sorting(git2::Sort::TIME)?;
+        revwalk.push_head()?;
+
+        let mut authors: HashMap<String, LintOutput> = HashMap::new();
+
+        for oid in revwalk {
+            let commit = repo.find_commit(oid?)?;
+
+            if commit.parent_count() > 1 && !self.args.include_merges {
+                continue;
+            }
+
+            let day = grit_utils::convert_git_time(&commit.time());
+
+            if self.args.start_date.map(|d| day < d).unwrap_or(false)
+                || self.args.end_date.map(|d| day > d).unwrap_or(false)
+            {
+                continue;
+            }
+
+            let author = commit.author();
+            let name = String::from_utf8_lossy(author.name_bytes()).to_string();
+            let email = String::from_utf8_lossy(author.email_bytes()).to_string();
+
+            if identity::is_excluded(&name, &email) {
+                continue;
+            }
+
+            let message = String::from_utf8_lossy(commit.message_bytes()).to_string();
+            let subject = message.lines().next().unwrap_or_default();
+
+            let display = identity::display_author(&name, &email);
+            let v = authors.entry(display.clone()).or_insert(LintOutput {
+                author: display,
+                commits: 0,
+                compliant: 0,
+            });
+
+            v.commits += 1;
+
+            if rule.is_match(subject) {
+                v.compliant += 1;
+            } else {
+                debug!("{} does not follow the rule: {}", commit.id(), subject);
+            }
+        }
+
+        let mut output: Vec<LintOutput> = authors.into_values().collect();
+        output.sort_by(|a, b| b.commits.cmp(&a.commits).then(a.author.cmp(&b.author)));
+
+        Ok(output)
+    }
+
+    fn display_output(&self, output: &[LintOutput]) -> Result<()> {
+        let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+
+        let mut table = Table::new();
+
+        table.set_titles(table_row!["author", "commits", "compliant", "compliance %"]);
+
+        output.iter().for_each(|r| {
+            table.add_row(table_row![
+                r.author,
+                r.commits,
+                r.compliant,
+                output::percent(r.compliance())
+            ]);
+        });
+
+        if self.args.format != OutputFormat::Json && self.args.format != OutputFormat::Prom {
+            let (compliant, commits) = totals(output);
+
+            table.add_row(table_row![
+                "Total",
+                commits,
+                compliant,
+                output::percent(compliance(compliant, commits))
+            ]);
+        }
+
+        table.add_label(0, "author");
+        table.add_metric(
+            3,
+            "grit_commit_message_compliance",
+            "Percent of the author's commit subjects following the rule.",
+        );
+
+        table.write(&mut w, self.args.format)?;
+
+        Ok(())
+    }
+
+    /// fails with a `GritError::PolicyViolation` when the share of compliant commits
+    /// is under `--fail-under`
+    fn check(&self, output: &[LintOutput]) -> Result<()> {
+        let (compliant, commits) = totals(output);
+        let share = compliance(compliant, commits);
+
+        match self.args.fail_under {
+            Some(min) if share < min => Err(GritError::PolicyViolation(format!(
+                "{:.1}% of the commit messages follow the rule, under {:.1}%",
+                share, min
+            ))
+            .into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// the compliant and total commits of all the authors
+fn totals(output: &[LintOutput]) -> (usize, usize) {
+    output
+        .iter()
+        .fold((0, 0), |(c, t), r| (c + r.compliant, t + r.commits))
+}
+
+impl Processable<()> for LintHistory {
+    fn process(&self) -> Result<()> {
+        let output = self.process_lint()?;
+
+        self.display_output(&output)?;
+
+        self.check(&output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::{Repository, Signature, Time};
+    use log::LevelFilter;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    /// the fixture followed by a conventional commit from each of Alice and Bob, and one
+    /// more of Bob's that is not
+    fn lint_repo() -> TempDir {
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let repo = Repository::open(td.path()).unwrap();
+
+        let commits = [
+            ("Alice", "alice@example.com", "feat(cli): add run"),
+            (
+                "Bob",
+                "bob@example.com",
+                "fix!: drop the old flag\n\nfixes the build",
+            ),
+            ("Bob", "bob@example.com", "wip"),
+        ];
+
+        for (i, (name, email, message)) in commits.iter().enumerate() {
+            let head = repo.head().unwrap().peel_to_commit().unwrap();
+            // from 2020-01-06 12:00 UTC, a day apart
+            let sig = Signature::new(
+                name,
+                email,
+                &Time::new(1_578_312_000 + i as i64 * 86_400, 0),
+            )
+            .unwrap();
+
+            repo.commit(
+                Some("HEAD"),
+                &sig,
+                &sig,
+                message,
+                &head.tree().unwrap(),
+                &[&head],
+            )
+            .unwrap();
+        }
+
+        td
+    }
+
+    #[test]
+    fn test_subject_regex() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let rule = subject_regex(None).unwrap();
+
+        assert!(rule.is_match("feat: add lint-history"));
+        assert!(rule.is_match("fix(fame)!: count renames"));
+        assert!(!rule.is_match("Fix the build"));
+        assert!(!rule.is_match("feat:missing space"));
+        assert!(subject_regex(Some("^[A-Z]+-[0-9]+ ")).is_ok());
+        assert!(subject_regex(Some("(")).is_err());
+    }
+
+    #[test]
+    fn test_process_lint_history() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td = lint_repo();
+        let path = td.path().to_str().unwrap();
+
+        let lint = |pattern: Option<&str>, fail_under: Option<f64>| {
+            LintHistory::new(
+                LintHistoryArgs::builder(path.to_string())
+                    .pattern(pattern.map(String::from))
+                    .fail_under(fail_under)
+                    .file(Some(String::from("target/test_lint_history.csv")))
+                    .build()
+                    .unwrap(),
+            )
+        };
+
+        let output = lint(None, None).process_lint().unwrap();
+
+        assert_eq!(
+            output,
+            vec![
+                LintOutput {
+                    author: String::from("Bob"),
+                    commits: 4,
+                    compliant: 1,
+                },
+                LintOutput {
+                    author: String::from("Alice"),
+                    commits: 3,
+                    compliant: 1,
+                },
+            ]
+        );
+
+        assert!(lint(None, Some(25.0)).process().is_ok());
+
+        let csv = std::fs::read_to_string("target/test_lint_history.csv").unwrap();
+        assert_eq!(
+            csv,
+            "author,commits,compliant,compliance %\nBob,4,1,25.0\nAlice,3,1,33.3\nTotal,7,2,28.6\n"
+        );
+
+        let err = lint(None, Some(50.0)).process().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "policy violated: 28.6% of the commit messages follow the rule, under 50.0%"
+        );
+
+        let custom = lint(Some("^fixture"), None).process_lint().unwrap();
+        assert_eq!(custom[0].compliant, 2);
+        assert_eq!(custom[1].compliant, 2);
+    }
+}
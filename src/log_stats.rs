@@ -0,0 +1,173 @@
+use super::Processable;
+use crate::diff_stats;
+use crate::diff_stats::CommitStats;
+use crate::identity;
+use crate::table::Table;
+use crate::utils::grit_utils;
+use crate::utils::grit_utils::OutputFormat;
+use anyhow::Result;
+use chrono::NaiveDate;
+
+pub struct LogStatsArgs {
+    path: String,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+    include: Option<String>,
+    exclude: Option<String>,
+    file: Option<String>,
+    format: OutputFormat,
+}
+
+impl LogStatsArgs {
+    pub fn new(
+        path: String,
+        start_date: Option<NaiveDate>,
+        end_date: Option<NaiveDate>,
+        include: Option<String>,
+        exclude: Option<String>,
+        file: Option<String>,
+        format: OutputFormat,
+    ) -> LogStatsArgs {
+        LogStatsArgs {
+            path,
+            start_date,
+            end_date,
+            include,
+            exclude,
+            file,
+            format,
+        }
+    }
+}
+
+pub struct LogStats {
+    args: LogStatsArgs,
+}
+
+impl LogStats {
+    pub fn new(args: LogStatsArgs) -> LogStats {
+        LogStats { args }
+    }
+
+    /// every commit in the date range, merges included, newest first
+    fn process_commits(&self) -> Result<Vec<CommitStats>> {
+        let commits = diff_stats::commit_stats(
+            &self.args.path,
+            self.args.start_date,
+            self.args.end_date,
+            self.args.include.clone(),
+            self.args.exclude.clone(),
+            true,
+        )?;
+
+        Ok(commits
+            .into_iter()
+            .filter(|c| !identity::is_excluded(&c.name, &c.email))
+            .collect())
+    }
+
+    fn display_output(&self, commits: &[CommitStats]) -> Result<()> {
+        let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+
+        let mut table = Table::new();
+
+        table.set_titles(table_row![
+            "commit",
+            "author",
+            "date",
+            "files",
+            "insertions",
+            "deletions",
+            "merge"
+        ]);
+
+        commits.iter().for_each(|c| {
+            table.add_row(table_row![
+                c.oid.to_string(),
+                identity::display_author(&c.name, &c.email),
+                grit_utils::format_date(grit_utils::convert_git_time(&c.time)),
+                c.stats.files,
+                c.stats.insertions,
+                c.stats.deletions,
+                c.merge
+            ]);
+        });
+
+        table.add_label(0, "commit");
+        table.add_label(1, "author");
+        table.add_metric(3, "grit_commit_files", "Files the commit changed.");
+        table.add_metric(4, "grit_commit_insertions", "Lines the commit added.");
+        table.add_metric(5, "grit_commit_deletions", "Lines the commit removed.");
+
+        table.write(&mut w, self.args.format)?;
+
+        Ok(())
+    }
+}
+
+impl Processable<()> for LogStats {
+    fn process(&self) -> Result<()> {
+        let commits = self.process_commits()?;
+
+        self.display_output(&commits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::LevelFilter;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    #[test]
+    fn test_process_log_stats() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+        let file = "target/test_log_stats.csv";
+
+        LogStats::new(LogStatsArgs::new(
+            path.to_string(),
+            None,
+            None,
+            None,
+            None,
+            Some(file.to_string()),
+            OutputFormat::Csv,
+        ))
+        .process()
+        .unwrap();
+
+        let csv = std::fs::read_to_string(file).unwrap();
+        let rows: Vec<Vec<&str>> = csv.lines().map(|l| l.split(',').collect()).collect();
+
+        assert_eq!(
+            rows[0],
+            vec![
+                "commit",
+                "author",
+                "date",
+                "files",
+                "insertions",
+                "deletions",
+                "merge"
+            ]
+        );
+        assert_eq!(
+            rows[1..]
+                .iter()
+                .map(|r| r[1..].join(","))
+                .collect::<Vec<String>>(),
+            vec![
+                "Bob,2020-01-05,1,1,1,false",
+                "Alice,2020-01-02,1,2,0,false",
+                "Bob,2020-01-02,1,4,0,false",
+                "Alice,2020-01-01,2,5,0,false",
+            ]
+        );
+        assert!(rows[1..].iter().all(|r| r[0].len() == 40));
+    }
+}
@@ -0,0 +1,151 @@
+use crate::utils::grit_utils;
+use chrono::{Local, SecondsFormat};
+use log::kv::{Error, Key, Value, VisitSource};
+use log::{LevelFilter, Log, Metadata, Record};
+use simple_logger::SimpleLogger;
+use std::time::Instant;
+
+/// Writes each record as a JSON object on its own line of stderr, for CI runs and log
+/// aggregators.  Besides the time, level, module and message, a record carries the
+/// milliseconds since the run started and any key values it was logged with, such as the
+/// `duration_ms` of a timed phase.
+struct JsonLogger {
+    level: LevelFilter,
+    start: Instant,
+}
+
+impl JsonLogger {
+    fn record_json(&self, record: &Record) -> String {
+        let mut fields = JsonFields::default();
+        // a field that can't be read is left out rather than losing the record
+        let _ = record.key_values().visit(&mut fields);
+
+        let mut members = vec![
+            format!(
+                "\"timestamp\":{}",
+                grit_utils::json_string(
+                    &Local::now().to_rfc3339_opts(SecondsFormat::Millis, false)
+                )
+            ),
+            format!(
+                "\"level\":{}",
+                grit_utils::json_string(record.level().as_str())
+            ),
+            format!(
+                "\"module\":{}",
+                grit_utils::json_string(record.module_path().unwrap_or_else(|| record.target()))
+            ),
+            format!(
+                "\"message\":{}",
+                grit_utils::json_string(&record.args().to_string())
+            ),
+            format!("\"elapsed_ms\":{}", self.start.elapsed().as_millis()),
+        ];
+        members.extend(fields.0);
+
+        format!("{{{}}}", members.join(","))
+    }
+}
+
+impl Log for JsonLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("{}", self.record_json(record));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// the key values of a record as JSON members
+#[derive(Default)]
+struct JsonFields(Vec<String>);
+
+impl<'kvs> VisitSource<'kvs> for JsonFields {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), Error> {
+        self.0.push(format!(
+            "{}:{}",
+            grit_utils::json_string(key.as_str()),
+            json_value(&value)
+        ));
+
+        Ok(())
+    }
+}
+
+/// numbers and booleans as themselves, everything else as a string
+fn json_value(value: &Value) -> String {
+    if let Some(b) = value.to_bool() {
+        b.to_string()
+    } else if let Some(n) = value.to_u64() {
+        n.to_string()
+    } else if let Some(n) = value.to_i64() {
+        n.to_string()
+    } else if let Some(n) = value.to_f64().filter(|n| n.is_finite()) {
+        n.to_string()
+    } else {
+        grit_utils::json_string(&value.to_string())
+    }
+}
+
+/// logs at `level`, as JSON lines with `json` or else in the human readable format
+pub fn init(level: LevelFilter, json: bool) {
+    if json {
+        let logger = JsonLogger {
+            level,
+            start: Instant::now(),
+        };
+
+        log::set_boxed_logger(Box::new(logger))
+            .map(|()| log::set_max_level(level))
+            .unwrap();
+    } else {
+        SimpleLogger::new().with_level(level).init().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Level;
+
+    #[test]
+    fn test_record_json() {
+        let logger = JsonLogger {
+            level: LevelFilter::Info,
+            start: Instant::now(),
+        };
+        let fields: [(&str, Value); 3] = [
+            ("duration_ms", Value::from(12u64)),
+            ("file", Value::from("src/main.rs")),
+            ("cached", Value::from(false)),
+        ];
+
+        let json = logger.record_json(
+            &Record::builder()
+                .args(format_args!("blamed \"{}\"", "src/main.rs"))
+                .level(Level::Info)
+                .module_path(Some("grit::fame"))
+                .key_values(&fields)
+                .build(),
+        );
+
+        assert!(json.starts_with("{\"timestamp\":\""), "{}", json);
+        assert!(
+            json.contains(
+                "\"level\":\"INFO\",\"module\":\"grit::fame\",\"message\":\"blamed \\\"src/main.rs\\\"\",\"elapsed_ms\":"
+            ),
+            "{}",
+            json
+        );
+        assert!(
+            json.ends_with(",\"duration_ms\":12,\"file\":\"src/main.rs\",\"cached\":false}"),
+            "{}",
+            json
+        );
+    }
+}
@@ -1,9 +1,37 @@
 //! grit
 //! Usage:
-//! grit fame [--sort=<field>] [--start-date=<string>] [--end-date=<string>] [--include=<string>] [--exclude=<string>] [--verbose] [--debug]
-//! grit bydate [--start-date=<string>] [--end-date=<string>] [--file=<string>] [--image] [--html] [--ignore-weekends] [--ignore-gap-fill] [--verbose] [--debug]
-//! grit byfile [--in-file=<string>] [--file=<string>] [--image] [--html] [--verbose] [--debug]
-//! grit effort [--start-date=<string>] [--end-date=<string>] [--table] [--include=<string>] [--exclude=<string>] [--verbose] [--debug]
+//! grit fame [--sort=<field>] [--start-date=<string>] [--since-author-first-commit=<string>] [--end-date=<string>] [--at=<string>] [--include=<string>] [--exclude=<string>] [--include-from=<string>] [--exclude-from=<string>] [--include-vendored] [--ignore-revs-file=<string>] [--timing[=<file>]] [--top=<number>] [--min-lines=<number>] [--min-commits=<number>] [--subdir=<string>] [--record=<string>] [--replay=<string>] [--strict] [--by-language] [--language-map=<string>] [--fail-if-bus-factor-below=<number>] [--fail-if-author-share-above=<number>] [--quiet] [--stream] [--sample=<number>] [--threads=<number>] [--former-names] [--extended] [--mode=<string>] [--commit-metric=<string>] [--summary] [--author-display=<string>] [--anonymize] [--anonymize-salt=<string>] [--alias-file=<string>] [--teams=<string>] [--group-by=<string>] [--format=<string>] [--precision=<number>] [--columns=<string>] [--exclude-bots] [--bot-pattern=<string>] [--exclude-authors-regex=<string>] [--only-authors-regex=<string>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit bydate [--start-date=<string>] [--since-author-first-commit=<string>] [--end-date=<string>] [--file=<string>] [--image] [--html] [--chart-theme=<string>] [--chart-title=<string>] [--chart-width=<number>] [--chart-height=<number>] [--chart=<string>] [--ignore-weekends] [--weekend-days=<string>] [--ignore-gap-fill] [--cumulative] [--rolling=<number>] [--split-merges] [--teams=<string>] [--group-by=<string>] [--path-filter=<string>] [--format=<string>] [--exclude-bots] [--bot-pattern=<string>] [--exclude-authors-regex=<string>] [--only-authors-regex=<string>] [--timezone=<string>] [--date-format=<string>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit byfile [--in-file=<string>] [--at=<string>] [--dir=<string>] [--rank] [--history] [--lines=<string>] [--ignore-revs-file=<string>] [--strict] [--file=<string>] [--image] [--html] [--chart-theme=<string>] [--chart-title=<string>] [--chart-width=<number>] [--chart-height=<number>] [--author-display=<string>] [--anonymize] [--anonymize-salt=<string>] [--alias-file=<string>] [--format=<string>] [--precision=<number>] [--exclude-bots] [--bot-pattern=<string>] [--exclude-authors-regex=<string>] [--only-authors-regex=<string>] [--date-format=<string>] [--relative-to=<string>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit effort [--start-date=<string>] [--since-author-first-commit=<string>] [--end-date=<string>] [--table] [--file=<string>] [--image] [--html] [--chart-theme=<string>] [--chart-title=<string>] [--chart-width=<number>] [--chart-height=<number>] [--by-author] [--by-dir[=<number>]] [--trend=<string>] [--week-start=<string>] [--stream] [--sample=<number>] [--threads=<number>] [--min-loc=<number>] [--min-age-days=<number>] [--include-deleted] [--include=<string>] [--exclude=<string>] [--include-from=<string>] [--exclude-from=<string>] [--include-vendored] [--ignore-revs-file=<string>] [--timing[=<file>]] [--top=<number>] [--min-commits=<number>] [--strict] [--author-display=<string>] [--anonymize] [--anonymize-salt=<string>] [--alias-file=<string>] [--teams=<string>] [--group-by=<string>] [--format=<string>] [--columns=<string>] [--exclude-bots] [--bot-pattern=<string>] [--exclude-authors-regex=<string>] [--only-authors-regex=<string>] [--timezone=<string>] [--date-format=<string>] [--relative-to=<string>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit annotate <in-file> [--format=<string>] [--file=<string>] [--ignore-revs-file=<string>] [--author-display=<string>] [--anonymize] [--anonymize-salt=<string>] [--alias-file=<string>] [--date-format=<string>] [--relative-to=<string>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit fame-diff --from=<string> [--to=<string>] [--include=<string>] [--exclude=<string>] [--include-from=<string>] [--exclude-from=<string>] [--include-vendored] [--csv] [--file=<string>] [--author-display=<string>] [--anonymize] [--anonymize-salt=<string>] [--alias-file=<string>] [--format=<string>] [--precision=<number>] [--exclude-bots] [--bot-pattern=<string>] [--exclude-authors-regex=<string>] [--only-authors-regex=<string>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit heat <in-file> [--output=html] [--color-by=<string>] [--github] [--file=<string>] [--ignore-revs-file=<string>] [--author-display=<string>] [--anonymize] [--anonymize-salt=<string>] [--alias-file=<string>] [--date-format=<string>] [--relative-to=<string>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit ownership [--snapshots=<string>] [--depth=<number>] [--treemap] [--start-date=<string>] [--since-author-first-commit=<string>] [--end-date=<string>] [--include=<string>] [--exclude=<string>] [--include-from=<string>] [--exclude-from=<string>] [--include-vendored] [--file=<string>] [--image] [--html] [--chart-theme=<string>] [--chart-title=<string>] [--chart-width=<number>] [--chart-height=<number>] [--author-display=<string>] [--anonymize] [--anonymize-salt=<string>] [--alias-file=<string>] [--format=<string>] [--precision=<number>] [--exclude-bots] [--bot-pattern=<string>] [--exclude-authors-regex=<string>] [--only-authors-regex=<string>] [--date-format=<string>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit growth [--interval=<string>] [--week-start=<string>] [--start-date=<string>] [--since-author-first-commit=<string>] [--end-date=<string>] [--include=<string>] [--exclude=<string>] [--include-from=<string>] [--exclude-from=<string>] [--file=<string>] [--image] [--html] [--chart-theme=<string>] [--chart-title=<string>] [--chart-width=<number>] [--chart-height=<number>] [--format=<string>] [--date-format=<string>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit survival [--interval=<string>] [--week-start=<string>] [--start-date=<string>] [--since-author-first-commit=<string>] [--end-date=<string>] [--include=<string>] [--exclude=<string>] [--include-from=<string>] [--exclude-from=<string>] [--file=<string>] [--image] [--html] [--chart-theme=<string>] [--chart-title=<string>] [--chart-width=<number>] [--chart-height=<number>] [--format=<string>] [--precision=<number>] [--date-format=<string>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit tenure [--start-date=<string>] [--since-author-first-commit=<string>] [--end-date=<string>] [--inactive-days=<number>] [--path-filter=<string>] [--file=<string>] [--author-display=<string>] [--anonymize] [--anonymize-salt=<string>] [--alias-file=<string>] [--format=<string>] [--exclude-bots] [--bot-pattern=<string>] [--exclude-authors-regex=<string>] [--only-authors-regex=<string>] [--date-format=<string>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit worklife [--start-date=<string>] [--since-author-first-commit=<string>] [--end-date=<string>] [--work-hours=<string>] [--weekend-days=<string>] [--timezone=<string>] [--file=<string>] [--author-display=<string>] [--anonymize] [--anonymize-salt=<string>] [--alias-file=<string>] [--format=<string>] [--precision=<number>] [--exclude-bots] [--bot-pattern=<string>] [--exclude-authors-regex=<string>] [--only-authors-regex=<string>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit commitsize [--start-date=<string>] [--since-author-first-commit=<string>] [--end-date=<string>] [--include=<string>] [--exclude=<string>] [--include-from=<string>] [--exclude-from=<string>] [--restrict-author=<string>] [--giant-lines=<number>] [--list-giant] [--file=<string>] [--author-display=<string>] [--anonymize] [--anonymize-salt=<string>] [--alias-file=<string>] [--format=<string>] [--precision=<number>] [--exclude-bots] [--bot-pattern=<string>] [--exclude-authors-regex=<string>] [--only-authors-regex=<string>] [--date-format=<string>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit log-stats [--start-date=<string>] [--since-author-first-commit=<string>] [--end-date=<string>] [--include=<string>] [--exclude=<string>] [--include-from=<string>] [--exclude-from=<string>] [--file=<string>] [--author-display=<string>] [--anonymize] [--anonymize-salt=<string>] [--alias-file=<string>] [--format=<string>] [--exclude-bots] [--bot-pattern=<string>] [--exclude-authors-regex=<string>] [--only-authors-regex=<string>] [--timezone=<string>] [--date-format=<string>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit merges [--start-date=<string>] [--since-author-first-commit=<string>] [--end-date=<string>] [--file=<string>] [--author-display=<string>] [--anonymize] [--anonymize-salt=<string>] [--alias-file=<string>] [--format=<string>] [--exclude-bots] [--bot-pattern=<string>] [--exclude-authors-regex=<string>] [--only-authors-regex=<string>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit compare --base=<string> [--head=<string>] [--include=<string>] [--exclude=<string>] [--include-from=<string>] [--exclude-from=<string>] [--file=<string>] [--author-display=<string>] [--anonymize] [--anonymize-salt=<string>] [--alias-file=<string>] [--format=<string>] [--exclude-bots] [--bot-pattern=<string>] [--exclude-authors-regex=<string>] [--only-authors-regex=<string>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit branches [--base=<string>] [--older-than=<number>] [--file=<string>] [--author-display=<string>] [--anonymize] [--anonymize-salt=<string>] [--alias-file=<string>] [--format=<string>] [--exclude-bots] [--bot-pattern=<string>] [--exclude-authors-regex=<string>] [--only-authors-regex=<string>] [--date-format=<string>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit releases [--tag-pattern=<string>] [--semver] [--file=<string>] [--author-display=<string>] [--anonymize] [--anonymize-salt=<string>] [--alias-file=<string>] [--format=<string>] [--exclude-bots] [--bot-pattern=<string>] [--exclude-authors-regex=<string>] [--only-authors-regex=<string>] [--date-format=<string>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit newcontributors [--start-date=<string>] [--since-author-first-commit=<string>] [--end-date=<string>] [--file=<string>] [--author-display=<string>] [--anonymize] [--anonymize-salt=<string>] [--alias-file=<string>] [--format=<string>] [--exclude-bots] [--bot-pattern=<string>] [--exclude-authors-regex=<string>] [--only-authors-regex=<string>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit lint-history [--start-date=<string>] [--since-author-first-commit=<string>] [--end-date=<string>] [--pattern=<string>] [--include-merges] [--fail-under=<number>] [--file=<string>] [--author-display=<string>] [--anonymize] [--anonymize-salt=<string>] [--alias-file=<string>] [--format=<string>] [--precision=<number>] [--exclude-bots] [--bot-pattern=<string>] [--exclude-authors-regex=<string>] [--only-authors-regex=<string>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit identities [--start-date=<string>] [--since-author-first-commit=<string>] [--end-date=<string>] [--max-distance=<number>] [--write-aliases=<string>] [--write-mailmap=<string>] [--interactive] [--file=<string>] [--format=<string>] [--exclude-bots] [--bot-pattern=<string>] [--exclude-authors-regex=<string>] [--only-authors-regex=<string>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit age [--include=<string>] [--exclude=<string>] [--include-from=<string>] [--exclude-from=<string>] [--include-vendored] [--older-than=<number>] [--as-of=<string>] [--file=<string>] [--format=<string>] [--precision=<number>] [--exclude-bots] [--bot-pattern=<string>] [--exclude-authors-regex=<string>] [--only-authors-regex=<string>] [--strict] [--date-format=<string>] [--relative-to=<string>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit owners-audit [--codeowners=<string>] [--threshold=<number>] [--include=<string>] [--exclude=<string>] [--include-from=<string>] [--exclude-from=<string>] [--include-vendored] [--teams=<string>] [--alias-file=<string>] [--file=<string>] [--format=<string>] [--precision=<number>] [--exclude-bots] [--bot-pattern=<string>] [--exclude-authors-regex=<string>] [--only-authors-regex=<string>] [--strict] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit symbols [--include=<string>] [--exclude=<string>] [--include-from=<string>] [--exclude-from=<string>] [--include-vendored] [--author-display=<string>] [--anonymize] [--anonymize-salt=<string>] [--alias-file=<string>] [--file=<string>] [--format=<string>] [--precision=<number>] [--exclude-bots] [--bot-pattern=<string>] [--exclude-authors-regex=<string>] [--only-authors-regex=<string>] [--relative-to=<string>] [--strict] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit timeline [--metric=<string>] [--start-date=<string>] [--since-author-first-commit=<string>] [--end-date=<string>] [--restrict-author=<string>] [--top=<number>] [--week-start=<string>] [--timezone=<string>] [--file=<string>] [--image] [--html] [--chart-theme=<string>] [--chart-title=<string>] [--chart-width=<number>] [--chart-height=<number>] [--format=<string>] [--author-display=<string>] [--anonymize] [--anonymize-salt=<string>] [--alias-file=<string>] [--exclude-bots] [--bot-pattern=<string>] [--exclude-authors-regex=<string>] [--only-authors-regex=<string>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit snapshot [--store=<string>] [--include=<string>] [--exclude=<string>] [--include-from=<string>] [--exclude-from=<string>] [--include-vendored] [--author-display=<string>] [--anonymize] [--anonymize-salt=<string>] [--alias-file=<string>] [--exclude-bots] [--bot-pattern=<string>] [--exclude-authors-regex=<string>] [--only-authors-regex=<string>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit trends [--store=<string>] [--since=<string>] [--file=<string>] [--format=<string>] [--precision=<number>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit report [--analyses=<string>] [--out-dir=<string>] [--start-date=<string>] [--since-author-first-commit=<string>] [--end-date=<string>] [--include=<string>] [--exclude=<string>] [--include-from=<string>] [--exclude-from=<string>] [--include-vendored] [--author-display=<string>] [--anonymize] [--anonymize-salt=<string>] [--alias-file=<string>] [--format=<string>] [--exclude-bots] [--bot-pattern=<string>] [--exclude-authors-regex=<string>] [--only-authors-regex=<string>] [--date-format=<string>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit tui [--start-date=<string>] [--since-author-first-commit=<string>] [--end-date=<string>] [--include=<string>] [--exclude=<string>] [--include-from=<string>] [--exclude-from=<string>] [--include-vendored] [--restrict-author=<string>] [--author-display=<string>] [--anonymize] [--anonymize-salt=<string>] [--alias-file=<string>] [--exclude-bots] [--bot-pattern=<string>] [--exclude-authors-regex=<string>] [--only-authors-regex=<string>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit serve [--bind=<string>] [--port=<number>] [--include-vendored] [--author-display=<string>] [--anonymize] [--anonymize-salt=<string>] [--alias-file=<string>] [--exclude-bots] [--bot-pattern=<string>] [--exclude-authors-regex=<string>] [--only-authors-regex=<string>] [--repo=<string>] [--no-discover] [--verbose] [--debug] [--json-logs] [--no-progress]
+//! grit completions <shell>
+//! grit help-pages [--out-dir=<string>] [--verbose] [--debug] [--json-logs] [--no-progress]
 //!
 //! Options:
 //! --debug                     enables debug
@@ -11,16 +39,120 @@
 //! --sort=<field>              sort field, either 'commit' (default), 'loc', 'files'
 //! --start-date=<string>       start date in YYYY-MM-DD format.
 //! --end-date=<string>         end date in YYYY-MM-DD format.
-//! --include=<string>          comma delimited, glob file path to include path1/*,path2/*
-//! --exclude=<string>          comma delimited, glob file path to exclude path1/*,path2/*
-//! --file=<string>             output file for the by date file.  Sends to stdout by default.  If using image flag, file name needs to be *.svg
+//! --since-author-first-commit=<string> starts at the day of the author's first commit, matched by name, email or alias, instead of --start-date
+//! --include=<string>          comma delimited, glob file path to include path1/*,path2/*.  Either / or \ separates directories
+//! --exclude=<string>          comma delimited, glob file path to exclude path1/*,path2/*.  Either / or \ separates directories
+//! --include-from=<string>     file of globs to include, one per line.  Blank lines and lines starting with # are skipped.  Added to --include
+//! --exclude-from=<string>     file of globs to exclude, one per line.  Blank lines and lines starting with # are skipped.  Added to --exclude
+//! --include-vendored          keeps files .gitattributes marks linguist-vendored or linguist-generated, which are left out by default
+//! --ignore-revs-file=<string> blames lines past the commits listed in the file, e.g. .git-blame-ignore-revs.  Defaults to the blame.ignoreRevsFile git config
+//! --timing[=<file>]           fame and effort print the time spent in each phase, and blaming the slowest files, to stderr.  With a file, every timing is also written to it as JSON
+//! --path-filter=<string>      comma delimited, glob file paths.  bydate and tenure only count commits touching a matching path
+//! --file=<string>             output file, written in --format or else the format its .csv, .json, .md, .prom or .txt extension names.  Sends to stdout by default.  With --image, file name needs to be *.svg
 //! --in-file=<string>          input file for by_file
-//! --image                     creates an image for the by_date & by_file graph.  file is required
+//! --dir=<string>              summarize every file in the directory for by_file instead of a single file
+//! --rank                      with dir, order the files by the top contributor's share of lines
+//! --history                   byfile lists every commit changing the file, following renames, instead of the blame by author and date
+//! --lines=<string>            byfile blames only this 1-based, inclusive range of the file's lines, e.g. 100-250
+//! --image                     creates an image for the by_date, by_file & effort graph.  file is required
 //! --html                      creates a HTML file to help visualize the SVG output
+//! --chart=<string>            bydate image type, either 'line' (default) or a 'calendar' heatmap
+//! --chart-theme=<string>      image color theme, either 'default', 'tableau' or 'dark'
+//! --chart-title=<string>      image title, replacing the default title
+//! --chart-width=<number>      image width in pixels, overriding the size picked from the data
+//! --chart-height=<number>     image height in pixels, overriding the size picked from the data
 //! --table                     display as a table to stdout
+//! --top=<number>              only show the first N rows, the rest are collapsed into an 'Others' row
+//! --min-lines=<number>        fame authors with fewer lines are collapsed into an 'Others' row
+//! --min-commits=<number>      rows with fewer commits are collapsed into an 'Others' row
+//! --min-loc=<number>          effort leaves out files with fewer lines, noting how many in a footer
+//! --min-age-days=<number>     effort leaves out files whose oldest line is younger than this many days
+//! --include-deleted           effort adds the files deleted in the date range, from the diffs of its commits, with churn and deleted columns
+//! --at=<string>               blame files as of this revision (sha, branch, tag) instead of HEAD
+//! --subdir=<string>           fame only blames the files under this directory, which must exist in the blamed tree
+//! --record=<string>           saves fame's raw blame data to this file
+//! --replay=<string>           re-renders fame from a recorded file, so only sort, filter, and output args apply
+//! --exclude-bots              leave out authors matching dependabot, renovate or [bot], case insensitive.  Not applied to annotate, heat, or growth
+//! --bot-pattern=<string>      comma delimited, regex of more author names or emails to treat as bots with exclude-bots
+//! --exclude-authors-regex=<string> regex of author names or emails to leave out, e.g. '.*@vendor\.com'.  Not applied to annotate, heat, or growth
+//! --only-authors-regex=<string> regex of author names or emails to count, leaving out everyone else
+//! --timezone=<string>         day commits are counted on, 'local' (default), 'utc', each commit's 'author' offset, a fixed offset such as '+05:30' or an IANA name such as 'Europe/Paris'
+//! --date-format=<string>      strftime format of the dates in the output, e.g. '%d/%m/%Y', or 'iso-week' for ISO week dates.  Defaults to '%Y-%m-%d'
+//! --precision=<number>        decimals of the percentages in fame, fame-diff, byfile, ownership, survival, worklife, commitsize, lint-history, age, owners-audit, symbols and trends.  Defaults to 1
+//! --by-author                 effort rows per author of each file instead of per file totals
+//! --by-dir[=<number>]         effort rolls files up into their leading directories, one level unless a depth is given
+//! --trend=<string>            effort outputs each file's commits per period instead, either 'daily', 'weekly', 'monthly', 'quarterly' or 'yearly'
+//! --strict                    fail the run when any file could not be processed.  Skipped files are listed on stderr either way
+//! --by-language               fame matrix of each author's LOC per language, by file extension
+//! --language-map=<string>     comma delimited ext=Language pairs overriding the default languages, e.g. ts=Frontend,rs=Backend
+//! --from=<string>             fame-diff revision to compare from
+//! --to=<string>               fame-diff revision to compare to.  Defaults to HEAD
+//! --format=<string>           output format, either 'table', 'csv', 'json', 'markdown' or 'prom' (Prometheus gauges for fame, bydate and effort).  Defaults to the format a --file's .csv, .json, .md, .prom or .txt extension names, else a table on a terminal and csv (json for annotate) when piped or written to a file
+//! --color-by=<string>         color heat lines by 'age' (default) or 'author'
+//! --github                    heat links authors to their GitHub login and avatar.  Built with --features github, uses GITHUB_TOKEN for non noreply emails
+//! --snapshots=<string>        ownership snapshot period, either 'monthly', 'quarterly' (default) or 'yearly'
+//! --interval=<string>         growth sample interval, either 'daily', 'weekly' (default) or 'monthly'
+//!                             survival sample interval, either 'daily', 'weekly', 'monthly' (default), 'quarterly' or 'yearly'
+//! --out-dir=<string>          help-pages writes the pages to this directory.  Defaults to 'man'
+//!                             report writes each analysis to this directory.  Defaults to 'report'
+//! --analyses=<string>         comma delimited analyses report runs, from 'fame', 'age', 'effort', 'commitsize' and 'timeline'.  Defaults to all of them
+//! --fail-if-bus-factor-below=<number> fame exits with code 2 when fewer authors than this own more than half of the lines
+//! --fail-if-author-share-above=<number> fame exits with code 2 when any author owns more than this percent of the lines
+//! --pattern=<string>          lint-history regex commit subjects must match, replacing the conventional commits rule
+//! --base=<string>             compare branch (sha, branch, tag) the head branch is measured against, and branches counts commits ahead and behind.  Defaults to HEAD for branches
+//! --head=<string>             compare branch (sha, branch, tag) whose unique commits are reported.  Defaults to HEAD
+//! --include-merges            lint-history also checks the subjects of merge commits
+//! --fail-under=<number>       lint-history exits with code 2 when fewer than this percent of the commit subjects match
+//! --max-distance=<number>     identities clusters names within this many edits of each other.  Defaults to 2
+//! --write-aliases=<string>    identities writes the suggested merges to this --alias-file
+//! --write-mailmap=<string>    identities writes the suggested merges to this .mailmap
+//! --interactive               identities asks to confirm each suggested merge before writing it
+//! --bind=<string>             serve listens on this address.  Defaults to 127.0.0.1
+//! --port=<number>             serve listens on this port.  Defaults to 8080
+//! --store=<string>            JSONL file snapshot appends to and trends reads.  Defaults to grit-snapshots.jsonl in the git directory, shared by all of a repo's worktrees
+//! --since=<string>            trends compares to the first snapshot taken on or after this date in YYYY-MM-DD format.  Defaults to the oldest
+//! -q, --quiet                 leaves the report off stdout, for CI gating on the exit code.  --file is still written
+//! --mode=<string>             fame credits authors by 'blame' (default) or by the lines their commits added and removed with 'diff'
+//! --commit-metric=<string>    fame counts the commits behind each author's blamed lines with 'blame' (default), or every commit they made in the date range with 'revwalk'
+//! --summary                   fame appends the Gini coefficient of the LOC per author, the top 3 authors' share of the LOC and the mean and median LOC per author
+//! --stream                    fame and effort write each file's results as csv once blamed, then a summary, bounding memory on very large repos
+//! --sample=<number>           fame and effort blame only this percent of the files, picked by a hash of their path, and estimate the repo's totals from them
+//! --threads=<number>          worker threads fame and effort blame files on.  Defaults to one per CPU
+//! --former-names              fame lists on stderr the paths renamed files had when their blamed lines were written, with the LOC from each
+//! --extended                  fame adds each author's first and last commit dates, active days and LOC per active day
+//! --columns=<string>          comma delimited fame and effort columns to output, in this order, named by their titles ignoring case and punctuation, e.g. author,loc,commits
+//! --giant-lines=<number>      commitsize counts commits changing at least this many lines as giant.  Defaults to 1000
+//! --list-giant                commitsize lists the giant commits instead of the per author statistics
+//! --week-start=<string>       first day of the week for weekly growth and survival intervals and effort trends, e.g. 'sun'.  Defaults to 'mon'
+//! --work-hours=<string>       worklife counts weekday commits outside these hours, e.g. '9-18' (default) or '08:30-17:00'
+//! --older-than=<number>       age counts lines older than this many months as stale, defaulting to 12.  branches lists only the branches without a commit for more than this many days
+//! --tag-pattern=<string>      releases only counts the tags matching this glob, e.g. 'v*'.  Defaults to every tag
+//! --semver                    releases only counts MAJOR.MINOR.PATCH tags, in version order, with each release's type, line and authors and the mean commits and authors per type
+//! --metric=<string>           timeline counts either 'commits' (default) or 'lines' added per author and week
+//! --as-of=<string>            age measures line ages at this date in YYYY-MM-DD format.  Defaults to today
+//! --codeowners=<string>       owners-audit reads this CODEOWNERS file instead of .github/CODEOWNERS, CODEOWNERS or docs/CODEOWNERS at HEAD
+//! --threshold=<number>        owners-audit flags CODEOWNERS patterns whose owners wrote less than this percent of the lines.  Defaults to 10
+//! --inactive-days=<number>    tenure flags authors without a commit for more than this many days.  Defaults to 90
+//! --depth=<number>            number of leading directories ownership groups files by.  Defaults to 1
+//! --treemap                   ownership blames the repo once, as of the end date, and outputs its directory tree as JSON for d3.  With --image, a treemap SVG sized by LOC and colored by owner
 //! --ignore-weekends           ignore weekends when calculating # of commits
+//! --weekend-days=<string>     comma delimited days bydate --ignore-weekends and worklife treat as the weekend, e.g. 'fri,sat'.  Defaults to 'sat,sun'
 //! --ignore-gap-fill           ignore filling empty dates with 0 commits
+//! --cumulative                bydate outputs and charts the running total of commits up to each date instead of the commits per date
+//! --rolling=<number>          bydate adds an average column of the commits over the N days ending on each date and charts it instead of the daily count
+//! --split-merges              bydate counts merge and regular commits as two series, with a kind column in the text output
+//! --repo=<string>             path in the git repository to analyze, which is found by searching up from it as git does.  Defaults to the current directory
+//! --no-discover               only opens the repository at --repo instead of searching up from it, e.g. to keep an outer repo from being picked up
+//! --relative-to=<string>      report file paths relative to the 'repo' root (default) or the 'cwd'
+//! --author-display=<string>   show authors by 'name' (default), 'email', 'both' (name <email>) or 'initials'
+//! --anonymize                 replaces every author with a stable pseudonym, Author- and a hash of their email, in every output so it can be shared
+//! --anonymize-salt=<string>   secret mixed into the anonymize hashes, so a pseudonym cannot be matched to a known email
+//! --alias-file=<string>       toml file of "Name <email>" = ["alias", ...] entries.  Authors committing under any alias are shown as that identity
+//! --teams=<string>            toml file of "Team" = ["name", "email", ...] entries mapping authors to teams.  owners-audit matches @org/team owners against them
+//! --group-by=<string>         fame, bydate and effort group authors by 'author' (default) or by their 'team' in --teams
 //! -v, --verbose
+//! --json-logs                 writes the logs to stderr as JSON lines with a timestamp, level, module, message, elapsed_ms and fields such as duration_ms, for CI and log aggregation
+//! --no-progress               writes a line to stderr at every tenth of the files instead of drawing progress bars.  Bars are also left out when stderr is not a terminal
 
 #[macro_use]
 extern crate log;
@@ -29,16 +161,63 @@ extern crate charts;
 extern crate chrono;
 extern crate clap;
 extern crate csv;
-extern crate simple_logger;
 extern crate tokio;
 
 #[macro_use]
 mod utils;
+#[macro_use]
+mod table;
 
+mod age;
+mod annotate;
+mod author_stats;
+mod branches;
 mod by_date;
 mod by_file;
+mod cache;
+mod cancel;
+mod chart;
+mod commit_size;
+mod compare;
+mod completions;
+mod diff_stats;
 mod effort;
+mod error;
 mod fame;
+mod fame_diff;
+mod github;
+mod growth;
+mod heat;
+mod help_pages;
+mod identities;
+mod identity;
+mod ignore_revs;
+mod language;
+mod lint_history;
+mod log_stats;
+mod logging;
+mod merges;
+mod metric;
+mod new_contributors;
+mod output;
+mod owners_audit;
+mod ownership;
+mod policy;
+mod progress;
+mod releases;
+mod report;
+mod sample;
+mod serve;
+mod snapshot;
+mod survival;
+#[cfg(feature = "symbols")]
+mod symbols;
+mod tenure;
+mod timeline;
+mod timing;
+mod treemap;
+mod tui;
+mod worklife;
 
 #[cfg(test)]
 #[macro_use]
@@ -46,16 +225,50 @@ mod grit_test;
 
 pub use crate::utils::grit_utils;
 
+use crate::age::{Age, AgeArgs};
+use crate::annotate::{Annotate, AnnotateArgs};
+use crate::branches::{Branches, BranchesArgs};
 use crate::by_date::{ByDate, ByDateArgs};
-use crate::by_file::{ByFile, ByFileArgs};
+use crate::by_file::{ByFile, ByFileArgs, LineRange};
+use crate::chart::ChartOptions;
+use crate::commit_size::{CommitSize, CommitSizeArgs};
+use crate::compare::{Compare, CompareArgs};
+use crate::completions::{Completions, CompletionsArgs};
 use crate::effort::{Effort, EffortArgs};
-use crate::fame::{Fame, FameArgs};
+use crate::error::GritError;
+use crate::fame::{CommitMetric, Fame, FameArgs, FameMode};
+use crate::fame_diff::{FameDiff, FameDiffArgs};
+use crate::growth::{Growth, GrowthArgs};
+use crate::heat::{Heat, HeatArgs};
+use crate::help_pages::{HelpPages, HelpPagesArgs};
+use crate::identities::{Identities, IdentitiesArgs};
+use crate::lint_history::{LintHistory, LintHistoryArgs};
+use crate::log_stats::{LogStats, LogStatsArgs};
+use crate::merges::{Merges, MergesArgs};
+use crate::new_contributors::{NewContributors, NewContributorsArgs};
+use crate::owners_audit::{OwnersAudit, OwnersAuditArgs};
+use crate::ownership::{Ownership, OwnershipArgs};
+use crate::policy::Policy;
+use crate::releases::{Releases, ReleasesArgs};
+use crate::report::{Report, ReportArgs};
+use crate::serve::{Serve, ServeArgs};
+use crate::snapshot::{Snapshot, SnapshotArgs, Trends, TrendsArgs};
+use crate::survival::{Survival, SurvivalArgs};
+#[cfg(feature = "symbols")]
+use crate::symbols::{Symbols, SymbolsArgs};
+use crate::tenure::{Tenure, TenureArgs};
+use crate::timeline::{Timeline, TimelineArgs};
+use crate::tui::{Tui, TuiArgs};
+use crate::utils::grit_utils::{OutputFormat, RelativeTo, Timezone};
+use crate::worklife::{WorkHours, Worklife, WorklifeArgs};
 
 use anyhow::Result;
-use chrono::{Date, Local, NaiveDate, TimeZone};
+use chrono::NaiveDate;
 use clap::{App, Arg, ArgMatches};
 use log::LevelFilter;
-use simple_logger::SimpleLogger;
+use std::io;
+use std::io::IsTerminal;
+use std::process;
 use std::str;
 
 pub const DEFAULT_THREADS: usize = 10;
@@ -64,22 +277,65 @@ pub trait Processable<T> {
     fn process(&self) -> Result<T>;
 }
 
-fn parse_datelocal(date_string: &str) -> Result<Date<Local>> {
+fn parse_datelocal(date_string: &str) -> Result<NaiveDate> {
     let utc_dt = NaiveDate::parse_from_str(date_string, "%Y-%m-%d");
 
     match utc_dt {
-        Ok(d) => Ok(Local
-            .from_local_date(&d)
-            .single()
-            .expect("Cannot unwrap date")),
+        Ok(d) => Ok(d),
         Err(_e) => {
             panic!("Dates must be in the 'YYYY-MM-DD' format ");
         }
     }
 }
 
-fn parse_date_arg(date_string: Option<&str>) -> Option<Date<Local>> {
-    let result: Option<Date<Local>> = match date_string {
+/// the comma delimited globs of the `name` option followed by those in the file of its
+/// `-from` option.  Exits when the file cannot be read.
+fn patterns_arg(args: &ArgMatches, name: &str) -> Option<String> {
+    let mut patterns: Vec<String> = args.value_of(name).map(String::from).into_iter().collect();
+
+    if let Some(file) = args.value_of(format!("{}-from", name).as_str()) {
+        patterns.extend(exit_on_invalid(grit_utils::read_patterns_file(file)));
+    }
+
+    if patterns.is_empty() {
+        None
+    } else {
+        Some(patterns.join(","))
+    }
+}
+
+/// the root of the repository `--repo` is in, found by searching up from it as git does
+/// unless `--no-discover`.  Left as given when there is none, for opening it to report.
+/// Exits when the root isn't valid UTF-8.
+fn repo_arg(args: &ArgMatches) -> String {
+    let path = args.value_of("repo").unwrap();
+
+    if args.is_present("no-discover") {
+        return path.to_string();
+    }
+
+    exit_on_invalid(grit_utils::discover_repo(path).or_else(
+        |e| match e.downcast_ref::<GritError>() {
+            Some(GritError::RepoNotFound(_)) => Ok(path.to_string()),
+            _ => Err(e),
+        },
+    ))
+}
+
+/// the start date, or the day of the first commit of the `--since-author-first-commit`
+/// author.  Exits when the author has no commits.
+fn start_date_arg(args: &ArgMatches) -> Option<NaiveDate> {
+    match args.value_of("since-author-first-commit") {
+        Some(author) => Some(exit_on_invalid(grit_utils::author_first_commit_date(
+            &repo_arg(args),
+            author,
+        ))),
+        None => parse_date_arg(args.value_of("start-date")),
+    }
+}
+
+fn parse_date_arg(date_string: Option<&str>) -> Option<NaiveDate> {
+    let result: Option<NaiveDate> = match date_string {
         Some(b) => {
             let dt = parse_datelocal(b);
 
@@ -92,76 +348,411 @@ fn parse_date_arg(date_string: Option<&str>) -> Option<Date<Local>> {
 }
 
 fn convert_str_string(op: Option<&str>) -> Option<String> {
-    let result = match op {
-        Some(s) => Some(s.to_string()),
-        None => None,
-    };
+    op.map(|s| s.to_string())
+}
 
-    result
+fn parse_usize_arg(value: Option<&str>) -> Option<usize> {
+    value.map(|v| v.parse().expect("Value must be a positive number"))
 }
 
-fn is_svg(val: &str) -> Result<(), String> {
-    if grit_utils::check_file_type(val, "svg") {
-        Ok(())
-    } else {
-        Err(String::from("the file format must by svg"))
+fn parse_i32_arg(value: Option<&str>) -> Option<i32> {
+    value.map(|v| v.parse().expect("Value must be a number"))
+}
+
+fn is_number(val: &str) -> Result<(), String> {
+    match val.parse::<usize>() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(String::from("the value must be a positive number")),
     }
 }
 
-fn is_csv(val: &str) -> Result<(), String> {
-    if grit_utils::check_file_type(val, "csv") {
-        Ok(())
-    } else {
-        Err(String::from("the file format must be csv"))
+/// `--relative-to`, exiting with clap's usage error when it can't be parsed
+fn parse_relative_to(args: &ArgMatches) -> RelativeTo {
+    args.value_of_t("relative-to").unwrap_or_else(|e| e.exit())
+}
+
+fn is_port(val: &str) -> Result<(), String> {
+    match val.parse::<u16>() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(String::from("the port must be a number up to 65535")),
+    }
+}
+
+fn is_percent(val: &str) -> Result<(), String> {
+    match val.parse::<f64>() {
+        Ok(p) if (0.0..=100.0).contains(&p) => Ok(()),
+        _ => Err(String::from("the value must be a percent from 0 to 100")),
+    }
+}
+
+fn is_sample_percent(val: &str) -> Result<(), String> {
+    match val.parse::<f64>() {
+        Ok(p) if p > 0.0 && p <= 100.0 => Ok(()),
+        _ => Err(String::from(
+            "the sample must be a percent above 0, up to 100",
+        )),
     }
 }
 
+fn is_date(val: &str) -> Result<(), String> {
+    match NaiveDate::parse_from_str(val, "%Y-%m-%d") {
+        Ok(_) => Ok(()),
+        Err(_) => Err(String::from("dates must be in the 'YYYY-MM-DD' format")),
+    }
+}
+
+fn is_date_format(val: &str) -> Result<(), String> {
+    grit_utils::parse_date_format(val)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn is_analyses(val: &str) -> Result<(), String> {
+    report::parse_analyses(val)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn is_bot_pattern(val: &str) -> Result<(), String> {
+    identity::bot_regex(Some(val))
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn is_author_regex(val: &str) -> Result<(), String> {
+    identity::author_regex(val)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn is_subject_pattern(val: &str) -> Result<(), String> {
+    lint_history::subject_regex(Some(val))
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn is_alias_file(val: &str) -> Result<(), String> {
+    identity::Aliases::load(val)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn is_teams_file(val: &str) -> Result<(), String> {
+    identity::Teams::load(val)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn is_work_hours(val: &str) -> Result<(), String> {
+    val.parse::<WorkHours>()
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn is_line_range(val: &str) -> Result<(), String> {
+    val.parse::<LineRange>()
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn is_weekdays(val: &str) -> Result<(), String> {
+    grit_utils::parse_weekdays(val)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn is_timezone(val: &str) -> Result<(), String> {
+    val.parse::<Timezone>()
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
 fn main() {
     let arg_start_date = Arg::new("start-date")
-        .about("start date in YYYY-MM-DD format")
+        .help("start date in YYYY-MM-DD format")
         .takes_value(true)
+        .validator(is_date)
         .long("start-date");
 
+    let arg_since_author_first_commit = Arg::new("since-author-first-commit")
+        .help("starts at the day of the author's first commit, matched by name, email or alias, instead of --start-date")
+        .takes_value(true)
+        .conflicts_with("start-date")
+        .long("since-author-first-commit");
+
     let arg_end_date = Arg::new("end-date")
-        .about("end date in YYYY-MM-DD format")
+        .help("end date in YYYY-MM-DD format")
         .takes_value(true)
+        .validator(is_date)
         .long("end-date");
 
+    let arg_store = Arg::new("store")
+        .help("JSONL file the snapshots are kept in.  Defaults to grit-snapshots.jsonl in the git directory, shared by all of a repo's worktrees")
+        .takes_value(true)
+        .long("store");
+
     let arg_include = Arg::new("include")
-        .about("comma delimited, glob file path to include path1/*,path2/*")
+        .help("comma delimited, glob file path to include path1/*,path2/*.  Either / or \\ separates directories")
         .takes_value(true)
         .long("include");
 
     let arg_exclude = Arg::new("exclude")
-        .about("comma delimited, glob file path to exclude path1/*,path2/*")
+        .help("comma delimited, glob file path to exclude path1/*,path2/*.  Either / or \\ separates directories")
         .takes_value(true)
         .long("exclude");
 
+    let arg_include_from = Arg::new("include-from")
+        .help("file of globs to include, one per line.  Blank lines and lines starting with # are skipped.  Added to --include")
+        .takes_value(true)
+        .long("include-from");
+
+    let arg_exclude_from = Arg::new("exclude-from")
+        .help("file of globs to exclude, one per line.  Blank lines and lines starting with # are skipped.  Added to --exclude")
+        .takes_value(true)
+        .long("exclude-from");
+
+    let arg_include_vendored = Arg::new("include-vendored")
+        .help("keeps files .gitattributes marks linguist-vendored or linguist-generated, which are left out by default")
+        .takes_value(false)
+        .long("include-vendored");
+
+    let arg_ignore_revs_file = Arg::new("ignore-revs-file")
+        .help("blames lines past the commits listed in the file, e.g. .git-blame-ignore-revs.  Defaults to the blame.ignoreRevsFile git config")
+        .takes_value(true)
+        .long("ignore-revs-file");
+
+    let arg_timing = Arg::new("timing")
+        .help("prints the time spent in each phase, and blaming the slowest files, to stderr.  --timing=<file> also writes every timing to the file as JSON")
+        .takes_value(true)
+        .min_values(0)
+        .require_equals(true)
+        .long("timing");
+
     let arg_restrict_author = Arg::new("restrict-author")
-        .about("comma delimited of author's names to restrict")
+        .help("comma delimited of author's names to restrict")
         .takes_value(true)
         .long("restrict-author");
 
+    let arg_date_format = Arg::new("date-format")
+        .help("strftime format of the dates in the output, e.g. '%d/%m/%Y', or 'iso-week' for ISO week dates like 2020-W01-3.  Defaults to '%Y-%m-%d'")
+        .takes_value(true)
+        .validator(is_date_format)
+        .long("date-format");
+
+    let arg_precision = Arg::new("precision")
+        .help("decimals of the percentages in the output.  Defaults to 1")
+        .takes_value(true)
+        .validator(is_number)
+        .long("precision");
+
+    let arg_repo = Arg::new("repo")
+        .help("path to the git repository to analyze.  Defaults to the current directory")
+        .takes_value(true)
+        .default_value(".")
+        .long("repo");
+
+    let arg_no_discover = Arg::new("no-discover")
+        .help("only opens the repository at --repo, instead of searching up from it for the one it is in")
+        .takes_value(false)
+        .long("no-discover");
+
+    let arg_chart_theme = Arg::new("chart-theme")
+        .help("color theme for images, either 'default', 'tableau' or 'dark'")
+        .requires("image")
+        .takes_value(true)
+        .possible_values(["default", "tableau", "dark"])
+        .default_value("default")
+        .long("chart-theme");
+
+    let arg_chart_title = Arg::new("chart-title")
+        .help("title for images, replacing the default title")
+        .requires("image")
+        .takes_value(true)
+        .long("chart-title");
+
+    let arg_chart_width = Arg::new("chart-width")
+        .help("width of images in pixels")
+        .requires("image")
+        .takes_value(true)
+        .validator(is_number)
+        .long("chart-width");
+
+    let arg_chart_height = Arg::new("chart-height")
+        .help("height of images in pixels")
+        .requires("image")
+        .takes_value(true)
+        .validator(is_number)
+        .long("chart-height");
+
+    let arg_format = Arg::new("format")
+        .help("output format, either 'table', 'csv', 'json', 'markdown' or 'prom'.  Defaults to a table on a terminal, csv otherwise")
+        .takes_value(true)
+        .possible_values(["table", "csv", "json", "markdown", "prom"])
+        .long("format");
+
+    let arg_author_display = Arg::new("author-display")
+        .help("show authors by 'name' (default), 'email', 'both' or 'initials'")
+        .takes_value(true)
+        .possible_values(["name", "email", "both", "initials"])
+        .default_value("name")
+        .long("author-display");
+
+    let arg_anonymize = Arg::new("anonymize")
+        .help("replaces every author with a stable pseudonym, Author- and a hash of their email, so the output can be shared")
+        .long("anonymize");
+
+    let arg_anonymize_salt = Arg::new("anonymize-salt")
+        .help("secret mixed into the anonymize hashes, so pseudonyms cannot be matched to known emails")
+        .takes_value(true)
+        .requires("anonymize")
+        .long("anonymize-salt");
+
+    let arg_alias_file = Arg::new("alias-file")
+        .help("toml file mapping each canonical author, \"Name <email>\", to the other names and emails they commit under")
+        .takes_value(true)
+        .validator(is_alias_file)
+        .long("alias-file");
+
+    let arg_teams = Arg::new("teams")
+        .help("toml file mapping each team to the names and emails of its authors, \"Team\" = [\"name\", \"email\"]")
+        .takes_value(true)
+        .validator(is_teams_file)
+        .long("teams");
+
+    let arg_group_by = Arg::new("group-by")
+        .help("groups authors by 'author' (default) or by their 'team' in the --teams file")
+        .takes_value(true)
+        .possible_values(["author", "team"])
+        .requires_if("team", "teams")
+        .long("group-by");
+
+    let arg_relative_to = Arg::new("relative-to")
+        .help("report file paths relative to the repository root or the current directory")
+        .takes_value(true)
+        .possible_values(["repo", "cwd"])
+        .default_value("repo")
+        .long("relative-to");
+
+    let arg_top = Arg::new("top")
+        .help("only show the first N rows, the rest are collapsed into an 'Others' row")
+        .takes_value(true)
+        .validator(is_number)
+        .long("top");
+
+    let arg_min_commits = Arg::new("min-commits")
+        .help("rows with fewer commits are collapsed into an 'Others' row")
+        .takes_value(true)
+        .validator(is_number)
+        .long("min-commits");
+
+    let arg_at = Arg::new("at")
+        .help("blame files as of this revision (sha, branch, tag) instead of HEAD")
+        .takes_value(true)
+        .long("at");
+
+    let arg_path_filter = Arg::new("path-filter")
+        .help(
+            "comma delimited, glob file paths.  Only commits touching a matching path are counted",
+        )
+        .takes_value(true)
+        .long("path-filter");
+
+    let arg_exclude_bots = Arg::new("exclude-bots")
+        .help(
+            "leave out commits and lines by bots such as dependabot, renovate and *[bot] accounts",
+        )
+        .takes_value(false)
+        .long("exclude-bots");
+
+    let arg_bot_pattern = Arg::new("bot-pattern")
+        .help("comma delimited, regex of more author names or emails treated as bots")
+        .takes_value(true)
+        .requires("exclude-bots")
+        .validator(is_bot_pattern)
+        .long("bot-pattern");
+
+    let arg_exclude_authors_regex = Arg::new("exclude-authors-regex")
+        .help("regex of author names or emails to leave out, e.g. '.*@vendor\\.com'")
+        .takes_value(true)
+        .validator(is_author_regex)
+        .long("exclude-authors-regex");
+
+    let arg_only_authors_regex = Arg::new("only-authors-regex")
+        .help("regex of author names or emails to count, leaving out everyone else")
+        .takes_value(true)
+        .validator(is_author_regex)
+        .long("only-authors-regex");
+
+    let arg_timezone = Arg::new("timezone")
+        .help("bucket commits into days in 'local' time (default), 'utc', each commit's 'author' offset, a fixed offset such as '+05:30' or an IANA name such as 'Europe/Paris'")
+        .takes_value(true)
+        .validator(is_timezone)
+        .long("timezone");
+
+    let arg_weekend_days = Arg::new("weekend-days")
+        .help("comma delimited days that make up the weekend, e.g. fri,sat.  Defaults to sat,sun")
+        .takes_value(true)
+        .validator(is_weekdays)
+        .long("weekend-days");
+
+    let arg_week_start = Arg::new("week-start")
+        .help("first day of the week for weekly intervals, e.g. sun.  Defaults to mon")
+        .takes_value(true)
+        .validator(is_weekdays)
+        .long("week-start");
+
+    let arg_strict = Arg::new("strict")
+        .help("fail the run when any file could not be processed, instead of skipping it")
+        .takes_value(false)
+        .long("strict");
+
+    let arg_stream = Arg::new("stream")
+        .help("writes the results of each file as csv once it is blamed, then a summary, to bound memory use on very large repos")
+        .takes_value(false)
+        .long("stream");
+
+    let arg_columns = Arg::new("columns")
+        .help("comma delimited columns to output, in this order, named by their titles, e.g. author,loc,commits")
+        .takes_value(true)
+        .conflicts_with("stream")
+        .long("columns");
+
+    let arg_sample = Arg::new("sample")
+        .help("blames only this percent of the files, picked by a hash of their path, and estimates the repo's totals from them with a 95% margin of error.  For repos too large to blame in full")
+        .takes_value(true)
+        .validator(is_sample_percent)
+        .conflicts_with("stream")
+        .long("sample");
+
+    let arg_threads = Arg::new("threads")
+        .help("worker threads to blame files on, one per CPU when not given or 0")
+        .takes_value(true)
+        .validator(is_number)
+        .long("threads");
+
     let arg_debug = Arg::new("debug")
-        .about("enables debug logging")
+        .help("enables debug logging")
         .takes_value(false)
         .short('d');
     let arg_verbose = Arg::new("verbose")
-        .about("enables info logging")
+        .help("enables info logging")
         .takes_value(false)
         .short('v');
+    let arg_json_logs = Arg::new("json-logs")
+        .help("writes the logs to stderr as JSON lines with a timestamp, module and elapsed milliseconds, for CI and log aggregation")
+        .takes_value(false)
+        .long("json-logs");
+    let arg_no_progress = Arg::new("no-progress")
+        .help("writes a line at every tenth of the files instead of drawing progress bars, which is the default when stderr is not a terminal")
+        .takes_value(false)
+        .long("no-progress");
 
-    let arg_file = Arg::new("file")
-        .about("output file for the by date file.  Sends to stdout by default.  If using image flag, file name needs to be *.svg")
-        .takes_value(true).long("file").validator(is_svg);
-
-    let arg_cvs_file = Arg::new("file")
-        .about("output file for csv.  Must end in .csv")
+    let arg_output_file = Arg::new("file")
+        .help("output file, written in --format or else the format its .csv, .json, .md, .prom or .txt extension names.  Sends to stdout by default.  With --image, file name needs to be *.svg")
         .takes_value(true)
-        .long("file")
-        .validator(is_csv);
+        .long("file");
 
-    let matches = App::new("Grit")
+    let mut app = App::new("Grit")
         .about("git repository analyzer")
         .author("Todd Bush")
         .subcommand(
@@ -169,19 +760,123 @@ fn main() {
             .about("will create a table of metrics per author.  This may take a while for repos with long commit history, consider using date ranges to reduce computation time.")
             .args(&[
                 Arg::new("sort")
-                    .about("sort field, either 'commit', 'loc', 'files")
+                    .help("sort field, either 'commit', 'loc' or 'files'")
                     .takes_value(true)
+                    .possible_values(["commit", "loc", "files"])
                     .default_value("commit")
                     .long("sort"),
                 arg_start_date.clone(),
+                arg_since_author_first_commit.clone(),
                 arg_end_date.clone(),
                 arg_include.clone(),
                 arg_exclude.clone(),
+                arg_include_from.clone(),
+                arg_exclude_from.clone(),
+                arg_include_vendored.clone(),
+                arg_ignore_revs_file.clone(),
+                arg_timing.clone(),
                 arg_restrict_author.clone(),
-                Arg::new("csv").about("output to csv, stdout or file if file arg is present").takes_value(false).long("csv"),
-                arg_cvs_file.clone(),
+                Arg::new("csv").help("output to csv, stdout or file if file arg is present").takes_value(false).long("csv"),
+                arg_output_file.clone(),
+                arg_top.clone(),
+                Arg::new("min-lines")
+                    .help("authors with fewer lines are collapsed into an 'Others' row")
+                    .takes_value(true)
+                    .validator(is_number)
+                    .long("min-lines"),
+                arg_min_commits.clone(),
+                arg_at.clone().conflicts_with("end-date"),
+                Arg::new("subdir")
+                    .help("only blame the files under this directory, e.g. packages/foo in a monorepo")
+                    .takes_value(true)
+                    .long("subdir"),
+                Arg::new("record")
+                    .help("saves the raw blame data to this file so later runs can replay it")
+                    .takes_value(true)
+                    .long("record"),
+                Arg::new("replay")
+                    .help("reads the blame data saved with record instead of blaming the repo again")
+                    .takes_value(true)
+                    .conflicts_with_all(&["record", "start-date", "end-date", "include", "exclude", "at", "subdir", "sample"])
+                    .long("replay"),
+                arg_strict.clone(),
+                Arg::new("by-language")
+                    .help("show the LOC of each author per language instead of the fame metrics")
+                    .takes_value(false)
+                    .long("by-language"),
+                Arg::new("language-map")
+                    .help("comma delimited ext=Language pairs added to or replacing the default language of an extension")
+                    .takes_value(true)
+                    .requires("by-language")
+                    .long("language-map"),
+                Arg::new("mode")
+                    .help("'blame' (default) credits each author with the lines they last touched, 'diff' with the lines their commits added and removed")
+                    .takes_value(true)
+                    .possible_values(["blame", "diff"])
+                    .default_value("blame")
+                    .conflicts_with_all(&["at", "subdir", "record", "replay", "by-language", "stream", "summary", "fail-if-bus-factor-below", "fail-if-author-share-above", "sample"])
+                    .long("mode"),
+                Arg::new("commit-metric")
+                    .help("'blame' (default) counts the commits behind each author's blamed lines, 'revwalk' every commit they made in the date range, including those whose lines were since overwritten")
+                    .takes_value(true)
+                    .possible_values(["blame", "revwalk"])
+                    .default_value("blame")
+                    .conflicts_with_all(&["mode", "stream"])
+                    .long("commit-metric"),
+                Arg::new("summary")
+                    .help("appends the Gini coefficient of the LOC per author, the top 3 authors' share of the LOC and the mean and median LOC per author")
+                    .takes_value(false)
+                    .conflicts_with_all(&["by-language", "stream"])
+                    .long("summary"),
+                Arg::new("fail-if-bus-factor-below")
+                    .help("exits with code 2 when fewer authors than this own more than half of the lines")
+                    .takes_value(true)
+                    .validator(is_number)
+                    .long("fail-if-bus-factor-below"),
+                Arg::new("fail-if-author-share-above")
+                    .help("exits with code 2 when any author owns more than this percent of the lines")
+                    .takes_value(true)
+                    .validator(is_percent)
+                    .long("fail-if-author-share-above"),
+                Arg::new("quiet")
+                    .help("leaves the report off stdout, for CI gating on the exit code.  --file is still written")
+                    .takes_value(false)
+                    .short('q')
+                    .long("quiet"),
+                arg_stream
+                    .clone()
+                    .conflicts_with_all(&["format", "top", "min-lines", "min-commits", "record", "replay", "by-language"]),
+                arg_sample.clone(),
+                arg_threads.clone(),
+                Arg::new("former-names")
+                    .help("lists on stderr the paths renamed files had when their blamed lines were written, with the LOC from each.  A renamed file still counts as one file")
+                    .takes_value(false)
+                    .conflicts_with_all(&["replay", "stream", "mode"])
+                    .long("former-names"),
+                Arg::new("extended")
+                    .help("adds each author's first and last commit dates, the days they committed on and their LOC per such day")
+                    .takes_value(false)
+                    .conflicts_with_all(&["stream", "mode", "by-language"])
+                    .long("extended"),
+                arg_author_display.clone(),
+                arg_anonymize.clone(),
+                arg_anonymize_salt.clone(),
+                arg_alias_file.clone(),
+                arg_teams.clone(),
+                arg_group_by.clone(),
+                arg_format.clone().conflicts_with("csv"),
+                arg_precision.clone(),
+                arg_columns.clone().conflicts_with("by-language"),
+                arg_exclude_bots.clone(),
+                arg_bot_pattern.clone(),
+                arg_exclude_authors_regex.clone(),
+                arg_only_authors_regex.clone(),
+                arg_repo.clone(),
+                arg_no_discover.clone(),
                 arg_debug.clone(),
                 arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
             ]),
         )
         .subcommand(
@@ -189,29 +884,70 @@ fn main() {
             .about("will create a csv of date and commit count to stdout or file.  Option to produce a SVG image.")
             .args(&[
                 arg_start_date.clone(),
+                arg_since_author_first_commit.clone(),
                 arg_end_date.clone(),
-                arg_file.clone(),
+                arg_output_file.clone(),
                 Arg::new("image")
-                    .about("creates an image for the graph.  file is required")
+                    .help("creates an image for the graph.  file is required")
                     .requires("file")
                     .takes_value(false)
                     .long("image"),
                 Arg::new("html")
-                    .about("creates a HTML file to help visualize the SVG output")
+                    .help("creates a HTML file to help visualize the SVG output")
                     .requires("image")
                     .takes_value(false)
                     .long("html"),
+                arg_chart_theme.clone(),
+                arg_chart_title.clone(),
+                arg_chart_width.clone(),
+                arg_chart_height.clone(),
+                Arg::new("chart")
+                    .help("image chart type, either 'line' (default) or 'calendar'")
+                    .requires("image")
+                    .takes_value(true)
+                    .possible_values(["line", "calendar"])
+                    .default_value("line")
+                    .long("chart"),
                 Arg::new("ignore-weekends")
-                    .about("ignore weekends when calculating # of commits")
+                    .help("ignore weekends when calculating # of commits")
                     .takes_value(false)
                     .long("ignore-weekends"),
+                arg_weekend_days.clone(),
                 Arg::new("ignore-gap-fill")
-                    .about("ignore filling empty dates with 0 commits")
+                    .help("ignore filling empty dates with 0 commits")
                     .takes_value(false)
                     .long("ignore-gap-fill"),
+                Arg::new("cumulative")
+                    .help("output the running total of commits up to each date instead of the commits per date")
+                    .takes_value(false)
+                    .long("cumulative"),
+                Arg::new("rolling")
+                    .help("average the commits over the N days ending on each date, smoothing the chart")
+                    .takes_value(true)
+                    .validator(is_number)
+                    .conflicts_with_all(&["cumulative", "ignore-gap-fill"])
+                    .long("rolling"),
+                Arg::new("split-merges")
+                    .help("count merge commits and regular commits as separate series")
+                    .takes_value(false)
+                    .long("split-merges"),
                 arg_restrict_author.clone(),
+                arg_teams.clone(),
+                arg_group_by.clone(),
+                arg_path_filter.clone(),
+                arg_format.clone(),
+                arg_exclude_bots.clone(),
+                arg_bot_pattern.clone(),
+                arg_exclude_authors_regex.clone(),
+                arg_only_authors_regex.clone(),
+                arg_timezone.clone(),
+                arg_date_format.clone(),
+                arg_repo.clone(),
+                arg_no_discover.clone(),
                 arg_debug.clone(),
                 arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
             ]),
         )
         .subcommand(
@@ -219,131 +955,2287 @@ fn main() {
             .about("will create a csv of author, date, and commit counts to stdout or file.  Option to produce a SVG image.")
             .args(&[
                 Arg::new("in-file")
-                    .about("input file")
+                    .help("input file")
                     .takes_value(true)
-                    .required(true)
+                    .required_unless_present("dir")
+                    .conflicts_with("dir")
                     .long("in-file"),
-                arg_file.clone(),
+                Arg::new("dir")
+                    .help("summarize every file in the directory instead of a single file")
+                    .takes_value(true)
+                    .conflicts_with("image")
+                    .long("dir"),
+                Arg::new("rank")
+                    .help("with dir, order the files by the top contributor's share of lines")
+                    .requires("dir")
+                    .takes_value(false)
+                    .long("rank"),
+                arg_ignore_revs_file.clone(),
+                Arg::new("history")
+                    .help("list every commit changing the file, following renames, instead of the blame by author and date")
+                    .takes_value(false)
+                    .conflicts_with_all(&["dir", "image"])
+                    .long("history"),
+                Arg::new("lines")
+                    .help("blames only this range of the file's lines, e.g. 100-250")
+                    .takes_value(true)
+                    .validator(is_line_range)
+                    .conflicts_with_all(&["dir", "history"])
+                    .long("lines"),
+                arg_at,
+                arg_strict.clone(),
+                arg_relative_to.clone(),
+                arg_output_file.clone(),
                 Arg::new("image")
-                    .about("creates an image for the graph.  file is required")
+                    .help("creates an image for the graph.  file is required")
                     .requires("file")
                     .takes_value(false)
                     .long("image"),
                 Arg::new("html")
-                    .about("creates a HTML file to help visualize the SVG output")
+                    .help("creates a HTML file to help visualize the SVG output")
                     .requires("image")
                     .takes_value(false)
                     .long("html"),
+                arg_chart_theme.clone(),
+                arg_chart_title.clone(),
+                arg_chart_width.clone(),
+                arg_chart_height.clone(),
                 arg_restrict_author.clone(),
+                arg_author_display.clone(),
+                arg_anonymize.clone(),
+                arg_anonymize_salt.clone(),
+                arg_alias_file.clone(),
+                arg_format.clone(),
+                arg_precision.clone(),
+                arg_exclude_bots.clone(),
+                arg_bot_pattern.clone(),
+                arg_exclude_authors_regex.clone(),
+                arg_only_authors_regex.clone(),
+                arg_date_format.clone(),
+                arg_repo.clone(),
+                arg_no_discover.clone(),
                 arg_debug.clone(),
                 arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
             ]),
         )
         .subcommand(
             App::new("effort")
-            .about("will output the # of commits and # of active dates for each file.  Default is CSV, option for a table.  This may take a while for repos with long commit history, consider using date ranges to reduce computation time.")
+            .about("will output the # of commits, # of active dates, LOC and last modified date for each file.  Default is CSV, option for a table.  This may take a while for repos with long commit history, consider using date ranges to reduce computation time.")
             .args(&[
                 arg_start_date.clone(),
+                arg_since_author_first_commit.clone(),
                 arg_end_date.clone(),
-                arg_include,
-                arg_exclude,
+                arg_include.clone(),
+                arg_exclude.clone(),
+                arg_include_from.clone(),
+                arg_exclude_from.clone(),
+                arg_include_vendored.clone(),
+                arg_ignore_revs_file.clone(),
+                arg_timing,
                 arg_restrict_author.clone(),
+                arg_format.clone().conflicts_with("table"),
+                arg_columns.conflicts_with("image"),
+                arg_author_display.clone(),
+                arg_anonymize.clone(),
+                arg_anonymize_salt.clone(),
+                arg_alias_file.clone(),
+                arg_teams.clone(),
+                arg_group_by.clone(),
+                arg_exclude_bots.clone(),
+                arg_bot_pattern.clone(),
+                arg_exclude_authors_regex.clone(),
+                arg_only_authors_regex.clone(),
+                arg_timezone.clone(),
+                arg_date_format.clone(),
+                arg_repo.clone(),
+                arg_no_discover.clone(),
                 arg_debug.clone(),
                 arg_verbose.clone(),
-                arg_restrict_author.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+                arg_relative_to.clone(),
+                arg_top.clone(),
+                arg_min_commits,
+                arg_strict.clone(),
+                Arg::new("file")
+                    .help("output file.  Sends to stdout by default.  If using image flag, file name needs to be *.svg")
+                    .takes_value(true)
+                    .long("file"),
+                Arg::new("image")
+                    .help("creates a bar chart of the commits to the busiest files, --top of them or 20, or a heatmap of their commits per period with --trend.  file is required")
+                    .requires("file")
+                    .conflicts_with_all(&["by-author", "stream"])
+                    .takes_value(false)
+                    .long("image"),
+                Arg::new("html")
+                    .help("creates a HTML file to help visualize the SVG output")
+                    .requires("image")
+                    .takes_value(false)
+                    .long("html"),
+                arg_chart_theme.clone(),
+                arg_chart_title.clone(),
+                arg_chart_width.clone(),
+                arg_chart_height.clone(),
+                Arg::new("by-author")
+                    .help("break the commits and active days of each file down by author")
+                    .takes_value(false)
+                    .long("by-author"),
+                Arg::new("by-dir")
+                    .help("roll the files up into their leading directories, --by-dir=2 for two levels.  Defaults to one level")
+                    .takes_value(true)
+                    .min_values(0)
+                    .require_equals(true)
+                    .default_missing_value("1")
+                    .validator(is_number)
+                    .long("by-dir"),
+                Arg::new("trend")
+                    .help("outputs the commits to each file per period instead, one column per period, to show whether work on a file is picking up or winding down")
+                    .takes_value(true)
+                    .possible_values(["daily", "weekly", "monthly", "quarterly", "yearly"])
+                    .conflicts_with_all(&["by-author", "group-by", "stream", "columns"])
+                    .long("trend"),
+                arg_week_start.clone(),
+                Arg::new("include-deleted")
+                    .help("adds the files deleted in the date range, from the diffs of its commits, with churn and deleted columns")
+                    .takes_value(false)
+                    .conflicts_with_all(&["stream", "sample"])
+                    .long("include-deleted"),
+                arg_sample,
+                arg_threads,
+                Arg::new("min-loc")
+                    .help("leaves out files with fewer lines, after blaming them")
+                    .takes_value(true)
+                    .validator(is_number)
+                    .long("min-loc"),
+                Arg::new("min-age-days")
+                    .help("leaves out files whose oldest line is younger than this many days")
+                    .takes_value(true)
+                    .validator(is_number)
+                    .long("min-age-days"),
                 Arg::new("table")
-                    .about("display as a table to stdout")
+                    .help("display as a table to stdout")
                     .takes_value(false)
                     .long("table"),
+                arg_stream.conflicts_with_all(&["format", "table", "top", "min-commits", "by-dir"]),
             ]),
         )
-        .get_matches();
-
-    let processasble = match matches.subcommand_name() {
-        Some("fame") => handle_fame(matches.subcommand_matches("fame").unwrap()),
-        Some("bydate") => handle_bydate(matches.subcommand_matches("bydate").unwrap()),
-        Some("byfile") => handle_byfile(matches.subcommand_matches("byfile").unwrap()),
-        Some("effort") => handle_effort(matches.subcommand_matches("effort").unwrap()),
-        Some(_) => panic!("Unknown command was given"),
-        None => panic!("No command was given"),
-    };
-
-    processasble.process().expect("Could not complete process");
-}
-
-fn handle_fame(args: &ArgMatches) -> Box<dyn Processable<()>> {
-    set_logging(args.is_present("debug"), args.is_present("verbose"));
-    let fame_args = FameArgs::new(
-        String::from("."),
-        convert_str_string(args.value_of("sort")),
-        parse_date_arg(args.value_of("start-date")),
-        parse_date_arg(args.value_of("end-date")),
-        convert_str_string(args.value_of("include")),
-        convert_str_string(args.value_of("exclude")),
-        convert_str_string(args.value_of("restrict-author")),
-        args.is_present("csv"),
-        convert_str_string(args.value_of("file")),
-    );
-
-    Box::new(Fame::new(fame_args))
-}
-
-fn handle_bydate(args: &ArgMatches) -> Box<dyn Processable<()>> {
-    set_logging(args.is_present("debug"), args.is_present("verbose"));
-    let args = ByDateArgs::new(
-        String::from("."),
-        parse_date_arg(args.value_of("start-date")),
-        parse_date_arg(args.value_of("end-date")),
-        convert_str_string(args.value_of("file")),
-        args.is_present("image"),
-        args.is_present("ignore_weekends"),
-        args.is_present("ignore-gap_fill"),
-        args.is_present("html"),
-        convert_str_string(args.value_of("restrict-author")),
-    );
-
-    Box::new(ByDate::new(args))
-}
-
-fn handle_byfile(args: &ArgMatches) -> Box<dyn Processable<()>> {
-    set_logging(args.is_present("debug"), args.is_present("verbose"));
-    let args = ByFileArgs::new(
-        ".".to_string(),
-        args.value_of("in-file").unwrap().to_string(),
-        convert_str_string(args.value_of("file")),
-        args.is_present("image"),
-        args.is_present("html"),
-        convert_str_string(args.value_of("restrict-author")),
-    );
-
-    Box::new(ByFile::new(args))
-}
-
-fn handle_effort(args: &ArgMatches) -> Box<dyn Processable<()>> {
-    set_logging(args.is_present("debug"), args.is_present("verbose"));
-    let ea = EffortArgs::new(
-        ".".to_string(),
-        parse_date_arg(args.value_of("start-date")),
-        parse_date_arg(args.value_of("end-date")),
-        args.is_present("table"),
-        convert_str_string(args.value_of("include")),
-        convert_str_string(args.value_of("exclude")),
-        convert_str_string(args.value_of("restrict-author")),
-    );
-
-    Box::new(Effort::new(ea))
-}
-
-fn set_logging(debug: bool, verbose: bool) {
-    let level = if debug {
-        LevelFilter::Debug
-    } else if verbose {
-        LevelFilter::Info
-    } else {
-        LevelFilter::Error
-    };
+        .subcommand(
+            App::new("annotate")
+            .about("will output per line author, commit, date, and age data for a file.  Default is JSON, option for CSV.  Intended for editor integrations.")
+            .args(&[
+                Arg::new("in-file")
+                    .help("file to annotate")
+                    .takes_value(true)
+                    .required(true)
+                    .index(1),
+                Arg::new("format")
+                    .help("output format, either 'json', 'csv', 'markdown' or 'table'.  Defaults to a table on a terminal, json otherwise")
+                    .takes_value(true)
+                    .possible_values(["json", "csv", "markdown", "table"])
+                    .long("format"),
+                Arg::new("file")
+                    .help("output file.  Sends to stdout by default")
+                    .takes_value(true)
+                    .long("file"),
+                arg_ignore_revs_file.clone(),
+                arg_relative_to.clone(),
+                arg_author_display.clone(),
+                arg_anonymize.clone(),
+                arg_anonymize_salt.clone(),
+                arg_alias_file.clone(),
+                arg_date_format.clone(),
+                arg_repo.clone(),
+                arg_no_discover.clone(),
+                arg_debug.clone(),
+                arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+            ]),
+        )
+        .subcommand(
+            App::new("heat")
+            .about("will render a file's source as HTML with each line colored by age or by author.")
+            .args(&[
+                Arg::new("in-file")
+                    .help("file to render")
+                    .takes_value(true)
+                    .required(true)
+                    .index(1),
+                Arg::new("output")
+                    .help("output format, currently only 'html'")
+                    .takes_value(true)
+                    .possible_values(["html"])
+                    .default_value("html")
+                    .long("output"),
+                Arg::new("color-by")
+                    .help("color lines by 'age' (default) or 'author'")
+                    .takes_value(true)
+                    .possible_values(["age", "author"])
+                    .default_value("age")
+                    .long("color-by"),
+                Arg::new("file")
+                    .help("output file.  Sends to stdout by default")
+                    .takes_value(true)
+                    .long("file"),
+                arg_ignore_revs_file.clone(),
+                arg_relative_to.clone(),
+                Arg::new("github")
+                    .help("link authors to their GitHub login and avatar.  Needs the github feature, and GITHUB_TOKEN for emails that are not GitHub noreply addresses")
+                    .takes_value(false)
+                    .hide(!cfg!(feature = "github"))
+                    .long("github"),
+                arg_author_display.clone(),
+                arg_anonymize.clone(),
+                arg_anonymize_salt.clone(),
+                arg_alias_file.clone(),
+                arg_date_format.clone(),
+                arg_repo.clone(),
+                arg_no_discover.clone(),
+                arg_debug.clone(),
+                arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+            ]),
+        )
+        .subcommand(
+            App::new("fame-diff")
+            .about("will compare the fame metrics of two revisions, showing which authors gained or lost ownership.")
+            .args(&[
+                Arg::new("from")
+                    .help("revision (sha, branch, tag) to compare from")
+                    .takes_value(true)
+                    .required(true)
+                    .long("from"),
+                Arg::new("to")
+                    .help("revision (sha, branch, tag) to compare to")
+                    .takes_value(true)
+                    .default_value("HEAD")
+                    .long("to"),
+                arg_include.clone(),
+                arg_exclude.clone(),
+                arg_include_from.clone(),
+                arg_exclude_from.clone(),
+                arg_include_vendored.clone(),
+                arg_restrict_author.clone(),
+                Arg::new("csv").help("output to csv, stdout or file if file arg is present").takes_value(false).long("csv"),
+                arg_output_file.clone(),
+                arg_author_display.clone(),
+                arg_anonymize.clone(),
+                arg_anonymize_salt.clone(),
+                arg_alias_file.clone(),
+                arg_format.clone().conflicts_with("csv"),
+                arg_precision.clone(),
+                arg_exclude_bots.clone(),
+                arg_bot_pattern.clone(),
+                arg_exclude_authors_regex.clone(),
+                arg_only_authors_regex.clone(),
+                arg_repo.clone(),
+                arg_no_discover.clone(),
+                arg_debug.clone(),
+                arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+            ]),
+        )
+        .subcommand(
+            App::new("ownership")
+            .about("will blame the repo at the end of each period and output a csv of each author's share of every directory over time.")
+            .args(&[
+                Arg::new("snapshots")
+                    .help("snapshot period, either 'monthly', 'quarterly' or 'yearly'")
+                    .takes_value(true)
+                    .possible_values(["monthly", "quarterly", "yearly"])
+                    .default_value("quarterly")
+                    .long("snapshots"),
+                Arg::new("depth")
+                    .help("number of leading directories used to group files")
+                    .takes_value(true)
+                    .validator(is_number)
+                    .default_value("1")
+                    .long("depth"),
+                Arg::new("treemap")
+                    .help("blames the repo once, as of the end date, and outputs its directory tree as JSON for d3, every node holding its LOC and owner")
+                    .takes_value(false)
+                    .conflicts_with_all(&["snapshots", "depth", "start-date", "format"])
+                    .long("treemap"),
+                arg_start_date.clone(),
+                arg_since_author_first_commit.clone(),
+                arg_end_date.clone(),
+                arg_include.clone(),
+                arg_exclude.clone(),
+                arg_include_from.clone(),
+                arg_exclude_from.clone(),
+                arg_include_vendored.clone(),
+                Arg::new("file")
+                    .help("output file.  Sends to stdout by default.  If using image flag, file name needs to be *.svg")
+                    .takes_value(true)
+                    .long("file"),
+                Arg::new("image")
+                    .help("creates a treemap SVG of the directory tree, sized by LOC and colored by owner")
+                    .requires("treemap")
+                    .takes_value(false)
+                    .long("image"),
+                Arg::new("html")
+                    .help("creates a HTML file to help visualize the SVG output")
+                    .requires("image")
+                    .takes_value(false)
+                    .long("html"),
+                arg_chart_theme.clone(),
+                arg_chart_title.clone(),
+                arg_chart_width.clone(),
+                arg_chart_height.clone(),
+                arg_author_display.clone(),
+                arg_anonymize.clone(),
+                arg_anonymize_salt.clone(),
+                arg_alias_file.clone(),
+                arg_format.clone(),
+                arg_precision.clone(),
+                arg_exclude_bots.clone(),
+                arg_bot_pattern.clone(),
+                arg_exclude_authors_regex.clone(),
+                arg_only_authors_regex.clone(),
+                arg_date_format.clone(),
+                arg_repo.clone(),
+                arg_no_discover.clone(),
+                arg_debug.clone(),
+                arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+            ]),
+        )
+        .subcommand(
+            App::new("growth")
+            .about("will sample the repo at every interval and output a csv of the total LOC at each sample.  Option to produce a SVG image.")
+            .args(&[
+                Arg::new("interval")
+                    .help("sample interval, either 'daily', 'weekly' or 'monthly'")
+                    .takes_value(true)
+                    .possible_values(["daily", "weekly", "monthly"])
+                    .default_value("weekly")
+                    .long("interval"),
+                arg_week_start.clone(),
+                arg_start_date.clone(),
+                arg_since_author_first_commit.clone(),
+                arg_end_date.clone(),
+                arg_include.clone(),
+                arg_exclude.clone(),
+                arg_include_from.clone(),
+                arg_exclude_from.clone(),
+                Arg::new("file")
+                    .help("output file.  Sends to stdout by default.  If using image flag, file name needs to be *.svg")
+                    .takes_value(true)
+                    .long("file"),
+                Arg::new("image")
+                    .help("creates an image for the graph.  file is required")
+                    .requires("file")
+                    .takes_value(false)
+                    .long("image"),
+                Arg::new("html")
+                    .help("creates a HTML file to help visualize the SVG output")
+                    .requires("image")
+                    .takes_value(false)
+                    .long("html"),
+                arg_chart_theme.clone(),
+                arg_chart_title.clone(),
+                arg_chart_width.clone(),
+                arg_chart_height.clone(),
+                arg_format.clone(),
+                arg_date_format.clone(),
+                arg_repo.clone(),
+                arg_no_discover.clone(),
+                arg_debug.clone(),
+                arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+            ]),
+        )
+        .subcommand(
+            App::new("survival")
+            .about("will sample the repo at every interval, diffing forward to track the lines each sample added, and output a csv of the share of lines still alive after each number of intervals.  Option to produce a SVG image of the survival curve.")
+            .args(&[
+                Arg::new("interval")
+                    .help("sample interval, either 'daily', 'weekly', 'monthly', 'quarterly' or 'yearly'")
+                    .takes_value(true)
+                    .possible_values(["daily", "weekly", "monthly", "quarterly", "yearly"])
+                    .default_value("monthly")
+                    .long("interval"),
+                arg_week_start.clone(),
+                arg_start_date.clone(),
+                arg_since_author_first_commit.clone(),
+                arg_end_date.clone(),
+                arg_include.clone(),
+                arg_exclude.clone(),
+                arg_include_from.clone(),
+                arg_exclude_from.clone(),
+                Arg::new("file")
+                    .help("output file.  Sends to stdout by default.  If using image flag, file name needs to be *.svg")
+                    .takes_value(true)
+                    .long("file"),
+                Arg::new("image")
+                    .help("creates an image for the graph.  file is required")
+                    .requires("file")
+                    .takes_value(false)
+                    .long("image"),
+                Arg::new("html")
+                    .help("creates a HTML file to help visualize the SVG output")
+                    .requires("image")
+                    .takes_value(false)
+                    .long("html"),
+                arg_chart_theme.clone(),
+                arg_chart_title.clone(),
+                arg_chart_width.clone(),
+                arg_chart_height.clone(),
+                arg_format.clone(),
+                arg_precision.clone(),
+                arg_date_format.clone(),
+                arg_repo.clone(),
+                arg_no_discover.clone(),
+                arg_debug.clone(),
+                arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+            ]),
+        )
+        .subcommand(
+            App::new("tenure")
+            .about("will output each author's first and last commit, active span, and time since their last commit, flagging inactive authors.")
+            .args(&[
+                arg_start_date.clone(),
+                arg_since_author_first_commit.clone(),
+                arg_end_date.clone(),
+                arg_restrict_author.clone(),
+                Arg::new("inactive-days")
+                    .help("authors without a commit for more than this many days are flagged as inactive")
+                    .takes_value(true)
+                    .validator(is_number)
+                    .default_value("90")
+                    .long("inactive-days"),
+                arg_path_filter,
+                arg_output_file.clone(),
+                arg_author_display.clone(),
+                arg_anonymize.clone(),
+                arg_anonymize_salt.clone(),
+                arg_alias_file.clone(),
+                arg_format.clone(),
+                arg_exclude_bots.clone(),
+                arg_bot_pattern.clone(),
+                arg_exclude_authors_regex.clone(),
+                arg_only_authors_regex.clone(),
+                arg_date_format.clone(),
+                arg_repo.clone(),
+                arg_no_discover.clone(),
+                arg_debug.clone(),
+                arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+            ]),
+        )
+        .subcommand(
+            App::new("worklife")
+            .about("will output the share of each author's commits made on weekends and on weekdays outside working hours.")
+            .args(&[
+                arg_start_date.clone(),
+                arg_since_author_first_commit.clone(),
+                arg_end_date.clone(),
+                arg_restrict_author.clone(),
+                Arg::new("work-hours")
+                    .help("working hours as start-end, e.g. 9-18 or 08:30-17:00, in the --timezone clock")
+                    .takes_value(true)
+                    .validator(is_work_hours)
+                    .default_value("9-18")
+                    .long("work-hours"),
+                arg_weekend_days,
+                arg_timezone.clone(),
+                arg_output_file.clone(),
+                arg_author_display.clone(),
+                arg_anonymize.clone(),
+                arg_anonymize_salt.clone(),
+                arg_alias_file.clone(),
+                arg_format.clone(),
+                arg_precision.clone(),
+                arg_exclude_bots.clone(),
+                arg_bot_pattern.clone(),
+                arg_exclude_authors_regex.clone(),
+                arg_only_authors_regex.clone(),
+                arg_repo.clone(),
+                arg_no_discover.clone(),
+                arg_debug.clone(),
+                arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+            ]),
+        )
+        .subcommand(
+            App::new("commitsize")
+            .about("will output the mean, median, 90th percentile and largest lines changed per commit for each author and the whole repo, and how many commits are giant.  Merge commits are left out.")
+            .args(&[
+                arg_start_date.clone(),
+                arg_since_author_first_commit.clone(),
+                arg_end_date.clone(),
+                arg_include.clone(),
+                arg_exclude.clone(),
+                arg_include_from.clone(),
+                arg_exclude_from.clone(),
+                arg_restrict_author.clone(),
+                Arg::new("giant-lines")
+                    .help("lines added plus deleted at which a commit counts as giant")
+                    .takes_value(true)
+                    .validator(is_number)
+                    .default_value("1000")
+                    .long("giant-lines"),
+                Arg::new("list-giant")
+                    .help("lists the giant commits, biggest first, instead of the per author statistics")
+                    .takes_value(false)
+                    .long("list-giant"),
+                arg_output_file.clone(),
+                arg_author_display.clone(),
+                arg_anonymize.clone(),
+                arg_anonymize_salt.clone(),
+                arg_alias_file.clone(),
+                arg_format.clone(),
+                arg_precision.clone(),
+                arg_exclude_bots.clone(),
+                arg_bot_pattern.clone(),
+                arg_exclude_authors_regex.clone(),
+                arg_only_authors_regex.clone(),
+                arg_date_format.clone(),
+                arg_repo.clone(),
+                arg_no_discover.clone(),
+                arg_debug.clone(),
+                arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+            ]),
+        )
+        .subcommand(
+            App::new("log-stats")
+            .about("will output one row per commit with its author, date, files changed, insertions, deletions and whether it is a merge.  Merges are diffed against their first parent.")
+            .args(&[
+                arg_start_date.clone(),
+                arg_since_author_first_commit.clone(),
+                arg_end_date.clone(),
+                arg_include.clone(),
+                arg_exclude.clone(),
+                arg_include_from.clone(),
+                arg_exclude_from.clone(),
+                arg_output_file.clone(),
+                arg_author_display.clone(),
+                arg_anonymize.clone(),
+                arg_anonymize_salt.clone(),
+                arg_alias_file.clone(),
+                arg_format.clone(),
+                arg_exclude_bots.clone(),
+                arg_bot_pattern.clone(),
+                arg_exclude_authors_regex.clone(),
+                arg_only_authors_regex.clone(),
+                arg_timezone.clone(),
+                arg_date_format.clone(),
+                arg_repo.clone(),
+                arg_no_discover.clone(),
+                arg_debug.clone(),
+                arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+            ]),
+        )
+        .subcommand(
+            App::new("merges")
+            .about("will output, for each merge commit, the merger and the hours from the merged branch's first commit to the merge.  An approximation of review latency from the history alone.")
+            .args(&[
+                arg_start_date.clone(),
+                arg_since_author_first_commit.clone(),
+                arg_end_date.clone(),
+                arg_output_file.clone(),
+                arg_author_display.clone(),
+                arg_anonymize.clone(),
+                arg_anonymize_salt.clone(),
+                arg_alias_file.clone(),
+                arg_format.clone(),
+                arg_exclude_bots.clone(),
+                arg_bot_pattern.clone(),
+                arg_exclude_authors_regex.clone(),
+                arg_only_authors_regex.clone(),
+                arg_repo.clone(),
+                arg_no_discover.clone(),
+                arg_debug.clone(),
+                arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+            ]),
+        )
+        .subcommand(
+            App::new("compare")
+            .about("will output the commits, authors, files and lines unique to a head branch, from its merge base with the base branch.  Handy for pre-merge review summaries.")
+            .args(&[
+                Arg::new("base")
+                    .help("revision (sha, branch, tag) the head branch is measured against")
+                    .takes_value(true)
+                    .required(true)
+                    .long("base"),
+                Arg::new("head")
+                    .help("revision (sha, branch, tag) whose unique commits are reported")
+                    .takes_value(true)
+                    .default_value("HEAD")
+                    .long("head"),
+                arg_include.clone(),
+                arg_exclude.clone(),
+                arg_include_from.clone(),
+                arg_exclude_from.clone(),
+                arg_output_file.clone(),
+                arg_author_display.clone(),
+                arg_anonymize.clone(),
+                arg_anonymize_salt.clone(),
+                arg_alias_file.clone(),
+                arg_format.clone(),
+                arg_exclude_bots.clone(),
+                arg_bot_pattern.clone(),
+                arg_exclude_authors_regex.clone(),
+                arg_only_authors_regex.clone(),
+                arg_repo.clone(),
+                arg_no_discover.clone(),
+                arg_debug.clone(),
+                arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+            ]),
+        )
+        .subcommand(
+            App::new("branches")
+            .about("will list every local and remote branch with its last commit date and author, and the commits it is ahead and behind a base branch.  Option to list only branches idle for a number of days.")
+            .args(&[
+                Arg::new("base")
+                    .help("revision (sha, branch, tag) the branches are counted ahead and behind of")
+                    .takes_value(true)
+                    .default_value("HEAD")
+                    .long("base"),
+                Arg::new("older-than")
+                    .help("only list branches without a commit for more than this many days")
+                    .takes_value(true)
+                    .validator(is_number)
+                    .long("older-than"),
+                arg_output_file.clone(),
+                arg_author_display.clone(),
+                arg_anonymize.clone(),
+                arg_anonymize_salt.clone(),
+                arg_alias_file.clone(),
+                arg_format.clone(),
+                arg_exclude_bots.clone(),
+                arg_bot_pattern.clone(),
+                arg_exclude_authors_regex.clone(),
+                arg_only_authors_regex.clone(),
+                arg_date_format.clone(),
+                arg_repo.clone(),
+                arg_no_discover.clone(),
+                arg_debug.clone(),
+                arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+            ]),
+        )
+        .subcommand(
+            App::new("releases")
+            .about("will list every tag with its date, the commits and authors since the tag before it and the days between them.  A release cadence report.")
+            .args(&[
+                Arg::new("tag-pattern")
+                    .help("only count the tags matching this glob as releases, e.g. 'v*'")
+                    .takes_value(true)
+                    .long("tag-pattern"),
+                Arg::new("semver")
+                    .help("only count MAJOR.MINOR.PATCH tags, in version order, with each release's type, line and authors and the mean commits and authors per type of release")
+                    .takes_value(false)
+                    .long("semver"),
+                arg_output_file.clone(),
+                arg_author_display.clone(),
+                arg_anonymize.clone(),
+                arg_anonymize_salt.clone(),
+                arg_alias_file.clone(),
+                arg_format.clone(),
+                arg_exclude_bots.clone(),
+                arg_bot_pattern.clone(),
+                arg_exclude_authors_regex.clone(),
+                arg_only_authors_regex.clone(),
+                arg_date_format.clone(),
+                arg_repo.clone(),
+                arg_no_discover.clone(),
+                arg_debug.clone(),
+                arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+            ]),
+        )
+        .subcommand(
+            App::new("newcontributors")
+            .about("will output, for each month, how many authors made their first ever commit and how many returned.  A community health measure.")
+            .args(&[
+                arg_start_date.clone(),
+                arg_since_author_first_commit.clone(),
+                arg_end_date.clone(),
+                arg_output_file.clone(),
+                arg_author_display.clone(),
+                arg_anonymize.clone(),
+                arg_anonymize_salt.clone(),
+                arg_alias_file.clone(),
+                arg_format.clone(),
+                arg_exclude_bots.clone(),
+                arg_bot_pattern.clone(),
+                arg_exclude_authors_regex.clone(),
+                arg_only_authors_regex.clone(),
+                arg_repo.clone(),
+                arg_no_discover.clone(),
+                arg_debug.clone(),
+                arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+            ]),
+        )
+        .subcommand(
+            App::new("lint-history")
+            .about("will output, for each author, the share of their commit subjects following the conventional commits rule or a custom regex.  Option to fail the run under a threshold.")
+            .args(&[
+                arg_start_date.clone(),
+                arg_since_author_first_commit.clone(),
+                arg_end_date.clone(),
+                Arg::new("pattern")
+                    .help("regex commit subjects must match, replacing the conventional commits rule")
+                    .takes_value(true)
+                    .validator(is_subject_pattern)
+                    .long("pattern"),
+                Arg::new("include-merges")
+                    .help("also check the subjects of merge commits")
+                    .takes_value(false)
+                    .long("include-merges"),
+                Arg::new("fail-under")
+                    .help("exits with code 2 when fewer than this percent of the commit subjects match")
+                    .takes_value(true)
+                    .validator(is_percent)
+                    .long("fail-under"),
+                arg_output_file.clone(),
+                arg_author_display.clone(),
+                arg_anonymize.clone(),
+                arg_anonymize_salt.clone(),
+                arg_alias_file.clone(),
+                arg_format.clone(),
+                arg_precision.clone(),
+                arg_exclude_bots.clone(),
+                arg_bot_pattern.clone(),
+                arg_exclude_authors_regex.clone(),
+                arg_only_authors_regex.clone(),
+                arg_repo.clone(),
+                arg_no_discover.clone(),
+                arg_debug.clone(),
+                arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+            ]),
+        )
+        .subcommand(
+            App::new("identities")
+            .about("will list the name and email variants authors committed under, clustered by shared emails, email local parts and similar names.  Option to write the suggested merges as an alias file or .mailmap.")
+            .args(&[
+                arg_start_date.clone(),
+                arg_since_author_first_commit.clone(),
+                arg_end_date.clone(),
+                Arg::new("max-distance")
+                    .help("cluster names within this many edits of each other")
+                    .takes_value(true)
+                    .validator(is_number)
+                    .default_value("2")
+                    .long("max-distance"),
+                Arg::new("write-aliases")
+                    .help("write the suggested merges to this file, in the --alias-file format")
+                    .takes_value(true)
+                    .long("write-aliases"),
+                Arg::new("write-mailmap")
+                    .help("write the suggested merges to this file, in the .mailmap format")
+                    .takes_value(true)
+                    .long("write-mailmap"),
+                Arg::new("interactive")
+                    .help("ask to confirm each suggested merge before writing it")
+                    .takes_value(false)
+                    .long("interactive"),
+                arg_output_file.clone(),
+                arg_format.clone(),
+                arg_exclude_bots.clone(),
+                arg_bot_pattern.clone(),
+                arg_exclude_authors_regex.clone(),
+                arg_only_authors_regex.clone(),
+                arg_repo.clone(),
+                arg_no_discover.clone(),
+                arg_debug.clone(),
+                arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+            ]),
+        )
+        .subcommand(
+            App::new("age")
+            .about("will output the median, 75th and 90th percentile age of the blamed lines of each file and the whole repo, the share of lines older than a number of months, and the oldest line.")
+            .args(&[
+                arg_include.clone(),
+                arg_exclude.clone(),
+                arg_include_from.clone(),
+                arg_exclude_from.clone(),
+                arg_include_vendored.clone(),
+                Arg::new("older-than")
+                    .help("lines older than this many months count as stale")
+                    .takes_value(true)
+                    .validator(is_number)
+                    .default_value("12")
+                    .long("older-than"),
+                Arg::new("as-of")
+                    .help("measure ages at this date in YYYY-MM-DD format instead of today")
+                    .takes_value(true)
+                    .long("as-of"),
+                arg_output_file.clone(),
+                arg_format.clone(),
+                arg_precision.clone(),
+                arg_exclude_bots.clone(),
+                arg_bot_pattern.clone(),
+                arg_exclude_authors_regex.clone(),
+                arg_only_authors_regex.clone(),
+                arg_relative_to.clone(),
+                arg_strict.clone(),
+                arg_date_format.clone(),
+                arg_repo.clone(),
+                arg_no_discover.clone(),
+                arg_debug.clone(),
+                arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+            ]),
+        )
+        .subcommand(
+            App::new("owners-audit")
+            .about("will compare the owners the CODEOWNERS file declares for each pattern to the blamed lines of its files, flagging patterns whose owners wrote less than a threshold of the lines.")
+            .args(&[
+                Arg::new("codeowners")
+                    .help("CODEOWNERS file to audit.  Defaults to .github/CODEOWNERS, CODEOWNERS or docs/CODEOWNERS at HEAD")
+                    .takes_value(true)
+                    .long("codeowners"),
+                Arg::new("threshold")
+                    .help("flags patterns whose owners wrote less than this percent of the lines")
+                    .takes_value(true)
+                    .validator(is_percent)
+                    .default_value("10")
+                    .long("threshold"),
+                arg_include.clone(),
+                arg_exclude.clone(),
+                arg_include_from.clone(),
+                arg_exclude_from.clone(),
+                arg_include_vendored.clone(),
+                arg_teams.clone(),
+                arg_alias_file.clone(),
+                arg_output_file.clone(),
+                arg_format.clone(),
+                arg_precision.clone(),
+                arg_exclude_bots.clone(),
+                arg_bot_pattern.clone(),
+                arg_exclude_authors_regex.clone(),
+                arg_only_authors_regex.clone(),
+                arg_strict.clone(),
+                arg_repo.clone(),
+                arg_no_discover.clone(),
+                arg_debug.clone(),
+                arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+            ]),
+        )
+        .subcommand(
+            App::new("symbols")
+            .about("will map the blamed lines of each Rust file to its functions, structs, enums and traits, outputting each symbol's primary author and when it was last modified.")
+            .hide(!cfg!(feature = "symbols"))
+            .args(&[
+                arg_include.clone(),
+                arg_exclude.clone(),
+                arg_include_from.clone(),
+                arg_exclude_from.clone(),
+                arg_include_vendored.clone(),
+                arg_author_display.clone(),
+                arg_anonymize.clone(),
+                arg_anonymize_salt.clone(),
+                arg_alias_file.clone(),
+                arg_output_file.clone(),
+                arg_format.clone(),
+                arg_precision.clone(),
+                arg_exclude_bots.clone(),
+                arg_bot_pattern.clone(),
+                arg_exclude_authors_regex.clone(),
+                arg_only_authors_regex.clone(),
+                arg_relative_to,
+                arg_strict,
+                arg_repo.clone(),
+                arg_no_discover.clone(),
+                arg_debug.clone(),
+                arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+            ]),
+        )
+        .subcommand(
+            App::new("timeline")
+            .about("will output the commits or lines added of each author per week over the repo's life.  Option to produce a stacked area SVG image.")
+            .args(&[
+                Arg::new("metric")
+                    .help("what to count per author and week, either 'commits' or 'lines' added")
+                    .takes_value(true)
+                    .possible_values(["commits", "lines"])
+                    .default_value("commits")
+                    .long("metric"),
+                arg_start_date.clone(),
+                arg_since_author_first_commit.clone(),
+                arg_end_date.clone(),
+                arg_restrict_author.clone(),
+                arg_top,
+                arg_week_start,
+                arg_timezone,
+                Arg::new("file")
+                    .help("output file.  Sends to stdout by default.  If using image flag, file name needs to be *.svg")
+                    .takes_value(true)
+                    .long("file"),
+                Arg::new("image")
+                    .help("creates a stacked area image of the timeline.  file is required")
+                    .requires("file")
+                    .takes_value(false)
+                    .long("image"),
+                Arg::new("html")
+                    .help("creates a HTML file to help visualize the SVG output")
+                    .requires("image")
+                    .takes_value(false)
+                    .long("html"),
+                arg_chart_theme,
+                arg_chart_title,
+                arg_chart_width,
+                arg_chart_height,
+                arg_format.clone(),
+                arg_author_display.clone(),
+                arg_anonymize.clone(),
+                arg_anonymize_salt.clone(),
+                arg_alias_file.clone(),
+                arg_exclude_bots.clone(),
+                arg_bot_pattern.clone(),
+                arg_exclude_authors_regex.clone(),
+                arg_only_authors_regex.clone(),
+                arg_repo.clone(),
+                arg_no_discover.clone(),
+                arg_debug.clone(),
+                arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+            ]),
+        )
+        .subcommand(
+            App::new("snapshot")
+            .about("will append the current ownership and activity of every author, with a timestamp, to a local JSONL store.  Run it on a schedule and compare the snapshots with trends.")
+            .args(&[
+                arg_store.clone(),
+                arg_include.clone(),
+                arg_exclude.clone(),
+                arg_include_from.clone(),
+                arg_exclude_from.clone(),
+                arg_include_vendored.clone(),
+                arg_author_display.clone(),
+                arg_anonymize.clone(),
+                arg_anonymize_salt.clone(),
+                arg_alias_file.clone(),
+                arg_exclude_bots.clone(),
+                arg_bot_pattern.clone(),
+                arg_exclude_authors_regex.clone(),
+                arg_only_authors_regex.clone(),
+                arg_repo.clone(),
+                arg_no_discover.clone(),
+                arg_debug.clone(),
+                arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+            ]),
+        )
+        .subcommand(
+            App::new("trends")
+            .about("will output how each author's ownership, lines, commits and active days changed between two snapshots, the latest and the first taken since a date.")
+            .args(&[
+                arg_store,
+                Arg::new("since")
+                    .help("compares to the first snapshot taken on or after this date in YYYY-MM-DD format.  Defaults to the oldest snapshot")
+                    .takes_value(true)
+                    .long("since"),
+                arg_output_file.clone(),
+                arg_format.clone(),
+                arg_precision.clone(),
+                arg_repo.clone(),
+                arg_no_discover.clone(),
+                arg_debug.clone(),
+                arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+            ]),
+        )
+        .subcommand(
+            App::new("report")
+            .about("will run several analyses in one process, writing each to a file in a directory.  The file lists, commit walks and blames they share are only done once.")
+            .args(&[
+                Arg::new("analyses")
+                    .help("comma delimited analyses to run, from 'fame', 'age', 'effort', 'commitsize' and 'timeline'")
+                    .takes_value(true)
+                    .validator(is_analyses)
+                    .default_value("fame,age,effort,commitsize,timeline")
+                    .long("analyses"),
+                Arg::new("out-dir")
+                    .help("directory the analyses are written to, created when missing")
+                    .takes_value(true)
+                    .default_value("report")
+                    .long("out-dir"),
+                arg_start_date.clone(),
+                arg_since_author_first_commit.clone(),
+                arg_end_date.clone(),
+                arg_include.clone(),
+                arg_exclude.clone(),
+                arg_include_from.clone(),
+                arg_exclude_from.clone(),
+                arg_include_vendored.clone(),
+                arg_author_display.clone(),
+                arg_anonymize.clone(),
+                arg_anonymize_salt.clone(),
+                arg_alias_file.clone(),
+                arg_format,
+                arg_exclude_bots.clone(),
+                arg_bot_pattern.clone(),
+                arg_exclude_authors_regex.clone(),
+                arg_only_authors_regex.clone(),
+                arg_date_format,
+                arg_repo.clone(),
+                arg_no_discover.clone(),
+                arg_debug.clone(),
+                arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+            ]),
+        )
+        .subcommand(
+            App::new("tui")
+            .about("will browse the fame, bydate and effort results in an interactive terminal UI with sortable columns and an author filter.")
+            .hide(!cfg!(feature = "tui"))
+            .args(&[
+                arg_start_date.clone(),
+                arg_since_author_first_commit.clone(),
+                arg_end_date.clone(),
+                arg_include,
+                arg_exclude,
+                arg_include_from,
+                arg_exclude_from,
+                arg_include_vendored.clone(),
+                arg_restrict_author.clone(),
+                arg_author_display.clone(),
+                arg_anonymize.clone(),
+                arg_anonymize_salt.clone(),
+                arg_alias_file.clone(),
+                arg_exclude_bots.clone(),
+                arg_bot_pattern.clone(),
+                arg_exclude_authors_regex.clone(),
+                arg_only_authors_regex.clone(),
+                arg_repo.clone(),
+                arg_no_discover.clone(),
+                arg_debug.clone(),
+                arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+            ]),
+        )
+        .subcommand(
+            App::new("serve")
+            .about("will serve the fame, bydate and effort results as JSON over HTTP, running the analysis on each request.  Query parameters mirror the command's options, e.g. /fame?sort=loc&start-date=2024-01-01.")
+            .args(&[
+                Arg::new("bind")
+                    .help("address to listen on")
+                    .takes_value(true)
+                    .default_value("127.0.0.1")
+                    .long("bind"),
+                Arg::new("port")
+                    .help("port to listen on")
+                    .takes_value(true)
+                    .validator(is_port)
+                    .default_value("8080")
+                    .long("port"),
+                arg_include_vendored,
+                arg_author_display,
+                arg_anonymize,
+                arg_anonymize_salt,
+                arg_alias_file,
+                arg_exclude_bots,
+                arg_bot_pattern,
+                arg_exclude_authors_regex,
+                arg_only_authors_regex,
+                arg_repo.clone(),
+                arg_no_discover.clone(),
+                arg_debug.clone(),
+                arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+            ]),
+        )
+        .subcommand(
+            App::new("completions")
+            .about("will output a completion script for the shell.  e.g. grit completions bash > /etc/bash_completion.d/grit")
+            .arg(
+                Arg::new("shell")
+                    .help("shell to complete, either 'bash', 'zsh', 'fish' or 'powershell'")
+                    .required(true)
+                    .possible_values(completions::SHELLS)
+                    .index(1),
+            ),
+        )
+        .subcommand(
+            App::new("help-pages")
+            .about("will write a man page and a long form markdown help page, with examples, for every command to a directory.  For packagers shipping documentation built from the CLI definitions.")
+            .args(&[
+                Arg::new("out-dir")
+                    .help("directory the pages are written to, created when missing")
+                    .takes_value(true)
+                    .default_value("man")
+                    .long("out-dir"),
+                arg_debug.clone(),
+                arg_verbose.clone(),
+                arg_json_logs.clone(),
+                arg_no_progress.clone(),
+            ]),
+        );
+
+    let matches = app.get_matches_mut();
+
+    // serve wraps each response in an envelope of its own request
+    if let Some((name, sub_matches)) = matches.subcommand().filter(|(n, _)| *n != "serve") {
+        set_envelope(app.find_subcommand(name).unwrap(), sub_matches);
+    }
+
+    // only the commands taking a date range have the arguments to look up
+    if let Some((_, sub_matches)) = matches.subcommand().filter(|(n, _)| {
+        app.find_subcommand(*n)
+            .map(|c| c.get_arguments().any(|a| a.get_id() == "start-date"))
+            .unwrap_or(false)
+    }) {
+        if let Err(e) = grit_utils::check_date_range(
+            parse_date_arg(sub_matches.value_of("start-date")),
+            parse_date_arg(sub_matches.value_of("end-date")),
+        ) {
+            eprintln!("error: {:#}", e);
+            process::exit(1);
+        }
+    }
+
+    let processasble = match matches.subcommand_name() {
+        Some("fame") => handle_fame(matches.subcommand_matches("fame").unwrap()),
+        Some("bydate") => handle_bydate(matches.subcommand_matches("bydate").unwrap()),
+        Some("byfile") => handle_byfile(matches.subcommand_matches("byfile").unwrap()),
+        Some("effort") => handle_effort(matches.subcommand_matches("effort").unwrap()),
+        Some("annotate") => handle_annotate(matches.subcommand_matches("annotate").unwrap()),
+        Some("heat") => handle_heat(matches.subcommand_matches("heat").unwrap()),
+        Some("fame-diff") => handle_fame_diff(matches.subcommand_matches("fame-diff").unwrap()),
+        Some("ownership") => handle_ownership(matches.subcommand_matches("ownership").unwrap()),
+        Some("growth") => handle_growth(matches.subcommand_matches("growth").unwrap()),
+        Some("survival") => handle_survival(matches.subcommand_matches("survival").unwrap()),
+        Some("tenure") => handle_tenure(matches.subcommand_matches("tenure").unwrap()),
+        Some("worklife") => handle_worklife(matches.subcommand_matches("worklife").unwrap()),
+        Some("commitsize") => handle_commitsize(matches.subcommand_matches("commitsize").unwrap()),
+        Some("log-stats") => handle_log_stats(matches.subcommand_matches("log-stats").unwrap()),
+        Some("merges") => handle_merges(matches.subcommand_matches("merges").unwrap()),
+        Some("compare") => handle_compare(matches.subcommand_matches("compare").unwrap()),
+        Some("branches") => handle_branches(matches.subcommand_matches("branches").unwrap()),
+        Some("releases") => handle_releases(matches.subcommand_matches("releases").unwrap()),
+        Some("newcontributors") => {
+            handle_new_contributors(matches.subcommand_matches("newcontributors").unwrap())
+        }
+        Some("lint-history") => {
+            handle_lint_history(matches.subcommand_matches("lint-history").unwrap())
+        }
+        Some("identities") => handle_identities(matches.subcommand_matches("identities").unwrap()),
+        Some("age") => handle_age(matches.subcommand_matches("age").unwrap()),
+        Some("owners-audit") => {
+            handle_owners_audit(matches.subcommand_matches("owners-audit").unwrap())
+        }
+        #[cfg(feature = "symbols")]
+        Some("symbols") => handle_symbols(matches.subcommand_matches("symbols").unwrap()),
+        #[cfg(not(feature = "symbols"))]
+        Some("symbols") => {
+            eprintln!("error: grit was built without symbol ownership, rebuild it with --features symbols");
+            process::exit(1);
+        }
+        Some("timeline") => handle_timeline(matches.subcommand_matches("timeline").unwrap()),
+        Some("snapshot") => handle_snapshot(matches.subcommand_matches("snapshot").unwrap()),
+        Some("trends") => handle_trends(matches.subcommand_matches("trends").unwrap()),
+        Some("report") => handle_report(matches.subcommand_matches("report").unwrap()),
+        Some("tui") => handle_tui(matches.subcommand_matches("tui").unwrap()),
+        Some("serve") => handle_serve(matches.subcommand_matches("serve").unwrap()),
+        Some("completions") => {
+            handle_completions(&app, matches.subcommand_matches("completions").unwrap())
+        }
+        Some("help-pages") => {
+            handle_help_pages(&app, matches.subcommand_matches("help-pages").unwrap())
+        }
+        Some(_) => panic!("Unknown command was given"),
+        None => panic!("No command was given"),
+    };
+
+    if let Err(e) = processasble.process().and_then(|()| timing::report()) {
+        eprintln!("error: {:#}", e);
+
+        // policy violations get their own code so CI can tell them from failures
+        match e.downcast_ref::<GritError>() {
+            Some(GritError::PolicyViolation(_)) => process::exit(2),
+            _ => process::exit(1),
+        }
+    }
+}
+
+/// the arguments a command's builder validated, or the reason they are invalid and exit
+fn exit_on_invalid<T>(args: Result<T>) -> T {
+    args.unwrap_or_else(|e| {
+        eprintln!("error: {:#}", e);
+        process::exit(1);
+    })
+}
+
+fn handle_fame(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    set_precision(args.value_of("precision"));
+    timing::set_timing(args.is_present("timing"), args.value_of("timing"));
+    grit_utils::set_quiet(args.is_present("quiet"));
+    grit_utils::set_threads(parse_usize_arg(args.value_of("threads")));
+    grit_utils::set_include_vendored(args.is_present("include-vendored"));
+    ignore_revs::set_ignore_revs_file(args.value_of("ignore-revs-file"));
+    set_author_filters(args);
+    set_teams(args);
+    set_author_display(args.value_of("author-display"));
+    set_anonymize(args);
+    set_aliases(args.value_of("alias-file"));
+    let fame_args = FameArgs::builder(repo_arg(args))
+        .sort(convert_str_string(args.value_of("sort")))
+        .start_date(start_date_arg(args))
+        .end_date(parse_date_arg(args.value_of("end-date")))
+        .include(patterns_arg(args, "include"))
+        .exclude(patterns_arg(args, "exclude"))
+        .restrict_authors(convert_str_string(args.value_of("restrict-author")))
+        .format(parse_output_format(
+            flag_or_format(args, "csv"),
+            args.value_of("file"),
+            OutputFormat::Csv,
+        ))
+        .file(convert_str_string(args.value_of("file")))
+        .top(parse_usize_arg(args.value_of("top")))
+        .min_lines(parse_i32_arg(args.value_of("min-lines")))
+        .min_commits(parse_i32_arg(args.value_of("min-commits")))
+        .at(convert_str_string(args.value_of("at")))
+        .record(convert_str_string(args.value_of("record")))
+        .replay(convert_str_string(args.value_of("replay")))
+        .strict(args.is_present("strict"))
+        .by_language(args.is_present("by-language"))
+        .language_map(convert_str_string(args.value_of("language-map")))
+        .subdir(convert_str_string(args.value_of("subdir")))
+        .policy(Policy::new(
+            parse_usize_arg(args.value_of("fail-if-bus-factor-below")),
+            args.value_of("fail-if-author-share-above")
+                .map(|v| v.parse().expect("Cannot parse fail-if-author-share-above")),
+        ))
+        .stream(args.is_present("stream"))
+        .mode(
+            args.value_of("mode")
+                .unwrap()
+                .parse::<FameMode>()
+                .expect("Cannot parse mode"),
+        )
+        .summary(args.is_present("summary"))
+        .commit_metric(
+            args.value_of("commit-metric")
+                .unwrap()
+                .parse::<CommitMetric>()
+                .expect("Cannot parse commit-metric"),
+        )
+        .columns(grit_utils::convert_string_list_to_vec(convert_str_string(
+            args.value_of("columns"),
+        )))
+        .sample(
+            args.value_of("sample")
+                .map(|v| v.parse().expect("Cannot parse sample")),
+        )
+        .former_names(args.is_present("former-names"))
+        .extended(args.is_present("extended"))
+        .build();
+
+    Box::new(Fame::new(exit_on_invalid(fame_args)))
+}
+
+fn handle_bydate(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    set_date_format(args.value_of("date-format"));
+    set_author_filters(args);
+    set_teams(args);
+    set_timezone(args.value_of("timezone"));
+    set_weekend_days(args.value_of("weekend-days"));
+    let args = ByDateArgs::builder(repo_arg(args))
+        .start_date(start_date_arg(args))
+        .end_date(parse_date_arg(args.value_of("end-date")))
+        .file(convert_str_string(args.value_of("file")))
+        .image(args.is_present("image"))
+        .ignore_weekends(args.is_present("ignore-weekends"))
+        .ignore_gap_fill(args.is_present("ignore-gap-fill"))
+        .html(args.is_present("html"))
+        .restrict_authors(convert_str_string(args.value_of("restrict-author")))
+        .chart(
+            args.value_of("chart")
+                .unwrap()
+                .parse()
+                .expect("Cannot parse chart"),
+        )
+        .chart_options(parse_chart_options(args))
+        .format(parse_output_format(
+            args.value_of("format"),
+            args.value_of("file"),
+            OutputFormat::Csv,
+        ))
+        .path_filter(convert_str_string(args.value_of("path-filter")))
+        .cumulative(args.is_present("cumulative"))
+        .rolling(parse_usize_arg(args.value_of("rolling")))
+        .split_merges(args.is_present("split-merges"))
+        .build();
+
+    Box::new(ByDate::new(exit_on_invalid(args)))
+}
+
+fn handle_byfile(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    set_precision(args.value_of("precision"));
+    set_date_format(args.value_of("date-format"));
+    // a file asked for by name is looked up even when vendored
+    grit_utils::set_include_vendored(true);
+    ignore_revs::set_ignore_revs_file(args.value_of("ignore-revs-file"));
+    set_author_filters(args);
+    set_author_display(args.value_of("author-display"));
+    set_anonymize(args);
+    set_aliases(args.value_of("alias-file"));
+    let args = ByFileArgs::builder(
+        repo_arg(args),
+        args.value_of("in-file").unwrap_or_default().to_string(),
+    )
+    .output_file(convert_str_string(args.value_of("file")))
+    .image(args.is_present("image"))
+    .html(args.is_present("html"))
+    .restrict_authors(convert_str_string(args.value_of("restrict-author")))
+    .relative_to(parse_relative_to(args))
+    .at(convert_str_string(args.value_of("at")))
+    .dir(convert_str_string(args.value_of("dir")))
+    .rank(args.is_present("rank"))
+    .chart_options(parse_chart_options(args))
+    .format(parse_output_format(
+        args.value_of("format"),
+        args.value_of("file"),
+        OutputFormat::Csv,
+    ))
+    .strict(args.is_present("strict"))
+    .history(args.is_present("history"))
+    .lines(
+        args.value_of("lines")
+            .map(|v| v.parse().expect("Cannot parse lines")),
+    )
+    .build();
+
+    Box::new(ByFile::new(exit_on_invalid(args)))
+}
+
+fn handle_effort(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    timing::set_timing(args.is_present("timing"), args.value_of("timing"));
+    set_date_format(args.value_of("date-format"));
+    grit_utils::set_threads(parse_usize_arg(args.value_of("threads")));
+    grit_utils::set_include_vendored(args.is_present("include-vendored"));
+    ignore_revs::set_ignore_revs_file(args.value_of("ignore-revs-file"));
+    set_author_filters(args);
+    set_teams(args);
+    set_timezone(args.value_of("timezone"));
+    set_week_start(args.value_of("week-start"));
+    set_author_display(args.value_of("author-display"));
+    set_anonymize(args);
+    set_aliases(args.value_of("alias-file"));
+    let ea = EffortArgs::builder(repo_arg(args))
+        .start_date(start_date_arg(args))
+        .end_date(parse_date_arg(args.value_of("end-date")))
+        .format(parse_output_format(
+            flag_or_format(args, "table"),
+            args.value_of("file"),
+            OutputFormat::Csv,
+        ))
+        .include(patterns_arg(args, "include"))
+        .exclude(patterns_arg(args, "exclude"))
+        .restrict_authors(convert_str_string(args.value_of("restrict-author")))
+        .relative_to(parse_relative_to(args))
+        .top(parse_usize_arg(args.value_of("top")))
+        .min_commits(parse_i32_arg(args.value_of("min-commits")))
+        .strict(args.is_present("strict"))
+        .file(convert_str_string(args.value_of("file")))
+        // effort is per file, so teams are shown as a breakdown like authors
+        .by_author(args.is_present("by-author") || args.value_of("group-by") == Some("team"))
+        .by_dir(parse_usize_arg(args.value_of("by-dir")))
+        .stream(args.is_present("stream"))
+        .min_loc(parse_usize_arg(args.value_of("min-loc")))
+        .min_age_days(parse_usize_arg(args.value_of("min-age-days")))
+        .image(args.is_present("image"))
+        .html(args.is_present("html"))
+        .chart_options(parse_chart_options(args))
+        .include_deleted(args.is_present("include-deleted"))
+        .trend(
+            args.value_of("trend")
+                .map(|v| v.parse().expect("Cannot parse trend")),
+        )
+        .columns(grit_utils::convert_string_list_to_vec(convert_str_string(
+            args.value_of("columns"),
+        )))
+        .sample(
+            args.value_of("sample")
+                .map(|v| v.parse().expect("Cannot parse sample")),
+        )
+        .build();
+
+    Box::new(Effort::new(exit_on_invalid(ea)))
+}
+
+fn handle_annotate(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    set_date_format(args.value_of("date-format"));
+    set_author_display(args.value_of("author-display"));
+    set_anonymize(args);
+    set_aliases(args.value_of("alias-file"));
+    ignore_revs::set_ignore_revs_file(args.value_of("ignore-revs-file"));
+    let aa = AnnotateArgs::new(
+        repo_arg(args),
+        args.value_of("in-file").unwrap().to_string(),
+        convert_str_string(args.value_of("file")),
+        parse_output_format(
+            args.value_of("format"),
+            args.value_of("file"),
+            OutputFormat::Json,
+        ),
+        parse_relative_to(args),
+    );
+
+    Box::new(Annotate::new(aa))
+}
+
+fn handle_heat(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    set_date_format(args.value_of("date-format"));
+    set_author_display(args.value_of("author-display"));
+    set_anonymize(args);
+    set_aliases(args.value_of("alias-file"));
+    ignore_revs::set_ignore_revs_file(args.value_of("ignore-revs-file"));
+    let ha = HeatArgs::new(
+        repo_arg(args),
+        args.value_of("in-file").unwrap().to_string(),
+        convert_str_string(args.value_of("file")),
+        args.value_of("color-by")
+            .unwrap()
+            .parse()
+            .expect("Cannot parse color-by"),
+        parse_relative_to(args),
+        args.is_present("github"),
+    );
+
+    Box::new(Heat::new(ha))
+}
+
+fn handle_fame_diff(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    set_precision(args.value_of("precision"));
+    grit_utils::set_include_vendored(args.is_present("include-vendored"));
+    set_author_filters(args);
+    set_author_display(args.value_of("author-display"));
+    set_anonymize(args);
+    set_aliases(args.value_of("alias-file"));
+    let fda = FameDiffArgs::builder(
+        repo_arg(args),
+        args.value_of("from").unwrap().to_string(),
+        args.value_of("to").unwrap().to_string(),
+    )
+    .include(patterns_arg(args, "include"))
+    .exclude(patterns_arg(args, "exclude"))
+    .restrict_authors(convert_str_string(args.value_of("restrict-author")))
+    .format(parse_output_format(
+        flag_or_format(args, "csv"),
+        args.value_of("file"),
+        OutputFormat::Csv,
+    ))
+    .file(convert_str_string(args.value_of("file")))
+    .build();
+
+    Box::new(FameDiff::new(exit_on_invalid(fda)))
+}
+
+fn handle_ownership(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    set_precision(args.value_of("precision"));
+    set_date_format(args.value_of("date-format"));
+    grit_utils::set_include_vendored(args.is_present("include-vendored"));
+    set_author_filters(args);
+    set_author_display(args.value_of("author-display"));
+    set_anonymize(args);
+    set_aliases(args.value_of("alias-file"));
+    let oa = OwnershipArgs::builder(repo_arg(args))
+        .snapshots(
+            args.value_of("snapshots")
+                .unwrap()
+                .parse()
+                .expect("Cannot parse snapshots"),
+        )
+        .start_date(start_date_arg(args))
+        .end_date(parse_date_arg(args.value_of("end-date")))
+        .include(patterns_arg(args, "include"))
+        .exclude(patterns_arg(args, "exclude"))
+        .depth(parse_usize_arg(args.value_of("depth")).unwrap())
+        .treemap(args.is_present("treemap"))
+        .file(convert_str_string(args.value_of("file")))
+        .image(args.is_present("image"))
+        .html(args.is_present("html"))
+        .chart_options(parse_chart_options(args))
+        .format(parse_output_format(
+            args.value_of("format"),
+            args.value_of("file"),
+            OutputFormat::Csv,
+        ))
+        .build();
+
+    Box::new(Ownership::new(exit_on_invalid(oa)))
+}
+
+fn handle_growth(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    set_date_format(args.value_of("date-format"));
+    set_week_start(args.value_of("week-start"));
+    let ga = GrowthArgs::builder(repo_arg(args))
+        .interval(
+            args.value_of("interval")
+                .unwrap()
+                .parse()
+                .expect("Cannot parse interval"),
+        )
+        .start_date(start_date_arg(args))
+        .end_date(parse_date_arg(args.value_of("end-date")))
+        .include(patterns_arg(args, "include"))
+        .exclude(patterns_arg(args, "exclude"))
+        .file(convert_str_string(args.value_of("file")))
+        .image(args.is_present("image"))
+        .html(args.is_present("html"))
+        .chart_options(parse_chart_options(args))
+        .format(parse_output_format(
+            args.value_of("format"),
+            args.value_of("file"),
+            OutputFormat::Csv,
+        ))
+        .build();
+
+    Box::new(Growth::new(exit_on_invalid(ga)))
+}
+
+fn handle_survival(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    set_precision(args.value_of("precision"));
+    set_date_format(args.value_of("date-format"));
+    set_week_start(args.value_of("week-start"));
+    let sa = SurvivalArgs::builder(repo_arg(args))
+        .interval(
+            args.value_of("interval")
+                .unwrap()
+                .parse()
+                .expect("Cannot parse interval"),
+        )
+        .start_date(start_date_arg(args))
+        .end_date(parse_date_arg(args.value_of("end-date")))
+        .include(patterns_arg(args, "include"))
+        .exclude(patterns_arg(args, "exclude"))
+        .file(convert_str_string(args.value_of("file")))
+        .image(args.is_present("image"))
+        .html(args.is_present("html"))
+        .chart_options(parse_chart_options(args))
+        .format(parse_output_format(
+            args.value_of("format"),
+            args.value_of("file"),
+            OutputFormat::Csv,
+        ))
+        .build();
+
+    Box::new(Survival::new(exit_on_invalid(sa)))
+}
+
+fn handle_tenure(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    set_date_format(args.value_of("date-format"));
+    set_author_filters(args);
+    set_author_display(args.value_of("author-display"));
+    set_anonymize(args);
+    set_aliases(args.value_of("alias-file"));
+    let ta = TenureArgs::builder(repo_arg(args))
+        .start_date(start_date_arg(args))
+        .end_date(parse_date_arg(args.value_of("end-date")))
+        .restrict_authors(convert_str_string(args.value_of("restrict-author")))
+        .inactive_days(parse_i32_arg(args.value_of("inactive-days")).unwrap() as i64)
+        .file(convert_str_string(args.value_of("file")))
+        .format(parse_output_format(
+            args.value_of("format"),
+            args.value_of("file"),
+            OutputFormat::Csv,
+        ))
+        .path_filter(convert_str_string(args.value_of("path-filter")))
+        .build();
+
+    Box::new(Tenure::new(exit_on_invalid(ta)))
+}
+
+fn handle_worklife(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    set_precision(args.value_of("precision"));
+    set_author_filters(args);
+    set_timezone(args.value_of("timezone"));
+    set_weekend_days(args.value_of("weekend-days"));
+    set_author_display(args.value_of("author-display"));
+    set_anonymize(args);
+    set_aliases(args.value_of("alias-file"));
+    let wa = WorklifeArgs::new(
+        repo_arg(args),
+        start_date_arg(args),
+        parse_date_arg(args.value_of("end-date")),
+        convert_str_string(args.value_of("restrict-author")),
+        args.value_of("work-hours")
+            .unwrap()
+            .parse()
+            .expect("Cannot parse work-hours"),
+        convert_str_string(args.value_of("file")),
+        parse_output_format(
+            args.value_of("format"),
+            args.value_of("file"),
+            OutputFormat::Csv,
+        ),
+    );
+
+    Box::new(Worklife::new(wa))
+}
+
+fn handle_commitsize(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    set_precision(args.value_of("precision"));
+    set_date_format(args.value_of("date-format"));
+    set_author_filters(args);
+    set_author_display(args.value_of("author-display"));
+    set_anonymize(args);
+    set_aliases(args.value_of("alias-file"));
+    let ca = CommitSizeArgs::builder(repo_arg(args))
+        .start_date(start_date_arg(args))
+        .end_date(parse_date_arg(args.value_of("end-date")))
+        .include(patterns_arg(args, "include"))
+        .exclude(patterns_arg(args, "exclude"))
+        .restrict_authors(convert_str_string(args.value_of("restrict-author")))
+        .giant_lines(
+            args.value_of("giant-lines")
+                .unwrap()
+                .parse()
+                .expect("Cannot parse giant-lines"),
+        )
+        .list_giant(args.is_present("list-giant"))
+        .file(convert_str_string(args.value_of("file")))
+        .format(parse_output_format(
+            args.value_of("format"),
+            args.value_of("file"),
+            OutputFormat::Csv,
+        ))
+        .build();
+
+    Box::new(CommitSize::new(exit_on_invalid(ca)))
+}
+
+fn handle_log_stats(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    set_date_format(args.value_of("date-format"));
+    set_author_filters(args);
+    set_author_display(args.value_of("author-display"));
+    set_anonymize(args);
+    set_aliases(args.value_of("alias-file"));
+    set_timezone(args.value_of("timezone"));
+    let la = LogStatsArgs::new(
+        repo_arg(args),
+        start_date_arg(args),
+        parse_date_arg(args.value_of("end-date")),
+        patterns_arg(args, "include"),
+        patterns_arg(args, "exclude"),
+        convert_str_string(args.value_of("file")),
+        parse_output_format(
+            args.value_of("format"),
+            args.value_of("file"),
+            OutputFormat::Csv,
+        ),
+    );
+
+    Box::new(LogStats::new(la))
+}
+
+fn handle_merges(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    set_author_filters(args);
+    set_author_display(args.value_of("author-display"));
+    set_anonymize(args);
+    set_aliases(args.value_of("alias-file"));
+    let ma = MergesArgs::new(
+        repo_arg(args),
+        start_date_arg(args),
+        parse_date_arg(args.value_of("end-date")),
+        convert_str_string(args.value_of("file")),
+        parse_output_format(
+            args.value_of("format"),
+            args.value_of("file"),
+            OutputFormat::Csv,
+        ),
+    );
+
+    Box::new(Merges::new(ma))
+}
+
+fn handle_compare(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    set_author_filters(args);
+    set_author_display(args.value_of("author-display"));
+    set_anonymize(args);
+    set_aliases(args.value_of("alias-file"));
+    let ca = CompareArgs::new(
+        repo_arg(args),
+        args.value_of("base").unwrap().to_string(),
+        args.value_of("head").unwrap().to_string(),
+        patterns_arg(args, "include"),
+        patterns_arg(args, "exclude"),
+        convert_str_string(args.value_of("file")),
+        parse_output_format(
+            args.value_of("format"),
+            args.value_of("file"),
+            OutputFormat::Csv,
+        ),
+    );
+
+    Box::new(Compare::new(ca))
+}
+
+fn handle_branches(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    set_date_format(args.value_of("date-format"));
+    set_author_filters(args);
+    set_author_display(args.value_of("author-display"));
+    set_anonymize(args);
+    set_aliases(args.value_of("alias-file"));
+    let ba = BranchesArgs::new(
+        repo_arg(args),
+        args.value_of("base").unwrap().to_string(),
+        parse_i32_arg(args.value_of("older-than")).map(|d| d as i64),
+        convert_str_string(args.value_of("file")),
+        parse_output_format(
+            args.value_of("format"),
+            args.value_of("file"),
+            OutputFormat::Csv,
+        ),
+    );
+
+    Box::new(Branches::new(ba))
+}
+
+fn handle_releases(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    set_date_format(args.value_of("date-format"));
+    set_author_filters(args);
+    set_author_display(args.value_of("author-display"));
+    set_anonymize(args);
+    set_aliases(args.value_of("alias-file"));
+    let ra = ReleasesArgs::new(
+        repo_arg(args),
+        convert_str_string(args.value_of("tag-pattern")),
+        args.is_present("semver"),
+        convert_str_string(args.value_of("file")),
+        parse_output_format(
+            args.value_of("format"),
+            args.value_of("file"),
+            OutputFormat::Csv,
+        ),
+    );
+
+    Box::new(Releases::new(ra))
+}
+
+fn handle_new_contributors(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    set_author_filters(args);
+    set_author_display(args.value_of("author-display"));
+    set_anonymize(args);
+    set_aliases(args.value_of("alias-file"));
+    let na = NewContributorsArgs::new(
+        repo_arg(args),
+        start_date_arg(args),
+        parse_date_arg(args.value_of("end-date")),
+        convert_str_string(args.value_of("file")),
+        parse_output_format(
+            args.value_of("format"),
+            args.value_of("file"),
+            OutputFormat::Csv,
+        ),
+    );
+
+    Box::new(NewContributors::new(na))
+}
+
+fn handle_lint_history(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    set_precision(args.value_of("precision"));
+    set_author_filters(args);
+    set_author_display(args.value_of("author-display"));
+    set_anonymize(args);
+    set_aliases(args.value_of("alias-file"));
+    let la = LintHistoryArgs::builder(repo_arg(args))
+        .start_date(start_date_arg(args))
+        .end_date(parse_date_arg(args.value_of("end-date")))
+        .pattern(convert_str_string(args.value_of("pattern")))
+        .include_merges(args.is_present("include-merges"))
+        .fail_under(
+            args.value_of("fail-under")
+                .map(|v| v.parse().expect("Cannot parse fail-under")),
+        )
+        .file(convert_str_string(args.value_of("file")))
+        .format(parse_output_format(
+            args.value_of("format"),
+            args.value_of("file"),
+            OutputFormat::Csv,
+        ))
+        .build();
+
+    Box::new(LintHistory::new(exit_on_invalid(la)))
+}
+
+fn handle_identities(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    set_author_filters(args);
+    let ia = IdentitiesArgs::builder(repo_arg(args))
+        .start_date(start_date_arg(args))
+        .end_date(parse_date_arg(args.value_of("end-date")))
+        .max_distance(parse_usize_arg(args.value_of("max-distance")).unwrap())
+        .write_aliases(convert_str_string(args.value_of("write-aliases")))
+        .write_mailmap(convert_str_string(args.value_of("write-mailmap")))
+        .interactive(args.is_present("interactive"))
+        .file(convert_str_string(args.value_of("file")))
+        .format(parse_output_format(
+            args.value_of("format"),
+            args.value_of("file"),
+            OutputFormat::Csv,
+        ))
+        .build();
+
+    Box::new(Identities::new(exit_on_invalid(ia)))
+}
+
+fn handle_age(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    set_precision(args.value_of("precision"));
+    set_date_format(args.value_of("date-format"));
+    grit_utils::set_include_vendored(args.is_present("include-vendored"));
+    set_author_filters(args);
+    let aa = AgeArgs::builder(repo_arg(args))
+        .include(patterns_arg(args, "include"))
+        .exclude(patterns_arg(args, "exclude"))
+        .older_than(parse_i32_arg(args.value_of("older-than")).unwrap() as i64)
+        .on_date(parse_date_arg(args.value_of("as-of")))
+        .file(convert_str_string(args.value_of("file")))
+        .format(parse_output_format(
+            args.value_of("format"),
+            args.value_of("file"),
+            OutputFormat::Csv,
+        ))
+        .relative_to(parse_relative_to(args))
+        .strict(args.is_present("strict"))
+        .build();
+
+    Box::new(Age::new(exit_on_invalid(aa)))
+}
+
+fn handle_owners_audit(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    set_precision(args.value_of("precision"));
+    grit_utils::set_include_vendored(args.is_present("include-vendored"));
+    set_author_filters(args);
+    set_aliases(args.value_of("alias-file"));
+    if let Some(v) = args.value_of("teams") {
+        identity::set_teams(identity::Teams::load(v).expect("Cannot read teams"));
+    }
+    let oa = OwnersAuditArgs::builder(repo_arg(args))
+        .codeowners(convert_str_string(args.value_of("codeowners")))
+        .threshold(
+            args.value_of("threshold")
+                .unwrap()
+                .parse()
+                .expect("Cannot parse threshold"),
+        )
+        .include(patterns_arg(args, "include"))
+        .exclude(patterns_arg(args, "exclude"))
+        .file(convert_str_string(args.value_of("file")))
+        .format(parse_output_format(
+            args.value_of("format"),
+            args.value_of("file"),
+            OutputFormat::Csv,
+        ))
+        .strict(args.is_present("strict"))
+        .build();
+
+    Box::new(OwnersAudit::new(exit_on_invalid(oa)))
+}
+
+#[cfg(feature = "symbols")]
+fn handle_symbols(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    set_precision(args.value_of("precision"));
+    grit_utils::set_include_vendored(args.is_present("include-vendored"));
+    set_author_filters(args);
+    set_author_display(args.value_of("author-display"));
+    set_anonymize(args);
+    set_aliases(args.value_of("alias-file"));
+    let sa = SymbolsArgs::new(
+        repo_arg(args),
+        patterns_arg(args, "include"),
+        patterns_arg(args, "exclude"),
+        convert_str_string(args.value_of("file")),
+        parse_output_format(
+            args.value_of("format"),
+            args.value_of("file"),
+            OutputFormat::Csv,
+        ),
+        parse_relative_to(args),
+        args.is_present("strict"),
+    );
+
+    Box::new(Symbols::new(sa))
+}
+
+fn handle_timeline(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    set_author_filters(args);
+    set_timezone(args.value_of("timezone"));
+    set_week_start(args.value_of("week-start"));
+    set_author_display(args.value_of("author-display"));
+    set_anonymize(args);
+    set_aliases(args.value_of("alias-file"));
+    let ta = TimelineArgs::builder(repo_arg(args))
+        .start_date(start_date_arg(args))
+        .end_date(parse_date_arg(args.value_of("end-date")))
+        .metric(
+            args.value_of("metric")
+                .unwrap()
+                .parse()
+                .expect("Cannot parse metric"),
+        )
+        .restrict_authors(convert_str_string(args.value_of("restrict-author")))
+        .top(parse_usize_arg(args.value_of("top")))
+        .file(convert_str_string(args.value_of("file")))
+        .image(args.is_present("image"))
+        .html(args.is_present("html"))
+        .chart_options(parse_chart_options(args))
+        .format(parse_output_format(
+            args.value_of("format"),
+            args.value_of("file"),
+            OutputFormat::Csv,
+        ))
+        .build();
+
+    Box::new(Timeline::new(exit_on_invalid(ta)))
+}
+
+fn handle_snapshot(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    grit_utils::set_include_vendored(args.is_present("include-vendored"));
+    set_author_filters(args);
+    set_author_display(args.value_of("author-display"));
+    set_anonymize(args);
+    set_aliases(args.value_of("alias-file"));
+    let sa = SnapshotArgs::new(
+        repo_arg(args),
+        patterns_arg(args, "include"),
+        patterns_arg(args, "exclude"),
+        convert_str_string(args.value_of("store")),
+    );
+
+    Box::new(Snapshot::new(sa))
+}
+
+fn handle_trends(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    set_precision(args.value_of("precision"));
+    let ta = TrendsArgs::new(
+        repo_arg(args),
+        convert_str_string(args.value_of("store")),
+        parse_date_arg(args.value_of("since")),
+        convert_str_string(args.value_of("file")),
+        parse_output_format(
+            args.value_of("format"),
+            args.value_of("file"),
+            OutputFormat::Csv,
+        ),
+    );
+
+    Box::new(Trends::new(ta))
+}
+
+fn handle_report(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    set_date_format(args.value_of("date-format"));
+    grit_utils::set_include_vendored(args.is_present("include-vendored"));
+    set_author_filters(args);
+    set_author_display(args.value_of("author-display"));
+    set_anonymize(args);
+    set_aliases(args.value_of("alias-file"));
+    cache::set_enabled(true);
+    let ra = ReportArgs::builder(
+        repo_arg(args),
+        report::parse_analyses(args.value_of("analyses").unwrap()).expect("Cannot parse analyses"),
+        args.value_of("out-dir").unwrap().to_string(),
+    )
+    .start_date(start_date_arg(args))
+    .end_date(parse_date_arg(args.value_of("end-date")))
+    .include(patterns_arg(args, "include"))
+    .exclude(patterns_arg(args, "exclude"))
+    .format(
+        args.value_of("format")
+            .map(|f| f.parse().expect("Cannot parse format"))
+            .unwrap_or(OutputFormat::Csv),
+    )
+    .build();
+
+    Box::new(Report::new(exit_on_invalid(ra)))
+}
+
+fn handle_serve(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    grit_utils::set_include_vendored(args.is_present("include-vendored"));
+    set_author_filters(args);
+    set_author_display(args.value_of("author-display"));
+    set_anonymize(args);
+    set_aliases(args.value_of("alias-file"));
+    let sa = ServeArgs::new(
+        repo_arg(args),
+        args.value_of("bind").unwrap().to_string(),
+        args.value_of("port")
+            .unwrap()
+            .parse()
+            .expect("Cannot parse port"),
+    );
+
+    Box::new(Serve::new(sa))
+}
+
+fn handle_tui(args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    grit_utils::set_include_vendored(args.is_present("include-vendored"));
+    set_author_filters(args);
+    set_author_display(args.value_of("author-display"));
+    set_anonymize(args);
+    set_aliases(args.value_of("alias-file"));
+    let ta = TuiArgs::new(
+        repo_arg(args),
+        start_date_arg(args),
+        parse_date_arg(args.value_of("end-date")),
+        patterns_arg(args, "include"),
+        patterns_arg(args, "exclude"),
+        convert_str_string(args.value_of("restrict-author")),
+    );
+
+    Box::new(Tui::new(ta))
+}
+
+/// the shorthand flag (--csv, --table) names the format when it is present
+fn flag_or_format<'a>(args: &'a ArgMatches, flag: &'a str) -> Option<&'a str> {
+    if args.is_present(flag) {
+        Some(flag)
+    } else {
+        args.value_of("format")
+    }
+}
+
+/// an explicit format always wins, otherwise a table on a terminal and `piped` when the
+/// output is redirected or written to a file
+/// the format asked for, else the one `file`'s extension names, else a table for a terminal
+/// and `piped` for anything else
+fn parse_output_format(
+    format: Option<&str>,
+    file: Option<&str>,
+    piped: OutputFormat,
+) -> OutputFormat {
+    match (format, file.and_then(grit_utils::file_format)) {
+        (Some(f), _) => f.parse().expect("Cannot parse format"),
+        (None, Some(f)) => f,
+        (None, None) if file.is_none() && io::stdout().is_terminal() => OutputFormat::Table,
+        (None, None) => piped,
+    }
+}
+
+fn parse_chart_options(args: &ArgMatches) -> ChartOptions {
+    ChartOptions::new(
+        args.value_of("chart-theme")
+            .unwrap()
+            .parse()
+            .expect("Cannot parse chart-theme"),
+        convert_str_string(args.value_of("chart-title")),
+        parse_usize_arg(args.value_of("chart-width")).map(|w| w as isize),
+        parse_usize_arg(args.value_of("chart-height")).map(|h| h as isize),
+    )
+}
+
+fn set_author_filters(args: &ArgMatches) {
+    if args.is_present("exclude-bots") {
+        identity::set_bot_filter(
+            identity::bot_regex(args.value_of("bot-pattern")).expect("Cannot parse bot-pattern"),
+        );
+    }
+
+    let author_regex = |name: &str| {
+        args.value_of(name)
+            .map(|v| identity::author_regex(v).expect("Cannot parse author regex"))
+    };
+
+    identity::set_author_filter(identity::AuthorFilter::new(
+        author_regex("exclude-authors-regex"),
+        author_regex("only-authors-regex"),
+    ));
+}
+
+fn set_timezone(value: Option<&str>) {
+    if let Some(v) = value {
+        grit_utils::set_timezone(v.parse().expect("Cannot parse timezone"));
+    }
+}
+
+fn set_date_format(value: Option<&str>) {
+    if let Some(v) = value {
+        grit_utils::set_date_format(
+            grit_utils::parse_date_format(v).expect("Cannot parse date-format"),
+        );
+    }
+}
+
+fn set_precision(value: Option<&str>) {
+    if let Some(v) = value {
+        output::set_precision(v.parse().expect("Cannot parse precision"));
+    }
+}
+
+fn set_weekend_days(value: Option<&str>) {
+    if let Some(v) = value {
+        grit_utils::set_weekend_days(
+            grit_utils::parse_weekdays(v).expect("Cannot parse weekend-days"),
+        );
+    }
+}
+
+fn set_week_start(value: Option<&str>) {
+    if let Some(v) = value {
+        grit_utils::set_week_start(
+            grit_utils::parse_weekdays(v).expect("Cannot parse week-start")[0],
+        );
+    }
+}
+
+fn set_author_display(value: Option<&str>) {
+    if let Some(v) = value {
+        identity::set_author_display(v.parse().expect("Cannot parse author-display"));
+    }
+}
+
+fn set_anonymize(args: &ArgMatches) {
+    if args.is_present("anonymize") {
+        identity::set_anonymize(args.value_of("anonymize-salt").unwrap_or("").to_string());
+    }
+}
+
+/// maps authors to the `--teams` file's teams, grouping them by team with `--group-by team`
+fn set_teams(args: &ArgMatches) {
+    if let Some(v) = args.value_of("teams") {
+        identity::set_teams(identity::Teams::load(v).expect("Cannot read teams"));
+    }
+
+    if let Some(v) = args.value_of("group-by") {
+        identity::set_group_by(v.parse().expect("Cannot parse group-by"));
+    }
+}
+
+fn set_aliases(value: Option<&str>) {
+    if let Some(v) = value {
+        identity::set_aliases(identity::Aliases::load(v).expect("Cannot read alias-file"));
+    }
+}
+
+/// records the command, its arguments and the repository HEAD for json output
+fn set_envelope(command: &App, args: &ArgMatches) {
+    let parameters: Vec<(String, String)> = command
+        .get_arguments()
+        // the salt is a secret, knowing it undoes the anonymizing
+        .filter(|a| args.is_present(a.get_id()) && a.get_id() != "anonymize-salt")
+        .map(|a| {
+            let value = if a.is_takes_value_set() {
+                args.values_of(a.get_id())
+                    .map(|v| v.collect::<Vec<&str>>().join(","))
+                    .unwrap_or_default()
+            } else {
+                String::from("true")
+            };
+            (a.get_id().to_string(), value)
+        })
+        .collect();
+
+    let repo_path = parameters
+        .iter()
+        .find(|(k, _)| k == "repo")
+        .map(|(_, v)| v.as_str())
+        .unwrap_or(".");
+
+    let head = grit_utils::open_repo(repo_path)
+        .ok()
+        .and_then(|r| r.head().ok().and_then(|h| h.target()))
+        .map(|oid| oid.to_string());
+
+    output::set_envelope(output::Envelope {
+        command: command.get_name().to_string(),
+        parameters,
+        head,
+        generated_at: chrono::Utc::now().to_rfc3339(),
+    });
+}
+
+/// sets up what a command writes to stderr, its logs and progress
+fn set_logging(args: &ArgMatches) {
+    progress::set_no_progress(args.is_present("no-progress"));
+
+    let level = if args.is_present("debug") {
+        LevelFilter::Debug
+    } else if args.is_present("verbose") {
+        LevelFilter::Info
+    } else {
+        LevelFilter::Error
+    };
+
+    logging::init(level, args.is_present("json-logs"));
+}
+
+fn handle_completions(app: &App<'static>, args: &ArgMatches) -> Box<dyn Processable<()>> {
+    let ca = CompletionsArgs::new(
+        args.value_of("shell")
+            .unwrap()
+            .parse()
+            .expect("Cannot parse shell"),
+        app.clone(),
+    );
+
+    Box::new(Completions::new(ca))
+}
+
+fn handle_help_pages(app: &App<'static>, args: &ArgMatches) -> Box<dyn Processable<()>> {
+    set_logging(args);
+    let ha = HelpPagesArgs::new(args.value_of("out-dir").unwrap().to_string(), app.clone());
 
-    SimpleLogger::new().with_level(level).init().unwrap();
+    Box::new(HelpPages::new(ha))
 }
 
 #[cfg(test)]
@@ -360,7 +3252,7 @@ mod tests {
 
         match r {
             Ok(d) => println!("date parsed to {}", d),
-            Err(e) => assert!(false, "error thrown {:?}", e),
+            Err(e) => panic!("error thrown {:?}", e),
         }
     }
 
@@ -372,7 +3264,7 @@ mod tests {
         let r = parse_datelocal("2020-04-01t");
 
         match r {
-            Ok(d) => assert!(false, "date should of failed.  Result:{}", d),
+            Ok(d) => panic!("date should of failed.  Result:{}", d),
             Err(e) => println!("error expected: {:?}", e),
         }
     }
@@ -0,0 +1,263 @@
+use super::Processable;
+use crate::identity;
+use crate::table::Table;
+use crate::utils::grit_utils;
+use crate::utils::grit_utils::OutputFormat;
+use anyhow::Result;
+use chrono::NaiveDate;
+use git2::{Commit, Oid, Repository, Time};
+
+const SECONDS_PER_HOUR: f64 = 3600.0;
+
+pub struct MergesArgs {
+    path: String,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+    file: Option<String>,
+    format: OutputFormat,
+}
+
+impl MergesArgs {
+    pub fn new(
+        path: String,
+        start_date: Option<NaiveDate>,
+        end_date: Option<NaiveDate>,
+        file: Option<String>,
+        format: OutputFormat,
+    ) -> MergesArgs {
+        MergesArgs {
+            path,
+            start_date,
+            end_date,
+            file,
+            format,
+        }
+    }
+}
+
+/// A merge commit and the commits it brought in from the merged branch.
+#[derive(Clone, Debug, PartialEq)]
+struct MergeOutput {
+    commit_id: Oid,
+    merger: String,
+    merged_at: Time,
+    branch_commits: usize,
+    /// the earliest authored of the branch commits
+    first_commit_at: Time,
+}
+
+impl MergeOutput {
+    /// hours between the first branch commit and the merge
+    fn latency_hours(&self) -> f64 {
+        (self.merged_at.seconds() - self.first_commit_at.seconds()).max(0) as f64 / SECONDS_PER_HOUR
+    }
+}
+
+pub struct Merges {
+    args: MergesArgs,
+}
+
+impl Merges {
+    pub fn new(args: MergesArgs) -> Merges {
+        Merges { args }
+    }
+
+    /// every merge commit in the date range, newest first
+    fn process_merges(&self) -> Result<Vec<MergeOutput>> {
+        let repo = grit_utils::open_repo(&self.args.path)?;
+
+        let mut revwalk = repo.revwalk()?;
+        revwalk.set_sorting(git2::Sort::TIME)?;
+        revwalk.push_head()?;
+
+        let mut output: Vec<MergeOutput> = Vec::new();
+
+        for oid in revwalk {
+            let commit = repo.find_commit(oid?)?;
+
+            if commit.parent_count() < 2 {
+                continue;
+            }
+
+            let day = grit_utils::convert_git_time(&commit.time());
+
+            if self.args.start_date.map(|d| day < d).unwrap_or(false)
+                || self.args.end_date.map(|d| day > d).unwrap_or(false)
+            {
+                continue;
+            }
+
+            let committer = commit.committer();
+            let name = String::from_utf8_lossy(committer.name_bytes()).to_string();
+            let email = String::from_utf8_lossy(committer.email_bytes()).to_string();
+
+            if identity::is_excluded(&name, &email) {
+                continue;
+            }
+
+            if let Some((branch_commits, first_commit_at)) = Merges::branch_commits(&repo, &commit)?
+            {
+                output.push(MergeOutput {
+                    commit_id: commit.id(),
+                    merger: identity::display_author(&name, &email),
+                    merged_at: commit.time(),
+                    branch_commits,
+                    first_commit_at,
+                });
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// the number of commits the merge brings in that the first parent did not have, and
+    /// the earliest author time among them.  None when the merge brings in nothing new.
+    fn branch_commits(repo: &Repository, merge: &Commit) -> Result<Option<(usize, Time)>> {
+        let mut revwalk = repo.revwalk()?;
+        for parent in merge.parent_ids().skip(1) {
+            revwalk.push(parent)?;
+        }
+        revwalk.hide(merge.parent_id(0)?)?;
+
+        let mut count = 0;
+        let mut first: Option<Time> = None;
+
+        for oid in revwalk {
+            let when = repo.find_commit(oid?)?.author().when();
+            count += 1;
+
+            if first.map(|f| when.seconds() < f.seconds()).unwrap_or(true) {
+                first = Some(when);
+            }
+        }
+
+        Ok(first.map(|f| (count, f)))
+    }
+
+    fn display_output(&self, output: &[MergeOutput]) -> Result<()> {
+        let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+
+        let mut table = Table::new();
+
+        table.set_titles(table_row![
+            "commit",
+            "merged at",
+            "merger",
+            "branch commits",
+            "first commit at",
+            "latency (hours)"
+        ]);
+
+        let datetime = |t: &Time| {
+            grit_utils::convert_git_datetime(t)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        };
+
+        output.iter().for_each(|r| {
+            table.add_row(table_row![
+                r.commit_id.to_string(),
+                datetime(&r.merged_at),
+                r.merger,
+                r.branch_commits,
+                datetime(&r.first_commit_at),
+                format!("{:.1}", r.latency_hours())
+            ]);
+        });
+
+        table.add_label(0, "commit");
+        table.add_label(2, "merger");
+        table.add_metric(
+            5,
+            "grit_merge_latency_hours",
+            "Hours between the merged branch's first commit and the merge.",
+        );
+
+        table.write(&mut w, self.args.format)?;
+
+        Ok(())
+    }
+}
+
+impl Processable<()> for Merges {
+    fn process(&self) -> Result<()> {
+        let output = self.process_merges()?;
+
+        self.display_output(&output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Signature;
+    use log::LevelFilter;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    /// the fixture with a two commit branch off Bob's 2020-01-02 commit, merged by Carol
+    /// on 2020-01-06
+    fn merge_repo() -> TempDir {
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let repo = Repository::open(td.path()).unwrap();
+
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let base = head.parent(0).unwrap().parent(0).unwrap();
+        let tree = base.tree().unwrap();
+
+        // 2020-01-02 18:00 UTC
+        let dave =
+            Signature::new("Dave", "dave@example.com", &Time::new(1_577_988_000, 0)).unwrap();
+        let b1 = repo
+            .commit(None, &dave, &dave, "branch", &tree, &[&base])
+            .unwrap();
+        let b1 = repo.find_commit(b1).unwrap();
+
+        // 2020-01-03 14:00 UTC
+        let dave =
+            Signature::new("Dave", "dave@example.com", &Time::new(1_578_060_000, 0)).unwrap();
+        let b2 = repo
+            .commit(None, &dave, &dave, "branch", &tree, &[&b1])
+            .unwrap();
+        let b2 = repo.find_commit(b2).unwrap();
+
+        // 2020-01-06 12:00 UTC
+        let carol =
+            Signature::new("Carol", "carol@example.com", &Time::new(1_578_312_000, 0)).unwrap();
+        repo.commit(
+            Some("HEAD"),
+            &carol,
+            &carol,
+            "merge",
+            &head.tree().unwrap(),
+            &[&head, &b2],
+        )
+        .unwrap();
+
+        td
+    }
+
+    #[test]
+    fn test_process_merges() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td = merge_repo();
+        let path = td.path().to_str().unwrap();
+
+        let merges = Merges::new(MergesArgs::new(
+            path.to_string(),
+            None,
+            None,
+            None,
+            OutputFormat::Csv,
+        ))
+        .process_merges()
+        .unwrap();
+
+        assert_eq!(merges.len(), 1);
+        assert_eq!(merges[0].merger, "Carol");
+        assert_eq!(merges[0].branch_commits, 2);
+        assert!((merges[0].latency_hours() - 90.0).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,441 @@
+use crate::diff_stats;
+use crate::utils::grit_utils;
+use anyhow::Result;
+use chrono::NaiveDate;
+use git2::{Commit, Repository};
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+/// Something a walker saw, a commit or the lines of a blame hunk, credited to `key`: the
+/// author, file or day the analysis groups by.
+pub struct Observation<'a, K> {
+    pub key: K,
+    pub commit_id: &'a str,
+    /// day of the commit, unknown for blame replayed from a record file
+    pub day: Option<NaiveDate>,
+    pub file: Option<&'a str>,
+    /// lines blamed on the commit, 0 for commit walks
+    pub lines: usize,
+    /// lines the commit added plus removed, only counted for metrics wanting diffs
+    pub churn: usize,
+}
+
+impl<'a, K> Observation<'a, K> {
+    pub fn new(key: K, commit_id: &'a str) -> Observation<'a, K> {
+        Observation {
+            key,
+            commit_id,
+            day: None,
+            file: None,
+            lines: 0,
+            churn: 0,
+        }
+    }
+}
+
+/// A measure tallied per key from the observations of a walk.  A new metric is a type
+/// implementing this, registered on a `Metrics` alongside the others it is walked with.
+pub trait Metric<K>: Any {
+    /// the name the metric is looked up by
+    fn name(&self) -> &'static str;
+
+    fn observe(&mut self, o: &Observation<K>);
+
+    /// the metric for `key`, 0 for keys never observed
+    fn value(&self, key: &K) -> usize;
+
+    /// the metric over every key, e.g. the distinct commits of all the authors
+    fn total(&self) -> usize;
+
+    /// whether the walker must diff each commit to fill in the churn
+    fn needs_diff(&self) -> bool {
+        false
+    }
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+/// Distinct commits per key.
+#[derive(Clone)]
+pub struct Commits<K> {
+    ids: HashMap<K, HashSet<String>>,
+    all: HashSet<String>,
+}
+
+impl<K: Eq + Hash> Commits<K> {
+    pub fn ids(&self, key: &K) -> Option<&HashSet<String>> {
+        self.ids.get(key)
+    }
+
+    pub fn all(&self) -> &HashSet<String> {
+        &self.all
+    }
+}
+
+impl<K> Default for Commits<K> {
+    fn default() -> Commits<K> {
+        Commits {
+            ids: HashMap::new(),
+            all: HashSet::new(),
+        }
+    }
+}
+
+impl<K: Eq + Hash + Clone + 'static> Metric<K> for Commits<K> {
+    fn name(&self) -> &'static str {
+        "commits"
+    }
+
+    fn observe(&mut self, o: &Observation<K>) {
+        self.ids
+            .entry(o.key.clone())
+            .or_default()
+            .insert(o.commit_id.to_string());
+        self.all.insert(o.commit_id.to_string());
+    }
+
+    fn value(&self, key: &K) -> usize {
+        self.ids.get(key).map(|c| c.len()).unwrap_or(0)
+    }
+
+    fn total(&self) -> usize {
+        self.all.len()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Blamed lines per key.
+#[derive(Clone)]
+pub struct Lines<K> {
+    lines: HashMap<K, usize>,
+}
+
+impl<K> Default for Lines<K> {
+    fn default() -> Lines<K> {
+        Lines {
+            lines: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Eq + Hash + Clone + 'static> Metric<K> for Lines<K> {
+    fn name(&self) -> &'static str {
+        "lines"
+    }
+
+    fn observe(&mut self, o: &Observation<K>) {
+        *self.lines.entry(o.key.clone()).or_default() += o.lines;
+    }
+
+    fn value(&self, key: &K) -> usize {
+        self.lines.get(key).copied().unwrap_or(0)
+    }
+
+    fn total(&self) -> usize {
+        self.lines.values().sum()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Distinct files per key.
+#[derive(Clone)]
+pub struct Files<K> {
+    files: HashMap<K, HashSet<String>>,
+    all: HashSet<String>,
+}
+
+impl<K: Eq + Hash> Files<K> {
+    pub fn names(&self, key: &K) -> Option<&HashSet<String>> {
+        self.files.get(key)
+    }
+}
+
+impl<K> Default for Files<K> {
+    fn default() -> Files<K> {
+        Files {
+            files: HashMap::new(),
+            all: HashSet::new(),
+        }
+    }
+}
+
+impl<K: Eq + Hash + Clone + 'static> Metric<K> for Files<K> {
+    fn name(&self) -> &'static str {
+        "files"
+    }
+
+    fn observe(&mut self, o: &Observation<K>) {
+        if let Some(f) = o.file {
+            self.files
+                .entry(o.key.clone())
+                .or_default()
+                .insert(f.to_string());
+            self.all.insert(f.to_string());
+        }
+    }
+
+    fn value(&self, key: &K) -> usize {
+        self.files.get(key).map(|f| f.len()).unwrap_or(0)
+    }
+
+    fn total(&self) -> usize {
+        self.all.len()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Distinct days with a commit per key.
+#[derive(Clone)]
+pub struct ActiveDays<K> {
+    days: HashMap<K, HashSet<NaiveDate>>,
+    all: HashSet<NaiveDate>,
+}
+
+impl<K: Eq + Hash> ActiveDays<K> {
+    pub fn days(&self, key: &K) -> Option<&HashSet<NaiveDate>> {
+        self.days.get(key)
+    }
+
+    pub fn all(&self) -> &HashSet<NaiveDate> {
+        &self.all
+    }
+}
+
+impl<K> Default for ActiveDays<K> {
+    fn default() -> ActiveDays<K> {
+        ActiveDays {
+            days: HashMap::new(),
+            all: HashSet::new(),
+        }
+    }
+}
+
+impl<K: Eq + Hash + Clone + 'static> Metric<K> for ActiveDays<K> {
+    fn name(&self) -> &'static str {
+        "active_days"
+    }
+
+    fn observe(&mut self, o: &Observation<K>) {
+        if let Some(day) = o.day {
+            self.days.entry(o.key.clone()).or_default().insert(day);
+            self.all.insert(day);
+        }
+    }
+
+    fn value(&self, key: &K) -> usize {
+        self.days.get(key).map(|d| d.len()).unwrap_or(0)
+    }
+
+    fn total(&self) -> usize {
+        self.all.len()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Lines added plus removed per key.
+#[derive(Clone)]
+pub struct Churn<K> {
+    churn: HashMap<K, usize>,
+}
+
+impl<K> Default for Churn<K> {
+    fn default() -> Churn<K> {
+        Churn {
+            churn: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Eq + Hash + Clone + 'static> Metric<K> for Churn<K> {
+    fn name(&self) -> &'static str {
+        "churn"
+    }
+
+    fn observe(&mut self, o: &Observation<K>) {
+        *self.churn.entry(o.key.clone()).or_default() += o.churn;
+    }
+
+    fn value(&self, key: &K) -> usize {
+        self.churn.get(key).copied().unwrap_or(0)
+    }
+
+    fn total(&self) -> usize {
+        self.churn.values().sum()
+    }
+
+    fn needs_diff(&self) -> bool {
+        true
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// The metrics fed by one walk, and the keys observed.
+pub struct Metrics<K> {
+    metrics: Vec<Box<dyn Metric<K>>>,
+    keys: HashSet<K>,
+}
+
+impl<K: Eq + Hash + Clone + 'static> Metrics<K> {
+    pub fn new() -> Metrics<K> {
+        Metrics {
+            metrics: Vec::new(),
+            keys: HashSet::new(),
+        }
+    }
+
+    pub fn with(mut self, metric: impl Metric<K>) -> Metrics<K> {
+        self.metrics.push(Box::new(metric));
+        self
+    }
+
+    pub fn observe(&mut self, o: &Observation<K>) {
+        self.metrics.iter_mut().for_each(|m| m.observe(o));
+
+        if !self.keys.contains(&o.key) {
+            self.keys.insert(o.key.clone());
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.keys.iter()
+    }
+
+    fn find(&self, name: &str) -> Option<&dyn Metric<K>> {
+        self.metrics
+            .iter()
+            .find(|m| m.name() == name)
+            .map(|m| m.as_ref())
+    }
+
+    /// the named metric for `key`, 0 when no such metric is registered
+    pub fn value(&self, name: &str, key: &K) -> usize {
+        self.find(name).map(|m| m.value(key)).unwrap_or(0)
+    }
+
+    /// the named metric over every key, 0 when no such metric is registered
+    pub fn total(&self, name: &str) -> usize {
+        self.find(name).map(|m| m.total()).unwrap_or(0)
+    }
+
+    /// the registered metric of type `M`, for the sets behind its values
+    pub fn get<M: Metric<K>>(&self) -> Option<&M> {
+        self.metrics
+            .iter()
+            .find_map(|m| m.as_any().downcast_ref::<M>())
+    }
+
+    fn needs_diff(&self) -> bool {
+        self.metrics.iter().any(|m| m.needs_diff())
+    }
+}
+
+/// walks every commit reachable from HEAD once, newest first, feeding each commit `key`
+/// gives a key to all the metrics.  Commits are only diffed against their first parent when
+/// a registered metric needs the churn.
+pub fn walk_commits<K, F>(repo: &Repository, metrics: &mut Metrics<K>, mut key: F) -> Result<()>
+where
+    K: Eq + Hash + Clone + 'static,
+    F: FnMut(&Commit) -> Result<Option<K>>,
+{
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(git2::Sort::TIME)?;
+    revwalk.push_head()?;
+
+    let diff = metrics.needs_diff();
+
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+
+        let k = match key(&commit)? {
+            Some(k) => k,
+            None => continue,
+        };
+
+        let commit_id = commit.id().to_string();
+        let mut o = Observation::new(k, &commit_id);
+        o.day = Some(grit_utils::convert_git_time(&commit.time()));
+
+        if diff {
+            let parent_tree = match commit.parent_count() {
+                0 => None,
+                _ => Some(commit.parent(0)?.tree()?),
+            };
+            o.churn = diff_stats::tree_diff_stats(
+                repo,
+                parent_tree.as_ref(),
+                &commit.tree()?,
+                None,
+                None,
+            )?
+            .lines();
+        }
+
+        metrics.observe(&o);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::LevelFilter;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    #[test]
+    fn test_walk_commits() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let repo = Repository::open(td.path()).unwrap();
+
+        let mut metrics: Metrics<String> = Metrics::new()
+            .with(Commits::default())
+            .with(ActiveDays::default())
+            .with(Churn::default());
+
+        walk_commits(&repo, &mut metrics, |c| {
+            Ok(c.author().name().map(String::from))
+        })
+        .unwrap();
+
+        let alice = String::from("Alice");
+        let bob = String::from("Bob");
+
+        assert_eq!(metrics.keys().count(), 2);
+        assert_eq!(metrics.value("commits", &alice), 2);
+        assert_eq!(metrics.value("active_days", &bob), 2);
+        assert_eq!(metrics.value("churn", &alice), 7);
+        assert_eq!(metrics.value("churn", &bob), 6);
+        assert_eq!(metrics.total("commits"), 4);
+        assert_eq!(metrics.total("active_days"), 3);
+        assert_eq!(metrics.value("lines", &alice), 0);
+        assert_eq!(
+            metrics
+                .get::<Commits<String>>()
+                .unwrap()
+                .ids(&bob)
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+}
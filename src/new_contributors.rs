@@ -0,0 +1,248 @@
+use super::Processable;
+use crate::identity;
+use crate::table::Table;
+use crate::utils::grit_utils;
+use crate::utils::grit_utils::OutputFormat;
+use anyhow::Result;
+use chrono::{Datelike, NaiveDate};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+pub struct NewContributorsArgs {
+    path: String,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+    file: Option<String>,
+    format: OutputFormat,
+}
+
+impl NewContributorsArgs {
+    pub fn new(
+        path: String,
+        start_date: Option<NaiveDate>,
+        end_date: Option<NaiveDate>,
+        file: Option<String>,
+        format: OutputFormat,
+    ) -> NewContributorsArgs {
+        NewContributorsArgs {
+            path,
+            start_date,
+            end_date,
+            file,
+            format,
+        }
+    }
+}
+
+/// The authors committing in a month, split by whether it was their first month.
+#[derive(Clone, Debug, PartialEq)]
+struct MonthOutput {
+    year: i32,
+    month: u32,
+    new: usize,
+    returning: usize,
+}
+
+impl MonthOutput {
+    fn active(&self) -> usize {
+        self.new + self.returning
+    }
+}
+
+pub struct NewContributors {
+    args: NewContributorsArgs,
+}
+
+impl NewContributors {
+    pub fn new(args: NewContributorsArgs) -> NewContributors {
+        NewContributors { args }
+    }
+
+    /// every month from the first to the last commit in the date range, oldest first.  An
+    /// author is new in the month of their earliest commit in the whole history, so the
+    /// start date doesn't make earlier contributors look new.
+    fn process_months(&self) -> Result<Vec<MonthOutput>> {
+        let repo = grit_utils::open_repo(&self.args.path)?;
+
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push_head()?;
+
+        let mut first_commit: HashMap<String, NaiveDate> = HashMap::new();
+        let mut active: BTreeMap<(i32, u32), HashSet<String>> = BTreeMap::new();
+
+        for oid in revwalk {
+            let commit = repo.find_commit(oid?)?;
+
+            let author = commit.author();
+            let name = String::from_utf8_lossy(author.name_bytes()).to_string();
+            let email = String::from_utf8_lossy(author.email_bytes()).to_string();
+
+            if identity::is_excluded(&name, &email) {
+                continue;
+            }
+
+            let key = identity::display_author(&name, &email);
+            let day = grit_utils::convert_git_time(&commit.time());
+
+            let first = first_commit.entry(key.clone()).or_insert(day);
+            if day < *first {
+                *first = day;
+            }
+
+            if self.args.start_date.map(|d| day < d).unwrap_or(false)
+                || self.args.end_date.map(|d| day > d).unwrap_or(false)
+            {
+                continue;
+            }
+
+            active
+                .entry((day.year(), day.month()))
+                .or_default()
+                .insert(key);
+        }
+
+        let (first_month, last_month) = match (active.keys().next(), active.keys().last()) {
+            (Some(f), Some(l)) => (*f, *l),
+            _ => return Ok(Vec::new()),
+        };
+
+        let mut output: Vec<MonthOutput> = Vec::new();
+        let (mut year, mut month) = first_month;
+
+        while (year, month) <= last_month {
+            let mut o = MonthOutput {
+                year,
+                month,
+                new: 0,
+                returning: 0,
+            };
+
+            for author in active.get(&(year, month)).into_iter().flatten() {
+                let first = first_commit[author];
+
+                if (first.year(), first.month()) == (year, month) {
+                    o.new += 1;
+                } else {
+                    o.returning += 1;
+                }
+            }
+
+            output.push(o);
+
+            if month == 12 {
+                year += 1;
+                month = 1;
+            } else {
+                month += 1;
+            }
+        }
+
+        Ok(output)
+    }
+
+    fn display_output(&self, output: &[MonthOutput]) -> Result<()> {
+        let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+
+        let mut table = Table::new();
+
+        table.set_titles(table_row!["month", "new", "returning", "active"]);
+
+        output.iter().for_each(|r| {
+            table.add_row(table_row![
+                format!("{}-{:0>2}", r.year, r.month),
+                r.new,
+                r.returning,
+                r.active()
+            ]);
+        });
+
+        table.add_label(0, "month");
+        table.add_metric(
+            1,
+            "grit_new_contributors",
+            "Authors making their first commit in the month.",
+        );
+        table.add_metric(
+            2,
+            "grit_returning_contributors",
+            "Authors committing in the month who had committed before.",
+        );
+
+        table.write(&mut w, self.args.format)?;
+
+        Ok(())
+    }
+}
+
+impl Processable<()> for NewContributors {
+    fn process(&self) -> Result<()> {
+        let output = self.process_months()?;
+
+        self.display_output(&output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use git2::{Repository, Signature, Time};
+    use log::LevelFilter;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    fn months(path: &str, start_date: Option<NaiveDate>) -> Vec<(i32, u32, usize, usize)> {
+        NewContributors::new(NewContributorsArgs::new(
+            path.to_string(),
+            start_date,
+            None,
+            None,
+            OutputFormat::Csv,
+        ))
+        .process_months()
+        .unwrap()
+        .into_iter()
+        .map(|m| (m.year, m.month, m.new, m.returning))
+        .collect()
+    }
+
+    #[test]
+    fn test_process_new_contributors() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let repo = Repository::open(td.path()).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+
+        // Bob returns and Carol joins in March 2020, after a quiet February
+        for (name, email, time) in [
+            ("Bob", "bob@example.com", 1_583_064_000), // 2020-03-01 12:00 UTC
+            ("Carol", "carol@example.com", 1_583_150_400), // 2020-03-02 12:00 UTC
+        ] {
+            let sig = Signature::new(name, email, &Time::new(time, 0)).unwrap();
+            let parent = repo.head().unwrap().peel_to_commit().unwrap();
+            repo.commit(
+                Some("HEAD"),
+                &sig,
+                &sig,
+                "later",
+                &head.tree().unwrap(),
+                &[&parent],
+            )
+            .unwrap();
+        }
+
+        let path = td.path().to_str().unwrap();
+
+        assert_eq!(
+            months(path, None),
+            vec![(2020, 1, 2, 0), (2020, 2, 0, 0), (2020, 3, 1, 1)]
+        );
+
+        // Bob's first commit is before the start date, so he is still returning
+        assert_eq!(
+            months(path, Some(NaiveDate::from_ymd_opt(2020, 3, 1).unwrap())),
+            vec![(2020, 3, 1, 1)]
+        );
+    }
+}
@@ -0,0 +1,510 @@
+use crate::table::Table;
+use crate::utils::grit_utils;
+use crate::utils::grit_utils::OutputFormat;
+use csv::Writer;
+use std::io;
+use std::io::Write;
+use std::sync::OnceLock;
+
+/// bumped whenever the shape of the json output changes
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// decimals of the percentages in every output unless `--precision` is given
+const DEFAULT_PRECISION: usize = 1;
+
+static ENVELOPE: OnceLock<Envelope> = OnceLock::new();
+static PRECISION: OnceLock<usize> = OnceLock::new();
+static NUMBER_LOCALE: OnceLock<NumberLocale> = OnceLock::new();
+
+/// Where json results came from, written around them so pipelines can detect format
+/// changes and trace a result back to the repository state and arguments behind it.
+#[derive(Clone, Debug, Default)]
+pub struct Envelope {
+    pub command: String,
+    pub parameters: Vec<(String, String)>,
+    pub head: Option<String>,
+    pub generated_at: String,
+}
+
+impl Envelope {
+    /// `results` as the "results" field of the envelope
+    pub fn wrap(&self, results: &str) -> String {
+        let parameters: Vec<String> = self
+            .parameters
+            .iter()
+            .map(|(k, v)| {
+                format!(
+                    "{}:{}",
+                    grit_utils::json_string(k),
+                    grit_utils::json_string(v)
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\"schema_version\":{},\"grit_version\":{},\"command\":{},\"parameters\":{{{}}},\"head\":{},\"generated_at\":{},\"results\":{}}}",
+            SCHEMA_VERSION,
+            grit_utils::json_string(env!("CARGO_PKG_VERSION")),
+            grit_utils::json_string(&self.command),
+            parameters.join(","),
+            self.head
+                .as_deref()
+                .map(grit_utils::json_string)
+                .unwrap_or_else(|| String::from("null")),
+            grit_utils::json_string(&self.generated_at),
+            results
+        )
+    }
+}
+
+/// sets the envelope json output is wrapped in for the rest of the run.  Only the first
+/// call has an effect.
+pub fn set_envelope(envelope: Envelope) {
+    let _ = ENVELOPE.set(envelope);
+}
+
+/// writes json `results`, inside the envelope when one is set
+pub fn write_json(out: &mut dyn Write, results: &str) -> io::Result<()> {
+    match ENVELOPE.get() {
+        Some(e) => writeln!(out, "{}", e.wrap(results)),
+        None => writeln!(out, "{}", results),
+    }
+}
+
+/// sets the decimals percentages are written with for the rest of the run.  Only the
+/// first call has an effect.
+pub fn set_precision(precision: usize) {
+    let _ = PRECISION.set(precision);
+}
+
+pub fn precision() -> usize {
+    *PRECISION.get().unwrap_or(&DEFAULT_PRECISION)
+}
+
+/// `value`, already scaled to 0-100, with the `--precision` decimals
+pub fn percent(value: f64) -> String {
+    format!("{:.*}", precision(), value)
+}
+
+/// The separators numbers are written with in table output.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct NumberLocale {
+    grouping: char,
+    decimal: char,
+}
+
+const ENGLISH_NUMBERS: NumberLocale = NumberLocale {
+    grouping: ',',
+    decimal: '.',
+};
+
+impl NumberLocale {
+    /// the separators of the first of LC_ALL, LC_NUMERIC and LANG that is set
+    fn from_env() -> NumberLocale {
+        ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .filter_map(|v| std::env::var(v).ok())
+            .find(|v| !v.is_empty())
+            .map(|v| NumberLocale::for_locale(&v))
+            .unwrap_or(ENGLISH_NUMBERS)
+    }
+
+    /// the separators used by the language of a locale such as `de_DE.UTF-8`.  English
+    /// ones for C, POSIX and languages not listed.
+    fn for_locale(locale: &str) -> NumberLocale {
+        let language = locale
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+
+        match language.as_str() {
+            "da" | "de" | "el" | "es" | "id" | "it" | "nl" | "pt" | "tr" => NumberLocale {
+                grouping: '.',
+                decimal: ',',
+            },
+            "cs" | "fi" | "fr" | "nb" | "pl" | "ru" | "sk" | "sv" | "uk" => NumberLocale {
+                grouping: ' ',
+                decimal: ',',
+            },
+            _ => ENGLISH_NUMBERS,
+        }
+    }
+
+    /// `cell` with its thousands grouped and the locale's decimal mark when it is a plain
+    /// number such as `-1234567.5`, and as is otherwise
+    fn format(&self, cell: &str) -> String {
+        if !is_number(cell) {
+            return cell.to_string();
+        }
+
+        let (sign, unsigned) = match cell.strip_prefix('-') {
+            Some(u) => ("-", u),
+            None => ("", cell),
+        };
+        let (whole, fraction) = match unsigned.split_once('.') {
+            Some((w, f)) => (w, Some(f)),
+            None => (unsigned, None),
+        };
+
+        let mut grouped = String::new();
+        for (i, c) in whole.chars().enumerate() {
+            if i > 0 && (whole.len() - i) % 3 == 0 {
+                grouped.push(self.grouping);
+            }
+            grouped.push(c);
+        }
+
+        match fraction {
+            Some(f) => format!("{}{}{}{}", sign, grouped, self.decimal, f),
+            None => format!("{}{}", sign, grouped),
+        }
+    }
+}
+
+/// true for an optionally negative run of digits with an optional fraction
+fn is_number(cell: &str) -> bool {
+    let unsigned = cell.strip_prefix('-').unwrap_or(cell);
+    let (whole, fraction) = match unsigned.split_once('.') {
+        Some((w, f)) => (w, Some(f)),
+        None => (unsigned, None),
+    };
+
+    !whole.is_empty()
+        && whole.chars().all(|c| c.is_ascii_digit())
+        && fraction.is_none_or(|f| !f.is_empty() && f.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// `value` as table output writes it, thousands grouped the way the locale does
+pub fn table_number(value: &str) -> String {
+    NUMBER_LOCALE
+        .get_or_init(NumberLocale::from_env)
+        .format(value)
+}
+
+/// A sink writing a command's results table in one output format.  Commands only build
+/// a `Table`, so a new format is a new sink here.
+pub trait OutputWriter {
+    fn write(&self, table: &Table, out: &mut dyn Write) -> io::Result<()>;
+}
+
+/// the sink for `format`
+pub fn writer(format: OutputFormat) -> Box<dyn OutputWriter> {
+    match format {
+        OutputFormat::Table => Box::new(TableWriter),
+        OutputFormat::Csv => Box::new(CsvWriter),
+        OutputFormat::Json => Box::new(JsonWriter),
+        OutputFormat::Markdown => Box::new(MarkdownWriter),
+        OutputFormat::Prom => Box::new(PromWriter),
+    }
+}
+
+/// plain text table with a title separator and no line separators between rows
+pub struct TableWriter;
+
+impl TableWriter {
+    fn write_separator(out: &mut dyn Write, widths: &[usize]) -> io::Result<()> {
+        let line: Vec<String> = widths.iter().map(|w| "-".repeat(w + 2)).collect();
+        writeln!(out, "+{}+", line.join("+"))
+    }
+
+    fn write_row(out: &mut dyn Write, widths: &[usize], row: &[String]) -> io::Result<()> {
+        let cells: Vec<String> = widths
+            .iter()
+            .enumerate()
+            .map(|(i, w)| {
+                let cell = row.get(i).map(|c| c.as_str()).unwrap_or("");
+                format!(" {:<width$} ", cell, width = w)
+            })
+            .collect();
+        writeln!(out, "|{}|", cells.join("|"))
+    }
+}
+
+impl TableWriter {
+    /// the table with the numbers of its numeric columns written for the locale.  Label
+    /// columns, such as authors and commit ids, and columns holding anything but numbers
+    /// are left as they are.
+    fn localized(table: &Table) -> Table {
+        let columns = table.column_widths().len();
+        let numeric: Vec<bool> = (0..columns)
+            .map(|c| {
+                !table.labels().iter().any(|(l, _)| *l == c)
+                    && table
+                        .rows()
+                        .iter()
+                        .filter_map(|r| r.get(c))
+                        .all(|v| v.is_empty() || is_number(v))
+            })
+            .collect();
+
+        let mut localized = Table::new();
+        localized.set_titles(table.titles().to_vec());
+
+        for row in table.rows().iter() {
+            localized.add_row(
+                row.iter()
+                    .enumerate()
+                    .map(|(c, v)| {
+                        if numeric[c] {
+                            table_number(v)
+                        } else {
+                            v.clone()
+                        }
+                    })
+                    .collect(),
+            );
+        }
+
+        localized
+    }
+}
+
+impl OutputWriter for TableWriter {
+    fn write(&self, table: &Table, out: &mut dyn Write) -> io::Result<()> {
+        let table = TableWriter::localized(table);
+        let widths = table.column_widths();
+
+        if widths.is_empty() {
+            return Ok(());
+        }
+
+        TableWriter::write_separator(out, &widths)?;
+        if !table.titles().is_empty() {
+            TableWriter::write_row(out, &widths, table.titles())?;
+            TableWriter::write_separator(out, &widths)?;
+        }
+        for row in table.rows().iter() {
+            TableWriter::write_row(out, &widths, row)?;
+        }
+        TableWriter::write_separator(out, &widths)?;
+
+        Ok(())
+    }
+}
+
+pub struct CsvWriter;
+
+impl OutputWriter for CsvWriter {
+    fn write(&self, table: &Table, out: &mut dyn Write) -> io::Result<()> {
+        let mut wrt = Writer::from_writer(out);
+
+        if !table.titles().is_empty() {
+            wrt.write_record(table.titles())?;
+        }
+        for row in table.rows().iter() {
+            wrt.write_record(row)?;
+        }
+        wrt.flush()
+    }
+}
+
+/// the rows as an array of objects keyed by the titles, numeric cells as JSON numbers
+pub struct JsonWriter;
+
+impl JsonWriter {
+    /// a number unless JSON would reject it, as it does leading zeros like a zip code's
+    fn cell(value: &str) -> String {
+        let unsigned = value.strip_prefix('-').unwrap_or(value);
+
+        let leading_zero =
+            unsigned.starts_with('0') && unsigned.len() > 1 && !unsigned.starts_with("0.");
+
+        if is_number(value) && !leading_zero {
+            value.to_string()
+        } else {
+            grit_utils::json_string(value)
+        }
+    }
+}
+
+impl OutputWriter for JsonWriter {
+    fn write(&self, table: &Table, out: &mut dyn Write) -> io::Result<()> {
+        let rows: Vec<String> = table
+            .rows()
+            .iter()
+            .map(|row| {
+                let fields: Vec<String> = table
+                    .titles()
+                    .iter()
+                    .zip(row.iter())
+                    .map(|(t, c)| format!("{}:{}", grit_utils::json_string(t), JsonWriter::cell(c)))
+                    .collect();
+                format!("{{{}}}", fields.join(","))
+            })
+            .collect();
+
+        write_json(out, &format!("[{}]", rows.join(",")))
+    }
+}
+
+/// a GitHub flavored markdown table, for pasting into issues and wikis
+pub struct MarkdownWriter;
+
+impl MarkdownWriter {
+    fn cell(value: &str) -> String {
+        value.replace('|', "\\|").replace('\n', " ")
+    }
+
+    fn write_row(out: &mut dyn Write, columns: usize, row: &[String]) -> io::Result<()> {
+        let cells: Vec<String> = (0..columns)
+            .map(|i| MarkdownWriter::cell(row.get(i).map(|c| c.as_str()).unwrap_or("")))
+            .collect();
+        writeln!(out, "| {} |", cells.join(" | "))
+    }
+}
+
+impl OutputWriter for MarkdownWriter {
+    fn write(&self, table: &Table, out: &mut dyn Write) -> io::Result<()> {
+        let columns = table.column_widths().len();
+
+        if columns == 0 {
+            return Ok(());
+        }
+
+        MarkdownWriter::write_row(out, columns, table.titles())?;
+        writeln!(out, "|{}", " --- |".repeat(columns))?;
+        for row in table.rows().iter() {
+            MarkdownWriter::write_row(out, columns, row)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// the metric columns in the Prometheus text format, one sample per row.  Cells that are
+/// not numbers are left out.
+pub struct PromWriter;
+
+impl PromWriter {
+    fn label_value(value: &str) -> String {
+        value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    }
+}
+
+impl OutputWriter for PromWriter {
+    fn write(&self, table: &Table, out: &mut dyn Write) -> io::Result<()> {
+        if table.metrics().is_empty() {
+            return Err(io::Error::other(
+                "prom output is not supported by this command",
+            ));
+        }
+
+        for m in table.metrics().iter() {
+            writeln!(out, "# HELP {} {}", m.name, m.help)?;
+            writeln!(out, "# TYPE {} gauge", m.name)?;
+
+            for row in table.rows().iter() {
+                let value = match row.get(m.column).and_then(|v| v.parse::<f64>().ok()) {
+                    Some(v) => v,
+                    None => continue,
+                };
+
+                let labels: Vec<String> = table
+                    .labels()
+                    .iter()
+                    .map(|(c, name)| {
+                        format!(
+                            "{}=\"{}\"",
+                            name,
+                            PromWriter::label_value(row.get(*c).map(|v| v.as_str()).unwrap_or(""))
+                        )
+                    })
+                    .collect();
+
+                if labels.is_empty() {
+                    writeln!(out, "{} {}", m.name, value)?;
+                } else {
+                    writeln!(out, "{}{{{}}} {}", m.name, labels.join(","), value)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_writer() {
+        let mut table = Table::new();
+        table.set_titles(table_row!["Author", "LOC"]);
+        table.add_row(table_row!["Todd | Bush", 948]);
+        table.add_row(table_row!["todd-bush"]);
+
+        let mut out: Vec<u8> = Vec::new();
+        writer(OutputFormat::Markdown)
+            .write(&table, &mut out)
+            .unwrap();
+
+        let expected = "\
+| Author | LOC |
+| --- | --- |
+| Todd \\| Bush | 948 |
+| todd-bush |  |
+";
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_json_writer_numbers() {
+        let mut table = Table::new();
+        table.set_titles(table_row!["Author", "LOC", "Share", "Zip", "Delta"]);
+        table.add_row(table_row!["1234", 948, "12.5", "02134", "-3"]);
+        table.add_row(table_row!["todd-bush", "1,024", "12.5%", "0", ""]);
+
+        let mut out: Vec<u8> = Vec::new();
+        writer(OutputFormat::Json).write(&table, &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "[{\"Author\":1234,\"LOC\":948,\"Share\":12.5,\"Zip\":\"02134\",\"Delta\":-3},\
+             {\"Author\":\"todd-bush\",\"LOC\":\"1,024\",\"Share\":\"12.5%\",\"Zip\":0,\"Delta\":\"\"}]\n"
+        );
+    }
+
+    #[test]
+    fn test_number_locale() {
+        let german = NumberLocale::for_locale("de_DE.UTF-8");
+
+        assert_eq!(NumberLocale::for_locale("C.UTF-8"), ENGLISH_NUMBERS);
+        assert_eq!(ENGLISH_NUMBERS.format("1234567"), "1,234,567");
+        assert_eq!(ENGLISH_NUMBERS.format("-1234.56"), "-1,234.56");
+        assert_eq!(ENGLISH_NUMBERS.format("123"), "123");
+        assert_eq!(german.format("1234567.5"), "1.234.567,5");
+        assert_eq!(NumberLocale::for_locale("fr_FR").format("12345"), "12 345");
+        assert_eq!(ENGLISH_NUMBERS.format("2020-01-05"), "2020-01-05");
+        assert_eq!(ENGLISH_NUMBERS.format("1."), "1.");
+    }
+
+    #[test]
+    fn test_envelope_wrap() {
+        let envelope = Envelope {
+            command: String::from("fame"),
+            parameters: vec![
+                (String::from("repo"), String::from(".")),
+                (String::from("sort"), String::from("loc")),
+            ],
+            head: Some(String::from("abc123")),
+            generated_at: String::from("2020-01-01T00:00:00+00:00"),
+        };
+
+        let expected = format!(
+            "{{\"schema_version\":1,\"grit_version\":\"{}\",\"command\":\"fame\",\"parameters\":{{\"repo\":\".\",\"sort\":\"loc\"}},\"head\":\"abc123\",\"generated_at\":\"2020-01-01T00:00:00+00:00\",\"results\":[]}}",
+            env!("CARGO_PKG_VERSION")
+        );
+        assert_eq!(envelope.wrap("[]"), expected);
+
+        let detached = Envelope {
+            head: None,
+            ..envelope
+        };
+        assert!(detached.wrap("[]").contains("\"head\":null"));
+    }
+}
@@ -0,0 +1,454 @@
+use super::Processable;
+use crate::error::GritError;
+use crate::fame::blame_files;
+use crate::identity;
+use crate::output;
+use crate::table::Table;
+use crate::utils::grit_utils;
+use crate::utils::grit_utils::OutputFormat;
+use anyhow::{anyhow, Result};
+use glob::{MatchOptions, Pattern};
+use std::collections::HashMap;
+use std::fs;
+
+/// where GitHub looks for the CODEOWNERS file, in the order it looks
+const CODEOWNERS_PATHS: [&str; 3] = [".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// `*` stops at a `/`, as in .gitignore
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+pub struct OwnersAuditArgs {
+    path: String,
+    codeowners: Option<String>,
+    threshold: f64,
+    include: Option<String>,
+    exclude: Option<String>,
+    file: Option<String>,
+    format: OutputFormat,
+    strict: bool,
+}
+
+impl OwnersAuditArgs {
+    pub fn builder(path: String) -> OwnersAuditArgsBuilder {
+        OwnersAuditArgsBuilder {
+            args: OwnersAuditArgs {
+                path,
+                codeowners: None,
+                threshold: 10.0,
+                include: None,
+                exclude: None,
+                file: None,
+                format: OutputFormat::Csv,
+                strict: false,
+            },
+        }
+    }
+}
+
+/// Builds a `OwnersAuditArgs` by name rather than position, validating it on `build`.
+pub struct OwnersAuditArgsBuilder {
+    args: OwnersAuditArgs,
+}
+
+impl OwnersAuditArgsBuilder {
+    pub fn codeowners(mut self, codeowners: Option<String>) -> OwnersAuditArgsBuilder {
+        self.args.codeowners = codeowners;
+        self
+    }
+
+    pub fn threshold(mut self, threshold: f64) -> OwnersAuditArgsBuilder {
+        self.args.threshold = threshold;
+        self
+    }
+
+    pub fn include(mut self, include: Option<String>) -> OwnersAuditArgsBuilder {
+        self.args.include = include;
+        self
+    }
+
+    pub fn exclude(mut self, exclude: Option<String>) -> OwnersAuditArgsBuilder {
+        self.args.exclude = exclude;
+        self
+    }
+
+    pub fn file(mut self, file: Option<String>) -> OwnersAuditArgsBuilder {
+        self.args.file = file;
+        self
+    }
+
+    pub fn format(mut self, format: OutputFormat) -> OwnersAuditArgsBuilder {
+        self.args.format = format;
+        self
+    }
+
+    pub fn strict(mut self, strict: bool) -> OwnersAuditArgsBuilder {
+        self.args.strict = strict;
+        self
+    }
+
+    pub fn build(self) -> Result<OwnersAuditArgs> {
+        grit_utils::check_output_file(self.args.file.as_deref(), self.args.format)?;
+
+        Ok(self.args)
+    }
+}
+
+/// A CODEOWNERS line: the files matching the pattern and the owners declared for them.
+#[derive(Debug)]
+struct OwnersRule {
+    line: usize,
+    pattern: String,
+    owners: Vec<String>,
+    /// the pattern, and the pattern with everything below it for directories
+    globs: Vec<Pattern>,
+}
+
+impl OwnersRule {
+    /// the globs matching what a .gitignore style CODEOWNERS pattern does.  A pattern
+    /// without a `/` but at its end matches at any depth, and a pattern matching a
+    /// directory matches everything below it.
+    fn globs(pattern: &str) -> Result<Vec<Pattern>> {
+        let trimmed = pattern.trim_end_matches('/');
+        let anchored = trimmed.contains('/');
+        let trimmed = trimmed.trim_start_matches('/');
+
+        let glob = if anchored {
+            trimmed.to_string()
+        } else {
+            format!("**/{}", trimmed)
+        };
+
+        [glob.clone(), format!("{}/**", glob)]
+            .iter()
+            .map(|g| {
+                Pattern::new(g).map_err(|e| {
+                    GritError::InvalidPattern {
+                        pattern: pattern.to_string(),
+                        message: e.msg.to_string(),
+                    }
+                    .into()
+                })
+            })
+            .collect()
+    }
+
+    fn matches(&self, file: &str) -> bool {
+        self.globs
+            .iter()
+            .any(|g| g.matches_with(file, MATCH_OPTIONS))
+    }
+}
+
+/// the rules of a CODEOWNERS file, in file order.  Blank lines and `#` comments are
+/// skipped, and a pattern without owners is kept, as it unsets the owners of its files.
+fn parse_codeowners(content: &str) -> Result<Vec<OwnersRule>> {
+    let mut rules = Vec::new();
+
+    for (i, line) in content.lines().enumerate() {
+        let line = match line.find(" #") {
+            Some(c) => &line[..c],
+            None => line,
+        }
+        .trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut parts = line.split_whitespace();
+        let pattern = parts.next().unwrap_or_default().to_string();
+
+        rules.push(OwnersRule {
+            line: i + 1,
+            globs: OwnersRule::globs(&pattern)
+                .map_err(|e| anyhow!("CODEOWNERS line {}: {}", i + 1, e))?,
+            pattern,
+            owners: parts.map(String::from).collect(),
+        });
+    }
+
+    Ok(rules)
+}
+
+/// true when the author is one of the declared owner: an email, a `@user` matched by
+/// name, email or GitHub noreply address, or an `@org/team` matched against the team of
+/// the author in the `--teams` file, with or without the org
+fn is_owner(owner: &str, name: &str, email: &str) -> bool {
+    let handle = match owner.strip_prefix('@') {
+        Some(h) => h,
+        None => return identity::is_author(owner, name, email),
+    };
+
+    if let Some((_, team)) = handle.split_once('/') {
+        let author_team = identity::team_of(name, email);
+        return author_team.eq_ignore_ascii_case(handle) || author_team.eq_ignore_ascii_case(team);
+    }
+
+    let local = email.split('@').next().unwrap_or_default();
+    let noreply = local.rsplit('+').next().unwrap_or_default();
+
+    identity::is_author(handle, name, email)
+        || (email.ends_with("@users.noreply.github.com") && noreply.eq_ignore_ascii_case(handle))
+        || local.eq_ignore_ascii_case(handle)
+}
+
+/// The lines blamed in the files of a CODEOWNERS rule, and those by its owners.
+#[derive(Debug, Default, PartialEq)]
+struct AuditOutput {
+    files: usize,
+    lines: i32,
+    owner_lines: i32,
+}
+
+impl AuditOutput {
+    /// the owners' share of the lines, 0 for rules matching no lines
+    fn owner_share(&self) -> f64 {
+        if self.lines == 0 {
+            0.0
+        } else {
+            self.owner_lines as f64 / self.lines as f64
+        }
+    }
+}
+
+pub struct OwnersAudit {
+    args: OwnersAuditArgs,
+}
+
+impl OwnersAudit {
+    pub fn new(args: OwnersAuditArgs) -> OwnersAudit {
+        OwnersAudit { args }
+    }
+
+    /// the CODEOWNERS given, or the first found in the tree of HEAD where GitHub looks
+    fn codeowners(&self) -> Result<String> {
+        if let Some(f) = &self.args.codeowners {
+            return fs::read_to_string(f).map_err(|e| anyhow!("cannot read {}: {}", f, e));
+        }
+
+        let repo = grit_utils::open_repo(&self.args.path)?;
+        let tree = repo.head()?.peel_to_tree()?;
+
+        for p in CODEOWNERS_PATHS.iter() {
+            if let Ok(entry) = tree.get_path(std::path::Path::new(p)) {
+                let blob = entry.to_object(&repo)?.peel_to_blob()?;
+                return Ok(String::from_utf8_lossy(blob.content()).to_string());
+            }
+        }
+
+        Err(anyhow!(
+            "no CODEOWNERS file in {}, looked for {}",
+            self.args.path,
+            CODEOWNERS_PATHS.join(", ")
+        ))
+    }
+
+    /// the blamed lines of the files each rule owns, in CODEOWNERS order.  A file belongs
+    /// to the last rule matching it, as on GitHub.
+    fn audit(&self, rules: &[OwnersRule]) -> Result<Vec<AuditOutput>> {
+        let file_names = grit_utils::generate_file_list(
+            &self.args.path,
+            self.args.include.clone(),
+            self.args.exclude.clone(),
+        )?;
+
+        let owned: Vec<(String, usize)> = file_names
+            .into_iter()
+            .filter_map(|f| {
+                let rule = rules.iter().rposition(|r| r.matches(&f))?;
+                Some((f, rule))
+            })
+            .collect();
+
+        let rule_of: HashMap<&str, usize> = owned.iter().map(|(f, r)| (f.as_str(), *r)).collect();
+        let blamed: Vec<String> = owned.iter().map(|(f, _)| f.clone()).collect();
+
+        let (collector, skipped) = blame_files(&self.args.path, &blamed, None, None)?;
+
+        grit_utils::report_skipped(&skipped, self.args.strict)?;
+
+        let mut output: Vec<AuditOutput> = rules.iter().map(|_| AuditOutput::default()).collect();
+
+        for (_, r) in owned.iter() {
+            output[*r].files += 1;
+        }
+
+        for b in collector.iter().flatten() {
+            if identity::is_excluded(&b.author, &b.email) {
+                continue;
+            }
+
+            let o = &mut output[rule_of[b.file_name.as_str()]];
+            o.lines += b.lines;
+
+            if rules[rule_of[b.file_name.as_str()]]
+                .owners
+                .iter()
+                .any(|owner| is_owner(owner, &b.author, &b.email))
+            {
+                o.owner_lines += b.lines;
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// flagged when the owners wrote less than the threshold of the lines, or the rule
+    /// owns no lines at all
+    fn flagged(&self, o: &AuditOutput) -> bool {
+        o.lines == 0 || o.owner_share() * 100.0 < self.args.threshold
+    }
+
+    fn audit_table(&self, rules: &[OwnersRule], output: &[AuditOutput]) -> Table {
+        let mut table = Table::new();
+
+        if self.args.format == OutputFormat::Table {
+            table.set_titles(table_row![
+                "Line",
+                "Pattern",
+                "Owners",
+                "Files",
+                "LOC",
+                "Owner LOC",
+                "Owner Share (%)",
+                "Flagged"
+            ]);
+        } else {
+            table.set_titles(table_row![
+                "line",
+                "pattern",
+                "owners",
+                "files",
+                "loc",
+                "owner loc",
+                "owner share (%)",
+                "flagged"
+            ]);
+        }
+
+        for (r, o) in rules.iter().zip(output.iter()) {
+            table.add_row(table_row![
+                r.line,
+                r.pattern,
+                r.owners.join(" "),
+                o.files,
+                o.lines,
+                o.owner_lines,
+                output::percent(o.owner_share() * 100.0),
+                self.flagged(o)
+            ]);
+        }
+
+        table.add_label(1, "pattern");
+        table.add_metric(
+            4,
+            "grit_codeowners_loc",
+            "Lines in the files of the pattern.",
+        );
+        table.add_metric(
+            6,
+            "grit_codeowners_owner_share_percent",
+            "Percent of the lines of the pattern blamed to its owners.",
+        );
+
+        table
+    }
+}
+
+impl Processable<()> for OwnersAudit {
+    fn process(&self) -> Result<()> {
+        let rules = parse_codeowners(&self.codeowners()?)?;
+        let output = self.audit(&rules)?;
+
+        let flagged = output.iter().filter(|o| self.flagged(o)).count();
+        if flagged > 0 {
+            info!(
+                "{} CODEOWNERS pattern(s) under {}% of their lines by their owners",
+                flagged, self.args.threshold
+            );
+        }
+
+        let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+        self.audit_table(&rules, &output)
+            .write(&mut w, self.args.format)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::LevelFilter;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    #[test]
+    fn test_parse_codeowners() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let rules = parse_codeowners(
+            "# owners\n*.md @docs\n\n/src/ @alice bob@example.com # core\nlib.rs\ndocs/*.md @writers\n",
+        )
+        .unwrap();
+
+        assert_eq!(rules.len(), 4);
+        assert_eq!(rules[1].line, 4);
+        assert_eq!(rules[1].owners, vec!["@alice", "bob@example.com"]);
+        assert!(rules[2].owners.is_empty());
+
+        assert!(rules[0].matches("README.md"));
+        assert!(rules[0].matches("docs/guide/intro.md"));
+        assert!(rules[1].matches("src/main.rs"));
+        assert!(rules[1].matches("src/a/b.rs"));
+        assert!(!rules[1].matches("lib/src/main.rs"));
+        assert!(rules[2].matches("src/lib.rs"));
+        assert!(rules[3].matches("docs/a.md"));
+        assert!(!rules[3].matches("docs/guide/a.md"));
+    }
+
+    #[test]
+    fn test_is_owner() {
+        assert!(is_owner("@alice", "Alice", "alice@example.com"));
+        assert!(is_owner(
+            "@tbush",
+            "Todd",
+            "1234+tbush@users.noreply.github.com"
+        ));
+        assert!(is_owner("BOB@example.com", "Bob", "bob@example.com"));
+        assert!(!is_owner("@alice", "Bob", "bob@example.com"));
+    }
+
+    #[test]
+    fn test_owners_audit() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let audit = OwnersAudit::new(
+            OwnersAuditArgs::builder(String::from(path))
+                .threshold(50.0)
+                .build()
+                .unwrap(),
+        );
+        let rules = parse_codeowners("* @bob\n/src/ @alice\n*.txt @carol\n").unwrap();
+        let output = audit.audit(&rules).unwrap();
+
+        // README.md is Bob's and Alice's, main.rs all Alice's and lib.rs all Bob's
+        let result: Vec<(usize, i32, i32, bool)> = output
+            .iter()
+            .map(|o| (o.files, o.lines, o.owner_lines, audit.flagged(o)))
+            .collect();
+
+        assert_eq!(
+            result,
+            vec![(1, 2, 1, false), (2, 9, 5, false), (0, 0, 0, true)]
+        );
+    }
+}
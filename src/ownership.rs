@@ -0,0 +1,615 @@
+use super::Processable;
+use crate::chart::ChartOptions;
+use crate::fame::{blame_files, BlameOutput};
+use crate::identity;
+use crate::output;
+use crate::table::Table;
+use crate::treemap::{self, TreemapNode};
+use crate::utils::grit_utils;
+use crate::utils::grit_utils::OutputFormat;
+use anyhow::{anyhow, Result};
+use chrono::{Datelike, Duration, NaiveDate};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SnapshotPeriod {
+    Daily,
+    Weekly,
+    Monthly,
+    Quarterly,
+    Yearly,
+}
+
+impl FromStr for SnapshotPeriod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "daily" => Ok(SnapshotPeriod::Daily),
+            "weekly" => Ok(SnapshotPeriod::Weekly),
+            "monthly" => Ok(SnapshotPeriod::Monthly),
+            "quarterly" => Ok(SnapshotPeriod::Quarterly),
+            "yearly" => Ok(SnapshotPeriod::Yearly),
+            _ => Err(anyhow!(
+                "period must be either 'daily', 'weekly', 'monthly', 'quarterly' or 'yearly'"
+            )),
+        }
+    }
+}
+
+impl SnapshotPeriod {
+    fn first_of_month(year: i32, month: u32) -> NaiveDate {
+        if month > 12 {
+            NaiveDate::from_ymd_opt(year + 1, month - 12, 1)
+        } else {
+            NaiveDate::from_ymd_opt(year, month, 1)
+        }
+        .expect("the first of a month is a valid date")
+    }
+
+    /// last day of the period containing `d`
+    pub(crate) fn period_end(&self, d: NaiveDate) -> NaiveDate {
+        let next_start = match self {
+            SnapshotPeriod::Daily => d + Duration::days(1),
+            SnapshotPeriod::Weekly => {
+                d + Duration::days(7 - grit_utils::days_into_week(d.weekday()))
+            }
+            SnapshotPeriod::Monthly => SnapshotPeriod::first_of_month(d.year(), d.month() + 1),
+            SnapshotPeriod::Quarterly => {
+                SnapshotPeriod::first_of_month(d.year(), (d.month0() / 3) * 3 + 4)
+            }
+            SnapshotPeriod::Yearly => NaiveDate::from_ymd_opt(d.year() + 1, 1, 1)
+                .expect("the first of a year is a valid date"),
+        };
+
+        next_start - Duration::days(1)
+    }
+
+    pub(crate) fn label(&self, d: NaiveDate) -> String {
+        match self {
+            SnapshotPeriod::Daily => grit_utils::format_date(d),
+            SnapshotPeriod::Weekly => {
+                format!("{}-W{:0>2}", d.iso_week().year(), d.iso_week().week())
+            }
+            SnapshotPeriod::Monthly => format!("{}-{:0>2}", d.year(), d.month()),
+            SnapshotPeriod::Quarterly => format!("{}-Q{}", d.year(), d.month0() / 3 + 1),
+            SnapshotPeriod::Yearly => format!("{}", d.year()),
+        }
+    }
+
+    /// the end date of every period from `start` to `end`, the last one capped at `end`
+    pub(crate) fn period_ends(&self, start: NaiveDate, end: NaiveDate) -> Vec<NaiveDate> {
+        let mut result: Vec<NaiveDate> = Vec::new();
+        let mut d = start;
+
+        while d <= end {
+            let pe = self.period_end(d);
+            result.push(if pe > end { end } else { pe });
+            d = pe + Duration::days(1);
+        }
+
+        result
+    }
+}
+
+pub struct OwnershipArgs {
+    path: String,
+    snapshots: SnapshotPeriod,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+    include: Option<String>,
+    exclude: Option<String>,
+    depth: usize,
+    treemap: bool,
+    file: Option<String>,
+    image: bool,
+    html: bool,
+    chart_options: ChartOptions,
+    format: OutputFormat,
+}
+
+impl OwnershipArgs {
+    pub fn builder(path: String) -> OwnershipArgsBuilder {
+        OwnershipArgsBuilder {
+            args: OwnershipArgs {
+                path,
+                snapshots: SnapshotPeriod::Quarterly,
+                start_date: None,
+                end_date: None,
+                include: None,
+                exclude: None,
+                depth: 1,
+                treemap: false,
+                file: None,
+                image: false,
+                html: false,
+                chart_options: ChartOptions::default(),
+                format: OutputFormat::Csv,
+            },
+        }
+    }
+}
+
+/// Builds a `OwnershipArgs` by name rather than position, validating it on `build`.
+pub struct OwnershipArgsBuilder {
+    args: OwnershipArgs,
+}
+
+impl OwnershipArgsBuilder {
+    pub fn snapshots(mut self, snapshots: SnapshotPeriod) -> OwnershipArgsBuilder {
+        self.args.snapshots = snapshots;
+        self
+    }
+
+    pub fn start_date(mut self, start_date: Option<NaiveDate>) -> OwnershipArgsBuilder {
+        self.args.start_date = start_date;
+        self
+    }
+
+    pub fn end_date(mut self, end_date: Option<NaiveDate>) -> OwnershipArgsBuilder {
+        self.args.end_date = end_date;
+        self
+    }
+
+    pub fn include(mut self, include: Option<String>) -> OwnershipArgsBuilder {
+        self.args.include = include;
+        self
+    }
+
+    pub fn exclude(mut self, exclude: Option<String>) -> OwnershipArgsBuilder {
+        self.args.exclude = exclude;
+        self
+    }
+
+    pub fn depth(mut self, depth: usize) -> OwnershipArgsBuilder {
+        self.args.depth = depth;
+        self
+    }
+
+    pub fn treemap(mut self, treemap: bool) -> OwnershipArgsBuilder {
+        self.args.treemap = treemap;
+        self
+    }
+
+    pub fn file(mut self, file: Option<String>) -> OwnershipArgsBuilder {
+        self.args.file = file;
+        self
+    }
+
+    pub fn image(mut self, image: bool) -> OwnershipArgsBuilder {
+        self.args.image = image;
+        self
+    }
+
+    pub fn html(mut self, html: bool) -> OwnershipArgsBuilder {
+        self.args.html = html;
+        self
+    }
+
+    pub fn chart_options(mut self, chart_options: ChartOptions) -> OwnershipArgsBuilder {
+        self.args.chart_options = chart_options;
+        self
+    }
+
+    pub fn format(mut self, format: OutputFormat) -> OwnershipArgsBuilder {
+        self.args.format = format;
+        self
+    }
+
+    pub fn build(self) -> Result<OwnershipArgs> {
+        grit_utils::validate_args(
+            self.args.start_date,
+            self.args.end_date,
+            self.args.file.as_deref(),
+            self.args.format,
+        )?;
+
+        Ok(self.args)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct OwnershipOutput {
+    snapshot: String,
+    date: NaiveDate,
+    commit_id: String,
+    directory: String,
+    author: String,
+    lines: i32,
+    share: f64,
+}
+
+pub struct Ownership {
+    args: OwnershipArgs,
+}
+
+impl Ownership {
+    pub fn new(args: OwnershipArgs) -> Ownership {
+        Ownership { args }
+    }
+
+    fn first_commit_date(&self) -> Result<NaiveDate> {
+        let repo = grit_utils::open_repo(&self.args.path)?;
+        let mut revwalk = repo.revwalk()?;
+        revwalk.set_sorting(git2::Sort::REVERSE | git2::Sort::TIME)?;
+        revwalk.push_head()?;
+
+        let oid = revwalk
+            .next()
+            .ok_or_else(|| anyhow!("repository has no commits"))??;
+        let commit = repo.find_commit(oid)?;
+
+        Ok(grit_utils::convert_git_time(&commit.time()))
+    }
+
+    /// the id of the latest commit as of `day` and the blame of every file in it, None when
+    /// the repo had no commits yet
+    fn blame_at(&self, day: NaiveDate) -> Result<Option<(String, Vec<BlameOutput>)>> {
+        let range = grit_utils::find_commit_range(&self.args.path, None, Some(day))?;
+
+        let commit = match range.latest {
+            Some(c) => c,
+            None => {
+                info!("no commits as of {}", grit_utils::format_date(day));
+                return Ok(None);
+            }
+        };
+
+        let file_names = grit_utils::generate_file_list_at(
+            &self.args.path,
+            &commit,
+            self.args.include.clone(),
+            self.args.exclude.clone(),
+        )?;
+
+        let (collector, skipped) =
+            blame_files(&self.args.path, &file_names, None, Some(commit.clone()))?;
+
+        grit_utils::report_skipped(&skipped, false)?;
+
+        let commit_id = git2::Oid::from_bytes(&commit)?.to_string();
+
+        Ok(Some((commit_id, collector.into_iter().flatten().collect())))
+    }
+
+    /// the directory tree of the repo as of the end date, every node holding the lines
+    /// each author owns beneath it
+    fn process_treemap(&self) -> Result<TreemapNode> {
+        let end_date = self.args.end_date.unwrap_or_else(grit_utils::today);
+
+        let file_lines: Vec<(String, String, i32)> = self
+            .blame_at(end_date)?
+            .map(|(_, b)| b)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|b| !identity::is_excluded(&b.author, &b.email))
+            .map(|b| {
+                let author = identity::display_author(&b.author, &b.email);
+                (b.file_name, author, b.lines)
+            })
+            .collect();
+
+        let name = std::fs::canonicalize(&self.args.path)
+            .ok()
+            .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+            .unwrap_or_else(|| self.args.path.clone());
+
+        Ok(TreemapNode::build(&name, &file_lines))
+    }
+
+    fn display_treemap(&self, root: &TreemapNode) -> Result<()> {
+        let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+
+        writeln!(w, "{}", root.to_json())?;
+
+        Ok(())
+    }
+
+    fn create_treemap_image(&self, root: &TreemapNode) -> Result<()> {
+        let file = self
+            .args
+            .file
+            .clone()
+            .unwrap_or_else(|| String::from("ownership.svg"));
+
+        if !grit_utils::check_file_type(&file, "svg") {
+            return Err(anyhow!("the image file format must be svg"));
+        }
+
+        let svg = treemap::treemap_svg(
+            &self.args.chart_options.title("Ownership"),
+            self.args.chart_options.size((1280, 960)),
+            root,
+            &self.args.chart_options.theme.colors(),
+        );
+
+        std::fs::write(Path::new(&file), svg)?;
+
+        if self.args.html {
+            grit_utils::create_html(&file)?;
+        }
+
+        Ok(())
+    }
+
+    /// totals the blamed lines per directory and author, with each author's share of the directory
+    fn aggregate(
+        &self,
+        snapshot: &str,
+        date: NaiveDate,
+        commit_id: &str,
+        blame_outputs: &[BlameOutput],
+    ) -> Vec<OwnershipOutput> {
+        let mut dir_author: HashMap<(String, String), i32> = HashMap::new();
+        let mut dir_total: HashMap<String, i32> = HashMap::new();
+
+        for b in blame_outputs
+            .iter()
+            .filter(|b| !identity::is_excluded(&b.author, &b.email))
+        {
+            let dir = grit_utils::directory(&b.file_name, self.args.depth);
+
+            *dir_author
+                .entry((dir.clone(), identity::display_author(&b.author, &b.email)))
+                .or_insert(0) += b.lines;
+            *dir_total.entry(dir).or_insert(0) += b.lines;
+        }
+
+        let mut result: Vec<OwnershipOutput> = dir_author
+            .into_iter()
+            .map(|((directory, author), lines)| {
+                let total = dir_total[&directory];
+
+                OwnershipOutput {
+                    snapshot: snapshot.to_string(),
+                    date,
+                    commit_id: commit_id.to_string(),
+                    directory,
+                    author,
+                    lines,
+                    share: if total > 0 {
+                        lines as f64 / total as f64
+                    } else {
+                        0.0
+                    },
+                }
+            })
+            .collect();
+
+        result.sort_by(|a, b| {
+            a.directory
+                .cmp(&b.directory)
+                .then(b.lines.cmp(&a.lines))
+                .then(a.author.cmp(&b.author))
+        });
+
+        result
+    }
+
+    fn display_output(&self, output: &[OwnershipOutput]) -> Result<()> {
+        let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+
+        let mut table = Table::new();
+
+        table.set_titles(table_row![
+            "snapshot",
+            "date",
+            "commit",
+            "directory",
+            "author",
+            "loc",
+            "share (%)"
+        ]);
+
+        output.iter().for_each(|r| {
+            table.add_row(table_row![
+                r.snapshot,
+                grit_utils::format_date(r.date),
+                r.commit_id,
+                r.directory,
+                r.author,
+                r.lines,
+                output::percent(r.share * 100.0)
+            ]);
+        });
+
+        table.write(&mut w, self.args.format)?;
+
+        Ok(())
+    }
+}
+
+impl Processable<()> for Ownership {
+    fn process(&self) -> Result<()> {
+        if self.args.treemap {
+            let root = self.process_treemap()?;
+
+            return if self.args.image {
+                self.create_treemap_image(&root)
+            } else {
+                self.display_treemap(&root)
+            };
+        }
+
+        let start_date = match self.args.start_date {
+            Some(d) => d,
+            None => self.first_commit_date()?,
+        };
+        let end_date = self.args.end_date.unwrap_or_else(grit_utils::today);
+
+        let mut output: Vec<OwnershipOutput> = Vec::new();
+
+        for period_end in self.args.snapshots.period_ends(start_date, end_date) {
+            if let Some((commit_id, blame_outputs)) = self.blame_at(period_end)? {
+                output.extend(self.aggregate(
+                    &self.args.snapshots.label(period_end),
+                    period_end,
+                    &commit_id,
+                    &blame_outputs,
+                ));
+            }
+        }
+
+        self.display_output(&output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::LevelFilter;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    #[test]
+    fn test_period_ends() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let ends = SnapshotPeriod::Quarterly.period_ends(
+            NaiveDate::from_ymd_opt(2020, 2, 10).unwrap(),
+            NaiveDate::from_ymd_opt(2020, 11, 5).unwrap(),
+        );
+
+        assert_eq!(
+            ends,
+            vec![
+                NaiveDate::from_ymd_opt(2020, 3, 31).unwrap(),
+                NaiveDate::from_ymd_opt(2020, 6, 30).unwrap(),
+                NaiveDate::from_ymd_opt(2020, 9, 30).unwrap(),
+                NaiveDate::from_ymd_opt(2020, 11, 5).unwrap(),
+            ]
+        );
+
+        assert_eq!(
+            SnapshotPeriod::Monthly.period_end(NaiveDate::from_ymd_opt(2020, 12, 3).unwrap()),
+            NaiveDate::from_ymd_opt(2020, 12, 31).unwrap()
+        );
+        assert_eq!(
+            SnapshotPeriod::Quarterly.label(NaiveDate::from_ymd_opt(2020, 11, 5).unwrap()),
+            "2020-Q4"
+        );
+        assert_eq!(
+            SnapshotPeriod::Yearly
+                .period_ends(
+                    NaiveDate::from_ymd_opt(2020, 6, 1).unwrap(),
+                    NaiveDate::from_ymd_opt(2021, 1, 1).unwrap()
+                )
+                .len(),
+            2
+        );
+
+        // 2020-03-11 is a Wednesday
+        assert_eq!(
+            SnapshotPeriod::Weekly.period_ends(
+                NaiveDate::from_ymd_opt(2020, 3, 11).unwrap(),
+                NaiveDate::from_ymd_opt(2020, 3, 20).unwrap()
+            ),
+            vec![
+                NaiveDate::from_ymd_opt(2020, 3, 15).unwrap(),
+                NaiveDate::from_ymd_opt(2020, 3, 20).unwrap()
+            ]
+        );
+        assert_eq!(
+            SnapshotPeriod::Weekly.label(NaiveDate::from_ymd_opt(2020, 3, 15).unwrap()),
+            "2020-W11"
+        );
+        assert_eq!(
+            SnapshotPeriod::Daily
+                .period_ends(
+                    NaiveDate::from_ymd_opt(2020, 2, 27).unwrap(),
+                    NaiveDate::from_ymd_opt(2020, 3, 1).unwrap()
+                )
+                .len(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_process_ownership() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let args = OwnershipArgs::builder(path.to_string())
+            .snapshots(SnapshotPeriod::Yearly)
+            .end_date(NaiveDate::from_ymd_opt(2020, 12, 31))
+            .include(Some(String::from("src/*")))
+            .file(Some(String::from("target/test_ownership.csv")))
+            .build()
+            .unwrap();
+
+        let ownership = Ownership::new(args);
+
+        let result = match ownership.process() {
+            Ok(()) => true,
+            Err(e) => {
+                error!("test_process_ownership ended in error {:?}", e);
+                false
+            }
+        };
+
+        assert!(result, "See error above");
+
+        let head = git2::Repository::open(path)
+            .unwrap()
+            .head()
+            .unwrap()
+            .target()
+            .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string("target/test_ownership.csv").unwrap(),
+            format!(
+                "snapshot,date,commit,directory,author,loc,share (%)\n\
+                 2020,2020-12-31,{0},src,Alice,5,55.6\n\
+                 2020,2020-12-31,{0},src,Bob,4,44.4\n",
+                head
+            )
+        );
+    }
+
+    #[test]
+    fn test_process_ownership_treemap() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let treemap_args = |file: &str, image: bool| {
+            OwnershipArgs::builder(path.to_string())
+                .treemap(true)
+                .file(Some(String::from(file)))
+                .image(image)
+                .format(OutputFormat::Json)
+                .build()
+                .unwrap()
+        };
+
+        let root = Ownership::new(treemap_args("target/test_ownership_treemap.json", false))
+            .process_treemap()
+            .unwrap();
+
+        assert_eq!(root.loc, 11);
+        assert_eq!(root.owner(), Some("Alice"));
+        assert_eq!(
+            root.children
+                .iter()
+                .map(|c| (c.name.as_str(), c.loc, c.owner()))
+                .collect::<Vec<_>>(),
+            vec![("src", 9, Some("Alice")), ("README.md", 2, Some("Alice"))]
+        );
+        assert_eq!(root.children[0].children[1].path, "src/lib.rs");
+        assert_eq!(root.children[0].children[1].owner(), Some("Bob"));
+
+        let file = "target/test_ownership_treemap.svg";
+        Ownership::new(treemap_args(file, true)).process().unwrap();
+
+        let svg = std::fs::read_to_string(file).unwrap();
+        assert!(svg.contains("<title>src/main.rs: 5 lines, Alice owns 100.0%</title>"));
+    }
+}
@@ -0,0 +1,122 @@
+use crate::error::GritError;
+use anyhow::Result;
+use std::cmp::Reverse;
+
+/// Thresholds that fail the run, so grit can gate a CI pipeline.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Policy {
+    bus_factor_below: Option<usize>,
+    /// percent of the lines
+    author_share_above: Option<f64>,
+}
+
+impl Policy {
+    pub fn new(bus_factor_below: Option<usize>, author_share_above: Option<f64>) -> Policy {
+        Policy {
+            bus_factor_below,
+            author_share_above,
+        }
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.bus_factor_below.is_some() || self.author_share_above.is_some()
+    }
+
+    /// checks the lines owned by each author against the thresholds, failing with a
+    /// `GritError::PolicyViolation` naming every one crossed
+    pub fn check(&self, lines: &[(String, i32)]) -> Result<()> {
+        let mut violations: Vec<String> = Vec::new();
+
+        if let Some(min) = self.bus_factor_below {
+            let factor = bus_factor(lines);
+            if factor < min {
+                violations.push(format!("bus factor is {}, below {}", factor, min));
+            }
+        }
+
+        if let Some(max) = self.author_share_above {
+            let total: i64 = lines.iter().map(|(_, l)| *l as i64).sum();
+
+            for (author, l) in lines.iter() {
+                let share = *l as f64 / total.max(1) as f64 * 100.0;
+                if share > max {
+                    violations.push(format!(
+                        "{} owns {:.1}% of the lines, above {:.1}%",
+                        author, share, max
+                    ));
+                }
+            }
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(GritError::PolicyViolation(violations.join("; ")).into())
+        }
+    }
+}
+
+/// the fewest authors who together own more than half of the lines
+pub fn bus_factor(lines: &[(String, i32)]) -> usize {
+    let total: i64 = lines.iter().map(|(_, l)| *l as i64).sum();
+
+    let mut sorted: Vec<i32> = lines.iter().map(|(_, l)| *l).collect();
+    sorted.sort_by_key(|l| Reverse(*l));
+
+    let mut owned: i64 = 0;
+
+    for (i, l) in sorted.iter().enumerate() {
+        owned += *l as i64;
+        if owned * 2 > total {
+            return i + 1;
+        }
+    }
+
+    sorted.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::LevelFilter;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    fn lines(owned: &[(&str, i32)]) -> Vec<(String, i32)> {
+        owned.iter().map(|(a, l)| (a.to_string(), *l)).collect()
+    }
+
+    #[test]
+    fn test_bus_factor() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        assert_eq!(bus_factor(&lines(&[("Alice", 6), ("Bob", 5)])), 1);
+        assert_eq!(bus_factor(&lines(&[("Alice", 5), ("Bob", 5)])), 2);
+        assert_eq!(
+            bus_factor(&lines(&[
+                ("Alice", 3),
+                ("Bob", 3),
+                ("Carol", 3),
+                ("Dave", 1)
+            ])),
+            2
+        );
+        assert_eq!(bus_factor(&[]), 0);
+    }
+
+    #[test]
+    fn test_policy_check() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let owned = lines(&[("Alice", 6), ("Bob", 5)]);
+
+        assert!(Policy::default().check(&owned).is_ok());
+        assert!(Policy::new(Some(1), Some(60.0)).check(&owned).is_ok());
+
+        let err = Policy::new(Some(2), Some(50.0)).check(&owned).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "policy violated: bus factor is 1, below 2; Alice owns 54.5% of the lines, above 50.0%"
+        );
+    }
+}
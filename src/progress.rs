@@ -0,0 +1,123 @@
+use indicatif::ProgressBar;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+/// the share of the work, in tenths, between progress lines
+const LINE_STEPS: u64 = 10;
+
+static NO_PROGRESS: OnceLock<bool> = OnceLock::new();
+
+/// swaps the progress bars for progress lines when `no_progress`.  Only the first call has
+/// an effect.
+pub fn set_no_progress(no_progress: bool) {
+    let _ = NO_PROGRESS.set(no_progress);
+}
+
+/// bars are drawn unless `--no-progress` was given or stderr is not a terminal, e.g. in CI
+/// where redrawing a bar corrupts the log
+pub fn bars_enabled() -> bool {
+    !*NO_PROGRESS.get().unwrap_or(&false) && std::io::stderr().is_terminal()
+}
+
+/// Progress through `total` files, as a bar on a terminal or otherwise as a line on stderr
+/// each time another tenth of the files is done.  Can be shared between the tasks doing
+/// the work.
+pub struct Progress {
+    label: &'static str,
+    total: u64,
+    done: AtomicU64,
+    bar: Option<ProgressBar>,
+}
+
+impl Progress {
+    /// `label` names the work in the progress lines, e.g. "blamed"
+    pub fn new(label: &'static str, total: usize) -> Progress {
+        let total = total as u64;
+
+        Progress {
+            label,
+            total,
+            done: AtomicU64::new(0),
+            bar: if bars_enabled() {
+                Some(ProgressBar::new(total))
+            } else {
+                None
+            },
+        }
+    }
+
+    /// counts one more file done
+    pub fn inc(&self) {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+
+        match &self.bar {
+            Some(bar) => bar.inc(1),
+            None => {
+                if let Some(line) = self.line(done) {
+                    eprintln!("{}", line);
+                }
+            }
+        }
+    }
+
+    /// the line to write when `done` files are done, if it completes another step
+    fn line(&self, done: u64) -> Option<String> {
+        if self.total == 0 || done * LINE_STEPS / self.total == (done - 1) * LINE_STEPS / self.total
+        {
+            return None;
+        }
+
+        Some(format!(
+            "{} {} of {} files ({}%)",
+            self.label,
+            done,
+            self.total,
+            done * 100 / self.total
+        ))
+    }
+
+    /// leaves the finished bar in place
+    pub fn finish(&self) {
+        if let Some(bar) = &self.bar {
+            bar.finish();
+        }
+    }
+
+    /// removes the finished bar
+    pub fn finish_and_clear(&self) {
+        if let Some(bar) = &self.bar {
+            bar.finish_and_clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_lines() {
+        let progress = Progress {
+            label: "blamed",
+            total: 25,
+            done: AtomicU64::new(0),
+            bar: None,
+        };
+
+        let lines: Vec<String> = (1..=25).filter_map(|d| progress.line(d)).collect();
+
+        assert_eq!(lines.len(), 10);
+        assert_eq!(lines[0], "blamed 3 of 25 files (12%)");
+        assert_eq!(lines[9], "blamed 25 of 25 files (100%)");
+
+        // fewer files than steps, so a line for each
+        let few = Progress {
+            label: "blamed",
+            total: 3,
+            done: AtomicU64::new(0),
+            bar: None,
+        };
+        assert_eq!(few.line(2), Some("blamed 2 of 3 files (66%)".to_string()));
+    }
+}
@@ -0,0 +1,560 @@
+use super::Processable;
+use crate::identity;
+use crate::table::Table;
+use crate::utils::grit_utils;
+use crate::utils::grit_utils::OutputFormat;
+use anyhow::Result;
+use chrono::NaiveDate;
+use git2::{ObjectType, Oid, Repository};
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::OnceLock;
+
+static SEMVER_REGEX: OnceLock<Regex> = OnceLock::new();
+
+pub struct ReleasesArgs {
+    path: String,
+    tag_pattern: Option<String>,
+    semver: bool,
+    file: Option<String>,
+    format: OutputFormat,
+}
+
+impl ReleasesArgs {
+    pub fn new(
+        path: String,
+        tag_pattern: Option<String>,
+        semver: bool,
+        file: Option<String>,
+        format: OutputFormat,
+    ) -> ReleasesArgs {
+        ReleasesArgs {
+            path,
+            tag_pattern,
+            semver,
+            file,
+            format,
+        }
+    }
+}
+
+/// The MAJOR.MINOR.PATCH of a semver release tag.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Version {
+    major: u64,
+    minor: u64,
+    patch: u64,
+}
+
+impl Version {
+    /// the version of a tag such as `v1.2.3` or `release-1.2.3+build.5`, after any prefix
+    /// without digits.  Pre-releases such as `1.2.3-rc.1` are not releases, so they are None
+    /// like tags that aren't semver.
+    fn parse(tag: &str) -> Option<Version> {
+        let regex = SEMVER_REGEX.get_or_init(|| {
+            Regex::new(r"^[^0-9]*(\d+)\.(\d+)\.(\d+)(?:\+[0-9A-Za-z.-]+)?$")
+                .expect("invalid semver regex")
+        });
+
+        let caps = regex.captures(tag)?;
+        let part = |i: usize| caps[i].parse::<u64>().ok();
+
+        Some(Version {
+            major: part(1)?,
+            minor: part(2)?,
+            patch: part(3)?,
+        })
+    }
+
+    /// a patch release fixes a line, a minor release starts one and a major release starts
+    /// a series of them
+    fn release_type(&self) -> ReleaseType {
+        if self.patch > 0 {
+            ReleaseType::Patch
+        } else if self.minor > 0 {
+            ReleaseType::Minor
+        } else {
+            ReleaseType::Major
+        }
+    }
+
+    /// the MAJOR.MINOR release line the version is on
+    fn line(&self) -> String {
+        format!("{}.{}", self.major, self.minor)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum ReleaseType {
+    Major,
+    Minor,
+    Patch,
+}
+
+impl fmt::Display for ReleaseType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReleaseType::Major => write!(f, "major"),
+            ReleaseType::Minor => write!(f, "minor"),
+            ReleaseType::Patch => write!(f, "patch"),
+        }
+    }
+}
+
+/// A tag and the commits made since the tag before it.
+#[derive(Clone, Debug, PartialEq)]
+struct ReleaseOutput {
+    tag: String,
+    /// only with `--semver`
+    version: Option<Version>,
+    /// the tagger's date for an annotated tag, the tagged commit's otherwise
+    date: NaiveDate,
+    /// non merge commits reachable from the tag and not from the previous one
+    commits: usize,
+    authors: BTreeSet<String>,
+    /// days since the previous tag, none for the first
+    days_since: Option<i64>,
+}
+
+/// A tag pointing at a commit.
+struct ReleaseTag {
+    name: String,
+    version: Option<Version>,
+    date: NaiveDate,
+    commit: Oid,
+}
+
+/// The mean commits and authors of the releases of a type, appended to the `--semver` rows.
+#[derive(Clone, Debug, PartialEq)]
+struct ReleaseTypeMeans {
+    release_type: ReleaseType,
+    releases: usize,
+    commits: f64,
+    authors: f64,
+}
+
+impl ReleaseTypeMeans {
+    /// the means of each type with releases in `output`, major first
+    fn of(output: &[ReleaseOutput]) -> Vec<ReleaseTypeMeans> {
+        [ReleaseType::Major, ReleaseType::Minor, ReleaseType::Patch]
+            .iter()
+            .filter_map(|t| {
+                let releases: Vec<&ReleaseOutput> = output
+                    .iter()
+                    .filter(|r| r.version.map(|v| v.release_type()) == Some(*t))
+                    .collect();
+
+                if releases.is_empty() {
+                    return None;
+                }
+
+                let n = releases.len() as f64;
+
+                Some(ReleaseTypeMeans {
+                    release_type: *t,
+                    releases: releases.len(),
+                    commits: releases.iter().map(|r| r.commits as f64).sum::<f64>() / n,
+                    authors: releases.iter().map(|r| r.authors.len() as f64).sum::<f64>() / n,
+                })
+            })
+            .collect()
+    }
+}
+
+pub struct Releases {
+    args: ReleasesArgs,
+}
+
+impl Releases {
+    pub fn new(args: ReleasesArgs) -> Releases {
+        Releases { args }
+    }
+
+    /// every tag pointing at a commit with the date it was made, oldest first, or with
+    /// `--semver` every release tag, lowest version first
+    fn tags(&self, repo: &Repository) -> Result<Vec<ReleaseTag>> {
+        let mut tags: Vec<ReleaseTag> = Vec::new();
+
+        for name in repo.tag_names(self.args.tag_pattern.as_deref())?.iter() {
+            let name = match name {
+                Some(n) => n,
+                None => continue,
+            };
+
+            let object = repo.revparse_single(&format!("refs/tags/{}", name))?;
+
+            // tags of trees and blobs are not releases
+            let commit = match object.peel_to_commit() {
+                Ok(c) => c,
+                Err(_) => {
+                    info!("skipping tag {}, it does not point at a commit", name);
+                    continue;
+                }
+            };
+
+            let time = match object.kind() {
+                Some(ObjectType::Tag) => object
+                    .as_tag()
+                    .and_then(|t| t.tagger())
+                    .map(|s| s.when())
+                    .unwrap_or_else(|| commit.time()),
+                _ => commit.time(),
+            };
+
+            let version = if self.args.semver {
+                match Version::parse(name) {
+                    Some(v) => Some(v),
+                    None => {
+                        info!("skipping tag {}, it is not a semver release", name);
+                        continue;
+                    }
+                }
+            } else {
+                None
+            };
+
+            tags.push(ReleaseTag {
+                name: name.to_string(),
+                version,
+                date: grit_utils::convert_git_time(&time),
+                commit: commit.id(),
+            });
+        }
+
+        tags.sort_by(|a, b| {
+            a.version
+                .cmp(&b.version)
+                .then(a.date.cmp(&b.date))
+                .then(a.name.cmp(&b.name))
+        });
+
+        Ok(tags)
+    }
+
+    /// each tag with the commits and authors since the tag before it, oldest first
+    fn process_releases(&self) -> Result<Vec<ReleaseOutput>> {
+        let repo = grit_utils::open_repo(&self.args.path)?;
+
+        let mut output: Vec<ReleaseOutput> = Vec::new();
+        let mut previous: Option<(NaiveDate, Oid)> = None;
+
+        for ReleaseTag {
+            name,
+            version,
+            date,
+            commit,
+        } in self.tags(&repo)?
+        {
+            let mut revwalk = repo.revwalk()?;
+            revwalk.push(commit)?;
+            if let Some((_, p)) = previous {
+                revwalk.hide(p)?;
+            }
+
+            let mut commits = 0;
+            let mut authors: BTreeSet<String> = BTreeSet::new();
+
+            for oid in revwalk {
+                let c = repo.find_commit(oid?)?;
+
+                if c.parent_count() > 1 {
+                    continue;
+                }
+
+                let author = c.author();
+                let name = String::from_utf8_lossy(author.name_bytes()).to_string();
+                let email = String::from_utf8_lossy(author.email_bytes()).to_string();
+
+                if identity::is_excluded(&name, &email) {
+                    continue;
+                }
+
+                commits += 1;
+                authors.insert(identity::display_author(&name, &email));
+            }
+
+            output.push(ReleaseOutput {
+                tag: name,
+                version,
+                date,
+                commits,
+                authors,
+                days_since: previous.map(|(d, _)| (date - d).num_days()),
+            });
+
+            previous = Some((date, commit));
+        }
+
+        Ok(output)
+    }
+
+    fn display_output(&self, output: &[ReleaseOutput]) -> Result<()> {
+        let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+
+        let table = if self.args.semver {
+            Releases::semver_table(output)
+        } else {
+            Releases::tag_table(output)
+        };
+
+        table.write(&mut w, self.args.format)?;
+
+        Ok(())
+    }
+
+    fn tag_table(output: &[ReleaseOutput]) -> Table {
+        let mut table = Table::new();
+
+        table.set_titles(table_row![
+            "tag",
+            "date",
+            "commits",
+            "authors",
+            "days since previous"
+        ]);
+
+        output.iter().for_each(|r| {
+            table.add_row(table_row![
+                r.tag,
+                grit_utils::format_date(r.date),
+                r.commits,
+                r.authors.len(),
+                r.days_since.map(|d| d.to_string()).unwrap_or_default()
+            ]);
+        });
+
+        table.add_label(0, "tag");
+        Releases::add_metrics(&mut table, 2);
+
+        table
+    }
+
+    /// the releases with their type, line and the names of their authors, followed by the
+    /// mean commits and authors of each type of release
+    fn semver_table(output: &[ReleaseOutput]) -> Table {
+        let mut table = Table::new();
+
+        table.set_titles(table_row![
+            "tag",
+            "type",
+            "line",
+            "date",
+            "commits",
+            "authors",
+            "days since previous",
+            "contributors"
+        ]);
+
+        output.iter().for_each(|r| {
+            table.add_row(table_row![
+                r.tag,
+                r.version
+                    .map(|v| v.release_type().to_string())
+                    .unwrap_or_default(),
+                r.version.map(|v| v.line()).unwrap_or_default(),
+                grit_utils::format_date(r.date),
+                r.commits,
+                r.authors.len(),
+                r.days_since.map(|d| d.to_string()).unwrap_or_default(),
+                r.authors
+                    .iter()
+                    .cloned()
+                    .collect::<Vec<String>>()
+                    .join("; ")
+            ]);
+        });
+
+        for m in ReleaseTypeMeans::of(output) {
+            table.add_row(vec![
+                format!("Mean of {} releases ({})", m.release_type, m.releases),
+                m.release_type.to_string(),
+                String::new(),
+                String::new(),
+                format!("{:.1}", m.commits),
+                format!("{:.1}", m.authors),
+                String::new(),
+                String::new(),
+            ]);
+        }
+
+        table.add_label(0, "tag");
+        table.add_label(1, "type");
+        Releases::add_metrics(&mut table, 4);
+
+        table
+    }
+
+    /// the commits, authors and days since previous metrics, in columns from `first`
+    fn add_metrics(table: &mut Table, first: usize) {
+        table.add_metric(
+            first,
+            "grit_release_commits",
+            "Commits since the previous release.",
+        );
+        table.add_metric(
+            first + 1,
+            "grit_release_authors",
+            "Authors of the commits since the previous release.",
+        );
+        table.add_metric(
+            first + 2,
+            "grit_release_days_since_previous",
+            "Days between the release and the previous one.",
+        );
+    }
+}
+
+impl Processable<()> for Releases {
+    fn process(&self) -> Result<()> {
+        let output = self.process_releases()?;
+
+        self.display_output(&output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::{Signature, Time};
+    use log::LevelFilter;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    /// the fixture with a lightweight `v0.1.0` tag on Alice's first commit, an annotated
+    /// `v0.2.0` on HEAD made 2020-01-06 and a `nightly` tag on Bob's commit
+    fn release_repo() -> TempDir {
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let repo = Repository::open(td.path()).unwrap();
+
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let first = head
+            .parent(0)
+            .unwrap()
+            .parent(0)
+            .unwrap()
+            .parent(0)
+            .unwrap();
+        repo.tag_lightweight("v0.1.0", first.as_object(), false)
+            .unwrap();
+        repo.tag_lightweight(
+            "nightly",
+            head.parent(0).unwrap().parent(0).unwrap().as_object(),
+            false,
+        )
+        .unwrap();
+
+        // 2020-01-06 12:00 UTC
+        let tagger =
+            Signature::new("Carol", "carol@example.com", &Time::new(1_578_312_000, 0)).unwrap();
+        repo.tag("v0.2.0", head.as_object(), &tagger, "second release", false)
+            .unwrap();
+
+        td
+    }
+
+    fn releases(path: &str, tag_pattern: Option<&str>) -> Vec<(String, usize, usize, Option<i64>)> {
+        Releases::new(ReleasesArgs::new(
+            path.to_string(),
+            tag_pattern.map(String::from),
+            false,
+            None,
+            OutputFormat::Csv,
+        ))
+        .process_releases()
+        .unwrap()
+        .into_iter()
+        .map(|r| (r.tag, r.commits, r.authors.len(), r.days_since))
+        .collect()
+    }
+
+    #[test]
+    fn test_process_releases() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td = release_repo();
+        let path = td.path().to_str().unwrap();
+
+        assert_eq!(
+            releases(path, Some("v*")),
+            vec![
+                (String::from("v0.1.0"), 1, 1, None),
+                (String::from("v0.2.0"), 3, 2, Some(5)),
+            ]
+        );
+
+        assert_eq!(
+            releases(path, None),
+            vec![
+                (String::from("v0.1.0"), 1, 1, None),
+                (String::from("nightly"), 1, 1, Some(1)),
+                (String::from("v0.2.0"), 2, 2, Some(4)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_version_parse() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let v = |major, minor, patch| {
+            Some(Version {
+                major,
+                minor,
+                patch,
+            })
+        };
+
+        assert_eq!(Version::parse("v1.2.3"), v(1, 2, 3));
+        assert_eq!(Version::parse("release-10.0.1+build.5"), v(10, 0, 1));
+        assert_eq!(Version::parse("2.0.0"), v(2, 0, 0));
+        assert_eq!(Version::parse("v1.2.3-rc.1"), None);
+        assert_eq!(Version::parse("v1.2"), None);
+        assert_eq!(Version::parse("nightly"), None);
+
+        assert_eq!(v(2, 0, 0).unwrap().release_type(), ReleaseType::Major);
+        assert_eq!(v(1, 3, 0).unwrap().release_type(), ReleaseType::Minor);
+        assert_eq!(v(1, 3, 2).unwrap().release_type(), ReleaseType::Patch);
+        assert_eq!(v(1, 3, 2).unwrap().line(), "1.3");
+    }
+
+    #[test]
+    fn test_process_releases_semver() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td = release_repo();
+        let path = td.path().to_str().unwrap();
+
+        // a patch on Alice's second commit, and a release candidate that isn't a release
+        let repo = Repository::open(path).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.tag_lightweight("v0.1.1", head.parent(0).unwrap().as_object(), false)
+            .unwrap();
+        repo.tag_lightweight("v1.0.0-rc.1", head.as_object(), false)
+            .unwrap();
+
+        let releases = Releases::new(ReleasesArgs::new(
+            path.to_string(),
+            None,
+            true,
+            None,
+            OutputFormat::Csv,
+        ));
+
+        let mut csv: Vec<u8> = Vec::new();
+        Releases::semver_table(&releases.process_releases().unwrap())
+            .write(&mut csv, OutputFormat::Csv)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "tag,type,line,date,commits,authors,days since previous,contributors\n\
+             v0.1.0,minor,0.1,2020-01-01,1,1,,Alice\n\
+             v0.1.1,patch,0.1,2020-01-02,2,2,1,Alice; Bob\n\
+             v0.2.0,minor,0.2,2020-01-06,1,1,4,Bob\n\
+             Mean of minor releases (2),minor,,,1.0,1.0,,\n\
+             Mean of patch releases (1),patch,,,2.0,2.0,,\n"
+        );
+    }
+}
@@ -0,0 +1,301 @@
+use super::Processable;
+use crate::age::{Age, AgeArgs};
+use crate::commit_size::{CommitSize, CommitSizeArgs};
+use crate::effort::{Effort, EffortArgs};
+use crate::fame::{Fame, FameArgs};
+use crate::timeline::{Timeline, TimelineArgs};
+use crate::utils::grit_utils;
+use crate::utils::grit_utils::OutputFormat;
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Instant;
+
+const OLDER_THAN_MONTHS: i64 = 12;
+const GIANT_LINES: usize = 1000;
+
+/// An analysis `grit report` can run.  Those sharing a blame or commit walk are listed
+/// next to each other, so the later one finds it cached.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Analysis {
+    Fame,
+    Age,
+    Effort,
+    CommitSize,
+    Timeline,
+}
+
+pub const ALL_ANALYSES: [Analysis; 5] = [
+    Analysis::Fame,
+    Analysis::Age,
+    Analysis::Effort,
+    Analysis::CommitSize,
+    Analysis::Timeline,
+];
+
+impl FromStr for Analysis {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "fame" => Ok(Analysis::Fame),
+            "age" => Ok(Analysis::Age),
+            "effort" => Ok(Analysis::Effort),
+            "commitsize" => Ok(Analysis::CommitSize),
+            "timeline" => Ok(Analysis::Timeline),
+            _ => Err(anyhow!(
+                "analysis must be either 'fame', 'age', 'effort', 'commitsize' or 'timeline'"
+            )),
+        }
+    }
+}
+
+impl Analysis {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Analysis::Fame => "fame",
+            Analysis::Age => "age",
+            Analysis::Effort => "effort",
+            Analysis::CommitSize => "commitsize",
+            Analysis::Timeline => "timeline",
+        }
+    }
+}
+
+/// parses a comma delimited list of analyses, keeping the order they run in
+pub fn parse_analyses(value: &str) -> Result<Vec<Analysis>> {
+    let named: Vec<Analysis> = value
+        .split(',')
+        .map(|s| s.trim().parse())
+        .collect::<Result<_>>()?;
+
+    Ok(ALL_ANALYSES
+        .iter()
+        .filter(|a| named.contains(a))
+        .cloned()
+        .collect())
+}
+
+pub struct ReportArgs {
+    path: String,
+    analyses: Vec<Analysis>,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+    include: Option<String>,
+    exclude: Option<String>,
+    out_dir: String,
+    format: OutputFormat,
+}
+
+impl ReportArgs {
+    pub fn builder(path: String, analyses: Vec<Analysis>, out_dir: String) -> ReportArgsBuilder {
+        ReportArgsBuilder {
+            args: ReportArgs {
+                path,
+                analyses,
+                start_date: None,
+                end_date: None,
+                include: None,
+                exclude: None,
+                out_dir,
+                format: OutputFormat::Csv,
+            },
+        }
+    }
+}
+
+/// Builds a `ReportArgs` by name rather than position, validating it on `build`.
+pub struct ReportArgsBuilder {
+    args: ReportArgs,
+}
+
+impl ReportArgsBuilder {
+    pub fn start_date(mut self, start_date: Option<NaiveDate>) -> ReportArgsBuilder {
+        self.args.start_date = start_date;
+        self
+    }
+
+    pub fn end_date(mut self, end_date: Option<NaiveDate>) -> ReportArgsBuilder {
+        self.args.end_date = end_date;
+        self
+    }
+
+    pub fn include(mut self, include: Option<String>) -> ReportArgsBuilder {
+        self.args.include = include;
+        self
+    }
+
+    pub fn exclude(mut self, exclude: Option<String>) -> ReportArgsBuilder {
+        self.args.exclude = exclude;
+        self
+    }
+
+    pub fn format(mut self, format: OutputFormat) -> ReportArgsBuilder {
+        self.args.format = format;
+        self
+    }
+
+    pub fn build(self) -> Result<ReportArgs> {
+        grit_utils::check_date_range(self.args.start_date, self.args.end_date)?;
+
+        Ok(self.args)
+    }
+}
+
+pub struct Report {
+    args: ReportArgs,
+}
+
+impl Report {
+    pub fn new(args: ReportArgs) -> Report {
+        Report { args }
+    }
+
+    /// the file the analysis is written to, named after it with the format's extension
+    fn output_file(&self, analysis: Analysis) -> String {
+        let ext = match self.args.format {
+            OutputFormat::Table => "txt",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Json => "json",
+            OutputFormat::Markdown => "md",
+            OutputFormat::Prom => "prom",
+        };
+
+        Path::new(&self.args.out_dir)
+            .join(format!("{}.{}", analysis.name(), ext))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn processor(&self, analysis: Analysis) -> Result<Box<dyn Processable<()>>> {
+        let a = &self.args;
+        let file = Some(self.output_file(analysis));
+
+        Ok(match analysis {
+            Analysis::Fame => Box::new(Fame::new(
+                FameArgs::builder(a.path.clone())
+                    .start_date(a.start_date)
+                    .end_date(a.end_date)
+                    .include(a.include.clone())
+                    .exclude(a.exclude.clone())
+                    .format(a.format)
+                    .file(file)
+                    .build()?,
+            )),
+            Analysis::Age => Box::new(Age::new(
+                AgeArgs::builder(a.path.clone())
+                    .include(a.include.clone())
+                    .exclude(a.exclude.clone())
+                    .older_than(OLDER_THAN_MONTHS)
+                    .file(file)
+                    .format(a.format)
+                    .build()?,
+            )),
+            Analysis::Effort => Box::new(Effort::new(
+                EffortArgs::builder(a.path.clone())
+                    .start_date(a.start_date)
+                    .end_date(a.end_date)
+                    .format(a.format)
+                    .include(a.include.clone())
+                    .exclude(a.exclude.clone())
+                    .file(file)
+                    .build()?,
+            )),
+            Analysis::CommitSize => Box::new(CommitSize::new(
+                CommitSizeArgs::builder(a.path.clone())
+                    .start_date(a.start_date)
+                    .end_date(a.end_date)
+                    .include(a.include.clone())
+                    .exclude(a.exclude.clone())
+                    .giant_lines(GIANT_LINES)
+                    .file(file)
+                    .format(a.format)
+                    .build()?,
+            )),
+            Analysis::Timeline => Box::new(Timeline::new(
+                TimelineArgs::builder(a.path.clone())
+                    .start_date(a.start_date)
+                    .end_date(a.end_date)
+                    .file(file)
+                    .format(a.format)
+                    .build()?,
+            )),
+        })
+    }
+}
+
+impl Processable<()> for Report {
+    fn process(&self) -> Result<()> {
+        fs::create_dir_all(&self.args.out_dir)?;
+
+        let start = Instant::now();
+
+        for analysis in self.args.analyses.iter() {
+            let analysis_start = Instant::now();
+
+            self.processor(*analysis)?.process()?;
+
+            info!(
+                "wrote {} in {:?}",
+                self.output_file(*analysis),
+                analysis_start.elapsed()
+            );
+        }
+
+        info!(
+            "wrote {} analyses to {} in {:?}",
+            self.args.analyses.len(),
+            self.args.out_dir,
+            start.elapsed()
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::LevelFilter;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    #[test]
+    fn test_parse_analyses() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        assert_eq!(
+            parse_analyses("timeline, fame").unwrap(),
+            vec![Analysis::Fame, Analysis::Timeline]
+        );
+        assert!(parse_analyses("fame,bydate").is_err());
+    }
+
+    #[test]
+    fn test_process_report() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+        let out_dir = "target/test_process_report";
+
+        Report::new(
+            ReportArgs::builder(path.to_string(), ALL_ANALYSES.to_vec(), out_dir.to_string())
+                .build()
+                .unwrap(),
+        )
+        .process()
+        .unwrap();
+
+        for name in ["fame", "age", "effort", "commitsize", "timeline"].iter() {
+            let written = fs::read_to_string(format!("{}/{}.csv", out_dir, name)).unwrap();
+            assert!(written.lines().count() > 1, "{} is empty", name);
+        }
+
+        let fame = fs::read_to_string(format!("{}/fame.csv", out_dir)).unwrap();
+        assert!(fame.lines().any(|l| l.starts_with("Alice,2,2,6,")));
+    }
+}
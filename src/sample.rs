@@ -0,0 +1,156 @@
+use crate::output;
+use std::fmt;
+
+/// z score of a 95% confidence interval
+const Z_95: f64 = 1.96;
+
+/// FNV-1a, stable across builds and platforms unlike the std hashers
+fn path_hash(path: &str) -> u64 {
+    path.bytes().fold(0xcbf2_9ce4_8422_2325, |h, b| {
+        (h ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// The files `--sample` keeps out of all the candidates.  A file is picked by a hash of its
+/// path, so reruns and later commits sample the same files.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sample {
+    percent: f64,
+    population: usize,
+    sampled: usize,
+}
+
+impl Sample {
+    /// about `percent` of `file_names`, along with the sample they make
+    pub fn files(file_names: Vec<String>, percent: f64) -> (Sample, Vec<String>) {
+        let population = file_names.len();
+        let picked: Vec<String> = file_names
+            .into_iter()
+            .filter(|f| ((path_hash(f) % 10_000) as f64) < percent * 100.0)
+            .collect();
+
+        info!(
+            "sampled {} of {} files at {}%",
+            picked.len(),
+            population,
+            percent
+        );
+
+        (
+            Sample {
+                percent,
+                population,
+                sampled: picked.len(),
+            },
+            picked,
+        )
+    }
+
+    /// the total of a per file value over every candidate file, from its value in each
+    /// sampled file
+    pub fn estimate(&self, values: &[f64]) -> Estimate {
+        let n = values.len() as f64;
+        let population = self.population as f64;
+
+        if values.is_empty() {
+            return Estimate {
+                total: 0.0,
+                margin: 0.0,
+            };
+        }
+
+        let mean = values.iter().sum::<f64>() / n;
+        let variance = if values.len() > 1 {
+            values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0)
+        } else {
+            0.0
+        };
+
+        // sampling without replacement, so the error shrinks as the sample nears all files
+        let correction = (1.0 - n / population).max(0.0);
+
+        Estimate {
+            total: population * mean,
+            margin: Z_95 * population * (correction * variance / n).sqrt(),
+        }
+    }
+}
+
+impl fmt::Display for Sample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "sampled {} of {} files ({}%)",
+            output::table_number(&self.sampled.to_string()),
+            output::table_number(&self.population.to_string()),
+            output::percent(self.percent)
+        )
+    }
+}
+
+/// A total extrapolated from a sample and its margin of error at 95% confidence.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Estimate {
+    pub total: f64,
+    pub margin: f64,
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "~{} ± {}",
+            output::table_number(&format!("{:.0}", self.total)),
+            output::table_number(&format!("{:.0}", self.margin))
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::LevelFilter;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    #[test]
+    fn test_sample_files() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let file_names: Vec<String> = (0..1000).map(|i| format!("src/file{}.rs", i)).collect();
+
+        let (sample, picked) = Sample::files(file_names.clone(), 10.0);
+        assert_eq!(sample.population, 1000);
+        assert!(picked.len() > 50 && picked.len() < 150);
+        assert_eq!(Sample::files(file_names.clone(), 10.0).1, picked);
+
+        // a bigger sample keeps every file of a smaller one
+        let (_, more) = Sample::files(file_names.clone(), 50.0);
+        assert!(picked.iter().all(|f| more.contains(f)));
+
+        assert_eq!(Sample::files(file_names, 100.0).1.len(), 1000);
+    }
+
+    #[test]
+    fn test_estimate() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let sample = Sample {
+            percent: 10.0,
+            population: 40,
+            sampled: 4,
+        };
+
+        let estimate = sample.estimate(&[10.0, 20.0, 30.0, 40.0]);
+        assert_eq!(estimate.total, 1000.0);
+        assert!((estimate.margin - 480.1).abs() < 0.1);
+
+        // every file sampled, so nothing is left to estimate
+        let all = Sample {
+            percent: 100.0,
+            population: 4,
+            sampled: 4,
+        };
+        assert_eq!(all.estimate(&[10.0, 20.0, 30.0, 40.0]).margin, 0.0);
+    }
+}
@@ -0,0 +1,617 @@
+use super::Processable;
+use crate::by_date::{ByDate, ByDateArgs};
+use crate::cancel;
+use crate::effort::{Effort, EffortArgs};
+use crate::fame::{CommitMetric, Fame, FameArgs, FameMode};
+use crate::output;
+use crate::table::Table;
+use crate::utils::grit_utils;
+use crate::utils::grit_utils::OutputFormat;
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Take, Write};
+use std::net::{TcpListener, TcpStream};
+use std::str::FromStr;
+use std::sync::mpsc::{self, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// the requests answered at once, as each blames on a runtime of its own.  As many more
+/// wait their turn, and the rest are turned away with a 503.
+const MAX_CONNECTIONS: usize = 4;
+
+/// how long a connection may take to send its request, or to take the response
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// the most bytes read of the request line and headers together
+const MAX_REQUEST_BYTES: u64 = 8 * 1024;
+
+pub struct ServeArgs {
+    path: String,
+    bind: String,
+    port: u16,
+}
+
+impl ServeArgs {
+    pub fn new(path: String, bind: String, port: u16) -> ServeArgs {
+        ServeArgs { path, bind, port }
+    }
+}
+
+/// A query parameter the endpoint does not take, or one it cannot parse.
+#[derive(Debug)]
+struct BadRequest(String);
+
+impl fmt::Display for BadRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for BadRequest {}
+
+/// The decoded query parameters of a request, taken as the endpoint reads them.
+struct Query {
+    params: BTreeMap<String, String>,
+}
+
+impl Query {
+    fn parse(query: &str) -> Result<Query> {
+        let mut params = BTreeMap::new();
+
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            params.insert(decode(name)?, decode(value)?);
+        }
+
+        Ok(Query { params })
+    }
+
+    fn string(&mut self, name: &str) -> Option<String> {
+        self.params.remove(name)
+    }
+
+    /// true when the parameter is given without a value or as anything but false or 0
+    fn flag(&mut self, name: &str) -> bool {
+        self.string(name)
+            .map(|v| v != "false" && v != "0")
+            .unwrap_or(false)
+    }
+
+    fn parsed<T: FromStr>(&mut self, name: &str) -> Result<Option<T>> {
+        match self.string(name) {
+            Some(v) => v
+                .parse()
+                .map(Some)
+                .map_err(|_| BadRequest(format!("cannot parse {} '{}'", name, v)).into()),
+            None => Ok(None),
+        }
+    }
+
+    fn date(&mut self, name: &str) -> Result<Option<NaiveDate>> {
+        match self.string(name) {
+            Some(v) => {
+                let day = NaiveDate::parse_from_str(&v, "%Y-%m-%d").map_err(|_| {
+                    BadRequest(format!("{} must be in the 'YYYY-MM-DD' format", name))
+                })?;
+                Ok(Some(day))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// fails naming the first parameter the endpoint did not read
+    fn finish(self) -> Result<()> {
+        match self.params.keys().next() {
+            Some(name) => Err(BadRequest(format!("unknown parameter '{}'", name)).into()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// percent decodes a query string part, with + for a space
+fn decode(value: &str) -> Result<String> {
+    let bytes = value.as_bytes();
+    let mut decoded: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let hex = value
+                    .get(i + 1..i + 3)
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+                    .ok_or_else(|| BadRequest(format!("invalid escape in '{}'", value)))?;
+                decoded.push(hex);
+                i += 2;
+            }
+            b => decoded.push(b),
+        }
+        i += 1;
+    }
+
+    Ok(String::from_utf8_lossy(&decoded).to_string())
+}
+
+/// A status line and JSON body.
+#[derive(Debug, PartialEq)]
+struct Response {
+    status: u16,
+    body: String,
+}
+
+impl Response {
+    fn error(status: u16, message: &str) -> Response {
+        Response {
+            status,
+            body: format!("{{\"error\":{}}}", grit_utils::json_string(message)),
+        }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            408 => "Request Timeout",
+            431 => "Request Header Fields Too Large",
+            503 => "Service Unavailable",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+pub struct Serve {
+    args: ServeArgs,
+}
+
+impl Serve {
+    pub fn new(args: ServeArgs) -> Serve {
+        Serve { args }
+    }
+
+    /// runs the endpoint's analysis with the query's parameters, as the json output of the
+    /// command of the same name
+    fn route(path: &str, target: &str) -> Response {
+        let (endpoint, query) = target.split_once('?').unwrap_or((target, ""));
+        let command = endpoint.trim_start_matches('/');
+
+        if !["fame", "bydate", "effort"].contains(&command) {
+            return Response::error(404, &format!("no endpoint {}", endpoint));
+        }
+
+        let result = Query::parse(query).and_then(|q| {
+            let parameters: Vec<(String, String)> = q
+                .params
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+
+            Serve::table(path, command, q).map(|t| (t, parameters))
+        });
+
+        match result {
+            Ok((table, parameters)) => {
+                let mut results: Vec<u8> = Vec::new();
+
+                if let Err(e) = table.write(&mut results, OutputFormat::Json) {
+                    return Response::error(500, &e.to_string());
+                }
+
+                let envelope = output::Envelope {
+                    command: command.to_string(),
+                    parameters,
+                    head: head(path),
+                    generated_at: chrono::Utc::now().to_rfc3339(),
+                };
+
+                Response {
+                    status: 200,
+                    body: envelope.wrap(String::from_utf8_lossy(&results).trim_end()),
+                }
+            }
+            Err(e) if e.downcast_ref::<BadRequest>().is_some() => {
+                Response::error(400, &e.to_string())
+            }
+            Err(e) => Response::error(500, &e.to_string()),
+        }
+    }
+
+    /// every endpoint takes the dates, include, exclude and restrict-author
+    fn table(path: &str, command: &str, mut q: Query) -> Result<Table> {
+        let path = path.to_string();
+        let start_date = q.date("start-date")?;
+        let end_date = q.date("end-date")?;
+        grit_utils::check_date_range(start_date, end_date)
+            .map_err(|e| BadRequest(e.to_string()))?;
+        let include = q.string("include");
+        let exclude = q.string("exclude");
+        let restrict_authors = q.string("restrict-author");
+
+        match command {
+            "fame" => {
+                let sort = q.string("sort");
+                if let Some(s) = &sort {
+                    if !["commit", "loc", "files"].contains(&s.as_str()) {
+                        return Err(BadRequest(String::from(
+                            "sort must be either 'commit', 'loc' or 'files'",
+                        ))
+                        .into());
+                    }
+                }
+
+                let top = q.parsed("top")?;
+                let min_lines = q.parsed("min-lines")?;
+                let min_commits = q.parsed("min-commits")?;
+                let at = q.string("at");
+                let mode = match q.string("mode") {
+                    Some(m) => m
+                        .parse()
+                        .map_err(|e: anyhow::Error| BadRequest(e.to_string()))?,
+                    None => FameMode::Blame,
+                };
+                let commit_metric = match q.string("commit-metric") {
+                    Some(m) => m
+                        .parse()
+                        .map_err(|e: anyhow::Error| BadRequest(e.to_string()))?,
+                    None => CommitMetric::Blame,
+                };
+                q.finish()?;
+
+                Fame::new(
+                    FameArgs::builder(path)
+                        .sort(sort)
+                        .start_date(start_date)
+                        .end_date(end_date)
+                        .include(include)
+                        .exclude(exclude)
+                        .restrict_authors(restrict_authors)
+                        .format(OutputFormat::Json)
+                        .top(top)
+                        .min_lines(min_lines)
+                        .min_commits(min_commits)
+                        .at(at)
+                        .mode(mode)
+                        .commit_metric(commit_metric)
+                        .build()
+                        .map_err(|e| BadRequest(e.to_string()))?,
+                )
+                .results_table()
+            }
+            "bydate" => {
+                let ignore_weekends = q.flag("ignore-weekends");
+                let ignore_gap_fill = q.flag("ignore-gap-fill");
+                let cumulative = q.flag("cumulative");
+                let rolling = q.parsed("rolling")?;
+                let split_merges = q.flag("split-merges");
+                let path_filter = q.string("path-filter");
+                q.finish()?;
+
+                if include.is_some() || exclude.is_some() {
+                    return Err(BadRequest(String::from(
+                        "bydate takes path-filter instead of include and exclude",
+                    ))
+                    .into());
+                }
+
+                ByDate::new(
+                    ByDateArgs::builder(path)
+                        .start_date(start_date)
+                        .end_date(end_date)
+                        .ignore_weekends(ignore_weekends)
+                        .ignore_gap_fill(ignore_gap_fill)
+                        .restrict_authors(restrict_authors)
+                        .format(OutputFormat::Json)
+                        .path_filter(path_filter)
+                        .cumulative(cumulative)
+                        .rolling(rolling)
+                        .split_merges(split_merges)
+                        .build()
+                        .map_err(|e| BadRequest(e.to_string()))?,
+                )
+                .results_table()
+            }
+            _ => {
+                let top = q.parsed("top")?;
+                let min_commits = q.parsed("min-commits")?;
+                let by_author = q.flag("by-author");
+                let by_dir = q.parsed("by-dir")?;
+                q.finish()?;
+
+                Effort::new(
+                    EffortArgs::builder(path)
+                        .start_date(start_date)
+                        .end_date(end_date)
+                        .format(OutputFormat::Json)
+                        .include(include)
+                        .exclude(exclude)
+                        .restrict_authors(restrict_authors)
+                        .top(top)
+                        .min_commits(min_commits)
+                        .by_author(by_author)
+                        .by_dir(by_dir)
+                        .build()
+                        .map_err(|e| BadRequest(e.to_string()))?,
+                )
+                .results_table()
+            }
+        }
+    }
+
+    /// reads a line of the request, failing once the request is too large or too slow
+    fn read_line(
+        reader: &mut Take<&mut dyn BufRead>,
+        line: &mut String,
+    ) -> Result<usize, Response> {
+        let read = reader.read_line(line).map_err(|e| match e.kind() {
+            ErrorKind::WouldBlock | ErrorKind::TimedOut => {
+                Response::error(408, "timed out reading the request")
+            }
+            _ => Response::error(400, "malformed request"),
+        })?;
+
+        if reader.limit() == 0 && !line.ends_with('\n') {
+            return Err(Response::error(
+                431,
+                "the request line and headers are too large",
+            ));
+        }
+
+        Ok(read)
+    }
+
+    /// the request line, read along with the headers up to `MAX_REQUEST_BYTES`
+    fn read_request(reader: &mut dyn BufRead) -> Result<String, Response> {
+        let mut reader = reader.take(MAX_REQUEST_BYTES);
+
+        let mut request_line = String::new();
+        Serve::read_line(&mut reader, &mut request_line)?;
+
+        // the headers are not used
+        let mut header = String::new();
+        while Serve::read_line(&mut reader, &mut header)? > 0 && !header.trim().is_empty() {
+            header.clear();
+        }
+
+        Ok(request_line)
+    }
+
+    /// answers one request read from `reader`, only GET is allowed
+    fn handle(path: &str, reader: &mut dyn BufRead, out: &mut dyn Write) -> Result<()> {
+        let response = match Serve::read_request(reader) {
+            Ok(request_line) => {
+                let mut parts = request_line.split_whitespace();
+
+                match (parts.next(), parts.next()) {
+                    (Some("GET"), Some(target)) => {
+                        info!("GET {}", target);
+                        Serve::route(path, target)
+                    }
+                    (Some(_), Some(_)) => Response::error(405, "only GET is supported"),
+                    _ => Response::error(400, "malformed request"),
+                }
+            }
+            Err(response) => response,
+        };
+
+        Serve::respond(out, &response)
+    }
+
+    fn respond(out: &mut dyn Write, response: &Response) -> Result<()> {
+        write!(
+            out,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            response.status,
+            response.reason(),
+            response.body.len(),
+            response.body
+        )?;
+        out.flush()?;
+
+        Ok(())
+    }
+
+    /// answers the connections sent down `receiver` one at a time, until the sender is gone
+    fn work(path: &str, receiver: &Mutex<mpsc::Receiver<TcpStream>>) {
+        loop {
+            // the lock is held while waiting for a connection, not while answering it
+            let stream = match receiver.lock().map(|r| r.recv()) {
+                Ok(Ok(s)) => s,
+                _ => return,
+            };
+
+            if let Err(e) = Serve::serve_connection(path, stream) {
+                error!("cannot answer the request: {}", e);
+            }
+        }
+    }
+
+    fn serve_connection(path: &str, stream: TcpStream) -> Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut stream = stream;
+
+        Serve::handle(path, &mut reader, &mut stream)
+    }
+}
+
+/// the commit HEAD points at now, as the repo may move while serving
+fn head(path: &str) -> Option<String> {
+    grit_utils::open_repo(path)
+        .ok()
+        .and_then(|r| r.head().ok().and_then(|h| h.target()))
+        .map(|oid| oid.to_string())
+}
+
+impl Processable<()> for Serve {
+    fn process(&self) -> Result<()> {
+        grit_utils::open_repo(&self.args.path)?;
+        cancel::keep_default_handler();
+
+        let listener =
+            TcpListener::bind((self.args.bind.as_str(), self.args.port)).map_err(|e| {
+                anyhow!(
+                    "cannot listen on {}:{}: {}",
+                    self.args.bind,
+                    self.args.port,
+                    e
+                )
+            })?;
+
+        eprintln!(
+            "serving /fame, /bydate and /effort on http://{}",
+            listener.local_addr()?
+        );
+
+        let (sender, receiver) = mpsc::sync_channel::<TcpStream>(MAX_CONNECTIONS);
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        for _ in 0..MAX_CONNECTIONS {
+            let (path, receiver) = (self.args.path.clone(), receiver.clone());
+            thread::spawn(move || Serve::work(&path, &receiver));
+        }
+
+        for stream in listener.incoming() {
+            let stream = stream.and_then(|s| {
+                s.set_read_timeout(Some(IO_TIMEOUT))?;
+                s.set_write_timeout(Some(IO_TIMEOUT))?;
+                Ok(s)
+            });
+
+            match stream.map(|s| sender.try_send(s)) {
+                Ok(Ok(())) => {}
+                Ok(Err(TrySendError::Full(mut s))) => {
+                    warn!("turned a request away, {} are waiting", MAX_CONNECTIONS);
+                    let busy = Response::error(503, "too many requests, try again later");
+                    if let Err(e) = Serve::respond(&mut s, &busy) {
+                        error!("cannot answer the request: {}", e);
+                    }
+                }
+                Ok(Err(TrySendError::Disconnected(_))) => {
+                    return Err(anyhow!("the request workers have stopped"))
+                }
+                Err(e) => error!("cannot accept a connection: {}", e),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::LevelFilter;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    #[test]
+    fn test_decode() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        assert_eq!(decode("src%2F*.rs").unwrap(), "src/*.rs");
+        assert_eq!(decode("Alice+Smith").unwrap(), "Alice Smith");
+        assert!(decode("100%").is_err());
+    }
+
+    #[test]
+    fn test_route() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let bydate = Serve::route(path, "/bydate?cumulative&end-date=2020-01-02");
+        assert_eq!(bydate.status, 200);
+        assert!(bydate.body.contains("\"command\":\"bydate\""));
+        assert!(bydate.body.contains(
+            "\"results\":[{\"date\":\"2020-01-01\",\"count\":1},{\"date\":\"2020-01-02\",\"count\":3}]"
+        ));
+
+        let fame = Serve::route(path, "/fame?sort=loc&include=src%2Fmain.rs");
+        assert_eq!(fame.status, 200);
+        assert!(fame.body.contains("\"Author\":\"Alice\""));
+        assert!(!fame.body.contains("\"Author\":\"Bob\""));
+
+        assert_eq!(Serve::route(path, "/effort?by-author").status, 200);
+        assert_eq!(Serve::route(path, "/nothing").status, 404);
+        assert_eq!(
+            Serve::route(path, "/fame?colour=red"),
+            Response::error(400, "unknown parameter 'colour'")
+        );
+        assert_eq!(
+            Serve::route(path, "/bydate?start-date=yesterday").status,
+            400
+        );
+        assert_eq!(
+            Serve::route(path, "/fame?start-date=2020-01-05&end-date=2020-01-01"),
+            Response::error(400, "start-date 2020-01-05 is after end-date 2020-01-01")
+        );
+    }
+
+    #[test]
+    fn test_handle() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let mut out: Vec<u8> = Vec::new();
+        Serve::handle(
+            ".",
+            &mut "POST /fame HTTP/1.1\r\nHost: localhost\r\n\r\n".as_bytes(),
+            &mut out,
+        )
+        .unwrap();
+
+        let response = String::from_utf8(out).unwrap();
+        assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+        assert!(response.ends_with("\r\n\r\n{\"error\":\"only GET is supported\"}"));
+    }
+
+    #[test]
+    fn test_handle_limits() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let handle = |request: &str| {
+            let mut out: Vec<u8> = Vec::new();
+            Serve::handle(".", &mut request.as_bytes(), &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        let long_target = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(10_000));
+        assert!(handle(&long_target).starts_with("HTTP/1.1 431 Request Header Fields Too Large"));
+
+        let many_headers = format!("GET /fame HTTP/1.1\r\n{}\r\n", "X-A: b\r\n".repeat(2_000));
+        assert!(handle(&many_headers).starts_with("HTTP/1.1 431 "));
+
+        assert!(handle("GET").starts_with("HTTP/1.1 400 Bad Request"));
+        assert!(handle("GET /nothing HTTP/1.1\r\n").starts_with("HTTP/1.1 404 Not Found"));
+    }
+
+    #[test]
+    fn test_handle_timeout() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        /// a client that sends half a request line and then nothing
+        struct Stalled(bool);
+
+        impl Read for Stalled {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                if std::mem::replace(&mut self.0, true) {
+                    return Err(std::io::Error::new(ErrorKind::WouldBlock, "timed out"));
+                }
+                buf[..4].copy_from_slice(b"GET ");
+                Ok(4)
+            }
+        }
+
+        let mut out: Vec<u8> = Vec::new();
+        Serve::handle(".", &mut BufReader::new(Stalled(false)), &mut out).unwrap();
+
+        let response = String::from_utf8(out).unwrap();
+        assert!(response.starts_with("HTTP/1.1 408 Request Timeout\r\n"));
+    }
+}
@@ -0,0 +1,627 @@
+use super::Processable;
+use crate::fame::fame_snapshot;
+use crate::identity;
+use crate::output;
+use crate::table::Table;
+use crate::utils::grit_utils;
+use crate::utils::grit_utils::OutputFormat;
+use anyhow::{anyhow, Result};
+use chrono::offset::Local;
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+/// the store inside the git directory, so it is never committed by accident
+const DEFAULT_STORE: &str = "grit-snapshots.jsonl";
+
+/// the store `grit snapshot` appends to and `grit trends` reads, `store` when given
+fn store_path(path: &str, store: Option<&str>) -> Result<String> {
+    match store {
+        Some(s) => Ok(s.to_string()),
+        None => {
+            let repo = grit_utils::open_repo(path)?;
+            Ok(grit_utils::common_dir(&repo)
+                .join(DEFAULT_STORE)
+                .to_string_lossy()
+                .to_string())
+        }
+    }
+}
+
+/// One author's ownership and activity when a snapshot was taken.  Commits and active
+/// days count the whole history up to the snapshot's HEAD.
+#[derive(Clone, Debug, PartialEq)]
+struct SnapshotRecord {
+    taken_at: String,
+    head: String,
+    author: String,
+    lines: i64,
+    files: i64,
+    /// percent of the lines
+    ownership: f64,
+    commits: i64,
+    active_days: i64,
+}
+
+impl SnapshotRecord {
+    /// the record as one line of the store
+    fn to_json(&self) -> String {
+        format!(
+            "{{\"taken_at\":{},\"head\":{},\"author\":{},\"lines\":{},\"files\":{},\"ownership\":{:.2},\"commits\":{},\"active_days\":{}}}",
+            grit_utils::json_string(&self.taken_at),
+            grit_utils::json_string(&self.head),
+            grit_utils::json_string(&self.author),
+            self.lines,
+            self.files,
+            self.ownership,
+            self.commits,
+            self.active_days
+        )
+    }
+
+    fn from_json(line: &str) -> Result<SnapshotRecord> {
+        let fields = parse_object(line)?;
+
+        let text = |k: &str| {
+            fields
+                .get(k)
+                .cloned()
+                .ok_or_else(|| anyhow!("snapshot record has no {}: {}", k, line))
+        };
+        let number = |k: &str| {
+            text(k)?
+                .parse::<f64>()
+                .map_err(|_| anyhow!("snapshot record has a bad {}: {}", k, line))
+        };
+
+        Ok(SnapshotRecord {
+            taken_at: text("taken_at")?,
+            head: text("head")?,
+            author: text("author")?,
+            lines: number("lines")? as i64,
+            files: number("files")? as i64,
+            ownership: number("ownership")?,
+            commits: number("commits")? as i64,
+            active_days: number("active_days")? as i64,
+        })
+    }
+
+    /// the day the snapshot was taken, in the local timezone
+    fn day(&self) -> Option<NaiveDate> {
+        DateTime::parse_from_rfc3339(&self.taken_at)
+            .ok()
+            .map(|t| t.with_timezone(&Local).date_naive())
+    }
+}
+
+/// the keys and values of a flat JSON object, the numbers kept as written
+fn parse_object(line: &str) -> Result<HashMap<String, String>> {
+    let bad = || anyhow!("not a snapshot record: {}", line);
+
+    let mut chars = line.trim().chars().peekable();
+    let mut fields = HashMap::new();
+
+    if chars.next() != Some('{') {
+        return Err(bad());
+    }
+
+    let string = |chars: &mut std::iter::Peekable<std::str::Chars>| -> Option<String> {
+        let mut value = String::new();
+
+        loop {
+            match chars.next()? {
+                '"' => return Some(value),
+                '\\' => match chars.next()? {
+                    'n' => value.push('\n'),
+                    'r' => value.push('\r'),
+                    't' => value.push('\t'),
+                    'u' => {
+                        let code: String = chars.by_ref().take(4).collect();
+                        value.push(char::from_u32(u32::from_str_radix(&code, 16).ok()?)?);
+                    }
+                    c => value.push(c),
+                },
+                c => value.push(c),
+            }
+        }
+    };
+
+    loop {
+        match chars.next() {
+            Some('}') if fields.is_empty() => break,
+            Some('"') => {}
+            _ => return Err(bad()),
+        }
+
+        let key = string(&mut chars).ok_or_else(bad)?;
+
+        if chars.next() != Some(':') {
+            return Err(bad());
+        }
+
+        let value = if chars.peek() == Some(&'"') {
+            chars.next();
+            string(&mut chars).ok_or_else(bad)?
+        } else {
+            let mut number = String::new();
+            while let Some(c) = chars.next_if(|c| *c != ',' && *c != '}') {
+                number.push(c);
+            }
+            number
+        };
+
+        fields.insert(key, value);
+
+        match chars.next() {
+            Some(',') => continue,
+            Some('}') => break,
+            _ => return Err(bad()),
+        }
+    }
+
+    Ok(fields)
+}
+
+/// the records in the store, grouped per snapshot in the order they were taken
+fn read_snapshots(store: &str) -> Result<Vec<Vec<SnapshotRecord>>> {
+    let file = File::open(store).map_err(|e| anyhow!("cannot read {}: {}", store, e))?;
+
+    let mut snapshots: Vec<Vec<SnapshotRecord>> = Vec::new();
+
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let record = SnapshotRecord::from_json(&line)?;
+
+        match snapshots.last_mut() {
+            Some(s) if s[0].taken_at == record.taken_at => s.push(record),
+            _ => snapshots.push(vec![record]),
+        }
+    }
+
+    Ok(snapshots)
+}
+
+/// An author's commits and the days they were made on.
+#[derive(Default)]
+struct Activity {
+    commits: HashSet<git2::Oid>,
+    days: HashSet<NaiveDate>,
+}
+
+pub struct SnapshotArgs {
+    path: String,
+    include: Option<String>,
+    exclude: Option<String>,
+    store: Option<String>,
+}
+
+impl SnapshotArgs {
+    pub fn new(
+        path: String,
+        include: Option<String>,
+        exclude: Option<String>,
+        store: Option<String>,
+    ) -> SnapshotArgs {
+        SnapshotArgs {
+            path,
+            include,
+            exclude,
+            store,
+        }
+    }
+}
+
+pub struct Snapshot {
+    args: SnapshotArgs,
+}
+
+impl Snapshot {
+    pub fn new(args: SnapshotArgs) -> Snapshot {
+        Snapshot { args }
+    }
+
+    /// the commits and active days of every author, merges left out
+    fn activity(&self) -> Result<HashMap<String, Activity>> {
+        let repo = grit_utils::open_repo(&self.args.path)?;
+
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push_head()?;
+
+        let mut activity: HashMap<String, Activity> = HashMap::new();
+
+        for oid in revwalk {
+            let commit = repo.find_commit(oid?)?;
+
+            if commit.parent_count() > 1 {
+                continue;
+            }
+
+            let author = commit.author();
+            let name = String::from_utf8_lossy(author.name_bytes()).to_string();
+            let email = String::from_utf8_lossy(author.email_bytes()).to_string();
+
+            if identity::is_excluded(&name, &email) {
+                continue;
+            }
+
+            let a = activity
+                .entry(identity::display_author(&name, &email))
+                .or_default();
+            a.commits.insert(commit.id());
+            a.days.insert(grit_utils::convert_git_time(&commit.time()));
+        }
+
+        Ok(activity)
+    }
+
+    /// a record for every author owning lines or having commits, most lines first
+    fn take(&self, taken_at: &str) -> Result<Vec<SnapshotRecord>> {
+        let repo = grit_utils::open_repo(&self.args.path)?;
+        let head = repo.head()?.peel_to_commit()?.id().to_string();
+
+        let file_names = grit_utils::generate_file_list(
+            &self.args.path,
+            self.args.include.clone(),
+            self.args.exclude.clone(),
+        )?;
+
+        let fame = fame_snapshot(&self.args.path, &file_names, None, None, None)?;
+        grit_utils::report_skipped(&fame.skipped, false)?;
+
+        let activity = self.activity()?;
+
+        let authors: BTreeSet<&str> = fame
+            .output
+            .iter()
+            .map(|o| o.author.as_str())
+            .chain(activity.keys().map(String::as_str))
+            .collect();
+
+        let mut records: Vec<SnapshotRecord> = authors
+            .into_iter()
+            .map(|a| {
+                let owned = fame.output.iter().find(|o| o.author == a);
+                let active = activity.get(a);
+
+                SnapshotRecord {
+                    taken_at: taken_at.to_string(),
+                    head: head.clone(),
+                    author: a.to_string(),
+                    lines: owned.map(|o| o.lines as i64).unwrap_or_default(),
+                    files: owned.map(|o| o.file_count as i64).unwrap_or_default(),
+                    ownership: owned.map(|o| o.perc_lines * 100.0).unwrap_or_default(),
+                    commits: active.map(|a| a.commits.len() as i64).unwrap_or_default(),
+                    active_days: active.map(|a| a.days.len() as i64).unwrap_or_default(),
+                }
+            })
+            .collect();
+
+        records.sort_by(|a, b| b.lines.cmp(&a.lines).then(a.author.cmp(&b.author)));
+
+        Ok(records)
+    }
+}
+
+impl Processable<()> for Snapshot {
+    fn process(&self) -> Result<()> {
+        let store = store_path(&self.args.path, self.args.store.as_deref())?;
+
+        let records = self.take(&Utc::now().to_rfc3339())?;
+
+        if let Some(dir) = Path::new(&store).parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let mut file = OpenOptions::new().create(true).append(true).open(&store)?;
+
+        for r in records.iter() {
+            writeln!(file, "{}", r.to_json())?;
+        }
+
+        info!("recorded {} authors in {}", records.len(), store);
+
+        Ok(())
+    }
+}
+
+pub struct TrendsArgs {
+    path: String,
+    store: Option<String>,
+    since: Option<NaiveDate>,
+    file: Option<String>,
+    format: OutputFormat,
+}
+
+impl TrendsArgs {
+    pub fn new(
+        path: String,
+        store: Option<String>,
+        since: Option<NaiveDate>,
+        file: Option<String>,
+        format: OutputFormat,
+    ) -> TrendsArgs {
+        TrendsArgs {
+            path,
+            store,
+            since,
+            file,
+            format,
+        }
+    }
+}
+
+/// How an author's ownership and activity changed from one snapshot to another.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct TrendLine {
+    author: String,
+    ownership_from: f64,
+    ownership_to: f64,
+    lines_delta: i64,
+    commits_delta: i64,
+    active_days_delta: i64,
+}
+
+impl TrendLine {
+    /// in percentage points
+    fn ownership_delta(&self) -> f64 {
+        self.ownership_to - self.ownership_from
+    }
+}
+
+pub struct Trends {
+    args: TrendsArgs,
+}
+
+impl Trends {
+    pub fn new(args: TrendsArgs) -> Trends {
+        Trends { args }
+    }
+
+    /// the snapshots compared, the first taken on or after `--since` and the latest
+    fn select(&self, mut snapshots: Vec<Vec<SnapshotRecord>>) -> Result<TrendWindow> {
+        let since = self.args.since;
+
+        let from = snapshots
+            .iter()
+            .position(|s| since.map(|d| s[0].day() >= Some(d)).unwrap_or(true));
+
+        match from {
+            Some(i) if i + 1 < snapshots.len() => {
+                let to = snapshots.pop().unwrap();
+                let from = snapshots.swap_remove(i);
+                Ok(TrendWindow { from, to })
+            }
+            _ => Err(anyhow!(
+                "trends needs at least two snapshots, run grit snapshot again later"
+            )),
+        }
+    }
+
+    /// pairs up the authors of both snapshots, biggest ownership gain first
+    fn trends(window: &TrendWindow) -> Vec<TrendLine> {
+        let from: HashMap<&str, &SnapshotRecord> =
+            window.from.iter().map(|r| (r.author.as_str(), r)).collect();
+        let to: HashMap<&str, &SnapshotRecord> =
+            window.to.iter().map(|r| (r.author.as_str(), r)).collect();
+
+        let authors: BTreeSet<&str> = from.keys().chain(to.keys()).cloned().collect();
+
+        let mut result: Vec<TrendLine> = authors
+            .into_iter()
+            .map(|a| {
+                let f = from.get(a);
+                let t = to.get(a);
+                let value = |r: Option<&&SnapshotRecord>, v: fn(&SnapshotRecord) -> i64| {
+                    r.map(|r| v(r)).unwrap_or_default()
+                };
+
+                TrendLine {
+                    author: a.to_string(),
+                    ownership_from: f.map(|r| r.ownership).unwrap_or_default(),
+                    ownership_to: t.map(|r| r.ownership).unwrap_or_default(),
+                    lines_delta: value(t, |r| r.lines) - value(f, |r| r.lines),
+                    commits_delta: value(t, |r| r.commits) - value(f, |r| r.commits),
+                    active_days_delta: value(t, |r| r.active_days) - value(f, |r| r.active_days),
+                }
+            })
+            .collect();
+
+        result.sort_by(|a, b| {
+            b.ownership_delta()
+                .partial_cmp(&a.ownership_delta())
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(b.commits_delta.cmp(&a.commits_delta))
+                .then(a.author.cmp(&b.author))
+        });
+
+        result
+    }
+
+    fn display_output(&self, window: &TrendWindow, output: &[TrendLine]) -> Result<()> {
+        let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+
+        if self.args.format == OutputFormat::Table {
+            writeln!(
+                w,
+                "Trends from {} to {}",
+                window.from[0].taken_at, window.to[0].taken_at
+            )?;
+        }
+
+        let mut table = Table::new();
+
+        table.set_titles(table_row![
+            "author",
+            "ownership % from",
+            "ownership % to",
+            "ownership change (pp)",
+            "lines change",
+            "commits",
+            "active days"
+        ]);
+
+        output.iter().for_each(|t| {
+            table.add_row(table_row![
+                t.author,
+                output::percent(t.ownership_from),
+                output::percent(t.ownership_to),
+                format!("{:+.*}", output::precision(), t.ownership_delta()),
+                format!("{:+}", t.lines_delta),
+                t.commits_delta,
+                t.active_days_delta
+            ]);
+        });
+
+        table.add_label(0, "author");
+        table.add_metric(
+            3,
+            "grit_trend_ownership_change",
+            "Change in the author's share of the lines between snapshots, in percentage points.",
+        );
+        table.add_metric(
+            5,
+            "grit_trend_commits",
+            "Commits the author made between snapshots.",
+        );
+
+        table.write(&mut w, self.args.format)?;
+
+        Ok(())
+    }
+}
+
+/// The snapshots `grit trends` compares.
+struct TrendWindow {
+    from: Vec<SnapshotRecord>,
+    to: Vec<SnapshotRecord>,
+}
+
+impl Processable<()> for Trends {
+    fn process(&self) -> Result<()> {
+        let store = store_path(&self.args.path, self.args.store.as_deref())?;
+
+        let window = self.select(read_snapshots(&store)?)?;
+
+        let output = Trends::trends(&window);
+
+        self.display_output(&window, &output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::{Repository, Signature, Time};
+    use log::LevelFilter;
+    use std::fs;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    #[test]
+    fn test_snapshot_record_json() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let record = SnapshotRecord {
+            taken_at: String::from("2020-01-06T12:00:00+00:00"),
+            head: String::from("abc"),
+            author: String::from("Zoë \"z\" O'Neil\t"),
+            lines: 5,
+            files: 2,
+            ownership: 55.556,
+            commits: 3,
+            active_days: 2,
+        };
+
+        let json = record.to_json();
+        assert_eq!(
+            json,
+            "{\"taken_at\":\"2020-01-06T12:00:00+00:00\",\"head\":\"abc\",\"author\":\"Zoë \\\"z\\\" O'Neil\\t\",\"lines\":5,\"files\":2,\"ownership\":55.56,\"commits\":3,\"active_days\":2}"
+        );
+
+        let parsed = SnapshotRecord::from_json(&json).unwrap();
+        assert_eq!(parsed.author, record.author);
+        assert_eq!(parsed.ownership, 55.56);
+        assert_eq!(parsed.active_days, 2);
+
+        assert!(SnapshotRecord::from_json("{\"taken_at\":\"x\"}").is_err());
+        assert!(SnapshotRecord::from_json("not json").is_err());
+    }
+
+    #[test]
+    fn test_snapshot_and_trends() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+        let store = format!("{}/snapshots.jsonl", td.path().to_str().unwrap());
+
+        let snapshot = || {
+            Snapshot::new(SnapshotArgs::new(
+                path.to_string(),
+                None,
+                None,
+                Some(store.clone()),
+            ))
+        };
+        let trends = || {
+            Trends::new(TrendsArgs::new(
+                path.to_string(),
+                Some(store.clone()),
+                None,
+                Some(String::from("target/test_trends.csv")),
+                OutputFormat::Csv,
+            ))
+        };
+
+        snapshot().process().unwrap();
+
+        let first = read_snapshots(&store).unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(
+            first[0]
+                .iter()
+                .map(|r| (
+                    r.author.as_str(),
+                    r.lines,
+                    r.files,
+                    r.commits,
+                    r.active_days
+                ))
+                .collect::<Vec<_>>(),
+            vec![("Alice", 6, 2, 2, 2), ("Bob", 5, 2, 2, 2)]
+        );
+        assert!(trends().process().is_err());
+
+        // Carol rewrites src/lib.rs a day after the fixture
+        let repo = Repository::open(td.path()).unwrap();
+        fs::write(td.path().join("src/lib.rs"), "a\nb\nc\nd\ne\nf\ng\nh\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("src/lib.rs")).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let sig =
+            Signature::new("Carol", "carol@example.com", &Time::new(1_578_312_000, 0)).unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "rewrite lib", &tree, &[&head])
+            .unwrap();
+
+        snapshot().process().unwrap();
+        trends().process().unwrap();
+
+        let written = fs::read_to_string("target/test_trends.csv").unwrap();
+        assert_eq!(
+            written,
+            "author,ownership % from,ownership % to,ownership change (pp),lines change,commits,active days\n\
+             Carol,0.0,53.3,+53.3,+8,1,1\n\
+             Alice,54.5,40.0,-14.5,+0,0,0\n\
+             Bob,45.5,6.7,-38.8,-4,0,0\n"
+        );
+    }
+}
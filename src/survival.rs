@@ -0,0 +1,508 @@
+use super::Processable;
+use crate::chart;
+use crate::chart::ChartOptions;
+use crate::growth::Growth;
+use crate::output;
+use crate::ownership::SnapshotPeriod;
+use crate::table::Table;
+use crate::utils::grit_utils;
+use crate::utils::grit_utils::OutputFormat;
+use anyhow::{anyhow, Result};
+use charts::{LineSeriesView, MarkerType, PointDatum, PointLabelPosition, ScaleBand, ScaleLinear};
+use chrono::NaiveDate;
+use git2::{Delta, DiffFindOptions, DiffOptions, Oid, Patch, Repository, Tree};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+pub struct SurvivalArgs {
+    path: String,
+    interval: SnapshotPeriod,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+    include: Option<String>,
+    exclude: Option<String>,
+    file: Option<String>,
+    image: bool,
+    html: bool,
+    chart_options: ChartOptions,
+    format: OutputFormat,
+}
+
+impl SurvivalArgs {
+    pub fn builder(path: String) -> SurvivalArgsBuilder {
+        SurvivalArgsBuilder {
+            args: SurvivalArgs {
+                path,
+                interval: SnapshotPeriod::Monthly,
+                start_date: None,
+                end_date: None,
+                include: None,
+                exclude: None,
+                file: None,
+                image: false,
+                html: false,
+                chart_options: ChartOptions::default(),
+                format: OutputFormat::Csv,
+            },
+        }
+    }
+}
+
+/// Builds a `SurvivalArgs` by name rather than position, validating it on `build`.
+pub struct SurvivalArgsBuilder {
+    args: SurvivalArgs,
+}
+
+impl SurvivalArgsBuilder {
+    pub fn interval(mut self, interval: SnapshotPeriod) -> SurvivalArgsBuilder {
+        self.args.interval = interval;
+        self
+    }
+
+    pub fn start_date(mut self, start_date: Option<NaiveDate>) -> SurvivalArgsBuilder {
+        self.args.start_date = start_date;
+        self
+    }
+
+    pub fn end_date(mut self, end_date: Option<NaiveDate>) -> SurvivalArgsBuilder {
+        self.args.end_date = end_date;
+        self
+    }
+
+    pub fn include(mut self, include: Option<String>) -> SurvivalArgsBuilder {
+        self.args.include = include;
+        self
+    }
+
+    pub fn exclude(mut self, exclude: Option<String>) -> SurvivalArgsBuilder {
+        self.args.exclude = exclude;
+        self
+    }
+
+    pub fn file(mut self, file: Option<String>) -> SurvivalArgsBuilder {
+        self.args.file = file;
+        self
+    }
+
+    pub fn image(mut self, image: bool) -> SurvivalArgsBuilder {
+        self.args.image = image;
+        self
+    }
+
+    pub fn html(mut self, html: bool) -> SurvivalArgsBuilder {
+        self.args.html = html;
+        self
+    }
+
+    pub fn chart_options(mut self, chart_options: ChartOptions) -> SurvivalArgsBuilder {
+        self.args.chart_options = chart_options;
+        self
+    }
+
+    pub fn format(mut self, format: OutputFormat) -> SurvivalArgsBuilder {
+        self.args.format = format;
+        self
+    }
+
+    pub fn build(self) -> Result<SurvivalArgs> {
+        grit_utils::validate_args(
+            self.args.start_date,
+            self.args.end_date,
+            self.args.file.as_deref(),
+            self.args.format,
+        )?;
+
+        Ok(self.args)
+    }
+}
+
+/// A point of the survival curve: of the lines added by `cohorts` samples, how many
+/// were still there `periods` samples later.
+#[derive(Clone, Debug, PartialEq)]
+struct SurvivalOutput {
+    periods: usize,
+    cohorts: usize,
+    lines: i64,
+    surviving: i64,
+}
+
+impl SurvivalOutput {
+    fn survival(&self) -> f64 {
+        if self.lines == 0 {
+            0.0
+        } else {
+            self.surviving as f64 / self.lines as f64
+        }
+    }
+}
+
+impl PointDatum<String, f32> for SurvivalOutput {
+    fn get_x(&self) -> String {
+        self.periods.to_string()
+    }
+
+    fn get_y(&self) -> f32 {
+        (self.survival() * 100.0) as f32
+    }
+
+    fn get_key(&self) -> String {
+        String::from("")
+    }
+}
+
+/// the sample each line of a file was added in, by line
+type FileCohorts = HashMap<String, Vec<usize>>;
+
+pub struct Survival {
+    args: SurvivalArgs,
+}
+
+impl Survival {
+    pub fn new(args: SurvivalArgs) -> Survival {
+        Survival { args }
+    }
+
+    /// the lines of `old` after applying the zero context hunks of `patch`, with the added
+    /// lines marked as `cohort`
+    fn apply_patch(patch: &Patch, old: &[usize], cohort: usize) -> Result<Vec<usize>> {
+        let mut lines: Vec<usize> = Vec::with_capacity(old.len());
+        let mut old_pos = 0;
+
+        for h in 0..patch.num_hunks() {
+            let (hunk, _) = patch.hunk(h)?;
+            let old_start = hunk.old_start() as usize;
+            let old_lines = hunk.old_lines() as usize;
+
+            // a pure insertion goes after line old_start, a change replaces it onwards
+            let unchanged = if old_lines == 0 {
+                old_start
+            } else {
+                old_start - 1
+            }
+            .min(old.len());
+
+            if unchanged > old_pos {
+                lines.extend_from_slice(&old[old_pos..unchanged]);
+            }
+            old_pos = (unchanged + old_lines).max(old_pos);
+            lines.extend(std::iter::repeat_n(cohort, hunk.new_lines() as usize));
+        }
+
+        if old_pos < old.len() {
+            lines.extend_from_slice(&old[old_pos..]);
+        }
+
+        Ok(lines)
+    }
+
+    /// moves `files` from the `old` tree to `new`, marking the lines added on the way
+    fn diff_forward(
+        &self,
+        repo: &Repository,
+        files: &mut FileCohorts,
+        old: Option<&Tree>,
+        new: &Tree,
+        cohort: usize,
+    ) -> Result<()> {
+        let mut opts = DiffOptions::new();
+        opts.context_lines(0);
+
+        let mut diff = repo.diff_tree_to_tree(old, Some(new), Some(&mut opts))?;
+        diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+
+        let paths: Vec<String> = diff
+            .deltas()
+            .filter_map(|d| d.new_file().path_bytes())
+            .map(grit_utils::file_name_from_bytes)
+            .collect();
+
+        let keep: HashSet<String> = grit_utils::filter_file_names(
+            paths,
+            self.args.include.clone(),
+            self.args.exclude.clone(),
+        )?
+        .into_iter()
+        .collect();
+
+        for idx in 0..diff.deltas().len() {
+            let delta = diff.get_delta(idx).unwrap();
+            let path_of = |f: git2::DiffFile| f.path_bytes().map(grit_utils::file_name_from_bytes);
+            let old_path = path_of(delta.old_file());
+            let new_path = path_of(delta.new_file());
+
+            let previous = match (delta.status(), &old_path) {
+                (Delta::Added, _) | (_, None) => Vec::new(),
+                (Delta::Copied, Some(p)) => files.get(p).cloned().unwrap_or_default(),
+                (_, Some(p)) => files.remove(p).unwrap_or_default(),
+            };
+
+            let new_path = match new_path {
+                Some(p) if delta.status() != Delta::Deleted && keep.contains(&p) => p,
+                _ => continue,
+            };
+
+            let lines = match Patch::from_diff(&diff, idx)? {
+                Some(p) if !delta.flags().is_binary() => {
+                    Survival::apply_patch(&p, &previous, cohort)?
+                }
+                _ => continue,
+            };
+
+            files.insert(new_path, lines);
+        }
+
+        Ok(())
+    }
+
+    /// the lines of each sample still alive at every later sample, as
+    /// `alive[cohort][sample]`
+    fn cohort_lines(&self) -> Result<Vec<Vec<i64>>> {
+        let repo = grit_utils::open_repo(&self.args.path)?;
+        let history = Growth::history(&repo)?;
+
+        let first = match history.first() {
+            Some(h) => h,
+            None => return Err(anyhow!("repository has no commits")),
+        };
+
+        let start_date = match self.args.start_date {
+            Some(d) => d,
+            None => grit_utils::convert_git_time(&git2::Time::new(first.0, 0)),
+        };
+        let end_date = self.args.end_date.unwrap_or_else(grit_utils::today);
+
+        let mut files: FileCohorts = HashMap::new();
+        let mut previous: Option<(Oid, Tree)> = None;
+        let mut alive: Vec<Vec<i64>> = Vec::new();
+        let mut next = 0;
+
+        for period_end in self.args.interval.period_ends(start_date, end_date) {
+            let end_sec = grit_utils::end_of_day_seconds(period_end);
+
+            while next < history.len() && history[next].0 <= end_sec {
+                next += 1;
+            }
+
+            if next == 0 {
+                continue;
+            }
+
+            let oid = history[next - 1].1;
+            let sample = alive.len();
+
+            match &previous {
+                Some((prev_oid, _)) if *prev_oid == oid => {}
+                _ => {
+                    let tree = repo.find_commit(oid)?.tree()?;
+                    self.diff_forward(
+                        &repo,
+                        &mut files,
+                        previous.as_ref().map(|p| &p.1),
+                        &tree,
+                        sample,
+                    )?;
+                    previous = Some((oid, tree));
+                }
+            }
+
+            alive.push(Vec::new());
+
+            let mut counts: Vec<i64> = vec![0; sample + 1];
+            files.values().flatten().for_each(|c| counts[*c] += 1);
+
+            for (cohort, count) in counts.into_iter().enumerate() {
+                alive[cohort].push(count);
+            }
+        }
+
+        Ok(alive)
+    }
+
+    /// pools the cohorts into the share of lines surviving each number of periods
+    fn survival_curve(alive: &[Vec<i64>]) -> Vec<SurvivalOutput> {
+        let mut curve: Vec<SurvivalOutput> = Vec::new();
+
+        for periods in 0..alive.len() {
+            let mut point = SurvivalOutput {
+                periods,
+                cohorts: 0,
+                lines: 0,
+                surviving: 0,
+            };
+
+            for counts in alive.iter().filter(|c| c.len() > periods) {
+                if counts[0] > 0 {
+                    point.cohorts += 1;
+                    point.lines += counts[0];
+                    point.surviving += counts[periods];
+                }
+            }
+
+            if point.cohorts > 0 {
+                curve.push(point);
+            }
+        }
+
+        curve
+    }
+
+    fn display_text_output(&self, output: &[SurvivalOutput]) -> Result<()> {
+        let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+
+        let mut table = Table::new();
+
+        table.set_titles(table_row![
+            "periods",
+            "cohorts",
+            "lines",
+            "surviving",
+            "survival (%)"
+        ]);
+
+        output.iter().for_each(|r| {
+            table.add_row(table_row![
+                r.periods,
+                r.cohorts,
+                r.lines,
+                r.surviving,
+                output::percent(r.survival() * 100.0)
+            ]);
+        });
+
+        table.write(&mut w, self.args.format)?;
+
+        Ok(())
+    }
+
+    fn create_output_image(&self, output: &[SurvivalOutput]) -> Result<()> {
+        let file = self
+            .args
+            .file
+            .clone()
+            .unwrap_or_else(|| String::from("survival.svg"));
+
+        if !grit_utils::check_file_type(&file, "svg") {
+            return Err(anyhow!("the image file format must be svg"));
+        }
+
+        let (width, height) = self.args.chart_options.size(if output.len() > 60 {
+            (1920, 960)
+        } else if output.len() > 35 {
+            (1280, 960)
+        } else {
+            (1027, 768)
+        });
+        let (top, right, bottom, left) = (90, 40, 80, 80);
+        let periods = output.iter().map(|o| o.periods.to_string()).collect();
+        let x = ScaleBand::new()
+            .set_domain(periods)
+            .set_range(vec![0, width - left - right]);
+        let y = ScaleLinear::new()
+            .set_domain(vec![0_f32, 105_f32])
+            .set_range(vec![height - top - bottom, 0]);
+        let line_view = LineSeriesView::new()
+            .set_x_scale(&x)
+            .set_y_scale(&y)
+            .set_marker_type(MarkerType::Circle)
+            .set_label_position(PointLabelPosition::NW)
+            .set_label_visibility(false)
+            .set_colors(self.args.chart_options.theme.colors())
+            .load_data(&output.to_vec())
+            .map_err(|e| anyhow!("Failed to create Line View: {}", e))?;
+        chart::configure_chart(&self.args.chart_options, (width, height), "Line Survival")
+            .set_margins(top, right, bottom, left)
+            .add_view(&line_view)
+            .add_axis_bottom(&x)
+            .add_axis_left(&y)
+            .add_left_axis_label("Surviving Lines (%)")
+            .add_bottom_axis_label("Periods Since Added")
+            .save(Path::new(&file))
+            .map_err(|e| anyhow!("Failed to create Chart: {}", e))?;
+
+        if self.args.html {
+            grit_utils::create_html(&file)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Processable<()> for Survival {
+    fn process(&self) -> Result<()> {
+        let output = Survival::survival_curve(&self.cohort_lines()?);
+
+        if self.args.image {
+            self.create_output_image(&output)
+        } else {
+            self.display_text_output(&output)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use log::LevelFilter;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    #[test]
+    fn test_survival_curve() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let survival = Survival::new(
+            SurvivalArgs::builder(path.to_string())
+                .interval(SnapshotPeriod::Daily)
+                .end_date(Some(NaiveDate::from_ymd_opt(2020, 1, 5).unwrap()))
+                .build()
+                .unwrap(),
+        );
+
+        // 5 lines on 2020-01-01, 6 on 2020-01-02 and 1 on 2020-01-05, which replaced
+        // a README line from the first day
+        let alive = survival.cohort_lines().unwrap();
+
+        assert_eq!(alive[0], vec![5, 5, 5, 5, 4]);
+        assert_eq!(alive[1], vec![6, 6, 6, 6]);
+        assert_eq!(alive[4], vec![1]);
+
+        let curve: Vec<(usize, usize, i64, i64)> = Survival::survival_curve(&alive)
+            .iter()
+            .map(|p| (p.periods, p.cohorts, p.lines, p.surviving))
+            .collect();
+
+        assert_eq!(
+            curve,
+            vec![
+                (0, 3, 12, 12),
+                (1, 2, 11, 11),
+                (2, 2, 11, 11),
+                (3, 2, 11, 11),
+                (4, 1, 5, 4)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_survival_include() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let survival = Survival::new(
+            SurvivalArgs::builder(path.to_string())
+                .end_date(Some(NaiveDate::from_ymd_opt(2020, 1, 31).unwrap()))
+                .include(Some(String::from("src/*.rs")))
+                .build()
+                .unwrap(),
+        );
+
+        assert_eq!(survival.cohort_lines().unwrap(), vec![vec![9]]);
+    }
+}
@@ -0,0 +1,672 @@
+use super::Processable;
+use crate::identity;
+use crate::ignore_revs;
+use crate::output;
+use crate::progress::Progress;
+use crate::table::Table;
+use crate::utils::grit_utils;
+use crate::utils::grit_utils::{OutputFormat, RelativeTo, SkippedFile};
+use anyhow::Result;
+use chrono::NaiveDate;
+use git2::{Repository, Tree};
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// the extensions of the languages symbols can be found in
+const SUPPORTED_EXTENSIONS: [&str; 1] = ["rs"];
+
+/// item kinds that only name the symbols inside them
+const SCOPE_KINDS: [&str; 3] = ["impl", "trait", "mod"];
+
+/// the display name of the author of a line and the day it was written, None when the
+/// author is excluded
+type BlamedLine = Option<(String, NaiveDate)>;
+
+static ITEM_REGEX: OnceLock<Regex> = OnceLock::new();
+
+pub struct SymbolsArgs {
+    path: String,
+    include: Option<String>,
+    exclude: Option<String>,
+    file: Option<String>,
+    format: OutputFormat,
+    relative_to: RelativeTo,
+    strict: bool,
+}
+
+impl SymbolsArgs {
+    pub fn new(
+        path: String,
+        include: Option<String>,
+        exclude: Option<String>,
+        file: Option<String>,
+        format: OutputFormat,
+        relative_to: RelativeTo,
+        strict: bool,
+    ) -> SymbolsArgs {
+        SymbolsArgs {
+            path,
+            include,
+            exclude,
+            file,
+            format,
+            relative_to,
+            strict,
+        }
+    }
+}
+
+/// An item of a source file and the 1-based, inclusive lines it spans.
+#[derive(Clone, Debug, PartialEq)]
+struct Symbol {
+    kind: String,
+    name: String,
+    start: usize,
+    end: usize,
+}
+
+/// `source` with comments and the contents of string and char literals blanked out, every
+/// newline kept, so braces and keywords inside them aren't taken for code
+fn code_only(source: &str) -> Vec<char> {
+    let chars: Vec<char> = source.chars().collect();
+    let blank = |c: char| if c == '\n' { '\n' } else { ' ' };
+    let is_ident = |c: Option<&char>| c.is_some_and(|c| c.is_alphanumeric() || *c == '_');
+
+    let mut code: Vec<char> = Vec::with_capacity(chars.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        // the length of the literal or comment starting at i, blanked out below
+        let skip = if c == '/' && next == Some('/') {
+            chars[i..]
+                .iter()
+                .position(|c| *c == '\n')
+                .unwrap_or(chars.len() - i)
+        } else if c == '/' && next == Some('*') {
+            let mut depth = 0;
+            let mut j = i;
+            while j < chars.len() {
+                match (chars[j], chars.get(j + 1)) {
+                    ('/', Some('*')) => {
+                        depth += 1;
+                        j += 2;
+                    }
+                    ('*', Some('/')) => {
+                        depth -= 1;
+                        j += 2;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    _ => j += 1,
+                }
+            }
+            j - i
+        } else if c == 'r'
+            && (!is_ident(i.checked_sub(1).and_then(|p| chars.get(p)))
+                || (i >= 1 && chars[i - 1] == 'b' && !is_ident(chars.get(i.wrapping_sub(2)))))
+            && matches!(next, Some('"') | Some('#'))
+        {
+            let hashes = chars[i + 1..].iter().take_while(|c| **c == '#').count();
+            if chars.get(i + 1 + hashes) == Some(&'"') {
+                let close: String = std::iter::once('"')
+                    .chain(std::iter::repeat_n('#', hashes))
+                    .collect();
+                let rest: String = chars[i + 2 + hashes..].iter().collect();
+                let end = rest
+                    .find(&close)
+                    .map(|e| rest[..e].chars().count() + close.len())
+                    .unwrap_or(chars.len() - i - 2 - hashes);
+                2 + hashes + end
+            } else {
+                0
+            }
+        } else if c == '"' {
+            let mut j = i + 1;
+            while j < chars.len() && chars[j] != '"' {
+                j += if chars[j] == '\\' { 2 } else { 1 };
+            }
+            (j + 1).min(chars.len()) - i
+        } else if c == '\'' && next == Some('\\') {
+            chars[i + 2..]
+                .iter()
+                .position(|c| *c == '\'')
+                .map(|p| p + 3)
+                .unwrap_or(1)
+        } else if c == '\'' && chars.get(i + 2) == Some(&'\'') {
+            3
+        } else {
+            0
+        };
+
+        if skip == 0 {
+            code.push(c);
+            i += 1;
+        } else {
+            let end = (i + skip).min(chars.len());
+            code.extend(chars[i..end].iter().map(|c| blank(*c)));
+            i = end;
+        }
+    }
+
+    code
+}
+
+/// the type an `impl` line is for, from what follows the `impl` keyword
+fn impl_name(rest: &str) -> String {
+    // generics on the impl itself come first
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in rest.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            c if depth == 0 && !c.is_whitespace() => {
+                start = i;
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    let target = &rest[start..];
+    let target = match target.rfind(" for ") {
+        Some(f) => &target[f + 5..],
+        None => target,
+    };
+
+    target
+        .trim_start()
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == ':')
+        .collect()
+}
+
+/// the functions, structs, enums, traits, impls and modules of a Rust source file, in the
+/// order they start.  Items are found by their keywords at the start of a line and end at
+/// the brace closing their body, or at their `;`.
+fn rust_items(source: &str) -> Vec<Symbol> {
+    let regex = ITEM_REGEX.get_or_init(|| {
+        Regex::new(
+            r"^\s*(?:pub(?:\s*\([^)]*\))?\s+)?(?:(?:const|async|unsafe|default|extern)\s+)*(fn|struct|enum|trait|union|mod|impl)\b(.*)$",
+        )
+        .expect("invalid item regex")
+    });
+
+    let code = code_only(source);
+    let text: String = code.iter().collect();
+
+    let mut line_starts: Vec<usize> = vec![0];
+    line_starts.extend(
+        code.iter()
+            .enumerate()
+            .filter(|(_, c)| **c == '\n')
+            .map(|(i, _)| i + 1),
+    );
+    let line_of = |offset: usize| line_starts.partition_point(|s| *s <= offset);
+
+    let mut items: Vec<Symbol> = Vec::new();
+
+    for (l, line) in text.lines().enumerate() {
+        let caps = match regex.captures(line) {
+            Some(c) => c,
+            None => continue,
+        };
+
+        let kind = caps[1].to_string();
+        let rest = &caps[2];
+        let name = if kind == "impl" {
+            impl_name(rest)
+        } else {
+            rest.trim_start()
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || *c == '_')
+                .collect()
+        };
+
+        if name.is_empty() {
+            continue;
+        }
+
+        // the body starts at the first brace outside parentheses and brackets, unless a
+        // `;` ends the item first
+        let mut depth = 0;
+        let mut end = None;
+        let mut i = line_starts[l];
+
+        while i < code.len() {
+            match code[i] {
+                '(' | '[' => depth += 1,
+                ')' | ']' => depth -= 1,
+                ';' if depth == 0 => {
+                    end = Some(i);
+                    break;
+                }
+                '{' if depth == 0 => {
+                    let mut braces = 0;
+                    for (j, c) in code.iter().enumerate().skip(i) {
+                        match c {
+                            '{' => braces += 1,
+                            '}' => braces -= 1,
+                            _ => continue,
+                        }
+                        if braces == 0 {
+                            end = Some(j);
+                            break;
+                        }
+                    }
+                    break;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+
+        if let Some(e) = end {
+            items.push(Symbol {
+                kind,
+                name,
+                start: l + 1,
+                end: line_of(e),
+            });
+        }
+    }
+
+    items
+}
+
+/// the symbols of a Rust source file, named with the impls, traits and modules they are
+/// in such as `tests::test_fame` or `Fame::process`.  Impls and modules are not symbols.
+fn rust_symbols(source: &str) -> Vec<Symbol> {
+    let items = rust_items(source);
+
+    items
+        .iter()
+        .filter(|s| s.kind != "impl" && s.kind != "mod")
+        .map(|s| {
+            let mut names: Vec<&str> = items
+                .iter()
+                .filter(|o| {
+                    SCOPE_KINDS.contains(&o.kind.as_str())
+                        && o.start <= s.start
+                        && o.end >= s.end
+                        && *o != s
+                })
+                .map(|o| o.name.as_str())
+                .collect();
+            names.push(&s.name);
+
+            Symbol {
+                name: names.join("::"),
+                ..s.clone()
+            }
+        })
+        .collect()
+}
+
+/// Who owns a symbol: the author of most of its blamed lines and when it last changed.
+#[derive(Clone, Debug, PartialEq)]
+struct SymbolOwnership {
+    file: String,
+    symbol: Symbol,
+    primary_author: String,
+    primary_lines: usize,
+    lines: usize,
+    authors: usize,
+    last_modified: Option<NaiveDate>,
+}
+
+impl SymbolOwnership {
+    /// the ownership of `symbol` from the author and date of every line of its file
+    fn new(file: &str, symbol: Symbol, blamed: &[BlamedLine]) -> Self {
+        let mut author_lines: HashMap<&str, usize> = HashMap::new();
+        let mut last_modified: Option<NaiveDate> = None;
+        let mut lines = 0;
+
+        for (author, date) in blamed
+            .iter()
+            .skip(symbol.start - 1)
+            .take(symbol.end + 1 - symbol.start)
+            .flatten()
+        {
+            *author_lines.entry(author).or_insert(0) += 1;
+            last_modified = Some(last_modified.map_or(*date, |d| d.max(*date)));
+            lines += 1;
+        }
+
+        let (primary_author, primary_lines) = author_lines
+            .iter()
+            .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+            .map(|(a, l)| (a.to_string(), *l))
+            .unwrap_or_default();
+
+        SymbolOwnership {
+            file: file.to_string(),
+            symbol,
+            primary_author,
+            primary_lines,
+            lines,
+            authors: author_lines.len(),
+            last_modified,
+        }
+    }
+
+    fn primary_share(&self) -> f64 {
+        if self.lines == 0 {
+            0.0
+        } else {
+            self.primary_lines as f64 / self.lines as f64
+        }
+    }
+}
+
+pub struct Symbols {
+    args: SymbolsArgs,
+}
+
+impl Symbols {
+    pub fn new(args: SymbolsArgs) -> Symbols {
+        Symbols { args }
+    }
+
+    /// the author and date of each line of `file_name` at HEAD
+    fn blame_lines(repo: &Repository, file_name: &str) -> Result<Vec<BlamedLine>> {
+        let blame = ignore_revs::blame_file(repo, &grit_utils::git_path(file_name), None, None)?;
+        let mut lines: Vec<BlamedLine> = Vec::new();
+
+        for b in blame.iter() {
+            let name = String::from_utf8_lossy(b.signature.name_bytes()).to_string();
+            let email = String::from_utf8_lossy(b.signature.email_bytes()).to_string();
+
+            let line = if identity::is_excluded(&name, &email) {
+                None
+            } else {
+                Some((
+                    identity::display_author(&name, &email),
+                    grit_utils::convert_git_time(&b.signature.when()),
+                ))
+            };
+
+            lines.extend(std::iter::repeat_n(line, b.lines));
+        }
+
+        Ok(lines)
+    }
+
+    fn file_symbols(
+        repo: &Repository,
+        tree: &Tree,
+        file_name: &str,
+    ) -> Result<Vec<SymbolOwnership>> {
+        let blob = tree
+            .get_path(&grit_utils::git_path(file_name))?
+            .to_object(repo)?
+            .peel_to_blob()?;
+        let source = String::from_utf8_lossy(blob.content());
+
+        let blamed = Symbols::blame_lines(repo, file_name)?;
+
+        Ok(rust_symbols(&source)
+            .into_iter()
+            .map(|s| SymbolOwnership::new(file_name, s, &blamed))
+            .collect())
+    }
+
+    /// the ownership of every symbol in the supported files, by file and line, the files
+    /// named relative to the `relative_to` directory
+    fn symbol_results(&self) -> Result<Vec<SymbolOwnership>> {
+        let file_names: Vec<String> = grit_utils::generate_file_list(
+            &self.args.path,
+            self.args.include.clone(),
+            self.args.exclude.clone(),
+        )?
+        .into_iter()
+        .filter(|f| {
+            grit_utils::get_filename_extension(f).is_some_and(|e| SUPPORTED_EXTENSIONS.contains(&e))
+        })
+        .collect();
+
+        let repo = grit_utils::open_repo(&self.args.path)?;
+        let tree = repo.head()?.peel_to_tree()?;
+
+        let mut results: Vec<SymbolOwnership> = Vec::new();
+        let mut skipped: Vec<SkippedFile> = Vec::new();
+
+        let pb = Progress::new("parsed", file_names.len());
+
+        for file_name in file_names.iter() {
+            pb.inc();
+
+            match Symbols::file_symbols(&repo, &tree, file_name) {
+                Ok(s) => results.extend(s),
+                Err(e) => {
+                    error!("Error in processing file {}: {}", file_name, e);
+                    skipped.push(SkippedFile::new(file_name, e.to_string()));
+                }
+            }
+        }
+
+        pb.finish_and_clear();
+
+        grit_utils::report_skipped(&skipped, self.args.strict)?;
+
+        results.sort_by(|a, b| {
+            a.file
+                .cmp(&b.file)
+                .then(a.symbol.start.cmp(&b.symbol.start))
+        });
+
+        let display_names: HashMap<&String, String> = file_names
+            .iter()
+            .zip(grit_utils::relative_file_paths(
+                &self.args.path,
+                &file_names,
+                self.args.relative_to,
+            )?)
+            .collect();
+
+        for r in results.iter_mut() {
+            if let Some(f) = display_names.get(&r.file) {
+                r.file = f.clone();
+            }
+        }
+
+        Ok(results)
+    }
+
+    fn symbols_table(&self, results: &[SymbolOwnership]) -> Table {
+        let mut table = Table::new();
+
+        if self.args.format == OutputFormat::Table {
+            table.set_titles(table_row![
+                "File",
+                "Symbol",
+                "Kind",
+                "Lines",
+                "LOC",
+                "Primary Author",
+                "Primary Share (%)",
+                "Authors",
+                "Last Modified"
+            ]);
+        } else {
+            table.set_titles(table_row![
+                "file",
+                "symbol",
+                "kind",
+                "lines",
+                "loc",
+                "primary author",
+                "primary share (%)",
+                "authors",
+                "last modified"
+            ]);
+        }
+
+        for r in results.iter() {
+            table.add_row(table_row![
+                r.file,
+                r.symbol.name,
+                r.symbol.kind,
+                format!("{}-{}", r.symbol.start, r.symbol.end),
+                r.lines,
+                r.primary_author,
+                output::percent(r.primary_share() * 100.0),
+                r.authors,
+                r.last_modified
+                    .map(grit_utils::format_date)
+                    .unwrap_or_default()
+            ]);
+        }
+
+        table.add_label(0, "file");
+        table.add_label(1, "symbol");
+        table.add_label(5, "author");
+        table.add_metric(4, "grit_symbol_loc", "Blamed lines of the symbol.");
+        table.add_metric(
+            6,
+            "grit_symbol_primary_author_share_percent",
+            "Percent of the symbol's lines blamed to its primary author.",
+        );
+
+        table
+    }
+}
+
+impl Processable<()> for Symbols {
+    fn process(&self) -> Result<()> {
+        let results = self.symbol_results()?;
+
+        let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+        self.symbols_table(&results)
+            .write(&mut w, self.args.format)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use log::LevelFilter;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    #[test]
+    fn test_rust_symbols() {
+        let source = r###"use std::fmt;
+
+/// a { brace in a comment
+pub(crate) struct Point(i32, [u8; 2]);
+
+pub enum Shape {
+    Dot(Point),
+}
+
+impl<T: Clone> fmt::Display for Wrapper<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let open = '{';
+        write!(f, "{} }} {}", open, r#"}"#)
+    }
+}
+
+trait Area {
+    fn area(&self) -> f64;
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    async fn test_area() {
+        /* } */
+    }
+}
+"###;
+
+        let symbols: Vec<(String, String, usize, usize)> = rust_symbols(source)
+            .into_iter()
+            .map(|s| (s.kind, s.name, s.start, s.end))
+            .collect();
+
+        let expected: Vec<(String, String, usize, usize)> = [
+            ("struct", "Point", 4, 4),
+            ("enum", "Shape", 6, 8),
+            ("fn", "Wrapper::fmt", 11, 14),
+            ("trait", "Area", 17, 19),
+            ("fn", "Area::area", 18, 18),
+            ("fn", "tests::test_area", 24, 26),
+        ]
+        .iter()
+        .map(|(k, n, s, e)| (k.to_string(), n.to_string(), *s, *e))
+        .collect();
+
+        assert_eq!(symbols, expected);
+    }
+
+    #[test]
+    fn test_symbol_results() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let symbols = Symbols::new(SymbolsArgs::new(
+            String::from(path),
+            None,
+            None,
+            None,
+            OutputFormat::Csv,
+            RelativeTo::Repo,
+            false,
+        ));
+
+        let results: Vec<_> = symbols
+            .symbol_results()
+            .unwrap()
+            .into_iter()
+            .map(|r| {
+                (
+                    r.file,
+                    r.symbol.name,
+                    r.primary_author,
+                    r.lines,
+                    r.last_modified,
+                )
+            })
+            .collect();
+
+        // main was written on 2020-01-01 and run added on 2020-01-02, both by Alice
+        assert_eq!(
+            results,
+            vec![
+                (
+                    String::from("src/lib.rs"),
+                    String::from("run"),
+                    String::from("Bob"),
+                    4,
+                    Some(NaiveDate::from_ymd_opt(2020, 1, 2).unwrap())
+                ),
+                (
+                    String::from("src/main.rs"),
+                    String::from("main"),
+                    String::from("Alice"),
+                    3,
+                    Some(NaiveDate::from_ymd_opt(2020, 1, 1).unwrap())
+                ),
+                (
+                    String::from("src/main.rs"),
+                    String::from("run"),
+                    String::from("Alice"),
+                    1,
+                    Some(NaiveDate::from_ymd_opt(2020, 1, 2).unwrap())
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,284 @@
+use crate::output;
+use crate::utils::grit_utils::OutputFormat;
+use anyhow::{anyhow, Result};
+use std::io;
+use std::io::Write;
+
+#[macro_export]
+macro_rules! table_row {
+    ($($e:expr),* $(,)?) => {
+        vec![$($e.to_string()),*]
+    };
+}
+
+/// A numeric column written as a Prometheus gauge.
+pub(crate) struct Metric {
+    pub column: usize,
+    pub name: String,
+    pub help: String,
+}
+
+/// Titled rows of a command's results, written out in any `OutputFormat` by the
+/// sinks in `output`.
+pub struct Table {
+    titles: Vec<String>,
+    rows: Vec<Vec<String>>,
+    labels: Vec<(usize, String)>,
+    metrics: Vec<Metric>,
+}
+
+impl Table {
+    pub fn new() -> Table {
+        Table {
+            titles: Vec::new(),
+            rows: Vec::new(),
+            labels: Vec::new(),
+            metrics: Vec::new(),
+        }
+    }
+
+    /// uses `column` as the `name` label of every prom metric
+    pub fn add_label(&mut self, column: usize, name: &str) {
+        self.labels.push((column, name.to_string()));
+    }
+
+    /// writes `column` as the `name` gauge in the prom output
+    pub fn add_metric(&mut self, column: usize, name: &str, help: &str) {
+        self.metrics.push(Metric {
+            column,
+            name: name.to_string(),
+            help: help.to_string(),
+        });
+    }
+
+    pub fn set_titles(&mut self, titles: Vec<String>) {
+        self.titles = titles;
+    }
+
+    pub fn add_row(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+
+    /// keeps only `columns`, in the order given.  A column is named by its title,
+    /// ignoring case, spaces and punctuation, so `active_days` picks `Active Days`.
+    pub fn select_columns(&mut self, columns: &[String]) -> Result<()> {
+        let keys: Vec<String> = self.titles.iter().map(|t| column_key(t)).collect();
+
+        let picked = columns
+            .iter()
+            .map(|c| {
+                keys.iter()
+                    .position(|k| *k == column_key(c))
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "unknown column '{}', expected one of: {}",
+                            c,
+                            self.titles
+                                .iter()
+                                .map(|t| t.to_lowercase())
+                                .collect::<Vec<String>>()
+                                .join(", ")
+                        )
+                    })
+            })
+            .collect::<Result<Vec<usize>>>()?;
+
+        let select = |row: &[String]| -> Vec<String> {
+            picked
+                .iter()
+                .map(|i| row.get(*i).cloned().unwrap_or_default())
+                .collect()
+        };
+
+        self.titles = select(&self.titles);
+        self.rows = self.rows.iter().map(|r| select(r)).collect();
+
+        let moved = |column: usize| picked.iter().position(|i| *i == column);
+
+        self.labels = self
+            .labels
+            .iter()
+            .filter_map(|(c, name)| Some((moved(*c)?, name.clone())))
+            .collect();
+        self.metrics = self
+            .metrics
+            .drain(..)
+            .filter_map(|m| {
+                Some(Metric {
+                    column: moved(m.column)?,
+                    ..m
+                })
+            })
+            .collect();
+
+        Ok(())
+    }
+
+    pub(crate) fn column_widths(&self) -> Vec<usize> {
+        let mut widths: Vec<usize> = Vec::new();
+
+        for row in std::iter::once(&self.titles).chain(self.rows.iter()) {
+            for (i, cell) in row.iter().enumerate() {
+                let len = cell.chars().count();
+                if i >= widths.len() {
+                    widths.push(len);
+                } else if len > widths[i] {
+                    widths[i] = len;
+                }
+            }
+        }
+
+        widths
+    }
+
+    pub(crate) fn titles(&self) -> &[String] {
+        &self.titles
+    }
+
+    pub(crate) fn rows(&self) -> &[Vec<String>] {
+        &self.rows
+    }
+
+    pub(crate) fn labels(&self) -> &[(usize, String)] {
+        &self.labels
+    }
+
+    pub(crate) fn metrics(&self) -> &[Metric] {
+        &self.metrics
+    }
+
+    pub fn print(&self, out: &mut dyn Write) -> io::Result<()> {
+        self.write(out, OutputFormat::Table)
+    }
+
+    pub fn write(&self, out: &mut dyn Write, format: OutputFormat) -> io::Result<()> {
+        output::writer(format).write(self, out)
+    }
+}
+
+/// the title lowercased, with everything but letters and digits left out
+fn column_key(title: &str) -> String {
+    title
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+impl Default for Table {
+    fn default() -> Self {
+        Table::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_print_table() {
+        let mut table = Table::new();
+        table.set_titles(table_row!["Author", "LOC"]);
+        table.add_row(table_row!["Todd Bush", 948]);
+        table.add_row(table_row!["todd-bush", 10]);
+
+        let mut out: Vec<u8> = Vec::new();
+        table.print(&mut out).unwrap();
+
+        let expected = "\
++-----------+-----+
+| Author    | LOC |
++-----------+-----+
+| Todd Bush | 948 |
+| todd-bush | 10  |
++-----------+-----+
+";
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_write_csv_and_json() {
+        let mut table = Table::new();
+        table.set_titles(table_row!["Author", "LOC"]);
+        table.add_row(table_row!["Bush, Todd", 948]);
+
+        let mut out: Vec<u8> = Vec::new();
+        table.write(&mut out, OutputFormat::Csv).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Author,LOC\n\"Bush, Todd\",948\n"
+        );
+
+        let mut out: Vec<u8> = Vec::new();
+        table.write(&mut out, OutputFormat::Json).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "[{\"Author\":\"Bush, Todd\",\"LOC\":948}]\n"
+        );
+    }
+
+    #[test]
+    fn test_select_columns() {
+        let mut table = Table::new();
+        table.set_titles(table_row!["Author", "Active Days", "LOC"]);
+        table.add_row(table_row!["Todd Bush", 3, 948]);
+        table.add_row(table_row!["Total"]);
+        table.add_label(0, "author");
+        table.add_metric(1, "grit_author_active_days", "Days.");
+        table.add_metric(2, "grit_author_loc", "Lines.");
+
+        table
+            .select_columns(&[String::from("loc"), String::from("active_days")])
+            .unwrap();
+
+        let mut out: Vec<u8> = Vec::new();
+        table.write(&mut out, OutputFormat::Csv).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "LOC,Active Days\n948,3\n,\n"
+        );
+        assert!(table.labels().is_empty());
+        assert_eq!(
+            table
+                .metrics()
+                .iter()
+                .map(|m| m.column)
+                .collect::<Vec<usize>>(),
+            vec![1, 0]
+        );
+
+        let err = table
+            .select_columns(&[String::from("commits")])
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown column 'commits', expected one of: loc, active days"
+        );
+    }
+
+    #[test]
+    fn test_write_prom() {
+        let mut table = Table::new();
+        table.set_titles(table_row!["Author", "LOC"]);
+        table.add_row(table_row!["Todd \"TB\" Bush", 948]);
+        table.add_row(table_row!["todd-bush", 10.5]);
+        table.add_row(table_row!["Total", "n/a"]);
+
+        let mut out: Vec<u8> = Vec::new();
+        assert!(table.write(&mut out, OutputFormat::Prom).is_err());
+
+        table.add_label(0, "author");
+        table.add_metric(1, "grit_author_loc", "Lines of code blamed to the author.");
+
+        let mut out: Vec<u8> = Vec::new();
+        table.write(&mut out, OutputFormat::Prom).unwrap();
+
+        let expected = "\
+# HELP grit_author_loc Lines of code blamed to the author.
+# TYPE grit_author_loc gauge
+grit_author_loc{author=\"Todd \\\"TB\\\" Bush\"} 948
+grit_author_loc{author=\"todd-bush\"} 10.5
+";
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+}
@@ -0,0 +1,340 @@
+use super::Processable;
+use crate::identity;
+use crate::table::Table;
+use crate::utils::grit_utils;
+use crate::utils::grit_utils::OutputFormat;
+use anyhow::Result;
+use chrono::NaiveDate;
+use std::collections::HashMap;
+
+const DAYS_PER_MONTH: f64 = 30.44;
+
+pub struct TenureArgs {
+    path: String,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+    restrict_authors: Option<String>,
+    inactive_days: i64,
+    file: Option<String>,
+    format: OutputFormat,
+    path_filter: Option<String>,
+}
+
+impl TenureArgs {
+    pub fn builder(path: String) -> TenureArgsBuilder {
+        TenureArgsBuilder {
+            args: TenureArgs {
+                path,
+                start_date: None,
+                end_date: None,
+                restrict_authors: None,
+                inactive_days: 90,
+                file: None,
+                format: OutputFormat::Csv,
+                path_filter: None,
+            },
+        }
+    }
+}
+
+/// Builds a `TenureArgs` by name rather than position, validating it on `build`.
+pub struct TenureArgsBuilder {
+    args: TenureArgs,
+}
+
+impl TenureArgsBuilder {
+    pub fn start_date(mut self, start_date: Option<NaiveDate>) -> TenureArgsBuilder {
+        self.args.start_date = start_date;
+        self
+    }
+
+    pub fn end_date(mut self, end_date: Option<NaiveDate>) -> TenureArgsBuilder {
+        self.args.end_date = end_date;
+        self
+    }
+
+    pub fn restrict_authors(mut self, restrict_authors: Option<String>) -> TenureArgsBuilder {
+        self.args.restrict_authors = restrict_authors;
+        self
+    }
+
+    pub fn inactive_days(mut self, inactive_days: i64) -> TenureArgsBuilder {
+        self.args.inactive_days = inactive_days;
+        self
+    }
+
+    pub fn file(mut self, file: Option<String>) -> TenureArgsBuilder {
+        self.args.file = file;
+        self
+    }
+
+    pub fn format(mut self, format: OutputFormat) -> TenureArgsBuilder {
+        self.args.format = format;
+        self
+    }
+
+    pub fn path_filter(mut self, path_filter: Option<String>) -> TenureArgsBuilder {
+        self.args.path_filter = path_filter;
+        self
+    }
+
+    pub fn build(self) -> Result<TenureArgs> {
+        grit_utils::validate_args(
+            self.args.start_date,
+            self.args.end_date,
+            self.args.file.as_deref(),
+            self.args.format,
+        )?;
+
+        Ok(self.args)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct TenureOutput {
+    author: String,
+    first_commit: NaiveDate,
+    last_commit: NaiveDate,
+    commits: i32,
+}
+
+impl TenureOutput {
+    fn new(author: String, day: NaiveDate) -> TenureOutput {
+        TenureOutput {
+            author,
+            first_commit: day,
+            last_commit: day,
+            commits: 0,
+        }
+    }
+
+    /// days between the first and the last commit, counting both days
+    fn active_days(&self) -> i64 {
+        (self.last_commit - self.first_commit).num_days() + 1
+    }
+
+    fn days_inactive(&self, as_of: NaiveDate) -> i64 {
+        (as_of - self.last_commit).num_days().max(0)
+    }
+
+    fn months_inactive(&self, as_of: NaiveDate) -> f64 {
+        self.days_inactive(as_of) as f64 / DAYS_PER_MONTH
+    }
+}
+
+pub struct Tenure {
+    args: TenureArgs,
+}
+
+impl Tenure {
+    pub fn new(args: TenureArgs) -> Tenure {
+        Tenure { args }
+    }
+
+    /// the first and last commit of every author in the date range, earliest starter first
+    fn process_tenure(&self) -> Result<Vec<TenureOutput>> {
+        let restrict_authors =
+            grit_utils::convert_string_list_to_vec(self.args.restrict_authors.clone());
+
+        let path_filter = grit_utils::parse_patterns(self.args.path_filter.clone())?;
+
+        let repo = grit_utils::open_repo(&self.args.path)?;
+
+        let mut revwalk = repo.revwalk()?;
+        revwalk.set_sorting(git2::Sort::TIME)?;
+        revwalk.push_head()?;
+
+        let mut output_map: HashMap<String, TenureOutput> = HashMap::new();
+
+        for oid in revwalk {
+            let commit = repo.find_commit(oid?)?;
+            let day = grit_utils::convert_git_time(&commit.time());
+
+            if self.args.start_date.map(|d| day < d).unwrap_or(false)
+                || self.args.end_date.map(|d| day > d).unwrap_or(false)
+            {
+                continue;
+            }
+
+            let author = commit.author();
+            let name = String::from_utf8_lossy(author.name_bytes()).to_string();
+            let email = String::from_utf8_lossy(author.email_bytes()).to_string();
+
+            if let Some(v) = &restrict_authors {
+                if v.iter().any(|a| a == &name) {
+                    continue;
+                }
+            }
+
+            if identity::is_excluded(&name, &email) {
+                continue;
+            }
+
+            if let Some(pf) = &path_filter {
+                if !grit_utils::commit_touches_paths(&repo, &commit, pf)? {
+                    continue;
+                }
+            }
+
+            let key = identity::display_author(&name, &email);
+
+            let o = output_map
+                .entry(key.clone())
+                .or_insert_with(|| TenureOutput::new(key, day));
+
+            if day < o.first_commit {
+                o.first_commit = day;
+            }
+            if day > o.last_commit {
+                o.last_commit = day;
+            }
+            o.commits += 1;
+        }
+
+        let mut output: Vec<TenureOutput> = output_map.into_values().collect();
+
+        output.sort_by(|a, b| {
+            a.first_commit
+                .cmp(&b.first_commit)
+                .then(a.author.cmp(&b.author))
+        });
+
+        Ok(output)
+    }
+
+    fn display_output(&self, output: &[TenureOutput], as_of: NaiveDate) -> Result<()> {
+        let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+
+        let mut table = Table::new();
+
+        table.set_titles(table_row![
+            "author",
+            "first commit",
+            "last commit",
+            "commits",
+            "active days",
+            "months inactive",
+            "inactive"
+        ]);
+
+        output.iter().for_each(|r| {
+            table.add_row(table_row![
+                r.author,
+                grit_utils::format_date(r.first_commit),
+                grit_utils::format_date(r.last_commit),
+                r.commits,
+                r.active_days(),
+                format!("{:.1}", r.months_inactive(as_of)),
+                if r.days_inactive(as_of) > self.args.inactive_days {
+                    "yes"
+                } else {
+                    "no"
+                }
+            ]);
+        });
+
+        table.write(&mut w, self.args.format)?;
+
+        Ok(())
+    }
+}
+
+impl Processable<()> for Tenure {
+    fn process(&self) -> Result<()> {
+        let output = self.process_tenure()?;
+        let as_of = self.args.end_date.unwrap_or_else(grit_utils::today);
+
+        self.display_output(&output, as_of)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use log::LevelFilter;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    #[test]
+    fn test_tenure_output() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let mut o = TenureOutput::new(
+            String::from("todd"),
+            NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
+        );
+        o.last_commit = NaiveDate::from_ymd_opt(2020, 1, 31).unwrap();
+
+        assert_eq!(o.active_days(), 31);
+        assert_eq!(
+            o.days_inactive(NaiveDate::from_ymd_opt(2020, 3, 1).unwrap()),
+            30
+        );
+        assert_eq!(
+            o.days_inactive(NaiveDate::from_ymd_opt(2020, 1, 15).unwrap()),
+            0
+        );
+        assert!(
+            (o.months_inactive(NaiveDate::from_ymd_opt(2021, 1, 31).unwrap()) - 12.0).abs() < 0.1
+        );
+    }
+
+    #[test]
+    fn test_process_tenure() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let args = TenureArgs::builder(path.to_string())
+            .file(Some(String::from("target/test_tenure.csv")))
+            .build()
+            .unwrap();
+
+        let tenure = Tenure::new(args);
+
+        let output = tenure.process_tenure().unwrap();
+
+        let tenures: Vec<(&str, String, String, i32)> = output
+            .iter()
+            .map(|o| {
+                (
+                    o.author.as_str(),
+                    grit_utils::format_date(o.first_commit),
+                    grit_utils::format_date(o.last_commit),
+                    o.commits,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            tenures,
+            vec![
+                (
+                    "Alice",
+                    String::from("2020-01-01"),
+                    String::from("2020-01-02"),
+                    2
+                ),
+                (
+                    "Bob",
+                    String::from("2020-01-02"),
+                    String::from("2020-01-05"),
+                    2
+                ),
+            ]
+        );
+
+        let result = match tenure.process() {
+            Ok(()) => true,
+            Err(e) => {
+                error!("test_process_tenure ended in error {:?}", e);
+                false
+            }
+        };
+
+        assert!(result, "See error above");
+    }
+}
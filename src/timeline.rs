@@ -0,0 +1,503 @@
+use super::Processable;
+use crate::chart;
+use crate::chart::ChartOptions;
+use crate::diff_stats;
+use crate::identity;
+use crate::ownership::SnapshotPeriod;
+use crate::table::Table;
+use crate::utils::grit_utils;
+use crate::utils::grit_utils::OutputFormat;
+use anyhow::{anyhow, Result};
+use charts::{AreaSeriesView, AxisPosition, Color, MarkerType, PointDatum, ScaleBand, ScaleLinear};
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+
+const OTHERS_AUTHOR: &str = "Others";
+
+/// What the timeline counts per author and week.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TimelineMetric {
+    Commits,
+    /// lines added
+    Lines,
+}
+
+impl FromStr for TimelineMetric {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "commits" => Ok(TimelineMetric::Commits),
+            "lines" => Ok(TimelineMetric::Lines),
+            _ => Err(anyhow!("metric must be either 'commits' or 'lines'")),
+        }
+    }
+}
+
+pub struct TimelineArgs {
+    path: String,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+    metric: TimelineMetric,
+    restrict_authors: Option<String>,
+    top: Option<usize>,
+    file: Option<String>,
+    image: bool,
+    html: bool,
+    chart_options: ChartOptions,
+    format: OutputFormat,
+}
+
+impl TimelineArgs {
+    pub fn builder(path: String) -> TimelineArgsBuilder {
+        TimelineArgsBuilder {
+            args: TimelineArgs {
+                path,
+                start_date: None,
+                end_date: None,
+                metric: TimelineMetric::Commits,
+                restrict_authors: None,
+                top: None,
+                file: None,
+                image: false,
+                html: false,
+                chart_options: ChartOptions::default(),
+                format: OutputFormat::Csv,
+            },
+        }
+    }
+}
+
+/// Builds a `TimelineArgs` by name rather than position, validating it on `build`.
+pub struct TimelineArgsBuilder {
+    args: TimelineArgs,
+}
+
+impl TimelineArgsBuilder {
+    pub fn start_date(mut self, start_date: Option<NaiveDate>) -> TimelineArgsBuilder {
+        self.args.start_date = start_date;
+        self
+    }
+
+    pub fn end_date(mut self, end_date: Option<NaiveDate>) -> TimelineArgsBuilder {
+        self.args.end_date = end_date;
+        self
+    }
+
+    pub fn metric(mut self, metric: TimelineMetric) -> TimelineArgsBuilder {
+        self.args.metric = metric;
+        self
+    }
+
+    pub fn restrict_authors(mut self, restrict_authors: Option<String>) -> TimelineArgsBuilder {
+        self.args.restrict_authors = restrict_authors;
+        self
+    }
+
+    pub fn top(mut self, top: Option<usize>) -> TimelineArgsBuilder {
+        self.args.top = top;
+        self
+    }
+
+    pub fn file(mut self, file: Option<String>) -> TimelineArgsBuilder {
+        self.args.file = file;
+        self
+    }
+
+    pub fn image(mut self, image: bool) -> TimelineArgsBuilder {
+        self.args.image = image;
+        self
+    }
+
+    pub fn html(mut self, html: bool) -> TimelineArgsBuilder {
+        self.args.html = html;
+        self
+    }
+
+    pub fn chart_options(mut self, chart_options: ChartOptions) -> TimelineArgsBuilder {
+        self.args.chart_options = chart_options;
+        self
+    }
+
+    pub fn format(mut self, format: OutputFormat) -> TimelineArgsBuilder {
+        self.args.format = format;
+        self
+    }
+
+    pub fn build(self) -> Result<TimelineArgs> {
+        grit_utils::validate_args(
+            self.args.start_date,
+            self.args.end_date,
+            self.args.file.as_deref(),
+            self.args.format,
+        )?;
+
+        Ok(self.args)
+    }
+}
+
+/// Every week from the first commit to the last, and each author's count per week, the
+/// biggest contributor first.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct TimelineOutput {
+    weeks: Vec<String>,
+    authors: Vec<(String, Vec<usize>)>,
+}
+
+impl TimelineOutput {
+    /// the top of each author's band, the authors stacked in order from the bottom
+    fn stacked(&self) -> Vec<Vec<usize>> {
+        let mut running = vec![0; self.weeks.len()];
+
+        self.authors
+            .iter()
+            .map(|(_, counts)| {
+                running.iter_mut().zip(counts).for_each(|(r, c)| *r += c);
+                running.clone()
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct TimelinePoint {
+    week: String,
+    value: f32,
+}
+
+impl PointDatum<String, f32> for TimelinePoint {
+    fn get_x(&self) -> String {
+        self.week.clone()
+    }
+
+    fn get_y(&self) -> f32 {
+        self.value
+    }
+
+    fn get_key(&self) -> String {
+        String::from("")
+    }
+}
+
+pub struct Timeline {
+    args: TimelineArgs,
+}
+
+impl Timeline {
+    pub fn new(args: TimelineArgs) -> Timeline {
+        Timeline { args }
+    }
+
+    /// the commits or lines added of each author per week, the authors past `top` folded
+    /// into Others
+    fn process_timeline(&self) -> Result<TimelineOutput> {
+        let restrict_authors =
+            grit_utils::convert_string_list_to_vec(self.args.restrict_authors.clone());
+
+        let commits = diff_stats::commit_stats(
+            &self.args.path,
+            self.args.start_date,
+            self.args.end_date,
+            None,
+            None,
+            false,
+        )?;
+
+        let days: Vec<NaiveDate> = commits
+            .iter()
+            .map(|c| grit_utils::convert_git_time(&c.time))
+            .collect();
+
+        let (first, last) = match (days.iter().min(), days.iter().max()) {
+            (Some(f), Some(l)) => (*f, *l),
+            _ => return Ok(TimelineOutput::default()),
+        };
+
+        let period = SnapshotPeriod::Weekly;
+        let week_ends = period.period_ends(first, period.period_end(last));
+        let week_index: HashMap<NaiveDate, usize> =
+            week_ends.iter().enumerate().map(|(i, d)| (*d, i)).collect();
+
+        let mut counts: HashMap<String, Vec<usize>> = HashMap::new();
+
+        for (c, day) in commits.iter().zip(days) {
+            if restrict_authors
+                .as_ref()
+                .map(|ra| ra.contains(&c.name))
+                .unwrap_or(false)
+                || identity::is_excluded(&c.name, &c.email)
+            {
+                continue;
+            }
+
+            let value = match self.args.metric {
+                TimelineMetric::Commits => 1,
+                TimelineMetric::Lines => c.stats.insertions,
+            };
+
+            counts
+                .entry(identity::display_author(&c.name, &c.email))
+                .or_insert_with(|| vec![0; week_ends.len()])
+                [week_index[&period.period_end(day)]] += value;
+        }
+
+        let mut authors: Vec<(String, Vec<usize>)> = counts.into_iter().collect();
+        authors.sort_by(|a, b| {
+            b.1.iter()
+                .sum::<usize>()
+                .cmp(&a.1.iter().sum::<usize>())
+                .then(a.0.cmp(&b.0))
+        });
+
+        if let Some(top) = self.args.top {
+            if authors.len() > top {
+                let mut others = vec![0; week_ends.len()];
+                for (_, v) in authors.drain(top..) {
+                    others.iter_mut().zip(v).for_each(|(o, c)| *o += c);
+                }
+                authors.push((String::from(OTHERS_AUTHOR), others));
+            }
+        }
+
+        Ok(TimelineOutput {
+            weeks: week_ends.iter().map(|d| period.label(*d)).collect(),
+            authors,
+        })
+    }
+
+    fn metric_name(&self) -> &'static str {
+        match self.args.metric {
+            TimelineMetric::Commits => "commits",
+            TimelineMetric::Lines => "lines added",
+        }
+    }
+
+    /// one row per author and week they were active in
+    fn display_text_output(&self, output: &TimelineOutput) -> Result<()> {
+        let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+
+        let mut table = Table::new();
+
+        table.set_titles(table_row!["week", "author", self.metric_name()]);
+
+        for (i, week) in output.weeks.iter().enumerate() {
+            for (author, counts) in output.authors.iter() {
+                if counts[i] > 0 {
+                    table.add_row(table_row![week, author, counts[i]]);
+                }
+            }
+        }
+
+        table.add_label(0, "week");
+        table.add_label(1, "author");
+        table.add_metric(
+            2,
+            &format!(
+                "grit_author_weekly_{}",
+                self.metric_name().replace(' ', "_")
+            ),
+            &format!("The author's {} in the week.", self.metric_name()),
+        );
+
+        table.write(&mut w, self.args.format)?;
+
+        Ok(())
+    }
+
+    /// a stacked area chart with one band per author.  The bands are drawn from the top
+    /// down, each painting over the one stacked above it.
+    fn create_output_image(&self, output: &TimelineOutput) -> Result<()> {
+        let file = self
+            .args
+            .file
+            .clone()
+            .unwrap_or_else(|| String::from("timeline.svg"));
+
+        if !grit_utils::check_file_type(&file, "svg") {
+            return Err(anyhow!("the image file format must be svg"));
+        }
+
+        if output.weeks.is_empty() {
+            return Err(anyhow!("no commits to chart"));
+        }
+
+        let (width, height) = self.args.chart_options.size(if output.weeks.len() > 60 {
+            (1920, 960)
+        } else if output.weeks.len() > 35 {
+            (1280, 960)
+        } else {
+            (1027, 768)
+        });
+        let (top, right, bottom, left) = (120, 40, 80, 80);
+
+        let stacked = output.stacked();
+        let max_total = stacked
+            .last()
+            .and_then(|s| s.iter().max())
+            .cloned()
+            .unwrap_or(0) as f32
+            * 1.05
+            + 1.0;
+
+        let x = ScaleBand::new()
+            .set_domain(output.weeks.clone())
+            .set_range(vec![0, width - left - right]);
+        let y = ScaleLinear::new()
+            .set_domain(vec![0_f32, max_total])
+            .set_range(vec![height - top - bottom, 0]);
+
+        let colors = self.args.chart_options.theme.colors();
+        let mut views = Vec::new();
+
+        for (i, ((author, _), band)) in output.authors.iter().zip(stacked.iter()).enumerate().rev()
+        {
+            let points: Vec<TimelinePoint> = output
+                .weeks
+                .iter()
+                .zip(band)
+                .map(|(week, v)| TimelinePoint {
+                    week: week.clone(),
+                    value: *v as f32,
+                })
+                .collect();
+
+            views.push(
+                AreaSeriesView::new()
+                    .set_x_scale(&x)
+                    .set_y_scale(&y)
+                    .set_marker_type(MarkerType::Circle)
+                    .set_label_visibility(false)
+                    .set_colors(Color::from_vec_of_hex_strings(vec![&colors
+                        [i % colors.len()]
+                    .as_hex()]))
+                    .set_custom_data_label(author.clone())
+                    .load_data(&points)
+                    .map_err(|e| anyhow!("Failed to create Area View: {}", e))?,
+            );
+        }
+
+        let mut chart =
+            chart::configure_chart(&self.args.chart_options, (width, height), "Timeline")
+                .set_margins(top, right, bottom, left);
+
+        for view in views.iter() {
+            chart = chart.add_view(view);
+        }
+
+        chart
+            .add_axis_bottom(&x)
+            .add_axis_left(&y)
+            .add_left_axis_label(self.metric_name())
+            .add_legend_at(AxisPosition::Top)
+            .set_bottom_axis_tick_label_rotation(-45)
+            .save(Path::new(&file))
+            .map_err(|e| anyhow!("Failed to create Chart: {}", e))?;
+
+        if self.args.html {
+            grit_utils::create_html(&file)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Processable<()> for Timeline {
+    fn process(&self) -> Result<()> {
+        let output = self.process_timeline()?;
+
+        if self.args.image {
+            self.create_output_image(&output)
+        } else {
+            self.display_text_output(&output)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::LevelFilter;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    fn timeline_args(path: &str, metric: TimelineMetric, top: Option<usize>) -> TimelineArgs {
+        TimelineArgs::builder(path.to_string())
+            .metric(metric)
+            .top(top)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_process_timeline() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        // every fixture commit falls in the week of Monday 2019-12-30
+        let output = Timeline::new(timeline_args(path, TimelineMetric::Commits, None))
+            .process_timeline()
+            .unwrap();
+
+        assert_eq!(output.weeks, vec!["2020-W01"]);
+        assert_eq!(
+            output.authors,
+            vec![
+                (String::from("Alice"), vec![2]),
+                (String::from("Bob"), vec![2])
+            ]
+        );
+
+        let output = Timeline::new(timeline_args(path, TimelineMetric::Lines, Some(1)))
+            .process_timeline()
+            .unwrap();
+
+        assert_eq!(
+            output.authors,
+            vec![
+                (String::from("Alice"), vec![7]),
+                (String::from(OTHERS_AUTHOR), vec![5])
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stacked() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let output = TimelineOutput {
+            weeks: vec![String::from("2020-W01"), String::from("2020-W02")],
+            authors: vec![
+                (String::from("Alice"), vec![3, 0]),
+                (String::from("Bob"), vec![1, 2]),
+            ],
+        };
+
+        assert_eq!(output.stacked(), vec![vec![3, 0], vec![4, 2]]);
+    }
+
+    #[test]
+    fn test_timeline_image() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+        let file = "target/test_timeline_image.svg";
+
+        let args = TimelineArgs::builder(path.to_string())
+            .file(Some(String::from(file)))
+            .image(true)
+            .build()
+            .unwrap();
+
+        Timeline::new(args).process().unwrap();
+
+        let svg = std::fs::read_to_string(file).unwrap();
+        assert!(svg.contains("Alice"));
+        assert!(svg.contains("Bob"));
+    }
+}
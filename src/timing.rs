@@ -0,0 +1,183 @@
+use crate::table::Table;
+use crate::utils::grit_utils;
+use crate::utils::grit_utils::OutputFormat;
+use anyhow::Result;
+use std::fs::File;
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// slowest files listed in the summary, the trace has all of them
+const SUMMARY_FILES: usize = 10;
+
+static TIMINGS: OnceLock<Mutex<Timings>> = OnceLock::new();
+
+/// The time spent in each phase of a run, in the order the phases started, and blaming
+/// each file.
+#[derive(Default)]
+struct Timings {
+    phases: Vec<(&'static str, usize, Duration)>,
+    files: Vec<(String, Duration)>,
+    trace: Option<String>,
+}
+
+impl Timings {
+    fn add_phase(&mut self, name: &'static str, elapsed: Duration) {
+        match self.phases.iter_mut().find(|p| p.0 == name) {
+            Some(p) => {
+                p.1 += 1;
+                p.2 += elapsed;
+            }
+            None => self.phases.push((name, 1, elapsed)),
+        }
+    }
+
+    /// the files, slowest first
+    fn slowest_files(&self) -> Vec<&(String, Duration)> {
+        let mut files: Vec<&(String, Duration)> = self.files.iter().collect();
+        files.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        files
+    }
+
+    fn summary_table(&self) -> Table {
+        let mut table = Table::new();
+        table.set_titles(table_row!["phase", "calls", "ms"]);
+
+        for (name, calls, elapsed) in self.phases.iter() {
+            table.add_row(table_row![name, calls, millis(*elapsed)]);
+        }
+
+        for (file, elapsed) in self.slowest_files().into_iter().take(SUMMARY_FILES) {
+            table.add_row(table_row![format!("blame {}", file), 1, millis(*elapsed)]);
+        }
+
+        table
+    }
+
+    fn trace_json(&self) -> String {
+        let phases: Vec<String> = self
+            .phases
+            .iter()
+            .map(|(name, calls, elapsed)| {
+                format!(
+                    "{{\"phase\":{},\"calls\":{},\"ms\":{}}}",
+                    grit_utils::json_string(name),
+                    calls,
+                    millis(*elapsed)
+                )
+            })
+            .collect();
+        let files: Vec<String> = self
+            .slowest_files()
+            .into_iter()
+            .map(|(file, elapsed)| {
+                format!(
+                    "{{\"file\":{},\"ms\":{}}}",
+                    grit_utils::json_string(file),
+                    millis(*elapsed)
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\"phases\":[{}],\"files\":[{}]}}",
+            phases.join(","),
+            files.join(",")
+        )
+    }
+}
+
+/// collects timings for the rest of the run when `enabled`, to be written to `trace` as
+/// JSON as well as summarized on stderr.  Only the first call has an effect.
+pub fn set_timing(enabled: bool, trace: Option<&str>) {
+    if enabled {
+        let _ = TIMINGS.set(Mutex::new(Timings {
+            trace: trace.map(String::from),
+            ..Timings::default()
+        }));
+    }
+}
+
+fn with_timings(f: impl FnOnce(&mut Timings)) {
+    if let Some(t) = TIMINGS.get() {
+        f(&mut t.lock().expect("cannot lock the timings"));
+    }
+}
+
+/// runs `f`, adding the time it takes to the phase `name` and logging it
+pub fn phase<T>(name: &'static str, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+
+    info!(phase = name, duration_ms = duration_ms(elapsed); "{} took {:?}", name, elapsed);
+    with_timings(|t| t.add_phase(name, elapsed));
+
+    result
+}
+
+/// records the time blaming `file` took
+pub fn record_file(file: &str, elapsed: Duration) {
+    info!(file = file, duration_ms = duration_ms(elapsed); "Processed {} in {:?}", file, elapsed);
+    with_timings(|t| t.files.push((file.to_string(), elapsed)));
+}
+
+/// milliseconds to a tenth, as a number for structured logs
+fn duration_ms(d: Duration) -> f64 {
+    (d.as_secs_f64() * 10_000.0).round() / 10.0
+}
+
+fn millis(d: Duration) -> String {
+    format!("{:.1}", d.as_secs_f64() * 1000.0)
+}
+
+/// writes the phases and the slowest files to stderr, and the trace file if there is one.
+/// Does nothing unless timings were collected.
+pub fn report() -> Result<()> {
+    let t = match TIMINGS.get() {
+        Some(t) => t.lock().expect("cannot lock the timings"),
+        None => return Ok(()),
+    };
+
+    t.summary_table()
+        .write(&mut std::io::stderr(), OutputFormat::Table)?;
+
+    if let Some(trace) = &t.trace {
+        writeln!(File::create(trace)?, "{}", t.trace_json())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timings() {
+        let mut t = Timings::default();
+        t.add_phase("file listing", Duration::from_millis(2));
+        t.add_phase("blame", Duration::from_millis(10));
+        t.add_phase("file listing", Duration::from_millis(3));
+        t.files.push(("a.rs".to_string(), Duration::from_millis(4)));
+        t.files
+            .push(("b\"c.rs".to_string(), Duration::from_millis(6)));
+
+        assert_eq!(
+            t.trace_json(),
+            "{\"phases\":[{\"phase\":\"file listing\",\"calls\":2,\"ms\":5.0},\
+             {\"phase\":\"blame\",\"calls\":1,\"ms\":10.0}],\
+             \"files\":[{\"file\":\"b\\\"c.rs\",\"ms\":6.0},{\"file\":\"a.rs\",\"ms\":4.0}]}"
+        );
+
+        let mut out: Vec<u8> = Vec::new();
+        t.summary_table()
+            .write(&mut out, OutputFormat::Csv)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "phase,calls,ms\nfile listing,2,5.0\nblame,1,10.0\n\"blame b\"\"c.rs\",1,6.0\nblame a.rs,1,4.0\n"
+        );
+    }
+}
@@ -0,0 +1,476 @@
+use crate::utils::grit_utils;
+use charts::Color;
+use std::collections::HashMap;
+
+const TITLE_HEIGHT: f64 = 30.0;
+const LEGEND_HEIGHT: f64 = 24.0;
+const HEADER_HEIGHT: f64 = 14.0;
+const PADDING: f64 = 2.0;
+const SWATCH_SIZE: f64 = 10.0;
+/// rough width of a 10px sans-serif character, used to decide whether a label fits
+const CHAR_WIDTH: f64 = 6.0;
+
+/// A directory or file of the blamed tree, sized by its lines and colored by the author
+/// owning most of them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TreemapNode {
+    pub name: String,
+    pub path: String,
+    pub loc: i32,
+    /// lines owned by each author, most first
+    pub authors: Vec<(String, i32)>,
+    pub children: Vec<TreemapNode>,
+}
+
+impl TreemapNode {
+    /// builds the directory tree from the lines each author owns per file, the root
+    /// named `name`
+    pub fn build(name: &str, file_lines: &[(String, String, i32)]) -> TreemapNode {
+        let mut files: HashMap<&str, HashMap<&str, i32>> = HashMap::new();
+
+        for (file_name, author, lines) in file_lines.iter() {
+            *files
+                .entry(file_name)
+                .or_default()
+                .entry(author)
+                .or_insert(0) += lines;
+        }
+
+        let mut root = TreemapNode {
+            name: name.to_string(),
+            path: String::from("."),
+            ..Default::default()
+        };
+
+        for (file_name, authors) in files.into_iter() {
+            let parts: Vec<&str> = file_name.split('/').collect();
+            let authors = authors
+                .into_iter()
+                .map(|(a, l)| (a.to_string(), l))
+                .collect();
+
+            root.insert(&parts, authors);
+        }
+
+        root.total();
+        root
+    }
+
+    fn insert(&mut self, parts: &[&str], authors: Vec<(String, i32)>) {
+        let (name, rest) = match parts.split_first() {
+            Some(p) => p,
+            None => return,
+        };
+
+        let path = if self.path == "." {
+            name.to_string()
+        } else {
+            format!("{}/{}", self.path, name)
+        };
+
+        if rest.is_empty() {
+            self.children.push(TreemapNode {
+                name: name.to_string(),
+                path,
+                authors,
+                ..Default::default()
+            });
+            return;
+        }
+
+        let i = match self.children.iter().position(|c| c.name == *name) {
+            Some(i) => i,
+            None => {
+                self.children.push(TreemapNode {
+                    name: name.to_string(),
+                    path,
+                    ..Default::default()
+                });
+                self.children.len() - 1
+            }
+        };
+
+        self.children[i].insert(rest, authors);
+    }
+
+    /// rolls the authors' lines up from the files, sorting children biggest first
+    fn total(&mut self) {
+        if !self.children.is_empty() {
+            let mut authors: HashMap<String, i32> = HashMap::new();
+
+            for c in self.children.iter_mut() {
+                c.total();
+                for (a, l) in c.authors.iter() {
+                    *authors.entry(a.clone()).or_insert(0) += l;
+                }
+            }
+
+            self.authors = authors.into_iter().collect();
+            self.children
+                .sort_by(|a, b| b.loc.cmp(&a.loc).then(a.name.cmp(&b.name)));
+        }
+
+        self.authors
+            .sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        self.loc = self.authors.iter().map(|a| a.1).sum();
+    }
+
+    /// the author owning the most lines
+    pub fn owner(&self) -> Option<&str> {
+        self.authors.first().map(|a| a.0.as_str())
+    }
+
+    /// the owner's percent of the lines
+    pub fn share(&self) -> f64 {
+        match self.authors.first() {
+            Some(a) if self.loc > 0 => a.1 as f64 / self.loc as f64 * 100.0,
+            _ => 0.0,
+        }
+    }
+
+    /// the tree as nested JSON for `d3.hierarchy`, files carrying their lines as `value`
+    pub fn to_json(&self) -> String {
+        let mut fields = vec![
+            format!("\"name\":{}", grit_utils::json_string(&self.name)),
+            format!("\"path\":{}", grit_utils::json_string(&self.path)),
+            format!("\"loc\":{}", self.loc),
+            format!(
+                "\"owner\":{}",
+                self.owner()
+                    .map(grit_utils::json_string)
+                    .unwrap_or_else(|| String::from("null"))
+            ),
+            format!("\"share\":{:.1}", self.share()),
+        ];
+
+        if self.children.is_empty() {
+            fields.push(format!("\"value\":{}", self.loc));
+        } else {
+            let children: Vec<String> = self.children.iter().map(|c| c.to_json()).collect();
+            fields.push(format!("\"children\":[{}]", children.join(",")));
+        }
+
+        format!("{{{}}}", fields.join(","))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Rect {
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+}
+
+/// the worst aspect ratio of a row of areas laid along a side of length `side`
+fn worst_ratio(row: &[f64], side: f64) -> f64 {
+    let sum: f64 = row.iter().sum();
+    let max = row.iter().cloned().fold(0.0, f64::max);
+    let min = row.iter().cloned().fold(f64::INFINITY, f64::min);
+
+    if sum <= 0.0 || min <= 0.0 {
+        return f64::INFINITY;
+    }
+
+    let side2 = side * side;
+    let sum2 = sum * sum;
+
+    (side2 * max / sum2).max(sum2 / (side2 * min))
+}
+
+/// splits `rect` into one rectangle per value, each sized by its share of the total,
+/// keeping them as close to square as it can.  values are expected biggest first.
+fn squarify(values: &[f64], rect: Rect) -> Vec<Rect> {
+    let total: f64 = values.iter().sum();
+
+    if total <= 0.0 || rect.w <= 0.0 || rect.h <= 0.0 {
+        return vec![
+            Rect {
+                w: 0.0,
+                h: 0.0,
+                ..rect
+            };
+            values.len()
+        ];
+    }
+
+    let scale = rect.w * rect.h / total;
+    let areas: Vec<f64> = values.iter().map(|v| v * scale).collect();
+
+    let mut result: Vec<Rect> = Vec::with_capacity(areas.len());
+    let mut r = rect;
+    let mut start = 0;
+
+    while start < areas.len() {
+        let side = r.w.min(r.h);
+        let mut end = start + 1;
+
+        while end < areas.len()
+            && worst_ratio(&areas[start..=end], side) <= worst_ratio(&areas[start..end], side)
+        {
+            end += 1;
+        }
+
+        let row_sum: f64 = areas[start..end].iter().sum();
+
+        if r.w >= r.h {
+            // a column down the left side
+            let width = if r.h > 0.0 { row_sum / r.h } else { 0.0 };
+            let mut y = r.y;
+
+            for a in areas[start..end].iter() {
+                let h = if width > 0.0 { a / width } else { 0.0 };
+                result.push(Rect {
+                    x: r.x,
+                    y,
+                    w: width,
+                    h,
+                });
+                y += h;
+            }
+
+            r = Rect {
+                x: r.x + width,
+                w: (r.w - width).max(0.0),
+                ..r
+            };
+        } else {
+            // a row along the top
+            let height = if r.w > 0.0 { row_sum / r.w } else { 0.0 };
+            let mut x = r.x;
+
+            for a in areas[start..end].iter() {
+                let w = if height > 0.0 { a / height } else { 0.0 };
+                result.push(Rect {
+                    x,
+                    y: r.y,
+                    w,
+                    h: height,
+                });
+                x += w;
+            }
+
+            r = Rect {
+                y: r.y + height,
+                h: (r.h - height).max(0.0),
+                ..r
+            };
+        }
+
+        start = end;
+    }
+
+    result
+}
+
+fn fits(label: &str, rect: Rect) -> bool {
+    rect.h >= HEADER_HEIGHT && (label.chars().count() as f64 * CHAR_WIDTH + 6.0) <= rect.w
+}
+
+fn render(node: &TreemapNode, rect: Rect, colors: &HashMap<&str, String>, out: &mut Vec<String>) {
+    if rect.w < 1.0 || rect.h < 1.0 {
+        return;
+    }
+
+    let owner = node.owner().unwrap_or_default();
+    let color = colors.get(owner).map(|c| c.as_str()).unwrap_or("#cccccc");
+    let tooltip = grit_utils::html_escape(&format!(
+        "{}: {} lines, {} owns {:.1}%",
+        node.path,
+        node.loc,
+        owner,
+        node.share()
+    ));
+    let is_file = node.children.is_empty();
+
+    out.push(format!(
+        "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\" fill-opacity=\"{}\" stroke=\"#ffffff\"><title>{}</title></rect>",
+        rect.x,
+        rect.y,
+        rect.w,
+        rect.h,
+        color,
+        if is_file { "1" } else { "0.3" },
+        tooltip
+    ));
+
+    if fits(&node.name, rect) {
+        out.push(format!(
+            "<text x=\"{:.1}\" y=\"{:.1}\">{}</text>",
+            rect.x + 3.0,
+            rect.y + 11.0,
+            grit_utils::html_escape(&node.name)
+        ));
+    }
+
+    if is_file {
+        return;
+    }
+
+    let inner = Rect {
+        x: rect.x + PADDING,
+        y: rect.y + HEADER_HEIGHT,
+        w: rect.w - 2.0 * PADDING,
+        h: rect.h - HEADER_HEIGHT - PADDING,
+    };
+
+    let values: Vec<f64> = node.children.iter().map(|c| c.loc as f64).collect();
+
+    for (child, r) in node.children.iter().zip(squarify(&values, inner)) {
+        render(child, r, colors, out);
+    }
+}
+
+/// renders the tree as a squarified treemap, each file filled with the color of its
+/// owner and each directory shaded with the color of its own
+pub fn treemap_svg(
+    title: &str,
+    size: (isize, isize),
+    root: &TreemapNode,
+    colors: &[Color],
+) -> String {
+    let (width, height) = (size.0 as f64, size.1 as f64);
+    let palette: Vec<String> = colors.iter().map(|c| c.as_hex()).collect();
+
+    let author_colors: HashMap<&str, String> = root
+        .authors
+        .iter()
+        .enumerate()
+        .filter(|_| !palette.is_empty())
+        .map(|(i, a)| (a.0.as_str(), palette[i % palette.len()].clone()))
+        .collect();
+
+    let mut legend: Vec<String> = Vec::new();
+    let mut x = 0.0;
+
+    for a in root.authors.iter().take(palette.len()) {
+        legend.push(format!(
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{size}\" height=\"{size}\" fill=\"{}\"/><text x=\"{:.1}\" y=\"{:.1}\">{}</text>",
+            x,
+            TITLE_HEIGHT,
+            author_colors[a.0.as_str()],
+            x + SWATCH_SIZE + 4.0,
+            TITLE_HEIGHT + SWATCH_SIZE - 1.0,
+            grit_utils::html_escape(&a.0),
+            size = SWATCH_SIZE
+        ));
+        x += SWATCH_SIZE + 16.0 + a.0.chars().count() as f64 * CHAR_WIDTH;
+    }
+
+    let mut cells: Vec<String> = Vec::new();
+    let top = TITLE_HEIGHT + LEGEND_HEIGHT;
+
+    render(
+        root,
+        Rect {
+            x: 0.0,
+            y: top,
+            w: width,
+            h: (height - top).max(0.0),
+        },
+        &author_colors,
+        &mut cells,
+    );
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" font-family=\"sans-serif\" font-size=\"10\"><text x=\"0\" y=\"16\" font-size=\"16\">{title}</text>{legend}{cells}</svg>",
+        width = size.0,
+        height = size.1,
+        title = grit_utils::html_escape(title),
+        legend = legend.join(""),
+        cells = cells.join("")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::LevelFilter;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    fn file_lines() -> Vec<(String, String, i32)> {
+        [
+            ("src/main.rs", "Alice", 6),
+            ("src/main.rs", "Bob", 2),
+            ("src/util/io.rs", "Bob", 4),
+            ("README.md", "Carol", 3),
+        ]
+        .iter()
+        .map(|(f, a, l)| (f.to_string(), a.to_string(), *l))
+        .collect()
+    }
+
+    #[test]
+    fn test_treemap_node() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let root = TreemapNode::build("repo", &file_lines());
+
+        assert_eq!(root.loc, 15);
+        assert_eq!(root.owner(), Some("Alice"));
+        assert_eq!(
+            root.children.iter().map(|c| &c.path).collect::<Vec<_>>(),
+            vec!["src", "README.md"]
+        );
+
+        let src = &root.children[0];
+        assert_eq!(src.loc, 12);
+        assert_eq!(
+            src.authors,
+            vec![(String::from("Alice"), 6), (String::from("Bob"), 6)]
+        );
+        assert_eq!(src.children[1].path, "src/util");
+        assert_eq!(src.children[1].children[0].path, "src/util/io.rs");
+
+        assert_eq!(
+            root.children[1].to_json(),
+            "{\"name\":\"README.md\",\"path\":\"README.md\",\"loc\":3,\"owner\":\"Carol\",\"share\":100.0,\"value\":3}"
+        );
+        assert!(root.to_json().contains("\"children\":["));
+    }
+
+    #[test]
+    fn test_squarify() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let rect = Rect {
+            x: 0.0,
+            y: 0.0,
+            w: 6.0,
+            h: 4.0,
+        };
+        let rects = squarify(&[6.0, 6.0, 4.0, 3.0, 2.0, 2.0, 1.0], rect);
+
+        assert_eq!(rects.len(), 7);
+        assert_eq!(
+            rects[0],
+            Rect {
+                x: 0.0,
+                y: 0.0,
+                w: 3.0,
+                h: 2.0
+            }
+        );
+
+        let area: f64 = rects.iter().map(|r| r.w * r.h).sum();
+        assert!((area - 24.0).abs() < 1e-9);
+        assert!(rects.iter().all(|r| r.x >= 0.0
+            && r.y >= 0.0
+            && r.x + r.w <= 6.0 + 1e-9
+            && r.y + r.h <= 4.0 + 1e-9));
+    }
+
+    #[test]
+    fn test_treemap_svg() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let root = TreemapNode::build("repo", &file_lines());
+        let svg = treemap_svg("Ownership", (800, 600), &root, &Color::color_scheme_10());
+
+        assert!(svg.starts_with("<svg"));
+        // 3 legend swatches, the root, 2 directories and 3 files
+        assert_eq!(svg.matches("<rect").count(), 9);
+        assert!(svg.contains("<title>src/main.rs: 8 lines, Alice owns 75.0%</title>"));
+    }
+}
@@ -0,0 +1,556 @@
+#![cfg_attr(not(feature = "tui"), allow(dead_code))]
+
+use super::Processable;
+use crate::by_date::{ByDate, ByDateArgs};
+use crate::effort::{Effort, EffortArgs};
+use crate::fame::{Fame, FameArgs};
+use crate::table::Table;
+use crate::utils::grit_utils::OutputFormat;
+use anyhow::Result;
+use chrono::NaiveDate;
+use std::cmp::Ordering;
+
+const HELP: &str =
+    "<-/-> tab  1-9 sort by column  r reverse  / filter authors  up/down scroll  q quit";
+
+pub struct TuiArgs {
+    path: String,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+    include: Option<String>,
+    exclude: Option<String>,
+    restrict_authors: Option<String>,
+}
+
+impl TuiArgs {
+    pub fn new(
+        path: String,
+        start_date: Option<NaiveDate>,
+        end_date: Option<NaiveDate>,
+        include: Option<String>,
+        exclude: Option<String>,
+        restrict_authors: Option<String>,
+    ) -> TuiArgs {
+        TuiArgs {
+            path,
+            start_date,
+            end_date,
+            include,
+            exclude,
+            restrict_authors,
+        }
+    }
+}
+
+/// What a key press asks the browser to do.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Input {
+    Left,
+    Right,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Char(char),
+    Backspace,
+    Enter,
+    Escape,
+    Quit,
+}
+
+struct Tab {
+    name: String,
+    table: Table,
+    /// sort column and whether it is descending
+    sort: Option<(usize, bool)>,
+}
+
+/// The tabs and the view state of the browser, kept apart from the terminal so it can be
+/// tested.
+struct TuiState {
+    tabs: Vec<Tab>,
+    current: usize,
+    filter: String,
+    editing: bool,
+    scroll: usize,
+    page: usize,
+}
+
+impl TuiState {
+    fn new(tabs: Vec<(String, Table)>) -> TuiState {
+        TuiState {
+            tabs: tabs
+                .into_iter()
+                .map(|(name, table)| Tab {
+                    name,
+                    table,
+                    sort: None,
+                })
+                .collect(),
+            current: 0,
+            filter: String::new(),
+            editing: false,
+            scroll: 0,
+            page: 10,
+        }
+    }
+
+    fn tab(&self) -> &Tab {
+        &self.tabs[self.current]
+    }
+
+    fn author_column(table: &Table) -> Option<usize> {
+        table
+            .titles()
+            .iter()
+            .position(|t| t.eq_ignore_ascii_case("author"))
+    }
+
+    /// numbers compare by value, everything else as text
+    fn compare_cells(a: &str, b: &str) -> Ordering {
+        match (a.parse::<f64>(), b.parse::<f64>()) {
+            (Ok(x), Ok(y)) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+            _ => a.cmp(b),
+        }
+    }
+
+    /// the rows of the current tab matching the author filter, in sort order
+    fn rows(&self) -> Vec<Vec<String>> {
+        let tab = self.tab();
+        let filter = self.filter.to_lowercase();
+
+        let mut rows: Vec<Vec<String>> = match TuiState::author_column(&tab.table) {
+            Some(c) if !filter.is_empty() => tab
+                .table
+                .rows()
+                .iter()
+                .filter(|r| {
+                    r.get(c)
+                        .map(|a| a.to_lowercase().contains(&filter))
+                        .unwrap_or(false)
+                })
+                .cloned()
+                .collect(),
+            _ => tab.table.rows().to_vec(),
+        };
+
+        if let Some((column, descending)) = tab.sort {
+            rows.sort_by(|a, b| {
+                let ordering = TuiState::compare_cells(
+                    a.get(column).map(|c| c.as_str()).unwrap_or(""),
+                    b.get(column).map(|c| c.as_str()).unwrap_or(""),
+                );
+                if descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+        }
+
+        rows
+    }
+
+    /// sorts by `column`, flipping the direction when it is already the sort column.
+    /// Numbers start out largest first.
+    fn sort_by(&mut self, column: usize) {
+        let tab = &mut self.tabs[self.current];
+
+        if column >= tab.table.titles().len() {
+            return;
+        }
+
+        tab.sort = match tab.sort {
+            Some((c, descending)) if c == column => Some((c, !descending)),
+            _ => Some((column, column > 0)),
+        };
+        self.scroll = 0;
+    }
+
+    fn switch_tab(&mut self, forward: bool) {
+        let count = self.tabs.len();
+        self.current = if forward {
+            (self.current + 1) % count
+        } else {
+            (self.current + count - 1) % count
+        };
+        self.scroll = 0;
+    }
+
+    fn scroll_by(&mut self, lines: isize) {
+        let max = self.rows().len().saturating_sub(1) as isize;
+        self.scroll = (self.scroll as isize + lines).clamp(0, max.max(0)) as usize;
+    }
+
+    /// applies a key press, returning false when the browser should close
+    fn handle(&mut self, input: Input) -> bool {
+        if self.editing {
+            match input {
+                Input::Char(c) => self.filter.push(c),
+                Input::Backspace => {
+                    self.filter.pop();
+                }
+                Input::Enter => self.editing = false,
+                Input::Escape => {
+                    self.filter.clear();
+                    self.editing = false;
+                }
+                Input::Quit => return false,
+                _ => {}
+            }
+            self.scroll = 0;
+            return true;
+        }
+
+        match input {
+            Input::Quit | Input::Escape | Input::Char('q') => return false,
+            Input::Left => self.switch_tab(false),
+            Input::Right => self.switch_tab(true),
+            Input::Up => self.scroll_by(-1),
+            Input::Down => self.scroll_by(1),
+            Input::PageUp => self.scroll_by(-(self.page as isize)),
+            Input::PageDown => self.scroll_by(self.page as isize),
+            Input::Char('/') => self.editing = true,
+            Input::Char('r') => {
+                if let Some((c, d)) = self.tab().sort {
+                    self.tabs[self.current].sort = Some((c, !d));
+                }
+            }
+            Input::Char(c) if c.is_ascii_digit() && c != '0' => {
+                self.sort_by(c.to_digit(10).unwrap_or(1) as usize - 1)
+            }
+            _ => {}
+        }
+
+        true
+    }
+
+    /// the titles of the current tab, the sort column marked with its direction
+    fn titles(&self) -> Vec<String> {
+        let tab = self.tab();
+        let mut titles = tab.table.titles().to_vec();
+
+        if let Some((c, descending)) = tab.sort {
+            if let Some(t) = titles.get_mut(c) {
+                t.push_str(if descending { " v" } else { " ^" });
+            }
+        }
+
+        titles
+    }
+
+    /// draws the tab bar, the author filter, the visible rows of the table and the keys
+    #[cfg(feature = "tui")]
+    fn draw(&mut self, frame: &mut ratatui::Frame) {
+        use ratatui::layout::{Alignment, Constraint, Layout};
+        use ratatui::style::{Modifier, Style};
+        use ratatui::text::Line;
+        use ratatui::widgets::{Block, Cell, Paragraph, Row, Table as TableWidget, Tabs};
+
+        let [tabs_area, filter_area, table_area, help_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        // the table's borders and header take three of its lines
+        self.page = (table_area.height as usize).saturating_sub(3).max(1);
+
+        let tabs = Tabs::new(self.tabs.iter().map(|t| t.name.clone()))
+            .select(self.current)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_widget(tabs, tabs_area);
+
+        let filter = match TuiState::author_column(&self.tab().table) {
+            Some(_) => format!(
+                "author filter: {}{}",
+                self.filter,
+                if self.editing { "_" } else { "" }
+            ),
+            None => String::from("author filter: not available on this tab"),
+        };
+        frame.render_widget(Paragraph::new(filter), filter_area);
+
+        let titles = self.titles();
+        let rows = self.rows();
+
+        let widths: Vec<Constraint> = (0..titles.len())
+            .map(|c| {
+                let cells = rows.iter().filter_map(|r| r.get(c));
+                let width = cells
+                    .chain(std::iter::once(&titles[c]))
+                    .map(|v| v.chars().count())
+                    .max()
+                    .unwrap_or(0);
+                Constraint::Length(width as u16)
+            })
+            .collect();
+
+        let body = rows.iter().skip(self.scroll).map(|r| {
+            Row::new(r.iter().map(|v| {
+                let line = Line::from(v.as_str());
+                if v.parse::<f64>().is_ok() {
+                    Cell::from(line.alignment(Alignment::Right))
+                } else {
+                    Cell::from(line)
+                }
+            }))
+        });
+
+        let table = TableWidget::new(body, widths)
+            .header(Row::new(titles).style(Style::default().add_modifier(Modifier::BOLD)))
+            .block(Block::bordered().title(if rows.is_empty() { "no rows" } else { "" }));
+        frame.render_widget(table, table_area);
+
+        frame.render_widget(Paragraph::new(HELP), help_area);
+    }
+}
+
+pub struct Tui {
+    args: TuiArgs,
+}
+
+impl Tui {
+    pub fn new(args: TuiArgs) -> Tui {
+        Tui { args }
+    }
+
+    /// runs fame, bydate and effort once, each into its own tab
+    fn load_tabs(&self) -> Result<Vec<(String, Table)>> {
+        let fame = Fame::new(
+            FameArgs::builder(self.args.path.clone())
+                .sort(Some(String::from("loc")))
+                .start_date(self.args.start_date)
+                .end_date(self.args.end_date)
+                .include(self.args.include.clone())
+                .exclude(self.args.exclude.clone())
+                .restrict_authors(self.args.restrict_authors.clone())
+                .format(OutputFormat::Table)
+                .build()?,
+        );
+
+        let by_date = ByDate::new(
+            ByDateArgs::builder(self.args.path.clone())
+                .start_date(self.args.start_date)
+                .end_date(self.args.end_date)
+                .restrict_authors(self.args.restrict_authors.clone())
+                .format(OutputFormat::Table)
+                .build()?,
+        );
+
+        let effort = Effort::new(
+            EffortArgs::builder(self.args.path.clone())
+                .start_date(self.args.start_date)
+                .end_date(self.args.end_date)
+                .format(OutputFormat::Table)
+                .include(self.args.include.clone())
+                .exclude(self.args.exclude.clone())
+                .restrict_authors(self.args.restrict_authors.clone())
+                .by_author(true)
+                .build()?,
+        );
+
+        Ok(vec![
+            (String::from("Fame"), fame.results_table()?),
+            (String::from("By Date"), by_date.results_table()?),
+            (String::from("Effort"), effort.results_table()?),
+        ])
+    }
+}
+
+#[cfg(feature = "tui")]
+fn input(key: ratatui::crossterm::event::KeyEvent) -> Option<Input> {
+    use ratatui::crossterm::event::{KeyCode, KeyEventKind, KeyModifiers};
+
+    if key.kind != KeyEventKind::Press {
+        return None;
+    }
+
+    match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Some(Input::Quit),
+        KeyCode::Left | KeyCode::BackTab => Some(Input::Left),
+        KeyCode::Right | KeyCode::Tab => Some(Input::Right),
+        KeyCode::Up => Some(Input::Up),
+        KeyCode::Down => Some(Input::Down),
+        KeyCode::PageUp => Some(Input::PageUp),
+        KeyCode::PageDown => Some(Input::PageDown),
+        KeyCode::Char(c) => Some(Input::Char(c)),
+        KeyCode::Backspace => Some(Input::Backspace),
+        KeyCode::Enter => Some(Input::Enter),
+        KeyCode::Esc => Some(Input::Escape),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "tui")]
+fn run(state: &mut TuiState) -> Result<()> {
+    use ratatui::crossterm::event::{self, Event};
+
+    // switches to the alternate screen in raw mode, restoring the terminal on a panic too
+    let mut terminal = ratatui::init();
+
+    let mut browse = || -> Result<()> {
+        loop {
+            terminal.draw(|frame| state.draw(frame))?;
+
+            if let Event::Key(key) = event::read()? {
+                if let Some(i) = input(key) {
+                    if !state.handle(i) {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    };
+
+    let result = browse();
+    ratatui::restore();
+
+    result
+}
+
+impl Processable<()> for Tui {
+    #[cfg(feature = "tui")]
+    fn process(&self) -> Result<()> {
+        use std::io::IsTerminal;
+
+        if !std::io::stdout().is_terminal() {
+            return Err(anyhow::anyhow!("tui needs an interactive terminal"));
+        }
+
+        let mut state = TuiState::new(self.load_tabs()?);
+
+        run(&mut state)
+    }
+
+    #[cfg(not(feature = "tui"))]
+    fn process(&self) -> Result<()> {
+        Err(anyhow::anyhow!(
+            "grit was built without the terminal UI, rebuild it with --features tui"
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::LevelFilter;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    fn test_state() -> TuiState {
+        let mut fame = Table::new();
+        fame.set_titles(table_row!["Author", "LOC"]);
+        fame.add_row(table_row!["Alice", 9]);
+        fame.add_row(table_row!["Bob", 40]);
+        fame.add_row(table_row!["alan", 100]);
+
+        let mut dates = Table::new();
+        dates.set_titles(table_row!["date", "count"]);
+        dates.add_row(table_row!["2020-01-01", 1]);
+
+        TuiState::new(vec![
+            (String::from("Fame"), fame),
+            (String::from("By Date"), dates),
+        ])
+    }
+
+    fn authors(state: &TuiState) -> Vec<String> {
+        state.rows().iter().map(|r| r[0].clone()).collect()
+    }
+
+    #[test]
+    fn test_tui_sort_and_filter() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let mut state = test_state();
+
+        assert!(state.handle(Input::Char('2')));
+        assert_eq!(authors(&state), vec!["alan", "Bob", "Alice"]);
+
+        state.handle(Input::Char('r'));
+        assert_eq!(authors(&state), vec!["Alice", "Bob", "alan"]);
+
+        state.handle(Input::Char('1'));
+        assert_eq!(authors(&state), vec!["Alice", "Bob", "alan"]);
+
+        state.handle(Input::Char('/'));
+        state.handle(Input::Char('A'));
+        state.handle(Input::Char('l'));
+        assert!(state.handle(Input::Char('q')));
+        state.handle(Input::Backspace);
+        state.handle(Input::Enter);
+        assert_eq!(state.filter, "Al");
+        assert_eq!(authors(&state), vec!["Alice", "alan"]);
+
+        state.handle(Input::Right);
+        assert_eq!(state.tab().name, "By Date");
+        assert_eq!(state.rows().len(), 1);
+
+        state.handle(Input::Right);
+        assert_eq!(state.tab().name, "Fame");
+        assert!(!state.handle(Input::Char('q')));
+    }
+
+    #[cfg(feature = "tui")]
+    #[test]
+    fn test_tui_draw() {
+        use ratatui::backend::TestBackend;
+        use ratatui::Terminal;
+
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let mut state = test_state();
+        state.handle(Input::Char('2'));
+
+        let mut terminal = Terminal::new(TestBackend::new(40, 9)).unwrap();
+        let mut screen = |state: &mut TuiState| -> Vec<String> {
+            terminal.draw(|frame| state.draw(frame)).unwrap();
+
+            let buffer = terminal.backend().buffer();
+            (0..buffer.area.height)
+                .map(|y| {
+                    (0..buffer.area.width)
+                        .map(|x| buffer[(x, y)].symbol())
+                        .collect::<String>()
+                })
+                .collect()
+        };
+
+        let lines = screen(&mut state);
+
+        assert!(lines[0].starts_with(" Fame │ By Date"));
+        assert!(lines[1].starts_with("author filter: "));
+        assert!(lines[3].contains("LOC v"));
+        assert!(lines[4].contains("alan") && lines[4].contains("100"));
+        assert!(lines[8].starts_with("<-/-> tab"));
+
+        // the bordered table has room for 3 rows, scrolling starts it at the second
+        assert_eq!(state.page, 3);
+        state.handle(Input::Down);
+        let lines = screen(&mut state);
+        assert!(lines[4].contains("Bob"));
+        assert!(lines[5].contains("Alice"));
+        assert!(lines[6].trim_matches(|c| c == '│' || c == ' ').is_empty());
+    }
+
+    #[test]
+    fn test_tui_load_tabs() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let tui = Tui::new(TuiArgs::new(path.to_string(), None, None, None, None, None));
+
+        let tabs = tui.load_tabs().unwrap();
+
+        assert_eq!(tabs.len(), 3);
+        assert_eq!(tabs[0].1.rows()[0][0], "Alice");
+        assert_eq!(tabs[1].1.rows().len(), 5);
+        assert_eq!(tabs[2].1.rows().len(), 4);
+    }
+}
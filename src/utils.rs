@@ -1,280 +1,1845 @@
 #[macro_export]
-macro_rules! filter_try {
-    ($e:expr) => {
-        match $e {
-            Ok(t) => t,
-            Err(e) => return Some(Err(e)),
-        }
+macro_rules! format_tostr {
+    ($msg:expr, $s:expr) => {
+        format!($msg, $s).as_str()
     };
 }
 
-#[macro_export]
-macro_rules! format_tostr {
-    ($msg:expr, $s:expr) => {
-        format!($msg, $s).as_str()
-    };
-}
+pub mod grit_utils {
+
+    use crate::cache::Memo;
+    use crate::error::GritError;
+    use crate::identity;
+    use crate::progress;
+    use crate::timing;
+    use anyhow::{anyhow, Result};
+    use chrono::format::{Item, StrftimeItems};
+    use chrono::{
+        DateTime, Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Weekday,
+    };
+    use chrono_tz::Tz;
+    use git2::{Commit, ObjectType, Oid, Repository, RepositoryOpenFlags, Time, Tree};
+    use glob::Pattern;
+    use indicatif::{ProgressBar, ProgressStyle};
+    use std::cell::RefCell;
+    use std::collections::{HashMap, HashSet};
+    use std::env;
+    use std::ffi::OsStr;
+    use std::fs;
+    use std::fs::File;
+    use std::io::Write;
+    use std::path::{Component, Path, PathBuf};
+    use std::str::FromStr;
+    use std::sync::{Arc, Mutex, OnceLock};
+    use tokio::runtime::{Builder, Runtime};
+
+    type GenResult<T> = Result<T>;
+
+    /// The commits made between a start and end date, found in one walk of the history.
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub struct CommitRange {
+        /// the oldest commit made on or after the start date, with a start date and a
+        /// commit in range
+        pub earliest: Option<Vec<u8>>,
+        /// the newest commit made on or before the end date, with an end date and a commit
+        /// in range
+        pub latest: Option<Vec<u8>>,
+        /// every commit made within the dates, None when neither is given
+        pub commits: Option<Arc<HashSet<Oid>>>,
+    }
+
+    impl CommitRange {
+        /// the range of dates no commit was made within
+        pub fn empty() -> CommitRange {
+            CommitRange {
+                earliest: None,
+                latest: None,
+                commits: Some(Arc::new(HashSet::new())),
+            }
+        }
+
+        /// true when dates were given and no commit was made within them.  The bounds are
+        /// None then too, which must not be taken for the whole history.
+        pub fn is_empty(&self) -> bool {
+            self.commits.as_ref().map(|c| c.is_empty()).unwrap_or(false)
+        }
+
+        /// true when the commit was made within the dates, always without dates
+        pub fn contains(&self, oid: &Oid) -> bool {
+            self.commits
+                .as_ref()
+                .map(|c| c.contains(oid))
+                .unwrap_or(true)
+        }
+    }
+
+    type CommitRangeKey = (String, Option<NaiveDate>, Option<NaiveDate>);
+
+    /// files blamed at a time by the streaming output, bounding what is held in memory
+    pub const STREAM_CHUNK_FILES: usize = 256;
+
+    /// files blamed at once, so a large repo doesn't queue a task for every file up front
+    pub const MAX_IN_FLIGHT_FILES: usize = 64;
+
+    /// Base directory that file paths are reported relative to.
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    pub enum RelativeTo {
+        Repo,
+        Cwd,
+    }
+
+    impl FromStr for RelativeTo {
+        type Err = anyhow::Error;
+
+        fn from_str(s: &str) -> Result<Self> {
+            match s {
+                "repo" => Ok(RelativeTo::Repo),
+                "cwd" => Ok(RelativeTo::Cwd),
+                _ => Err(anyhow!("relative-to must be either 'repo' or 'cwd'")),
+            }
+        }
+    }
+
+    static TIMEZONE: OnceLock<Timezone> = OnceLock::new();
+    static WEEKEND_DAYS: OnceLock<Vec<Weekday>> = OnceLock::new();
+    static WEEK_START: OnceLock<Weekday> = OnceLock::new();
+    static INCLUDE_VENDORED: OnceLock<bool> = OnceLock::new();
+    static THREADS: OnceLock<usize> = OnceLock::new();
+    static QUIET: OnceLock<bool> = OnceLock::new();
+    static DATE_FORMAT: OnceLock<String> = OnceLock::new();
+    static RAW_PATHS: OnceLock<Mutex<HashMap<String, Vec<u8>>>> = OnceLock::new();
+    static FILE_LISTS: Memo<(String, Oid), Vec<String>> = Memo::new();
+    static COMMIT_RANGES: Memo<CommitRangeKey, CommitRange> = Memo::new();
+
+    /// strftime format of ISO week dates, e.g. 2020-W01-3
+    const ISO_WEEK_FORMAT: &str = "%G-W%V-%u";
+
+    /// Timezone that commit times are bucketed into days with.
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    pub enum Timezone {
+        Local,
+        Utc,
+        /// the offset recorded with each commit
+        Author,
+        /// a fixed offset, in seconds east of UTC
+        Offset(i32),
+        /// an IANA zone such as Europe/Paris, its offset following daylight saving time
+        Named(Tz),
+    }
+
+    impl FromStr for Timezone {
+        type Err = anyhow::Error;
+
+        fn from_str(s: &str) -> Result<Self> {
+            match s {
+                "local" => Ok(Timezone::Local),
+                "utc" | "UTC" => Ok(Timezone::Utc),
+                "author" => Ok(Timezone::Author),
+                _ => parse_offset(s)
+                    .map(Timezone::Offset)
+                    .or_else(|| s.parse::<Tz>().ok().map(Timezone::Named))
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "timezone must be 'local', 'utc', 'author', an offset such as '+05:30' or an IANA name such as 'Europe/Paris'"
+                        )
+                    }),
+            }
+        }
+    }
+
+    /// seconds east of UTC for `+HH`, `+HHMM` or `+HH:MM`, and the same with `-`
+    fn parse_offset(s: &str) -> Option<i32> {
+        let (sign, rest) = match s.chars().next()? {
+            '+' => (1, &s[1..]),
+            '-' => (-1, &s[1..]),
+            _ => return None,
+        };
+
+        let digits: String = rest.chars().filter(|c| *c != ':').collect();
+
+        if !digits.chars().all(|c| c.is_ascii_digit()) || rest.matches(':').count() > 1 {
+            return None;
+        }
+
+        let (hours, minutes) = match digits.len() {
+            2 => (digits.parse::<i32>().ok()?, 0),
+            4 => (
+                digits[..2].parse::<i32>().ok()?,
+                digits[2..].parse::<i32>().ok()?,
+            ),
+            _ => return None,
+        };
+
+        if hours > 14 || minutes > 59 {
+            return None;
+        }
+
+        Some(sign * (hours * 3600 + minutes * 60))
+    }
+
+    /// sets the timezone `convert_git_time` buckets days in for the rest of the run.
+    /// Only the first call has an effect.
+    pub fn set_timezone(tz: Timezone) {
+        let _ = TIMEZONE.set(tz);
+    }
+
+    /// How tabular results are written.
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    pub enum OutputFormat {
+        Table,
+        Csv,
+        Json,
+        Markdown,
+        Prom,
+    }
+
+    impl FromStr for OutputFormat {
+        type Err = anyhow::Error;
+
+        fn from_str(s: &str) -> Result<Self> {
+            match s {
+                "table" => Ok(OutputFormat::Table),
+                "csv" => Ok(OutputFormat::Csv),
+                "json" => Ok(OutputFormat::Json),
+                "markdown" => Ok(OutputFormat::Markdown),
+                "prom" => Ok(OutputFormat::Prom),
+                _ => Err(anyhow!(
+                    "format must be either 'table', 'csv', 'json', 'markdown' or 'prom'"
+                )),
+            }
+        }
+    }
+
+    /// A file left out of the results because processing it failed.
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    pub struct SkippedFile {
+        pub file_name: String,
+        pub reason: String,
+    }
+
+    impl SkippedFile {
+        pub fn new(file_name: &str, reason: String) -> SkippedFile {
+            SkippedFile {
+                file_name: file_name.to_string(),
+                reason,
+            }
+        }
+    }
+
+    /// prints the skipped files to stderr, failing the run when `strict` is set
+    pub fn report_skipped(skipped: &[SkippedFile], strict: bool) -> GenResult<()> {
+        if skipped.is_empty() {
+            return Ok(());
+        }
+
+        eprintln!("Skipped {} file(s):", skipped.len());
+        for s in skipped.iter() {
+            eprintln!("  {}: {}", s.file_name, s.reason);
+        }
+
+        if strict {
+            Err(anyhow!("{} file(s) could not be processed", skipped.len()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// a spinner on stderr for work without a known length.  `template` is an indicatif
+    /// template, e.g. "{spinner} {pos} commits read"
+    pub fn spinner(template: &str) -> ProgressBar {
+        if !progress::bars_enabled() {
+            return ProgressBar::hidden();
+        }
+
+        let pb = ProgressBar::new_spinner();
+        pb.set_style(ProgressStyle::default_spinner().template(template));
+        pb.enable_steady_tick(100);
+        pb
+    }
+
+    /// discards the reports that would go to stdout.  Files are still written.
+    pub fn set_quiet(quiet: bool) {
+        let _ = QUIET.set(quiet);
+    }
+
+    /// the output file when one is given, stdout otherwise
+    pub fn output_writer(file: Option<&str>) -> GenResult<Box<dyn Write>> {
+        match file {
+            Some(f) => Ok(Box::new(File::create(f)?)),
+            None if *QUIET.get().unwrap_or(&false) => Ok(Box::new(std::io::sink())),
+            None => Ok(Box::new(std::io::stdout())),
+        }
+    }
+
+    /// lists the files in the tree of HEAD, filtered by the include and exclude globs.
+    /// Reads the tree rather than the working directory, so bare repositories work too.
+    pub fn generate_file_list(
+        path: &str,
+        include: Option<String>,
+        exclude: Option<String>,
+    ) -> GenResult<Vec<String>> {
+        timing::phase("file listing", || {
+            let repo = open_repo(path)?;
+            let file_names = cached_tree_file_names(path, &repo, &repo.head()?.peel_to_tree()?)?;
+
+            filter_file_names(file_names, include, exclude)
+        })
+    }
+
+    /// lists the files in the tree of the commit `commit_id`, filtered by the include and
+    /// exclude globs.
+    pub fn generate_file_list_at(
+        path: &str,
+        commit_id: &[u8],
+        include: Option<String>,
+        exclude: Option<String>,
+    ) -> GenResult<Vec<String>> {
+        timing::phase("file listing", || {
+            let repo = open_repo(path)?;
+            let commit = repo.find_commit(Oid::from_bytes(commit_id)?)?;
+            let file_names = cached_tree_file_names(path, &repo, &commit.tree()?)?;
+
+            filter_file_names(file_names, include, exclude)
+        })
+    }
+
+    /// the worker threads blames and diffs run on, one per CPU when None or 0.  Only the
+    /// first call has an effect.
+    pub fn set_threads(threads: Option<usize>) {
+        if let Some(t) = threads.filter(|t| *t > 0) {
+            let _ = THREADS.set(t);
+        }
+    }
+
+    /// a runtime for running files or commits concurrently, on `--threads` workers named
+    /// after `name`
+    pub fn runtime(name: &str) -> Result<Runtime> {
+        let mut builder = Builder::new_multi_thread();
+
+        if let Some(t) = THREADS.get() {
+            builder.worker_threads(*t);
+        }
+
+        builder
+            .thread_name(name)
+            .build()
+            .map_err(|e| anyhow!("cannot start the {} threads: {}", name, e))
+    }
+
+    /// keeps files `.gitattributes` marks `linguist-vendored` or `linguist-generated` in the
+    /// file lists.  They are left out by default.
+    pub fn set_include_vendored(include: bool) {
+        let _ = INCLUDE_VENDORED.set(include);
+    }
+
+    fn cached_tree_file_names(
+        path: &str,
+        repo: &Repository,
+        tree: &Tree,
+    ) -> GenResult<Vec<String>> {
+        FILE_LISTS.get_or_try((path.to_string(), tree.id()), || {
+            tree_file_names(repo, tree)
+        })
+    }
+
+    /// the paths of every blob in `tree`, less the vendored and generated ones.  Submodules
+    /// are commits, not blobs, so are left out.
+    fn tree_file_names(repo: &Repository, tree: &Tree) -> GenResult<Vec<String>> {
+        let mut file_names: Vec<String> = Vec::new();
+        let mut rules: Vec<(usize, Vec<LinguistRule>)> = Vec::new();
+        let include_vendored = *INCLUDE_VENDORED.get().unwrap_or(&false);
+
+        walk_tree(
+            repo,
+            tree,
+            b"",
+            include_vendored,
+            &mut file_names,
+            &mut rules,
+        )?;
+
+        if rules.is_empty() {
+            return Ok(file_names);
+        }
+
+        // deeper .gitattributes files take precedence, so their rules are checked last
+        rules.sort_by_key(|(depth, _)| *depth);
+        let rules: Vec<LinguistRule> = rules.into_iter().flat_map(|(_, r)| r).collect();
+
+        Ok(file_names
+            .into_iter()
+            .filter(|f| !LinguistRule::is_vendored(&rules, f))
+            .collect())
+    }
+
+    /// walks `tree` depth first by the bytes of its paths, as git's own tree walk gives up
+    /// on directory names that are not UTF-8
+    fn walk_tree(
+        repo: &Repository,
+        tree: &Tree,
+        root: &[u8],
+        include_vendored: bool,
+        file_names: &mut Vec<String>,
+        rules: &mut Vec<(usize, Vec<LinguistRule>)>,
+    ) -> GenResult<()> {
+        for entry in tree.iter() {
+            let mut path = root.to_vec();
+            path.extend_from_slice(entry.name_bytes());
+
+            match entry.kind() {
+                Some(ObjectType::Tree) => {
+                    path.push(b'/');
+                    let subtree = repo.find_tree(entry.id())?;
+                    walk_tree(repo, &subtree, &path, include_vendored, file_names, rules)?;
+                }
+                Some(ObjectType::Blob) => {
+                    if entry.name_bytes() == b".gitattributes" && !include_vendored {
+                        if let Ok(blob) = repo.find_blob(entry.id()) {
+                            let content = String::from_utf8_lossy(blob.content());
+                            rules.push((
+                                root.iter().filter(|b| **b == b'/').count(),
+                                LinguistRule::parse(&String::from_utf8_lossy(root), &content),
+                            ));
+                        }
+                    }
+                    file_names.push(file_name_from_bytes(&path));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn raw_paths() -> &'static Mutex<HashMap<String, Vec<u8>>> {
+        RAW_PATHS.get_or_init(|| Mutex::new(HashMap::new()))
+    }
+
+    /// the file name of a repo path as git stores it.  Paths that are not UTF-8 are named
+    /// lossily, with a warning, and remembered so `git_path` can map the name back.
+    pub fn file_name_from_bytes(raw: &[u8]) -> String {
+        if let Ok(name) = std::str::from_utf8(raw) {
+            return name.to_string();
+        }
+
+        let name = String::from_utf8_lossy(raw).to_string();
+        let mut paths = raw_paths().lock().expect("cannot lock the raw paths");
+
+        if !paths.contains_key(&name) {
+            warn!(
+                "{:?} is not a UTF-8 path, it is shown as {}",
+                String::from_utf8_lossy(&raw.escape_ascii().collect::<Vec<u8>>()),
+                name
+            );
+            paths.insert(name.clone(), raw.to_vec());
+        }
+
+        name
+    }
+
+    /// the path git knows the file `file_name` by, undoing the lossy naming of paths that
+    /// are not UTF-8
+    pub fn git_path(file_name: &str) -> PathBuf {
+        match raw_paths()
+            .lock()
+            .expect("cannot lock the raw paths")
+            .get(file_name)
+        {
+            Some(raw) => bytes_path(raw),
+            None => PathBuf::from(file_name),
+        }
+    }
+
+    #[cfg(unix)]
+    fn bytes_path(raw: &[u8]) -> PathBuf {
+        use std::os::unix::ffi::OsStrExt;
+
+        PathBuf::from(OsStr::from_bytes(raw))
+    }
+
+    #[cfg(not(unix))]
+    fn bytes_path(raw: &[u8]) -> PathBuf {
+        PathBuf::from(String::from_utf8_lossy(raw).to_string())
+    }
+
+    /// A `.gitattributes` line setting or unsetting `linguist-vendored` or
+    /// `linguist-generated`.
+    #[derive(Debug)]
+    struct LinguistRule {
+        /// directory of the `.gitattributes` file, with a trailing `/` unless it is the root
+        dir: String,
+        pattern: Pattern,
+        /// patterns with a `/` match the path from `dir`, the others any file name
+        anchored: bool,
+        vendored: Option<bool>,
+        generated: Option<bool>,
+    }
+
+    impl LinguistRule {
+        fn parse(dir: &str, content: &str) -> Vec<LinguistRule> {
+            content
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                .filter_map(|l| {
+                    let mut parts = l.split_whitespace();
+                    let glob = parts.next()?;
+
+                    let mut rule_attrs = (None, None);
+                    for attr in parts {
+                        let (name, value) = LinguistRule::attr_value(attr);
+                        match name {
+                            "linguist-vendored" => rule_attrs.0 = Some(value),
+                            "linguist-generated" => rule_attrs.1 = Some(value),
+                            _ => {}
+                        }
+                    }
+
+                    if rule_attrs == (None, None) {
+                        return None;
+                    }
+
+                    let anchored = glob.trim_end_matches('/').contains('/');
+                    let pattern = Pattern::new(glob.trim_start_matches('/')).ok()?;
+
+                    Some(LinguistRule {
+                        dir: dir.to_string(),
+                        pattern,
+                        anchored,
+                        vendored: rule_attrs.0,
+                        generated: rule_attrs.1,
+                    })
+                })
+                .collect()
+        }
+
+        /// `attr`, `-attr`, `!attr` and `attr=value` as the name and whether it is set
+        fn attr_value(attr: &str) -> (&str, bool) {
+            if let Some(name) = attr.strip_prefix('-').or_else(|| attr.strip_prefix('!')) {
+                return (name, false);
+            }
+
+            match attr.split_once('=') {
+                Some((name, value)) => (name, value != "false"),
+                None => (attr, true),
+            }
+        }
+
+        fn matches(&self, file_name: &str) -> bool {
+            let rel = match file_name.strip_prefix(self.dir.as_str()) {
+                Some(r) => r,
+                None => return false,
+            };
+
+            if self.anchored {
+                let options = glob::MatchOptions {
+                    require_literal_separator: true,
+                    ..Default::default()
+                };
+                self.pattern.matches_with(rel, options)
+            } else {
+                let name = rel.rsplit('/').next().unwrap_or(rel);
+                self.pattern.matches(name)
+            }
+        }
+
+        /// whether the last rule matching `file_name` for either attribute sets it
+        fn is_vendored(rules: &[LinguistRule], file_name: &str) -> bool {
+            let mut vendored = false;
+            let mut generated = false;
+
+            for r in rules.iter().filter(|r| r.matches(file_name)) {
+                vendored = r.vendored.unwrap_or(vendored);
+                generated = r.generated.unwrap_or(generated);
+            }
+
+            vendored || generated
+        }
+    }
+
+    /// uses `/` as the path separator, which is what git and the globs match against.
+    /// Lets Windows users write `src\*` as well as `src/*`.
+    pub fn normalize_separators(path: &str) -> String {
+        path.replace('\\', "/")
+    }
+
+    /// the repo relative path of `subdir`, checked to be a directory in the tree of
+    /// `commit_id`, or of HEAD when none is given
+    pub fn subdir_path(
+        repo_path: &str,
+        commit_id: Option<&[u8]>,
+        subdir: &str,
+    ) -> GenResult<String> {
+        let repo = open_repo(repo_path)?;
+        let tree = match commit_id {
+            Some(c) => repo.find_commit(Oid::from_bytes(c)?)?.tree()?,
+            None => repo.head()?.peel_to_tree()?,
+        };
+
+        let normalized = normalize_separators(subdir);
+        let normalized = normalized.trim_matches('/');
+
+        let is_dir = !normalized.is_empty()
+            && tree
+                .get_path(Path::new(normalized))
+                .map(|e| e.kind() == Some(ObjectType::Tree))
+                .unwrap_or(false);
+
+        if !is_dir {
+            return Err(anyhow!(
+                "{} is not a directory in the repository tree",
+                subdir
+            ));
+        }
+
+        Ok(normalized.to_string())
+    }
+
+    /// the globs listed in `file`, one per line.  Blank lines and lines starting with `#` are
+    /// skipped.
+    pub fn read_patterns_file(file: &str) -> GenResult<Vec<String>> {
+        let content =
+            fs::read_to_string(file).map_err(|e| anyhow!("cannot read {}: {}", file, e))?;
+
+        Ok(content
+            .lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(String::from)
+            .collect())
+    }
+
+    /// parses a comma delimited list of globs
+    pub fn parse_patterns(patterns: Option<String>) -> GenResult<Option<Vec<Pattern>>> {
+        match patterns {
+            Some(p) => {
+                let result: Result<Vec<Pattern>, GritError> = p
+                    .split(',')
+                    .map(|s| {
+                        Pattern::new(&normalize_separators(s)).map_err(|e| {
+                            GritError::InvalidPattern {
+                                pattern: s.to_string(),
+                                message: e.msg.to_string(),
+                            }
+                        })
+                    })
+                    .collect();
+                Ok(Some(result?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn filter_file_names(
+        file_names: Vec<String>,
+        include: Option<String>,
+        exclude: Option<String>,
+    ) -> GenResult<Vec<String>> {
+        let includes = parse_patterns(include)?;
+        let excludes = parse_patterns(exclude)?;
+
+        let result = file_names
+            .into_iter()
+            .map(|s| normalize_separators(&s))
+            .filter_map(|s| {
+                let result = match &includes {
+                    Some(il) => {
+                        if il.iter().any(|p| p.matches(&s)) {
+                            Some(s)
+                        } else {
+                            None
+                        }
+                    }
+                    None => Some(s),
+                };
+                result
+            })
+            .filter_map(|s| {
+                let result = if let Some(el) = &excludes {
+                    if el.iter().any(|p| p.matches(&s)) {
+                        None
+                    } else {
+                        Some(s)
+                    }
+                } else {
+                    Some(s)
+                };
+
+                result
+            })
+            .collect();
+
+        Ok(result)
+    }
+
+    pub fn convert_string_list_to_vec(input: Option<String>) -> Option<Vec<String>> {
+        let result: Option<Vec<String>> =
+            input.map(|s| s.split(",").map(|e| e.to_string()).collect());
+
+        result
+    }
+
+    /// the day of `time` in the timezone set with `set_timezone`, local by default
+    pub fn convert_git_time(time: &Time) -> NaiveDate {
+        commit_day(time, *TIMEZONE.get().unwrap_or(&Timezone::Local))
+    }
+
+    /// the wall clock date and time of `time` in the timezone set with `set_timezone`
+    pub fn convert_git_datetime(time: &Time) -> NaiveDateTime {
+        commit_datetime(time, *TIMEZONE.get().unwrap_or(&Timezone::Local))
+    }
+
+    /// comma delimited day names such as `fri,sat`
+    pub fn parse_weekdays(s: &str) -> GenResult<Vec<Weekday>> {
+        s.split(',')
+            .map(|d| {
+                d.trim()
+                    .parse::<Weekday>()
+                    .map_err(|_| anyhow!("'{}' is not a day of the week, use mon to sun", d.trim()))
+            })
+            .collect()
+    }
+
+    /// sets the days `is_weekend` treats as the weekend for the rest of the run, Saturday
+    /// and Sunday by default.  Only the first call has an effect.
+    pub fn set_weekend_days(days: Vec<Weekday>) {
+        let _ = WEEKEND_DAYS.set(days);
+    }
+
+    /// true when `time` falls on a weekend day, set with `set_weekend_days`, in the timezone
+    /// set with `set_timezone`
+    pub fn is_weekend(time: &Time) -> bool {
+        let day = convert_git_time(time).weekday();
+
+        match WEEKEND_DAYS.get() {
+            Some(days) => days.contains(&day),
+            None => day == Weekday::Sat || day == Weekday::Sun,
+        }
+    }
+
+    /// sets the first day of the week for weekly periods for the rest of the run, Monday by
+    /// default.  Only the first call has an effect.
+    pub fn set_week_start(day: Weekday) {
+        let _ = WEEK_START.set(day);
+    }
+
+    /// days between the start of the week set with `set_week_start` and `day`
+    pub fn days_into_week(day: Weekday) -> i64 {
+        days_since(day, *WEEK_START.get().unwrap_or(&Weekday::Mon))
+    }
+
+    fn days_since(day: Weekday, start: Weekday) -> i64 {
+        (day.num_days_from_monday() as i64 - start.num_days_from_monday() as i64).rem_euclid(7)
+    }
+
+    /// the UTC date and time `seconds` after the epoch, the epoch itself when out of range
+    fn utc_datetime(seconds: i64) -> NaiveDateTime {
+        DateTime::from_timestamp(seconds, 0)
+            .map(|d| d.naive_utc())
+            .unwrap_or_default()
+    }
+
+    fn commit_datetime(time: &Time, tz: Timezone) -> NaiveDateTime {
+        let offset = match tz {
+            Timezone::Local => {
+                return Local
+                    .from_utc_datetime(&utc_datetime(time.seconds()))
+                    .naive_local()
+            }
+            Timezone::Named(tz) => {
+                return tz
+                    .from_utc_datetime(&utc_datetime(time.seconds()))
+                    .naive_local()
+            }
+            Timezone::Utc => 0,
+            Timezone::Author => time.offset_minutes() * 60,
+            Timezone::Offset(o) => o,
+        };
+
+        utc_datetime(time.seconds() + offset as i64)
+    }
+
+    fn commit_day(time: &Time, tz: Timezone) -> NaiveDate {
+        commit_datetime(time, tz).date()
+    }
+
+    /// the local date today
+    pub fn today() -> NaiveDate {
+        Local::now().date_naive()
+    }
+
+    /// seconds since the epoch at the start of `d`, taking the day as UTC as the date
+    /// filters always have
+    pub fn start_of_day_seconds(d: NaiveDate) -> i64 {
+        d.and_time(NaiveTime::MIN).and_utc().timestamp()
+    }
+
+    /// seconds since the epoch at the last second of `d`, taking the day as UTC
+    pub fn end_of_day_seconds(d: NaiveDate) -> i64 {
+        start_of_day_seconds(d) + 86_399
+    }
+
+    /// formats `d` with the format set by `set_date_format`, YYYY-MM-DD by default
+    pub fn format_date(d: NaiveDate) -> String {
+        match DATE_FORMAT.get() {
+            Some(f) => d.format(f).to_string(),
+            None => format!("{}-{:0>2}-{:0>2}", d.year(), d.month(), d.day()),
+        }
+    }
+
+    /// fails when the start date falls after the end date, as the range would be empty
+    pub fn check_date_range(start: Option<NaiveDate>, end: Option<NaiveDate>) -> Result<()> {
+        match (start, end) {
+            (Some(s), Some(e)) if s > e => Err(anyhow!(
+                "start-date {} is after end-date {}",
+                s.format("%Y-%m-%d"),
+                e.format("%Y-%m-%d")
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// the format `file`'s extension names, e.g. json for a .json file
+    pub fn file_format(file: &str) -> Option<OutputFormat> {
+        match get_filename_extension(file)? {
+            "csv" => Some(OutputFormat::Csv),
+            "json" => Some(OutputFormat::Json),
+            "md" => Some(OutputFormat::Markdown),
+            "prom" => Some(OutputFormat::Prom),
+            "txt" => Some(OutputFormat::Table),
+            _ => None,
+        }
+    }
+
+    /// fails when `file`'s extension names a different format than the one written, e.g.
+    /// json written to a .csv file.  Extensions of no format, such as .svg, are let through.
+    pub fn check_output_file(file: Option<&str>, format: OutputFormat) -> Result<()> {
+        let extension = match file.and_then(get_filename_extension) {
+            Some(e) if ["csv", "json", "md", "prom", "txt"].contains(&e) => e,
+            _ => return Ok(()),
+        };
+
+        let (name, expected) = match format {
+            OutputFormat::Table => ("table", "txt"),
+            OutputFormat::Csv => ("csv", "csv"),
+            OutputFormat::Json => ("json", "json"),
+            OutputFormat::Markdown => ("markdown", "md"),
+            OutputFormat::Prom => ("prom", "prom"),
+        };
+
+        if extension != expected {
+            return Err(anyhow!(
+                "the file for {} output must end in .{}, not .{}",
+                name,
+                expected,
+                extension
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// fails when the chart `--image` writes is given a file not ending in .svg
+    pub fn check_image_file(file: Option<&str>) -> Result<()> {
+        match file {
+            Some(f) if !check_file_type(f, "svg") => {
+                Err(anyhow!("the image file format must be svg, not {}", f))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// the checks every command's arguments go through before it runs
+    pub fn validate_args(
+        start_date: Option<NaiveDate>,
+        end_date: Option<NaiveDate>,
+        file: Option<&str>,
+        format: OutputFormat,
+    ) -> Result<()> {
+        check_date_range(start_date, end_date)?;
+        check_output_file(file, format)
+    }
+
+    /// the strftime format `value` names, either itself or 'iso-week'.  Fails on
+    /// specifiers chrono doesn't know.
+    pub fn parse_date_format(value: &str) -> Result<String> {
+        let format = match value {
+            "iso-week" => ISO_WEEK_FORMAT,
+            f => f,
+        };
+
+        if StrftimeItems::new(format).any(|i| i == Item::Error) {
+            return Err(anyhow!("{} is not a valid strftime format", value));
+        }
+
+        Ok(format.to_string())
+    }
+
+    /// sets the format `format_date` writes dates in for the rest of the run.  Only the
+    /// first call has an effect.
+    pub fn set_date_format(format: String) {
+        let _ = DATE_FORMAT.set(format);
+    }
+
+    /// quotes and escapes a value for use as a JSON string
+    pub fn json_string(value: &str) -> String {
+        let mut result = String::with_capacity(value.len() + 2);
+        result.push('"');
+
+        for c in value.chars() {
+            match c {
+                '"' => result.push_str("\\\""),
+                '\\' => result.push_str("\\\\"),
+                '\n' => result.push_str("\\n"),
+                '\r' => result.push_str("\\r"),
+                '\t' => result.push_str("\\t"),
+                c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+                c => result.push(c),
+            }
+        }
+
+        result.push('"');
+        result
+    }
+
+    /// escapes a value for use as HTML text or an attribute
+    pub fn html_escape(value: &str) -> String {
+        value
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    }
+
+    pub fn get_filename_extension(filename: &str) -> Option<&str> {
+        Path::new(filename).extension().and_then(OsStr::to_str)
+    }
+
+    pub fn strip_extension(filename: &str) -> Option<&str> {
+        Path::new(filename).file_stem().and_then(OsStr::to_str)
+    }
+
+    pub fn create_html(filename: &str) -> GenResult<()> {
+        let file_base = strip_extension(filename)
+            .ok_or_else(|| GritError::InvalidFileName(filename.to_string()))?;
+
+        let html_file = format!("{}{}", file_base, ".html");
+        let html_output = format!(
+            "<html><head></head><body><img src=\"{}\"/></body></html>",
+            filename
+        );
+
+        File::create(&html_file)
+            .and_then(|mut output| output.write_all(html_output.as_bytes()))
+            .map_err(|source| GritError::Io {
+                path: html_file,
+                source,
+            })?;
+
+        Ok(())
+    }
+
+    thread_local! {
+        /// the repository last opened by this thread and its path
+        static THREAD_REPO: RefCell<Option<(String, Repository)>> = const { RefCell::new(None) };
+    }
+
+    /// runs `f` with this thread's repository at `path`, opening it only on first use.
+    /// Repositories aren't `Send`, so the blame workers each keep their own and reuse it
+    /// across files instead of opening one per file.
+    pub fn with_thread_repo<T>(
+        path: &str,
+        f: impl FnOnce(&Repository) -> GenResult<T>,
+    ) -> GenResult<T> {
+        let cached = THREAD_REPO.with(|r| r.borrow_mut().take());
+
+        let repo = match cached {
+            Some((p, repo)) if p == path => repo,
+            _ => open_repo(path)?,
+        };
+
+        let result = f(&repo);
+
+        THREAD_REPO.with(|r| *r.borrow_mut() = Some((path.to_string(), repo)));
+
+        result
+    }
+
+    /// opens the repository at `path`, with a readable error when there is none
+    pub fn open_repo(path: &str) -> GenResult<Repository> {
+        Repository::open(path).map_err(|_| GritError::RepoNotFound(path.to_string()).into())
+    }
+
+    /// the root of the repository `path` is in, searching up through its parents as git
+    /// does and stopping at the `GIT_CEILING_DIRECTORIES`.  The git directory of a bare one.
+    /// Fails rather than mangle a root that isn't valid UTF-8.
+    pub fn discover_repo(path: &str) -> GenResult<String> {
+        let ceilings: Vec<PathBuf> = env::var_os("GIT_CEILING_DIRECTORIES")
+            .map(|c| {
+                env::split_paths(&c)
+                    .filter(|p| !p.as_os_str().is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let repo = Repository::open_ext(path, RepositoryOpenFlags::empty(), &ceilings)
+            .map_err(|_| GritError::RepoNotFound(path.to_string()))?;
+        let root = repo.workdir().unwrap_or_else(|| repo.path());
+        let root = root.components().as_path();
+
+        root.to_str()
+            .map(String::from)
+            .ok_or_else(|| GritError::NonUtf8Path(root.display().to_string()).into())
+    }
+
+    pub fn check_file_type(filename: &str, ext: &str) -> bool {
+        let file_ext = get_filename_extension(filename).unwrap_or_default();
+
+        ext.eq_ignore_ascii_case(file_ext)
+    }
+
+    fn repo_workdir(repo_path: &str) -> GenResult<PathBuf> {
+        let repo = open_repo(repo_path)?;
+        let workdir = repo
+            .workdir()
+            .ok_or_else(|| anyhow!("repository at {} has no working directory", repo_path))?;
+
+        Ok(workdir.canonicalize()?)
+    }
+
+    /// the directory a repository's linked worktrees share, holding the objects, refs and
+    /// config.  The git directory itself outside a linked worktree.
+    pub fn common_dir(repo: &Repository) -> PathBuf {
+        if repo.is_worktree() {
+            if let Ok(c) = fs::read_to_string(repo.path().join("commondir")) {
+                let dir = repo.path().join(c.trim());
+                return dir.canonicalize().unwrap_or(dir);
+            }
+        }
+
+        repo.path().to_path_buf()
+    }
+
+    /// `path` with the '.' and '..' components resolved without touching the file system
+    pub fn normalize_path(path: &Path) -> PathBuf {
+        let mut result = PathBuf::new();
+
+        for c in path.components() {
+            match c {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    result.pop();
+                }
+                c => result.push(c.as_os_str()),
+            }
+        }
+
+        result
+    }
+
+    /// returns `path` relative to `base`.  Both paths must be absolute.
+    pub fn diff_paths(path: &Path, base: &Path) -> PathBuf {
+        let path_comps: Vec<Component> = path.components().collect();
+        let base_comps: Vec<Component> = base.components().collect();
+
+        let common = path_comps
+            .iter()
+            .zip(base_comps.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        let mut result = PathBuf::new();
+
+        for _ in common..base_comps.len() {
+            result.push("..");
+        }
+
+        for c in path_comps.iter().skip(common) {
+            result.push(c.as_os_str());
+        }
+
+        result
+    }
+
+    /// the first `depth` directories of the file's path, "." for files in the root
+    pub fn directory(file_name: &str, depth: usize) -> String {
+        let parent: Vec<String> = Path::new(file_name)
+            .parent()
+            .map(|p| {
+                p.components()
+                    .take(depth)
+                    .map(|c| c.as_os_str().to_string_lossy().to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        if parent.is_empty() {
+            String::from(".")
+        } else {
+            parent.join("/")
+        }
+    }
+
+    /// converts repo relative file names into paths relative to `relative_to`
+    pub fn relative_file_paths(
+        repo_path: &str,
+        file_names: &[String],
+        relative_to: RelativeTo,
+    ) -> GenResult<Vec<String>> {
+        if relative_to == RelativeTo::Repo {
+            return Ok(file_names.to_vec());
+        }
+
+        let workdir = repo_workdir(repo_path)?;
+        let cwd = env::current_dir()?.canonicalize()?;
+
+        let result = file_names
+            .iter()
+            .map(|f| {
+                diff_paths(&workdir.join(f), &cwd)
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+
+        Ok(result)
+    }
+
+    /// converts a file name given relative to `relative_to` into a repo relative path
+    pub fn repo_file_path(
+        repo_path: &str,
+        file_name: &str,
+        relative_to: RelativeTo,
+    ) -> GenResult<String> {
+        if relative_to == RelativeTo::Repo {
+            return Ok(normalize_separators(file_name));
+        }
+
+        let workdir = repo_workdir(repo_path)?;
+        let full_path = env::current_dir()?.canonicalize()?.join(file_name);
+
+        // a file a sparse checkout leaves out isn't on disk to canonicalize
+        let full_path = full_path
+            .canonicalize()
+            .unwrap_or_else(|_| normalize_path(&full_path));
+
+        let relative = full_path
+            .strip_prefix(&workdir)
+            .map_err(|_| anyhow!("{} is not inside the repository", file_name))?;
+
+        Ok(normalize_separators(&relative.to_string_lossy()))
+    }
+
+    /// resolves a revision (sha, branch, tag, HEAD~n) to the commit id bytes
+    pub fn resolve_rev(repo_path: &str, rev: &str) -> GenResult<Vec<u8>> {
+        let repo = open_repo(repo_path)?;
+        let commit = repo.revparse_single(rev)?.peel_to_commit()?;
+
+        Ok(commit.id().as_bytes().to_vec())
+    }
+
+    /// true when the commit changed a file matching one of `patterns`, diffing against its
+    /// first parent, or against nothing for a root commit
+    pub fn commit_touches_paths(
+        repo: &Repository,
+        commit: &Commit,
+        patterns: &[Pattern],
+    ) -> Result<bool, git2::Error> {
+        let tree = commit.tree()?;
+        let parent_tree = match commit.parent(0) {
+            Ok(p) => Some(p.tree()?),
+            Err(_) => None,
+        };
+
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+
+        let touched = diff.deltas().any(|d| {
+            [d.old_file().path(), d.new_file().path()]
+                .iter()
+                .flatten()
+                .map(|p| normalize_separators(&p.to_string_lossy()))
+                .any(|p| patterns.iter().any(|pat| pat.matches(&p)))
+        });
+
+        Ok(touched)
+    }
+
+    pub fn find_commit_range(
+        repo_path: &str,
+        start_date: Option<NaiveDate>,
+        end_date: Option<NaiveDate>,
+    ) -> GenResult<CommitRange> {
+        timing::phase("commit range", || {
+            COMMIT_RANGES.get_or_try((repo_path.to_string(), start_date, end_date), || {
+                walk_commit_range(repo_path, start_date, end_date)
+            })
+        })
+    }
+
+    /// true when no commit was made within the dates, logging that there is nothing to
+    /// report
+    pub fn range_is_empty(
+        repo_path: &str,
+        start_date: Option<NaiveDate>,
+        end_date: Option<NaiveDate>,
+    ) -> GenResult<bool> {
+        let empty = find_commit_range(repo_path, start_date, end_date)?.is_empty();
+
+        if empty {
+            info!(
+                "no commits between {:?} and {:?}, nothing to report",
+                start_date, end_date
+            );
+        }
+
+        Ok(empty)
+    }
+
+    /// the day of `author`'s first commit reachable from HEAD, to start a range at.  The
+    /// author is matched by name, email or aliased identity, ignoring case.
+    pub fn author_first_commit_date(repo_path: &str, author: &str) -> GenResult<NaiveDate> {
+        let repo = open_repo(repo_path)?;
+
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push_head()?;
+
+        let mut first: Option<Time> = None;
+
+        for id in revwalk {
+            let commit = repo.find_commit(id?)?;
+            let sig = commit.author();
+            let name = String::from_utf8_lossy(sig.name_bytes());
+            let email = String::from_utf8_lossy(sig.email_bytes());
+
+            if identity::is_author(author, &name, &email)
+                && first.is_none_or(|t| commit.time().seconds() < t.seconds())
+            {
+                first = Some(commit.time());
+            }
+        }
+
+        first
+            .map(|t| convert_git_time(&t))
+            .ok_or_else(|| anyhow!("{} has no commits in {}", author, repo_path))
+    }
+
+    /// walks the history once, keeping the commits whose time is within the dates.  Every
+    /// commit is looked at, as commit times need not follow the ancestry.
+    fn walk_commit_range(
+        repo_path: &str,
+        start_date: Option<NaiveDate>,
+        end_date: Option<NaiveDate>,
+    ) -> GenResult<CommitRange> {
+        if start_date.is_none() && end_date.is_none() {
+            return Ok(CommitRange::default());
+        }
+
+        let start_date_sec = start_date.map(start_of_day_seconds);
+        let end_date_sec = end_date.map(end_of_day_seconds);
+
+        let repo = open_repo(repo_path)?;
+
+        let mut revwalk = repo.revwalk()?;
+        revwalk.set_sorting(git2::Sort::TIME)?;
+        revwalk.push_head()?;
+
+        let mut commits: HashSet<Oid> = HashSet::new();
+        let mut earliest: Option<(i64, Oid)> = None;
+        let mut latest: Option<(i64, Oid)> = None;
+
+        for id in revwalk {
+            let oid = id?;
+            let commit_time = repo.find_commit(oid)?.time().seconds();
+
+            if start_date_sec.map(|s| commit_time < s).unwrap_or(false)
+                || end_date_sec.map(|e| commit_time > e).unwrap_or(false)
+            {
+                continue;
+            }
+
+            commits.insert(oid);
+
+            if earliest.map(|(t, _)| commit_time <= t).unwrap_or(true) {
+                earliest = Some((commit_time, oid));
+            }
+
+            if latest.map(|(t, _)| commit_time > t).unwrap_or(true) {
+                latest = Some((commit_time, oid));
+            }
+        }
+
+        if commits.is_empty() {
+            return Ok(CommitRange::empty());
+        }
+
+        let bytes = |c: Option<(i64, Oid)>| c.map(|(_, oid)| oid.as_bytes().to_vec());
+
+        Ok(CommitRange {
+            earliest: start_date.and(bytes(earliest)),
+            latest: end_date.and(bytes(latest)),
+            commits: Some(Arc::new(commits)),
+        })
+    }
+
+    #[cfg(test)]
+    mod tests {
+
+        use super::*;
+        use chrono::NaiveDate;
+        use log::LevelFilter;
+        use tempfile::TempDir;
+
+        const DIR: &str = ".";
+
+        #[test]
+        fn test_generate_file_list_bad_pattern() {
+            crate::grit_test::set_test_logging(LevelFilter::Info);
+            let result = generate_file_list(DIR, Some(String::from("src/*,src/[*")), None);
+
+            let err = result.expect_err("bad pattern should not panic");
+            assert!(err.to_string().contains("src/[*"), "{}", err);
+        }
+
+        #[test]
+        fn test_open_repo_missing() {
+            crate::grit_test::set_test_logging(LevelFilter::Info);
+            let err = open_repo("/does/not/exist").err().unwrap();
+
+            assert_eq!(
+                err.to_string(),
+                "no git repository found at '/does/not/exist'"
+            );
+        }
+
+        #[test]
+        fn test_discover_repo() {
+            crate::grit_test::set_test_logging(LevelFilter::Info);
+
+            let td = crate::grit_test::fixture_repo();
+            let subdir = td.path().join("src");
+
+            let root = discover_repo(subdir.to_str().unwrap()).unwrap();
+            assert_eq!(
+                Path::new(&root).canonicalize().unwrap(),
+                td.path().canonicalize().unwrap()
+            );
+
+            let err = discover_repo("/does/not/exist").err().unwrap();
+            assert_eq!(
+                err.to_string(),
+                "no git repository found at '/does/not/exist'"
+            );
+
+            // reached through a UTF-8 link, the root itself is latin-1
+            use std::os::unix::ffi::OsStrExt;
+            let raw = td.path().join(OsStr::from_bytes(b"caf\xe9"));
+            Repository::init(&raw).unwrap();
+            let link = td.path().join("cafe");
+            std::os::unix::fs::symlink(&raw, &link).unwrap();
+
+            let err = discover_repo(link.to_str().unwrap()).err().unwrap();
+            assert!(
+                err.to_string().ends_with("caf\u{fffd}' is not valid UTF-8"),
+                "{}",
+                err
+            );
+        }
+
+        #[test]
+        fn test_create_html_no_file_name() {
+            crate::grit_test::set_test_logging(LevelFilter::Info);
+
+            let err = create_html("").err().unwrap();
+
+            assert_eq!(err.to_string(), "invalid file name ''");
+        }
+
+        #[test]
+        fn test_report_skipped() {
+            crate::grit_test::set_test_logging(LevelFilter::Info);
+            let skipped = vec![SkippedFile::new("gone.rs", String::from("not found"))];
+
+            assert!(report_skipped(&[], true).is_ok());
+            assert!(report_skipped(&skipped, false).is_ok());
+            assert_eq!(
+                report_skipped(&skipped, true).err().unwrap().to_string(),
+                "1 file(s) could not be processed"
+            );
+        }
+
+        #[test]
+        fn test_generate_file_list_all() {
+            crate::grit_test::set_test_logging(LevelFilter::Info);
+            let result = generate_file_list(DIR, None, None).unwrap();
+
+            info!("include all {:?}", result);
+
+            assert!(
+                result.len() >= 6,
+                "test_generate_file_list_all was {}",
+                result.len()
+            );
+        }
+
+        #[test]
+        fn test_generate_file_list_rust() {
+            crate::grit_test::set_test_logging(LevelFilter::Info);
+            let result = generate_file_list(DIR, Some("*.rs".to_string()), None).unwrap();
+
+            info!("include *.rs {:?}", result);
+
+            assert!(
+                result.iter().all(|s| s.ends_with(".rs")),
+                "test_generate_file_list_all was {}",
+                result.len()
+            );
+        }
+
+        #[test]
+        fn test_generate_file_list_exclude_rust() {
+            crate::grit_test::set_test_logging(LevelFilter::Info);
+            let result = generate_file_list(DIR, None, Some("*.rs".to_string())).unwrap();
+
+            info!("excludes *.rs {:?}", result);
+
+            assert!(
+                !result.iter().any(|s| s.ends_with(".rs")),
+                "test_generate_file_list_exclude_rust was {}",
+                result.len()
+            );
+        }
+
+        #[test]
+        fn test_weekdays() {
+            crate::grit_test::set_test_logging(LevelFilter::Info);
+
+            assert_eq!(
+                parse_weekdays("fri, Sat").unwrap(),
+                vec![Weekday::Fri, Weekday::Sat]
+            );
+            assert!(parse_weekdays("fri,someday").is_err());
+
+            assert_eq!(days_since(Weekday::Mon, Weekday::Mon), 0);
+            assert_eq!(days_since(Weekday::Sun, Weekday::Mon), 6);
+            assert_eq!(days_since(Weekday::Mon, Weekday::Sun), 1);
+            assert_eq!(days_since(Weekday::Fri, Weekday::Sat), 6);
+        }
+
+        #[test]
+        fn test_directory() {
+            crate::grit_test::set_test_logging(LevelFilter::Info);
+
+            assert_eq!(directory("README.md", 1), ".");
+            assert_eq!(directory("src/main.rs", 1), "src");
+            assert_eq!(directory("src/a/b/c.rs", 1), "src");
+            assert_eq!(directory("src/a/b/c.rs", 2), "src/a");
+        }
+
+        #[test]
+        fn test_filter_file_names_windows_separators() {
+            crate::grit_test::set_test_logging(LevelFilter::Info);
+
+            let file_names = vec![
+                String::from("src\\main.rs"),
+                String::from("src/utils.rs"),
+                String::from("README.md"),
+            ];
+
+            let expected = vec![String::from("src/main.rs"), String::from("src/utils.rs")];
+
+            assert_eq!(
+                filter_file_names(file_names.clone(), Some(String::from("src\\*")), None).unwrap(),
+                expected
+            );
+            assert_eq!(
+                filter_file_names(file_names, Some(String::from("src/*")), None).unwrap(),
+                expected
+            );
+            assert_eq!(
+                repo_file_path(".", "src\\main.rs", RelativeTo::Repo).unwrap(),
+                "src/main.rs"
+            );
+        }
+
+        #[test]
+        fn test_read_patterns_file() {
+            let td = tempfile::tempdir().unwrap();
+            let file = td.path().join("patterns");
+            fs::write(&file, "# generated code\nsrc/gen/*\n\n  *.lock  \n").unwrap();
+
+            let patterns = read_patterns_file(file.to_str().unwrap()).unwrap();
+
+            assert_eq!(patterns, vec!["src/gen/*", "*.lock"]);
+            assert!(read_patterns_file("no-such-patterns-file").is_err());
+        }
+
+        #[test]
+        fn test_subdir_path() {
+            crate::grit_test::set_test_logging(LevelFilter::Info);
 
-pub mod grit_utils {
+            let td: TempDir = crate::grit_test::fixture_repo();
+            let path = td.path().to_str().unwrap();
 
-    use anyhow::Result;
-    use chrono::{Date, Datelike, Local, NaiveDateTime, TimeZone};
-    use git2::{Repository, StatusOptions, Time};
-    use glob::Pattern;
-    use std::ffi::OsStr;
-    use std::fs::File;
-    use std::io::Write;
-    use std::path::Path;
+            assert_eq!(subdir_path(path, None, "src/").unwrap(), "src");
+            assert!(subdir_path(path, None, "README.md").is_err());
+            assert!(subdir_path(path, None, "docs").is_err());
+        }
 
-    type GenResult<T> = Result<T>;
+        #[test]
+        fn test_generate_file_list_at() {
+            crate::grit_test::set_test_logging(LevelFilter::Info);
 
-    pub fn generate_file_list(
-        path: &str,
-        include: Option<String>,
-        exclude: Option<String>,
-    ) -> GenResult<Vec<String>> {
-        let repo = Repository::open(path)?;
+            let td: TempDir = crate::grit_test::fixture_repo();
+            let path = td.path().to_str().unwrap();
 
-        let mut status_opts = StatusOptions::new();
-        status_opts.include_untracked(false);
-        status_opts.include_unmodified(true);
-        status_opts.include_ignored(false);
-        status_opts.include_unreadable(false);
-        status_opts.exclude_submodules(true);
+            let head = resolve_rev(path, "HEAD").unwrap();
+            let result =
+                generate_file_list_at(path, &head, Some("src/*".to_string()), None).unwrap();
 
-        let statuses = repo.statuses(Some(&mut status_opts))?;
+            info!("files at HEAD {:?}", result);
 
-        let includes: Option<Vec<Pattern>> = match include {
-            Some(e) => Some(
-                e.split(',')
-                    .map(|s| {
-                        Pattern::new(s).expect(format_tostr!("cannot create new Pattern {} ", s))
-                    })
-                    .collect(),
-            ),
-            None => None,
-        };
+            assert_eq!(result, vec!["src/lib.rs", "src/main.rs"]);
 
-        let excludes: Option<Vec<Pattern>> = match exclude {
-            Some(e) => Some(
-                e.split(',')
-                    .map(|s| {
-                        Pattern::new(s).expect(format_tostr!("cannot create new Pattern {} ", s))
-                    })
-                    .collect(),
-            ),
-            None => None,
-        };
+            // Bob's lib.rs came in with the second commit
+            let first = resolve_rev(path, "HEAD~3").unwrap();
+            let result =
+                generate_file_list_at(path, &first, Some("src/*".to_string()), None).unwrap();
 
-        let file_names: Vec<String> = statuses
-            .iter()
-            .filter_map(|se| {
-                let s = se
-                    .path()
-                    .expect("Cannot create string from path")
-                    .to_string();
+            assert_eq!(result, vec!["src/main.rs"]);
+        }
 
-                let result = match &includes {
-                    Some(il) => {
-                        if il.iter().any(|p| p.matches(&s)) {
-                            Some(s)
-                        } else {
-                            None
-                        }
-                    }
-                    None => Some(s),
-                };
-                result
-            })
-            .filter_map(|s| {
-                let result = if let Some(el) = &excludes {
-                    if el.iter().any(|p| p.matches(&s)) {
-                        None
-                    } else {
-                        Some(s)
-                    }
-                } else {
-                    Some(s)
-                };
+        #[test]
+        fn test_with_thread_repo() {
+            crate::grit_test::set_test_logging(LevelFilter::Info);
 
-                result
-            })
-            .collect();
+            let td: TempDir = crate::grit_test::fixture_repo();
+            let path = td.path().to_str().unwrap().to_string();
 
-        Ok(file_names)
-    }
+            assert!(with_thread_repo(&path, |repo| Ok(repo.head()?.target())).is_ok());
 
-    pub fn convert_string_list_to_vec(input: Option<String>) -> Option<Vec<String>> {
-        let result: Option<Vec<String>> = match input {
-            Some(s) => Some(s.split(",").map(|e| e.to_string()).collect()),
-            None => None,
-        };
+            // the second call reuses the open repository rather than reading the deleted one
+            drop(td);
+            assert!(with_thread_repo(&path, |repo| Ok(repo.is_bare())).is_ok());
+            assert!(Repository::open(&path).is_err());
 
-        result
-    }
+            let other: TempDir = TempDir::new().unwrap();
+            assert!(with_thread_repo(other.path().to_str().unwrap(), |_| Ok(())).is_err());
+        }
 
-    pub fn convert_git_time(time: &Time) -> Date<Local> {
-        Local
-            .from_utc_datetime(&NaiveDateTime::from_timestamp(time.seconds(), 0))
-            .date()
-    }
+        #[test]
+        fn test_runtime() {
+            // 0 keeps the default of one worker per CPU
+            set_threads(Some(0));
+            assert!(THREADS.get().is_none());
 
-    pub fn format_date(d: Date<Local>) -> String {
-        format!("{}-{:0>2}-{:0>2}", d.year(), d.month(), d.day())
-    }
+            let rt = runtime("grit-test-runner").unwrap();
 
-    pub fn get_filename_extension(filename: &str) -> Option<&str> {
-        Path::new(filename).extension().and_then(OsStr::to_str)
-    }
+            let name =
+                rt.block_on(rt.spawn(async { std::thread::current().name().map(String::from) }));
+            assert_eq!(name.unwrap().as_deref(), Some("grit-test-runner"));
 
-    pub fn strip_extension(filename: &str) -> Option<&str> {
-        Path::new(filename).file_stem().and_then(OsStr::to_str)
-    }
+            // a panicking task is an error to its caller rather than ending the run
+            let panicked = rt.block_on(rt.spawn(async { panic!("blame failed") }));
+            assert!(panicked.is_err());
+        }
 
-    pub fn create_html(filename: &str) -> GenResult<()> {
-        let file_base = match strip_extension(filename) {
-            Some(f) => f,
-            None => panic!("cannot create html file"),
-        };
+        #[test]
+        fn test_generate_file_list_bare() {
+            crate::grit_test::set_test_logging(LevelFilter::Info);
 
-        let html_file = format!("{}{}", file_base, ".html");
-        let html_output = format!(
-            "<html><head></head><body><img src=\"{}\"/></body></html>",
-            filename
-        );
+            let td: TempDir = crate::grit_test::fixture_repo();
+            let path = td.path().to_str().unwrap();
 
-        let mut output = File::create(html_file).expect("HTML file creation failed");
-        output
-            .write_all(html_output.as_bytes())
-            .expect("Writing to HTML File failed");
+            // a file removed from the working directory is still in the HEAD tree
+            std::fs::remove_file(td.path().join("src/main.rs")).unwrap();
+            let result = generate_file_list(path, None, None).unwrap();
+            assert!(result.contains(&"src/main.rs".to_string()));
+
+            let bare = TempDir::new().unwrap();
+            git2::build::RepoBuilder::new()
+                .bare(true)
+                .clone(path, bare.path())
+                .unwrap();
+
+            let mut bare_result =
+                generate_file_list(bare.path().to_str().unwrap(), None, None).unwrap();
+            let mut result = result;
+            bare_result.sort();
+            result.sort();
+            assert_eq!(bare_result, result);
+        }
 
-        Ok(())
-    }
+        #[cfg(unix)]
+        #[test]
+        fn test_generate_file_list_non_utf8() {
+            use std::os::unix::ffi::OsStrExt;
 
-    pub fn check_file_type(filename: &str, ext: &str) -> bool {
-        let file_ext = match get_filename_extension(filename) {
-            Some(f) => f,
-            None => "",
-        };
+            crate::grit_test::set_test_logging(LevelFilter::Info);
 
-        ext.eq_ignore_ascii_case(file_ext)
-    }
+            let td: TempDir = crate::grit_test::fixture_repo();
+            let path = td.path().to_str().unwrap();
+            let repo = Repository::open(path).unwrap();
+
+            // a latin-1 directory and file name, as left by older tools
+            let raw = Path::new(OsStr::from_bytes(b"caf\xe9/men\xfc.txt"));
+            std::fs::create_dir_all(td.path().join(raw.parent().unwrap())).unwrap();
+            std::fs::write(td.path().join(raw), "one\ntwo\n").unwrap();
+
+            let mut index = repo.index().unwrap();
+            index.add_path(raw).unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let sig =
+                git2::Signature::new("Carol", "carol@example.com", &Time::new(1_578_312_000, 0))
+                    .unwrap();
+            let head = repo.head().unwrap().peel_to_commit().unwrap();
+            repo.commit(Some("HEAD"), &sig, &sig, "latin-1", &tree, &[&head])
+                .unwrap();
+
+            let result = generate_file_list(path, None, None).unwrap();
+            let name = "caf\u{fffd}/men\u{fffd}.txt";
+
+            assert_eq!(result.len(), 4);
+            assert!(result.contains(&name.to_string()));
+            assert_eq!(git_path(name), raw);
+            assert_eq!(git_path("src/main.rs"), PathBuf::from("src/main.rs"));
+
+            let blame = repo.blame_file(&git_path(name), None).unwrap();
+            assert_eq!(blame.iter().map(|h| h.lines_in_hunk()).sum::<usize>(), 2);
+        }
 
-    pub fn find_commit_range(
-        repo_path: &str,
-        start_date: Option<Date<Local>>,
-        end_date: Option<Date<Local>>,
-    ) -> GenResult<(Option<Vec<u8>>, Option<Vec<u8>>)> {
-        let mut earliest_commit = None;
-        let mut latest_commit = None;
-
-        let repo = Repository::open(repo_path)
-            .expect(format_tostr!("Could not open repo for path {}", repo_path));
-
-        if let Some(d) = start_date {
-            let start_date_sec = d.naive_local().and_hms(0, 0, 0).timestamp();
-            let mut revwalk = repo.revwalk()?;
-            revwalk
-                .set_sorting(git2::Sort::NONE | git2::Sort::TIME)
-                .expect("Could not sort revwalk");
-            revwalk.push_head()?;
-
-            for id in revwalk {
-                let oid = id?;
-                let commit = repo.find_commit(oid)?;
-                let commit_time = commit.time().seconds();
-
-                if commit_time >= start_date_sec {
-                    earliest_commit = Some(oid.as_bytes().iter().map(|b| *b).collect())
-                } else {
-                    break;
-                }
-            }
+        #[test]
+        fn test_linguist_rules() {
+            crate::grit_test::set_test_logging(LevelFilter::Info);
+
+            let mut rules = LinguistRule::parse(
+                "",
+                "# generated code\n*.lock linguist-generated\nvendor/** linguist-vendored\n\
+                 *.pb.go linguist-generated=true\nvendor/keep.rs -linguist-vendored\n",
+            );
+            rules.extend(LinguistRule::parse("web/", "/dist/* linguist-generated\n"));
+
+            assert_eq!(rules.len(), 5);
+            assert!(LinguistRule::is_vendored(&rules, "Cargo.lock"));
+            assert!(LinguistRule::is_vendored(&rules, "sub/yarn.lock"));
+            assert!(LinguistRule::is_vendored(&rules, "vendor/lib/a.rs"));
+            assert!(LinguistRule::is_vendored(&rules, "api/x.pb.go"));
+            assert!(LinguistRule::is_vendored(&rules, "web/dist/app.js"));
+            assert!(!LinguistRule::is_vendored(&rules, "vendor/keep.rs"));
+            assert!(!LinguistRule::is_vendored(&rules, "dist/app.js"));
+            assert!(!LinguistRule::is_vendored(&rules, "web/dist/js/app.js"));
+            assert!(!LinguistRule::is_vendored(&rules, "src/main.rs"));
         }
 
-        if let Some(d) = end_date {
-            let end_date_sec = d.naive_local().and_hms(23, 59, 59).timestamp();
+        #[test]
+        fn test_commit_touches_paths() {
+            crate::grit_test::set_test_logging(LevelFilter::Info);
 
-            let mut revwalk = repo.revwalk()?;
-            revwalk
-                .set_sorting(git2::Sort::REVERSE | git2::Sort::TIME)
-                .expect("Could not sort revwalk");
-            revwalk.push_head()?;
+            let td: TempDir = crate::grit_test::fixture_repo();
+            let repo = Repository::open(td.path()).unwrap();
 
-            for id in revwalk {
-                let oid = id?;
-                let commit = repo.find_commit(oid)?;
-                let commit_time = commit.time().seconds();
+            let mut revwalk = repo.revwalk().unwrap();
+            revwalk.push_head().unwrap();
 
-                if commit_time <= end_date_sec {
-                    latest_commit = Some(oid.as_bytes().iter().map(|b| *b).collect())
-                } else {
-                    break;
-                }
-            }
+            let commits: Vec<Commit> = revwalk
+                .map(|oid| repo.find_commit(oid.unwrap()).unwrap())
+                .collect();
+
+            let touching = |pattern: &str| {
+                let patterns = parse_patterns(Some(String::from(pattern)))
+                    .unwrap()
+                    .unwrap();
+
+                commits
+                    .iter()
+                    .filter(|c| commit_touches_paths(&repo, c, &patterns).unwrap())
+                    .count()
+            };
+
+            assert_eq!(touching("*"), 4);
+            assert_eq!(touching("src/main.rs"), 2);
+            assert_eq!(touching("src/lib.rs"), 1);
+            assert_eq!(touching("no/such/dir/*"), 0);
         }
 
-        Ok((earliest_commit, latest_commit))
-    }
+        #[test]
+        fn test_resolve_rev_bad() {
+            crate::grit_test::set_test_logging(LevelFilter::Info);
+            assert!(resolve_rev(DIR, "no-such-revision").is_err());
+        }
 
-    #[cfg(test)]
-    mod tests {
+        #[test]
+        fn test_commit_day() {
+            crate::grit_test::set_test_logging(LevelFilter::Info);
 
-        use super::*;
-        use chrono::NaiveDate;
-        use log::LevelFilter;
-        use tempfile::TempDir;
+            // 2020-01-01 23:30 UTC, committed from UTC-08:00
+            let time = Time::new(1_577_921_400, -480);
 
-        const DIR: &str = ".";
+            assert_eq!(format_date(commit_day(&time, Timezone::Utc)), "2020-01-01");
+            assert_eq!(
+                format_date(commit_day(&time, Timezone::Author)),
+                "2020-01-01"
+            );
+            assert_eq!(
+                format_date(commit_day(&time, "+05:30".parse().unwrap())),
+                "2020-01-02"
+            );
+            assert_eq!(
+                format_date(commit_day(&Time::new(1_577_833_200, 60), Timezone::Author)),
+                "2020-01-01"
+            );
+            assert_eq!(
+                format_date(commit_day(&Time::new(1_577_833_200, 60), Timezone::Utc)),
+                "2019-12-31"
+            );
+
+            assert_eq!(
+                "-0800".parse::<Timezone>().unwrap(),
+                Timezone::Offset(-28_800)
+            );
+            assert_eq!("+09".parse::<Timezone>().unwrap(), Timezone::Offset(32_400));
+            assert_eq!(
+                "Europe/Paris".parse::<Timezone>().unwrap(),
+                Timezone::Named(Tz::Europe__Paris)
+            );
+            assert!("Europe/Nowhere".parse::<Timezone>().is_err());
+            assert!("+25:00".parse::<Timezone>().is_err());
+        }
 
         #[test]
-        fn test_generate_file_list_all() {
+        fn test_commit_day_named_timezone() {
             crate::grit_test::set_test_logging(LevelFilter::Info);
-            let result = generate_file_list(DIR, None, None).unwrap();
 
-            info!("include all {:?}", result);
+            let utc = |d: u32, h: u32, m: u32| {
+                let t = NaiveDate::from_ymd_opt(2020, 3, d)
+                    .unwrap()
+                    .and_hms_opt(h, m, 0)
+                    .unwrap();
+                Time::new(t.and_utc().timestamp(), 0)
+            };
+            let paris: Timezone = "Europe/Paris".parse().unwrap();
 
-            assert!(
-                result.len() >= 6,
-                "test_generate_file_list_all was {}",
-                result.len()
+            // Paris moved from +01:00 to +02:00 at 01:00 UTC on 2020-03-29
+            assert_eq!(
+                commit_datetime(&utc(29, 0, 30), paris)
+                    .format("%H:%M")
+                    .to_string(),
+                "01:30"
+            );
+            assert_eq!(
+                commit_datetime(&utc(29, 1, 30), paris)
+                    .format("%H:%M")
+                    .to_string(),
+                "03:30"
+            );
+
+            // 22:30 UTC is the next day in summer time, but not at the winter offset
+            assert_eq!(
+                format_date(commit_day(&utc(28, 22, 30), paris)),
+                "2020-03-28"
+            );
+            assert_eq!(
+                format_date(commit_day(&utc(29, 22, 30), paris)),
+                "2020-03-30"
+            );
+            assert_eq!(
+                format_date(commit_day(&utc(29, 22, 30), "+01:00".parse().unwrap())),
+                "2020-03-29"
             );
         }
 
         #[test]
-        fn test_generate_file_list_rust() {
+        fn test_format_date() {
             crate::grit_test::set_test_logging(LevelFilter::Info);
-            let result = generate_file_list(DIR, Some("*.rs".to_string()), None).unwrap();
+            let test_date = NaiveDate::from_ymd_opt(2020, 3, 13).unwrap();
 
-            info!("include *.rs {:?}", result);
+            assert_eq!(format_date(test_date), "2020-03-13");
+        }
 
-            assert!(
-                result.iter().all(|s| s.ends_with(".rs")),
-                "test_generate_file_list_all was {}",
-                result.len()
+        #[test]
+        fn test_check_date_range() {
+            crate::grit_test::set_test_logging(LevelFilter::Info);
+            let q3_start = NaiveDate::from_ymd_opt(2023, 7, 1).unwrap();
+            let q3_end = NaiveDate::from_ymd_opt(2023, 9, 30).unwrap();
+
+            assert!(check_date_range(Some(q3_start), Some(q3_end)).is_ok());
+            assert!(check_date_range(Some(q3_start), Some(q3_start)).is_ok());
+            assert!(check_date_range(Some(q3_end), None).is_ok());
+
+            let e = check_date_range(Some(q3_end), Some(q3_start)).unwrap_err();
+            assert_eq!(
+                e.to_string(),
+                "start-date 2023-09-30 is after end-date 2023-07-01"
             );
         }
 
         #[test]
-        fn test_generate_file_list_exclude_rust() {
+        fn test_file_format() {
             crate::grit_test::set_test_logging(LevelFilter::Info);
-            let result = generate_file_list(DIR, None, Some("*.rs".to_string())).unwrap();
 
-            info!("excludes *.rs {:?}", result);
+            assert_eq!(file_format("fame.txt"), Some(OutputFormat::Table));
+            assert_eq!(file_format("out/fame.json"), Some(OutputFormat::Json));
+            assert_eq!(file_format("fame.md"), Some(OutputFormat::Markdown));
+            assert_eq!(file_format("chart.svg"), None);
+            assert_eq!(file_format("fame"), None);
+        }
 
-            assert!(
-                !result.iter().any(|s| s.ends_with(".rs")),
-                "test_generate_file_list_exclude_rust was {}",
-                result.len()
+        #[test]
+        fn test_check_output_file() {
+            crate::grit_test::set_test_logging(LevelFilter::Info);
+
+            assert!(check_output_file(None, OutputFormat::Json).is_ok());
+            assert!(check_output_file(Some("out.csv"), OutputFormat::Csv).is_ok());
+            assert!(check_output_file(Some("out.json"), OutputFormat::Json).is_ok());
+            assert!(check_output_file(Some("chart.svg"), OutputFormat::Csv).is_ok());
+
+            let e = check_output_file(Some("out.csv"), OutputFormat::Json).unwrap_err();
+            assert_eq!(
+                e.to_string(),
+                "the file for json output must end in .json, not .csv"
             );
         }
 
         #[test]
-        fn test_format_date() {
+        fn test_check_image_file() {
             crate::grit_test::set_test_logging(LevelFilter::Info);
-            let test_date = Local.ymd(2020, 3, 13);
 
-            assert_eq!(format_date(test_date), "2020-03-13");
+            assert!(check_image_file(None).is_ok());
+            assert!(check_image_file(Some("out/chart.SVG")).is_ok());
+            assert!(check_image_file(Some("chart.json")).is_err());
+            assert!(check_image_file(Some("chart")).is_err());
+        }
+
+        #[test]
+        fn test_parse_date_format() {
+            crate::grit_test::set_test_logging(LevelFilter::Info);
+            let test_date = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+
+            let iso_week = parse_date_format("iso-week").unwrap();
+            assert_eq!(test_date.format(&iso_week).to_string(), "2020-W01-3");
+
+            // the last days of 2019 fall in the first ISO week of 2020
+            let test_date = NaiveDate::from_ymd_opt(2019, 12, 30).unwrap();
+            assert_eq!(test_date.format(&iso_week).to_string(), "2020-W01-1");
+
+            let dmy = parse_date_format("%d/%m/%Y").unwrap();
+            assert_eq!(test_date.format(&dmy).to_string(), "30/12/2019");
+
+            assert!(parse_date_format("%Y-%Q").is_err());
+        }
+
+        #[test]
+        fn test_json_string() {
+            assert_eq!(json_string("plain"), "\"plain\"");
+            assert_eq!(json_string("a \"b\"\n"), "\"a \\\"b\\\"\\n\"");
+            assert_eq!(json_string("c:\\dir"), "\"c:\\\\dir\"");
+        }
+
+        #[test]
+        fn test_html_escape() {
+            assert_eq!(
+                html_escape("a < b && \"c\""),
+                "a &lt; b &amp;&amp; &quot;c&quot;"
+            );
         }
 
         #[test]
@@ -299,17 +1864,75 @@ pub mod grit_utils {
             assert!(!check_file_type("test.rs", "txt"));
         }
 
+        #[test]
+        fn test_diff_paths() {
+            crate::grit_test::set_test_logging(LevelFilter::Info);
+            assert_eq!(
+                diff_paths(Path::new("/repo/src/main.rs"), Path::new("/repo")),
+                PathBuf::from("src/main.rs")
+            );
+            assert_eq!(
+                diff_paths(Path::new("/repo/src/main.rs"), Path::new("/repo/docs")),
+                PathBuf::from("../src/main.rs")
+            );
+            assert_eq!(
+                diff_paths(Path::new("/other/README.md"), Path::new("/repo/src")),
+                PathBuf::from("../../other/README.md")
+            );
+        }
+
+        #[test]
+        fn test_normalize_path() {
+            assert_eq!(
+                normalize_path(Path::new("/repo/docs/../src/./main.rs")),
+                PathBuf::from("/repo/src/main.rs")
+            );
+        }
+
+        #[test]
+        fn test_linked_worktree() {
+            crate::grit_test::set_test_logging(LevelFilter::Info);
+
+            let td: TempDir = crate::grit_test::fixture_repo();
+            let repo = Repository::open(td.path()).unwrap();
+            let wt_dir = tempfile::Builder::new()
+                .prefix("grit-worktree")
+                .tempdir()
+                .unwrap();
+            let wt_path = wt_dir.path().join("wt");
+            repo.worktree("wt", &wt_path, None).unwrap();
+
+            let wt_repo = Repository::open(&wt_path).unwrap();
+            assert!(wt_repo.is_worktree());
+            assert_eq!(
+                common_dir(&wt_repo),
+                common_dir(&repo).canonicalize().unwrap()
+            );
+
+            // the file list comes from the tree, so it doesn't matter what is checked out
+            fs::remove_file(wt_path.join("src/lib.rs")).unwrap();
+            let file_names = generate_file_list(wt_path.to_str().unwrap(), None, None).unwrap();
+            assert_eq!(file_names, vec!["README.md", "src/lib.rs", "src/main.rs"]);
+        }
+
+        #[test]
+        fn test_relative_to_from_str() {
+            assert_eq!("repo".parse::<RelativeTo>().unwrap(), RelativeTo::Repo);
+            assert_eq!("cwd".parse::<RelativeTo>().unwrap(), RelativeTo::Cwd);
+            assert!("root".parse::<RelativeTo>().is_err());
+        }
+
         #[test]
         fn test_find_commit_range_no() {
             crate::grit_test::set_test_logging(LevelFilter::Info);
 
-            let td: TempDir = crate::grit_test::init_repo();
+            let td: TempDir = crate::grit_test::fixture_repo();
             let path = td.path().to_str().unwrap();
 
-            let (early, late) = find_commit_range(path, None, None).unwrap();
+            let range = find_commit_range(path, None, None).unwrap();
 
-            assert_eq!(early, None);
-            assert_eq!(late, None);
+            assert_eq!(range, CommitRange::default());
+            assert!(range.contains(&Oid::zero()));
         }
 
         #[test]
@@ -328,18 +1951,82 @@ pub mod grit_utils {
         fn test_find_commit_range_early() {
             crate::grit_test::set_test_logging(LevelFilter::Info);
 
-            let utc_dt = NaiveDate::parse_from_str("2020-03-26", "%Y-%m-%d").unwrap();
+            let ed = NaiveDate::parse_from_str("2020-01-03", "%Y-%m-%d").unwrap();
+            let td: TempDir = crate::grit_test::fixture_repo();
+            let path = td.path().to_str().unwrap();
+            let repo = Repository::open(path).unwrap();
+
+            let range = find_commit_range(path, Some(ed), None).unwrap();
+
+            // only Bob's README edit of 2020-01-05 is on or after the start
+            assert_eq!(
+                Oid::from_bytes(&range.earliest.unwrap()).unwrap(),
+                repo.head().unwrap().target().unwrap()
+            );
+            assert_eq!(range.latest, None);
+        }
+
+        #[test]
+        fn test_find_commit_range_dates() {
+            crate::grit_test::set_test_logging(LevelFilter::Info);
+
+            let td: TempDir = crate::grit_test::fixture_repo();
+            let path = td.path().to_str().unwrap();
+            let repo = Repository::open(path).unwrap();
+
+            let day = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+            let time = |c: &Option<Vec<u8>>| {
+                let oid = Oid::from_bytes(c.as_ref().unwrap()).unwrap();
+                format_date(convert_git_time(&repo.find_commit(oid).unwrap().time()))
+            };
+
+            // Bob's and Alice's commits of 2020-01-02
+            let range =
+                find_commit_range(path, Some(day("2020-01-02")), Some(day("2020-01-04"))).unwrap();
+
+            assert_eq!(range.commits.as_ref().unwrap().len(), 2);
+            assert_eq!(time(&range.earliest), "2020-01-02");
+            assert_eq!(time(&range.latest), "2020-01-02");
+
+            let head = repo.head().unwrap().peel_to_commit().unwrap();
+            assert!(!range.contains(&head.id()));
+            assert!(range.contains(&head.parent_id(0).unwrap()));
+
+            let empty =
+                find_commit_range(path, Some(day("2020-01-03")), Some(day("2020-01-04"))).unwrap();
 
-            let ed = Local.from_local_date(&utc_dt).single().unwrap();
-            let td: TempDir = crate::grit_test::init_repo();
+            assert_eq!(empty, CommitRange::empty());
+            assert!(empty.is_empty());
+            assert!(!range.is_empty());
+        }
+
+        #[test]
+        fn test_range_is_empty() {
+            crate::grit_test::set_test_logging(LevelFilter::Info);
+
+            let td: TempDir = crate::grit_test::fixture_repo();
             let path = td.path().to_str().unwrap();
+            let day = |d: &str| Some(NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap());
+
+            // the fixture's commits are made from 2020-01-01 to 2020-01-05
+            assert!(range_is_empty(path, None, day("2019-12-31")).unwrap());
+            assert!(range_is_empty(path, day("2020-01-06"), None).unwrap());
+            assert!(!range_is_empty(path, None, None).unwrap());
+            assert!(!range_is_empty(path, day("2020-01-05"), None).unwrap());
+            assert!(!find_commit_range(path, None, None).unwrap().is_empty());
+        }
 
-            let (early, late) = find_commit_range(path, Some(ed), None).unwrap();
+        #[test]
+        fn test_author_first_commit_date() {
+            let td: TempDir = crate::grit_test::fixture_repo();
+            let path = td.path().to_str().unwrap();
 
-            //info!("early = {:?}", early.unwrap());
+            let bob = author_first_commit_date(path, "Bob").unwrap();
+            let alice = author_first_commit_date(path, "ALICE@example.com").unwrap();
 
-            assert!(early.unwrap().len() > 0);
-            assert_eq!(late, None);
+            assert_eq!(format_date(bob), "2020-01-02");
+            assert_eq!(format_date(alice), "2020-01-01");
+            assert!(author_first_commit_date(path, "Carol").is_err());
         }
     }
 }
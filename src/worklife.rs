@@ -0,0 +1,309 @@
+use super::Processable;
+use crate::identity;
+use crate::output;
+use crate::table::Table;
+use crate::utils::grit_utils;
+use crate::utils::grit_utils::OutputFormat;
+use anyhow::{anyhow, Result};
+use chrono::{NaiveDate, Timelike};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Working hours as minutes after midnight, the end excluded.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct WorkHours {
+    start: u32,
+    end: u32,
+}
+
+impl WorkHours {
+    fn minutes(s: &str) -> Option<u32> {
+        let (h, m) = match s.split_once(':') {
+            Some((h, m)) => (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?),
+            None => (s.parse::<u32>().ok()?, 0),
+        };
+
+        if h > 24 || m > 59 || (h == 24 && m > 0) {
+            return None;
+        }
+
+        Some(h * 60 + m)
+    }
+
+    fn contains(&self, minutes: u32) -> bool {
+        minutes >= self.start && minutes < self.end
+    }
+}
+
+impl Default for WorkHours {
+    fn default() -> Self {
+        WorkHours {
+            start: 9 * 60,
+            end: 18 * 60,
+        }
+    }
+}
+
+impl FromStr for WorkHours {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let hours = s.split_once('-').and_then(|(start, end)| {
+            Some(WorkHours {
+                start: WorkHours::minutes(start.trim())?,
+                end: WorkHours::minutes(end.trim())?,
+            })
+        });
+
+        match hours {
+            Some(h) if h.start < h.end => Ok(h),
+            _ => Err(anyhow!(
+                "work-hours must be a start and end hour such as '9-18' or '08:30-17:00'"
+            )),
+        }
+    }
+}
+
+pub struct WorklifeArgs {
+    path: String,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+    restrict_authors: Option<String>,
+    work_hours: WorkHours,
+    file: Option<String>,
+    format: OutputFormat,
+}
+
+impl WorklifeArgs {
+    pub fn new(
+        path: String,
+        start_date: Option<NaiveDate>,
+        end_date: Option<NaiveDate>,
+        restrict_authors: Option<String>,
+        work_hours: WorkHours,
+        file: Option<String>,
+        format: OutputFormat,
+    ) -> WorklifeArgs {
+        WorklifeArgs {
+            path,
+            start_date,
+            end_date,
+            restrict_authors,
+            work_hours,
+            file,
+            format,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct WorklifeOutput {
+    author: String,
+    commits: i32,
+    weekend: i32,
+    /// weekday commits outside the working hours
+    after_hours: i32,
+}
+
+impl WorklifeOutput {
+    fn new(author: String) -> WorklifeOutput {
+        WorklifeOutput {
+            author,
+            commits: 0,
+            weekend: 0,
+            after_hours: 0,
+        }
+    }
+
+    fn perc(&self, count: i32) -> f64 {
+        if self.commits == 0 {
+            0.0
+        } else {
+            count as f64 / self.commits as f64
+        }
+    }
+}
+
+pub struct Worklife {
+    args: WorklifeArgs,
+}
+
+impl Worklife {
+    pub fn new(args: WorklifeArgs) -> Worklife {
+        Worklife { args }
+    }
+
+    /// every author's commits in the date range split into weekend, after hours and the
+    /// rest, the busiest author first
+    fn process_worklife(&self) -> Result<Vec<WorklifeOutput>> {
+        let restrict_authors =
+            grit_utils::convert_string_list_to_vec(self.args.restrict_authors.clone());
+
+        let repo = grit_utils::open_repo(&self.args.path)?;
+
+        let mut revwalk = repo.revwalk()?;
+        revwalk.set_sorting(git2::Sort::TIME)?;
+        revwalk.push_head()?;
+
+        let mut output_map: HashMap<String, WorklifeOutput> = HashMap::new();
+
+        for oid in revwalk {
+            let commit = repo.find_commit(oid?)?;
+            let time = commit.time();
+            let day = grit_utils::convert_git_time(&time);
+
+            if self.args.start_date.map(|d| day < d).unwrap_or(false)
+                || self.args.end_date.map(|d| day > d).unwrap_or(false)
+            {
+                continue;
+            }
+
+            let author = commit.author();
+            let name = String::from_utf8_lossy(author.name_bytes()).to_string();
+            let email = String::from_utf8_lossy(author.email_bytes()).to_string();
+
+            if let Some(v) = &restrict_authors {
+                if v.iter().any(|a| a == &name) {
+                    continue;
+                }
+            }
+
+            if identity::is_excluded(&name, &email) {
+                continue;
+            }
+
+            let key = identity::display_author(&name, &email);
+            let o = output_map
+                .entry(key.clone())
+                .or_insert_with(|| WorklifeOutput::new(key));
+
+            o.commits += 1;
+
+            if grit_utils::is_weekend(&time) {
+                o.weekend += 1;
+            } else {
+                let at = grit_utils::convert_git_datetime(&time);
+                if !self.args.work_hours.contains(at.hour() * 60 + at.minute()) {
+                    o.after_hours += 1;
+                }
+            }
+        }
+
+        let mut output: Vec<WorklifeOutput> = output_map.into_values().collect();
+
+        output.sort_by(|a, b| b.commits.cmp(&a.commits).then(a.author.cmp(&b.author)));
+
+        Ok(output)
+    }
+
+    fn display_output(&self, output: &[WorklifeOutput]) -> Result<()> {
+        let mut w = grit_utils::output_writer(self.args.file.as_deref())?;
+
+        let mut table = Table::new();
+
+        table.set_titles(table_row![
+            "author",
+            "commits",
+            "weekend",
+            "weekend (%)",
+            "after hours",
+            "after hours (%)"
+        ]);
+
+        output.iter().for_each(|r| {
+            table.add_row(table_row![
+                r.author,
+                r.commits,
+                r.weekend,
+                output::percent(r.perc(r.weekend) * 100.0),
+                r.after_hours,
+                output::percent(r.perc(r.after_hours) * 100.0)
+            ]);
+        });
+
+        table.add_label(0, "author");
+        table.add_metric(
+            3,
+            "grit_author_weekend_commits_percent",
+            "Percent of the author's commits made on a weekend.",
+        );
+        table.add_metric(
+            5,
+            "grit_author_after_hours_commits_percent",
+            "Percent of the author's commits made on a weekday outside working hours.",
+        );
+
+        table.write(&mut w, self.args.format)?;
+
+        Ok(())
+    }
+}
+
+impl Processable<()> for Worklife {
+    fn process(&self) -> Result<()> {
+        let output = self.process_worklife()?;
+
+        self.display_output(&output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::LevelFilter;
+    use tempfile::TempDir;
+
+    const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+    #[test]
+    fn test_work_hours_from_str() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        assert_eq!("9-18".parse::<WorkHours>().unwrap(), WorkHours::default());
+        assert_eq!(
+            "08:30-17:00".parse::<WorkHours>().unwrap(),
+            WorkHours {
+                start: 510,
+                end: 1020
+            }
+        );
+        assert!("18-9".parse::<WorkHours>().is_err());
+        assert!("9".parse::<WorkHours>().is_err());
+        assert!("9-25".parse::<WorkHours>().is_err());
+    }
+
+    #[test]
+    fn test_process_worklife() {
+        crate::grit_test::set_test_logging(LOG_LEVEL);
+
+        let td: TempDir = crate::grit_test::fixture_repo();
+        let path = td.path().to_str().unwrap();
+
+        let worklife = |hours: &str| {
+            Worklife::new(WorklifeArgs::new(
+                path.to_string(),
+                None,
+                None,
+                None,
+                hours.parse().unwrap(),
+                None,
+                OutputFormat::Csv,
+            ))
+            .process_worklife()
+            .unwrap()
+        };
+
+        // Alice commits on Wednesday and Thursday at noon and 13:00, Bob on Thursday and
+        // Sunday at noon
+        let output = worklife("9-18");
+        assert_eq!(output[0].author, "Alice");
+        assert_eq!((output[0].weekend, output[0].after_hours), (0, 0));
+        assert_eq!(output[1].author, "Bob");
+        assert_eq!((output[1].weekend, output[1].after_hours), (1, 0));
+        assert!((output[1].perc(output[1].weekend) - 0.5).abs() < f64::EPSILON);
+
+        let output = worklife("13-18");
+        assert_eq!((output[0].weekend, output[0].after_hours), (0, 1));
+        assert_eq!((output[1].weekend, output[1].after_hours), (1, 1));
+    }
+}